@@ -91,6 +91,7 @@ impl pallet_aura::Config for Test {
 	type MaxAuthorities = ConstU32<100_000>;
 	type DisabledValidators = ();
 	type AllowMultipleBlocksPerSlot = ConstBool<true>;
+	type MaxBlocksPerSlot = ConstU32<{ u32::MAX }>;
 	type SlotDuration = TestSlotDuration;
 }
 
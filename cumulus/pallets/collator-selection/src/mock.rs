@@ -88,6 +88,7 @@ impl pallet_aura::Config for Test {
 	type MaxAuthorities = ConstU32<100_000>;
 	type DisabledValidators = ();
 	type AllowMultipleBlocksPerSlot = ConstBool<false>;
+	type MaxBlocksPerSlot = ConstU32<1>;
 	type SlotDuration = pallet_aura::MinimumPeriodTimesTwo<Self>;
 }
 
@@ -499,6 +499,7 @@ impl pallet_aura::Config for Runtime {
 	type DisabledValidators = ();
 	type MaxAuthorities = ConstU32<100_000>;
 	type AllowMultipleBlocksPerSlot = ConstBool<true>;
+	type MaxBlocksPerSlot = ConstU32<{ u32::MAX }>;
 	type SlotDuration = ConstU64<SLOT_DURATION>;
 }
 
@@ -770,6 +770,7 @@ impl pallet_aura::Config for Runtime {
 	type DisabledValidators = ();
 	type MaxAuthorities = ConstU32<100_000>;
 	type AllowMultipleBlocksPerSlot = ConstBool<false>;
+	type MaxBlocksPerSlot = ConstU32<1>;
 	type SlotDuration = pallet_aura::MinimumPeriodTimesTwo<Self>;
 }
 
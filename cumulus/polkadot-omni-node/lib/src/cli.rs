@@ -106,6 +106,10 @@ pub enum Subcommand {
 	/// Revert the chain to a previous state.
 	Revert(sc_cli::RevertCmd),
 
+	/// Export or import a state snapshot.
+	#[command(subcommand)]
+	Snapshot(sc_cli::SnapshotCmd),
+
 	/// Subcommand for generating and managing chain specifications.
 	///
 	/// A `chain-spec-builder` subcommand corresponds to the existing `chain-spec-builder` tool
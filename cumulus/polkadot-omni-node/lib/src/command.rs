@@ -192,6 +192,14 @@ where
 				node.prepare_revert_cmd(config, cmd)
 			})
 		},
+		Some(Subcommand::Snapshot(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			runner.async_run(|config| {
+				let node =
+					new_node_spec(&config, &cmd_config.runtime_resolver, &cli.node_extra_args())?;
+				node.prepare_snapshot_cmd(config, cmd)
+			})
+		},
 		Some(Subcommand::ChainSpecBuilder(cmd)) =>
 			cmd.run().map_err(|err| sc_cli::Error::Application(err.into())),
 
@@ -19,7 +19,9 @@ use cumulus_client_cli::ExportGenesisHeadCommand;
 use frame_benchmarking_cli::BlockCmd;
 #[cfg(any(feature = "runtime-benchmarks"))]
 use frame_benchmarking_cli::StorageCmd;
-use sc_cli::{CheckBlockCmd, ExportBlocksCmd, ExportStateCmd, ImportBlocksCmd, RevertCmd};
+use sc_cli::{
+	CheckBlockCmd, ExportBlocksCmd, ExportStateCmd, ImportBlocksCmd, RevertCmd, SnapshotCmd,
+};
 use sc_service::{Configuration, TaskManager};
 use std::{future::Future, pin::Pin};
 
@@ -59,6 +61,12 @@ pub trait NodeCommandRunner {
 		cmd: &RevertCmd,
 	) -> AsyncCmdResult<'_>;
 
+	fn prepare_snapshot_cmd(
+		self: Box<Self>,
+		config: Configuration,
+		cmd: &SnapshotCmd,
+	) -> AsyncCmdResult<'_>;
+
 	fn run_export_genesis_head_cmd(
 		self: Box<Self>,
 		config: Configuration,
@@ -128,6 +136,15 @@ where
 		Ok((Box::pin(cmd.run(partial.client, partial.backend, None)), partial.task_manager))
 	}
 
+	fn prepare_snapshot_cmd(
+		self: Box<Self>,
+		config: Configuration,
+		cmd: &SnapshotCmd,
+	) -> AsyncCmdResult<'_> {
+		let partial = T::new_partial(&config).map_err(sc_cli::Error::Service)?;
+		Ok((Box::pin(cmd.run(partial.client, partial.import_queue)), partial.task_manager))
+	}
+
 	fn run_export_genesis_head_cmd(
 		self: Box<Self>,
 		config: Configuration,
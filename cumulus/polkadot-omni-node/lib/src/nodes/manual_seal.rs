@@ -117,6 +117,7 @@ impl<NodeSpec: NodeSpecT> ManualSealNode<NodeSpec> {
 				net_config,
 				block_announce_validator_builder: None,
 				warp_sync_config: None,
+				header_verifier: None,
 				block_relay: None,
 				metrics,
 			})?;
@@ -169,6 +170,8 @@ impl<NodeSpec: NodeSpecT> ManualSealNode<NodeSpec> {
 			});
 
 		let client_for_cidp = client.clone();
+		let time_travel = sc_consensus_manual_seal::TimeTravel::new();
+		let time_travel_for_cidp = time_travel.clone();
 		let params = sc_consensus_manual_seal::ManualSealParams {
 			block_import: client.clone(),
 			env: proposer,
@@ -205,6 +208,11 @@ impl<NodeSpec: NodeSpecT> ManualSealNode<NodeSpec> {
 				let current_para_block_head =
 					Some(polkadot_primitives::HeadData(current_para_head.encode()));
 				let client_for_xcm = client_for_cidp.clone();
+				// This is intentional, as the runtime that we expect to run against this will
+				// never receive the aura-related inherents/digests, and providing real timestamps
+				// would cause aura <> timestamp checking to fail. Time travel moves the clock away
+				// from zero, which runtimes that still tie the timestamp to the aura slot reject.
+				let timestamp = time_travel_for_cidp.timestamp_provider(0);
 				async move {
 					use sp_runtime::traits::UniqueSaturatedInto;
 
@@ -233,15 +241,14 @@ impl<NodeSpec: NodeSpecT> ManualSealNode<NodeSpec> {
 							UpgradeGoAhead::GoAhead
 						}),
 					};
-					Ok((
-						// This is intentional, as the runtime that we expect to run against this
-						// will never receive the aura-related inherents/digests, and providing
-						// real timestamps would cause aura <> timestamp checking to fail.
-						sp_timestamp::InherentDataProvider::new(sp_timestamp::Timestamp::new(0)),
+					Ok::<_, Box<dyn std::error::Error + Send + Sync>>((
+						timestamp?,
 						mocked_parachain,
 					))
 				}
 			},
+			backend: Some(backend.clone()),
+			time_travel: Some(time_travel),
 		};
 		let authorship_future = sc_consensus_manual_seal::run_manual_seal(params);
 		task_manager.spawn_essential_handle().spawn_blocking(
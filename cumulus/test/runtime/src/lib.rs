@@ -369,6 +369,10 @@ impl pallet_aura::Config for Runtime {
 	type AllowMultipleBlocksPerSlot = ConstBool<false>;
 	#[cfg(not(feature = "sync-backing"))]
 	type AllowMultipleBlocksPerSlot = ConstBool<true>;
+	#[cfg(feature = "sync-backing")]
+	type MaxBlocksPerSlot = ConstU32<1>;
+	#[cfg(not(feature = "sync-backing"))]
+	type MaxBlocksPerSlot = ConstU32<{ u32::MAX }>;
 	type SlotDuration = ConstU64<SLOT_DURATION>;
 }
 
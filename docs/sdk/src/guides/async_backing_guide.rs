@@ -93,6 +93,7 @@
 //! impl pallet_aura::Config for Runtime {
 //! 	..
 //! 	type AllowMultipleBlocksPerSlot = ConstBool<false>;
+//! 	type MaxBlocksPerSlot = ConstU32<1>;
 //! 	#[cfg(feature = "experimental")]
 //! 	type SlotDuration = ConstU64<SLOT_DURATION>;
 //! 	..
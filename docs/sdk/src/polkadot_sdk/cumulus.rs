@@ -111,6 +111,7 @@ mod tests {
 				type DisabledValidators = ();
 				type MaxAuthorities = ConstU32<100_000>;
 				type AllowMultipleBlocksPerSlot = ConstBool<false>;
+				type MaxBlocksPerSlot = ConstU32<1>;
 				type SlotDuration = pallet_aura::MinimumPeriodTimesTwo<Self>;
 			}
 
@@ -53,6 +53,10 @@ pub enum Subcommand {
 	/// Revert the chain to a previous state.
 	Revert(sc_cli::RevertCmd),
 
+	/// Export or import a state snapshot.
+	#[command(subcommand)]
+	Snapshot(sc_cli::SnapshotCmd),
+
 	/// Sub-commands concerned with benchmarking.
 	/// The pallet benchmarking moved to the `pallet` sub-command.
 	#[command(subcommand)]
@@ -166,6 +170,29 @@ pub struct RunCmd {
 	/// networks.
 	#[arg(long)]
 	pub keep_finalized_for: Option<u32>,
+
+	/// Directory to archive the available data of finalized candidates to, before it is pruned
+	/// from the availability store. Archived PoVs are served through the
+	/// `availability_archivedPov` RPC.
+	///
+	/// Only supported on validators. Only candidates whose full available data is held by this
+	/// node are archived, i.e. the ones it backed or recovered. Candidates for which the node
+	/// only holds its own chunk are not recovered and therefore missing from the archive.
+	#[arg(long, conflicts_with = "pov_archive_bucket")]
+	pub pov_archive_dir: Option<PathBuf>,
+
+	/// Archive to a bucket of an S3-compatible object storage service instead of a directory,
+	/// given as `<endpoint>/<bucket>`, e.g. `https://s3.eu-central-1.amazonaws.com/my-povs`.
+	///
+	/// Requests are signed with the credentials in the `AWS_ACCESS_KEY_ID` and
+	/// `AWS_SECRET_ACCESS_KEY` environment variables. The same restrictions as for
+	/// `--pov-archive-dir` apply.
+	#[arg(long)]
+	pub pov_archive_bucket: Option<String>,
+
+	/// Region of the bucket given with `--pov-archive-bucket`.
+	#[arg(long, requires = "pov_archive_bucket", default_value = "us-east-1")]
+	pub pov_archive_bucket_region: String,
 }
 
 #[allow(missing_docs)]
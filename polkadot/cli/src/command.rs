@@ -14,7 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
 
-use crate::cli::{Cli, Subcommand, NODE_VERSION};
+use crate::cli::{Cli, RunCmd, Subcommand, NODE_VERSION};
 use frame_benchmarking_cli::{
 	BenchmarkCmd, ExtrinsicFactory, SubstrateRemarkBuilder, SUBSTRATE_REFERENCE_HARDWARE,
 };
@@ -157,6 +157,39 @@ fn set_default_ss58_version(spec: &Box<dyn polkadot_service::ChainSpec>) {
 	sp_core::crypto::set_default_ss58_version(ss58_version);
 }
 
+/// Where to archive PoVs to, as configured by `--pov-archive-dir` or `--pov-archive-bucket`.
+fn pov_archive_location(run: &RunCmd) -> Result<Option<polkadot_service::ArchiveLocation>> {
+	if let Some(path) = &run.pov_archive_dir {
+		return Ok(Some(polkadot_service::ArchiveLocation::Directory(path.clone())))
+	}
+	let Some(url) = &run.pov_archive_bucket else { return Ok(None) };
+
+	let (endpoint, bucket) = url
+		.trim_end_matches('/')
+		.rsplit_once('/')
+		.filter(|(endpoint, bucket)| {
+			endpoint.contains("://") && !endpoint.ends_with('/') && !bucket.is_empty()
+		})
+		.ok_or_else(|| {
+			Error::Other(format!(
+				"--pov-archive-bucket must be given as <endpoint>/<bucket>, got {url}"
+			))
+		})?;
+	let credential = |name: &str| {
+		std::env::var(name).map_err(|_| {
+			Error::Other(format!("{name} must be set when archiving PoVs to a bucket"))
+		})
+	};
+
+	Ok(Some(polkadot_service::ArchiveLocation::Bucket(polkadot_service::S3Config {
+		endpoint: endpoint.into(),
+		bucket: bucket.into(),
+		region: run.pov_archive_bucket_region.clone(),
+		access_key_id: credential("AWS_ACCESS_KEY_ID")?,
+		secret_access_key: credential("AWS_SECRET_ACCESS_KEY")?,
+	})))
+}
+
 /// Launch a node, accepting arguments just like a regular node,
 /// accepts an alternative overseer generator, to adjust behavior
 /// for integration tests as needed.
@@ -202,6 +235,7 @@ where
 		if cli.run.disable_worker_version_check { None } else { Some(NODE_VERSION.to_string()) };
 
 	let secure_validator_mode = cli.run.base.validator && !cli.run.insecure_validator;
+	let pov_archive = pov_archive_location(&cli.run)?;
 
 	runner.run_node_until_exit(move |config| async move {
 		let hwbench = (!cli.run.no_hardware_benchmarks)
@@ -236,6 +270,7 @@ where
 				prepare_workers_soft_max_num: cli.run.prepare_workers_soft_max_num,
 				enable_approval_voting_parallel: cli.run.enable_approval_voting_parallel,
 				keep_finalized_for: cli.run.keep_finalized_for,
+				pov_archive,
 			},
 		)
 		.map(|full| full.task_manager)?;
@@ -374,6 +409,18 @@ pub fn run() -> Result<()> {
 				))
 			})?)
 		},
+		Some(Subcommand::Snapshot(cmd)) => {
+			let runner = cli.create_runner(cmd)?;
+			let chain_spec = &runner.config().chain_spec;
+
+			set_default_ss58_version(chain_spec);
+
+			Ok(runner.async_run(|mut config| {
+				let (client, _, import_queue, task_manager) =
+					polkadot_service::new_chain_ops(&mut config)?;
+				Ok((cmd.run(client, import_queue).map_err(Error::SubstrateCli), task_manager))
+			})?)
+		},
 		Some(Subcommand::Benchmark(cmd)) => {
 			let runner = cli.create_runner(cmd)?;
 			let chain_spec = &runner.config().chain_spec;
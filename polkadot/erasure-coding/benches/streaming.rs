@@ -0,0 +1,108 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::time::Duration;
+
+const N_VALIDATORS: [usize; 3] = [200, 1000, 2000];
+
+const KB: usize = 1024;
+const MB: usize = 1024 * KB;
+
+fn construct(c: &mut Criterion) {
+	let pov = vec![0xfe; 5 * MB];
+
+	let mut group = c.benchmark_group("construct_streaming");
+	group.throughput(Throughput::Bytes(pov.len() as u64));
+	for n_validators in N_VALIDATORS {
+		group.bench_with_input(
+			BenchmarkId::new("in_memory", n_validators),
+			&n_validators,
+			|b, &n| {
+				b.iter(|| polkadot_erasure_coding::obtain_chunks(n, &pov).unwrap());
+			},
+		);
+
+		group.bench_with_input(
+			BenchmarkId::new("streamed", n_validators),
+			&n_validators,
+			|b, &n| {
+				b.iter(|| {
+					let mut chunks = vec![Vec::new(); n];
+					polkadot_erasure_coding::obtain_chunks_to_writers(n, &pov[..], &mut chunks)
+						.unwrap();
+					chunks
+				});
+			},
+		);
+	}
+	group.finish();
+}
+
+fn reconstruct(c: &mut Criterion) {
+	let pov = vec![0xfe; 5 * MB];
+
+	let mut group = c.benchmark_group("reconstruct_streaming");
+	group.throughput(Throughput::Bytes(pov.len() as u64));
+	for n_validators in N_VALIDATORS {
+		let all_chunks = polkadot_erasure_coding::obtain_chunks(n_validators, &pov).unwrap();
+		let chunks: Vec<_> = all_chunks
+			.iter()
+			.enumerate()
+			.take(polkadot_erasure_coding::recovery_threshold(n_validators).unwrap())
+			.map(|(i, c)| (&c[..], i))
+			.collect();
+
+		group.bench_with_input(
+			BenchmarkId::new("in_memory", n_validators),
+			&n_validators,
+			|b, &n| {
+				b.iter(|| {
+					let _pov: Vec<u8> =
+						polkadot_erasure_coding::reconstruct(n, chunks.clone()).unwrap();
+				});
+			},
+		);
+
+		group.bench_with_input(
+			BenchmarkId::new("streamed", n_validators),
+			&n_validators,
+			|b, &n| {
+				b.iter(|| {
+					let mut pov = Vec::with_capacity(pov.len());
+					polkadot_erasure_coding::reconstruct_to_writer(n, chunks.clone(), &mut pov)
+						.unwrap();
+					pov
+				});
+			},
+		);
+	}
+	group.finish();
+}
+
+fn criterion_config() -> Criterion {
+	Criterion::default()
+		.sample_size(15)
+		.warm_up_time(Duration::from_millis(200))
+		.measurement_time(Duration::from_secs(3))
+}
+
+criterion_group!(
+	name = streaming;
+	config = criterion_config();
+	targets = construct, reconstruct,
+);
+criterion_main!(streaming);
@@ -36,6 +36,13 @@ use thiserror::Error;
 
 use novelpoly::{CodeParams, WrappedShard};
 
+mod stream;
+
+pub use stream::{
+	available_data_reader, obtain_chunks_to_writers, obtain_chunks_v1_to_writers,
+	reconstruct_from_systematic_to_writer, reconstruct_to_writer,
+};
+
 // we are limited to the field order of GF(2^16), which is 65536
 const MAX_VALIDATORS: usize = novelpoly::f2e16::FIELD_SIZE;
 
@@ -84,6 +91,9 @@ pub enum Error {
 	/// Unknown error
 	#[error("An unknown error has appeared when deriving code parameters from validator count")]
 	UnknownCodeParam,
+	/// Reading or writing chunk data failed.
+	#[error("I/O error while streaming chunk data: {0}")]
+	Io(std::io::ErrorKind),
 }
 
 impl From<novelpoly::Error> for Error {
@@ -331,7 +341,7 @@ pub fn branch_hash(root: &H256, branch_nodes: &Proof, index: usize) -> Result<H2
 
 #[cfg(test)]
 mod tests {
-	use std::sync::Arc;
+	use std::{io::Read, sync::Arc};
 
 	use super::*;
 	use polkadot_node_primitives::{AvailableData, BlockData, PoV};
@@ -411,6 +421,108 @@ mod tests {
 		QuickCheck::new().quickcheck(property as fn(ArbitraryAvailableData, u16))
 	}
 
+	#[test]
+	fn available_data_reader_matches_encoding() {
+		fn property(available_data: ArbitraryAvailableData) {
+			let mut streamed = Vec::new();
+			available_data_reader(&available_data.0).read_to_end(&mut streamed).unwrap();
+			assert_eq!(streamed, available_data.0.encode());
+		}
+
+		QuickCheck::new().quickcheck(property as fn(ArbitraryAvailableData))
+	}
+
+	#[test]
+	fn streamed_chunks_match_obtain_chunks() {
+		fn property(available_data: ArbitraryAvailableData, n_validators: u16) {
+			let n_validators = n_validators.max(2) as usize;
+			let expected = obtain_chunks_v1(n_validators, &available_data.0).unwrap();
+
+			let mut chunks = vec![Vec::new(); n_validators];
+			obtain_chunks_v1_to_writers(n_validators, &available_data.0, &mut chunks).unwrap();
+
+			assert_eq!(chunks, expected);
+			assert_eq!(branches(&chunks).root(), branches(&expected).root());
+		}
+
+		QuickCheck::new().quickcheck(property as fn(ArbitraryAvailableData, u16))
+	}
+
+	#[test]
+	fn multi_segment_streamed_chunks_match_obtain_chunks() {
+		for n_validators in [10, 100] {
+			let k = code_params(n_validators).unwrap().k();
+			let segment_len = stream::segment_runs(k) * 2 * k;
+			// Two full segments, and a partial one with an odd length.
+			let block_data = (0..segment_len * 5 / 2 + 1).map(|i| (i % 251) as u8).collect();
+			let pov = PoV { block_data: BlockData(block_data) };
+			let available_data =
+				AvailableData { pov: pov.into(), validation_data: Default::default() };
+			let expected = obtain_chunks_v1(n_validators, &available_data).unwrap();
+
+			let mut chunks = vec![Vec::new(); n_validators];
+			obtain_chunks_v1_to_writers(n_validators, &available_data, &mut chunks).unwrap();
+
+			assert_eq!(chunks, expected, "{n_validators} validators");
+			assert_eq!(branches(&chunks).root(), branches(&expected).root());
+		}
+	}
+
+	#[test]
+	fn streamed_round_trip_works() {
+		// Large enough to span several segments.
+		let pov = PoV { block_data: BlockData((0..300_000).map(|i| i as u8).collect()) };
+		let available_data = AvailableData { pov: pov.into(), validation_data: Default::default() };
+		let mut chunks = vec![Vec::new(); 10];
+		obtain_chunks_v1_to_writers(10, &available_data, &mut chunks).unwrap();
+
+		let mut payload = Vec::new();
+		reconstruct_to_writer(
+			10,
+			[1, 4, 6, 9].into_iter().map(|i| (&chunks[i][..], i)),
+			&mut payload,
+		)
+		.unwrap();
+		assert_eq!(AvailableData::decode(&mut &payload[..]).unwrap(), available_data);
+
+		let mut payload = Vec::new();
+		reconstruct_from_systematic_to_writer(10, chunks.iter().map(|c| &c[..]), &mut payload)
+			.unwrap();
+		assert_eq!(AvailableData::decode(&mut &payload[..]).unwrap(), available_data);
+	}
+
+	#[test]
+	fn streaming_rejects_bad_input() {
+		let mut writers = vec![Vec::new(); 9];
+		assert_eq!(
+			obtain_chunks_to_writers(10, &[1u8, 2, 3][..], &mut writers),
+			Err(Error::WrongValidatorCount),
+		);
+
+		let mut writers = vec![Vec::new(); 10];
+		assert_eq!(obtain_chunks_to_writers(10, &[][..], &mut writers), Err(Error::BadPayload));
+
+		let chunks = obtain_chunks(10, &vec![7u8; 1000]).unwrap();
+		let mut payload = Vec::new();
+		assert_eq!(
+			reconstruct_to_writer(
+				10,
+				[
+					(&chunks[0][..], 0),
+					(&chunks[1][1..], 1),
+					(&chunks[2][..], 2),
+					(&chunks[3][..], 3)
+				],
+				&mut payload,
+			),
+			Err(Error::NonUniformChunks),
+		);
+		assert_eq!(
+			reconstruct_to_writer(10, [(&chunks[0][..], 10)], &mut payload),
+			Err(Error::ChunkIndexOutOfBounds { chunk_index: 10, n_validators: 10 }),
+		);
+	}
+
 	#[test]
 	fn reconstruct_does_not_panic_on_low_validator_count() {
 		let reconstructed = reconstruct_v1(1, [].iter().cloned());
@@ -0,0 +1,253 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Streaming variants of chunk construction and reconstruction.
+//!
+//! `novelpoly` encodes the payload in independent runs of `2 * k` bytes, each run contributing
+//! one 2-byte symbol to every chunk. Processing the payload in segments made of whole runs
+//! therefore yields exactly the same chunks as encoding it at once, while only ever holding a
+//! single segment of the payload in memory.
+
+use std::io::{self, Read, Write};
+
+use codec::{Compact, Encode};
+use novelpoly::WrappedShard;
+use polkadot_node_primitives::AvailableData;
+
+use crate::{code_params, Error};
+
+/// Number of payload bytes encoded per segment, before rounding to whole runs.
+const SEGMENT_BYTES: usize = 64 * 1024;
+
+/// Number of bytes of each chunk consumed per segment when reconstructing.
+///
+/// Every reconstruction call recomputes the erasure locator polynomial, so segments are kept much
+/// larger than when encoding.
+const CHUNK_SEGMENT_BYTES: usize = 8 * 1024;
+
+/// Number of runs of `2 * k` payload bytes making up a segment.
+pub(crate) fn segment_runs(k: usize) -> usize {
+	(SEGMENT_BYTES / (2 * k)).max(1)
+}
+
+/// A reader over the SCALE encoding of `AvailableData` which doesn't copy the PoV.
+pub fn available_data_reader(data: &AvailableData) -> impl Read + '_ {
+	let block_data = &data.pov.block_data.0[..];
+	let length_prefix = Compact(block_data.len() as u32).encode();
+
+	io::Cursor::new(length_prefix)
+		.chain(block_data)
+		.chain(io::Cursor::new(data.validation_data.encode()))
+}
+
+/// Stream erasure-coded chunks for v1 `AvailableData` into `writers`, one for each validator.
+///
+/// Produces the same chunks as [`obtain_chunks_v1`](crate::obtain_chunks_v1).
+pub fn obtain_chunks_v1_to_writers<W: Write>(
+	n_validators: usize,
+	data: &AvailableData,
+	writers: &mut [W],
+) -> Result<(), Error> {
+	encode_to_writers(n_validators, available_data_reader(data), data.encoded_size(), writers)
+}
+
+/// Erasure-code everything read from `reader`, appending the chunk of each validator to the
+/// writer at the same index.
+///
+/// Produces the same chunks as [`obtain_chunks`](crate::obtain_chunks) does for the encoded
+/// payload. Works only up to 65536 validators, and `writers` must contain one writer per
+/// validator.
+pub fn obtain_chunks_to_writers<R: Read, W: Write>(
+	n_validators: usize,
+	reader: R,
+	writers: &mut [W],
+) -> Result<(), Error> {
+	encode_to_writers(n_validators, reader, 0, writers)
+}
+
+/// Erasure-code everything read from `reader` segment by segment.
+///
+/// The segment buffer starts out with room for `payload_len_hint` bytes at most, and only grows
+/// as far as the reader actually yields data.
+fn encode_to_writers<R: Read, W: Write>(
+	n_validators: usize,
+	mut reader: R,
+	payload_len_hint: usize,
+	writers: &mut [W],
+) -> Result<(), Error> {
+	let params = code_params(n_validators)?;
+	if writers.len() != n_validators {
+		return Err(Error::WrongValidatorCount)
+	}
+
+	let encoder = params.make_encoder();
+	let segment_len = segment_runs(params.k()) * 2 * params.k();
+	let mut segment = Vec::with_capacity(segment_len.min(payload_len_hint));
+	let mut payload_len = 0;
+
+	loop {
+		segment.clear();
+		(&mut reader)
+			.take(segment_len as u64)
+			.read_to_end(&mut segment)
+			.map_err(io_error)?;
+		let read = segment.len();
+		if read == 0 {
+			break
+		}
+		payload_len += read;
+
+		let shards = encoder.encode::<WrappedShard>(&segment)?;
+		for (shard, writer) in shards.into_iter().zip(writers.iter_mut()) {
+			writer.write_all(&shard.into_inner()).map_err(io_error)?;
+		}
+
+		if read < segment_len {
+			break
+		}
+	}
+
+	if payload_len == 0 {
+		return Err(Error::BadPayload)
+	}
+
+	Ok(())
+}
+
+/// Reconstruct the encoded payload from a set of chunk readers, writing it to `writer`.
+///
+/// Provide an iterator containing chunk readers and the corresponding index. All readers must
+/// yield the same number of bytes. If too few chunks are provided, recovery is not possible.
+///
+/// The payload is written in whole runs, so it may be followed by zero padding. Works only up to
+/// 65536 validators, and `n_validators` must be non-zero.
+pub fn reconstruct_to_writer<R: Read, I, W: Write>(
+	n_validators: usize,
+	chunks: I,
+	writer: W,
+) -> Result<(), Error>
+where
+	I: IntoIterator<Item = (R, usize)>,
+{
+	let params = code_params(n_validators)?;
+	let mut readers: Vec<Option<R>> = (0..n_validators).map(|_| None).collect();
+	for (reader, chunk_index) in chunks.into_iter().take(n_validators) {
+		*readers
+			.get_mut(chunk_index)
+			.ok_or(Error::ChunkIndexOutOfBounds { chunk_index, n_validators })? = Some(reader);
+	}
+
+	let encoder = params.make_encoder();
+	stream_segments(readers, writer, |shards| encoder.reconstruct(shards).map_err(Into::into))
+}
+
+/// Reconstruct the encoded payload from readers over the systematic chunks, writing it to
+/// `writer`.
+///
+/// Provide an iterator containing readers over the first k chunks in order. If too few chunks
+/// are provided, recovery is not possible.
+///
+/// The payload is written in whole runs, so it may be followed by zero padding.
+pub fn reconstruct_from_systematic_to_writer<R: Read, I, W: Write>(
+	n_validators: usize,
+	chunks: I,
+	writer: W,
+) -> Result<(), Error>
+where
+	I: IntoIterator<Item = R>,
+{
+	let params = code_params(n_validators)?;
+	let k = params.k();
+	let readers: Vec<Option<R>> = chunks.into_iter().take(k).map(Some).collect();
+	if readers.len() < k {
+		return Err(Error::NotEnoughChunks)
+	}
+
+	let encoder = params.make_encoder();
+	stream_segments(readers, writer, |shards| {
+		encoder
+			.reconstruct_from_systematic(shards.into_iter().flatten().collect())
+			.map_err(Into::into)
+	})
+}
+
+/// Read the chunks segment by segment, writing whatever `decode` makes of each segment.
+fn stream_segments<R: Read, W: Write>(
+	mut readers: Vec<Option<R>>,
+	mut writer: W,
+	decode: impl Fn(Vec<Option<WrappedShard>>) -> Result<Vec<u8>, Error>,
+) -> Result<(), Error> {
+	let mut first = true;
+
+	loop {
+		let mut segment_read = None;
+		let mut shards = Vec::with_capacity(readers.len());
+		for reader in readers.iter_mut() {
+			let Some(reader) = reader else {
+				shards.push(None);
+				continue
+			};
+
+			let mut shard = vec![0u8; CHUNK_SEGMENT_BYTES];
+			let read = read_full(reader, &mut shard)?;
+			if *segment_read.get_or_insert(read) != read {
+				return Err(Error::NonUniformChunks)
+			}
+
+			shard.truncate(read);
+			shards.push(Some(WrappedShard::new(shard)));
+		}
+
+		let read = segment_read.ok_or(Error::NotEnoughChunks)?;
+		if read == 0 {
+			if first {
+				return Err(Error::NonUniformChunks)
+			}
+			break
+		}
+		if read % 2 != 0 {
+			return Err(Error::UnevenLength)
+		}
+		first = false;
+
+		writer.write_all(&decode(shards)?).map_err(io_error)?;
+
+		if read < CHUNK_SEGMENT_BYTES {
+			break
+		}
+	}
+
+	Ok(())
+}
+
+/// Fill `buf` from `reader`, returning fewer bytes only once the reader is exhausted.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize, Error> {
+	let mut filled = 0;
+	while filled < buf.len() {
+		match reader.read(&mut buf[filled..]) {
+			Ok(0) => break,
+			Ok(n) => filled += n,
+			Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+			Err(err) => return Err(io_error(err)),
+		}
+	}
+
+	Ok(filled)
+}
+
+fn io_error(err: io::Error) -> Error {
+	Error::Io(err.kind())
+}
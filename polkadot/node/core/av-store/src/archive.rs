@@ -0,0 +1,160 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Archival of finalized available data before it gets pruned.
+//!
+//! The availability store only keeps data for the keep-alive period. Nodes configured with a
+//! [`PovArchive`] additionally write the full [`AvailableData`] of finalized candidates to an
+//! append-only [`ObjectStore`] right before pruning it, from which it can be served later on.
+//! Objects are either kept in a local directory or in a bucket of an S3-compatible service.
+//!
+//! Only candidates for which the full data is stored locally are archived, i.e. candidates the
+//! node backed or received the full data for. Candidates for which we only hold our own chunk
+//! are not reconstructed.
+
+use std::{
+	fs,
+	io::{self, Write},
+	path::{Path, PathBuf},
+	sync::Arc,
+};
+
+use codec::{Decode, Encode};
+use polkadot_node_primitives::AvailableData;
+use polkadot_primitives::CandidateHash;
+
+use crate::Error;
+
+pub mod s3;
+
+use s3::{S3Bucket, S3Config};
+
+/// Key-value storage the [`PovArchive`] writes to.
+///
+/// This is the extension point for object-store-compatible backends. Objects are written once
+/// and never modified or deleted by the archive.
+pub trait ObjectStore: Send + Sync {
+	/// Store `value` under `key`. Storing an already present key must be a no-op.
+	fn put(&self, key: &str, value: &[u8]) -> io::Result<()>;
+
+	/// Load the object stored under `key`, if any.
+	fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>>;
+}
+
+/// An [`ObjectStore`] keeping every object in its own file within a local directory.
+#[derive(Debug, Clone)]
+pub struct LocalDirectory {
+	path: PathBuf,
+}
+
+impl LocalDirectory {
+	/// Open the directory at `path`, creating it if it doesn't exist yet.
+	pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+		let path = path.into();
+		fs::create_dir_all(&path)?;
+
+		Ok(Self { path })
+	}
+
+	/// The directory objects are stored in.
+	pub fn path(&self) -> &Path {
+		&self.path
+	}
+}
+
+impl ObjectStore for LocalDirectory {
+	fn put(&self, key: &str, value: &[u8]) -> io::Result<()> {
+		let path = self.path.join(key);
+		if path.exists() {
+			return Ok(())
+		}
+
+		// Write to a temporary file first, so that readers never observe partial objects.
+		let tmp_path = self.path.join(format!("{key}.tmp"));
+		let mut file = fs::File::create(&tmp_path)?;
+		file.write_all(value)?;
+		file.sync_all()?;
+
+		fs::rename(tmp_path, path)
+	}
+
+	fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+		match fs::read(self.path.join(key)) {
+			Ok(value) => Ok(Some(value)),
+			Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+			Err(err) => Err(err),
+		}
+	}
+}
+
+/// Where a [`PovArchive`] keeps its objects.
+#[derive(Debug, Clone)]
+pub enum ArchiveLocation {
+	/// A directory on the local file system, see [`LocalDirectory`].
+	Directory(PathBuf),
+	/// A bucket of an S3-compatible object storage service, see [`S3Bucket`].
+	Bucket(S3Config),
+}
+
+/// Archive of finalized [`AvailableData`], keyed by candidate hash.
+#[derive(Clone)]
+pub struct PovArchive {
+	store: Arc<dyn ObjectStore>,
+}
+
+impl PovArchive {
+	/// Create an archive writing to the given store.
+	pub fn new(store: Arc<dyn ObjectStore>) -> Self {
+		Self { store }
+	}
+
+	/// Create an archive writing to a local directory.
+	pub fn local(path: impl Into<PathBuf>) -> io::Result<Self> {
+		LocalDirectory::open(path).map(|dir| Self::new(Arc::new(dir)))
+	}
+
+	/// Create an archive writing to the given location.
+	pub fn open(location: ArchiveLocation) -> io::Result<Self> {
+		match location {
+			ArchiveLocation::Directory(path) => Self::local(path),
+			ArchiveLocation::Bucket(config) =>
+				S3Bucket::new(config).map(|bucket| Self::new(Arc::new(bucket))),
+		}
+	}
+
+	/// Archive the available data of a candidate.
+	pub fn archive(
+		&self,
+		candidate_hash: &CandidateHash,
+		available_data: &AvailableData,
+	) -> Result<(), Error> {
+		self.store
+			.put(&object_key(candidate_hash), &available_data.encode())
+			.map_err(Error::Archive)
+	}
+
+	/// Load the archived available data of a candidate, if any.
+	pub fn load(&self, candidate_hash: &CandidateHash) -> Result<Option<AvailableData>, Error> {
+		match self.store.get(&object_key(candidate_hash)).map_err(Error::Archive)? {
+			Some(raw) => Ok(Some(AvailableData::decode(&mut &raw[..])?)),
+			None => Ok(None),
+		}
+	}
+}
+
+fn object_key(candidate_hash: &CandidateHash) -> String {
+	format!("{:x}", candidate_hash.0)
+}
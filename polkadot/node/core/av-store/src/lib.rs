@@ -52,9 +52,13 @@ use polkadot_primitives::{
 };
 use util::availability_chunks::availability_chunk_indices;
 
+pub mod archive;
 mod metrics;
+pub mod rpc;
 pub use self::metrics::*;
 
+use archive::PovArchive;
+
 #[cfg(test)]
 mod tests;
 
@@ -78,6 +82,9 @@ const KEEP_UNAVAILABLE_FOR: Duration = Duration::from_secs(60 * 60);
 /// The pruning interval.
 const PRUNING_INTERVAL: Duration = Duration::from_secs(60 * 5);
 
+/// Finalized data that can't be archived is kept for at most 1 hour past its pruning time.
+const MAX_ARCHIVE_POSTPONEMENT: Duration = Duration::from_secs(60 * 60);
+
 /// Unix time wrapper with big-endian encoding.
 #[derive(Debug, Clone, Copy, PartialEq, PartialOrd, Eq, Ord)]
 struct BETimestamp(u64);
@@ -186,7 +193,11 @@ fn write_available_data(
 	tx.put_vec(config.col_data, &key[..], available_data.encode());
 }
 
-fn load_available_data(
+/// Load the full [`AvailableData`] stored for a candidate, if any.
+///
+/// Exposed so that offline tooling can read a stopped node's availability store without
+/// depending on the on-disk key layout.
+pub fn load_available_data(
 	db: &Arc<dyn Database>,
 	config: &Config,
 	hash: &CandidateHash,
@@ -375,6 +386,9 @@ pub enum Error {
 
 	#[error("Erasure root does not match expected one")]
 	InvalidErasureRoot,
+
+	#[error("Failed to access the PoV archive: {0}")]
+	Archive(#[source] io::Error),
 }
 
 impl Error {
@@ -454,6 +468,7 @@ pub struct AvailabilityStoreSubsystem {
 	metrics: Metrics,
 	clock: Box<dyn Clock>,
 	sync_oracle: Box<dyn SyncOracle + Send + Sync>,
+	archive: Option<PovArchive>,
 }
 
 impl AvailabilityStoreSubsystem {
@@ -498,8 +513,18 @@ impl AvailabilityStoreSubsystem {
 			known_blocks: KnownUnfinalizedBlocks::default(),
 			sync_oracle,
 			finalized_number: None,
+			archive: None,
 		}
 	}
+
+	/// Archive the available data of finalized candidates before pruning it.
+	///
+	/// If archiving fails, pruning is retried on later runs for up to an hour before the data is
+	/// pruned without being archived.
+	pub fn with_archive(mut self, archive: PovArchive) -> Self {
+		self.archive = Some(archive);
+		self
+	}
 }
 
 /// We keep the hashes and numbers of all unfinalized
@@ -657,6 +682,7 @@ async fn start_prune_all<Context>(
 	let metrics = subsystem.metrics.clone();
 	let db = subsystem.db.clone();
 	let config = subsystem.config;
+	let archive = subsystem.archive.clone();
 	let time_now = subsystem.clock.now()?;
 
 	ctx.spawn_blocking(
@@ -665,7 +691,7 @@ async fn start_prune_all<Context>(
 			let _timer = metrics.time_pruning();
 
 			gum::debug!(target: LOG_TARGET, "Prunning started");
-			let result = prune_all(&db, &config, time_now);
+			let result = prune_all(&db, &config, time_now, archive.as_ref(), &metrics);
 
 			if let Err(err) = pruning_result_tx.send(result).await {
 				// This usually means that the node is closing down, log it just in case
@@ -1353,7 +1379,13 @@ fn store_available_data(
 	Ok(())
 }
 
-fn prune_all(db: &Arc<dyn Database>, config: &Config, now: Duration) -> Result<(), Error> {
+fn prune_all(
+	db: &Arc<dyn Database>,
+	config: &Config,
+	now: Duration,
+	archive: Option<&PovArchive>,
+	metrics: &Metrics,
+) -> Result<(), Error> {
 	let (range_start, range_end) = pruning_range(now);
 
 	let mut tx = DBTransaction::new();
@@ -1363,17 +1395,48 @@ fn prune_all(db: &Arc<dyn Database>, config: &Config, now: Duration) -> Result<(
 
 	for r in iter {
 		let (k, _v) = r?;
-		tx.delete(config.col_meta, &k[..]);
 
-		let (_, candidate_hash) = match decode_pruning_key(&k[..]) {
+		let (prune_at, candidate_hash) = match decode_pruning_key(&k[..]) {
 			Ok(m) => m,
-			Err(_) => continue, // sanity
+			Err(_) => {
+				// sanity
+				tx.delete(config.col_meta, &k[..]);
+				continue
+			},
 		};
 
+		let meta = load_meta(db, config, &candidate_hash)?;
+
+		if let (Some(archive), Some(meta)) = (archive, &meta) {
+			match archive_candidate(db, config, archive, &candidate_hash, meta) {
+				Ok(true) => metrics.on_archived(),
+				Ok(false) => {},
+				Err(err) if now < prune_at + MAX_ARCHIVE_POSTPONEMENT => {
+					// Keep the candidate around, archiving is retried on the next pruning run.
+					gum::warn!(
+						target: LOG_TARGET,
+						?candidate_hash,
+						?err,
+						"Failed to archive available data, postponing pruning",
+					);
+					continue
+				},
+				Err(err) => {
+					gum::error!(
+						target: LOG_TARGET,
+						?candidate_hash,
+						?err,
+						"Failed to archive available data, pruning it anyway",
+					);
+				},
+			}
+		}
+
+		tx.delete(config.col_meta, &k[..]);
 		delete_meta(&mut tx, config, &candidate_hash);
 
 		// Clean up all attached data of the candidate.
-		if let Some(meta) = load_meta(db, config, &candidate_hash)? {
+		if let Some(meta) = meta {
 			// delete available data.
 			if meta.data_available {
 				delete_available_data(&mut tx, config, &candidate_hash)
@@ -1405,3 +1468,22 @@ fn prune_all(db: &Arc<dyn Database>, config: &Config, now: Duration) -> Result<(
 	db.write(tx)?;
 	Ok(())
 }
+
+// Write the available data of a finalized candidate to the archive, if we have it.
+// Returns whether anything was archived.
+fn archive_candidate(
+	db: &Arc<dyn Database>,
+	config: &Config,
+	archive: &PovArchive,
+	candidate_hash: &CandidateHash,
+	meta: &CandidateMeta,
+) -> Result<bool, Error> {
+	if !matches!(meta.state, State::Finalized(_)) || !meta.data_available {
+		return Ok(false)
+	}
+
+	match load_available_data(db, config, candidate_hash)? {
+		Some(available_data) => archive.archive(candidate_hash, &available_data).map(|()| true),
+		None => Ok(false),
+	}
+}
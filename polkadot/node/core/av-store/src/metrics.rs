@@ -19,6 +19,7 @@ use polkadot_node_subsystem_util::metrics::{self, prometheus};
 #[derive(Clone)]
 pub(crate) struct MetricsInner {
 	received_availability_chunks_total: prometheus::Counter<prometheus::U64>,
+	archived_available_data_total: prometheus::Counter<prometheus::U64>,
 	pruning: prometheus::Histogram,
 	process_block_finalized: prometheus::Histogram,
 	block_activated: prometheus::Histogram,
@@ -41,6 +42,12 @@ impl Metrics {
 		}
 	}
 
+	pub(crate) fn on_archived(&self) {
+		if let Some(metrics) = &self.0 {
+			metrics.archived_available_data_total.inc();
+		}
+	}
+
 	/// Provide a timer for `prune_povs` which observes on drop.
 	pub(crate) fn time_pruning(&self) -> Option<metrics::prometheus::prometheus::HistogramTimer> {
 		self.0.as_ref().map(|metrics| metrics.pruning.start_timer())
@@ -97,6 +104,13 @@ impl metrics::Metrics for Metrics {
 				)?,
 				registry,
 			)?,
+			archived_available_data_total: prometheus::register(
+				prometheus::Counter::new(
+					"polkadot_parachain_av_store_archived_available_data_total",
+					"Number of finalized available data archived before pruning.",
+				)?,
+				registry,
+			)?,
 			pruning: prometheus::register(
 				prometheus::Histogram::with_opts(prometheus::HistogramOpts::new(
 					"polkadot_parachain_av_store_pruning",
@@ -0,0 +1,64 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! RPC interface serving PoVs from a [`PovArchive`].
+
+use codec::Encode;
+use jsonrpsee::{
+	core::RpcResult,
+	proc_macros::rpc,
+	types::{ErrorObject, ErrorObjectOwned},
+};
+use polkadot_primitives::{CandidateHash, Hash};
+use sp_core::Bytes;
+
+use crate::{archive::PovArchive, Error};
+
+/// Error code returned when reading from the archive fails.
+const ARCHIVE_ERROR: i32 = 9000;
+
+/// RPC methods for looking up archived availability data.
+#[rpc(client, server)]
+pub trait AvailabilityArchiveApi {
+	/// Returns the SCALE-encoded PoV of the candidate with the given hash, if it was archived.
+	#[method(name = "availability_archivedPov", blocking)]
+	fn archived_pov(&self, candidate_hash: Hash) -> RpcResult<Option<Bytes>>;
+}
+
+/// A struct that implements the [`AvailabilityArchiveApiServer`].
+pub struct AvailabilityArchive {
+	archive: PovArchive,
+}
+
+impl AvailabilityArchive {
+	/// Create a new [`AvailabilityArchive`] serving PoVs from the given archive.
+	pub fn new(archive: PovArchive) -> Self {
+		Self { archive }
+	}
+}
+
+impl AvailabilityArchiveApiServer for AvailabilityArchive {
+	fn archived_pov(&self, candidate_hash: Hash) -> RpcResult<Option<Bytes>> {
+		let available_data =
+			self.archive.load(&CandidateHash(candidate_hash)).map_err(into_rpc_error)?;
+
+		Ok(available_data.map(|data| data.pov.encode().into()))
+	}
+}
+
+fn into_rpc_error(err: Error) -> ErrorObjectOwned {
+	ErrorObject::owned(ARCHIVE_ERROR, err.to_string(), None::<()>)
+}
@@ -15,6 +15,8 @@
 // along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
 
 use super::*;
+use archive::ObjectStore as _;
+use rpc::AvailabilityArchiveApiServer as _;
 
 use assert_matches::assert_matches;
 use futures::{channel::oneshot, executor, future, Future};
@@ -75,6 +77,7 @@ struct TestState {
 	persisted_validation_data: PersistedValidationData,
 	pruning_config: PruningConfig,
 	clock: TestClock,
+	archive: Option<PovArchive>,
 }
 
 impl TestState {
@@ -102,7 +105,7 @@ impl Default for TestState {
 
 		let clock = TestClock { inner: Arc::new(Mutex::new(Duration::from_secs(0))) };
 
-		Self { persisted_validation_data, pruning_config, clock }
+		Self { persisted_validation_data, pruning_config, clock, archive: None }
 	}
 }
 
@@ -129,7 +132,7 @@ fn test_harness<T: Future<Output = VirtualOverseer>>(
 	let (context, virtual_overseer) =
 		polkadot_node_subsystem_test_helpers::make_subsystem_context(pool.clone());
 
-	let subsystem = AvailabilityStoreSubsystem::with_pruning_config_and_clock(
+	let mut subsystem = AvailabilityStoreSubsystem::with_pruning_config_and_clock(
 		store,
 		TEST_CONFIG,
 		state.pruning_config.clone(),
@@ -137,6 +140,9 @@ fn test_harness<T: Future<Output = VirtualOverseer>>(
 		Box::new(NoSyncOracle),
 		Metrics::default(),
 	);
+	if let Some(archive) = state.archive {
+		subsystem = subsystem.with_archive(archive);
+	}
 
 	let subsystem = run(subsystem, context);
 
@@ -1215,6 +1221,139 @@ fn forkfullness_works() {
 	});
 }
 
+#[derive(Default)]
+struct MemoryObjectStore {
+	objects: Mutex<HashMap<String, Vec<u8>>>,
+	unavailable: bool,
+}
+
+impl archive::ObjectStore for MemoryObjectStore {
+	fn put(&self, key: &str, value: &[u8]) -> io::Result<()> {
+		if self.unavailable {
+			return Err(io::Error::other("archive unavailable"))
+		}
+		self.objects.lock().entry(key.to_owned()).or_insert_with(|| value.to_vec());
+		Ok(())
+	}
+
+	fn get(&self, key: &str) -> io::Result<Option<Vec<u8>>> {
+		Ok(self.objects.lock().get(key).cloned())
+	}
+}
+
+// Store the available data of a candidate, include it in a block and finalize that block.
+async fn store_and_finalize(
+	virtual_overseer: &mut VirtualOverseer,
+	test_state: &TestState,
+) -> (CandidateHash, AvailableData) {
+	let n_validators = 10;
+	let pov = PoV { block_data: BlockData(vec![4, 5, 6]) };
+	let candidate = TestCandidateBuilder { pov_hash: pov.hash(), ..Default::default() }.build();
+	let candidate_hash = candidate.hash();
+	let available_data = AvailableData {
+		pov: Arc::new(pov),
+		validation_data: test_state.persisted_validation_data.clone(),
+	};
+	let block_number = 10;
+
+	let chunks =
+		polkadot_erasure_coding::obtain_chunks_v1(n_validators as _, &available_data).unwrap();
+	let branches = polkadot_erasure_coding::branches(chunks.as_ref());
+
+	let (tx, rx) = oneshot::channel();
+	let block_msg = AvailabilityStoreMessage::StoreAvailableData {
+		candidate_hash,
+		n_validators,
+		available_data: available_data.clone(),
+		tx,
+		node_features: NodeFeatures::EMPTY,
+		core_index: CoreIndex(1),
+		expected_erasure_root: branches.root(),
+	};
+	virtual_overseer.send(FromOrchestra::Communication { msg: block_msg }).await;
+	rx.await.unwrap().unwrap();
+
+	let a_leaf = import_leaf(
+		virtual_overseer,
+		Hash::repeat_byte(2),
+		block_number,
+		vec![candidate_included(candidate)],
+		(0..n_validators).map(|_| Sr25519Keyring::Alice.public().into()).collect(),
+	)
+	.await;
+	overseer_signal(virtual_overseer, OverseerSignal::BlockFinalized(a_leaf, block_number)).await;
+
+	(candidate_hash, available_data)
+}
+
+#[test]
+fn finalized_data_is_archived_before_pruning() {
+	let store = test_store();
+	let archive = PovArchive::new(Arc::new(MemoryObjectStore::default()));
+	let test_state = TestState { archive: Some(archive.clone()), ..Default::default() };
+
+	test_harness(test_state.clone(), store.clone(), |mut virtual_overseer| async move {
+		let (candidate_hash, available_data) =
+			store_and_finalize(&mut virtual_overseer, &test_state).await;
+
+		// Nothing is archived while the data is kept in the store.
+		test_state.wait_for_pruning().await;
+		assert!(archive.load(&candidate_hash).unwrap().is_none());
+
+		test_state.clock.inc(test_state.pruning_config.keep_finalized_for * 2);
+		test_state.wait_for_pruning().await;
+
+		assert!(query_available_data(&mut virtual_overseer, candidate_hash).await.is_none());
+		assert_eq!(archive.load(&candidate_hash).unwrap(), Some(available_data.clone()));
+
+		let archived_pov =
+			rpc::AvailabilityArchive::new(archive).archived_pov(candidate_hash.0).unwrap();
+		assert_eq!(archived_pov, Some(available_data.pov.encode().into()));
+		virtual_overseer
+	});
+}
+
+#[test]
+fn pruning_is_postponed_while_archive_is_unavailable() {
+	let store = test_store();
+	let archive =
+		PovArchive::new(Arc::new(MemoryObjectStore { unavailable: true, ..Default::default() }));
+	let test_state = TestState { archive: Some(archive), ..Default::default() };
+
+	test_harness(test_state.clone(), store.clone(), |mut virtual_overseer| async move {
+		let (candidate_hash, available_data) =
+			store_and_finalize(&mut virtual_overseer, &test_state).await;
+
+		test_state.clock.inc(test_state.pruning_config.keep_finalized_for * 2);
+		test_state.wait_for_pruning().await;
+
+		// The data is kept until it can be archived.
+		assert_eq!(
+			query_available_data(&mut virtual_overseer, candidate_hash).await,
+			Some(available_data),
+		);
+
+		// But not forever.
+		test_state.clock.inc(MAX_ARCHIVE_POSTPONEMENT);
+		test_state.wait_for_pruning().await;
+
+		assert!(query_available_data(&mut virtual_overseer, candidate_hash).await.is_none());
+		virtual_overseer
+	});
+}
+
+#[test]
+fn local_directory_archive_is_append_only() {
+	let dir = tempfile::tempdir().unwrap();
+	let store = archive::LocalDirectory::open(dir.path().join("povs")).unwrap();
+
+	assert_eq!(store.get("candidate").unwrap(), None);
+
+	store.put("candidate", b"first").unwrap();
+	store.put("candidate", b"second").unwrap();
+	assert_eq!(store.get("candidate").unwrap(), Some(b"first".to_vec()));
+}
+
 async fn query_available_data(
 	virtual_overseer: &mut VirtualOverseer,
 	candidate_hash: CandidateHash,
@@ -42,6 +42,10 @@ enum NemesisVariant {
 	DisputeFinalizedCandidates(DisputeFinalizedCandidatesOptions),
 	/// Spam many request statements instead of sending a single one.
 	SpamStatementRequests(SpamStatementRequestsOptions),
+	/// Withhold approvals, approve without checking and spam assignments.
+	ApprovalVotingFaults(ApprovalVotingFaultsOptions),
+	/// Withhold or corrupt erasure chunks and available data.
+	AvailabilityFaults(AvailabilityFaultsOptions),
 }
 
 #[derive(Debug, Parser)]
@@ -105,6 +109,33 @@ impl MalusCli {
 
 				polkadot_cli::run_node(cli, SpamStatementRequests { spam_factor }, finality_delay)?
 			},
+			NemesisVariant::ApprovalVotingFaults(opts) => {
+				let ApprovalVotingFaultsOptions {
+					no_show_percentage,
+					approve_unchecked_percentage,
+					assignment_spam_factor,
+					cli,
+				} = opts;
+
+				polkadot_cli::run_node(
+					cli,
+					ApprovalVotingFaults {
+						no_show_percentage,
+						approve_unchecked_percentage,
+						assignment_spam_factor,
+					},
+					finality_delay,
+				)?
+			},
+			NemesisVariant::AvailabilityFaults(opts) => {
+				let AvailabilityFaultsOptions { fault, percentage, cli } = opts;
+
+				polkadot_cli::run_node(
+					cli,
+					AvailabilityFaults { fault, percentage },
+					finality_delay,
+				)?
+			},
 		}
 		Ok(())
 	}
@@ -244,4 +275,49 @@ mod tests {
 			assert!(opts.cli.run.base.bob);
 		});
 	}
+
+	#[test]
+	fn approval_voting_faults_options_work() {
+		let cli = MalusCli::try_parse_from(IntoIterator::into_iter([
+			"malus",
+			"approval-voting-faults",
+			"--no-show-percentage",
+			"50",
+			"--assignment-spam-factor",
+			"10",
+			"--bob",
+		]))
+		.unwrap();
+		assert_matches::assert_matches!(cli, MalusCli {
+			variant: NemesisVariant::ApprovalVotingFaults(opts),
+			..
+		} => {
+			assert_eq!(opts.no_show_percentage, 50);
+			assert_eq!(opts.approve_unchecked_percentage, 0);
+			assert_eq!(opts.assignment_spam_factor, 10);
+			assert!(opts.cli.run.base.bob);
+		});
+	}
+
+	#[test]
+	fn availability_faults_options_work() {
+		let cli = MalusCli::try_parse_from(IntoIterator::into_iter([
+			"malus",
+			"availability-faults",
+			"--fault",
+			"corrupt",
+			"--percentage",
+			"30",
+			"--bob",
+		]))
+		.unwrap();
+		assert_matches::assert_matches!(cli, MalusCli {
+			variant: NemesisVariant::AvailabilityFaults(opts),
+			..
+		} => {
+			assert_eq!(format!("{:?}", opts.fault), "Corrupt");
+			assert_eq!(opts.percentage, 30);
+			assert!(opts.cli.run.base.bob);
+		});
+	}
 }
@@ -0,0 +1,463 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A malicious node that misbehaves in approval voting.
+//!
+//! Three faults can be combined:
+//! - no-shows: the node's own approvals for a percentage of relay chain blocks are never sent to
+//!   the network, so its assignments for those blocks turn into no-shows;
+//! - approving without checking: a percentage of approval checks are answered as valid without
+//!   running the PVF, which approves invalid candidates;
+//! - assignment spam: every outgoing assignment message is sent multiple times.
+//!
+//! Attention: For usage with `zombienet` only!
+
+#![allow(missing_docs)]
+
+use polkadot_cli::{
+	service::{
+		AuxStore, Error, ExtendedOverseerGenArgs, Overseer, OverseerConnector, OverseerGen,
+		OverseerGenArgs, OverseerHandle,
+	},
+	validator_overseer_builder, Cli,
+};
+use polkadot_node_network_protocol::{
+	v3::{ApprovalDistributionMessage, ValidationProtocol},
+	ValidationProtocols, VersionedValidationProtocol,
+};
+use polkadot_node_primitives::ValidationResult;
+use polkadot_node_subsystem::SpawnGlue;
+use polkadot_node_subsystem_types::{ChainApiBackend, RuntimeApiSubsystemClient};
+use polkadot_node_subsystem_util::signing_key_and_index;
+use polkadot_primitives::{Hash, SessionIndex, ValidatorIndex};
+use sp_core::traits::SpawnNamed;
+use sp_keystore::KeystorePtr;
+
+use rand::distributions::{Bernoulli, Distribution};
+
+// Filter wrapping related types.
+use crate::{interceptor::*, shared::MALUS, variants::create_fake_candidate_commitments};
+
+use std::{
+	collections::HashMap,
+	sync::{Arc, Mutex},
+};
+
+/// Tells whether a validator index belongs to this node at a given relay chain block.
+trait OwnValidator: Send + Sync {
+	fn is_own(&self, block_hash: &Hash, validator: ValidatorIndex) -> bool;
+}
+
+/// Resolves the node's own validator index from the session of the block and the keystore.
+struct KeystoreOwnValidator<RuntimeClient> {
+	runtime_client: Arc<RuntimeClient>,
+	keystore: KeystorePtr,
+	/// The node's validator index per session, `None` if it is not a validator in it.
+	sessions: Mutex<HashMap<SessionIndex, Option<ValidatorIndex>>>,
+}
+
+impl<RuntimeClient> KeystoreOwnValidator<RuntimeClient>
+where
+	RuntimeClient: RuntimeApiSubsystemClient + ChainApiBackend,
+{
+	async fn own_validator_index(
+		&self,
+		block_hash: Hash,
+	) -> Result<Option<ValidatorIndex>, String> {
+		// The session of a block is the one announced for the child of its parent.
+		let parent_hash = ChainApiBackend::header(&*self.runtime_client, block_hash)
+			.await
+			.map_err(|e| e.to_string())?
+			.ok_or_else(|| "unknown block".to_string())?
+			.parent_hash;
+		let session = self
+			.runtime_client
+			.session_index_for_child(parent_hash)
+			.await
+			.map_err(|e| e.to_string())?;
+
+		if let Some(index) = self.sessions.lock().expect("poisoned lock").get(&session) {
+			return Ok(*index)
+		}
+
+		let session_info = self
+			.runtime_client
+			.session_info(block_hash, session)
+			.await
+			.map_err(|e| e.to_string())?
+			.ok_or_else(|| "unknown session".to_string())?;
+		let index = signing_key_and_index(session_info.validators.iter(), &self.keystore)
+			.map(|(_, index)| index);
+		self.sessions.lock().expect("poisoned lock").insert(session, index);
+		Ok(index)
+	}
+}
+
+impl<RuntimeClient> OwnValidator for KeystoreOwnValidator<RuntimeClient>
+where
+	RuntimeClient: RuntimeApiSubsystemClient + ChainApiBackend,
+{
+	fn is_own(&self, block_hash: &Hash, validator: ValidatorIndex) -> bool {
+		match futures::executor::block_on(self.own_validator_index(*block_hash)) {
+			Ok(own) => own == Some(validator),
+			Err(err) => {
+				gum::debug!(
+					target: MALUS,
+					?block_hash,
+					?err,
+					"Failed to resolve own validator index.",
+				);
+				false
+			},
+		}
+	}
+}
+
+/// Drops the node's own approvals and duplicates assignments sent through the network bridge.
+#[derive(Clone)]
+struct ApprovalGossipFaults {
+	/// Percentage of relay chain blocks for which approvals are withheld.
+	no_show_percentage: u8,
+	/// How many times each assignment message is sent.
+	assignment_spam_factor: u32,
+	/// Recognizes the approvals signed by this node.
+	own_validator: Arc<dyn OwnValidator>,
+}
+
+impl ApprovalGossipFaults {
+	/// Whether the node's approvals for `block_hash` are withheld.
+	///
+	/// The decision is derived from the block rather than drawn at random, so that an approval is
+	/// withheld every time it would be sent, including re-sends to new peers.
+	fn is_no_show(&self, block_hash: &Hash) -> bool {
+		let draw = u64::from_le_bytes(sp_core::blake2_64(block_hash.as_bytes())) % 100;
+		draw < u64::from(self.no_show_percentage)
+	}
+
+	/// Apply the faults to a single outgoing validation protocol message, returning the messages
+	/// to send instead.
+	fn tamper(&self, msg: VersionedValidationProtocol) -> Vec<VersionedValidationProtocol> {
+		match msg {
+			ValidationProtocols::V3(ValidationProtocol::ApprovalDistribution(
+				ApprovalDistributionMessage::Approvals(approvals),
+			)) => {
+				let approvals: Vec<_> = approvals
+					.into_iter()
+					.filter(|vote| {
+						let no_show = self.is_no_show(&vote.block_hash) &&
+							self.own_validator.is_own(&vote.block_hash, vote.validator);
+						if no_show {
+							gum::info!(
+								target: MALUS,
+								block_hash = ?vote.block_hash,
+								validator = ?vote.validator,
+								"😈 Withholding approval.",
+							);
+						}
+						!no_show
+					})
+					.collect();
+
+				if approvals.is_empty() {
+					Vec::new()
+				} else {
+					vec![ValidationProtocols::V3(ValidationProtocol::ApprovalDistribution(
+						ApprovalDistributionMessage::Approvals(approvals),
+					))]
+				}
+			},
+			msg @ ValidationProtocols::V3(ValidationProtocol::ApprovalDistribution(
+				ApprovalDistributionMessage::Assignments(_),
+			)) if self.assignment_spam_factor > 1 => {
+				gum::info!(
+					target: MALUS,
+					"😈 Duplicating assignments message {:?} times.",
+					self.assignment_spam_factor,
+				);
+				vec![msg; self.assignment_spam_factor as usize]
+			},
+			msg => vec![msg],
+		}
+	}
+}
+
+impl<Sender> MessageInterceptor<Sender> for ApprovalGossipFaults
+where
+	Sender: overseer::NetworkBridgeTxSenderTrait + Clone + Send + 'static,
+{
+	type Message = NetworkBridgeTxMessage;
+
+	/// Intercept outgoing validation protocol messages and tamper with approval distribution ones.
+	fn intercept_incoming(
+		&self,
+		_subsystem_sender: &mut Sender,
+		msg: FromOrchestra<Self::Message>,
+	) -> Option<FromOrchestra<Self::Message>> {
+		let messages = match msg {
+			FromOrchestra::Communication {
+				msg: NetworkBridgeTxMessage::SendValidationMessage(peers, msg),
+			} => self.tamper(msg).into_iter().map(|msg| (peers.clone(), msg)).collect::<Vec<_>>(),
+			FromOrchestra::Communication {
+				msg: NetworkBridgeTxMessage::SendValidationMessages(msgs),
+			} => msgs
+				.into_iter()
+				.flat_map(|(peers, msg)| {
+					self.tamper(msg).into_iter().map(move |msg| (peers.clone(), msg))
+				})
+				.collect(),
+			FromOrchestra::Communication { msg } =>
+				return Some(FromOrchestra::Communication { msg }),
+			FromOrchestra::Signal(signal) => return Some(FromOrchestra::Signal(signal)),
+		};
+
+		if messages.is_empty() {
+			None
+		} else {
+			Some(FromOrchestra::Communication {
+				msg: NetworkBridgeTxMessage::SendValidationMessages(messages),
+			})
+		}
+	}
+}
+
+/// Answers approval checks as valid without validating the candidate.
+#[derive(Clone)]
+struct ApproveWithoutChecking {
+	distribution: Bernoulli,
+}
+
+impl<Sender> MessageInterceptor<Sender> for ApproveWithoutChecking
+where
+	Sender: overseer::CandidateValidationSenderTrait + Clone + Send + 'static,
+{
+	type Message = CandidateValidationMessage;
+
+	/// Intercept validation requests issued for approval checks.
+	fn intercept_incoming(
+		&self,
+		_subsystem_sender: &mut Sender,
+		msg: FromOrchestra<Self::Message>,
+	) -> Option<FromOrchestra<Self::Message>> {
+		match msg {
+			FromOrchestra::Communication {
+				msg:
+					CandidateValidationMessage::ValidateFromExhaustive {
+						validation_data,
+						candidate_receipt,
+						exec_kind: PvfExecKind::Approval,
+						response_sender,
+						..
+					},
+			} if self.distribution.sample(&mut rand::thread_rng()) => {
+				gum::info!(
+					target: MALUS,
+					candidate_hash = ?candidate_receipt.hash(),
+					para_id = ?candidate_receipt.descriptor.para_id(),
+					"😈 Approving candidate without checking it.",
+				);
+
+				let commitments = create_fake_candidate_commitments(&validation_data);
+				let _ =
+					response_sender.send(Ok(ValidationResult::Valid(commitments, validation_data)));
+				None
+			},
+			msg => Some(msg),
+		}
+	}
+}
+
+#[derive(Debug, clap::Parser)]
+#[clap(rename_all = "kebab-case")]
+#[allow(missing_docs)]
+pub struct ApprovalVotingFaultsOptions {
+	/// Percentage of relay chain blocks for which approvals are withheld, turning the node into a
+	/// no-show. Must be in the range [0..=100].
+	#[clap(long, ignore_case = true, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=100))]
+	pub no_show_percentage: u8,
+
+	/// Percentage of approval checks answered as valid without running the PVF. Must be in the
+	/// range [0..=100].
+	#[clap(long, ignore_case = true, default_value_t = 0, value_parser = clap::value_parser!(u8).range(0..=100))]
+	pub approve_unchecked_percentage: u8,
+
+	/// How many times each assignment message is sent. `1` disables the spam.
+	#[clap(long, ignore_case = true, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..=10000))]
+	pub assignment_spam_factor: u32,
+
+	#[clap(flatten)]
+	pub cli: Cli,
+}
+
+/// ApprovalVotingFaults implementation wrapper which implements `OverseerGen` glue.
+pub(crate) struct ApprovalVotingFaults {
+	/// Percentage of relay chain blocks for which approvals are withheld.
+	pub no_show_percentage: u8,
+	/// Percentage of approval checks answered as valid without running the PVF.
+	pub approve_unchecked_percentage: u8,
+	/// How many times each assignment message is sent.
+	pub assignment_spam_factor: u32,
+}
+
+impl OverseerGen for ApprovalVotingFaults {
+	fn generate<Spawner, RuntimeClient>(
+		&self,
+		connector: OverseerConnector,
+		args: OverseerGenArgs<'_, Spawner, RuntimeClient>,
+		ext_args: Option<ExtendedOverseerGenArgs>,
+	) -> Result<(Overseer<SpawnGlue<Spawner>, Arc<RuntimeClient>>, OverseerHandle), Error>
+	where
+		RuntimeClient: RuntimeApiSubsystemClient + ChainApiBackend + AuxStore + 'static,
+		Spawner: 'static + SpawnNamed + Clone + Unpin,
+	{
+		gum::info!(
+			target: MALUS,
+			no_show_percentage = self.no_show_percentage,
+			approve_unchecked_percentage = self.approve_unchecked_percentage,
+			assignment_spam_factor = self.assignment_spam_factor,
+			"😈 Started Malus node misbehaving in approval voting.",
+		);
+
+		let ext_args =
+			ext_args.expect("Extended arguments required to build validator overseer are provided");
+		let gossip_faults = ApprovalGossipFaults {
+			no_show_percentage: self.no_show_percentage,
+			assignment_spam_factor: self.assignment_spam_factor,
+			own_validator: Arc::new(KeystoreOwnValidator {
+				runtime_client: args.runtime_client.clone(),
+				keystore: ext_args.keystore.clone(),
+				sessions: Mutex::new(HashMap::new()),
+			}),
+		};
+		let approve_without_checking = ApproveWithoutChecking {
+			distribution: Bernoulli::new(f64::from(self.approve_unchecked_percentage) / 100.0)
+				.expect("Invalid probability! Percentage must be in range [0..=100]."),
+		};
+
+		validator_overseer_builder(args, ext_args)?
+			.replace_network_bridge_tx(move |cb| InterceptedSubsystem::new(cb, gossip_faults))
+			.replace_candidate_validation(move |cb| {
+				InterceptedSubsystem::new(cb, approve_without_checking)
+			})
+			.build_with_connector(connector)
+			.map_err(|e| e.into())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use polkadot_node_primitives::approval::v2::{CandidateBitfield, IndirectSignedApprovalVoteV2};
+
+	const OWN: ValidatorIndex = ValidatorIndex(7);
+
+	struct FixedOwnValidator;
+
+	impl OwnValidator for FixedOwnValidator {
+		fn is_own(&self, _block_hash: &Hash, validator: ValidatorIndex) -> bool {
+			validator == OWN
+		}
+	}
+
+	fn faults(no_show_percentage: u8, assignment_spam_factor: u32) -> ApprovalGossipFaults {
+		ApprovalGossipFaults {
+			no_show_percentage,
+			assignment_spam_factor,
+			own_validator: Arc::new(FixedOwnValidator),
+		}
+	}
+
+	fn approval(block_hash: Hash, validator: ValidatorIndex) -> IndirectSignedApprovalVoteV2 {
+		IndirectSignedApprovalVoteV2 {
+			block_hash,
+			candidate_indices: CandidateBitfield::from(0),
+			validator,
+			signature: sp_core::crypto::UncheckedFrom::unchecked_from([1u8; 64]),
+		}
+	}
+
+	fn approvals_msg(approvals: Vec<IndirectSignedApprovalVoteV2>) -> VersionedValidationProtocol {
+		ValidationProtocols::V3(ValidationProtocol::ApprovalDistribution(
+			ApprovalDistributionMessage::Approvals(approvals),
+		))
+	}
+
+	fn sent_approvals(msgs: Vec<VersionedValidationProtocol>) -> Vec<ValidatorIndex> {
+		msgs.into_iter()
+			.flat_map(|msg| match msg {
+				ValidationProtocols::V3(ValidationProtocol::ApprovalDistribution(
+					ApprovalDistributionMessage::Approvals(approvals),
+				)) => approvals.into_iter().map(|vote| vote.validator).collect::<Vec<_>>(),
+				_ => panic!("unexpected message"),
+			})
+			.collect()
+	}
+
+	#[test]
+	fn withholds_only_own_approvals() {
+		let faults = faults(100, 1);
+		let block_hash = Hash::repeat_byte(1);
+
+		let sent = faults.tamper(approvals_msg(vec![
+			approval(block_hash, ValidatorIndex(1)),
+			approval(block_hash, OWN),
+			approval(block_hash, ValidatorIndex(2)),
+		]));
+
+		assert_eq!(sent_approvals(sent), vec![ValidatorIndex(1), ValidatorIndex(2)]);
+	}
+
+	#[test]
+	fn drops_message_with_only_withheld_approvals() {
+		let faults = faults(100, 1);
+
+		assert!(faults
+			.tamper(approvals_msg(vec![approval(Hash::repeat_byte(1), OWN)]))
+			.is_empty());
+	}
+
+	#[test]
+	fn no_show_is_decided_per_block() {
+		let faults = faults(50, 1);
+		let blocks = (0..=255u8).map(Hash::repeat_byte).collect::<Vec<_>>();
+
+		let withheld = blocks.iter().filter(|block_hash| faults.is_no_show(block_hash)).count();
+		assert!(withheld > 0 && withheld < blocks.len());
+
+		for block_hash in blocks {
+			let sent =
+				sent_approvals(faults.tamper(approvals_msg(vec![approval(block_hash, OWN)])));
+			assert_eq!(sent.is_empty(), faults.is_no_show(&block_hash));
+		}
+	}
+
+	#[test]
+	fn relays_own_approvals_without_no_shows() {
+		let faults = faults(0, 1);
+		let sent = faults.tamper(approvals_msg(vec![approval(Hash::repeat_byte(1), OWN)]));
+
+		assert_eq!(sent_approvals(sent), vec![OWN]);
+	}
+
+	#[test]
+	fn duplicates_assignments() {
+		let msg = ValidationProtocols::V3(ValidationProtocol::ApprovalDistribution(
+			ApprovalDistributionMessage::Assignments(Vec::new()),
+		));
+
+		assert_eq!(faults(0, 3).tamper(msg.clone()), vec![msg.clone(); 3]);
+		assert_eq!(faults(0, 1).tamper(msg.clone()), vec![msg]);
+	}
+}
@@ -0,0 +1,238 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! A malicious node that misbehaves when serving availability data.
+//!
+//! The node stores chunks honestly, but when they are read back from the availability store,
+//! a percentage of erasure chunks and full available data is either withheld or corrupted.
+//! Withholding makes peers fall back to other validators, corruption makes them receive chunks
+//! which fail the erasure root check.
+//!
+//! Note that the node's own availability recovery reads from the same store and is affected too.
+//!
+//! Attention: For usage with `zombienet` only!
+
+#![allow(missing_docs)]
+
+use futures::channel::oneshot;
+use polkadot_cli::{
+	service::{
+		AuxStore, Error, ExtendedOverseerGenArgs, Overseer, OverseerConnector, OverseerGen,
+		OverseerGenArgs, OverseerHandle,
+	},
+	validator_overseer_builder, Cli,
+};
+use polkadot_node_primitives::{AvailableData, BlockData, ErasureChunk, PoV};
+use polkadot_node_subsystem::SpawnGlue;
+use polkadot_node_subsystem_types::{ChainApiBackend, RuntimeApiSubsystemClient};
+use sp_core::traits::SpawnNamed;
+
+use rand::distributions::{Bernoulli, Distribution};
+
+// Filter wrapping related types.
+use crate::{interceptor::*, shared::MALUS};
+
+use std::sync::Arc;
+
+/// The fault injected when serving availability data.
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq)]
+#[value(rename_all = "kebab-case")]
+pub enum AvailabilityFault {
+	/// Pretend the data is not available.
+	Withhold,
+	/// Serve the data with its content altered.
+	Corrupt,
+}
+
+/// Wraps around the availability store and tampers with the data it serves.
+#[derive(Clone)]
+struct TamperAvailabilityData<Spawner> {
+	spawner: Spawner,
+	fault: AvailabilityFault,
+	distribution: Bernoulli,
+}
+
+fn corrupt_chunk(mut chunk: ErasureChunk) -> ErasureChunk {
+	match chunk.chunk.first_mut() {
+		Some(byte) => *byte = byte.wrapping_add(1),
+		None => chunk.chunk.push(0),
+	}
+	chunk
+}
+
+fn corrupt_available_data(data: AvailableData) -> AvailableData {
+	let mut block_data = data.pov.block_data.0.clone();
+	match block_data.first_mut() {
+		Some(byte) => *byte = byte.wrapping_add(1),
+		None => block_data.push(0),
+	}
+	AvailableData {
+		pov: Arc::new(PoV { block_data: BlockData(block_data) }),
+		validation_data: data.validation_data,
+	}
+}
+
+impl<Spawner> TamperAvailabilityData<Spawner>
+where
+	Spawner: overseer::gen::Spawner + Clone + 'static,
+{
+	/// Let the availability store answer the query on a new channel, then forward the corrupted
+	/// response to the original requester.
+	fn corrupt_response<T: Send + 'static>(
+		&self,
+		response_sender: oneshot::Sender<Option<T>>,
+		corrupt: fn(T) -> T,
+	) -> oneshot::Sender<Option<T>> {
+		let (tx, rx) = oneshot::channel();
+		self.spawner.spawn(
+			"malus-corrupt-availability-data",
+			Some("malus"),
+			Box::pin(async move {
+				if let Ok(response) = rx.await {
+					let _ = response_sender.send(response.map(corrupt));
+				}
+			}),
+		);
+		tx
+	}
+}
+
+impl<Sender, Spawner> MessageInterceptor<Sender> for TamperAvailabilityData<Spawner>
+where
+	Sender: overseer::AvailabilityStoreSenderTrait + Clone + Send + 'static,
+	Spawner: overseer::gen::Spawner + Clone + 'static,
+{
+	type Message = AvailabilityStoreMessage;
+
+	/// Intercept chunk and available data queries.
+	fn intercept_incoming(
+		&self,
+		_subsystem_sender: &mut Sender,
+		msg: FromOrchestra<Self::Message>,
+	) -> Option<FromOrchestra<Self::Message>> {
+		match msg {
+			FromOrchestra::Communication {
+				msg: AvailabilityStoreMessage::QueryChunk(candidate_hash, validator_index, tx),
+			} if self.distribution.sample(&mut rand::thread_rng()) => {
+				gum::info!(
+					target: MALUS,
+					?candidate_hash,
+					?validator_index,
+					fault = ?self.fault,
+					"😈 Tampering with erasure chunk.",
+				);
+
+				match self.fault {
+					AvailabilityFault::Withhold => {
+						let _ = tx.send(None);
+						None
+					},
+					AvailabilityFault::Corrupt => Some(FromOrchestra::Communication {
+						msg: AvailabilityStoreMessage::QueryChunk(
+							candidate_hash,
+							validator_index,
+							self.corrupt_response(tx, corrupt_chunk),
+						),
+					}),
+				}
+			},
+			FromOrchestra::Communication {
+				msg: AvailabilityStoreMessage::QueryAvailableData(candidate_hash, tx),
+			} if self.distribution.sample(&mut rand::thread_rng()) => {
+				gum::info!(
+					target: MALUS,
+					?candidate_hash,
+					fault = ?self.fault,
+					"😈 Tampering with available data.",
+				);
+
+				match self.fault {
+					AvailabilityFault::Withhold => {
+						let _ = tx.send(None);
+						None
+					},
+					AvailabilityFault::Corrupt => Some(FromOrchestra::Communication {
+						msg: AvailabilityStoreMessage::QueryAvailableData(
+							candidate_hash,
+							self.corrupt_response(tx, corrupt_available_data),
+						),
+					}),
+				}
+			},
+			msg => Some(msg),
+		}
+	}
+}
+
+#[derive(Debug, clap::Parser)]
+#[clap(rename_all = "kebab-case")]
+#[allow(missing_docs)]
+pub struct AvailabilityFaultsOptions {
+	/// The fault to inject into the served availability data.
+	#[clap(long, value_enum, ignore_case = true, default_value_t = AvailabilityFault::Withhold)]
+	pub fault: AvailabilityFault,
+
+	/// Determines the percentage of queries that are tampered with. Must be in the range
+	/// [0..=100].
+	#[clap(short, long, ignore_case = true, default_value_t = 100, value_parser = clap::value_parser!(u8).range(0..=100))]
+	pub percentage: u8,
+
+	#[clap(flatten)]
+	pub cli: Cli,
+}
+
+/// AvailabilityFaults implementation wrapper which implements `OverseerGen` glue.
+pub(crate) struct AvailabilityFaults {
+	/// The fault to inject into the served availability data.
+	pub fault: AvailabilityFault,
+	/// The probability of behaving maliciously.
+	pub percentage: u8,
+}
+
+impl OverseerGen for AvailabilityFaults {
+	fn generate<Spawner, RuntimeClient>(
+		&self,
+		connector: OverseerConnector,
+		args: OverseerGenArgs<'_, Spawner, RuntimeClient>,
+		ext_args: Option<ExtendedOverseerGenArgs>,
+	) -> Result<(Overseer<SpawnGlue<Spawner>, Arc<RuntimeClient>>, OverseerHandle), Error>
+	where
+		RuntimeClient: RuntimeApiSubsystemClient + ChainApiBackend + AuxStore + 'static,
+		Spawner: 'static + SpawnNamed + Clone + Unpin,
+	{
+		gum::info!(
+			target: MALUS,
+			fault = ?self.fault,
+			"😈 Started Malus node with a {:?} percent chance of tampering with served availability data.",
+			&self.percentage,
+		);
+
+		let tamper = TamperAvailabilityData {
+			spawner: SpawnGlue(args.spawner.clone()),
+			fault: self.fault,
+			distribution: Bernoulli::new(f64::from(self.percentage) / 100.0)
+				.expect("Invalid probability! Percentage must be in range [0..=100]."),
+		};
+
+		validator_overseer_builder(
+			args,
+			ext_args.expect("Extended arguments required to build validator overseer are provided"),
+		)?
+		.replace_availability_store(move |cb| InterceptedSubsystem::new(cb, tamper))
+		.build_with_connector(connector)
+		.map_err(|e| e.into())
+	}
+}
@@ -16,6 +16,8 @@
 
 //! Collection of behavior variants.
 
+mod approval_voting_faults;
+mod availability_faults;
 mod back_garbage_candidate;
 mod common;
 mod dispute_finalized_candidates;
@@ -25,6 +27,8 @@ mod suggest_garbage_candidate;
 mod support_disabled;
 
 pub(crate) use self::{
+	approval_voting_faults::{ApprovalVotingFaults, ApprovalVotingFaultsOptions},
+	availability_faults::{AvailabilityFaults, AvailabilityFaultsOptions},
 	back_garbage_candidate::{BackGarbageCandidateOptions, BackGarbageCandidates},
 	dispute_finalized_candidates::{DisputeFinalizedCandidates, DisputeFinalizedCandidatesOptions},
 	dispute_valid_candidates::{DisputeAncestorOptions, DisputeValidCandidates},
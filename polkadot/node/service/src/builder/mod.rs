@@ -35,7 +35,11 @@ use gum::info;
 use mmr_gadget::MmrGadget;
 use polkadot_availability_recovery::FETCH_CHUNKS_THRESHOLD;
 use polkadot_node_core_approval_voting::Config as ApprovalVotingConfig;
-use polkadot_node_core_av_store::Config as AvailabilityConfig;
+use polkadot_node_core_av_store::{
+	archive::{ArchiveLocation, PovArchive},
+	rpc::{AvailabilityArchive, AvailabilityArchiveApiServer},
+	Config as AvailabilityConfig,
+};
 use polkadot_node_core_candidate_validation::Config as CandidateValidationConfig;
 use polkadot_node_core_chain_selection::{
 	self as chain_selection_subsystem, Config as ChainSelectionConfig,
@@ -85,6 +89,9 @@ pub struct NewFullParams<OverseerGenerator: OverseerGen> {
 	pub prepare_workers_hard_max_num: Option<usize>,
 	/// How long finalized data should be kept in the availability store (in hours)
 	pub keep_finalized_for: Option<u32>,
+	/// An optional location to archive finalized available data to before it is pruned from the
+	/// availability store. Only supported on validators.
+	pub pov_archive: Option<ArchiveLocation>,
 	pub overseer_gen: OverseerGenerator,
 	pub overseer_message_channel_capacity_override: Option<usize>,
 	#[allow(dead_code)]
@@ -202,6 +209,7 @@ where
 					prepare_workers_soft_max_num,
 					prepare_workers_hard_max_num,
 					keep_finalized_for,
+					pov_archive,
 					enable_approval_voting_parallel,
 				},
 			overseer_connector,
@@ -247,6 +255,24 @@ where
 			Some(backoff)
 		};
 		let shared_voter_state = rpc_setup;
+		// Only validators run the availability store subsystem.
+		if pov_archive.is_some() && !role.is_authority() {
+			return Err(Error::PovArchiveRequiresValidator)
+		}
+		let pov_archive = pov_archive.map(PovArchive::open).transpose()?;
+		let rpc_extensions_builder = {
+			let pov_archive = pov_archive.clone();
+
+			move |subscription_executor: polkadot_rpc::SubscriptionTaskExecutor|
+			      -> Result<polkadot_rpc::RpcExtension, sc_service::Error> {
+				let mut io = rpc_extensions_builder(subscription_executor)?;
+				if let Some(archive) = &pov_archive {
+					io.merge(AvailabilityArchive::new(archive.clone()).into_rpc())
+						.map_err(|e| sc_service::Error::Other(e.to_string()))?;
+				}
+				Ok(io)
+			}
+		};
 		let auth_disc_publish_non_global_ips = config.network.allow_non_globals_in_dht;
 		let auth_disc_public_addresses = config.network.public_addresses.clone();
 
@@ -364,6 +390,13 @@ where
 			import_setup.1.shared_authority_set().clone(),
 			grandpa_hard_forks,
 		));
+		let header_verifier = Arc::new((
+			sc_consensus_babe::BabeHeaderVerifier::new(client.clone(), &import_setup.2),
+			sc_consensus_grandpa::GrandpaHeaderVerifier::new(
+				client.clone(),
+				import_setup.1.shared_authority_set().clone(),
+			),
+		));
 
 		let ext_overseer_args = if is_parachain_node.is_running_alongside_parachain_node() {
 			None
@@ -435,6 +468,7 @@ where
 				parachains_db,
 				candidate_validation_config,
 				availability_config,
+				pov_archive,
 				pov_req_receiver,
 				chunk_req_v1_receiver,
 				chunk_req_v2_receiver,
@@ -458,6 +492,7 @@ where
 				import_queue,
 				block_announce_validator_builder: None,
 				warp_sync_config: Some(WarpSyncConfig::WithProvider(warp_sync)),
+				header_verifier: Some(header_verifier),
 				block_relay: None,
 				metrics,
 			})?;
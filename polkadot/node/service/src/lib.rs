@@ -22,7 +22,7 @@ pub mod benchmarking;
 pub mod chain_spec;
 mod fake_runtime_api;
 mod grandpa_support;
-mod parachains_db;
+pub mod parachains_db;
 mod relay_chain_selection;
 
 #[cfg(feature = "full-node")]
@@ -59,6 +59,7 @@ use polkadot_overseer::SpawnGlue;
 
 #[cfg(feature = "full-node")]
 pub use {
+	polkadot_node_core_av_store::archive::{s3::S3Config, ArchiveLocation},
 	polkadot_overseer::{Handle, Overseer, OverseerConnector, OverseerHandle},
 	polkadot_primitives::runtime_api::ParachainHost,
 	relay_chain_selection::SelectRelayChain,
@@ -216,6 +217,10 @@ pub enum Error {
 	#[error("Creating a custom database is required for validators")]
 	DatabasePathRequired,
 
+	#[cfg(feature = "full-node")]
+	#[error("Archiving PoVs is only supported on validators")]
+	PovArchiveRequiresValidator,
+
 	#[cfg(feature = "full-node")]
 	#[error("Expected at least one of polkadot, kusama, westend or rococo runtime feature")]
 	NoRuntime,
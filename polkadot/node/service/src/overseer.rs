@@ -21,7 +21,7 @@ use sp_core::traits::SpawnNamed;
 
 use polkadot_availability_distribution::IncomingRequestReceivers;
 use polkadot_node_core_approval_voting::{Config as ApprovalVotingConfig, RealAssignmentCriteria};
-use polkadot_node_core_av_store::Config as AvailabilityConfig;
+use polkadot_node_core_av_store::{archive::PovArchive, Config as AvailabilityConfig};
 use polkadot_node_core_candidate_validation::Config as CandidateValidationConfig;
 use polkadot_node_core_chain_selection::Config as ChainSelectionConfig;
 use polkadot_node_core_dispute_coordinator::Config as DisputeCoordinatorConfig;
@@ -119,6 +119,8 @@ pub struct ExtendedOverseerGenArgs {
 	pub candidate_validation_config: Option<CandidateValidationConfig>,
 	/// Configuration for the availability store subsystem.
 	pub availability_config: AvailabilityConfig,
+	/// Archive to write finalized available data to before it is pruned, if any.
+	pub pov_archive: Option<PovArchive>,
 	/// POV request receiver.
 	pub pov_req_receiver: IncomingRequestReceiver<request_v1::PoVFetchingRequest>,
 	/// Erasure chunk request v1 receiver.
@@ -171,6 +173,7 @@ pub fn validator_overseer_builder<Spawner, RuntimeClient>(
 		parachains_db,
 		candidate_validation_config,
 		availability_config,
+		pov_archive,
 		pov_req_receiver,
 		chunk_req_v1_receiver,
 		chunk_req_v2_receiver,
@@ -269,12 +272,18 @@ where
 			&req_protocol_names,
 			Metrics::register(registry)?,
 		))
-		.availability_store(AvailabilityStoreSubsystem::new(
-			parachains_db.clone(),
-			availability_config,
-			Box::new(sync_service.clone()),
-			Metrics::register(registry)?,
-		))
+		.availability_store({
+			let subsystem = AvailabilityStoreSubsystem::new(
+				parachains_db.clone(),
+				availability_config,
+				Box::new(sync_service.clone()),
+				Metrics::register(registry)?,
+			);
+			match pov_archive {
+				Some(archive) => subsystem.with_archive(archive),
+				None => subsystem,
+			}
+		})
 		.bitfield_distribution(BitfieldDistributionSubsystem::new(Metrics::register(registry)?))
 		.bitfield_signing(BitfieldSigningSubsystem::new(
 			keystore.clone(),
@@ -393,6 +402,7 @@ pub fn validator_with_parallel_overseer_builder<Spawner, RuntimeClient>(
 		parachains_db,
 		candidate_validation_config,
 		availability_config,
+		pov_archive,
 		pov_req_receiver,
 		chunk_req_v1_receiver,
 		chunk_req_v2_receiver,
@@ -490,12 +500,18 @@ where
 			&req_protocol_names,
 			Metrics::register(registry)?,
 		))
-		.availability_store(AvailabilityStoreSubsystem::new(
-			parachains_db.clone(),
-			availability_config,
-			Box::new(sync_service.clone()),
-			Metrics::register(registry)?,
-		))
+		.availability_store({
+			let subsystem = AvailabilityStoreSubsystem::new(
+				parachains_db.clone(),
+				availability_config,
+				Box::new(sync_service.clone()),
+				Metrics::register(registry)?,
+			);
+			match pov_archive {
+				Some(archive) => subsystem.with_archive(archive),
+				None => subsystem,
+			}
+		})
 		.bitfield_distribution(BitfieldDistributionSubsystem::new(Metrics::register(registry)?))
 		.bitfield_signing(BitfieldSigningSubsystem::new(
 			keystore.clone(),
@@ -59,6 +59,10 @@ pub(crate) mod columns {
 	}
 }
 
+/// The number of columns of the parachains DB.
+#[cfg(any(test, feature = "full-node"))]
+pub const NUM_COLUMNS: u32 = columns::v4::NUM_COLUMNS;
+
 /// Columns used by different subsystems.
 #[cfg(any(test, feature = "full-node"))]
 #[derive(Debug, Clone)]
@@ -20,7 +20,9 @@
 use clap::Parser;
 use color_eyre::eyre;
 use colored::Colorize;
-use polkadot_subsystem_bench::{approval, availability, configuration, statement};
+use polkadot_subsystem_bench::{
+	approval, availability, configuration, disputes, prospective_parachains, statement,
+};
 use pyroscope::PyroscopeAgent;
 use pyroscope_pprofrs::{pprof_backend, PprofConfig};
 use serde::{Deserialize, Serialize};
@@ -42,6 +44,10 @@ pub enum TestObjective {
 	ApprovalVoting(approval::ApprovalsOptions),
 	// Benchmark the statement-distribution subsystem
 	StatementDistribution,
+	/// Benchmark the dispute-coordinator subsystem.
+	DisputeCoordinator(disputes::DisputesOptions),
+	/// Benchmark the prospective-parachains subsystem.
+	ProspectiveParachains(prospective_parachains::ProspectiveParachainsOptions),
 }
 
 impl std::fmt::Display for TestObjective {
@@ -54,6 +60,8 @@ impl std::fmt::Display for TestObjective {
 				Self::DataAvailabilityWrite => "DataAvailabilityWrite",
 				Self::ApprovalVoting(_) => "ApprovalVoting",
 				Self::StatementDistribution => "StatementDistribution",
+				Self::DisputeCoordinator(_) => "DisputeCoordinator",
+				Self::ProspectiveParachains(_) => "ProspectiveParachains",
 			}
 		)
 	}
@@ -169,6 +177,19 @@ impl BenchCli {
 					env.runtime()
 						.block_on(statement::benchmark_statement_distribution(&mut env, &state))
 				},
+				TestObjective::DisputeCoordinator(ref options) => {
+					let state = disputes::TestState::new(&test_config, options);
+					let mut env = disputes::prepare_test(&state, true);
+					env.runtime()
+						.block_on(disputes::benchmark_dispute_coordinator(&mut env, &state))
+				},
+				TestObjective::ProspectiveParachains(ref options) => {
+					let state = prospective_parachains::TestState::new(&test_config, options);
+					let mut env = prospective_parachains::prepare_test(&state, true);
+					env.runtime().block_on(
+						prospective_parachains::benchmark_prospective_parachains(&mut env, &state),
+					)
+				},
 			};
 			println!("\n{}\n{}", benchmark_name.purple(), usage);
 		}
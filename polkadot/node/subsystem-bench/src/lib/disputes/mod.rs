@@ -0,0 +1,214 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Dispute coordinator benchmark.
+//!
+//! Raises disputes for candidates included in every relay chain block, lets the node under test
+//! participate in them and then concludes them with the votes of the remaining validators.
+
+use crate::{
+	dummy_builder,
+	environment::{TestEnvironment, TestEnvironmentDependencies},
+	mock::{
+		approval_voting_parallel::MockApprovalVotingParallel,
+		availability_recovery::MockAvailabilityRecovery,
+		candidate_validation::MockCandidateValidation,
+		chain_api::{ChainApiState, MockChainApi},
+		runtime_api::{MockRuntimeApi, MockRuntimeApiCoreState},
+		AlwaysSupportsParachains,
+	},
+	network::new_network,
+	usage::BenchmarkUsage,
+	NODE_UNDER_TEST,
+};
+use colored::Colorize;
+use polkadot_node_core_dispute_coordinator::{Config, DisputeCoordinatorSubsystem};
+use polkadot_node_metrics::metrics::Metrics;
+use polkadot_node_subsystem::messages::{AllMessages, DisputeCoordinatorMessage};
+use polkadot_node_subsystem_util::database::Database;
+use polkadot_overseer::{
+	Handle as OverseerHandle, Overseer, OverseerConnector, OverseerMetrics, SpawnGlue,
+};
+use polkadot_primitives::{vstaging::CandidateReceiptV2 as CandidateReceipt, ValidatorId};
+use sc_keystore::LocalKeystore;
+use sc_service::SpawnTaskHandle;
+use serde::{Deserialize, Serialize};
+use sp_keystore::Keystore;
+use sp_runtime::RuntimeAppPublic;
+use std::{ops::Sub, sync::Arc, time::Instant};
+pub use test_state::TestState;
+
+mod test_state;
+
+const LOG_TARGET: &str = "subsystem-bench::disputes";
+
+const COL_DISPUTE_DATA: u32 = 0;
+const NUM_COLUMNS: u32 = 1;
+
+/// Parameters specific to the dispute coordinator benchmark
+#[derive(Debug, Clone, Serialize, Deserialize, clap::Parser)]
+#[clap(rename_all = "kebab-case")]
+#[allow(missing_docs)]
+pub struct DisputesOptions {
+	#[clap(short, long, default_value_t = 10)]
+	/// The number of candidates disputed in each relay chain block. Can't exceed `n_cores`.
+	pub n_disputes: usize,
+}
+
+fn build_overseer(
+	state: &TestState,
+	dependencies: &TestEnvironmentDependencies,
+) -> (Overseer<SpawnGlue<SpawnTaskHandle>, AlwaysSupportsParachains>, OverseerHandle) {
+	let overseer_connector = OverseerConnector::with_event_capacity(64000);
+	let overseer_metrics = OverseerMetrics::try_register(&dependencies.registry).unwrap();
+	let spawn_task_handle = dependencies.task_manager.spawn_handle();
+
+	let db = kvdb_memorydb::create(NUM_COLUMNS);
+	let db: Arc<dyn Database> =
+		Arc::new(polkadot_node_subsystem_util::database::kvdb_impl::DbAdapter::new(db, &[]));
+	let keystore = LocalKeystore::in_memory();
+	keystore
+		.sr25519_generate_new(
+			ValidatorId::ID,
+			Some(state.test_authorities.key_seeds.get(NODE_UNDER_TEST as usize).unwrap().as_str()),
+		)
+		.unwrap();
+
+	let mock_runtime_api = MockRuntimeApi::new(
+		state.config.clone(),
+		state.test_authorities.clone(),
+		state.candidate_receipts.clone(),
+		state.candidate_events.clone(),
+		Default::default(),
+		test_state::SESSION_INDEX,
+		MockRuntimeApiCoreState::Scheduled,
+	);
+	let chain_api_state = ChainApiState { block_headers: state.block_headers.clone() };
+	let mock_chain_api = MockChainApi::new(chain_api_state);
+	let subsystem = DisputeCoordinatorSubsystem::new(
+		db,
+		Config { col_dispute_data: COL_DISPUTE_DATA },
+		Arc::new(keystore),
+		Metrics::try_register(&dependencies.registry).unwrap(),
+		true,
+	);
+
+	let dummy = dummy_builder!(spawn_task_handle, overseer_metrics)
+		.replace_runtime_api(|_| mock_runtime_api)
+		.replace_chain_api(|_| mock_chain_api)
+		.replace_availability_recovery(|_| MockAvailabilityRecovery::new())
+		.replace_candidate_validation(|_| MockCandidateValidation::new())
+		.replace_approval_voting_parallel(|_| MockApprovalVotingParallel::new())
+		.replace_dispute_coordinator(|_| subsystem);
+	let (overseer, raw_handle) = dummy.build_with_connector(overseer_connector).unwrap();
+	let overseer_handle = OverseerHandle::new(raw_handle);
+
+	(overseer, overseer_handle)
+}
+
+pub fn prepare_test(state: &TestState, with_prometheus_endpoint: bool) -> TestEnvironment {
+	let dependencies = TestEnvironmentDependencies::default();
+	// The dispute coordinator doesn't talk to the network, the emulated network only serves to
+	// report the (absent) network usage.
+	let (network, _network_interface, _network_receiver) =
+		new_network(&state.config, &dependencies, &state.test_authorities, vec![]);
+	let (overseer, overseer_handle) = build_overseer(state, &dependencies);
+
+	TestEnvironment::new(
+		dependencies,
+		state.config.clone(),
+		network,
+		overseer,
+		overseer_handle,
+		state.test_authorities.clone(),
+		with_prometheus_endpoint,
+	)
+}
+
+fn import_votes(
+	candidate_receipt: &CandidateReceipt,
+	votes: &test_state::DisputeVotes,
+) -> AllMessages {
+	AllMessages::DisputeCoordinator(DisputeCoordinatorMessage::ImportStatements {
+		candidate_receipt: candidate_receipt.clone(),
+		session: test_state::SESSION_INDEX,
+		statements: votes.clone(),
+		pending_confirmation: None,
+	})
+}
+
+pub async fn benchmark_dispute_coordinator(
+	env: &mut TestEnvironment,
+	state: &TestState,
+) -> BenchmarkUsage {
+	let config = env.config().clone();
+
+	env.metrics().set_n_validators(config.n_validators);
+	env.metrics().set_n_cores(config.n_cores);
+
+	let test_start = Instant::now();
+	let mut disputes_raised = 0;
+	for block_info in state.block_infos.iter() {
+		let block_num = block_info.number as usize;
+		gum::info!(target: LOG_TARGET, "Current block {}/{} {:?}", block_num, config.num_blocks, block_info.hash);
+		env.metrics().set_current_block(block_num);
+
+		let block_start_ts = Instant::now();
+		env.import_block(block_info.clone()).await;
+
+		let disputed_candidates = state.disputed_candidates.get(&block_info.hash).unwrap();
+		for candidate in disputed_candidates.iter() {
+			let votes = state.initial_votes.get(&candidate.hash()).unwrap();
+			env.send_message(import_votes(candidate, votes)).await;
+		}
+		disputes_raised += disputed_candidates.len();
+
+		// The node under test participates in every dispute, as the candidates are included.
+		env.wait_until_metric(
+			"polkadot_parachain_dispute_participation_pipeline_durations_count",
+			None,
+			|value| value >= disputes_raised as f64,
+		)
+		.await;
+		gum::info!(target: LOG_TARGET, "Participated in {} disputes", disputes_raised);
+
+		for candidate in disputed_candidates.iter() {
+			let votes = state.concluding_votes.get(&candidate.hash()).unwrap();
+			env.send_message(import_votes(candidate, votes)).await;
+		}
+
+		env.wait_until_metric(
+			"polkadot_parachain_candidate_dispute_concluded",
+			Some(("validity", "valid")),
+			|value| value >= disputes_raised as f64,
+		)
+		.await;
+
+		let block_time = Instant::now().sub(block_start_ts).as_millis() as u64;
+		env.metrics().set_block_time(block_time);
+		gum::info!(target: LOG_TARGET, "All disputes for block concluded in {}", format!("{:?}ms", block_time).cyan());
+	}
+
+	let duration: u128 = test_start.elapsed().as_millis();
+	gum::info!(target: LOG_TARGET, "All blocks processed in {}", format!("{:?}ms", duration).cyan());
+	gum::info!(target: LOG_TARGET,
+		"Avg block time: {}",
+		format!("{} ms", test_start.elapsed().as_millis() / env.config().num_blocks as u128).red()
+	);
+
+	env.stop().await;
+	env.collect_resource_usage(&["dispute-coordinator"], false)
+}
@@ -0,0 +1,183 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{
+	configuration::{TestAuthorities, TestConfiguration},
+	disputes::DisputesOptions,
+	NODE_UNDER_TEST,
+};
+use itertools::Itertools;
+use polkadot_node_primitives::SignedDisputeStatement;
+use polkadot_node_subsystem_test_helpers::mock::new_block_import_info;
+use polkadot_overseer::BlockInfo;
+use polkadot_primitives::{
+	vstaging::{CandidateEvent, CandidateReceiptV2 as CandidateReceipt, MutateDescriptorV2},
+	BlockNumber, CandidateHash, CoreIndex, DisputeStatement, GroupIndex, Hash, Header, Id,
+	InvalidDisputeStatementKind, SessionIndex, ValidDisputeStatementKind, ValidatorIndex,
+	ValidatorPair,
+};
+use polkadot_primitives_test_helpers::{dummy_candidate_receipt_v2, dummy_head_data};
+use sp_core::{Pair, H256};
+use std::collections::HashMap;
+
+pub const SESSION_INDEX: SessionIndex = 0;
+
+/// The validator which raises the disputes by voting against the candidates.
+const INVALID_VOTER: u32 = NODE_UNDER_TEST + 1;
+
+/// Dispute statements imported for a candidate.
+pub type DisputeVotes = Vec<(SignedDisputeStatement, ValidatorIndex)>;
+
+#[derive(Clone)]
+pub struct TestState {
+	// Full test config
+	pub config: TestConfiguration,
+	// Authority keys for the network emulation.
+	pub test_authorities: TestAuthorities,
+	// Relay chain block infos
+	pub block_infos: Vec<BlockInfo>,
+	// Relay chain block headers
+	pub block_headers: HashMap<H256, Header>,
+	// Candidates backed and included in each relay chain block
+	pub candidate_receipts: HashMap<H256, Vec<CandidateReceipt>>,
+	// Inclusion events for the candidates of each relay chain block
+	pub candidate_events: HashMap<H256, Vec<CandidateEvent>>,
+	// Candidates disputed in each relay chain block
+	pub disputed_candidates: HashMap<H256, Vec<CandidateReceipt>>,
+	// Votes raising a dispute: one invalid and one valid vote per candidate
+	pub initial_votes: HashMap<CandidateHash, DisputeVotes>,
+	// Valid votes of all remaining validators, concluding the dispute
+	pub concluding_votes: HashMap<CandidateHash, DisputeVotes>,
+}
+
+impl TestState {
+	pub fn new(config: &TestConfiguration, options: &DisputesOptions) -> Self {
+		assert!(
+			config.n_validators > INVALID_VOTER as usize + 2,
+			"At least 4 validators are required to raise and conclude disputes"
+		);
+		assert!(
+			options.n_disputes <= config.n_cores,
+			"Only candidates included in a block can be disputed, one per core"
+		);
+
+		let test_authorities = config.generate_authorities();
+		let mut state = Self {
+			config: config.clone(),
+			test_authorities,
+			block_infos: (1..=config.num_blocks).map(generate_block_info).collect(),
+			block_headers: Default::default(),
+			candidate_receipts: Default::default(),
+			candidate_events: Default::default(),
+			disputed_candidates: Default::default(),
+			initial_votes: Default::default(),
+			concluding_votes: Default::default(),
+		};
+
+		state.block_headers = state.block_infos.iter().map(generate_block_header).collect();
+
+		for block_info in state.block_infos.iter() {
+			for core_idx in 0..config.n_cores {
+				let mut receipt = dummy_candidate_receipt_v2(block_info.hash);
+				receipt.descriptor.set_para_id(Id::new(core_idx as u32 + 1));
+				receipt.descriptor.set_core_index(CoreIndex(core_idx as u32));
+				receipt.descriptor.set_session_index(SESSION_INDEX);
+
+				state.candidate_events.entry(block_info.hash).or_default().push(
+					CandidateEvent::CandidateIncluded(
+						receipt.clone(),
+						dummy_head_data(),
+						CoreIndex(core_idx as u32),
+						GroupIndex(core_idx as u32),
+					),
+				);
+				state.candidate_receipts.entry(block_info.hash).or_default().push(receipt);
+			}
+
+			let disputed = state.candidate_receipts[&block_info.hash]
+				.iter()
+				.take(options.n_disputes)
+				.cloned()
+				.collect_vec();
+
+			for candidate in disputed.iter() {
+				let candidate_hash = candidate.hash();
+				let vote = |index: u32, valid: bool| {
+					let validator_index = ValidatorIndex(index);
+					let pair = state.test_authorities.validator_pairs.get(index as usize).unwrap();
+					(sign_dispute_statement(valid, candidate_hash, pair), validator_index)
+				};
+
+				state.initial_votes.insert(
+					candidate_hash,
+					vec![vote(INVALID_VOTER, false), vote(INVALID_VOTER + 1, true)],
+				);
+				state.concluding_votes.insert(
+					candidate_hash,
+					(INVALID_VOTER + 2..config.n_validators as u32)
+						.map(|index| vote(index, true))
+						.collect(),
+				);
+			}
+
+			state.disputed_candidates.insert(block_info.hash, disputed);
+		}
+
+		state
+	}
+}
+
+fn sign_dispute_statement(
+	valid: bool,
+	candidate_hash: CandidateHash,
+	pair: &ValidatorPair,
+) -> SignedDisputeStatement {
+	let statement = if valid {
+		DisputeStatement::Valid(ValidDisputeStatementKind::Explicit)
+	} else {
+		DisputeStatement::Invalid(InvalidDisputeStatementKind::Explicit)
+	};
+	let payload = statement.payload_data(candidate_hash, SESSION_INDEX).unwrap();
+
+	SignedDisputeStatement::new_checked(
+		statement,
+		candidate_hash,
+		SESSION_INDEX,
+		pair.public(),
+		pair.sign(&payload[..]),
+	)
+	.unwrap()
+}
+
+fn generate_block_info(block_num: usize) -> BlockInfo {
+	BlockInfo {
+		parent_hash: Hash::repeat_byte(block_num as u8 - 1),
+		..new_block_import_info(Hash::repeat_byte(block_num as u8), block_num as BlockNumber)
+	}
+}
+
+fn generate_block_header(info: &BlockInfo) -> (H256, Header) {
+	(
+		info.hash,
+		Header {
+			digest: Default::default(),
+			number: info.number,
+			parent_hash: info.parent_hash,
+			extrinsics_root: Default::default(),
+			state_root: Default::default(),
+		},
+	)
+}
@@ -21,10 +21,12 @@ pub mod approval;
 pub mod availability;
 pub mod configuration;
 pub(crate) mod display;
+pub mod disputes;
 pub(crate) mod environment;
 pub(crate) mod keyring;
 pub(crate) mod mock;
 pub(crate) mod network;
+pub mod prospective_parachains;
 pub mod statement;
 pub mod usage;
 pub mod utils;
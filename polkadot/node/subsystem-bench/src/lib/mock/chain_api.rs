@@ -104,19 +104,22 @@ impl MockChainApi {
 								)))
 								.unwrap();
 						},
-						ChainApiMessage::Ancestors { hash, k: _, response_channel } => {
+						ChainApiMessage::Ancestors { hash, k, response_channel } => {
 							let block_number = self
 								.state
 								.block_headers
 								.get(&hash)
 								.expect("Unknown block hash")
 								.number;
+							// Like the real Chain API, return up to `k` ancestors starting
+							// with the parent.
 							let ancestors = self
 								.state
 								.block_headers
 								.iter()
 								.filter(|(_, header)| header.number < block_number)
-								.sorted_by(|a, b| a.1.number.cmp(&b.1.number))
+								.sorted_by(|a, b| b.1.number.cmp(&a.1.number))
+								.take(k)
 								.map(|(hash, _)| *hash)
 								.collect_vec();
 							response_channel.send(Ok(ancestors)).unwrap();
@@ -27,7 +27,10 @@ use polkadot_node_subsystem::{
 use polkadot_node_subsystem_types::OverseerSignal;
 use polkadot_primitives::{
 	node_features,
-	vstaging::{CandidateEvent, CandidateReceiptV2 as CandidateReceipt, CoreState, OccupiedCore},
+	vstaging::{
+		async_backing::Constraints, CandidateEvent, CandidateReceiptV2 as CandidateReceipt,
+		CoreState, OccupiedCore,
+	},
 	ApprovalVotingParams, AsyncBackingParams, CoreIndex, GroupIndex, GroupRotationInfo,
 	Id as ParaId, IndexedVec, NodeFeatures, ScheduledCore, SessionIndex, SessionInfo,
 	ValidationCode, ValidatorIndex,
@@ -54,6 +57,8 @@ pub struct RuntimeApiState {
 	session_index: SessionIndex,
 	// The claim queue
 	claim_queue: BTreeMap<CoreIndex, VecDeque<ParaId>>,
+	// Backing constraints per block and para
+	backing_constraints: HashMap<H256, HashMap<ParaId, Constraints>>,
 }
 
 #[derive(Clone)]
@@ -112,12 +117,32 @@ impl MockRuntimeApi {
 				session_index,
 				node_features,
 				claim_queue,
+				backing_constraints: Default::default(),
 			},
 			config,
 			core_state,
 		}
 	}
 
+	/// Replaces the default claim queue, which assigns one core to each para.
+	///
+	/// Used to assign multiple cores to the same para (elastic scaling).
+	pub fn with_claim_queue(mut self, claim_queue: BTreeMap<CoreIndex, VecDeque<ParaId>>) -> Self {
+		self.state.claim_queue = claim_queue;
+		self
+	}
+
+	/// Sets the constraints answered to `BackingConstraints` requests, per block and para.
+	///
+	/// Paras without constraints for a block are answered with `None`.
+	pub fn with_backing_constraints(
+		mut self,
+		backing_constraints: HashMap<H256, HashMap<ParaId, Constraints>>,
+	) -> Self {
+		self.state.backing_constraints = backing_constraints;
+		self
+	}
+
 	fn session_info(&self) -> SessionInfo {
 		session_info_for_peers(&self.config, &self.state.authorities)
 	}
@@ -330,6 +355,43 @@ impl MockRuntimeApi {
 						RuntimeApiMessage::Request(_parent, RuntimeApiRequest::ClaimQueue(tx)) => {
 							tx.send(Ok(self.state.claim_queue.clone())).unwrap();
 						},
+						RuntimeApiMessage::Request(
+							_parent,
+							RuntimeApiRequest::SchedulingLookahead(_session_index, tx),
+						) => {
+							tx.send(Ok(self.config.allowed_ancestry_len + 1)).unwrap();
+						},
+						RuntimeApiMessage::Request(
+							block_hash,
+							RuntimeApiRequest::BackingConstraints(para_id, tx),
+						) => {
+							let constraints = self
+								.state
+								.backing_constraints
+								.get(&block_hash)
+								.and_then(|per_para| per_para.get(&para_id))
+								.cloned();
+							tx.send(Ok(constraints)).unwrap();
+						},
+						RuntimeApiMessage::Request(
+							_parent,
+							RuntimeApiRequest::CandidatesPendingAvailability(_para_id, tx),
+						) => {
+							// Candidates are never pending availability.
+							tx.send(Ok(vec![])).unwrap();
+						},
+						RuntimeApiMessage::Request(
+							_parent,
+							RuntimeApiRequest::FetchOnChainVotes(tx),
+						) => {
+							tx.send(Ok(None)).unwrap();
+						},
+						RuntimeApiMessage::Request(
+							_parent,
+							RuntimeApiRequest::UnappliedSlashes(tx),
+						) => {
+							tx.send(Ok(vec![])).unwrap();
+						},
 						// Long term TODO: implement more as needed.
 						message => {
 							unimplemented!("Unexpected runtime-api message: {:?}", message)
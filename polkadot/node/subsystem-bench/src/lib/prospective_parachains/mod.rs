@@ -0,0 +1,189 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Prospective parachains benchmark.
+//!
+//! Each para is assigned multiple cores (elastic scaling), so every relay chain block it gets a
+//! chain of candidates as deep as the number of its cores seconded, backed and requested for
+//! inclusion.
+
+use crate::{
+	dummy_builder,
+	environment::{TestEnvironment, TestEnvironmentDependencies},
+	mock::{
+		chain_api::{ChainApiState, MockChainApi},
+		runtime_api::{MockRuntimeApi, MockRuntimeApiCoreState},
+		AlwaysSupportsParachains,
+	},
+	network::new_network,
+	usage::BenchmarkUsage,
+};
+use colored::Colorize;
+use futures::channel::oneshot;
+use polkadot_node_core_prospective_parachains::ProspectiveParachainsSubsystem;
+use polkadot_node_metrics::metrics::Metrics;
+use polkadot_node_subsystem::messages::{
+	AllMessages, IntroduceSecondedCandidateRequest, ProspectiveParachainsMessage,
+};
+use polkadot_overseer::{
+	Handle as OverseerHandle, Overseer, OverseerConnector, OverseerMetrics, SpawnGlue,
+};
+use sc_service::SpawnTaskHandle;
+use serde::{Deserialize, Serialize};
+use std::{ops::Sub, time::Instant};
+pub use test_state::TestState;
+
+mod test_state;
+
+const LOG_TARGET: &str = "subsystem-bench::prospective-parachains";
+
+/// Parameters specific to the prospective parachains benchmark
+#[derive(Debug, Clone, Serialize, Deserialize, clap::Parser)]
+#[clap(rename_all = "kebab-case")]
+#[allow(missing_docs)]
+pub struct ProspectiveParachainsOptions {
+	#[clap(short, long, default_value_t = 3)]
+	/// The number of cores assigned to each para, which is also the depth of its fragment chain.
+	/// `n_cores` are split between `n_cores / cores_per_para` paras.
+	pub cores_per_para: usize,
+}
+
+fn build_overseer(
+	state: &TestState,
+	dependencies: &TestEnvironmentDependencies,
+) -> (Overseer<SpawnGlue<SpawnTaskHandle>, AlwaysSupportsParachains>, OverseerHandle) {
+	let overseer_connector = OverseerConnector::with_event_capacity(64000);
+	let overseer_metrics = OverseerMetrics::try_register(&dependencies.registry).unwrap();
+	let spawn_task_handle = dependencies.task_manager.spawn_handle();
+
+	let mock_runtime_api = MockRuntimeApi::new(
+		state.config.clone(),
+		state.test_authorities.clone(),
+		state.candidate_receipts.clone(),
+		Default::default(),
+		Default::default(),
+		0,
+		MockRuntimeApiCoreState::Scheduled,
+	)
+	.with_claim_queue(state.claim_queue.clone())
+	.with_backing_constraints(state.backing_constraints.clone());
+	let chain_api_state = ChainApiState { block_headers: state.block_headers.clone() };
+	let mock_chain_api = MockChainApi::new(chain_api_state);
+	let subsystem =
+		ProspectiveParachainsSubsystem::new(Metrics::try_register(&dependencies.registry).unwrap());
+
+	let dummy = dummy_builder!(spawn_task_handle, overseer_metrics)
+		.replace_runtime_api(|_| mock_runtime_api)
+		.replace_chain_api(|_| mock_chain_api)
+		.replace_prospective_parachains(|_| subsystem);
+	let (overseer, raw_handle) = dummy.build_with_connector(overseer_connector).unwrap();
+	let overseer_handle = OverseerHandle::new(raw_handle);
+
+	(overseer, overseer_handle)
+}
+
+pub fn prepare_test(state: &TestState, with_prometheus_endpoint: bool) -> TestEnvironment {
+	let dependencies = TestEnvironmentDependencies::default();
+	// Prospective parachains doesn't talk to the network, the emulated network only serves to
+	// report the (absent) network usage.
+	let (network, _network_interface, _network_receiver) =
+		new_network(&state.config, &dependencies, &state.test_authorities, vec![]);
+	let (overseer, overseer_handle) = build_overseer(state, &dependencies);
+
+	TestEnvironment::new(
+		dependencies,
+		state.config.clone(),
+		network,
+		overseer,
+		overseer_handle,
+		state.test_authorities.clone(),
+		with_prometheus_endpoint,
+	)
+}
+
+pub async fn benchmark_prospective_parachains(
+	env: &mut TestEnvironment,
+	state: &TestState,
+) -> BenchmarkUsage {
+	let config = env.config().clone();
+
+	env.metrics().set_n_validators(config.n_validators);
+	env.metrics().set_n_cores(config.n_cores);
+
+	let test_start = Instant::now();
+	for block_info in state.block_infos.iter() {
+		let block_num = block_info.number as usize;
+		gum::info!(target: LOG_TARGET, "Current block {}/{} {:?}", block_num, config.num_blocks, block_info.hash);
+		env.metrics().set_current_block(block_num);
+
+		let block_start_ts = Instant::now();
+		env.import_block(block_info.clone()).await;
+
+		let candidates = state.candidates.get(&block_info.hash).unwrap();
+		for (para, chain) in candidates.iter() {
+			for (candidate, pvd) in chain.iter() {
+				let (tx, rx) = oneshot::channel();
+				env.send_message(AllMessages::ProspectiveParachains(
+					ProspectiveParachainsMessage::IntroduceSecondedCandidate(
+						IntroduceSecondedCandidateRequest {
+							candidate_para: *para,
+							candidate_receipt: candidate.clone(),
+							persisted_validation_data: pvd.clone(),
+						},
+						tx,
+					),
+				))
+				.await;
+				assert!(rx.await.unwrap(), "Seconded candidate must be accepted");
+
+				env.send_message(AllMessages::ProspectiveParachains(
+					ProspectiveParachainsMessage::CandidateBacked(*para, candidate.hash()),
+				))
+				.await;
+			}
+		}
+
+		for (para, chain) in candidates.iter() {
+			let (tx, rx) = oneshot::channel();
+			env.send_message(AllMessages::ProspectiveParachains(
+				ProspectiveParachainsMessage::GetBackableCandidates(
+					block_info.hash,
+					*para,
+					chain.len() as u32,
+					Default::default(),
+					tx,
+				),
+			))
+			.await;
+			let backable = rx.await.unwrap();
+			assert_eq!(backable.len(), chain.len(), "The whole chain must be backable");
+		}
+
+		let block_time = Instant::now().sub(block_start_ts).as_millis() as u64;
+		env.metrics().set_block_time(block_time);
+		gum::info!(target: LOG_TARGET, "All work for block completed in {}", format!("{:?}ms", block_time).cyan());
+	}
+
+	let duration: u128 = test_start.elapsed().as_millis();
+	gum::info!(target: LOG_TARGET, "All blocks processed in {}", format!("{:?}ms", duration).cyan());
+	gum::info!(target: LOG_TARGET,
+		"Avg block time: {}",
+		format!("{} ms", test_start.elapsed().as_millis() / env.config().num_blocks as u128).red()
+	);
+
+	env.stop().await;
+	env.collect_resource_usage(&["prospective-parachains"], false)
+}
@@ -0,0 +1,171 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+use crate::{
+	configuration::{TestAuthorities, TestConfiguration},
+	prospective_parachains::ProspectiveParachainsOptions,
+};
+use codec::Encode;
+use polkadot_node_subsystem_test_helpers::mock::new_block_import_info;
+use polkadot_overseer::BlockInfo;
+use polkadot_primitives::{
+	async_backing::InboundHrmpLimitations,
+	vstaging::{
+		async_backing::Constraints, CandidateReceiptV2 as CandidateReceipt,
+		CommittedCandidateReceiptV2 as CommittedCandidateReceipt,
+	},
+	BlockNumber, CoreIndex, Hash, HeadData, Header, Id, PersistedValidationData,
+};
+use polkadot_primitives_test_helpers::{dummy_validation_code, make_candidate_v2};
+use sp_core::H256;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+const MAX_HEAD_DATA_SIZE: u32 = 1024;
+const MAX_CODE_SIZE: u32 = 1024 * 1024;
+
+#[derive(Clone)]
+pub struct TestState {
+	// Full test config
+	pub config: TestConfiguration,
+	// Authority keys for the network emulation.
+	pub test_authorities: TestAuthorities,
+	// Relay chain block infos
+	pub block_infos: Vec<BlockInfo>,
+	// Relay chain block headers
+	pub block_headers: HashMap<H256, Header>,
+	// The paras under test
+	pub paras: Vec<Id>,
+	// Each para is assigned `cores_per_para` cores
+	pub claim_queue: BTreeMap<CoreIndex, VecDeque<Id>>,
+	// Backing constraints of each para at each relay chain block
+	pub backing_constraints: HashMap<H256, HashMap<Id, Constraints>>,
+	// Candidates seconded and backed at each relay chain block, grouped by para
+	pub candidates:
+		HashMap<H256, HashMap<Id, Vec<(CommittedCandidateReceipt, PersistedValidationData)>>>,
+	// Receipts of the candidates seconded at each relay chain block
+	pub candidate_receipts: HashMap<H256, Vec<CandidateReceipt>>,
+}
+
+impl TestState {
+	pub fn new(config: &TestConfiguration, options: &ProspectiveParachainsOptions) -> Self {
+		let cores_per_para = options.cores_per_para;
+		let n_paras = config.n_cores / cores_per_para;
+		assert!(n_paras > 0, "`n_cores` must be at least `cores_per_para`");
+
+		let paras = (0..n_paras).map(|index| Id::new(index as u32 + 1)).collect::<Vec<_>>();
+		let lookahead = config.allowed_ancestry_len as usize + 1;
+		let claim_queue = (0..n_paras * cores_per_para)
+			.map(|core| {
+				let para = paras[core / cores_per_para];
+				(CoreIndex(core as u32), std::iter::repeat(para).take(lookahead).collect())
+			})
+			.collect();
+
+		let mut state = Self {
+			config: config.clone(),
+			test_authorities: config.generate_authorities(),
+			block_infos: (1..=config.num_blocks).map(generate_block_info).collect(),
+			block_headers: Default::default(),
+			paras,
+			claim_queue,
+			backing_constraints: Default::default(),
+			candidates: Default::default(),
+			candidate_receipts: Default::default(),
+		};
+
+		state.block_headers = state.block_infos.iter().map(generate_block_header).collect();
+
+		let validation_code_hash = dummy_validation_code().hash();
+		for block_info in state.block_infos.iter() {
+			// All candidates backed at the previous block got included, each para advanced its
+			// head by `cores_per_para` candidates.
+			let included = (block_info.number as usize - 1) * cores_per_para;
+			let min_relay_parent_number =
+				block_info.number.saturating_sub(config.allowed_ancestry_len).max(1);
+
+			for para in state.paras.iter() {
+				let chain = (included..included + cores_per_para)
+					.map(|parent| {
+						make_candidate_v2(
+							block_info.hash,
+							block_info.number,
+							*para,
+							head_data(parent),
+							head_data(parent + 1),
+							validation_code_hash,
+						)
+					})
+					.collect::<Vec<_>>();
+				let max_pov_size =
+					chain.first().expect("`cores_per_para` is not zero").1.max_pov_size;
+
+				state.backing_constraints.entry(block_info.hash).or_default().insert(
+					*para,
+					Constraints {
+						min_relay_parent_number,
+						max_pov_size,
+						max_code_size: MAX_CODE_SIZE,
+						max_head_data_size: MAX_HEAD_DATA_SIZE,
+						ump_remaining: 10,
+						ump_remaining_bytes: 1_000,
+						max_ump_num_per_candidate: 10,
+						dmp_remaining_messages: vec![],
+						hrmp_inbound: InboundHrmpLimitations { valid_watermarks: vec![] },
+						hrmp_channels_out: vec![],
+						max_hrmp_num_per_candidate: 0,
+						required_parent: head_data(included),
+						validation_code_hash,
+						upgrade_restriction: None,
+						future_validation_code: None,
+					},
+				);
+				state
+					.candidate_receipts
+					.entry(block_info.hash)
+					.or_default()
+					.extend(chain.iter().map(|(candidate, _)| candidate.to_plain()));
+				state.candidates.entry(block_info.hash).or_default().insert(*para, chain);
+			}
+		}
+
+		state
+	}
+}
+
+/// The head data of a para after `number` candidates.
+fn head_data(number: usize) -> HeadData {
+	HeadData((number as u64).encode())
+}
+
+fn generate_block_info(block_num: usize) -> BlockInfo {
+	BlockInfo {
+		parent_hash: Hash::repeat_byte(block_num as u8 - 1),
+		..new_block_import_info(Hash::repeat_byte(block_num as u8), block_num as BlockNumber)
+	}
+}
+
+fn generate_block_header(info: &BlockInfo) -> (H256, Header) {
+	(
+		info.hash,
+		Header {
+			digest: Default::default(),
+			number: info.number,
+			parent_hash: info.parent_hash,
+			extrinsics_root: Default::default(),
+			state_root: Default::default(),
+		},
+	)
+}
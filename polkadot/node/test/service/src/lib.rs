@@ -107,6 +107,7 @@ pub fn new_full<OverseerGenerator: OverseerGen>(
 					prepare_workers_soft_max_num: None,
 					enable_approval_voting_parallel: false,
 					keep_finalized_for: None,
+					pov_archive: None,
 				},
 			),
 		sc_network::config::NetworkBackendType::Litep2p =>
@@ -130,6 +131,7 @@ pub fn new_full<OverseerGenerator: OverseerGen>(
 					prepare_workers_soft_max_num: None,
 					enable_approval_voting_parallel: false,
 					keep_finalized_for: None,
+					pov_archive: None,
 				},
 			),
 	}
@@ -207,6 +209,7 @@ pub fn node_config(
 		trie_cache_maximum_size: Some(64 * 1024 * 1024),
 		state_pruning: Default::default(),
 		blocks_pruning: BlocksPruning::KeepFinalized,
+		state_replay_max_blocks: None,
 		chain_spec: Box::new(spec),
 		executor: ExecutorConfiguration {
 			wasm_method: WasmExecutionMethod::Compiled {
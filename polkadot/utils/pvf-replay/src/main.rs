@@ -0,0 +1,681 @@
+// Copyright (C) Parity Technologies (UK) Ltd.
+// This file is part of Polkadot.
+
+// Polkadot is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// Polkadot is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with Polkadot.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Replay the validation of a single parachain candidate offline.
+//!
+//! The candidate is run through the same prepare and execute workers that the node uses, with the
+//! executor parameters of the session the candidate was validated in. This makes it possible to
+//! reproduce the outcome of a dispute or a backing mismatch without a running network.
+//!
+//! All inputs are SCALE encoded, except the validation code, which is the raw (possibly compressed)
+//! wasm blob as stored on chain. The PoV and persisted validation data can alternatively be read
+//! from the availability store of a stopped node.
+//!
+//! Besides the outcome, the tool reports the time spent in preparation and execution, as well as
+//! the CPU time and peak memory used by the workers.
+//!
+//! The process exit code reflects the classification of the outcome:
+//!
+//! - `0`: the candidate is valid;
+//! - `10`: the candidate is invalid and the node would vote against it;
+//! - `11`: the candidate is possibly invalid, the node would retry before voting against it;
+//! - `12`: the outcome is inconclusive because of a local error;
+//! - `1`: the tool itself failed, e.g. because an input could not be read.
+
+use clap::{Parser, ValueEnum};
+use codec::{Decode, Encode};
+use nix::sys::resource::{getrusage, Usage, UsageWho};
+use polkadot_node_core_pvf::{
+	InvalidCandidate as WasmInvalidCandidate, Metrics, PossiblyInvalidError, PrepareError,
+	PrepareJobKind, Priority, PvfPrepData, ValidationError, EXECUTE_BINARY_NAME,
+	PREPARE_BINARY_NAME,
+};
+use polkadot_node_core_pvf_common::worker::get_total_cpu_usage;
+use polkadot_node_primitives::{AvailableData, PoV, NODE_VERSION, VALIDATION_CODE_BOMB_LIMIT};
+use polkadot_node_subsystem::messages::PvfExecKind;
+use polkadot_node_subsystem_util::metrics::{prometheus, Metrics as _};
+use polkadot_parachain_primitives::primitives::ValidationResult;
+use polkadot_primitives::{
+	executor_params::{
+		DEFAULT_APPROVAL_EXECUTION_TIMEOUT, DEFAULT_BACKING_EXECUTION_TIMEOUT,
+		DEFAULT_LENIENT_PREPARATION_TIMEOUT,
+	},
+	vstaging::CandidateReceiptV2 as CandidateReceipt,
+	CandidateCommitments, ExecutorParams, PersistedValidationData,
+	PvfExecKind as RuntimePvfExecKind, PvfPrepKind, ValidationCode,
+};
+use polkadot_service::parachains_db::{NUM_COLUMNS, REAL_COLUMNS};
+use std::{
+	path::{Path, PathBuf},
+	process::ExitCode,
+	sync::Arc,
+	time::Duration,
+};
+
+/// The kind of validation to replay. It selects the execution timeout and priority.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum ExecKind {
+	Backing,
+	Approval,
+	Dispute,
+}
+
+#[derive(Debug, Parser)]
+#[clap(author, version, about)]
+struct Opt {
+	/// Path to the SCALE encoded `CandidateReceipt`.
+	#[arg(long)]
+	candidate_receipt: PathBuf,
+
+	/// Path to the validation code, raw or compressed wasm.
+	#[arg(long)]
+	validation_code: PathBuf,
+
+	/// Path to the SCALE encoded `PersistedValidationData`.
+	#[arg(long, required_unless_present = "av_store")]
+	validation_data: Option<PathBuf>,
+
+	/// Path to the SCALE encoded `PoV`.
+	#[arg(long, required_unless_present = "av_store")]
+	pov: Option<PathBuf>,
+
+	/// Base path of a stopped node to load the PoV and persisted validation data from.
+	///
+	/// Only RocksDB availability stores are supported. The database is opened read-only.
+	#[arg(long, conflicts_with_all = ["validation_data", "pov"])]
+	av_store: Option<PathBuf>,
+
+	/// Id of the chain to load the availability store of, i.e. its directory in
+	/// `<av-store>/chains`.
+	///
+	/// Defaults to the only chain in the base path.
+	#[arg(long, requires = "av_store")]
+	chain_id: Option<String>,
+
+	/// Path to the SCALE encoded `ExecutorParams` of the session. Defaults to the empty set.
+	#[arg(long)]
+	executor_params: Option<PathBuf>,
+
+	/// The kind of validation to replay.
+	#[arg(long, value_enum, default_value_t = ExecKind::Approval)]
+	exec_kind: ExecKind,
+
+	/// Directory containing the prepare and execute worker binaries.
+	///
+	/// Defaults to the directory of this executable.
+	#[arg(long)]
+	workers_path: Option<PathBuf>,
+
+	/// Directory to store compiled artifacts in. Defaults to a temporary directory.
+	#[arg(long)]
+	cache_path: Option<PathBuf>,
+
+	/// Maximum size of the decompressed validation code.
+	#[arg(long, default_value_t = VALIDATION_CODE_BOMB_LIMIT as u32)]
+	validation_code_bomb_limit: u32,
+
+	/// Run the workers in secure validator mode, as a validator would.
+	#[arg(long)]
+	secure_validator_mode: bool,
+}
+
+/// The deterministic classification of a replayed validation.
+#[derive(Debug)]
+enum Outcome {
+	/// The candidate is valid.
+	Valid,
+	/// The candidate is invalid; this does not depend on the machine running the replay.
+	Invalid(String),
+	/// Execution failed in a way the node retries before treating the candidate as invalid.
+	PossiblyInvalid(String),
+	/// A local error prevented reaching a verdict.
+	Inconclusive(String),
+}
+
+impl Outcome {
+	fn exit_code(&self) -> ExitCode {
+		match self {
+			Outcome::Valid => ExitCode::SUCCESS,
+			Outcome::Invalid(_) => ExitCode::from(10),
+			Outcome::PossiblyInvalid(_) => ExitCode::from(11),
+			Outcome::Inconclusive(_) => ExitCode::from(12),
+		}
+	}
+}
+
+impl std::fmt::Display for Outcome {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		match self {
+			Outcome::Valid => write!(f, "valid"),
+			Outcome::Invalid(reason) => write!(f, "invalid: {reason}"),
+			Outcome::PossiblyInvalid(reason) => write!(f, "possibly-invalid: {reason}"),
+			Outcome::Inconclusive(reason) => write!(f, "inconclusive: {reason}"),
+		}
+	}
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, String> {
+	std::fs::read(path).map_err(|e| format!("failed to read {}: {e}", path.display()))
+}
+
+fn decode_file<T: Decode>(path: &Path) -> Result<T, String> {
+	let raw = read_file(path)?;
+	T::decode(&mut &raw[..]).map_err(|e| format!("failed to decode {}: {e}", path.display()))
+}
+
+/// Get the path of the parachains database of a node with the given base path.
+///
+/// Without a `chain_id`, the base path must contain exactly one chain.
+fn parachains_db_path(base_path: &Path, chain_id: Option<&str>) -> Result<PathBuf, String> {
+	let chains_path = base_path.join("chains");
+	let chain_id = match chain_id {
+		Some(chain_id) => chain_id.to_owned(),
+		None => {
+			let chains = std::fs::read_dir(&chains_path)
+				.map_err(|e| format!("failed to read {}: {e}", chains_path.display()))?
+				.filter_map(|entry| entry.ok())
+				.filter(|entry| entry.path().is_dir())
+				.map(|entry| entry.file_name().to_string_lossy().into_owned())
+				.collect::<Vec<_>>();
+			match &chains[..] {
+				[chain_id] => chain_id.clone(),
+				_ =>
+					return Err(format!(
+						"found chains {chains:?} in {}, select one with --chain-id",
+						chains_path.display(),
+					)),
+			}
+		},
+	};
+
+	Ok(chains_path.join(chain_id).join("db").join("full").join("parachains").join("db"))
+}
+
+/// Load the available data of a candidate from the availability store of a stopped node.
+fn load_from_av_store(
+	base_path: &Path,
+	chain_id: Option<&str>,
+	receipt: &CandidateReceipt,
+) -> Result<AvailableData, String> {
+	let path = parachains_db_path(base_path, chain_id)?;
+
+	// Open the database as a secondary instance, which is read-only and keeps its own logs
+	// outside of the node's database.
+	let secondary_path = tempfile::tempdir()
+		.map_err(|e| format!("failed to create the secondary database directory: {e}"))?;
+	let mut db_config = kvdb_rocksdb::DatabaseConfig::with_columns(NUM_COLUMNS);
+	db_config.create_if_missing = false;
+	db_config.secondary = Some(secondary_path.path().to_path_buf());
+	let db = kvdb_rocksdb::Database::open(&db_config, &path)
+		.map_err(|e| format!("failed to open {}: {e}", path.display()))?;
+	let db: Arc<dyn polkadot_node_subsystem_util::database::Database> =
+		Arc::new(polkadot_node_subsystem_util::database::kvdb_impl::DbAdapter::new(db, &[]));
+	let config = polkadot_node_core_av_store::Config {
+		col_data: REAL_COLUMNS.col_availability_data,
+		col_meta: REAL_COLUMNS.col_availability_meta,
+		keep_finalized_for: 0,
+	};
+
+	let candidate_hash = receipt.hash();
+	polkadot_node_core_av_store::load_available_data(&db, &config, &candidate_hash)
+		.map_err(|e| format!("failed to read the availability store: {e}"))?
+		.ok_or_else(|| format!("no available data for candidate {candidate_hash:?}"))
+}
+
+/// The checks performed by candidate validation before the PVF is run.
+fn basic_checks(
+	receipt: &CandidateReceipt,
+	pvd: &PersistedValidationData,
+	pov: &PoV,
+	validation_code: &ValidationCode,
+) -> Result<(), String> {
+	let descriptor = &receipt.descriptor;
+
+	let pov_size = pov.encoded_size();
+	if pov_size > pvd.max_pov_size as usize {
+		return Err(format!("PoV size {pov_size} exceeds the maximum of {}", pvd.max_pov_size))
+	}
+	if pov.hash() != descriptor.pov_hash() {
+		return Err("PoV hash mismatch".into())
+	}
+	if validation_code.hash() != descriptor.validation_code_hash() {
+		return Err("validation code hash mismatch".into())
+	}
+	if descriptor.check_collator_signature().is_err() {
+		return Err("bad collator signature".into())
+	}
+	if pvd.hash() != descriptor.persisted_validation_data_hash() {
+		return Err("persisted validation data hash mismatch".into())
+	}
+
+	Ok(())
+}
+
+fn classify_preparation(err: PrepareError) -> Outcome {
+	if err.is_deterministic() {
+		Outcome::Invalid(err.to_string())
+	} else {
+		Outcome::Inconclusive(err.to_string())
+	}
+}
+
+fn classify_execution(
+	receipt: &CandidateReceipt,
+	result: Result<ValidationResult, ValidationError>,
+) -> Outcome {
+	let res = match result {
+		Ok(res) => res,
+		Err(ValidationError::Invalid(WasmInvalidCandidate::HardTimeout)) =>
+			return Outcome::Invalid("execution timeout".into()),
+		Err(ValidationError::Invalid(WasmInvalidCandidate::WorkerReportedInvalid(e))) =>
+			return Outcome::Invalid(format!("execution error: {e}")),
+		Err(ValidationError::Invalid(WasmInvalidCandidate::PoVDecompressionFailure)) =>
+			return Outcome::Invalid("PoV decompression failure".into()),
+		Err(ValidationError::PossiblyInvalid(PossiblyInvalidError::AmbiguousWorkerDeath)) =>
+			return Outcome::PossiblyInvalid("ambiguous worker death".into()),
+		Err(ValidationError::PossiblyInvalid(PossiblyInvalidError::AmbiguousJobDeath(e))) =>
+			return Outcome::PossiblyInvalid(format!("ambiguous job death: {e}")),
+		Err(ValidationError::PossiblyInvalid(PossiblyInvalidError::JobError(e))) =>
+			return Outcome::PossiblyInvalid(format!("job error: {e}")),
+		Err(ValidationError::PossiblyInvalid(PossiblyInvalidError::RuntimeConstruction(e))) =>
+			return Outcome::PossiblyInvalid(format!("runtime construction: {e}")),
+		Err(ValidationError::Preparation(e)) => return classify_preparation(e),
+		Err(ValidationError::Internal(e)) => return Outcome::Inconclusive(e.to_string()),
+		Err(ValidationError::ExecutionDeadline) =>
+			return Outcome::Inconclusive("execution deadline reached".into()),
+	};
+
+	println!("head data size: {} bytes", res.head_data.0.len());
+	println!("upward messages: {}", res.upward_messages.len());
+	println!("horizontal messages: {}", res.horizontal_messages.len());
+	println!("processed downward messages: {}", res.processed_downward_messages);
+	println!("hrmp watermark: {}", res.hrmp_watermark);
+	println!("new validation code: {}", res.new_validation_code.is_some());
+
+	if res.head_data.hash() != receipt.descriptor.para_head() {
+		return Outcome::Invalid("para head hash mismatch".into())
+	}
+
+	let commitments = CandidateCommitments {
+		head_data: res.head_data,
+		upward_messages: res.upward_messages,
+		horizontal_messages: res.horizontal_messages,
+		new_validation_code: res.new_validation_code,
+		processed_downward_messages: res.processed_downward_messages,
+		hrmp_watermark: res.hrmp_watermark,
+	};
+	if commitments.hash() != receipt.commitments_hash {
+		return Outcome::Invalid("commitments hash mismatch".into())
+	}
+
+	Outcome::Valid
+}
+
+async fn replay(opt: Opt) -> Result<Outcome, String> {
+	let receipt: CandidateReceipt = decode_file(&opt.candidate_receipt)?;
+	let validation_code = ValidationCode(read_file(&opt.validation_code)?);
+	let executor_params = match opt.executor_params {
+		Some(ref path) => decode_file(path)?,
+		None => ExecutorParams::default(),
+	};
+	let (pvd, pov) = match opt.av_store {
+		Some(ref base_path) => {
+			let available_data = load_from_av_store(base_path, opt.chain_id.as_deref(), &receipt)?;
+			(available_data.validation_data, available_data.pov)
+		},
+		None => {
+			let pvd = decode_file(opt.validation_data.as_ref().expect("required by clap; qed"))?;
+			let pov: PoV = decode_file(opt.pov.as_ref().expect("required by clap; qed"))?;
+			(pvd, Arc::new(pov))
+		},
+	};
+
+	println!("candidate: {:?}", receipt.hash());
+	println!("para: {}", receipt.descriptor.para_id());
+	println!("relay parent: {:?}", receipt.descriptor.relay_parent());
+	println!("validation code size: {} bytes", validation_code.0.len());
+	println!("PoV size: {} bytes", pov.encoded_size());
+	println!("executor params: {:?}", executor_params);
+
+	if let Err(reason) = basic_checks(&receipt, &pvd, &pov, &validation_code) {
+		return Ok(Outcome::Invalid(reason))
+	}
+
+	let workers_path = match opt.workers_path {
+		Some(path) => path,
+		None => std::env::current_exe()
+			.map_err(|e| format!("failed to locate the current executable: {e}"))?
+			.parent()
+			.map(Path::to_path_buf)
+			.unwrap_or_default(),
+	};
+	let temp_cache;
+	let cache_path = match opt.cache_path {
+		Some(path) => path,
+		None => {
+			temp_cache = tempfile::tempdir()
+				.map_err(|e| format!("failed to create the cache directory: {e}"))?;
+			temp_cache.path().to_path_buf()
+		},
+	};
+
+	let config = polkadot_node_core_pvf::Config::new(
+		cache_path,
+		Some(NODE_VERSION.to_string()),
+		opt.secure_validator_mode,
+		workers_path.join(PREPARE_BINARY_NAME),
+		workers_path.join(EXECUTE_BINARY_NAME),
+		1,
+		1,
+		1,
+	);
+	// The preparation and execution times are read from the metrics of the validation host.
+	let registry = prometheus::Registry::new();
+	let metrics = Metrics::try_register(&registry)
+		.map_err(|e| format!("failed to register the metrics: {e}"))?;
+	let (mut host, task) = polkadot_node_core_pvf::start(config, metrics)
+		.await
+		.map_err(|e| format!("failed to start the validation host: {e}"))?;
+	let task = tokio::spawn(task);
+	// Starting the host runs security checks in child processes, which are not part of the replay.
+	let usage_before = children_usage()?;
+
+	let prep_timeout = executor_params
+		.pvf_prep_timeout(PvfPrepKind::Prepare)
+		.unwrap_or(DEFAULT_LENIENT_PREPARATION_TIMEOUT);
+	let (runtime_exec_kind, exec_kind, default_exec_timeout) = match opt.exec_kind {
+		ExecKind::Backing => (
+			RuntimePvfExecKind::Backing,
+			PvfExecKind::Backing(receipt.descriptor.relay_parent()),
+			DEFAULT_BACKING_EXECUTION_TIMEOUT,
+		),
+		ExecKind::Approval => (
+			RuntimePvfExecKind::Approval,
+			PvfExecKind::Approval,
+			DEFAULT_APPROVAL_EXECUTION_TIMEOUT,
+		),
+		ExecKind::Dispute =>
+			(RuntimePvfExecKind::Approval, PvfExecKind::Dispute, DEFAULT_APPROVAL_EXECUTION_TIMEOUT),
+	};
+	let exec_timeout = executor_params
+		.pvf_exec_timeout(runtime_exec_kind)
+		.unwrap_or(default_exec_timeout);
+
+	// The artifact is prepared by the execution request, as it would be on a validator.
+	let pvf = PvfPrepData::from_code(
+		validation_code.0,
+		executor_params,
+		prep_timeout,
+		PrepareJobKind::Compilation,
+		opt.validation_code_bomb_limit,
+	);
+
+	let result = async {
+		let (tx, rx) = futures::channel::oneshot::channel();
+		host.execute_pvf(
+			pvf,
+			exec_timeout,
+			Arc::new(pvd),
+			pov,
+			Priority::from(exec_kind),
+			exec_kind,
+			tx,
+		)
+		.await?;
+		rx.await.map_err(|_| "the validation host dropped the request".to_string())
+	}
+	.await?;
+
+	if let Some(elapsed) = observed_time(&registry, "polkadot_pvf_preparation_time") {
+		print_duration("preparation", elapsed, prep_timeout);
+	}
+	if let Some(elapsed) = observed_time(&registry, "polkadot_pvf_execution_time") {
+		print_duration("execution", elapsed, exec_timeout);
+	}
+	let outcome = classify_execution(&receipt, result);
+
+	// Shut the validation host down. Its workers are killed once the host task finishes and only
+	// count towards the resource usage of this process after they were waited for.
+	drop(host);
+	let _ = task.await;
+	while nix::sys::wait::wait().is_ok() {}
+	print_resources(&usage_before, &children_usage()?);
+
+	Ok(outcome)
+}
+
+/// Get the resource usage of all terminated child processes of this process.
+fn children_usage() -> Result<Usage, String> {
+	getrusage(UsageWho::RUSAGE_CHILDREN)
+		.map_err(|e| format!("failed to get the resource usage: {e}"))
+}
+
+/// Print the resources used by the workers between `before` and `after`.
+///
+/// The peak memory is the maximum resident set size of the largest worker process.
+fn print_resources(before: &Usage, after: &Usage) {
+	let cpu_time = get_total_cpu_usage(*after).saturating_sub(get_total_cpu_usage(*before));
+	println!("resources used:");
+	println!("  cpu time: {} ms", cpu_time.as_millis());
+	println!("  peak memory: {} KiB", after.max_rss());
+}
+
+/// The total time observed by the histogram `name` of the validation host metrics, if any.
+fn observed_time(registry: &prometheus::Registry, name: &str) -> Option<Duration> {
+	let families = registry.gather();
+	let histogram = families
+		.iter()
+		.find(|family| family.get_name() == name)?
+		.get_metric()
+		.first()?
+		.get_histogram();
+	(histogram.get_sample_count() > 0).then(|| Duration::from_secs_f64(histogram.get_sample_sum()))
+}
+
+fn print_duration(stage: &str, elapsed: Duration, timeout: Duration) {
+	println!("{stage} time: {} ms (timeout {} ms)", elapsed.as_millis(), timeout.as_millis());
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+	match replay(Opt::parse()).await {
+		Ok(outcome) => {
+			println!("outcome: {outcome}");
+			outcome.exit_code()
+		},
+		Err(e) => {
+			eprintln!("error: {e}");
+			ExitCode::FAILURE
+		},
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use polkadot_node_core_pvf::InternalValidationError;
+	use polkadot_parachain_primitives::primitives::HeadData;
+	use polkadot_primitives::{CoreIndex, Id as ParaId};
+	use polkadot_primitives_test_helpers::{
+		dummy_hash, dummy_pvd, dummy_validation_code, make_valid_candidate_descriptor_v2,
+	};
+
+	struct TestCandidate {
+		receipt: CandidateReceipt,
+		pvd: PersistedValidationData,
+		pov: PoV,
+		validation_code: ValidationCode,
+		result: ValidationResult,
+	}
+
+	fn test_candidate() -> TestCandidate {
+		let pvd = dummy_pvd(HeadData(vec![1, 2, 3]), 42);
+		let pov = PoV { block_data: vec![4, 5, 6].into() };
+		let validation_code = dummy_validation_code();
+		let result = ValidationResult {
+			head_data: HeadData(vec![7, 8, 9]),
+			new_validation_code: None,
+			upward_messages: Default::default(),
+			horizontal_messages: Default::default(),
+			processed_downward_messages: 0,
+			hrmp_watermark: 42,
+		};
+		let commitments = CandidateCommitments {
+			head_data: result.head_data.clone(),
+			upward_messages: Default::default(),
+			horizontal_messages: Default::default(),
+			new_validation_code: None,
+			processed_downward_messages: 0,
+			hrmp_watermark: 42,
+		};
+		let descriptor = make_valid_candidate_descriptor_v2(
+			ParaId::from(1_u32),
+			dummy_hash(),
+			CoreIndex(0),
+			1,
+			pvd.hash(),
+			pov.hash(),
+			validation_code.hash(),
+			result.head_data.hash(),
+			dummy_hash(),
+		);
+		let receipt = CandidateReceipt { descriptor, commitments_hash: commitments.hash() };
+
+		TestCandidate { receipt, pvd, pov, validation_code, result }
+	}
+
+	#[test]
+	fn basic_checks_accept_matching_inputs() {
+		let TestCandidate { receipt, pvd, pov, validation_code, .. } = test_candidate();
+
+		assert_eq!(basic_checks(&receipt, &pvd, &pov, &validation_code), Ok(()));
+	}
+
+	#[test]
+	fn basic_checks_reject_mismatching_inputs() {
+		let TestCandidate { receipt, pvd, pov, validation_code, .. } = test_candidate();
+
+		let other_pov = PoV { block_data: vec![0].into() };
+		assert_eq!(
+			basic_checks(&receipt, &pvd, &other_pov, &validation_code),
+			Err("PoV hash mismatch".into()),
+		);
+
+		let other_code = ValidationCode(vec![0]);
+		assert_eq!(
+			basic_checks(&receipt, &pvd, &pov, &other_code),
+			Err("validation code hash mismatch".into()),
+		);
+
+		let other_pvd = PersistedValidationData { relay_parent_number: 43, ..pvd.clone() };
+		assert_eq!(
+			basic_checks(&receipt, &other_pvd, &pov, &validation_code),
+			Err("persisted validation data hash mismatch".into()),
+		);
+
+		let small_pvd = PersistedValidationData { max_pov_size: 1, ..pvd };
+		assert!(basic_checks(&receipt, &small_pvd, &pov, &validation_code)
+			.unwrap_err()
+			.starts_with("PoV size"));
+	}
+
+	#[test]
+	fn matching_execution_result_is_valid() {
+		let TestCandidate { receipt, result, .. } = test_candidate();
+
+		assert!(matches!(classify_execution(&receipt, Ok(result)), Outcome::Valid));
+	}
+
+	#[test]
+	fn mismatching_execution_result_is_invalid() {
+		let TestCandidate { receipt, result, .. } = test_candidate();
+
+		let other_head = ValidationResult { head_data: HeadData(vec![0]), ..result.clone() };
+		assert!(matches!(
+			classify_execution(&receipt, Ok(other_head)),
+			Outcome::Invalid(reason) if reason == "para head hash mismatch",
+		));
+
+		let other_commitments = ValidationResult { hrmp_watermark: 0, ..result };
+		assert!(matches!(
+			classify_execution(&receipt, Ok(other_commitments)),
+			Outcome::Invalid(reason) if reason == "commitments hash mismatch",
+		));
+	}
+
+	#[test]
+	fn execution_errors_are_classified() {
+		let receipt = test_candidate().receipt;
+		let classify = |err| classify_execution(&receipt, Err(err));
+
+		assert!(matches!(
+			classify(ValidationError::Invalid(WasmInvalidCandidate::HardTimeout)),
+			Outcome::Invalid(_),
+		));
+		assert!(matches!(
+			classify(ValidationError::Invalid(WasmInvalidCandidate::WorkerReportedInvalid(
+				"panic".into()
+			))),
+			Outcome::Invalid(_),
+		));
+		assert!(matches!(
+			classify(ValidationError::PossiblyInvalid(PossiblyInvalidError::AmbiguousWorkerDeath)),
+			Outcome::PossiblyInvalid(_),
+		));
+		assert!(matches!(
+			classify(ValidationError::PossiblyInvalid(PossiblyInvalidError::JobError(
+				"oom".into()
+			))),
+			Outcome::PossiblyInvalid(_),
+		));
+		assert!(matches!(
+			classify(ValidationError::Preparation(PrepareError::Prevalidation("bad".into()))),
+			Outcome::Invalid(_),
+		));
+		assert!(matches!(
+			classify(ValidationError::Preparation(PrepareError::TimedOut)),
+			Outcome::Inconclusive(_),
+		));
+		assert!(matches!(
+			classify(ValidationError::Internal(InternalValidationError::CouldNotOpenFile(
+				"artifact".into()
+			))),
+			Outcome::Inconclusive(_),
+		));
+		assert!(matches!(classify(ValidationError::ExecutionDeadline), Outcome::Inconclusive(_),));
+	}
+
+	#[test]
+	fn parachains_db_path_is_resolved_from_base_path() {
+		let base_path = tempfile::tempdir().unwrap();
+		let base_path = base_path.path();
+
+		assert_eq!(
+			parachains_db_path(base_path, Some("westend2")),
+			Ok(base_path.join("chains/westend2/db/full/parachains/db")),
+		);
+
+		// Without a chain id, the only chain in the base path is used.
+		std::fs::create_dir_all(base_path.join("chains/polkadot")).unwrap();
+		assert_eq!(
+			parachains_db_path(base_path, None),
+			Ok(base_path.join("chains/polkadot/db/full/parachains/db")),
+		);
+
+		std::fs::create_dir_all(base_path.join("chains/kusama")).unwrap();
+		assert!(parachains_db_path(base_path, None).is_err());
+	}
+}
@@ -17,6 +17,7 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use polkadot_sdk::*;
+use std::path::PathBuf;
 
 /// An overarching CLI command definition.
 #[derive(Debug, clap::Parser)]
@@ -106,6 +107,43 @@ pub enum Subcommand {
 	/// Revert the chain to a previous state.
 	Revert(sc_cli::RevertCmd),
 
+	/// Export or import a state snapshot.
+	#[command(subcommand)]
+	Snapshot(sc_cli::SnapshotCmd),
+
 	/// Db meta columns information.
 	ChainInfo(sc_cli::ChainInfoCmd),
+
+	/// Find the GRANDPA voters responsible for finalizing two conflicting blocks.
+	GrandpaEquivocations(GrandpaEquivocationsCmd),
+}
+
+/// The `grandpa-equivocations` command.
+///
+/// Prints the equivocations found as JSON, together with the queries the voters have to answer
+/// to justify their votes. The answers are read from `--answers` on the next run.
+#[derive(Debug, Clone, clap::Parser)]
+pub struct GrandpaEquivocationsCmd {
+	/// The first justification or warp sync proof, SCALE encoded, as binary or hex.
+	pub first: PathBuf,
+
+	/// The second justification or warp sync proof, SCALE encoded, as binary or hex.
+	pub second: PathBuf,
+
+	/// The id of the authority set that signed the justifications, or of the first authority set
+	/// of a warp sync proof.
+	#[arg(long)]
+	pub set_id: u64,
+
+	/// JSON file with the authorities of the set, as a list of `["<SS58 public key>", weight]`.
+	#[arg(long)]
+	pub authorities: PathBuf,
+
+	/// Directory with the answers of the queried voters.
+	///
+	/// The answer to a query is read from `<round>-prevote-<voter>` or
+	/// `<round>-precommit-<block hash>-<voter>`, with the voter's public key in hex, and is SCALE
+	/// encoded, as binary or hex. The queries without an answer are reported as unjustified.
+	#[arg(long, value_name = "DIR")]
+	pub answers: Option<PathBuf>,
 }
@@ -0,0 +1,109 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! The `grandpa-equivocations` command.
+
+use polkadot_sdk::*;
+
+use crate::GrandpaEquivocationsCmd;
+use codec::{Decode, Encode};
+use node_primitives::{Block, BlockNumber, Hash, Header};
+use sc_cli::{Error, Result};
+use sc_consensus_grandpa::{
+	accountable_safety::{
+		decode_justifications, find_offenders_in_sets, Answer, QueriedVote, Query,
+	},
+	AuthorityList,
+};
+use std::{fs, path::Path};
+
+impl GrandpaEquivocationsCmd {
+	/// Run the command.
+	pub fn run(&self) -> Result<()> {
+		let authorities: AuthorityList = serde_json::from_slice(&fs::read(&self.authorities)?)
+			.map_err(|e| Error::Input(format!("Invalid authorities: {e}")))?;
+
+		let first = decode_justifications::<Block>(&read(&self.first)?, self.set_id, &authorities)
+			.map_err(|e| Error::Input(format!("{:?}: {e}", self.first)))?;
+		let second =
+			decode_justifications::<Block>(&read(&self.second)?, self.set_id, &authorities)
+				.map_err(|e| Error::Input(format!("{:?}: {e}", self.second)))?;
+
+		let offenders = find_offenders_in_sets(&first, &second, |query| self.answer(query))
+			.map_err(|e| Error::Application(Box::new(e)))?;
+
+		let equivocations = offenders
+			.equivocations
+			.iter()
+			.map(|proof| {
+				serde_json::json!({
+					"offender": proof.offender(),
+					"setId": proof.set_id(),
+					"round": proof.round(),
+					"proof": array_bytes::bytes2hex("0x", proof.encode()),
+				})
+			})
+			.collect::<Vec<_>>();
+		let unjustified = offenders
+			.unjustified
+			.iter()
+			.map(|query| serde_json::json!({ "query": query_name(query) }))
+			.collect::<Vec<_>>();
+
+		println!(
+			"{}",
+			serde_json::json!({ "equivocations": equivocations, "unjustified": unjustified })
+		);
+		Ok(())
+	}
+
+	/// Reads the answer to `query` from the answers directory, if there is one.
+	fn answer(&self, query: &Query<Hash, BlockNumber>) -> Option<Answer<Header>> {
+		let path = self.answers.as_ref()?.join(query_name(query));
+		if !path.exists() {
+			return None
+		}
+
+		// An answer that can't be read doesn't justify the vote.
+		let answer = read(&path).ok().and_then(|bytes| Answer::decode(&mut &bytes[..]).ok());
+		if answer.is_none() {
+			eprintln!("Ignoring invalid answer {path:?}");
+		}
+		answer
+	}
+}
+
+/// The name of the file holding the answer to `query`.
+fn query_name(query: &Query<Hash, BlockNumber>) -> String {
+	let voter = array_bytes::bytes2hex("", query.voter.as_ref());
+	match query.vote {
+		QueriedVote::Prevote => format!("{}-prevote-{voter}", query.round),
+		QueriedVote::Precommit(hash, _) =>
+			format!("{}-precommit-{}-{voter}", query.round, array_bytes::bytes2hex("", hash)),
+	}
+}
+
+/// Reads a SCALE encoded file, given as binary or hex.
+fn read(path: &Path) -> Result<Vec<u8>> {
+	let content = fs::read(path)?;
+	match std::str::from_utf8(&content).map(str::trim) {
+		Ok(hex) if hex.starts_with("0x") => array_bytes::hex2bytes(hex)
+			.map_err(|e| Error::Input(format!("{path:?}: invalid hex {e:?}"))),
+		_ => Ok(content),
+	}
+}
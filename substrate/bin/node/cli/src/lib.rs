@@ -37,6 +37,8 @@ pub mod chain_spec;
 mod cli;
 #[cfg(feature = "cli")]
 mod command;
+#[cfg(feature = "cli")]
+mod grandpa_equivocations;
 pub mod service;
 
 #[cfg(feature = "cli")]
@@ -516,6 +516,13 @@ pub fn new_full_base<N: NetworkBackend<Block, <Block as BlockT>::Hash>>(
 		import_setup.1.shared_authority_set().clone(),
 		Vec::default(),
 	));
+	let header_verifier = Arc::new((
+		sc_consensus_babe::BabeHeaderVerifier::new(client.clone(), &import_setup.2),
+		grandpa::GrandpaHeaderVerifier::new(
+			client.clone(),
+			import_setup.1.shared_authority_set().clone(),
+		),
+	));
 
 	let (network, system_rpc_tx, tx_handler_controller, sync_service) =
 		sc_service::build_network(sc_service::BuildNetworkParams {
@@ -527,6 +534,7 @@ pub fn new_full_base<N: NetworkBackend<Block, <Block as BlockT>::Hash>>(
 			import_queue,
 			block_announce_validator_builder: None,
 			warp_sync_config: Some(WarpSyncConfig::WithProvider(warp_sync)),
+			header_verifier: Some(header_verifier),
 			block_relay: None,
 			metrics,
 		})?;
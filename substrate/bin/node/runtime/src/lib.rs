@@ -3484,15 +3484,12 @@ impl_runtime_apis! {
 			block: Block,
 			tracer_type: pallet_revive::evm::TracerType,
 		) -> Vec<(u32, pallet_revive::evm::Trace)> {
-			use pallet_revive::tracing::trace;
 			let mut tracer = Revive::evm_tracer(tracer_type);
 			let mut traces = vec![];
 			let (header, extrinsics) = block.deconstruct();
 			Executive::initialize_block(&header);
 			for (index, ext) in extrinsics.into_iter().enumerate() {
-				trace(tracer.as_tracing(), || {
-					let _ = Executive::apply_extrinsic(ext);
-				});
+				let _ = Revive::trace_extrinsic(tracer.as_tracing(), ext, Executive::apply_extrinsic);
 
 				if let Some(tx_trace) = tracer.collect_trace() {
 					traces.push((index as u32, tx_trace));
@@ -3507,16 +3504,13 @@ impl_runtime_apis! {
 			tx_index: u32,
 			tracer_type: pallet_revive::evm::TracerType,
 		) -> Option<pallet_revive::evm::Trace> {
-			use pallet_revive::tracing::trace;
 			let mut tracer = Revive::evm_tracer(tracer_type);
 			let (header, extrinsics) = block.deconstruct();
 
 			Executive::initialize_block(&header);
 			for (index, ext) in extrinsics.into_iter().enumerate() {
 				if index as u32 == tx_index {
-				trace(tracer.as_tracing(), || {
-						let _ = Executive::apply_extrinsic(ext);
-					});
+					let _ = Revive::trace_extrinsic(tracer.as_tracing(), ext, Executive::apply_extrinsic);
 					break;
 				} else {
 					let _ = Executive::apply_extrinsic(ext);
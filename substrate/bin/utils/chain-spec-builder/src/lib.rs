@@ -20,12 +20,18 @@
 docify::compile_markdown!("README.docify.md", "README.md");
 
 use clap::{Parser, Subcommand};
+use remote_externalities::{Builder, Mode, OfflineConfig, SnapshotConfig};
 use sc_chain_spec::{
 	json_patch, set_code_substitute_in_json_chain_spec, update_code_in_json_chain_spec, ChainType,
-	GenericChainSpec, GenesisConfigBuilderRuntimeCaller,
+	GenericChainSpec, GenesisConfigBuilderRuntimeCaller, NoExtension,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use sp_core::{
+	crypto::{AccountId32, Ss58Codec},
+	storage::{well_known_keys::CODE, Storage},
+};
+use sp_runtime::{generic, traits::BlakeTwo256, OpaqueExtrinsic};
 use std::{
 	borrow::Cow,
 	fs,
@@ -53,6 +59,7 @@ pub enum ChainSpecBuilderCmd {
 	ListPresets(ListPresetsCmd),
 	DisplayPreset(DisplayPresetCmd),
 	AddCodeSubstitute(AddCodeSubstituteCmd),
+	Fork(ForkCmd),
 }
 
 /// Create a new chain spec by interacting with the provided runtime wasm blob.
@@ -175,6 +182,67 @@ pub struct AddCodeSubstituteCmd {
 	pub block_height: u64,
 }
 
+/// Creates a raw chain spec whose genesis is the state of a live chain at some block.
+///
+/// The state is read from a snapshot file created with `frame-remote-externalities`, e.g. by
+/// `try-runtime create-snapshot`. The resulting chain spec can be used to run a local development
+/// node on top of the forked state, e.g. `polkadot-omni-node --chain fork.json --dev`, which
+/// authors blocks with manual seal and accepts any extrinsic valid against the forked state.
+///
+/// Storage items tying the state to the consensus of the live chain (the timestamp, the aura slot
+/// and the relay chain progress of parachains) are removed, so that blocks can be authored locally.
+#[derive(Parser, Debug, Clone)]
+pub struct ForkCmd {
+	/// The path to the state snapshot file.
+	pub snapshot: PathBuf,
+	/// The name of chain.
+	#[arg(long, short = 'n', default_value = "Fork")]
+	pub chain_name: String,
+	/// The chain id.
+	#[arg(long, short = 'i', default_value = "fork")]
+	pub chain_id: String,
+	/// The chain type.
+	#[arg(value_enum, short = 't', default_value = "development")]
+	pub chain_type: ChainType,
+	/// The para ID of the forked chain.
+	#[arg(long, value_enum, short = 'p', requires = "relay_chain")]
+	pub para_id: Option<u32>,
+	/// The relay chain of the forked chain.
+	#[arg(long, value_enum, short = 'c', requires = "para_id")]
+	pub relay_chain: Option<String>,
+	/// The path to a runtime wasm blob replacing the code of the forked chain.
+	#[arg(long, short, alias = "runtime-wasm-path")]
+	pub runtime: Option<PathBuf>,
+	/// The account that becomes the key of `pallet-sudo`, as SS58 address or hex encoded bytes.
+	#[arg(long)]
+	pub sudo: Option<String>,
+	/// Raw storage overrides in `KEY=VALUE` format, with hex encoded key and value.
+	///
+	/// An empty value removes the key from the state. Multiple overrides can be specified by
+	/// passing the argument multiple times.
+	///
+	/// Example: `--storage 0x3a686561707061676573=0x0800000000000000`
+	#[arg(long)]
+	pub storage: Vec<String>,
+	/// The path to the chain spec of the forked chain.
+	///
+	/// Its properties (e.g. the token symbol and decimals) are kept by the fork, unless
+	/// `--properties` is given.
+	#[arg(long)]
+	pub chain_spec: Option<PathBuf>,
+	/// Chain properties in `KEY=VALUE` format, see `create --properties`.
+	///
+	/// Replaces the properties of the forked chain given by `--chain-spec`. The snapshot does not
+	/// contain the properties, so without either argument the fork has none.
+	#[arg(long)]
+	pub properties: Vec<String>,
+	/// The path to a json patch merged into the resulting chain spec.
+	///
+	/// Can be used to adjust any field, including the `genesis.raw.top` storage entries.
+	#[arg(long)]
+	pub patch: Option<PathBuf>,
+}
+
 /// Converts the given chain spec into the raw format.
 #[derive(Parser, Debug, Clone)]
 pub struct ConvertToRawCmd {
@@ -232,6 +300,10 @@ impl ChainSpecBuilder {
 				let chain_spec_json = generate_chain_spec_for_runtime(&cmd)?;
 				fs::write(chain_spec_path, chain_spec_json).map_err(|err| err.to_string())?;
 			},
+			ChainSpecBuilderCmd::Fork(cmd) => {
+				let chain_spec_json = generate_chain_spec_for_snapshot(&cmd)?;
+				fs::write(chain_spec_path, chain_spec_json).map_err(|err| err.to_string())?;
+			},
 			ChainSpecBuilderCmd::UpdateCode(UpdateCodeCmd {
 				ref input_chain_spec,
 				ref runtime,
@@ -459,6 +531,146 @@ pub fn generate_chain_spec_for_runtime(cmd: &CreateCmd) -> Result<String, String
 	}
 }
 
+/// Storage values tying a forked state to the consensus of the live chain, as pallet and storage
+/// item names.
+const LIVE_CONSENSUS_STORAGE: &[(&str, &str)] = &[
+	("Timestamp", "Now"),
+	("Aura", "CurrentSlot"),
+	("AuraExt", "SlotInfo"),
+	("ParachainSystem", "LastRelayChainBlockNumber"),
+	("ParachainSystem", "UnincludedSegment"),
+	("ParachainSystem", "AggregatedUnincludedSegment"),
+];
+
+/// Returns the storage key of a FRAME storage value.
+fn storage_value_key(pallet: &str, item: &str) -> Vec<u8> {
+	[sp_crypto_hashing::twox_128(pallet.as_bytes()), sp_crypto_hashing::twox_128(item.as_bytes())]
+		.concat()
+}
+
+/// Parses an account given as SS58 address or hex encoded bytes.
+fn parse_account(raw: &str) -> Result<Vec<u8>, String> {
+	match AccountId32::from_ss58check(raw) {
+		Ok(account) => Ok(AsRef::<[u8]>::as_ref(&account).to_vec()),
+		Err(_) => sp_core::bytes::from_hex(raw)
+			.map_err(|e| format!("Invalid account {raw}, expected SS58 address or hex: {e}")),
+	}
+}
+
+/// Parses a raw storage override passed as hex encoded KEY=VALUE pair.
+fn parse_storage_override(raw: &str) -> Result<(Vec<u8>, Option<Vec<u8>>), String> {
+	let (key, value) =
+		raw.split_once('=').ok_or_else(|| format!("Invalid storage override: {raw}"))?;
+	let key = sp_core::bytes::from_hex(key.trim())
+		.map_err(|e| format!("Invalid storage override key {key}: {e}"))?;
+	let value = match value.trim() {
+		"" => None,
+		value => Some(
+			sp_core::bytes::from_hex(value)
+				.map_err(|e| format!("Invalid storage override value {value}: {e}"))?,
+		),
+	};
+	Ok((key, value))
+}
+
+/// Loads all key-values from a `frame-remote-externalities` state snapshot.
+fn load_snapshot_storage(snapshot: &Path) -> Result<Storage, String> {
+	// The block type only matters for decoding the header stored in the snapshot.
+	type Block = generic::Block<generic::Header<u32, BlakeTwo256>, OpaqueExtrinsic>;
+
+	let runtime = tokio::runtime::Builder::new_current_thread()
+		.build()
+		.map_err(|e| format!("async runtime could not be created: {e}"))?;
+	let mut ext = runtime
+		.block_on(
+			Builder::<Block>::new()
+				.mode(Mode::Offline(OfflineConfig {
+					state_snapshot: SnapshotConfig::new(snapshot),
+				}))
+				.build(),
+		)
+		.map_err(|e| format!("snapshot file {snapshot:?} could not be loaded: {e}"))?;
+	Ok(ext.to_storage())
+}
+
+/// Processes `ForkCmd` and returns string representation of JSON version of the raw `ChainSpec`.
+pub fn generate_chain_spec_for_snapshot(cmd: &ForkCmd) -> Result<String, String> {
+	let mut storage = load_snapshot_storage(cmd.snapshot.as_path())?;
+
+	if let Some(ref runtime) = cmd.runtime {
+		let code =
+			fs::read(runtime.as_path()).map_err(|e| format!("wasm blob shall be readable {e}"))?;
+		storage.top.insert(CODE.to_vec(), code);
+	}
+	for (pallet, item) in LIVE_CONSENSUS_STORAGE {
+		storage.top.remove(&storage_value_key(pallet, item));
+	}
+	if let Some(ref sudo) = cmd.sudo {
+		storage.top.insert(storage_value_key("Sudo", "Key"), parse_account(sudo)?);
+	}
+	for raw in &cmd.storage {
+		match parse_storage_override(raw)? {
+			(key, Some(value)) => storage.top.insert(key, value),
+			(key, None) => storage.top.remove(&key),
+		};
+	}
+
+	let code = storage.top.get(CODE).cloned().ok_or_else(|| {
+		"The snapshot does not contain the runtime code, please provide it with `--runtime`"
+			.to_string()
+	})?;
+
+	let properties = if !cmd.properties.is_empty() {
+		let mut properties = sc_chain_spec::Properties::new();
+		for raw in &cmd.properties {
+			parse_properties(raw, &mut properties)?;
+		}
+		Some(properties)
+	} else if let Some(ref chain_spec) = cmd.chain_spec {
+		match extract_chain_spec_json(chain_spec.as_path())?["properties"].take() {
+			Value::Object(properties) => Some(properties),
+			Value::Null => None,
+			_ => return Err(format!("chain spec {chain_spec:?} has invalid properties")),
+		}
+	} else {
+		None
+	};
+
+	let mut builder = GenericChainSpec::<NoExtension>::builder(&code[..], None)
+		.with_name(&cmd.chain_name[..])
+		.with_id(&cmd.chain_id[..])
+		.with_chain_type(cmd.chain_type.clone());
+	if let Some(properties) = properties {
+		builder = builder.with_properties(properties);
+	}
+	let mut chain_spec = builder.build();
+	sc_chain_spec::ChainSpec::set_storage(&mut chain_spec, storage);
+
+	let mut chain_spec_json = serde_json::from_str::<Value>(&chain_spec.as_json(true)?)
+		.map_err(|e| format!("Conversion to json failed: {e}"))?;
+
+	if let (Some(para_id), Some(ref relay_chain)) = (cmd.para_id, &cmd.relay_chain) {
+		let parachain_properties = serde_json::json!({
+			"relay_chain": relay_chain,
+			"para_id": para_id,
+		});
+		json_patch::merge(&mut chain_spec_json, parachain_properties);
+	}
+
+	if let Some(ref patch_path) = cmd.patch {
+		let patch = fs::read(patch_path.as_path())
+			.map_err(|e| format!("patch file {patch_path:?} shall be readable: {e}"))?;
+		json_patch::merge(
+			&mut chain_spec_json,
+			serde_json::from_slice::<Value>(&patch[..]).map_err(|e| {
+				format!("patch file {patch_path:?} shall contain a valid json: {e}")
+			})?,
+		);
+	}
+
+	serde_json::to_string_pretty(&chain_spec_json).map_err(|e| format!("to pretty failed: {e}"))
+}
+
 /// Extract any chain spec and convert it to JSON
 fn extract_chain_spec_json(input_chain_spec: &Path) -> Result<serde_json::Value, String> {
 	let chain_spec = &fs::read(input_chain_spec)
@@ -22,7 +22,7 @@ use clap::Parser;
 
 use cmd_lib::spawn_with_output;
 use sc_chain_spec::update_code_in_json_chain_spec;
-use serde_json::{from_reader, from_str, Value};
+use serde_json::{from_reader, from_str, json, Value};
 use staging_chain_spec_builder::ChainSpecBuilder;
 
 // note: the runtime path will not be read, runtime code will be set directly, to avoid hassle with
@@ -257,6 +257,84 @@ fn test_create_with_properties() {
 	assert_output_eq_expected(true, SUFFIX, "tests/expected/create_with_properties.json");
 }
 
+#[test]
+fn test_fork() {
+	const SUFFIX: &str = "12";
+	const SUDO_KEY: &str = "0x5c0d1176a568c1f92944340dbfed9e9c530ebca703c85910e7164cb7d1c9e47b";
+	const TIMESTAMP_NOW: &str =
+		"0xf0c365c3cf59d671eb72da0e7a4113c49f1f0515f462cdcf84e0f1d6045dfcbb";
+	let builder = get_builder(
+		SUFFIX,
+		vec![
+			"fork",
+			"../../../utils/frame/remote-externalities/test_data/test.snap",
+			"--runtime",
+			"tests/input/code_040506.blob",
+			"--sudo",
+			"5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY",
+			"--storage",
+			"0x1234=0x5678",
+			"--para-id",
+			"1000",
+			"--relay-chain",
+			"rococo-local",
+		],
+	);
+	builder.run().unwrap();
+
+	let path = OUTPUT_FILE.to_string() + SUFFIX;
+	let output: Value = from_reader(File::open(path.clone()).unwrap()).unwrap();
+	std::fs::remove_file(path).expect("Failed to delete file");
+
+	assert_eq!(output["name"], "Fork");
+	assert_eq!(output["chainType"], "Development");
+	assert_eq!(output["para_id"], 1000);
+	let top = &output["genesis"]["raw"]["top"];
+	assert_eq!(top["0x3a636f6465"], "0x040506");
+	assert_eq!(top[SUDO_KEY], "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d");
+	assert_eq!(top["0x1234"], "0x5678");
+	assert!(top.get(TIMESTAMP_NOW).is_none());
+	// the rest of the state is taken from the snapshot.
+	assert!(top.as_object().unwrap().len() > 3);
+	assert!(output["properties"].is_null());
+}
+
+#[test]
+fn test_fork_properties() {
+	const SUFFIX: &str = "13";
+	let fork = |args: &[&'static str]| {
+		let mut cmd = vec![
+			"fork",
+			"../../../utils/frame/remote-externalities/test_data/test.snap",
+			"--runtime",
+			"tests/input/code_040506.blob",
+		];
+		cmd.extend_from_slice(args);
+		get_builder(SUFFIX, cmd).run().unwrap();
+
+		let path = OUTPUT_FILE.to_string() + SUFFIX;
+		let output: Value = from_reader(File::open(path.clone()).unwrap()).unwrap();
+		std::fs::remove_file(path).expect("Failed to delete file");
+		output["properties"].clone()
+	};
+
+	// the properties of the forked chain are kept
+	assert_eq!(
+		fork(&["--chain-spec", "tests/input/chain_spec_plain.json"]),
+		json!({"tokenDecimals": 12, "tokenSymbol": "UNIT"})
+	);
+	// unless they are replaced
+	assert_eq!(
+		fork(&[
+			"--chain-spec",
+			"tests/input/chain_spec_plain.json",
+			"--properties",
+			"tokenSymbol=FORK"
+		]),
+		json!({"tokenSymbol": "FORK"})
+	);
+}
+
 #[docify::export_content]
 fn cmd_create_default(runtime_path: &str) -> String {
 	bash!(
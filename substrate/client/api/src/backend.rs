@@ -623,6 +623,25 @@ pub trait Backend<Block: BlockT>: AuxStore + Send + Sync {
 		trie_cache_context: TrieCacheContext,
 	) -> sp_blockchain::Result<Self::State>;
 
+	/// Returns number and hash of the closest block at or below `number` whose state is kept as a
+	/// snapshot after the state of surrounding blocks was pruned.
+	fn state_snapshot(&self, _number: NumberFor<Block>) -> Option<(NumberFor<Block>, Block::Hash)> {
+		None
+	}
+
+	/// Returns state backend with the post-state of `base` and the trie nodes of `changes` on
+	/// top of it, rooted at `root`.
+	///
+	/// Used to rebuild pruned state by re-executing blocks on top of a state snapshot.
+	fn state_with_changes(
+		&self,
+		_base: Block::Hash,
+		_changes: BackendTransaction<HashingFor<Block>>,
+		_root: Block::Hash,
+	) -> sp_blockchain::Result<Self::State> {
+		Err(sp_blockchain::Error::Backend("State snapshots are not supported".into()))
+	}
+
 	/// Attempts to revert the chain by `n` blocks. If `revert_finalized` is set it will attempt to
 	/// revert past any finalized block, this is unsafe and can potentially leave the node in an
 	/// inconsistent state. All blocks higher than the best block are also reverted and not counting
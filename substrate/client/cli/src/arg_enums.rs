@@ -291,6 +291,10 @@ pub enum SyncMode {
 	FastUnsafe,
 	/// Prove finality and download the latest state.
 	Warp,
+	/// Full sync. Download headers first and block bodies from multiple peers in parallel.
+	///
+	/// Only available on nodes that configure a verifier for the downloaded headers.
+	HeaderFirst,
 }
 
 impl Into<sc_network::config::SyncMode> for SyncMode {
@@ -306,6 +310,7 @@ impl Into<sc_network::config::SyncMode> for SyncMode {
 				storage_chain_mode: false,
 			},
 			SyncMode::Warp => sc_network::config::SyncMode::Warp,
+			SyncMode::HeaderFirst => sc_network::config::SyncMode::HeaderFirst,
 		}
 	}
 }
@@ -0,0 +1,185 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use crate::{
+	error,
+	params::{BlockNumberOrHash, DatabaseParams, ImportParams, PruningParams, SharedParams},
+	CliConfiguration,
+};
+use clap::Parser;
+use sc_client_api::{BlockBackend, HeaderBackend, ProofProvider, UsageProvider};
+use sc_service::chain_ops::{export_snapshot, import_snapshot};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+use std::{
+	fmt::Debug,
+	fs,
+	io::{self, BufReader, BufWriter, Read, Write},
+	path::PathBuf,
+	str::FromStr,
+	sync::Arc,
+};
+
+/// State snapshot utilities.
+#[derive(Debug, clap::Subcommand)]
+pub enum SnapshotCmd {
+	/// Export the state of a finalized block, along with its header and justifications, into a
+	/// snapshot file.
+	Export(ExportSnapshotCmd),
+
+	/// Bootstrap the node from a snapshot file, without any network access.
+	Import(ImportSnapshotCmd),
+}
+
+/// The `snapshot export` command used to export a state snapshot.
+#[derive(Debug, Clone, Parser)]
+pub struct ExportSnapshotCmd {
+	/// Output file name or stdout if unspecified.
+	#[arg()]
+	pub output: Option<PathBuf>,
+
+	/// Block hash or number of the snapshot, the last finalized block if unspecified.
+	///
+	/// The block has to be finalized.
+	#[arg(long, value_name = "HASH or NUMBER")]
+	pub at: Option<BlockNumberOrHash>,
+
+	/// Approximate size in bytes of the state chunks.
+	#[arg(long, value_name = "BYTES", default_value_t = 2 * 1024 * 1024)]
+	pub chunk_size: usize,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub pruning_params: PruningParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub database_params: DatabaseParams,
+}
+
+/// The `snapshot import` command used to import a state snapshot.
+#[derive(Debug, Clone, Parser)]
+pub struct ImportSnapshotCmd {
+	/// Input file or stdin if unspecified.
+	#[arg()]
+	pub input: Option<PathBuf>,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub shared_params: SharedParams,
+
+	#[allow(missing_docs)]
+	#[clap(flatten)]
+	pub import_params: ImportParams,
+}
+
+impl SnapshotCmd {
+	/// Run the selected `snapshot` subcommand
+	pub async fn run<B, C, IQ>(&self, client: Arc<C>, import_queue: IQ) -> error::Result<()>
+	where
+		B: BlockT,
+		C: UsageProvider<B>
+			+ HeaderBackend<B>
+			+ BlockBackend<B>
+			+ ProofProvider<B>
+			+ Send
+			+ Sync
+			+ 'static,
+		IQ: sc_service::ImportQueue<B> + 'static,
+		<B::Hash as FromStr>::Err: Debug,
+		<<B::Header as HeaderT>::Number as FromStr>::Err: Debug,
+	{
+		match self {
+			SnapshotCmd::Export(cmd) => cmd.run(client).await,
+			SnapshotCmd::Import(cmd) => cmd.run(client, import_queue).await,
+		}
+	}
+}
+
+impl ExportSnapshotCmd {
+	/// Run the `snapshot export` command
+	pub async fn run<B, C>(&self, client: Arc<C>) -> error::Result<()>
+	where
+		B: BlockT,
+		C: UsageProvider<B> + HeaderBackend<B> + BlockBackend<B> + ProofProvider<B>,
+		<B::Hash as FromStr>::Err: Debug,
+		<<B::Header as HeaderT>::Number as FromStr>::Err: Debug,
+	{
+		let hash = match self.at.as_ref().map(|b| b.parse()).transpose()? {
+			Some(id) => client.expect_block_hash_from_id(&id)?,
+			None => client.usage_info().chain.finalized_hash,
+		};
+
+		let output: Box<dyn Write> = match &self.output {
+			Some(filename) => Box::new(BufWriter::new(fs::File::create(filename)?)),
+			None => Box::new(io::stdout()),
+		};
+
+		export_snapshot(client, hash, output, self.chunk_size).map_err(Into::into)
+	}
+}
+
+impl ImportSnapshotCmd {
+	/// Run the `snapshot import` command
+	pub async fn run<B, C, IQ>(&self, client: Arc<C>, import_queue: IQ) -> error::Result<()>
+	where
+		B: BlockT,
+		C: HeaderBackend<B> + ProofProvider<B> + Send + Sync + 'static,
+		IQ: sc_service::ImportQueue<B> + 'static,
+	{
+		let input: Box<dyn Read + Send> = match &self.input {
+			Some(filename) => Box::new(BufReader::new(fs::File::open(filename)?)),
+			None => Box::new(io::stdin()),
+		};
+
+		import_snapshot(client, import_queue, input).await.map_err(Into::into)
+	}
+}
+
+impl CliConfiguration for SnapshotCmd {
+	fn shared_params(&self) -> &SharedParams {
+		match self {
+			SnapshotCmd::Export(cmd) => &cmd.shared_params,
+			SnapshotCmd::Import(cmd) => &cmd.shared_params,
+		}
+	}
+
+	fn pruning_params(&self) -> Option<&PruningParams> {
+		match self {
+			SnapshotCmd::Export(cmd) => Some(&cmd.pruning_params),
+			SnapshotCmd::Import(cmd) => Some(&cmd.import_params.pruning_params),
+		}
+	}
+
+	fn database_params(&self) -> Option<&DatabaseParams> {
+		match self {
+			SnapshotCmd::Export(cmd) => Some(&cmd.database_params),
+			SnapshotCmd::Import(cmd) => Some(&cmd.import_params.database_params),
+		}
+	}
+
+	fn import_params(&self) -> Option<&ImportParams> {
+		match self {
+			SnapshotCmd::Export(_) => None,
+			SnapshotCmd::Import(cmd) => Some(&cmd.import_params),
+		}
+	}
+}
@@ -272,6 +272,14 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			.unwrap_or_else(|| Ok(BlocksPruning::KeepFinalized))
 	}
 
+	/// Get the maximum number of blocks re-executed to rebuild the state of a pruned block.
+	///
+	/// By default this is retrieved from `PruningParams` if it is available. Otherwise pruned
+	/// state is not rebuilt.
+	fn state_replay_max_blocks(&self) -> Result<Option<u32>> {
+		Ok(self.pruning_params().and_then(|x| x.state_replay_max_blocks))
+	}
+
 	/// Get the chain ID (string).
 	///
 	/// By default this is retrieved from `SharedParams`.
@@ -513,7 +521,7 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			impl_name: C::impl_name(),
 			impl_version: C::impl_version(),
 			tokio_handle,
-			transaction_pool: self.transaction_pool(is_dev)?,
+			transaction_pool: self.transaction_pool(is_dev)?.with_journal_dir(&config_dir),
 			network: self.network_config(
 				&chain_spec,
 				is_dev,
@@ -530,6 +538,7 @@ pub trait CliConfiguration<DCV: DefaultConfigurationValues = ()>: Sized {
 			trie_cache_maximum_size: self.trie_cache_maximum_size()?,
 			state_pruning: self.state_pruning()?,
 			blocks_pruning: self.blocks_pruning()?,
+			state_replay_max_blocks: self.state_replay_max_blocks()?,
 			executor: ExecutorConfiguration {
 				wasm_method: self.wasm_method()?,
 				default_heap_pages: self.default_heap_pages()?,
@@ -19,6 +19,7 @@
 use crate::error;
 use clap::Args;
 use sc_service::{BlocksPruning, PruningMode};
+use std::num::NonZeroU32;
 
 /// Parameters to define the pruning mode
 #[derive(Debug, Clone, Args)]
@@ -45,6 +46,24 @@ pub struct PruningParams {
 	#[arg(alias = "pruning", long, value_name = "PRUNING_MODE")]
 	pub state_pruning: Option<DatabasePruningMode>,
 
+	/// Keep a full state snapshot every `NUMBER` of finalized blocks in addition to the recent
+	/// state kept by `--state-pruning`.
+	///
+	/// The state of a pruned block can then be recovered by re-executing the blocks on top of
+	/// the nearest snapshot. Only compatible with a `NUMBER` state pruning mode and, like it, has
+	/// to be set on the first creation of the database. The interval may change between
+	/// subsequent runs and is kept when neither flag is given.
+	#[arg(long, value_name = "NUMBER")]
+	pub state_snapshot_interval: Option<NonZeroU32>,
+
+	/// Rebuild the state of pruned blocks, by re-executing up to `NUMBER` blocks on top of the
+	/// nearest state snapshot, see `--state-snapshot-interval`.
+	///
+	/// Queries at pruned blocks further away from a snapshot fail. Rebuilding is disabled by
+	/// default, as the re-execution can be requested through RPC.
+	#[arg(long, value_name = "NUMBER")]
+	pub state_replay_max_blocks: Option<u32>,
+
 	/// Specify the blocks pruning mode.
 	///
 	/// This mode specifies when the block's body (including justifications)
@@ -69,7 +88,18 @@ pub struct PruningParams {
 impl PruningParams {
 	/// Get the pruning value from the parameters
 	pub fn state_pruning(&self) -> error::Result<Option<PruningMode>> {
-		Ok(self.state_pruning.map(|v| v.into()))
+		match (self.state_pruning, self.state_snapshot_interval) {
+			(state_pruning, None) => Ok(state_pruning.map(|v| v.into())),
+			(Some(DatabasePruningMode::Custom(n)), Some(interval)) =>
+				Ok(Some(PruningMode::hybrid(n, interval))),
+			(None, Some(interval)) => Ok(Some(PruningMode::Hybrid {
+				constraints: Default::default(),
+				snapshot_interval: interval,
+			})),
+			(Some(_), Some(_)) => Err(error::Error::Input(
+				"`--state-snapshot-interval` requires a `NUMBER` state pruning mode".into(),
+			)),
+		}
 	}
 
 	/// Get the block pruning value from the parameters
@@ -162,4 +192,28 @@ mod tests {
 		assert!(matches!(dbg!(pruning.state_pruning), Some(DatabasePruningMode::ArchiveCanonical)));
 		assert!(matches!(pruning.blocks_pruning, DatabasePruningMode::ArchiveCanonical));
 	}
+
+	#[test]
+	fn state_snapshot_interval_parse_works() {
+		let interval = NonZeroU32::new(1000).unwrap();
+
+		let Cli { pruning } =
+			Cli::parse_from(["", "--state-pruning=256", "--state-snapshot-interval=1000"]);
+		assert_eq!(pruning.state_pruning().unwrap(), Some(PruningMode::hybrid(256, interval)));
+
+		let Cli { pruning } = Cli::parse_from(["", "--state-snapshot-interval=1000"]);
+		assert!(matches!(
+			pruning.state_pruning().unwrap(),
+			Some(PruningMode::Hybrid { snapshot_interval, .. }) if snapshot_interval == interval
+		));
+
+		let Cli { pruning } =
+			Cli::parse_from(["", "--state-pruning=archive", "--state-snapshot-interval=1000"]);
+		assert!(pruning.state_pruning().is_err());
+
+		assert!(Cli::try_parse_from(["", "--state-snapshot-interval=0"]).is_err());
+
+		let Cli { pruning } = Cli::parse_from(["", "--state-replay-max-blocks=100"]);
+		assert_eq!(pruning.state_replay_max_blocks, Some(100));
+	}
 }
@@ -17,7 +17,11 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use clap::{Args, ValueEnum};
-use sc_transaction_pool::TransactionPoolOptions;
+use sc_transaction_pool::{JournalOptions, TransactionPoolOptions};
+use std::path::PathBuf;
+
+/// Default path of the transaction pool journal, relative to the chain data directory.
+const DEFAULT_JOURNAL_PATH: &str = "txpool/journal";
 
 /// Type of transaction pool to be used
 #[derive(Debug, Clone, Copy, ValueEnum)]
@@ -59,17 +63,37 @@ pub struct TransactionPoolParams {
 	/// The type of transaction pool to be instantiated.
 	#[arg(long, value_enum, default_value_t = TransactionPoolType::SingleState)]
 	pub pool_type: TransactionPoolType,
+
+	/// Record the submitted transactions on disk and restore them after restart.
+	///
+	/// The restored transactions are revalidated against the best block. Only supported by the
+	/// fork-aware transaction pool.
+	#[arg(long)]
+	pub pool_journal: bool,
+
+	/// Maximum number of kilobytes of the transaction pool journal.
+	#[arg(long, value_name = "KBYTES", default_value_t = 20480)]
+	pub pool_journal_kbytes: usize,
 }
 
 impl TransactionPoolParams {
 	/// Fill the given `PoolConfiguration` by looking at the cli parameters.
 	pub fn transaction_pool(&self, is_dev: bool) -> TransactionPoolOptions {
-		TransactionPoolOptions::new_with_params(
+		let options = TransactionPoolOptions::new_with_params(
 			self.pool_limit,
 			self.pool_kbytes * 1024,
 			self.tx_ban_seconds,
 			self.pool_type.into(),
 			is_dev,
-		)
+		);
+
+		if self.pool_journal {
+			options.with_journal(JournalOptions {
+				path: PathBuf::from(DEFAULT_JOURNAL_PATH),
+				max_bytes: self.pool_journal_kbytes * 1024,
+			})
+		} else {
+			options
+		}
 	}
 }
@@ -131,6 +131,37 @@ impl<C, P, CIDP, N> AuraVerifier<C, P, CIDP, N>
 where
 	CIDP: Send,
 {
+	/// Check the slot claim of `header`, ensuring it doesn't exceed the number of blocks the
+	/// runtime allows to be built on the same slot.
+	///
+	/// Returns `true` if `header` was built on the same slot as its parent, as allowed by
+	/// [`AuraApi::max_blocks_per_slot`].
+	fn check_blocks_in_slot<B: BlockT>(&self, header: &B::Header) -> Result<bool, Error<B>>
+	where
+		C: ProvideRuntimeApi<B> + HeaderBackend<B>,
+		C::Api: AuraApi<B, AuthorityId<P>>,
+		P: Pair,
+		P::Public: Codec,
+		P::Signature: Codec,
+	{
+		let parent_hash = *header.parent_hash();
+
+		let Some(max_blocks_per_slot) = crate::standalone::max_blocks_per_slot_at::<
+			AuthorityId<P>,
+			B,
+			C,
+		>(self.client.as_ref(), parent_hash)
+		.map_err(Error::Client)?
+		else {
+			// The limit is only known to the runtime, which checks it on import.
+			return Ok(false)
+		};
+
+		let parent = self.client.expect_header(parent_hash).map_err(Error::Client)?;
+		crate::standalone::check_slot_claim::<B, P::Signature>(header, &parent, max_blocks_per_slot)
+			.map_err(Error::from)
+	}
+
 	async fn check_inherents<B: BlockT>(
 		&self,
 		block: B,
@@ -165,7 +196,7 @@ where
 #[async_trait::async_trait]
 impl<B: BlockT, C, P, CIDP> Verifier<B> for AuraVerifier<C, P, CIDP, NumberFor<B>>
 where
-	C: ProvideRuntimeApi<B> + Send + Sync + sc_client_api::backend::AuxStore,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + Send + Sync + sc_client_api::backend::AuxStore,
 	C::Api: BlockBuilderApi<B> + AuraApi<B, AuthorityId<P>> + ApiExt<B>,
 	P: Pair,
 	P::Public: Codec + Debug,
@@ -212,6 +243,15 @@ where
 
 		let slot_now = create_inherent_data_providers.slot();
 
+		// Blocks following their parent on the same slot are expected if the runtime allows
+		// multiple blocks per slot and must not be reported as equivocations.
+		let check_for_equivocation =
+			if self.check_blocks_in_slot::<B>(&block.header).map_err(|e| e.to_string())? {
+				CheckForEquivocation::No
+			} else {
+				self.check_for_equivocation
+			};
+
 		// we add one to allow for some small drift.
 		// FIXME #1019 in the future, alter this queue to allow deferring of
 		// headers
@@ -221,7 +261,7 @@ where
 			block.header,
 			hash,
 			&authorities[..],
-			self.check_for_equivocation,
+			check_for_equivocation,
 		)
 		.map_err(|e| e.to_string())?;
 		match checked_header {
@@ -24,22 +24,28 @@
 //!
 //! The author is allowed to issue one block but not more during that slot,
 //! and it will be built upon the longest valid chain that has been seen.
+//! Runtimes can allow more blocks per slot through [`AuraApi::max_blocks_per_slot`], in which
+//! case the slot is split into equally sized sub-slots with one block authored in each of them.
+//! Every block after the first one of a slot claims its index within the slot, which is checked
+//! against its parent on import.
 //!
 //! Blocks from future steps will be either deferred or rejected depending on how
 //! far in the future they are.
 //!
 //! NOTE: Aura itself is designed to be generic over the crypto used.
 #![forbid(missing_docs, unsafe_code)]
-use std::{fmt::Debug, marker::PhantomData, pin::Pin, sync::Arc};
+use std::{fmt::Debug, marker::PhantomData, pin::Pin, sync::Arc, time::Instant};
 
 use codec::Codec;
 use futures::prelude::*;
+use futures_timer::Delay;
+use log::{debug, warn};
 
 use sc_client_api::{backend::AuxStore, BlockOf};
 use sc_consensus::{BlockImport, BlockImportParams, ForkChoiceStrategy, StateAction};
 use sc_consensus_slots::{
 	BackoffAuthoringBlocksStrategy, InherentDataProviderExt, SimpleSlotWorkerToSlotWorker,
-	SlotInfo, StorageChanges,
+	SlotInfo, SlotResult, SlotWorker, StorageChanges,
 };
 use sc_telemetry::TelemetryHandle;
 use sp_api::{Core, ProvideRuntimeApi};
@@ -184,12 +190,12 @@ where
 	SO: SyncOracle + Send + Sync + Clone,
 	L: sc_consensus::JustificationSyncLink<B>,
 	CIDP: CreateInherentDataProviders<B, ()> + Send + 'static,
-	CIDP::InherentDataProviders: InherentDataProviderExt + Send,
+	CIDP::InherentDataProviders: InherentDataProviderExt + Send + 'static,
 	BS: BackoffAuthoringBlocksStrategy<NumberFor<B>> + Send + Sync + 'static,
 	Error: std::error::Error + Send + From<ConsensusError> + 'static,
 {
 	let worker = build_aura_worker::<P, _, _, _, _, _, _, _, _>(BuildAuraWorkerParams {
-		client,
+		client: client.clone(),
 		block_import,
 		proposer_factory,
 		keystore,
@@ -203,12 +209,25 @@ where
 		compatibility_mode,
 	});
 
+	let create_inherent_data_providers = Arc::new(create_inherent_data_providers);
+	let worker = MultiBlockSlotWorker {
+		worker: SimpleSlotWorkerToSlotWorker(worker),
+		client,
+		create_inherent_data_providers: create_inherent_data_providers.clone(),
+		_phantom: PhantomData::<fn() -> P>,
+	};
+
 	Ok(sc_consensus_slots::start_slot_worker(
 		slot_duration,
 		select_chain,
-		SimpleSlotWorkerToSlotWorker(worker),
+		worker,
 		sync_oracle,
-		create_inherent_data_providers,
+		move |parent: B::Hash, ()| {
+			let create_inherent_data_providers = create_inherent_data_providers.clone();
+			async move {
+				create_inherent_data_providers.create_inherent_data_providers(parent, ()).await
+			}
+		},
 	))
 }
 
@@ -250,6 +269,9 @@ pub struct BuildAuraWorkerParams<C, I, PF, SO, L, BS, N> {
 /// Build the aura worker.
 ///
 /// The caller is responsible for running this worker, otherwise it will do nothing.
+///
+/// A slot claim of the worker consists of the public key of the slot author and the index of the
+/// block within the slot, see [`standalone::index_in_slot`].
 pub fn build_aura_worker<P, B, C, PF, I, SO, L, BS, Error>(
 	BuildAuraWorkerParams {
 		client,
@@ -271,7 +293,7 @@ pub fn build_aura_worker<P, B, C, PF, I, SO, L, BS, Error>(
 	BlockImport = I,
 	SyncOracle = SO,
 	JustificationSyncLink = L,
-	Claim = P::Public,
+	Claim = (P::Public, u32),
 	AuxData = Vec<AuthorityId<P>>,
 >
 where
@@ -346,7 +368,7 @@ where
 	type CreateProposer =
 		Pin<Box<dyn Future<Output = Result<E::Proposer, ConsensusError>> + Send + 'static>>;
 	type Proposer = E::Proposer;
-	type Claim = P::Public;
+	type Claim = (P::Public, u32);
 	type AuxData = Vec<AuthorityId<P>>;
 
 	fn logging_target(&self) -> &'static str {
@@ -372,15 +394,24 @@ where
 
 	async fn claim_slot(
 		&mut self,
-		_header: &B::Header,
+		header: &B::Header,
 		slot: Slot,
 		authorities: &Self::AuxData,
 	) -> Option<Self::Claim> {
-		crate::standalone::claim_slot::<P>(slot, authorities, &self.keystore).await
+		let index = crate::standalone::index_in_slot::<B, P::Signature>(slot, header).ok()?;
+		let public = crate::standalone::claim_slot::<P>(slot, authorities, &self.keystore).await?;
+
+		Some((public, index))
 	}
 
-	fn pre_digest_data(&self, slot: Slot, _claim: &Self::Claim) -> Vec<sp_runtime::DigestItem> {
-		vec![crate::standalone::pre_digest::<P>(slot)]
+	fn pre_digest_data(&self, slot: Slot, claim: &Self::Claim) -> Vec<sp_runtime::DigestItem> {
+		let mut pre_digest = vec![crate::standalone::pre_digest::<P>(slot)];
+		// The first block of a slot doesn't need a slot claim.
+		if claim.1 > 0 {
+			pre_digest.push(crate::standalone::slot_claim::<P>(claim.1));
+		}
+
+		pre_digest
 	}
 
 	async fn block_import_params(
@@ -389,7 +420,7 @@ where
 		header_hash: &B::Hash,
 		body: Vec<B::Extrinsic>,
 		storage_changes: StorageChanges<B>,
-		public: Self::Claim,
+		(public, _): Self::Claim,
 		_authorities: Self::AuxData,
 	) -> Result<sc_consensus::BlockImportParams<B>, ConsensusError> {
 		let signature_digest_item =
@@ -457,6 +488,106 @@ where
 	}
 }
 
+/// A [`SlotWorker`] that builds up to [`AuraApi::max_blocks_per_slot`] blocks in every slot
+/// claimed by the wrapped worker.
+///
+/// The slot is split into equally sized sub-slots and each block is built on top of the block
+/// authored in the previous sub-slot.
+struct MultiBlockSlotWorker<W, C, CIDP, P> {
+	worker: W,
+	client: Arc<C>,
+	create_inherent_data_providers: Arc<CIDP>,
+	_phantom: PhantomData<fn() -> P>,
+}
+
+#[async_trait::async_trait]
+impl<B, W, C, CIDP, P, Proof> SlotWorker<B, Proof> for MultiBlockSlotWorker<W, C, CIDP, P>
+where
+	B: BlockT,
+	W: SlotWorker<B, Proof> + Send,
+	Proof: Send,
+	C: ProvideRuntimeApi<B> + Send + Sync,
+	C::Api: AuraApi<B, AuthorityId<P>>,
+	CIDP: CreateInherentDataProviders<B, ()> + 'static,
+	CIDP::InherentDataProviders: InherentDataProviderExt + Send + 'static,
+	P: Pair,
+	P::Public: Codec,
+{
+	async fn on_slot(&mut self, slot_info: SlotInfo<B>) -> Option<SlotResult<B, Proof>> {
+		let max_blocks_per_slot =
+			match crate::standalone::max_blocks_per_slot_at::<AuthorityId<P>, B, C>(
+				self.client.as_ref(),
+				slot_info.chain_head.hash(),
+			) {
+				Ok(max_blocks_per_slot) => max_blocks_per_slot.unwrap_or(1),
+				Err(err) => {
+					warn!(target: LOG_TARGET, "Unable to fetch the maximum blocks per slot: {}", err);
+					1
+				},
+			};
+
+		if max_blocks_per_slot <= 1 {
+			return self.worker.on_slot(slot_info).await
+		}
+
+		let SlotInfo { slot, ends_at, duration, block_size_limit, .. } = slot_info;
+		let sub_slot_duration = duration / max_blocks_per_slot;
+		let slot_started_at = ends_at.checked_sub(duration).unwrap_or_else(Instant::now);
+
+		let mut slot_info = slot_info;
+		let mut result = None;
+		for sub_slot in 1..=max_blocks_per_slot {
+			let sub_slot_ends_at =
+				std::cmp::min(slot_started_at + sub_slot_duration * sub_slot, ends_at);
+			slot_info.duration = sub_slot_duration;
+			slot_info.ends_at = sub_slot_ends_at;
+
+			// Stop as soon as we fail to author, e.g. because it is not our slot.
+			let Some(sub_slot_result) = self.worker.on_slot(slot_info).await else { break };
+			let chain_head = sub_slot_result.block.header().clone();
+			result = Some(sub_slot_result);
+
+			if sub_slot == max_blocks_per_slot {
+				break
+			}
+
+			Delay::new(sub_slot_ends_at.saturating_duration_since(Instant::now())).await;
+
+			let inherent_data_providers = match self
+				.create_inherent_data_providers
+				.create_inherent_data_providers(chain_head.hash(), ())
+				.await
+			{
+				Ok(inherent_data_providers) => inherent_data_providers,
+				Err(err) => {
+					warn!(
+						target: LOG_TARGET,
+						"Unable to author block in sub-slot. Failure creating inherent data provider: {}",
+						err,
+					);
+					break
+				},
+			};
+
+			if inherent_data_providers.slot() != slot {
+				debug!(target: LOG_TARGET, "Slot {slot} ended after {sub_slot} sub-slots.");
+				break
+			}
+
+			slot_info = SlotInfo {
+				slot,
+				ends_at,
+				create_inherent_data: Box::new(inherent_data_providers),
+				duration,
+				chain_head,
+				block_size_limit,
+			};
+		}
+
+		result
+	}
+}
+
 /// Aura Errors
 #[derive(Debug, thiserror::Error)]
 pub enum Error<B: BlockT> {
@@ -487,6 +618,12 @@ pub enum Error<B: BlockT> {
 	/// Inherents Error
 	#[error("Inherent error: {0}")]
 	Inherent(sp_inherents::Error),
+	/// Too many blocks built on the same slot
+	#[error("More than {1} blocks built on slot {0}")]
+	TooManyBlocksInSlot(Slot, u32),
+	/// Block claims the wrong index within its slot
+	#[error("Block claims index {1} in slot {0}, expected {2}")]
+	InvalidSlotClaim(Slot, u32, u32),
 }
 
 impl<B: BlockT> From<Error<B>> for String {
@@ -504,6 +641,18 @@ impl<B: BlockT> From<crate::standalone::PreDigestLookupError> for Error<B> {
 	}
 }
 
+impl<B: BlockT> From<crate::standalone::SlotClaimError> for Error<B> {
+	fn from(e: crate::standalone::SlotClaimError) -> Self {
+		match e {
+			crate::standalone::SlotClaimError::InvalidPreDigest(e) => Error::from(e),
+			crate::standalone::SlotClaimError::InvalidIndex { slot, claimed, expected } =>
+				Error::InvalidSlotClaim(slot, claimed, expected),
+			crate::standalone::SlotClaimError::TooManyBlocks { slot, max } =>
+				Error::TooManyBlocksInSlot(slot, max),
+		}
+	}
+}
+
 fn authorities<A, B, C>(
 	client: &C,
 	parent_hash: B::Hash,
@@ -554,6 +703,7 @@ mod tests {
 	use sc_consensus_slots::{BackoffAuthoringOnFinalizedHeadLagging, SimpleSlotWorker};
 	use sc_keystore::LocalKeystore;
 	use sc_network_test::{Block as TestBlock, *};
+	use sp_api::ApiRef;
 	use sp_application_crypto::{key_types::AURA, AppCrypto};
 	use sp_consensus::{DisableProofRecording, NoNetwork as DummyOracle, Proposal};
 	use sp_consensus_aura::sr25519::AuthorityPair;
@@ -828,6 +978,31 @@ mod tests {
 		assert!(worker.claim_slot(&head, 5.into(), &authorities).await.is_none());
 		assert!(worker.claim_slot(&head, 6.into(), &authorities).await.is_none());
 		assert!(worker.claim_slot(&head, 7.into(), &authorities).await.is_some());
+
+		// Blocks following their parent on the same slot claim the next index within the slot.
+		let head = Header::new(
+			1,
+			H256::from_low_u64_be(0),
+			H256::from_low_u64_be(0),
+			Default::default(),
+			Digest { logs: vec![standalone::pre_digest::<AuthorityPair>(3.into())] },
+		);
+		let claim = worker.claim_slot(&head, 3.into(), &authorities).await.unwrap();
+		assert_eq!(claim.1, 1);
+		assert_eq!(
+			worker.pre_digest_data(3.into(), &claim),
+			vec![
+				standalone::pre_digest::<AuthorityPair>(3.into()),
+				standalone::slot_claim::<AuthorityPair>(1),
+			],
+		);
+
+		let claim = worker.claim_slot(&head, 7.into(), &authorities).await.unwrap();
+		assert_eq!(claim.1, 0);
+		assert_eq!(
+			worker.pre_digest_data(7.into(), &claim),
+			vec![standalone::pre_digest::<AuthorityPair>(7.into())],
+		);
 	}
 
 	#[tokio::test]
@@ -880,4 +1055,163 @@ mod tests {
 		// The returned block should be imported and we should be able to get its header by now.
 		assert!(client.header(res.block.hash()).unwrap().is_some());
 	}
+
+	#[derive(Clone)]
+	struct TestApi {
+		max_blocks_per_slot: u32,
+	}
+
+	struct RuntimeApi {
+		inner: TestApi,
+	}
+
+	impl ProvideRuntimeApi<TestBlock> for TestApi {
+		type Api = RuntimeApi;
+
+		fn runtime_api(&self) -> ApiRef<'_, Self::Api> {
+			RuntimeApi { inner: self.clone() }.into()
+		}
+	}
+
+	sp_api::mock_impl_runtime_apis! {
+		impl AuraApi<TestBlock, AuthorityId<AuthorityPair>> for RuntimeApi {
+			fn slot_duration() -> SlotDuration {
+				SlotDuration::from_millis(SLOT_DURATION_MS)
+			}
+
+			fn authorities() -> Vec<AuthorityId<AuthorityPair>> {
+				Vec::new()
+			}
+
+			fn max_blocks_per_slot(&self) -> u32 {
+				self.inner.max_blocks_per_slot
+			}
+		}
+	}
+
+	/// A [`SlotWorker`] building empty blocks on top of the chain head of every slot it is called
+	/// with.
+	#[derive(Default)]
+	struct TestSlotWorker {
+		/// The chain head and duration of every slot the worker was called with.
+		slots: Vec<(H256, Duration)>,
+		/// The hashes of the built blocks.
+		built: Vec<H256>,
+		/// The number of blocks after which the worker fails to build blocks.
+		max_blocks: Option<usize>,
+	}
+
+	#[async_trait::async_trait]
+	impl SlotWorker<TestBlock, ()> for TestSlotWorker {
+		async fn on_slot(
+			&mut self,
+			slot_info: SlotInfo<TestBlock>,
+		) -> Option<SlotResult<TestBlock, ()>> {
+			self.slots.push((slot_info.chain_head.hash(), slot_info.duration));
+			if self.max_blocks.map_or(false, |max| self.built.len() >= max) {
+				return None
+			}
+
+			let header = Header::new(
+				slot_info.chain_head.number() + 1,
+				Default::default(),
+				Default::default(),
+				slot_info.chain_head.hash(),
+				Default::default(),
+			);
+			let block = TestBlock::new(header, Vec::new());
+			self.built.push(block.hash());
+
+			Some(SlotResult { block, storage_proof: () })
+		}
+	}
+
+	/// Run a [`MultiBlockSlotWorker`] wrapping `worker` on slot `1`.
+	///
+	/// The slot of the inherent data providers created for sub-slots is `sub_slot`.
+	async fn on_multi_block_slot(
+		max_blocks_per_slot: u32,
+		worker: TestSlotWorker,
+		sub_slot: u64,
+	) -> (TestSlotWorker, Option<SlotResult<TestBlock, ()>>) {
+		let mut worker = MultiBlockSlotWorker {
+			worker,
+			client: Arc::new(TestApi { max_blocks_per_slot }),
+			create_inherent_data_providers: Arc::new(move |_, ()| async move {
+				Ok::<_, Box<dyn std::error::Error + Send + Sync>>((InherentDataProvider::new(
+					sub_slot.into(),
+				),))
+			}),
+			_phantom: PhantomData::<fn() -> AuthorityPair>,
+		};
+
+		let duration = Duration::from_millis(300);
+		let result = worker
+			.on_slot(SlotInfo {
+				slot: 1.into(),
+				ends_at: Instant::now() + duration,
+				create_inherent_data: Box::new(()),
+				duration,
+				chain_head: Header::new(
+					0,
+					Default::default(),
+					Default::default(),
+					Default::default(),
+					Default::default(),
+				),
+				block_size_limit: None,
+			})
+			.await;
+
+		(worker.worker, result)
+	}
+
+	#[tokio::test]
+	async fn multi_block_slot_worker_builds_one_block_per_sub_slot() {
+		let (worker, result) = on_multi_block_slot(3, Default::default(), 1).await;
+
+		assert_eq!(worker.built.len(), 3);
+		assert_eq!(result.unwrap().block.hash(), worker.built[2]);
+
+		// Every block is built on top of the block of the previous sub-slot.
+		let sub_slot_duration = Duration::from_millis(100);
+		assert_eq!(
+			worker.slots[1..],
+			[(worker.built[0], sub_slot_duration), (worker.built[1], sub_slot_duration)]
+		);
+		assert_eq!(worker.slots[0].1, sub_slot_duration);
+	}
+
+	#[tokio::test]
+	async fn multi_block_slot_worker_builds_one_block_if_runtime_allows_one_block_per_slot() {
+		let (worker, result) = on_multi_block_slot(1, Default::default(), 1).await;
+
+		assert_eq!(worker.built.len(), 1);
+		assert_eq!(worker.slots[0].1, Duration::from_millis(300));
+		assert_eq!(result.unwrap().block.hash(), worker.built[0]);
+	}
+
+	#[tokio::test]
+	async fn multi_block_slot_worker_stops_on_authoring_failure() {
+		let worker = TestSlotWorker { max_blocks: Some(2), ..Default::default() };
+		let (worker, result) = on_multi_block_slot(4, worker, 1).await;
+
+		// The worker was asked to build in a third sub-slot, but didn't get to the fourth.
+		assert_eq!(worker.slots.len(), 3);
+		assert_eq!(result.unwrap().block.hash(), worker.built[1]);
+
+		let worker = TestSlotWorker { max_blocks: Some(0), ..Default::default() };
+		let (worker, result) = on_multi_block_slot(4, worker, 1).await;
+
+		assert_eq!(worker.slots.len(), 1);
+		assert!(result.is_none());
+	}
+
+	#[tokio::test]
+	async fn multi_block_slot_worker_stops_at_the_end_of_the_slot() {
+		let (worker, result) = on_multi_block_slot(3, Default::default(), 2).await;
+
+		assert_eq!(worker.slots.len(), 1);
+		assert_eq!(result.unwrap().block.hash(), worker.built[0]);
+	}
 }
@@ -25,7 +25,7 @@ use log::trace;
 use codec::Codec;
 
 use sc_client_api::UsageProvider;
-use sp_api::{Core, ProvideRuntimeApi};
+use sp_api::{ApiExt, Core, ProvideRuntimeApi};
 use sp_application_crypto::{AppCrypto, AppPublic};
 use sp_blockchain::Result as CResult;
 use sp_consensus::Error as ConsensusError;
@@ -65,6 +65,28 @@ where
 	client.runtime_api().slot_duration(block_hash).map_err(|err| err.into())
 }
 
+/// Get the maximum number of blocks an author may build within a single slot by reading from a
+/// runtime API at a given block's state.
+///
+/// Returns `None` if the runtime doesn't expose this value through the [`AuraApi`]. Such runtimes
+/// may still allow multiple blocks per slot, but then only the runtime itself enforces a limit.
+pub fn max_blocks_per_slot_at<A, B, C>(client: &C, block_hash: B::Hash) -> CResult<Option<u32>>
+where
+	A: Codec,
+	B: BlockT,
+	C: ProvideRuntimeApi<B>,
+	C::Api: AuraApi<B, A>,
+{
+	let runtime_api = client.runtime_api();
+
+	let api_version = runtime_api.api_version::<dyn AuraApi<B, A>>(block_hash)?.unwrap_or(1);
+	if api_version < 2 {
+		return Ok(None)
+	}
+
+	Ok(Some(runtime_api.max_blocks_per_slot(block_hash)?.max(1)))
+}
+
 /// Get the slot author for given block along with authorities.
 pub fn slot_author<P: Pair>(slot: Slot, authorities: &[AuthorityId<P>]) -> Option<&AuthorityId<P>> {
 	if authorities.is_empty() {
@@ -114,6 +136,17 @@ where
 	<DigestItem as CompatibleDigestItem<P::Signature>>::aura_pre_digest(slot)
 }
 
+/// Produce the pre-runtime digest containing the slot claim of a block.
+///
+/// This needs to be put into the block header of every block that is not the first block built
+/// on its slot, with the index returned by [`index_in_slot`].
+pub fn slot_claim<P: Pair>(index: u32) -> sp_runtime::DigestItem
+where
+	P::Signature: Codec,
+{
+	<DigestItem as CompatibleDigestItem<P::Signature>>::aura_slot_claim(index)
+}
+
 /// Produce the seal digest item by signing the hash of a block.
 ///
 /// Note that after this is added to a block header, the hash of the block will change.
@@ -186,6 +219,94 @@ pub fn find_pre_digest<B: BlockT, Signature: Codec>(
 	pre_digest.ok_or_else(|| PreDigestLookupError::NoDigestFound)
 }
 
+/// Extract the slot claim from a block header.
+///
+/// This fails if there are multiple slot claims.
+///
+/// Returns the index of the block within its slot, which is `0` for headers without a slot claim.
+pub fn find_slot_claim<B: BlockT, Signature: Codec>(
+	header: &B::Header,
+) -> Result<u32, PreDigestLookupError> {
+	let mut slot_claim: Option<u32> = None;
+	for log in header.digest().logs() {
+		match (CompatibleDigestItem::<Signature>::as_aura_slot_claim(log), slot_claim.is_some()) {
+			(Some(_), true) => return Err(PreDigestLookupError::MultipleHeaders),
+			(None, _) => {},
+			(s, false) => slot_claim = s,
+		}
+	}
+	Ok(slot_claim.unwrap_or_default())
+}
+
+/// Get the index within `slot` of a block built on top of `parent`.
+///
+/// This is the index the block has to claim using [`slot_claim`]. Parents without a pre-digest,
+/// like the genesis block, never share a slot with their children.
+pub fn index_in_slot<B: BlockT, Signature: Codec>(
+	slot: Slot,
+	parent: &B::Header,
+) -> Result<u32, PreDigestLookupError> {
+	if parent.number().is_zero() || find_pre_digest::<B, Signature>(parent).ok() != Some(slot) {
+		return Ok(0)
+	}
+
+	Ok(find_slot_claim::<B, Signature>(parent)?.saturating_add(1))
+}
+
+/// Errors in slot claim verification.
+#[derive(Debug, thiserror::Error)]
+pub enum SlotClaimError {
+	/// The header or its parent has no valid pre-digest.
+	#[error("Header has no valid pre-digest: {0}")]
+	InvalidPreDigest(#[from] PreDigestLookupError),
+
+	/// The header claims the wrong index within its slot.
+	#[error("Header claims index {claimed} in slot {slot}, expected {expected}")]
+	InvalidIndex {
+		/// The slot of the header.
+		slot: Slot,
+		/// The index claimed by the header.
+		claimed: u32,
+		/// The index following the parent's index.
+		expected: u32,
+	},
+
+	/// More blocks than allowed were built on the same slot.
+	#[error("More than {max} blocks built on slot {slot}")]
+	TooManyBlocks {
+		/// The slot of the header.
+		slot: Slot,
+		/// The maximum number of blocks per slot.
+		max: u32,
+	},
+}
+
+/// Check the slot claim of a header against its parent.
+///
+/// `max_blocks_per_slot` is the value returned by [`AuraApi::max_blocks_per_slot`] at the parent
+/// block.
+///
+/// Returns `true` if `header` was built on the same slot as its parent.
+pub fn check_slot_claim<B: BlockT, Signature: Codec>(
+	header: &B::Header,
+	parent: &B::Header,
+	max_blocks_per_slot: u32,
+) -> Result<bool, SlotClaimError> {
+	let slot = find_pre_digest::<B, Signature>(header)?;
+	let claimed = find_slot_claim::<B, Signature>(header)?;
+	let expected = index_in_slot::<B, Signature>(slot, parent)?;
+
+	if claimed != expected {
+		return Err(SlotClaimError::InvalidIndex { slot, claimed, expected })
+	}
+
+	if claimed >= max_blocks_per_slot {
+		return Err(SlotClaimError::TooManyBlocks { slot, max: max_blocks_per_slot })
+	}
+
+	Ok(claimed > 0)
+}
+
 /// Fetch the current set of authorities from the runtime at a specific block.
 ///
 /// The compatibility mode and context block number informs this function whether
@@ -323,7 +444,85 @@ where
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use sp_consensus_aura::sr25519::{AuthorityPair, AuthoritySignature};
 	use sp_keyring::sr25519::Keyring;
+	use sp_runtime::Digest;
+	use substrate_test_runtime_client::runtime::{Block, Header};
+
+	fn header(number: u64, slot: u64, index: Option<u32>) -> Header {
+		let mut logs = vec![pre_digest::<AuthorityPair>(slot.into())];
+		logs.extend(index.map(slot_claim::<AuthorityPair>));
+
+		Header::new(
+			number,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Digest { logs },
+		)
+	}
+
+	fn check(header: &Header, parent: &Header, max: u32) -> Result<bool, SlotClaimError> {
+		check_slot_claim::<Block, AuthoritySignature>(header, parent, max)
+	}
+
+	#[test]
+	fn slot_claim_is_checked_against_parent() {
+		let genesis = Header::new(
+			0,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Default::default(),
+		);
+		let parent = header(1, 1, None);
+
+		// The first block of a slot doesn't claim an index.
+		assert!(!check(&header(1, 0, None), &genesis, 1).unwrap());
+		assert!(!check(&header(2, 2, None), &parent, 1).unwrap());
+		assert!(matches!(
+			check(&header(2, 2, Some(1)), &parent, 3),
+			Err(SlotClaimError::InvalidIndex { claimed: 1, expected: 0, .. }),
+		));
+
+		// Following blocks on the same slot claim the index after their parent's index.
+		assert!(check(&header(2, 1, Some(1)), &parent, 3).unwrap());
+		assert!(check(&header(3, 1, Some(2)), &header(2, 1, Some(1)), 3).unwrap());
+		assert!(matches!(
+			check(&header(2, 1, None), &parent, 3),
+			Err(SlotClaimError::InvalidIndex { claimed: 0, expected: 1, .. }),
+		));
+		assert!(matches!(
+			check(&header(3, 1, Some(1)), &header(2, 1, Some(1)), 3),
+			Err(SlotClaimError::InvalidIndex { claimed: 1, expected: 2, .. }),
+		));
+	}
+
+	#[test]
+	fn slot_claim_is_limited_by_max_blocks_per_slot() {
+		let parent = header(2, 1, Some(1));
+
+		assert!(check(&header(3, 1, Some(2)), &parent, 3).unwrap());
+		assert!(matches!(
+			check(&header(3, 1, Some(2)), &parent, 2),
+			Err(SlotClaimError::TooManyBlocks { max: 2, .. }),
+		));
+		assert!(matches!(
+			check(&header(2, 1, Some(1)), &header(1, 1, None), 1),
+			Err(SlotClaimError::TooManyBlocks { max: 1, .. }),
+		));
+	}
+
+	#[test]
+	fn multiple_slot_claims_are_rejected() {
+		let mut header = header(2, 1, Some(1));
+		header.digest_mut().push(slot_claim::<AuthorityPair>(1));
+
+		assert!(matches!(
+			find_slot_claim::<Block, AuthoritySignature>(&header),
+			Err(PreDigestLookupError::MultipleHeaders),
+		));
+	}
 
 	#[test]
 	fn authorities_call_works() {
@@ -0,0 +1,218 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Verification of the BABE seals of headers downloaded ahead of their parents' import.
+
+use crate::{
+	find_next_config_digest, find_next_epoch_digest, find_pre_digest, verification, BabeLink,
+	Epoch, Error,
+};
+use parking_lot::Mutex;
+use sc_consensus_epochs::{descendent_query, Epoch as EpochT, SharedEpochChanges};
+use sc_consensus_slots::CheckedHeader;
+use sc_network_sync::strategy::header_first::HeaderVerifier;
+use sp_blockchain::{Error as ClientError, HeaderBackend, HeaderMetadata};
+use sp_consensus_babe::BabeConfiguration;
+use sp_consensus_slots::Slot;
+use sp_runtime::{
+	traits::{Block as BlockT, Header, Zero},
+	Justifications,
+};
+use std::{
+	collections::VecDeque,
+	fmt,
+	sync::Arc,
+	time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Number of verified headers whose epochs are kept. A header failing verification is followed
+/// by another header with the same parent, which may have passed this verifier already.
+const MAX_TRACKED_HEADERS: usize = 2;
+
+/// The epochs following a verified header.
+#[derive(Clone)]
+struct TrackedEpochs {
+	/// The slot of the header.
+	slot: Slot,
+	/// The epoch of the header, as announced.
+	epoch: Arc<Epoch>,
+	/// The epoch announced by the first block of `epoch`.
+	next: Option<Arc<Epoch>>,
+}
+
+/// Verifies the BABE seals of the headers downloaded by header-first sync.
+///
+/// The headers are verified before their parents are imported, so the epochs are followed
+/// through the epoch change digests of the verified headers, starting from the epoch changes of
+/// the block import at the last imported block.
+pub struct BabeHeaderVerifier<Block: BlockT, Client> {
+	client: Arc<Client>,
+	epoch_changes: SharedEpochChanges<Block, Epoch>,
+	config: BabeConfiguration,
+	/// The epochs following the last verified headers, most recent last.
+	tracked: Mutex<VecDeque<(Block::Hash, TrackedEpochs)>>,
+}
+
+impl<Block: BlockT, Client> BabeHeaderVerifier<Block, Client> {
+	/// Create a new verifier sharing the epoch changes of the block import.
+	pub fn new(client: Arc<Client>, link: &BabeLink<Block>) -> Self {
+		Self {
+			client,
+			epoch_changes: link.epoch_changes().clone(),
+			config: link.config().clone(),
+			tracked: Mutex::new(VecDeque::new()),
+		}
+	}
+}
+
+impl<Block: BlockT, Client> fmt::Debug for BabeHeaderVerifier<Block, Client> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("BabeHeaderVerifier").finish()
+	}
+}
+
+impl<Block, Client> BabeHeaderVerifier<Block, Client>
+where
+	Block: BlockT,
+	Client: HeaderBackend<Block> + HeaderMetadata<Block, Error = ClientError>,
+{
+	/// The epochs following the imported parent of `header`, which is at `slot`.
+	fn imported_epochs(&self, header: &Block::Header, slot: Slot) -> Result<TrackedEpochs, String> {
+		let parent_hash = *header.parent_hash();
+		let parent =
+			self.client.header(parent_hash).map_err(|e| e.to_string())?.ok_or_else(|| {
+				Error::<Block>::ParentUnavailable(parent_hash, header.hash()).to_string()
+			})?;
+		let parent_slot = if parent.number().is_zero() {
+			Slot::from(0)
+		} else {
+			find_pre_digest::<Block>(&parent)?.slot()
+		};
+
+		let epoch_changes = self.epoch_changes.shared_data();
+		let epoch_at = |slot| -> Result<Epoch, String> {
+			let descriptor = epoch_changes
+				.epoch_descriptor_for_child_of(
+					descendent_query(&*self.client),
+					&parent_hash,
+					*parent.number(),
+					slot,
+				)
+				.map_err(|e| e.to_string())?
+				.ok_or_else(|| Error::<Block>::FetchEpoch(parent_hash).to_string())?;
+			epoch_changes
+				.viable_epoch(&descriptor, |slot| Epoch::genesis(&self.config, slot))
+				.map(|epoch| epoch.as_ref().clone())
+				.ok_or_else(|| Error::<Block>::FetchEpoch(parent_hash).to_string())
+		};
+
+		let epoch = Arc::new(epoch_at(slot)?);
+		if parent_slot < epoch.start_slot {
+			// The child starts `epoch`, which is the next epoch of the parent.
+			return Ok(TrackedEpochs { slot: parent_slot, epoch: epoch.clone(), next: Some(epoch) })
+		}
+
+		let next = epoch_at(epoch.clone_for_slot(slot).end_slot())?;
+		let next = (next.start_slot > epoch.start_slot).then(|| Arc::new(next));
+		Ok(TrackedEpochs { slot: parent_slot, epoch, next })
+	}
+
+	/// Check the seal and the epoch change digests of `header`, whose parent is followed by
+	/// `parent`.
+	fn check(
+		&self,
+		parent: &TrackedEpochs,
+		header: &Block::Header,
+		slot: Slot,
+	) -> Result<TrackedEpochs, String> {
+		if slot <= parent.slot {
+			return Err(Error::<Block>::SlotMustIncrease(parent.slot, slot).into())
+		}
+
+		let epoch = match &parent.next {
+			Some(next) if next.start_slot <= slot => next.clone(),
+			_ => parent.epoch.clone(),
+		};
+		let first_in_epoch = parent.slot < epoch.start_slot;
+
+		let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis();
+		let slot_now = Slot::from((now / self.config.slot_duration.max(1) as u128) as u64);
+		let params = verification::VerificationParams::<Block> {
+			header: header.clone(),
+			pre_digest: None,
+			// We add one to the current slot to allow for some small drift.
+			slot_now: slot_now + 1,
+			epoch: &epoch,
+		};
+		if let CheckedHeader::Deferred(..) = verification::check_header::<Block>(params)? {
+			return Err(Error::<Block>::TooFarInFuture(header.hash()).into())
+		}
+
+		let next_epoch_digest = find_next_epoch_digest::<Block>(header)?;
+		let next_config_digest = find_next_config_digest::<Block>(header)?;
+		let next = match (first_in_epoch, next_epoch_digest, next_config_digest.is_some()) {
+			(true, Some(descriptor), _) => {
+				let epoch = if epoch.end_slot() <= slot {
+					epoch.clone_for_slot(slot)
+				} else {
+					Epoch::clone(&epoch)
+				};
+				let config =
+					next_config_digest.map(Into::into).unwrap_or_else(|| epoch.config.clone());
+				Some(Arc::new(epoch.increment((descriptor, config))))
+			},
+			(false, None, false) => parent.next.clone(),
+			(false, None, true) => return Err(Error::<Block>::UnexpectedConfigChange.into()),
+			(true, None, _) =>
+				return Err(Error::<Block>::ExpectedEpochChange(header.hash(), slot).into()),
+			(false, Some(_), _) => return Err(Error::<Block>::UnexpectedEpochChange.into()),
+		};
+
+		Ok(TrackedEpochs { slot, epoch, next })
+	}
+}
+
+impl<Block, Client> HeaderVerifier<Block> for BabeHeaderVerifier<Block, Client>
+where
+	Block: BlockT,
+	Client: HeaderBackend<Block> + HeaderMetadata<Block, Error = ClientError> + Send + Sync,
+{
+	fn verify(&self, header: &Block::Header, _: Option<&Justifications>) -> Result<(), String> {
+		let slot = find_pre_digest::<Block>(header)?.slot();
+		let parent_hash = *header.parent_hash();
+
+		let tracked = self
+			.tracked
+			.lock()
+			.iter()
+			.find(|(hash, _)| *hash == parent_hash)
+			.map(|(_, epochs)| epochs.clone());
+		let parent = match tracked {
+			Some(parent) => parent,
+			None => self.imported_epochs(header, slot)?,
+		};
+		let epochs = self.check(&parent, header, slot)?;
+
+		let mut tracked = self.tracked.lock();
+		if tracked.len() == MAX_TRACKED_HEADERS {
+			tracked.pop_front();
+		}
+		tracked.push_back((header.hash(), epochs));
+		Ok(())
+	}
+}
@@ -139,7 +139,9 @@ pub use sp_consensus_babe::{
 };
 
 pub use aux_schema::load_block_weight as block_weight;
+pub use header_verifier::BabeHeaderVerifier;
 
+mod header_verifier;
 mod migration;
 mod verification;
 
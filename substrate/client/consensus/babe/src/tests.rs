@@ -25,6 +25,7 @@ use sc_client_api::{BlockchainEvents, Finalizer};
 use sc_consensus::{BoxBlockImport, BoxJustificationImport};
 use sc_consensus_epochs::{EpochIdentifier, EpochIdentifierPosition};
 use sc_consensus_slots::BackoffAuthoringOnFinalizedHeadLagging;
+use sc_network_sync::strategy::header_first::HeaderVerifier;
 use sc_network_test::{Block as TestBlock, *};
 use sc_transaction_pool_api::RejectAllTxPool;
 use sp_application_crypto::key_types::BABE;
@@ -1229,6 +1230,81 @@ async fn allows_skipping_epochs() {
 	assert_eq!(epoch4, epoch4_);
 }
 
+#[tokio::test]
+async fn header_verifier_follows_epoch_changes() {
+	let mut net = BabeTestNet::new(1);
+
+	let peer = net.peer(0);
+	let data = peer.data.as_ref().expect("babe link set up during initialization");
+
+	let client = peer.client().as_client();
+	let mut block_import = data.block_import.lock().take().expect("import set up during init");
+
+	let mut proposer_factory = DummyFactory {
+		client: client.clone(),
+		epoch_changes: data.link.epoch_changes.clone(),
+		mutator: Arc::new(|_, _| ()),
+	};
+
+	let epoch_length = data.link.config.epoch_length;
+
+	// blocks in epochs 0 and 1, the first block of epoch 2 and, skipping epochs 3 and 4, two
+	// blocks in epoch 5.
+	let mut blocks = propose_and_import_blocks(
+		&client,
+		&mut proposer_factory,
+		&mut block_import,
+		client.chain_info().genesis_hash,
+		epoch_length as usize * 2 + 1,
+	)
+	.await;
+	let last_block = client.expect_header(*blocks.last().unwrap()).unwrap();
+	blocks.push(
+		propose_and_import_block(
+			&last_block,
+			Some((epoch_length * 5 + 1).into()),
+			&mut proposer_factory,
+			&mut block_import,
+		)
+		.await,
+	);
+	let last_block = client.expect_header(*blocks.last().unwrap()).unwrap();
+	blocks.push(
+		propose_and_import_block(&last_block, None, &mut proposer_factory, &mut block_import).await,
+	);
+	let headers = blocks
+		.iter()
+		.map(|hash| client.expect_header(*hash).unwrap())
+		.collect::<Vec<_>>();
+
+	// the epochs are followed from genesis through the digests of the verified headers.
+	let verifier = BabeHeaderVerifier::new(client.clone(), &data.link);
+	for header in &headers {
+		assert_eq!(verifier.verify(header, None), Ok(()));
+	}
+
+	// a header with a seal of another key is rejected, and can be replaced by a valid one.
+	let verifier = BabeHeaderVerifier::new(client.clone(), &data.link);
+	for header in &headers[..epoch_length as usize] {
+		assert_eq!(verifier.verify(header, None), Ok(()));
+	}
+	let mut forged = headers[epoch_length as usize].clone();
+	let seal = forged.digest_mut().pop().unwrap();
+	assert!(seal.as_babe_seal().is_some());
+	let signature = AuthorityPair::from_seed(&[2; 32]).sign(forged.hash().as_ref());
+	forged.digest_mut().push(Item::babe_seal(signature));
+	assert!(verifier.verify(&forged, None).is_err());
+	for header in &headers[epoch_length as usize..] {
+		assert_eq!(verifier.verify(header, None), Ok(()));
+	}
+
+	// the epochs of an imported parent are read from the epoch changes.
+	let verifier = BabeHeaderVerifier::new(client.clone(), &data.link);
+	for header in &headers[epoch_length as usize + 3..] {
+		assert_eq!(verifier.verify(header, None), Ok(()));
+	}
+}
+
 #[tokio::test]
 async fn allows_skipping_epochs_on_some_forks() {
 	let mut net = BabeTestNet::new(1);
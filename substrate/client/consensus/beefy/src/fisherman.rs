@@ -16,21 +16,54 @@
 // You should have received a copy of the GNU General Public License
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
-use crate::{error::Error, keystore::BeefyKeystore, round::Rounds, LOG_TARGET};
+use crate::{
+	error::Error, justification::BeefyVersionedFinalityProof, keystore::BeefyKeystore,
+	round::Rounds, LOG_TARGET,
+};
 use log::{debug, error, warn};
+use parking_lot::Mutex;
 use sc_client_api::Backend;
 use sp_api::ProvideRuntimeApi;
 use sp_application_crypto::RuntimeAppPublic;
 use sp_blockchain::HeaderBackend;
+use sp_consensus::SyncOracle;
 use sp_consensus_beefy::{
-	check_double_voting_proof, AuthorityIdBound, BeefyApi, BeefySignatureHasher, DoubleVotingProof,
-	OpaqueKeyOwnershipProof, ValidatorSetId,
+	check_commitment_signature, check_double_voting_proof, AuthorityIdBound, BeefyApi,
+	BeefySignatureHasher, Commitment, DoubleVotingProof, ForkVotingProof, FutureBlockVotingProof,
+	OpaqueKeyOwnershipProof, PayloadProvider, ValidatorSetId, VersionedFinalityProof, VoteMessage,
 };
 use sp_runtime::{
 	generic::BlockId,
 	traits::{Block, NumberFor},
 };
-use std::{marker::PhantomData, sync::Arc};
+use std::{
+	collections::{BTreeMap, BTreeSet},
+	marker::PhantomData,
+	sync::Arc,
+};
+
+/// Number of blocks a vote can be ahead of our best block before it is reported as a future
+/// block voting.
+///
+/// Our best block may lag behind the rest of the network, in which case the votes for the
+/// blocks we are about to import are perfectly valid.
+pub(crate) const FUTURE_BLOCK_VOTING_MARGIN: u32 = 32;
+
+/// Number of rounds for which the reported validators are remembered, to avoid reporting the
+/// same equivocation once for every vote or justification we receive.
+const MAX_REPORTED_ROUNDS: usize = 256;
+
+type Vote<B, AuthorityId> =
+	VoteMessage<NumberFor<B>, AuthorityId, <AuthorityId as RuntimeAppPublic>::Signature>;
+
+/// Kind of equivocation a commitment is proof of, when checked against the local chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Equivocation {
+	/// The commitment payload doesn't match the payload of the canonical block.
+	ForkVoting,
+	/// The commitment is for a block that is not yet known.
+	FutureBlockVoting,
+}
 
 /// Helper struct containing the key ownership proof for a validator.
 pub struct ProvedValidator {
@@ -38,17 +71,24 @@ pub struct ProvedValidator {
 }
 
 /// Helper used to check and report equivocations.
-pub struct Fisherman<B, BE, RuntimeApi, AuthorityId: AuthorityIdBound> {
+pub struct Fisherman<B, BE, P, RuntimeApi, AuthorityId: AuthorityIdBound> {
 	backend: Arc<BE>,
 	runtime: Arc<RuntimeApi>,
 	key_store: Arc<BeefyKeystore<AuthorityId>>,
+	payload_provider: P,
+	sync: Arc<dyn SyncOracle + Send + Sync>,
+	/// Validators already reported for fork or future block voting, by round.
+	reported: Mutex<BTreeMap<NumberFor<B>, BTreeSet<AuthorityId>>>,
 
 	_phantom: PhantomData<B>,
 }
 
-impl<B: Block, BE: Backend<B>, RuntimeApi: ProvideRuntimeApi<B>, AuthorityId>
-	Fisherman<B, BE, RuntimeApi, AuthorityId>
+impl<B, BE, P, RuntimeApi, AuthorityId> Fisherman<B, BE, P, RuntimeApi, AuthorityId>
 where
+	B: Block,
+	BE: Backend<B>,
+	P: PayloadProvider<B>,
+	RuntimeApi: ProvideRuntimeApi<B>,
 	RuntimeApi::Api: BeefyApi<B, AuthorityId>,
 	AuthorityId: AuthorityIdBound,
 {
@@ -56,8 +96,18 @@ where
 		backend: Arc<BE>,
 		runtime: Arc<RuntimeApi>,
 		keystore: Arc<BeefyKeystore<AuthorityId>>,
+		payload_provider: P,
+		sync: Arc<dyn SyncOracle + Send + Sync>,
 	) -> Self {
-		Self { backend, runtime, key_store: keystore, _phantom: Default::default() }
+		Self {
+			backend,
+			runtime,
+			key_store: keystore,
+			payload_provider,
+			sync,
+			reported: Default::default(),
+			_phantom: Default::default(),
+		}
 	}
 
 	fn prove_offenders<'a>(
@@ -164,4 +214,175 @@ where
 
 		Ok(())
 	}
+
+	/// Check the `vote` against the local canonical chain, and report it if it is a fork voting
+	/// or a future block voting equivocation.
+	///
+	/// Votes for blocks which are not yet finalized (but known), or which are only slightly
+	/// ahead of our best block, can't be judged and are ignored. Nothing is checked while the
+	/// node is major syncing.
+	pub fn check_vote(&self, vote: Vote<B, AuthorityId>) -> Result<(), Error> {
+		match self.check_commitment(&vote.commitment)? {
+			Some(equivocation) => self.report_vote(equivocation, vote),
+			None => Ok(()),
+		}
+	}
+
+	/// Check the commitment of the given finality proof against the local canonical chain, and
+	/// report each of its signers if it is a fork voting or a future block voting equivocation.
+	///
+	/// The signatures are expected to be in the same order as `validators`.
+	pub fn check_proof(
+		&self,
+		proof: BeefyVersionedFinalityProof<B, AuthorityId>,
+		validators: &[AuthorityId],
+	) -> Result<(), Error> {
+		let VersionedFinalityProof::V1(signed_commitment) = proof;
+		let Some(equivocation) = self.check_commitment(&signed_commitment.commitment)? else {
+			return Ok(())
+		};
+
+		let commitment = signed_commitment.commitment;
+		let votes =
+			validators
+				.iter()
+				.zip(signed_commitment.signatures)
+				.filter_map(|(id, signature)| {
+					signature.map(|signature| VoteMessage {
+						commitment: commitment.clone(),
+						id: id.clone(),
+						signature,
+					})
+				});
+		for vote in votes {
+			self.report_vote(equivocation, vote)?;
+		}
+		Ok(())
+	}
+
+	fn check_commitment(
+		&self,
+		commitment: &Commitment<NumberFor<B>>,
+	) -> Result<Option<Equivocation>, Error> {
+		// Our view of the chain is too far behind to judge the votes of the others.
+		if self.sync.is_major_syncing() {
+			return Ok(None)
+		}
+
+		let info = self.backend.blockchain().info();
+		let number = commitment.block_number;
+
+		// There shouldn't even be a payload to sign for a block nobody knows about yet. The
+		// blocks just ahead of our best block may only not be imported yet.
+		if number > info.best_number.saturating_add(FUTURE_BLOCK_VOTING_MARGIN.into()) {
+			return Ok(Some(Equivocation::FutureBlockVoting))
+		}
+		if number > info.best_number {
+			return Ok(None)
+		}
+		// Only the finalized blocks are known to be canonical.
+		if number > info.finalized_number {
+			return Ok(None)
+		}
+
+		let hash = self.backend.blockchain().expect_block_hash_from_id(&BlockId::Number(number))?;
+		let header = self.backend.blockchain().expect_header(hash)?;
+		match self.payload_provider.payload(&header) {
+			Some(payload) if payload != commitment.payload => Ok(Some(Equivocation::ForkVoting)),
+			Some(_) => Ok(None),
+			None => {
+				debug!(
+					target: LOG_TARGET,
+					"🥩 No payload for canonical block #{:?}, can't check commitment", number
+				);
+				Ok(None)
+			},
+		}
+	}
+
+	fn report_vote(
+		&self,
+		equivocation: Equivocation,
+		vote: Vote<B, AuthorityId>,
+	) -> Result<(), Error> {
+		if !check_commitment_signature::<_, _, BeefySignatureHasher>(
+			&vote.commitment,
+			&vote.id,
+			&vote.signature,
+		) {
+			debug!(target: LOG_TARGET, "🥩 Skipping report for badly signed vote {:?}", vote);
+			return Ok(());
+		}
+
+		if self.key_store.authority_id(&[vote.id.clone()]).is_some() {
+			warn!(target: LOG_TARGET, "🥩 Skipping report for own equivocation");
+			return Ok(());
+		}
+
+		let round = vote.commitment.block_number;
+		if self.reported.lock().get(&round).map_or(false, |ids| ids.contains(&vote.id)) {
+			debug!(target: LOG_TARGET, "🥩 Skipping already reported equivocation: {:?}", vote);
+			return Ok(());
+		}
+
+		debug!(target: LOG_TARGET, "🥩 Reporting {:?} equivocation: {:?}", equivocation, vote);
+
+		// The equivocated block may not be part of our chain, so both the key ownership proof
+		// and the report are generated at the **best** block.
+		let best_block_hash = self.backend.blockchain().info().best_hash;
+		let key_owner_proofs = self.prove_offenders(
+			BlockId::Hash(best_block_hash),
+			vec![&vote.id].into_iter(),
+			vote.commitment.validator_set_id,
+		)?;
+		let Some(ProvedValidator { key_owner_proof }) = key_owner_proofs.into_iter().next() else {
+			return Ok(())
+		};
+
+		let runtime_api = self.runtime.runtime_api();
+		let offender_id = vote.id.clone();
+		match equivocation {
+			Equivocation::ForkVoting => {
+				// Prove that the commitment block is an ancestor of the best block, whose MMR
+				// root is checked by the runtime.
+				let Some(ancestry_proof) = runtime_api
+					.generate_ancestry_proof(best_block_hash, vote.commitment.block_number, None)
+					.map_err(Error::RuntimeApi)?
+				else {
+					debug!(
+						target: LOG_TARGET,
+						"🥩 Couldn't generate ancestry proof for block #{:?}. \
+						Skipping report for fork voting equivocation",
+						vote.commitment.block_number
+					);
+					return Ok(());
+				};
+				let header = self.backend.blockchain().expect_header(best_block_hash)?;
+				runtime_api
+					.submit_report_fork_voting_unsigned_extrinsic(
+						best_block_hash,
+						ForkVotingProof { vote, ancestry_proof, header },
+						key_owner_proof,
+					)
+					.map_err(Error::RuntimeApi)?;
+			},
+			Equivocation::FutureBlockVoting => {
+				runtime_api
+					.submit_report_future_block_voting_unsigned_extrinsic(
+						best_block_hash,
+						FutureBlockVotingProof { vote },
+						key_owner_proof,
+					)
+					.map_err(Error::RuntimeApi)?;
+			},
+		}
+
+		let mut reported = self.reported.lock();
+		reported.entry(round).or_default().insert(offender_id);
+		while reported.len() > MAX_REPORTED_ROUNDS {
+			reported.pop_first();
+		}
+
+		Ok(())
+	}
 }
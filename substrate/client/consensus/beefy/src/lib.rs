@@ -315,15 +315,25 @@ where
 		links: BeefyVoterLinks<B, AuthorityId>,
 		pending_justifications: BTreeMap<NumberFor<B>, BeefyVersionedFinalityProof<B, AuthorityId>>,
 		is_authority: bool,
-	) -> BeefyWorker<B, BE, P, R, S, N, AuthorityId> {
+	) -> BeefyWorker<B, BE, P, R, S, N, AuthorityId>
+	where
+		P: PayloadProvider<B> + Clone,
+		S: SyncOracle + Send + Sync + 'static,
+	{
 		let key_store = Arc::new(self.key_store);
 		BeefyWorker {
 			backend: self.backend.clone(),
 			runtime: self.runtime.clone(),
 			key_store: key_store.clone(),
-			payload_provider,
-			sync,
-			fisherman: Arc::new(Fisherman::new(self.backend, self.runtime, key_store)),
+			payload_provider: payload_provider.clone(),
+			sync: sync.clone(),
+			fisherman: Arc::new(Fisherman::new(
+				self.backend,
+				self.runtime,
+				key_store,
+				payload_provider,
+				sync,
+			)),
 			metrics: self.metrics,
 			persisted_state: self.persisted_state,
 			comms,
@@ -24,7 +24,7 @@ use crate::{
 	error::Error,
 	find_authorities_change,
 	fisherman::Fisherman,
-	justification::BeefyVersionedFinalityProof,
+	justification::{proof_block_num_and_set_id, BeefyVersionedFinalityProof},
 	keystore::BeefyKeystore,
 	metric_inc, metric_set,
 	metrics::VoterMetrics,
@@ -387,7 +387,7 @@ pub(crate) struct BeefyWorker<B: Block, BE, P, RuntimeApi, S, N, AuthorityId: Au
 	pub key_store: Arc<BeefyKeystore<AuthorityId>>,
 	pub payload_provider: P,
 	pub sync: Arc<S>,
-	pub fisherman: Arc<Fisherman<B, BE, RuntimeApi, AuthorityId>>,
+	pub fisherman: Arc<Fisherman<B, BE, P, RuntimeApi, AuthorityId>>,
 
 	// communication (created once, but returned and reused if worker is restarted/reinitialized)
 	pub comms: BeefyComms<B, N, AuthorityId>,
@@ -522,6 +522,10 @@ where
 	where
 		<AuthorityId as RuntimeAppPublic>::Signature: Encode + Decode,
 	{
+		if let Err(e) = self.fisherman.check_vote(vote.clone()) {
+			debug!(target: LOG_TARGET, "🥩 Failed to check vote for equivocation: {}", e);
+		}
+
 		let block_num = vote.commitment.block_number;
 		match self.voting_oracle().triage_round(block_num)? {
 			RoundAction::Process =>
@@ -548,6 +552,8 @@ where
 		&mut self,
 		justification: BeefyVersionedFinalityProof<B, AuthorityId>,
 	) -> Result<(), Error> {
+		self.check_justification(&justification);
+
 		let signed_commitment = match justification {
 			VersionedFinalityProof::V1(ref sc) => sc,
 		};
@@ -968,6 +974,26 @@ where
 		(error, self.comms)
 	}
 
+	/// Check `justification` against the local canonical chain and report its signers if it
+	/// is proof of fork voting or future block voting.
+	///
+	/// Only justifications signed by one of the known validator sets can be checked.
+	fn check_justification(&self, justification: &BeefyVersionedFinalityProof<B, AuthorityId>) {
+		let (_, set_id) = proof_block_num_and_set_id::<B, AuthorityId>(justification);
+		let Some(rounds) = self
+			.persisted_state
+			.voting_oracle
+			.sessions
+			.iter()
+			.find(|rounds| rounds.validator_set_id() == set_id)
+		else {
+			return;
+		};
+		if let Err(e) = self.fisherman.check_proof(justification.clone(), rounds.validators()) {
+			debug!(target: LOG_TARGET, "🥩 Failed to check justification for equivocation: {}", e);
+		}
+	}
+
 	/// Report the given equivocation to the BEEFY runtime module.
 	fn report_double_voting(
 		&self,
@@ -1027,6 +1053,7 @@ pub(crate) mod tests {
 			notification::{BeefyBestBlockStream, BeefyVersionedFinalityProofStream},
 			request_response::outgoing_requests_engine::OnDemandJustificationsEngine,
 		},
+		fisherman::FUTURE_BLOCK_VOTING_MARGIN,
 		tests::{
 			create_beefy_keystore, get_beefy_streams, make_beefy_ids, BeefyPeer, BeefyTestNet,
 			TestApi,
@@ -1044,7 +1071,7 @@ pub(crate) mod tests {
 		ecdsa_crypto, known_payloads,
 		known_payloads::MMR_ROOT_ID,
 		mmr::MmrRootProvider,
-		test_utils::{generate_double_voting_proof, Keyring},
+		test_utils::{generate_double_voting_proof, signed_vote, Keyring},
 		ConsensusLog, Payload, SignedCommitment,
 	};
 	use sp_runtime::traits::{Header as HeaderT, One};
@@ -1153,9 +1180,15 @@ pub(crate) mod tests {
 			runtime: api.clone(),
 			key_store: key_store.clone(),
 			metrics,
-			payload_provider,
-			sync: Arc::new(sync),
-			fisherman: Arc::new(Fisherman::new(backend, api, key_store)),
+			payload_provider: payload_provider.clone(),
+			sync: Arc::new(sync.clone()),
+			fisherman: Arc::new(Fisherman::new(
+				backend,
+				api,
+				key_store,
+				payload_provider,
+				Arc::new(sync),
+			)),
 			links,
 			comms,
 			pending_justifications: BTreeMap::new(),
@@ -1582,6 +1615,8 @@ pub(crate) mod tests {
 			worker.backend.clone(),
 			worker.runtime.clone(),
 			worker.key_store.clone(),
+			worker.payload_provider.clone(),
+			worker.sync.clone(),
 		));
 
 		// let there be a block with num = 1:
@@ -1632,4 +1667,134 @@ pub(crate) mod tests {
 		// verify nothing reported to runtime
 		assert!(api_alice.reported_equivocations.as_ref().unwrap().lock().is_empty());
 	}
+
+	#[tokio::test]
+	async fn should_report_fork_and_future_block_votes() {
+		let set_id = 1;
+		let keys = [Keyring::Alice];
+		let validator_set = ValidatorSet::new(make_beefy_ids(&keys), set_id).unwrap();
+		let mut api_alice = TestApi::with_validator_set(&validator_set);
+		api_alice.allow_equivocations();
+		let api_alice = Arc::new(api_alice);
+
+		// block #1 is finalized by the worker creation
+		let mut net = BeefyTestNet::new(1);
+		let mut worker = create_beefy_worker(net.peer(0), &keys[0], 1, validator_set.clone());
+		worker.runtime = api_alice.clone();
+		worker.fisherman = Arc::new(Fisherman::new(
+			worker.backend.clone(),
+			worker.runtime.clone(),
+			worker.key_store.clone(),
+			worker.payload_provider.clone(),
+			worker.sync.clone(),
+		));
+		// let there be a non-finalized block #2
+		let _ = net.peer(0).push_blocks(1, false);
+
+		let hash1 = worker.backend.blockchain().hash(1).unwrap().unwrap();
+		let header1 = worker.backend.blockchain().expect_header(hash1).unwrap();
+		let canonical = worker.payload_provider.payload(&header1).unwrap();
+		let forked = Payload::from_single_entry(MMR_ROOT_ID, vec![42]);
+
+		// votes on the canonical chain, or on blocks which are not finalized yet, are fine
+		let fisherman = worker.fisherman.clone();
+		assert_eq!(fisherman.check_vote(signed_vote(1, canonical, set_id, &Keyring::Bob)), Ok(()));
+		assert_eq!(
+			fisherman.check_vote(signed_vote(2, forked.clone(), set_id, &Keyring::Bob)),
+			Ok(())
+		);
+		assert!(api_alice.reported_fork_votings.as_ref().unwrap().lock().is_empty());
+		assert!(api_alice.reported_future_block_votings.as_ref().unwrap().lock().is_empty());
+
+		// vote on a fork of the finalized chain
+		let fork_vote = signed_vote(1, forked.clone(), set_id, &Keyring::Bob);
+		assert_eq!(fisherman.check_vote(fork_vote.clone()), Ok(()));
+		{
+			let reported = api_alice.reported_fork_votings.as_ref().unwrap().lock();
+			assert_eq!(reported.len(), 1);
+			assert_eq!(reported[0].vote, fork_vote);
+			assert_eq!(*reported[0].header.number(), 2);
+		}
+
+		// the same equivocation is reported only once
+		assert_eq!(fisherman.check_vote(fork_vote.clone()), Ok(()));
+		assert_eq!(api_alice.reported_fork_votings.as_ref().unwrap().lock().len(), 1);
+
+		// a vote just ahead of our best block may be for a block we didn't import yet
+		let ahead_vote = signed_vote(3, forked.clone(), set_id, &Keyring::Bob);
+		assert_eq!(fisherman.check_vote(ahead_vote), Ok(()));
+		let margin_vote = signed_vote(
+			2 + FUTURE_BLOCK_VOTING_MARGIN as u64,
+			forked.clone(),
+			set_id,
+			&Keyring::Bob,
+		);
+		assert_eq!(fisherman.check_vote(margin_vote), Ok(()));
+		assert!(api_alice.reported_future_block_votings.as_ref().unwrap().lock().is_empty());
+
+		// vote on a block nobody can know about yet
+		let future_number = 3 + FUTURE_BLOCK_VOTING_MARGIN as u64;
+		let future_vote = signed_vote(future_number, forked.clone(), set_id, &Keyring::Bob);
+		assert_eq!(fisherman.check_vote(future_vote.clone()), Ok(()));
+		{
+			let reported = api_alice.reported_future_block_votings.as_ref().unwrap().lock();
+			assert_eq!(reported.len(), 1);
+			assert_eq!(reported[0].vote, future_vote);
+		}
+
+		// a justification on a fork reports all of its signers, but 'self'
+		let commitment = Commitment { payload: forked, block_number: 1, validator_set_id: set_id };
+		let signers = [Keyring::Alice, Keyring::Bob, Keyring::Charlie];
+		let justif = VersionedFinalityProof::<_, ecdsa_crypto::Signature>::V1(SignedCommitment {
+			commitment: commitment.clone(),
+			signatures: vec![
+				Some(Keyring::Alice.sign(&commitment.encode())),
+				None,
+				Some(Keyring::Charlie.sign(&commitment.encode())),
+			],
+		});
+		let validators: Vec<_> = signers.iter().map(|k| k.public()).collect();
+		api_alice.reported_fork_votings.as_ref().unwrap().lock().clear();
+		assert_eq!(fisherman.check_proof(justif, &validators), Ok(()));
+		{
+			let reported = api_alice.reported_fork_votings.as_ref().unwrap().lock();
+			assert_eq!(reported.len(), 1);
+			assert_eq!(reported[0].vote.id, Keyring::Charlie.public());
+		}
+
+		// badly signed votes are ignored
+		let mut bad_vote = signed_vote(
+			future_number,
+			Payload::from_single_entry(MMR_ROOT_ID, vec![]),
+			set_id,
+			&Keyring::Dave,
+		);
+		bad_vote.id = Keyring::Charlie.public();
+		assert_eq!(fisherman.check_vote(bad_vote), Ok(()));
+		assert_eq!(api_alice.reported_future_block_votings.as_ref().unwrap().lock().len(), 1);
+
+		// nothing is checked while major syncing
+		struct MajorSyncing;
+		impl SyncOracle for MajorSyncing {
+			fn is_major_syncing(&self) -> bool {
+				true
+			}
+			fn is_offline(&self) -> bool {
+				false
+			}
+		}
+		let syncing_fisherman = Fisherman::new(
+			worker.backend.clone(),
+			worker.runtime.clone(),
+			worker.key_store.clone(),
+			worker.payload_provider.clone(),
+			Arc::new(MajorSyncing),
+		);
+		let fork_vote = signed_vote(1, forked.clone(), set_id, &Keyring::Dave);
+		assert_eq!(syncing_fisherman.check_vote(fork_vote), Ok(()));
+		let future_vote = signed_vote(future_number, forked, set_id, &Keyring::Dave);
+		assert_eq!(syncing_fisherman.check_vote(future_vote), Ok(()));
+		assert_eq!(api_alice.reported_fork_votings.as_ref().unwrap().lock().len(), 1);
+		assert_eq!(api_alice.reported_future_block_votings.as_ref().unwrap().lock().len(), 1);
+	}
 }
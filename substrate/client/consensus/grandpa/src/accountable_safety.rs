@@ -0,0 +1,736 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Accountable safety for GRANDPA.
+//!
+//! Finalizing two conflicting blocks with the same authority set requires at least a third of the
+//! voters to misbehave. Given two justifications signed by the same authority set,
+//! [`AccountableSafety`] finds the voters responsible for it, following the protocol described in
+//! the GRANDPA paper.
+//!
+//! If both justifications are for the same round, the voters that signed conflicting precommits
+//! are found directly. Otherwise, the voters are queried for the votes that made them vote the way
+//! they did:
+//!
+//! - the voters that precommitted the block finalized in the later round are asked for the prevotes
+//!   of that round supporting their precommit,
+//! - the voters that prevoted a block not building on the block finalized in the earlier round are
+//!   asked for the votes of the previous round, showing that its estimate did not include that
+//!   block.
+//!
+//! The answers lead to votes of earlier rounds, until the round of the first justification is
+//! reached, where the answers conflict with the votes finalizing the block. An honest voter can
+//! always answer. The voters that cast conflicting votes (in the justifications or the answers)
+//! are reported with equivocation proofs in the format accepted by the runtime through
+//! `report_equivocation`, the voters that could not justify their votes are reported as well.
+
+use crate::{warp_proof::WarpSyncProof, AuthorityList, GrandpaJustification, SetId};
+use codec::{Decode, DecodeAll, Encode};
+use finality_grandpa::{voter_set::VoterSet, BlockNumberOps, Message};
+use sp_blockchain::Error as ClientError;
+use sp_consensus_grandpa::{
+	AuthorityId, AuthoritySignature, Equivocation, EquivocationProof, RoundNumber, SignedMessage,
+};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT, NumberFor, One};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// Errors encountered while looking for the voters responsible for conflicting finality.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+	/// One of the justifications is not valid for the given authority set.
+	#[error("Invalid justification: {0}")]
+	InvalidJustification(#[from] ClientError),
+	/// The justifications of different rounds finalize blocks of the same chain.
+	#[error("Justifications of rounds {0} and {1} don't finalize conflicting blocks")]
+	NotConflicting(RoundNumber, RoundNumber),
+	/// The input could not be decoded.
+	#[error("Failed to decode {0}")]
+	Decode(String),
+}
+
+/// A justification together with the id of the authority set that signed it.
+pub struct SetJustification<Block: BlockT> {
+	/// Id of the authority set.
+	pub set_id: SetId,
+	/// The authorities of the set.
+	pub authorities: AuthorityList,
+	/// The justification signed by the set.
+	pub justification: GrandpaJustification<Block>,
+}
+
+/// Decodes a SCALE encoded justification or warp sync proof into justifications, each together
+/// with the authority set that signed it.
+///
+/// The warp sync proof starts at the given authority set. The justifications are not verified.
+pub fn decode_justifications<Block: BlockT>(
+	encoded: &[u8],
+	set_id: SetId,
+	authorities: &AuthorityList,
+) -> Result<Vec<SetJustification<Block>>, Error> {
+	if let Ok(justification) = GrandpaJustification::<Block>::decode_all(&mut &encoded[..]) {
+		return Ok(vec![SetJustification {
+			set_id,
+			authorities: authorities.clone(),
+			justification,
+		}])
+	}
+
+	let proof = WarpSyncProof::<Block>::decode_all(&mut &encoded[..])
+		.map_err(|_| Error::Decode("neither a justification nor a warp sync proof".into()))?;
+	Ok(proof.into_set_justifications(set_id, authorities.clone()))
+}
+
+/// The vote a voter is asked to justify.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Encode, Decode)]
+pub enum QueriedVote<H, N> {
+	/// A precommit for the given block, justified by the prevotes of the same round supporting
+	/// it.
+	Precommit(H, N),
+	/// A prevote for a block not building on the block finalized in the earlier round, justified
+	/// by the prevotes and precommits of the previous round, showing that its estimate did not
+	/// include that block.
+	Prevote,
+}
+
+/// A question to a voter, see [`AccountableSafety`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Encode, Decode)]
+pub struct Query<H, N> {
+	/// The round of the queried vote.
+	pub round: RoundNumber,
+	/// The queried voter.
+	pub voter: AuthorityId,
+	/// The queried vote.
+	pub vote: QueriedVote<H, N>,
+}
+
+/// The answer to a [`Query`].
+#[derive(Debug, Clone, Encode, Decode)]
+pub struct Answer<Header: HeaderT> {
+	/// The signed votes justifying the queried vote.
+	pub votes: Vec<SignedMessage<Header>>,
+	/// The headers proving the ancestry of the voted blocks.
+	pub ancestry: Vec<Header>,
+}
+
+/// The voters found responsible for finalizing conflicting blocks.
+#[derive(Debug, Clone)]
+pub struct Offenders<H, N> {
+	/// Proofs of conflicting votes, accepted by the runtime's `report_equivocation`.
+	pub equivocations: Vec<EquivocationProof<H, N>>,
+	/// The queries that were not answered, or answered with votes not justifying the queried
+	/// vote.
+	pub unjustified: Vec<Query<H, N>>,
+}
+
+type QueryFor<Block> = Query<<Block as BlockT>::Hash, NumberFor<Block>>;
+type MessageFor<Block> = Message<<Block as BlockT>::Hash, NumberFor<Block>>;
+type VoteFor<Block> = (AuthorityId, (<Block as BlockT>::Hash, NumberFor<Block>));
+
+/// The interactive protocol finding the voters responsible for finalizing two conflicting blocks.
+///
+/// The queries returned by [`Self::next_query`] have to be put to the voters and their answers
+/// passed to [`Self::answer`], until no query is left. [`Self::finish`] returns the offenders.
+pub struct AccountableSafety<Block: BlockT> {
+	set_id: SetId,
+	voters: VoterSet<AuthorityId>,
+	/// The round of the earlier justification.
+	round: RoundNumber,
+	/// The block finalized in the earlier round, all the votes of later rounds build on it.
+	finalized: (Block::Hash, NumberFor<Block>),
+	/// The precommits finalizing it.
+	finalized_precommits: Vec<(AuthorityId, Block::Hash, NumberFor<Block>)>,
+	/// The known headers.
+	ancestry: HashMap<Block::Hash, Block::Header>,
+	/// The distinct votes cast by each voter in each round, in the order they were seen.
+	votes: Vec<((RoundNumber, AuthorityId), Vec<(MessageFor<Block>, AuthoritySignature)>)>,
+	/// Index of the votes of each voter in each round.
+	votes_index: HashMap<(RoundNumber, AuthorityId), usize>,
+	queries: VecDeque<QueryFor<Block>>,
+	queried: HashSet<QueryFor<Block>>,
+	unjustified: Vec<QueryFor<Block>>,
+}
+
+impl<Block: BlockT> AccountableSafety<Block>
+where
+	NumberFor<Block>: BlockNumberOps,
+{
+	/// Starts looking for the voters responsible for the two justifications, which must both be
+	/// valid for the authority set `set_id`.
+	///
+	/// Justifications of different rounds must finalize conflicting blocks, as far as it can be
+	/// told from the headers included in them.
+	pub fn new(
+		set_id: SetId,
+		authorities: &AuthorityList,
+		first: &GrandpaJustification<Block>,
+		second: &GrandpaJustification<Block>,
+	) -> Result<Self, Error> {
+		first.verify(set_id, authorities)?;
+		second.verify(set_id, authorities)?;
+		let voters = VoterSet::new(authorities.iter().cloned())
+			.ok_or(ClientError::Consensus(sp_consensus::Error::InvalidAuthoritiesSet))?;
+
+		let (earlier, later) = if first.justification.round <= second.justification.round {
+			(&first.justification, &second.justification)
+		} else {
+			(&second.justification, &first.justification)
+		};
+
+		let mut this = Self {
+			set_id,
+			voters,
+			round: earlier.round,
+			finalized: (earlier.commit.target_hash, earlier.commit.target_number),
+			finalized_precommits: earlier
+				.commit
+				.precommits
+				.iter()
+				.map(|signed| {
+					let precommit = &signed.precommit;
+					(signed.id.clone(), precommit.target_hash, precommit.target_number)
+				})
+				.collect(),
+			ancestry: HashMap::new(),
+			votes: Vec::new(),
+			votes_index: HashMap::new(),
+			queries: VecDeque::new(),
+			queried: HashSet::new(),
+			unjustified: Vec::new(),
+		};
+		for justification in [earlier, later] {
+			this.note_ancestry(justification.votes_ancestries.iter().cloned());
+			for signed in &justification.commit.precommits {
+				let message = Message::Precommit(signed.precommit.clone());
+				this.note_vote(justification.round, &signed.id, message, &signed.signature);
+			}
+		}
+
+		if earlier.round == later.round {
+			return Ok(this)
+		}
+
+		let later_target = (later.commit.target_hash, later.commit.target_number);
+		if this.descends(later_target, this.finalized) == Some(true) ||
+			this.descends(this.finalized, later_target) == Some(true)
+		{
+			return Err(Error::NotConflicting(earlier.round, later.round))
+		}
+
+		for signed in &later.commit.precommits {
+			let precommit = &signed.precommit;
+			this.query(Query {
+				round: later.round,
+				voter: signed.id.clone(),
+				vote: QueriedVote::Precommit(precommit.target_hash, precommit.target_number),
+			});
+		}
+		Ok(this)
+	}
+
+	/// Returns the next query to put to a voter, if any is left.
+	pub fn next_query(&mut self) -> Option<QueryFor<Block>> {
+		self.queries.pop_front()
+	}
+
+	/// Handles the answer to a query returned by [`Self::next_query`], `None` if the voter did not
+	/// answer.
+	pub fn answer(&mut self, query: QueryFor<Block>, answer: Option<Answer<Block::Header>>) {
+		let Some(answer) = answer else { return self.unjustified.push(query) };
+
+		// The votes justifying a precommit are cast in the same round, the ones justifying a
+		// prevote in the previous round.
+		let round = match query.vote {
+			QueriedVote::Precommit(..) => query.round,
+			QueriedVote::Prevote => query.round - 1,
+		};
+		self.note_ancestry(answer.ancestry);
+		let mut prevotes = Vec::new();
+		let mut precommits = Vec::new();
+		for signed in answer.votes {
+			if !self.voters.contains(&signed.id) ||
+				!sp_consensus_grandpa::check_message_signature(
+					&signed.message,
+					&signed.id,
+					&signed.signature,
+					round,
+					self.set_id,
+				) {
+				continue
+			}
+			match signed.message {
+				Message::Prevote(ref prevote) =>
+					prevotes.push((signed.id.clone(), (prevote.target_hash, prevote.target_number))),
+				Message::Precommit(ref precommit) => precommits
+					.push((signed.id.clone(), (precommit.target_hash, precommit.target_number))),
+				Message::PrimaryPropose(_) => continue,
+			}
+			self.note_vote(round, &signed.id, signed.message, &signed.signature);
+		}
+
+		let threshold = self.voters.threshold().get();
+		let finalized = self.finalized;
+		let follow_ups = match query.vote.clone() {
+			QueriedVote::Precommit(hash, number) => {
+				// An honest voter precommits a block supported by a supermajority of prevotes.
+				let supporters = prevotes
+					.iter()
+					.filter(|(_, block)| self.descends(*block, (hash, number)) == Some(true))
+					.map(|(voter, _)| voter);
+				if self.weight(supporters) < threshold {
+					return self.unjustified.push(query)
+				}
+
+				if round > self.round {
+					self.against_finalized(&prevotes)
+						.map(|(voter, _)| Query { round, voter, vote: QueriedVote::Prevote })
+						.collect()
+				} else {
+					Vec::new()
+				}
+			},
+			QueriedVote::Prevote => {
+				// An honest voter prevotes a block building on the estimate of the previous
+				// round, which does not include the finalized block if it is not supported by a
+				// supermajority of prevotes, or if it can't be supported by a supermajority of
+				// precommits anymore. Votes of unknown ancestry are taken as supporting it.
+				let prevoted = prevotes
+					.iter()
+					.filter(|(_, block)| self.descends(*block, finalized) != Some(false))
+					.map(|(voter, _)| voter);
+				let ghost_excludes = self.weight(prevoted) < threshold;
+
+				let precommitted = precommits
+					.iter()
+					.filter(|(_, block)| self.descends(*block, finalized) != Some(false))
+					.map(|(voter, _)| voter);
+				let missing = self.voters.total_weight().get() -
+					self.weight(precommits.iter().map(|(voter, _)| voter));
+				let completable_excludes = self.weight(precommitted) + missing < threshold;
+
+				if !ghost_excludes && !completable_excludes {
+					return self.unjustified.push(query)
+				}
+
+				// In the round of the finalized block, the precommits excluding it conflict with
+				// the ones finalizing it.
+				let mut follow_ups = Vec::new();
+				if ghost_excludes && round > self.round {
+					follow_ups.extend(self.against_finalized(&prevotes).map(|(voter, _)| Query {
+						round,
+						voter,
+						vote: QueriedVote::Prevote,
+					}));
+				} else if ghost_excludes {
+					// The voters that finalized the block must have seen a supermajority of
+					// prevotes for it.
+					follow_ups.extend(self.finalized_precommits.iter().map(
+						|(voter, hash, number)| Query {
+							round,
+							voter: voter.clone(),
+							vote: QueriedVote::Precommit(*hash, *number),
+						},
+					));
+				}
+				if completable_excludes && round > self.round {
+					follow_ups.extend(self.against_finalized(&precommits).map(
+						|(voter, (hash, number))| Query {
+							round,
+							voter,
+							vote: QueriedVote::Precommit(hash, number),
+						},
+					));
+				}
+				follow_ups
+			},
+		};
+
+		for query in follow_ups {
+			self.query(query);
+		}
+	}
+
+	/// Runs the protocol to completion, getting the answers from `ask`.
+	pub fn run(
+		mut self,
+		mut ask: impl FnMut(&QueryFor<Block>) -> Option<Answer<Block::Header>>,
+	) -> Offenders<Block::Hash, NumberFor<Block>> {
+		while let Some(query) = self.next_query() {
+			let answer = ask(&query);
+			self.answer(query, answer);
+		}
+		self.finish()
+	}
+
+	/// Returns the offenders found so far, the queries not answered yet are taken as unjustified.
+	pub fn finish(self) -> Offenders<Block::Hash, NumberFor<Block>> {
+		let mut equivocations = Vec::new();
+		for ((round, voter), votes) in &self.votes {
+			let prevotes = votes.iter().filter_map(|(message, signature)| match message {
+				Message::Prevote(prevote) => Some((prevote.clone(), signature.clone())),
+				_ => None,
+			});
+			if let Some(equivocation) = equivocation(*round, voter, prevotes) {
+				equivocations
+					.push(EquivocationProof::new(self.set_id, Equivocation::Prevote(equivocation)));
+			}
+
+			let precommits = votes.iter().filter_map(|(message, signature)| match message {
+				Message::Precommit(precommit) => Some((precommit.clone(), signature.clone())),
+				_ => None,
+			});
+			if let Some(equivocation) = equivocation(*round, voter, precommits) {
+				equivocations.push(EquivocationProof::new(
+					self.set_id,
+					Equivocation::Precommit(equivocation),
+				));
+			}
+		}
+
+		let mut unjustified = self.unjustified;
+		unjustified.extend(self.queries);
+		Offenders { equivocations, unjustified }
+	}
+
+	fn query(&mut self, query: QueryFor<Block>) {
+		if self.queried.insert(query.clone()) {
+			self.queries.push_back(query);
+		}
+	}
+
+	fn note_ancestry(&mut self, headers: impl IntoIterator<Item = Block::Header>) {
+		self.ancestry.extend(headers.into_iter().map(|header| (header.hash(), header)));
+	}
+
+	fn note_vote(
+		&mut self,
+		round: RoundNumber,
+		voter: &AuthorityId,
+		message: MessageFor<Block>,
+		signature: &AuthoritySignature,
+	) {
+		let index = *self.votes_index.entry((round, voter.clone())).or_insert_with(|| {
+			self.votes.push(((round, voter.clone()), Vec::new()));
+			self.votes.len() - 1
+		});
+		let votes = &mut self.votes[index].1;
+		if !votes.iter().any(|(seen, _)| *seen == message) {
+			votes.push((message, signature.clone()));
+		}
+	}
+
+	/// Returns the votes for blocks not building on the finalized block.
+	fn against_finalized<'a>(
+		&'a self,
+		votes: &'a [VoteFor<Block>],
+	) -> impl Iterator<Item = VoteFor<Block>> + 'a {
+		votes
+			.iter()
+			.filter(|(_, block)| self.descends(*block, self.finalized) == Some(false))
+			.cloned()
+	}
+
+	/// Returns the total weight of the given voters, counting each voter once.
+	fn weight<'a>(&self, voters: impl Iterator<Item = &'a AuthorityId>) -> u64 {
+		let voters = voters.collect::<HashSet<_>>();
+		voters
+			.into_iter()
+			.filter_map(|voter| self.voters.get(voter))
+			.map(|info| info.weight().get())
+			.sum()
+	}
+
+	/// Returns whether `block` is `base` or one of its descendants, `None` if the known headers
+	/// don't tell.
+	fn descends(
+		&self,
+		block: (Block::Hash, NumberFor<Block>),
+		base: (Block::Hash, NumberFor<Block>),
+	) -> Option<bool> {
+		let (mut hash, mut number) = block;
+		if number < base.1 {
+			return Some(false)
+		}
+		while number > base.1 {
+			let header = self.ancestry.get(&hash).filter(|header| *header.number() == number)?;
+			hash = *header.parent_hash();
+			number = number - One::one();
+		}
+		Some(hash == base.0)
+	}
+}
+
+/// Returns an equivocation of the voter if it signed two different votes.
+fn equivocation<V: Clone + Eq>(
+	round: RoundNumber,
+	voter: &AuthorityId,
+	mut votes: impl Iterator<Item = (V, AuthoritySignature)>,
+) -> Option<finality_grandpa::Equivocation<AuthorityId, V, AuthoritySignature>> {
+	let first = votes.next()?;
+	let second = votes.find(|(vote, _)| *vote != first.0)?;
+	Some(finality_grandpa::Equivocation {
+		round_number: round,
+		identity: voter.clone(),
+		first,
+		second,
+	})
+}
+
+/// Finds the voters responsible for finalizing conflicting blocks in any two justifications of the
+/// same authority set, getting the answers to the queries from `ask`.
+///
+/// Justifications of different rounds finalizing blocks of the same chain are skipped.
+pub fn find_offenders_in_sets<Block: BlockT>(
+	first: &[SetJustification<Block>],
+	second: &[SetJustification<Block>],
+	mut ask: impl FnMut(&QueryFor<Block>) -> Option<Answer<Block::Header>>,
+) -> Result<Offenders<Block::Hash, NumberFor<Block>>, Error>
+where
+	NumberFor<Block>: BlockNumberOps,
+{
+	let mut offenders = Offenders { equivocations: Vec::new(), unjustified: Vec::new() };
+	for first in first {
+		for second in second.iter().filter(|second| second.set_id == first.set_id) {
+			let protocol = match AccountableSafety::new(
+				first.set_id,
+				&first.authorities,
+				&first.justification,
+				&second.justification,
+			) {
+				Ok(protocol) => protocol,
+				Err(Error::NotConflicting(..)) => continue,
+				Err(err) => return Err(err),
+			};
+			let found = protocol.run(&mut ask);
+			offenders.equivocations.extend(found.equivocations);
+			offenders.unjustified.extend(found.unjustified);
+		}
+	}
+	Ok(offenders)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use assert_matches::assert_matches;
+	use sp_keyring::Ed25519Keyring::{self, *};
+	use substrate_test_runtime_client::runtime::{Block, Header, H256};
+
+	const SET_ID: SetId = 1;
+
+	type Offenders = super::Offenders<H256, u64>;
+
+	fn authorities() -> AuthorityList {
+		[Alice, Bob, Charlie, Dave]
+			.iter()
+			.map(|keyring| (keyring.public().into(), 1))
+			.collect()
+	}
+
+	fn signed(
+		voter: Ed25519Keyring,
+		round: u64,
+		message: Message<H256, u64>,
+	) -> SignedMessage<Header> {
+		let encoded = sp_consensus_grandpa::localized_payload(round, SET_ID, &message);
+		finality_grandpa::SignedMessage {
+			message,
+			signature: voter.sign(&encoded[..]).into(),
+			id: voter.public().into(),
+		}
+	}
+
+	fn prevote(voter: Ed25519Keyring, round: u64, target_hash: H256) -> SignedMessage<Header> {
+		signed(
+			voter,
+			round,
+			Message::Prevote(finality_grandpa::Prevote { target_hash, target_number: 1 }),
+		)
+	}
+
+	fn precommit(
+		voter: Ed25519Keyring,
+		round: u64,
+		target_hash: H256,
+		target_number: u64,
+	) -> SignedMessage<Header> {
+		let precommit = finality_grandpa::Precommit { target_hash, target_number };
+		signed(voter, round, Message::Precommit(precommit))
+	}
+
+	fn justification_with(
+		round: u64,
+		target_hash: H256,
+		precommits: Vec<SignedMessage<Header>>,
+		votes_ancestries: Vec<Header>,
+	) -> GrandpaJustification<Block> {
+		let precommits = precommits
+			.into_iter()
+			.map(|signed| match signed.message {
+				Message::Precommit(precommit) => finality_grandpa::SignedPrecommit {
+					precommit,
+					signature: signed.signature,
+					id: signed.id,
+				},
+				_ => panic!("only precommits are part of a justification"),
+			})
+			.collect();
+
+		sp_consensus_grandpa::GrandpaJustification {
+			round,
+			commit: finality_grandpa::Commit { target_hash, target_number: 1, precommits },
+			votes_ancestries,
+		}
+		.into()
+	}
+
+	fn justification(
+		round: u64,
+		target_hash: H256,
+		voters: &[Ed25519Keyring],
+	) -> GrandpaJustification<Block> {
+		let precommits =
+			voters.iter().map(|voter| precommit(*voter, round, target_hash, 1)).collect();
+		justification_with(round, target_hash, precommits, Vec::new())
+	}
+
+	fn offenders(
+		first: &GrandpaJustification<Block>,
+		second: &GrandpaJustification<Block>,
+		ask: impl FnMut(&Query<H256, u64>) -> Option<Answer<Header>>,
+	) -> Offenders {
+		AccountableSafety::new(SET_ID, &authorities(), first, second).unwrap().run(ask)
+	}
+
+	fn offender_ids(offenders: &Offenders) -> Vec<AuthorityId> {
+		offenders.equivocations.iter().map(|proof| proof.offender().clone()).collect()
+	}
+
+	#[test]
+	fn finds_voters_precommitting_conflicting_blocks() {
+		let first = justification(5, H256::repeat_byte(1), &[Alice, Bob, Charlie]);
+		let second = justification(5, H256::repeat_byte(2), &[Bob, Charlie, Dave]);
+
+		let found = offenders(&first, &second, |_| unreachable!("no query in the same round"));
+
+		assert_eq!(offender_ids(&found), vec![Bob.public().into(), Charlie.public().into()]);
+		assert!(found.unjustified.is_empty());
+		for proof in found.equivocations {
+			assert_eq!(proof.set_id(), SET_ID);
+			assert_eq!(proof.round(), 5);
+			assert!(sp_consensus_grandpa::check_equivocation_proof(proof));
+		}
+
+		// the same justification doesn't contain any equivocation.
+		assert!(offenders(&first, &first, |_| None).equivocations.is_empty());
+	}
+
+	#[test]
+	fn finds_double_precommits_within_a_justification() {
+		let first_hash = H256::repeat_byte(1);
+		let child =
+			Header::new(2, Default::default(), Default::default(), first_hash, Default::default());
+		let justification = justification_with(
+			5,
+			first_hash,
+			vec![
+				precommit(Alice, 5, first_hash, 1),
+				precommit(Bob, 5, first_hash, 1),
+				precommit(Charlie, 5, first_hash, 1),
+				precommit(Alice, 5, child.hash(), 2),
+			],
+			vec![child],
+		);
+
+		let found = offenders(&justification, &justification, |_| None);
+		assert_eq!(offender_ids(&found), vec![Alice.public().into()]);
+		assert!(sp_consensus_grandpa::check_equivocation_proof(found.equivocations[0].clone()));
+	}
+
+	#[test]
+	fn queries_voters_of_different_rounds() {
+		let (first_hash, second_hash) = (H256::repeat_byte(1), H256::repeat_byte(2));
+		let first = justification(5, first_hash, &[Alice, Bob, Charlie]);
+		let second = justification(7, second_hash, &[Bob, Charlie, Dave]);
+
+		// Bob and Charlie finalized the first block in round 5, and precommitted the second one in
+		// the same round to make the voters of later rounds ignore the first one.
+		let found = offenders(&first, &second, |query| {
+			let votes = match (query.round, &query.vote) {
+				(7, QueriedVote::Precommit(..)) =>
+					[Bob, Charlie, Dave].map(|voter| prevote(voter, 7, second_hash)).to_vec(),
+				(7, QueriedVote::Prevote) =>
+					[Bob, Charlie, Dave].map(|voter| prevote(voter, 6, second_hash)).to_vec(),
+				(6, QueriedVote::Prevote) =>
+					[Bob, Charlie, Dave].map(|voter| precommit(voter, 5, second_hash, 1)).to_vec(),
+				(5, QueriedVote::Precommit(..)) =>
+					[Alice, Bob, Charlie].map(|voter| prevote(voter, 5, first_hash)).to_vec(),
+				_ => return None,
+			};
+			Some(Answer { votes, ancestry: Vec::new() })
+		});
+
+		assert_eq!(offender_ids(&found), vec![Bob.public().into(), Charlie.public().into()]);
+		assert!(found.unjustified.is_empty());
+		for proof in found.equivocations {
+			assert_eq!(proof.round(), 5);
+			assert!(sp_consensus_grandpa::check_equivocation_proof(proof));
+		}
+
+		// the voters not answering are reported.
+		let found = offenders(&first, &second, |_| None);
+		assert!(found.equivocations.is_empty());
+		let unjustified =
+			found.unjustified.iter().map(|query| query.voter.clone()).collect::<Vec<_>>();
+		assert_eq!(
+			unjustified,
+			vec![Bob.public().into(), Charlie.public().into(), Dave.public().into()]
+		);
+
+		// and so are the ones answering with votes not justifying their vote.
+		let found = offenders(&first, &second, |query| {
+			Some(Answer {
+				votes: vec![prevote(Alice, query.round, second_hash)],
+				ancestry: Vec::new(),
+			})
+		});
+		assert_eq!(found.unjustified.len(), 3);
+	}
+
+	#[test]
+	fn rejects_invalid_and_not_conflicting_justifications() {
+		let first = justification(5, H256::repeat_byte(1), &[Alice, Bob, Charlie]);
+
+		// not enough voters to finalize the block.
+		let second = justification(5, H256::repeat_byte(2), &[Charlie, Dave]);
+		assert_matches!(
+			AccountableSafety::new(SET_ID, &authorities(), &first, &second).err(),
+			Some(Error::InvalidJustification(_))
+		);
+
+		let second = justification(6, H256::repeat_byte(1), &[Bob, Charlie, Dave]);
+		assert_matches!(
+			AccountableSafety::new(SET_ID, &authorities(), &first, &second).err(),
+			Some(Error::NotConflicting(5, 6))
+		);
+		let sets = |justification| {
+			vec![SetJustification { set_id: SET_ID, authorities: authorities(), justification }]
+		};
+		let found = find_offenders_in_sets(&sets(first), &sets(second), |_| None).unwrap();
+		assert!(found.equivocations.is_empty() && found.unjustified.is_empty());
+	}
+}
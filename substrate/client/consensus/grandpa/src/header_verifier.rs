@@ -0,0 +1,338 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Verification of the GRANDPA justifications of headers downloaded ahead of their parents'
+//! import.
+
+use crate::{
+	authorities::{DelayKind, SharedAuthoritySet},
+	find_forced_change, find_scheduled_change, GrandpaJustification,
+};
+use finality_grandpa::{voter_set::VoterSet, BlockNumberOps};
+use parking_lot::Mutex;
+use sc_client_api::utils::is_descendent_of;
+use sc_network_sync::strategy::header_first::HeaderVerifier;
+use sp_blockchain::{Error as ClientError, HeaderBackend, HeaderMetadata};
+use sp_consensus_grandpa::{AuthorityId, AuthorityList, SetId, GRANDPA_ENGINE_ID};
+use sp_runtime::{
+	traits::{Block as BlockT, Header, NumberFor},
+	Justifications,
+};
+use std::{collections::VecDeque, fmt, sync::Arc};
+
+/// Number of verified headers whose authority sets are kept. A header failing verification is
+/// followed by another header with the same parent, which may have passed this verifier already.
+const MAX_TRACKED_HEADERS: usize = 2;
+
+/// An authority set change signaled on the header chain.
+#[derive(Clone)]
+struct Change<N> {
+	/// Number of the block enacting the change.
+	effective_number: N,
+	authorities: AuthorityList,
+	forced: bool,
+}
+
+/// The authority set following a verified header.
+#[derive(Clone)]
+struct TrackedSet<N> {
+	set_id: SetId,
+	voters: Arc<VoterSet<AuthorityId>>,
+	/// The changes not enacted yet, in the order they were signaled.
+	pending: Vec<Change<N>>,
+}
+
+impl<N: Ord + Copy> TrackedSet<N> {
+	/// Enact the `forced` or standard changes effective at or before `number`.
+	fn enact(&mut self, number: N, forced: bool) -> Result<(), String> {
+		let (enacted, pending) =
+			std::mem::take(&mut self.pending).into_iter().partition::<Vec<_>, _>(|change| {
+				change.forced == forced && change.effective_number <= number
+			});
+		self.pending = pending;
+
+		for change in enacted {
+			self.voters = Arc::new(
+				VoterSet::new(change.authorities)
+					.ok_or_else(|| "Invalid authority set change".to_string())?,
+			);
+			self.set_id += 1;
+		}
+		Ok(())
+	}
+}
+
+/// Verifies the GRANDPA justifications of the headers downloaded by header-first sync.
+///
+/// The headers are verified before their parents are imported, so the authority set is followed
+/// through the authority set change digests of the verified headers, starting from the authority
+/// set of the block import at the last imported block. Blocks enacting a standard change must come
+/// with a justification.
+pub struct GrandpaHeaderVerifier<Block: BlockT, Client> {
+	client: Arc<Client>,
+	authority_set: SharedAuthoritySet<Block::Hash, NumberFor<Block>>,
+	/// The authority sets following the last verified headers, most recent last.
+	tracked: Mutex<VecDeque<(Block::Hash, TrackedSet<NumberFor<Block>>)>>,
+}
+
+impl<Block: BlockT, Client> GrandpaHeaderVerifier<Block, Client> {
+	/// Create a new verifier following the authority set of the block import.
+	pub fn new(
+		client: Arc<Client>,
+		authority_set: SharedAuthoritySet<Block::Hash, NumberFor<Block>>,
+	) -> Self {
+		Self { client, authority_set, tracked: Mutex::new(VecDeque::new()) }
+	}
+}
+
+impl<Block: BlockT, Client> fmt::Debug for GrandpaHeaderVerifier<Block, Client> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.debug_struct("GrandpaHeaderVerifier").finish()
+	}
+}
+
+impl<Block, Client> GrandpaHeaderVerifier<Block, Client>
+where
+	Block: BlockT,
+	NumberFor<Block>: BlockNumberOps,
+	Client: HeaderBackend<Block> + HeaderMetadata<Block, Error = ClientError>,
+{
+	/// The authority set following the imported block `parent_hash`.
+	fn imported_set(
+		&self,
+		parent_hash: Block::Hash,
+	) -> Result<TrackedSet<NumberFor<Block>>, String> {
+		let parent_number = self
+			.client
+			.number(parent_hash)
+			.map_err(|e| e.to_string())?
+			.ok_or_else(|| format!("Parent {parent_hash:?} is not imported"))?;
+		let is_descendent_of = is_descendent_of(&*self.client, None);
+
+		let authority_set = self.authority_set.inner();
+		let (set_id, authorities) = authority_set.current();
+		let mut pending = Vec::new();
+		for change in authority_set.pending_changes() {
+			if change.canon_hash == parent_hash ||
+				is_descendent_of(&change.canon_hash, &parent_hash).map_err(|e| e.to_string())?
+			{
+				pending.push(Change {
+					effective_number: change.effective_number(),
+					authorities: change.next_authorities.clone(),
+					forced: matches!(change.delay_kind, DelayKind::Best { .. }),
+				});
+			}
+		}
+		pending.sort_by_key(|change| change.effective_number);
+
+		let voters = VoterSet::new(authorities.iter().cloned())
+			.ok_or_else(|| "Invalid authority set".to_string())?;
+		let mut set = TrackedSet { set_id, voters: Arc::new(voters), pending };
+		// The changes enacted on the chain of the parent take effect once the enacting block is
+		// finalized, by which point any later justification is signed by the new set.
+		set.enact(parent_number, true)?;
+		set.enact(parent_number, false)?;
+		Ok(set)
+	}
+
+	/// Check the justification of `header` against the authority set following its parent,
+	/// `parent`.
+	fn check(
+		&self,
+		parent: &TrackedSet<NumberFor<Block>>,
+		header: &Block::Header,
+		justifications: Option<&Justifications>,
+	) -> Result<TrackedSet<NumberFor<Block>>, String> {
+		let number = *header.number();
+		let mut set = parent.clone();
+
+		if let Some(change) = find_scheduled_change::<Block>(header) {
+			set.pending.push(Change {
+				effective_number: number + change.delay,
+				authorities: change.next_authorities,
+				forced: false,
+			});
+		}
+		if let Some((_, change)) = find_forced_change::<Block>(header) {
+			set.pending.push(Change {
+				effective_number: number + change.delay,
+				authorities: change.next_authorities,
+				forced: true,
+			});
+		}
+
+		// Forced changes are enacted on import, standard changes once the enacting block is
+		// finalized by the current set.
+		set.enact(number, true)?;
+		let justification = justifications.and_then(|j| j.get(GRANDPA_ENGINE_ID));
+		if let Some(justification) = justification {
+			GrandpaJustification::<Block>::decode_and_verify_finalizes(
+				justification,
+				(header.hash(), number),
+				set.set_id,
+				&set.voters,
+			)
+			.map_err(|e| e.to_string())?;
+		}
+
+		let enacts_change = set
+			.pending
+			.iter()
+			.any(|change| !change.forced && change.effective_number <= number);
+		if enacts_change && justification.is_none() {
+			return Err(format!(
+				"Missing justification for block #{number} enacting an authority set change"
+			))
+		}
+		set.enact(number, false)?;
+
+		Ok(set)
+	}
+}
+
+impl<Block, Client> HeaderVerifier<Block> for GrandpaHeaderVerifier<Block, Client>
+where
+	Block: BlockT,
+	NumberFor<Block>: BlockNumberOps,
+	Client: HeaderBackend<Block> + HeaderMetadata<Block, Error = ClientError> + Send + Sync,
+{
+	fn verify(
+		&self,
+		header: &Block::Header,
+		justifications: Option<&Justifications>,
+	) -> Result<(), String> {
+		let parent_hash = *header.parent_hash();
+
+		let tracked = self
+			.tracked
+			.lock()
+			.iter()
+			.find(|(hash, _)| *hash == parent_hash)
+			.map(|(_, set)| set.clone());
+		let parent = match tracked {
+			Some(parent) => parent,
+			None => self.imported_set(parent_hash)?,
+		};
+		let set = self.check(&parent, header, justifications)?;
+
+		let mut tracked = self.tracked.lock();
+		if tracked.len() == MAX_TRACKED_HEADERS {
+			tracked.pop_front();
+		}
+		tracked.push_back((header.hash(), set));
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::AuthoritySet;
+	use codec::Encode;
+	use finality_grandpa::{Commit, Precommit, SignedPrecommit};
+	use sp_consensus_grandpa::{ConsensusLog, ScheduledChange};
+	use sp_keyring::Ed25519Keyring::{self, *};
+	use sp_runtime::{generic::Digest, DigestItem};
+	use substrate_test_runtime_client::runtime::{Block, Header as TestHeader, H256};
+
+	fn authorities(voters: &[Ed25519Keyring]) -> AuthorityList {
+		voters.iter().map(|voter| (voter.public().into(), 1)).collect()
+	}
+
+	fn header(number: u64, parent_hash: H256, logs: Vec<DigestItem>) -> TestHeader {
+		TestHeader::new(number, H256::zero(), H256::zero(), parent_hash, Digest { logs })
+	}
+
+	fn scheduled_change(voters: &[Ed25519Keyring], delay: u64) -> DigestItem {
+		let change = ScheduledChange { next_authorities: authorities(voters), delay };
+		DigestItem::Consensus(GRANDPA_ENGINE_ID, ConsensusLog::ScheduledChange(change).encode())
+	}
+
+	fn justifications(
+		header: &TestHeader,
+		set_id: SetId,
+		voters: &[Ed25519Keyring],
+	) -> Justifications {
+		let (target_hash, target_number) = (header.hash(), *header.number());
+		let precommits = voters
+			.iter()
+			.map(|voter| {
+				let precommit = Precommit { target_hash, target_number };
+				let message = finality_grandpa::Message::Precommit(precommit.clone());
+				let encoded = sp_consensus_grandpa::localized_payload(1, set_id, &message);
+				SignedPrecommit {
+					precommit,
+					signature: voter.sign(&encoded[..]).into(),
+					id: voter.public().into(),
+				}
+			})
+			.collect();
+		let justification: GrandpaJustification<Block> =
+			sp_consensus_grandpa::GrandpaJustification {
+				round: 1,
+				commit: Commit { target_hash, target_number, precommits },
+				votes_ancestries: Vec::new(),
+			}
+			.into();
+		(GRANDPA_ENGINE_ID, justification.encode()).into()
+	}
+
+	#[test]
+	fn follows_authority_set_changes() {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let genesis_hash = client.info().genesis_hash;
+		let authority_set = AuthoritySet::genesis(authorities(&[Alice, Bob, Charlie])).unwrap();
+		let verifier = GrandpaHeaderVerifier::<Block, _>::new(client, authority_set.into());
+
+		// #1 schedules a change to Dave, enacted by #2.
+		let header1 = header(1, genesis_hash, vec![scheduled_change(&[Dave], 1)]);
+		let header2 = header(2, header1.hash(), Vec::new());
+		let header3 = header(3, header2.hash(), Vec::new());
+		assert_eq!(verifier.verify(&header1, None), Ok(()));
+
+		// the enacting block must be finalized by the current set.
+		assert!(verifier.verify(&header2, None).is_err());
+		assert!(verifier.verify(&header2, Some(&justifications(&header2, 1, &[Dave]))).is_err());
+		let old_set = justifications(&header2, 0, &[Alice, Bob, Charlie]);
+		assert_eq!(verifier.verify(&header2, Some(&old_set)), Ok(()));
+
+		// the later blocks are finalized by the new set.
+		let old_set = justifications(&header3, 0, &[Alice, Bob, Charlie]);
+		assert!(verifier.verify(&header3, Some(&old_set)).is_err());
+		assert_eq!(verifier.verify(&header3, Some(&justifications(&header3, 1, &[Dave]))), Ok(()));
+	}
+
+	#[test]
+	fn rejects_justifications_of_other_blocks() {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let genesis_hash = client.info().genesis_hash;
+		let authority_set = AuthoritySet::genesis(authorities(&[Alice, Bob, Charlie])).unwrap();
+		let verifier = GrandpaHeaderVerifier::<Block, _>::new(client, authority_set.into());
+
+		let header1 = header(1, genesis_hash, Vec::new());
+		let other = header(1, genesis_hash, vec![scheduled_change(&[Dave], 0)]);
+		let justification = justifications(&other, 0, &[Alice, Bob, Charlie]);
+		assert!(verifier.verify(&header1, Some(&justification)).is_err());
+
+		// too few voters
+		let justification = justifications(&header1, 0, &[Alice, Bob]);
+		assert!(verifier.verify(&header1, Some(&justification)).is_err());
+		let justification = justifications(&header1, 0, &[Alice, Bob, Charlie]);
+		assert_eq!(verifier.verify(&header1, Some(&justification)), Ok(()));
+	}
+}
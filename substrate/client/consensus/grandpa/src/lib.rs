@@ -113,11 +113,13 @@ macro_rules! grandpa_log {
 	};
 }
 
+pub mod accountable_safety;
 mod authorities;
 mod aux_schema;
 mod communication;
 mod environment;
 mod finality_proof;
+mod header_verifier;
 mod import;
 mod justification;
 mod notification;
@@ -131,6 +133,7 @@ pub use aux_schema::best_justification;
 pub use communication::grandpa_protocol_name::standard_name as protocol_standard_name;
 pub use finality_grandpa::voter::report;
 pub use finality_proof::{FinalityProof, FinalityProofError, FinalityProofProvider};
+pub use header_verifier::GrandpaHeaderVerifier;
 pub use import::{find_forced_change, find_scheduled_change, GrandpaBlockImport};
 pub use justification::GrandpaJustification;
 pub use notification::{GrandpaJustificationSender, GrandpaJustificationStream};
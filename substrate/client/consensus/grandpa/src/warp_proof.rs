@@ -20,8 +20,9 @@
 use codec::{Decode, DecodeAll, Encode};
 
 use crate::{
-	best_justification, find_scheduled_change, AuthoritySetChanges, AuthoritySetHardFork,
-	BlockNumberOps, GrandpaJustification, SharedAuthoritySet,
+	accountable_safety::SetJustification, best_justification, find_scheduled_change,
+	AuthoritySetChanges, AuthoritySetHardFork, BlockNumberOps, GrandpaJustification,
+	SharedAuthoritySet,
 };
 use sc_client_api::Backend as ClientBackend;
 use sc_network_sync::strategy::warp::{EncodedProof, VerificationResult, WarpSyncProvider};
@@ -196,6 +197,35 @@ impl<Block: BlockT> WarpSyncProof<Block> {
 		Ok(final_outcome)
 	}
 
+	/// Splits the proof into its justifications, each together with the authority set that signed
+	/// it, starting at the given set id and with the given authorities.
+	///
+	/// The justifications are not verified.
+	pub fn into_set_justifications(
+		self,
+		set_id: SetId,
+		authorities: AuthorityList,
+	) -> Vec<SetJustification<Block>> {
+		let mut current_set_id = set_id;
+		let mut current_authorities = authorities;
+		let mut justifications = Vec::with_capacity(self.proofs.len());
+
+		for WarpSyncFragment { header, justification } in self.proofs {
+			let scheduled_change = find_scheduled_change::<Block>(&header);
+			justifications.push(SetJustification {
+				set_id: current_set_id,
+				authorities: current_authorities.clone(),
+				justification,
+			});
+
+			if let Some(scheduled_change) = scheduled_change {
+				current_authorities = scheduled_change.next_authorities;
+				current_set_id += 1;
+			}
+		}
+		justifications
+	}
+
 	/// Verifies the warp sync proof starting at the given set id and with the given authorities.
 	/// Verification stops when either the proof is exhausted or finality for the target header can
 	/// be proven. If the proof is valid the new set id and authorities is returned.
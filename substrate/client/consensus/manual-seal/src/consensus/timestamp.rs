@@ -20,6 +20,7 @@
 //! that expect this inherent.
 
 use crate::Error;
+use parking_lot::Mutex;
 use sc_client_api::{AuxStore, UsageProvider};
 use sp_api::ProvideRuntimeApi;
 use sp_blockchain::HeaderBackend;
@@ -122,6 +123,15 @@ impl SlotTimestampProvider {
 		Ok(time)
 	}
 
+	/// Apply the adjustments requested through the given [`TimeTravel`] handle.
+	///
+	/// The adjustments are consumed, so they only affect the block that is about to be created.
+	/// Blocks built on top of it will continue from its timestamp.
+	pub fn with_time_travel(self, time_travel: &TimeTravel) -> Result<Self, Error> {
+		let time = time_travel.take_and_apply(self.unix_millis.into_inner())?;
+		Ok(Self { unix_millis: atomic::AtomicU64::new(time), slot_duration: self.slot_duration })
+	}
+
 	/// Get the current slot number
 	pub fn slot(&self) -> Slot {
 		Slot::from_timestamp(
@@ -136,6 +146,107 @@ impl SlotTimestampProvider {
 	}
 }
 
+/// Adjustments of the timestamp for the next block, see [`TimeTravel`].
+#[derive(Debug, Default)]
+struct PendingTimeTravel {
+	// exact timestamp requested for the next block
+	next_timestamp: Option<u64>,
+	// milliseconds to add to the timestamp of the next block
+	increase: u64,
+}
+
+/// State shared by all clones of a [`TimeTravel`] handle.
+#[derive(Debug, Default)]
+struct TimeTravelState {
+	// adjustments for the next block only
+	pending: PendingTimeTravel,
+	// milliseconds all blocks built with `TimeTravel::timestamp_provider` are shifted by
+	offset: u64,
+}
+
+/// Shared handle that allows moving the clock of the [`SlotTimestampProvider`] forward.
+///
+/// This is usually driven over RPC (`engine_setNextTimestamp` and `engine_increaseTime`) and
+/// applied while creating the inherent data providers for the next block, either to a
+/// [`SlotTimestampProvider`] with [`SlotTimestampProvider::with_time_travel`] or to a plain
+/// clock with [`TimeTravel::timestamp_provider`].
+#[derive(Clone, Debug, Default)]
+pub struct TimeTravel {
+	state: Arc<Mutex<TimeTravelState>>,
+}
+
+impl TimeTravel {
+	/// Create a new handle without any pending adjustment.
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Use the given unix millisecond timestamp for the next block.
+	///
+	/// This overrides any increase requested before.
+	pub fn set_next_timestamp(&self, timestamp: u64) {
+		let mut state = self.state.lock();
+		let pending = &mut state.pending;
+		pending.next_timestamp = Some(timestamp);
+		pending.increase = 0;
+	}
+
+	/// Move the timestamp of the next block forward by `millis` milliseconds.
+	pub fn increase_time(&self, millis: u64) {
+		let mut state = self.state.lock();
+		let pending = &mut state.pending;
+		match pending.next_timestamp.as_mut() {
+			Some(timestamp) => *timestamp = timestamp.saturating_add(millis),
+			None => pending.increase = pending.increase.saturating_add(millis),
+		}
+	}
+
+	/// Drop all pending adjustments.
+	///
+	/// The offset accumulated by [`TimeTravel::timestamp_provider`] is kept, so the clock never
+	/// goes back behind blocks that were already built.
+	pub fn reset(&self) {
+		self.state.lock().pending = Default::default();
+	}
+
+	/// Create a timestamp inherent data provider for the next block from `timestamp`, the unix
+	/// millisecond timestamp the block would have without time travel, e.g. the system time.
+	///
+	/// Unlike [`SlotTimestampProvider::with_time_travel`], which continues from the timestamp of
+	/// the best block, the adjustments made here accumulate into an offset that is applied to
+	/// all later blocks as well.
+	pub fn timestamp_provider(
+		&self,
+		timestamp: u64,
+	) -> Result<sp_timestamp::InherentDataProvider, Error> {
+		let mut state = self.state.lock();
+		let target = Self::apply(
+			std::mem::take(&mut state.pending),
+			timestamp.saturating_add(state.offset),
+		)?;
+		state.offset = target.saturating_sub(timestamp);
+		Ok(sp_timestamp::InherentDataProvider::new(target.into()))
+	}
+
+	/// Consume the pending adjustments and apply them to `time`, the timestamp the next block
+	/// would have otherwise.
+	fn take_and_apply(&self, time: u64) -> Result<u64, Error> {
+		let pending = std::mem::take(&mut self.state.lock().pending);
+		Self::apply(pending, time)
+	}
+
+	fn apply(pending: PendingTimeTravel, time: u64) -> Result<u64, Error> {
+		match pending.next_timestamp {
+			Some(timestamp) if timestamp < time => Err(Error::StringError(format!(
+				"Next timestamp {} is before the earliest allowed timestamp {}",
+				timestamp, time
+			))),
+			Some(timestamp) => Ok(timestamp),
+			None => Ok(time.saturating_add(pending.increase)),
+		}
+	}
+}
+
 #[async_trait::async_trait]
 impl InherentDataProvider for SlotTimestampProvider {
 	async fn provide_inherent_data(
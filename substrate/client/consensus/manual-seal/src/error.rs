@@ -35,6 +35,8 @@ mod codes {
 	pub const CONSENSUS_ERROR: i32 = 14_000;
 	pub const INHERENTS_ERROR: i32 = 15_000;
 	pub const BLOCKCHAIN_ERROR: i32 = 16_000;
+	pub const NOT_ENABLED: i32 = 17_000;
+	pub const TOO_MANY_BLOCKS: i32 = 18_000;
 	pub const UNKNOWN_ERROR: i32 = 20_000;
 }
 
@@ -61,6 +63,12 @@ pub enum Error {
 	/// Supplied parent_hash doesn't exist in chain
 	#[error("Supplied parent_hash: {0} doesn't exist in chain")]
 	BlockNotFound(String),
+	/// The request needs a feature the authorship task was not started with
+	#[error("{0} is not enabled for this manual seal instance")]
+	NotEnabled(&'static str),
+	/// More blocks were requested to be mined than allowed at once
+	#[error("Cannot mine {0} blocks at once, the maximum is {max}", max = crate::rpc::MAX_MINED_BLOCKS)]
+	TooManyBlocks(u32),
 	/// Some string error
 	#[error("{0}")]
 	StringError(String),
@@ -97,6 +105,8 @@ impl Error {
 			ConsensusError(_) => codes::CONSENSUS_ERROR,
 			InherentError(_) => codes::INHERENTS_ERROR,
 			BlockchainError(_) => codes::BLOCKCHAIN_ERROR,
+			NotEnabled(_) => codes::NOT_ENABLED,
+			TooManyBlocks(_) => codes::TOO_MANY_BLOCKS,
 			SendError(_) | Canceled(_) => codes::SERVER_SHUTTING_DOWN,
 			_ => codes::UNKNOWN_ERROR,
 		}
@@ -40,18 +40,19 @@ use std::{marker::PhantomData, sync::Arc, time::Duration};
 mod error;
 mod finalize_block;
 mod seal_block;
+mod snapshot;
 
 pub mod consensus;
 pub mod rpc;
 
 pub use self::{
-	consensus::ConsensusDataProvider,
+	consensus::{timestamp::TimeTravel, ConsensusDataProvider},
 	error::Error,
 	finalize_block::{finalize_block, FinalizeBlockParams},
 	rpc::{CreatedBlock, EngineCommand},
 	seal_block::{seal_block, SealBlockParams, MAX_PROPOSAL_DURATION},
 };
-use sc_transaction_pool_api::TransactionPool;
+use sc_transaction_pool_api::MaintainedTransactionPool;
 use sp_api::ProvideRuntimeApi;
 
 const LOG_TARGET: &str = "manual-seal";
@@ -87,7 +88,7 @@ where
 }
 
 /// Params required to start the manual sealing authorship task.
-pub struct ManualSealParams<B: BlockT, BI, E, C: ProvideRuntimeApi<B>, TP, SC, CS, CIDP, P> {
+pub struct ManualSealParams<B: BlockT, BI, E, C: ProvideRuntimeApi<B>, TP, SC, CS, CIDP, P, CB> {
 	/// Block import instance.
 	pub block_import: BI,
 
@@ -112,6 +113,15 @@ pub struct ManualSealParams<B: BlockT, BI, E, C: ProvideRuntimeApi<B>, TP, SC, C
 
 	/// Something that can create the inherent data providers.
 	pub create_inherent_data_providers: CIDP,
+
+	/// Backend used to revert the chain to snapshots, [`EngineCommand::Revert`] fails if unset.
+	pub backend: Option<Arc<CB>>,
+
+	/// Handle used to move the clock of the timestamp inherent, it should be applied to the
+	/// timestamp provider created by `create_inherent_data_providers`, see [`TimeTravel`].
+	/// [`EngineCommand::SetNextTimestamp`] and
+	/// [`EngineCommand::IncreaseTime`] fail if unset.
+	pub time_travel: Option<TimeTravel>,
 }
 
 /// Params required to start the instant sealing authorship task.
@@ -161,7 +171,9 @@ pub async fn run_manual_seal<B, BI, CB, E, C, TP, SC, CS, CIDP, P>(
 		select_chain,
 		consensus_data_provider,
 		create_inherent_data_providers,
-	}: ManualSealParams<B, BI, E, C, TP, SC, CS, CIDP, P>,
+		backend,
+		time_travel,
+	}: ManualSealParams<B, BI, E, C, TP, SC, CS, CIDP, P, CB>,
 ) where
 	B: BlockT + 'static,
 	BI: BlockImport<B, Error = sp_consensus::Error> + Send + Sync + 'static,
@@ -171,10 +183,12 @@ pub async fn run_manual_seal<B, BI, CB, E, C, TP, SC, CS, CIDP, P>(
 	E::Proposer: Proposer<B, Proof = P>,
 	CS: Stream<Item = EngineCommand<<B as BlockT>::Hash>> + Unpin + 'static,
 	SC: SelectChain<B> + 'static,
-	TP: TransactionPool<Block = B>,
+	TP: MaintainedTransactionPool<Block = B>,
 	CIDP: CreateInherentDataProviders<B, ()>,
 	P: codec::Encode + Send + Sync + 'static,
 {
+	let mut snapshots = snapshot::Snapshots::<B>::default();
+
 	while let Some(command) = commands_stream.next().await {
 		match command {
 			EngineCommand::SealNewBlock { create_empty, finalize, parent_hash, sender } => {
@@ -204,6 +218,33 @@ pub async fn run_manual_seal<B, BI, CB, E, C, TP, SC, CS, CIDP, P>(
 				})
 				.await
 			},
+			EngineCommand::SetNextTimestamp { timestamp, mut sender } => {
+				let result = time_travel
+					.as_ref()
+					.map(|time_travel| time_travel.set_next_timestamp(timestamp))
+					.ok_or(Error::NotEnabled("Time travel"));
+				rpc::send_result(&mut sender, result)
+			},
+			EngineCommand::IncreaseTime { millis, mut sender } => {
+				let result = time_travel
+					.as_ref()
+					.map(|time_travel| time_travel.increase_time(millis))
+					.ok_or(Error::NotEnabled("Time travel"));
+				rpc::send_result(&mut sender, result)
+			},
+			EngineCommand::Snapshot { mut sender } =>
+				rpc::send_result(&mut sender, Ok(snapshots.take(&*client))),
+			EngineCommand::Revert { id, mut sender } => {
+				let result = match backend.as_ref() {
+					Some(backend) => snapshots.revert(id, &*client, &**backend, &*pool).await,
+					None => Err(Error::NotEnabled("Reverting")),
+				};
+				// pending adjustments were meant for a block on top of the reverted chain.
+				if let (Ok(true), Some(time_travel)) = (&result, &time_travel) {
+					time_travel.reset();
+				}
+				rpc::send_result(&mut sender, result)
+			},
 		}
 	}
 }
@@ -229,7 +270,7 @@ pub async fn run_instant_seal<B, BI, CB, E, C, TP, SC, CIDP, P>(
 	E: Environment<B> + 'static,
 	E::Proposer: Proposer<B, Proof = P>,
 	SC: SelectChain<B> + 'static,
-	TP: TransactionPool<Block = B>,
+	TP: MaintainedTransactionPool<Block = B>,
 	CIDP: CreateInherentDataProviders<B, ()>,
 	P: codec::Encode + Send + Sync + 'static,
 {
@@ -251,6 +292,8 @@ pub async fn run_instant_seal<B, BI, CB, E, C, TP, SC, CIDP, P>(
 		select_chain,
 		consensus_data_provider,
 		create_inherent_data_providers,
+		backend: None,
+		time_travel: None,
 	})
 	.await
 }
@@ -279,7 +322,7 @@ pub async fn run_instant_seal_and_finalize<B, BI, CB, E, C, TP, SC, CIDP, P>(
 	E: Environment<B> + 'static,
 	E::Proposer: Proposer<B, Proof = P>,
 	SC: SelectChain<B> + 'static,
-	TP: TransactionPool<Block = B>,
+	TP: MaintainedTransactionPool<Block = B>,
 	CIDP: CreateInherentDataProviders<B, ()>,
 	P: codec::Encode + Send + Sync + 'static,
 {
@@ -301,6 +344,8 @@ pub async fn run_instant_seal_and_finalize<B, BI, CB, E, C, TP, SC, CIDP, P>(
 		select_chain,
 		consensus_data_provider,
 		create_inherent_data_providers,
+		backend: None,
+		time_travel: None,
 	})
 	.await
 }
@@ -346,6 +391,7 @@ pub async fn run_delayed_finalize<B, CB, C, S>(
 #[cfg(test)]
 mod tests {
 	use super::*;
+	use rpc::{ManualSeal, ManualSealApiServer};
 	use sc_basic_authorship::ProposerFactory;
 	use sc_consensus::ImportedAux;
 	use sc_transaction_pool::{BasicPool, FullChainApi, Options, RevalidationType};
@@ -439,6 +485,8 @@ mod tests {
 			commands_stream,
 			select_chain,
 			create_inherent_data_providers: |_, _| async { Ok(()) },
+			backend: None,
+			time_travel: None,
 			consensus_data_provider: None,
 		}));
 
@@ -517,6 +565,8 @@ mod tests {
 			pool: pool.clone(),
 			select_chain,
 			create_inherent_data_providers: |_, _| async { Ok(()) },
+			backend: None,
+			time_travel: None,
 			consensus_data_provider: None,
 		}));
 
@@ -594,6 +644,8 @@ mod tests {
 			select_chain,
 			consensus_data_provider: None,
 			create_inherent_data_providers: |_, _| async { Ok(()) },
+			backend: None,
+			time_travel: None,
 		}));
 
 		// submit a transaction to pool.
@@ -678,6 +730,8 @@ mod tests {
 			select_chain,
 			consensus_data_provider: None,
 			create_inherent_data_providers: |_, _| async { Ok(()) },
+			backend: None,
+			time_travel: None,
 		}));
 
 		// submit a transaction to pool.
@@ -783,6 +837,8 @@ mod tests {
 			// use a provider that pushes some post digest data
 			consensus_data_provider: Some(Box::new(TestDigestProvider { _client: client.clone() })),
 			create_inherent_data_providers: |_, _| async { Ok(()) },
+			backend: None,
+			time_travel: None,
 		}));
 
 		let (tx, rx) = futures::channel::oneshot::channel();
@@ -800,4 +856,170 @@ mod tests {
 		let header = client.header(created_block.hash).unwrap().unwrap();
 		assert_eq!(header.number, 1);
 	}
+
+	#[tokio::test]
+	async fn manual_seal_snapshot_and_revert() {
+		let builder = TestClientBuilder::new();
+		let backend = builder.backend();
+		let (client, select_chain) = builder.build_with_longest_chain();
+		let client = Arc::new(client);
+		let spawner = sp_core::testing::TaskExecutor::new();
+		let genesis_hash = client.info().genesis_hash;
+		let pool_api = Arc::new(FullChainApi::new(client.clone(), None, &spawner.clone()));
+		let pool = Arc::new(BasicPool::with_revalidation_type(
+			Options::default(),
+			true.into(),
+			pool_api,
+			None,
+			RevalidationType::Full,
+			spawner.clone(),
+			0,
+			genesis_hash,
+			genesis_hash,
+		));
+		let env = ProposerFactory::new(spawner.clone(), client.clone(), pool.clone(), None, None);
+
+		let (sink, commands_stream) = futures::channel::mpsc::channel(1024);
+
+		// spawn the background authorship task
+		tokio::spawn(run_manual_seal(ManualSealParams {
+			block_import: client.clone(),
+			env,
+			client: client.clone(),
+			pool: pool.clone(),
+			commands_stream,
+			select_chain,
+			consensus_data_provider: None,
+			create_inherent_data_providers: |_, _| async { Ok(()) },
+			backend: Some(backend),
+			time_travel: None,
+		}));
+		let rpc = ManualSeal::new(sink);
+
+		let created = rpc.mine(1, false).await.unwrap();
+		assert_eq!(client.info().best_hash, created[0].hash);
+
+		let snapshot = rpc.snapshot().await.unwrap();
+		let later = rpc.snapshot().await.unwrap();
+		assert_ne!(snapshot, later);
+
+		let best_hash = client.info().best_hash;
+		assert!(pool.submit_one(best_hash, SOURCE, uxt(Alice, 0)).await.is_ok());
+		assert!(matches!(
+			rpc.mine(rpc::MAX_MINED_BLOCKS + 1, false).await,
+			Err(Error::TooManyBlocks(_))
+		));
+		let created = rpc.mine(2, false).await.unwrap();
+		assert_eq!(created.len(), 2);
+		assert_eq!(client.info().best_number, 3);
+		pool.maintain(sc_transaction_pool_api::ChainEvent::NewBestBlock {
+			hash: created[1].hash,
+			tree_route: None,
+		})
+		.await;
+		assert_eq!(pool.status().ready, 0);
+
+		assert!(rpc.revert(snapshot).await.unwrap());
+		assert_eq!(client.info().best_number, 1);
+		assert!(client.header(created[1].hash).unwrap().is_none());
+		// the transaction included in the reverted blocks is back in the pool.
+		assert_eq!(pool.status().ready, 1);
+
+		// the reverted snapshot and the ones taken after it are gone.
+		assert!(!rpc.revert(snapshot).await.unwrap());
+		assert!(!rpc.revert(later).await.unwrap());
+
+		// time travel was not enabled.
+		assert_matches::assert_matches!(rpc.increase_time(1_000).await, Err(Error::NotEnabled(_)));
+	}
+
+	struct TimestampRecorder {
+		timestamps: Arc<parking_lot::Mutex<Vec<u64>>>,
+	}
+	impl<B: BlockT> ConsensusDataProvider<B> for TimestampRecorder {
+		type Proof = ();
+
+		fn create_digest(
+			&self,
+			_parent: &B::Header,
+			inherents: &InherentData,
+		) -> Result<Digest, Error> {
+			use sp_timestamp::TimestampInherentData;
+			let timestamp = inherents.timestamp_inherent_data()?.expect("timestamp is provided");
+			self.timestamps.lock().push(*timestamp);
+			Ok(Digest { logs: vec![] })
+		}
+
+		fn append_block_import(
+			&self,
+			_parent: &B::Header,
+			_params: &mut BlockImportParams<B>,
+			_inherents: &InherentData,
+			_proof: Self::Proof,
+		) -> Result<(), Error> {
+			Ok(())
+		}
+	}
+
+	#[tokio::test]
+	async fn manual_seal_time_travel() {
+		let builder = TestClientBuilder::new();
+		let (client, select_chain) = builder.build_with_longest_chain();
+		let client = Arc::new(client);
+		let spawner = sp_core::testing::TaskExecutor::new();
+		let genesis_hash = client.info().genesis_hash;
+		let pool = Arc::new(BasicPool::with_revalidation_type(
+			Options::default(),
+			true.into(),
+			api(),
+			None,
+			RevalidationType::Full,
+			spawner.clone(),
+			0,
+			genesis_hash,
+			genesis_hash,
+		));
+		let env = ProposerFactory::new(spawner.clone(), client.clone(), pool.clone(), None, None);
+
+		let (sink, commands_stream) = futures::channel::mpsc::channel(1024);
+		let timestamps = Arc::new(parking_lot::Mutex::new(Vec::new()));
+		let time_travel = TimeTravel::new();
+
+		// spawn the background authorship task with a clock that stands still at 1000.
+		tokio::spawn(run_manual_seal(ManualSealParams {
+			block_import: client.clone(),
+			env,
+			client: client.clone(),
+			pool: pool.clone(),
+			commands_stream,
+			select_chain,
+			consensus_data_provider: Some(Box::new(TimestampRecorder {
+				timestamps: timestamps.clone(),
+			})),
+			create_inherent_data_providers: {
+				let time_travel = time_travel.clone();
+				move |_, _| {
+					let provider = time_travel.timestamp_provider(1_000);
+					async move { Ok::<_, Box<dyn std::error::Error + Send + Sync>>(provider?) }
+				}
+			},
+			backend: None,
+			time_travel: Some(time_travel),
+		}));
+		let rpc = ManualSeal::new(sink);
+
+		rpc.mine(1, false).await.unwrap();
+		rpc.increase_time(500).await.unwrap();
+		rpc.mine(1, false).await.unwrap();
+		// the increase is kept for the following blocks.
+		rpc.mine(1, false).await.unwrap();
+		rpc.set_next_timestamp(5_000).await.unwrap();
+		rpc.mine(1, false).await.unwrap();
+		assert_eq!(*timestamps.lock(), vec![1_000, 1_500, 1_500, 5_000]);
+
+		// the clock can't go backwards.
+		rpc.set_next_timestamp(100).await.unwrap();
+		assert!(rpc.mine(1, false).await.is_err());
+		assert_eq!(client.info().best_number, 4);
+	}
 }
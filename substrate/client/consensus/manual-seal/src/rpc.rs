@@ -28,6 +28,9 @@ use sc_consensus::ImportedAux;
 use serde::{Deserialize, Serialize};
 use sp_runtime::EncodedJustification;
 
+/// Maximal number of blocks created by a single `engine_mine` call.
+pub const MAX_MINED_BLOCKS: u32 = 1_000;
+
 /// Sender passed to the authorship task to report errors or successes.
 pub type Sender<T> = Option<oneshot::Sender<std::result::Result<T, Error>>>;
 
@@ -59,6 +62,32 @@ pub enum EngineCommand<Hash> {
 		/// finalization justification
 		justification: Option<EncodedJustification>,
 	},
+	/// Tells the engine to use the supplied unix millisecond timestamp for the next block
+	SetNextTimestamp {
+		/// timestamp of the next block
+		timestamp: u64,
+		/// sender to report errors/success to the rpc.
+		sender: Sender<()>,
+	},
+	/// Tells the engine to move the timestamp of the next block forward
+	IncreaseTime {
+		/// milliseconds to move the clock forward by
+		millis: u64,
+		/// sender to report errors/success to the rpc.
+		sender: Sender<()>,
+	},
+	/// Tells the engine to take a snapshot of the current best block
+	Snapshot {
+		/// sender to report the id of the snapshot to the rpc.
+		sender: Sender<u64>,
+	},
+	/// Tells the engine to revert the chain to the snapshot with the supplied id
+	Revert {
+		/// id of the snapshot
+		id: u64,
+		/// sender to report whether the snapshot was found to the rpc.
+		sender: Sender<bool>,
+	},
 }
 
 /// RPC trait that provides methods for interacting with the manual-seal authorship task over rpc.
@@ -80,6 +109,34 @@ pub trait ManualSealApi<Hash> {
 		hash: Hash,
 		justification: Option<EncodedJustification>,
 	) -> Result<bool, Error>;
+
+	/// Instructs the manual-seal authorship task to use the given unix millisecond timestamp for
+	/// the next block
+	#[method(name = "engine_setNextTimestamp")]
+	async fn set_next_timestamp(&self, timestamp: u64) -> Result<bool, Error>;
+
+	/// Instructs the manual-seal authorship task to move the timestamp of the next block forward
+	/// by the given amount of milliseconds
+	#[method(name = "engine_increaseTime")]
+	async fn increase_time(&self, millis: u64) -> Result<bool, Error>;
+
+	/// Instructs the manual-seal authorship task to create `count` blocks, one after the other.
+	///
+	/// At most [`MAX_MINED_BLOCKS`] blocks can be created at once.
+	#[method(name = "engine_mine")]
+	async fn mine(&self, count: u32, finalize: bool) -> Result<Vec<CreatedBlock<Hash>>, Error>;
+
+	/// Instructs the manual-seal authorship task to take a snapshot of the best block, returns
+	/// the id of the snapshot
+	#[method(name = "engine_snapshot")]
+	async fn snapshot(&self) -> Result<u64, Error>;
+
+	/// Instructs the manual-seal authorship task to revert the chain to the given snapshot
+	///
+	/// The snapshot and all snapshots taken after it are dropped. Returns `false` if the snapshot
+	/// doesn't exist.
+	#[method(name = "engine_revert")]
+	async fn revert(&self, id: u64) -> Result<bool, Error>;
 }
 
 /// A struct that implements the [`ManualSealApiServer`].
@@ -103,6 +160,16 @@ impl<Hash> ManualSeal<Hash> {
 	pub fn new(import_block_channel: mpsc::Sender<EngineCommand<Hash>>) -> Self {
 		Self { import_block_channel }
 	}
+
+	/// Send `command` to the authorship task and wait for the result.
+	async fn send<T>(
+		&self,
+		command: EngineCommand<Hash>,
+		receiver: oneshot::Receiver<std::result::Result<T, Error>>,
+	) -> Result<T, Error> {
+		self.import_block_channel.clone().send(command).await?;
+		receiver.await?
+	}
 }
 
 #[async_trait]
@@ -143,6 +210,41 @@ impl<Hash: Send + 'static> ManualSealApiServer<Hash> for ManualSeal<Hash> {
 		sink.send(command).await?;
 		receiver.await.map(|_| true).map_err(Into::into)
 	}
+
+	async fn set_next_timestamp(&self, timestamp: u64) -> Result<bool, Error> {
+		let (sender, receiver) = oneshot::channel();
+		self.send(EngineCommand::SetNextTimestamp { timestamp, sender: Some(sender) }, receiver)
+			.await
+			.map(|_| true)
+	}
+
+	async fn increase_time(&self, millis: u64) -> Result<bool, Error> {
+		let (sender, receiver) = oneshot::channel();
+		self.send(EngineCommand::IncreaseTime { millis, sender: Some(sender) }, receiver)
+			.await
+			.map(|_| true)
+	}
+
+	async fn mine(&self, count: u32, finalize: bool) -> Result<Vec<CreatedBlock<Hash>>, Error> {
+		if count > MAX_MINED_BLOCKS {
+			return Err(Error::TooManyBlocks(count))
+		}
+		let mut blocks = Vec::new();
+		for _ in 0..count {
+			blocks.push(self.create_block(true, finalize, None).await?);
+		}
+		Ok(blocks)
+	}
+
+	async fn snapshot(&self) -> Result<u64, Error> {
+		let (sender, receiver) = oneshot::channel();
+		self.send(EngineCommand::Snapshot { sender: Some(sender) }, receiver).await
+	}
+
+	async fn revert(&self, id: u64) -> Result<bool, Error> {
+		let (sender, receiver) = oneshot::channel();
+		self.send(EngineCommand::Revert { id, sender: Some(sender) }, receiver).await
+	}
 }
 
 /// report any errors or successes encountered by the authorship task back
@@ -0,0 +1,122 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Chain snapshots that the authorship task can revert to
+
+use crate::Error;
+use sc_client_api::backend::Backend as ClientBackend;
+use sc_transaction_pool_api::{ChainEvent, MaintainedTransactionPool, TransactionSource};
+use sp_blockchain::{Backend as _, HeaderBackend};
+use sp_runtime::traits::{Block as BlockT, Header as _, NumberFor};
+use std::collections::BTreeMap;
+
+/// Snapshots of the best block taken by the authorship task, keyed by their id.
+pub struct Snapshots<B: BlockT> {
+	// id handed out for the next snapshot
+	next_id: u64,
+	// best block at the time each snapshot was taken
+	snapshots: BTreeMap<u64, (NumberFor<B>, B::Hash)>,
+}
+
+impl<B: BlockT> Default for Snapshots<B> {
+	fn default() -> Self {
+		Self { next_id: 0, snapshots: BTreeMap::new() }
+	}
+}
+
+impl<B: BlockT> Snapshots<B> {
+	/// Record the current best block and return the id of the snapshot.
+	pub fn take<C: HeaderBackend<B>>(&mut self, client: &C) -> u64 {
+		let info = client.info();
+		let id = self.next_id;
+		self.next_id += 1;
+		self.snapshots.insert(id, (info.best_number, info.best_hash));
+		log::info!("📸 Snapshot {} taken at block #{} ({})", id, info.best_number, info.best_hash);
+		id
+	}
+
+	/// Revert the chain to the best block recorded by the snapshot `id`.
+	///
+	/// Finalized blocks are reverted as well, as long as their state is still available. The
+	/// extrinsics of the reverted blocks are submitted to the transaction pool again, so they can
+	/// be included in the next blocks. The snapshot and all snapshots taken after it are
+	/// dropped. Returns `false` if there is no snapshot with the given id.
+	pub async fn revert<C, CB, TP>(
+		&mut self,
+		id: u64,
+		client: &C,
+		backend: &CB,
+		pool: &TP,
+	) -> Result<bool, Error>
+	where
+		C: HeaderBackend<B>,
+		CB: ClientBackend<B>,
+		TP: MaintainedTransactionPool<Block = B>,
+	{
+		let Some((number, hash)) = self.snapshots.get(&id).copied() else { return Ok(false) };
+
+		if client.hash(number)? != Some(hash) {
+			return Err(Error::StringError(format!(
+				"Snapshot {} at block #{} ({}) is not part of the best chain",
+				id, number, hash
+			)))
+		}
+
+		// collect the extrinsics of the blocks about to be reverted, oldest block first.
+		let mut extrinsics = Vec::new();
+		let mut current = client.info().best_hash;
+		while current != hash {
+			let header = client
+				.header(current)?
+				.ok_or_else(|| Error::StringError(format!("Header for {} not found", current)))?;
+			extrinsics.push(backend.blockchain().body(current)?.unwrap_or_default());
+			current = *header.parent_hash();
+		}
+		let extrinsics = extrinsics.into_iter().rev().flatten().collect::<Vec<_>>();
+
+		let (reverted, _) = backend.revert(client.info().best_number - number, true)?;
+		if client.info().best_hash != hash {
+			return Err(Error::StringError(format!(
+				"Reverted {} blocks but could not reach snapshot {}, state may have been pruned",
+				reverted, id
+			)))
+		}
+
+		pool.maintain(ChainEvent::NewBestBlock { hash, tree_route: None }).await;
+		if !extrinsics.is_empty() {
+			let results = pool
+				.submit_at(hash, TransactionSource::External, extrinsics)
+				.await
+				.map_err(|e| Error::StringError(e.to_string()))?;
+			// inherents and transactions that are no longer valid are expected to be rejected.
+			for error in results.into_iter().filter_map(Result::err) {
+				log::debug!(target: crate::LOG_TARGET, "Reverted extrinsic not resubmitted: {}", error);
+			}
+		}
+
+		self.snapshots.split_off(&id);
+		log::info!(
+			"⏪ Reverted {} blocks to snapshot {} at block #{} ({})",
+			reverted,
+			id,
+			number,
+			hash
+		);
+		Ok(true)
+	}
+}
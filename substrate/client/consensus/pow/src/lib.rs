@@ -29,7 +29,13 @@
 //! the worker handle, you can pull the metadata needed to start the
 //! mining process via [`MiningHandle::metadata`], and then do the actual
 //! mining on a standalone thread. Finally, when a seal is found, call
-//! [`MiningHandle::submit`] to build the block.
+//! [`MiningHandle::submit`] to build the block. Miners running as a separate
+//! process can instead use the RPC interface in [`rpc`], which serves the
+//! same metadata through `pow_getWork` and accepts seals through
+//! `pow_submitWork`.
+//!
+//! Common difficulty adjustment algorithms are available in
+//! [`sp_consensus_pow::difficulty`].
 //!
 //! The auxiliary storage for PoW engine only stores the total difficulty.
 //! For other storage requirements for particular PoW algorithm (such as
@@ -39,6 +45,7 @@
 //! as the storage, but it is not recommended as it won't work well with light
 //! clients.
 
+pub mod rpc;
 mod worker;
 
 pub use crate::worker::{MiningBuild, MiningHandle, MiningMetadata};
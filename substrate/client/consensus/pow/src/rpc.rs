@@ -0,0 +1,265 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Local RPC interface for external miners.
+//!
+//! Exposes the current mining work of a [`MiningHandle`] through `pow_getWork` and accepts seals
+//! found for it through `pow_submitWork`. This allows a separate miner process to drive block
+//! production of a node running the [`start_mining_worker`](crate::start_mining_worker).
+//!
+//! Both methods are unsafe, as anyone able to call them could produce the blocks of the node.
+
+use crate::{MiningHandle, PowAlgorithm};
+use jsonrpsee::{core::RpcResult, proc_macros::rpc, Extensions};
+use sc_rpc_api::check_if_safe;
+use serde::{Deserialize, Serialize};
+use sp_core::Bytes;
+use sp_runtime::traits::Block as BlockT;
+
+/// Work to be mined, as returned by `pow_getWork`.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct MiningWork<Hash, Difficulty> {
+	/// Hash of the block the work builds on.
+	pub best_hash: Hash,
+	/// Pre-hash that needs to be sealed.
+	pub pre_hash: Hash,
+	/// Pre-runtime digest the seal must commit to, if any.
+	pub pre_runtime: Option<Bytes>,
+	/// Difficulty the seal needs to satisfy.
+	pub difficulty: Difficulty,
+}
+
+/// RPC trait that provides methods for external miners to fetch work and submit seals.
+#[rpc(client, server)]
+pub trait PowMiningApi<Hash, Difficulty> {
+	/// Returns the current work to be mined.
+	///
+	/// Returns `None` if there is no work available, e.g. while the node is major syncing.
+	#[method(name = "pow_getWork", with_extensions)]
+	fn get_work(&self) -> RpcResult<Option<MiningWork<Hash, Difficulty>>>;
+
+	/// Submits a seal for the work with the given pre-hash.
+	///
+	/// Returns `true` if the seal was valid and the block got imported. Seals for stale work are
+	/// rejected.
+	#[method(name = "pow_submitWork", with_extensions)]
+	async fn submit_work(&self, pre_hash: Hash, seal: Bytes) -> RpcResult<bool>;
+}
+
+/// A struct that implements the [`PowMiningApiServer`].
+pub struct PowMining<Block: BlockT, Algorithm: PowAlgorithm<Block>, L, Proof>
+where
+	L: sc_consensus::JustificationSyncLink<Block>,
+{
+	handle: MiningHandle<Block, Algorithm, L, Proof>,
+}
+
+impl<Block, Algorithm, L, Proof> PowMining<Block, Algorithm, L, Proof>
+where
+	Block: BlockT,
+	Algorithm: PowAlgorithm<Block>,
+	L: sc_consensus::JustificationSyncLink<Block>,
+{
+	/// Create a new [`PowMining`] serving the work of the given mining handle.
+	pub fn new(handle: MiningHandle<Block, Algorithm, L, Proof>) -> Self {
+		Self { handle }
+	}
+}
+
+#[jsonrpsee::core::async_trait]
+impl<Block, Algorithm, L, Proof> PowMiningApiServer<Block::Hash, Algorithm::Difficulty>
+	for PowMining<Block, Algorithm, L, Proof>
+where
+	Block: BlockT,
+	Algorithm: PowAlgorithm<Block> + Send + Sync + 'static,
+	Algorithm::Difficulty: Serialize + Send + Sync + 'static,
+	L: sc_consensus::JustificationSyncLink<Block> + 'static,
+	Proof: Send + 'static,
+{
+	fn get_work(
+		&self,
+		ext: &Extensions,
+	) -> RpcResult<Option<MiningWork<Block::Hash, Algorithm::Difficulty>>> {
+		check_if_safe(ext)?;
+
+		Ok(self.handle.metadata().map(|metadata| MiningWork {
+			best_hash: metadata.best_hash,
+			pre_hash: metadata.pre_hash,
+			pre_runtime: metadata.pre_runtime.map(Into::into),
+			difficulty: metadata.difficulty,
+		}))
+	}
+
+	async fn submit_work(
+		&self,
+		ext: &Extensions,
+		pre_hash: Block::Hash,
+		seal: Bytes,
+	) -> RpcResult<bool> {
+		check_if_safe(ext)?;
+
+		Ok(self.handle.submit_work(pre_hash, seal.0).await)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{Error, MiningBuild, MiningMetadata, PowBlockImport, Seal};
+	use sc_block_builder::BlockBuilderBuilder;
+	use sc_rpc_api::DenyUnsafe;
+	use sp_consensus::Proposal;
+	use sp_core::U256;
+	use sp_runtime::generic::BlockId;
+	use std::sync::Arc;
+	use substrate_test_runtime_client::{
+		runtime::{Block, Hash},
+		DefaultTestClientBuilderExt, TestClient, TestClientBuilder, TestClientBuilderExt,
+	};
+
+	/// Accepts the seal `b"valid"` only.
+	#[derive(Clone)]
+	struct TestAlgorithm;
+
+	impl PowAlgorithm<Block> for TestAlgorithm {
+		type Difficulty = U256;
+
+		fn difficulty(&self, _parent: Hash) -> Result<U256, Error<Block>> {
+			Ok(U256::one())
+		}
+
+		fn verify(
+			&self,
+			_parent: &BlockId<Block>,
+			_pre_hash: &Hash,
+			_pre_digest: Option<&[u8]>,
+			seal: &Seal,
+			_difficulty: U256,
+		) -> Result<bool, Error<Block>> {
+			Ok(seal == b"valid")
+		}
+	}
+
+	type TestHandle = MiningHandle<Block, TestAlgorithm, (), ()>;
+
+	/// Returns a mining handle with a block on top of genesis to be mined.
+	fn mining_handle() -> (Arc<TestClient>, TestHandle, MiningWork<Hash, U256>) {
+		let (client, select_chain) = TestClientBuilder::new().build_with_longest_chain();
+		let client = Arc::new(client);
+		let genesis_hash = client.chain_info().genesis_hash;
+
+		let block_import = PowBlockImport::new(
+			client.clone(),
+			client.clone(),
+			TestAlgorithm,
+			u64::MAX,
+			select_chain,
+			|_, ()| async { Ok::<_, Box<dyn std::error::Error + Send + Sync>>(()) },
+		);
+		let handle = MiningHandle::new(TestAlgorithm, Box::new(block_import), ());
+
+		let built = BlockBuilderBuilder::new(&*client)
+			.on_parent_block(genesis_hash)
+			.with_parent_block_number(0)
+			.build()
+			.unwrap()
+			.build()
+			.unwrap();
+		let work = MiningWork {
+			best_hash: genesis_hash,
+			pre_hash: built.block.hash(),
+			pre_runtime: None,
+			difficulty: U256::one(),
+		};
+		handle.on_build(MiningBuild {
+			metadata: MiningMetadata {
+				best_hash: work.best_hash,
+				pre_hash: work.pre_hash,
+				pre_runtime: None,
+				difficulty: work.difficulty,
+			},
+			proposal: Proposal {
+				block: built.block,
+				proof: (),
+				storage_changes: built.storage_changes,
+			},
+		});
+
+		(client, handle, work)
+	}
+
+	#[tokio::test]
+	async fn submitted_work_is_imported() {
+		let (client, handle, work) = mining_handle();
+		let mut api = PowMining::new(handle).into_rpc();
+		api.extensions_mut().insert(DenyUnsafe::No);
+
+		let served: Option<MiningWork<Hash, U256>> = api
+			.call("pow_getWork", jsonrpsee::core::EmptyServerParams::new())
+			.await
+			.unwrap();
+		assert_eq!(served, Some(work.clone()));
+
+		// Seals for other work and invalid seals are rejected.
+		let stale: bool = api
+			.call("pow_submitWork", (Hash::repeat_byte(1), Bytes(b"valid".to_vec())))
+			.await
+			.unwrap();
+		assert!(!stale);
+		let invalid: bool = api
+			.call("pow_submitWork", (work.pre_hash, Bytes(b"invalid".to_vec())))
+			.await
+			.unwrap();
+		assert!(!invalid);
+		assert_eq!(client.chain_info().best_number, 0);
+
+		let valid: bool = api
+			.call("pow_submitWork", (work.pre_hash, Bytes(b"valid".to_vec())))
+			.await
+			.unwrap();
+		assert!(valid);
+		assert_eq!(client.chain_info().best_number, 1);
+
+		// The work is consumed by the imported block.
+		let served: Option<MiningWork<Hash, U256>> = api
+			.call("pow_getWork", jsonrpsee::core::EmptyServerParams::new())
+			.await
+			.unwrap();
+		assert_eq!(served, None);
+	}
+
+	#[tokio::test]
+	async fn mining_is_unsafe() {
+		let (_, handle, work) = mining_handle();
+		let mut api = PowMining::new(handle).into_rpc();
+		api.extensions_mut().insert(DenyUnsafe::Yes);
+
+		let request = r#"{"jsonrpc":"2.0","method":"pow_getWork","params":[],"id":1}"#;
+		let (response, _) = api.raw_json_request(request, 1).await.unwrap();
+		let expected = r#"{"jsonrpc":"2.0","id":1,"error":{"code":-32601,"message":"RPC call is unsafe to be called externally"}}"#;
+		assert_eq!(response, expected);
+
+		let request = format!(
+			r#"{{"jsonrpc":"2.0","method":"pow_submitWork","params":["{:?}","0x76616c6964"],"id":1}}"#,
+			work.pre_hash,
+		);
+		let (response, _) = api.raw_json_request(&request, 1).await.unwrap();
+		assert_eq!(response, expected);
+	}
+}
@@ -17,6 +17,7 @@
 // along with this program. If not, see <https://www.gnu.org/licenses/>.
 
 use futures::{
+	lock::Mutex as AsyncMutex,
 	prelude::*,
 	task::{Context, Poll},
 };
@@ -78,7 +79,7 @@ pub struct MiningHandle<
 	algorithm: Arc<Algorithm>,
 	justification_sync_link: Arc<L>,
 	build: Arc<Mutex<Option<MiningBuild<Block, Algorithm, Proof>>>>,
-	block_import: Arc<Mutex<BoxBlockImport<Block>>>,
+	block_import: Arc<AsyncMutex<BoxBlockImport<Block>>>,
 }
 
 impl<Block, Algorithm, L, Proof> MiningHandle<Block, Algorithm, L, Proof>
@@ -102,7 +103,7 @@ where
 			algorithm: Arc::new(algorithm),
 			justification_sync_link: Arc::new(justification_sync_link),
 			build: Arc::new(Mutex::new(None)),
-			block_import: Arc::new(Mutex::new(block_import)),
+			block_import: Arc::new(AsyncMutex::new(block_import)),
 		}
 	}
 
@@ -137,6 +138,21 @@ where
 		self.build.lock().as_ref().map(|b| b.metadata.clone())
 	}
 
+	/// Submit a seal mined by an external miner for the work with the given `pre_hash`.
+	///
+	/// Unlike [`Self::submit`], the seal is rejected if the current mining build changed since the
+	/// miner fetched its work, as the seal would then be checked against the wrong pre-hash.
+	/// Returns true if the submission is successful.
+	pub async fn submit_work(&self, pre_hash: Block::Hash, seal: Seal) -> bool {
+		match self.metadata() {
+			Some(metadata) if metadata.pre_hash == pre_hash => self.submit(seal).await,
+			_ => {
+				debug!(target: LOG_TARGET, "Ignoring seal submitted for stale work {:?}", pre_hash);
+				false
+			},
+		}
+	}
+
 	/// Submit a mined seal. The seal will be validated again. Returns true if the submission is
 	/// successful.
 	pub async fn submit(&self, seal: Seal) -> bool {
@@ -192,7 +208,7 @@ where
 		import_block.insert_intermediate(INTERMEDIATE_KEY, intermediate);
 
 		let header = import_block.post_header();
-		let block_import = self.block_import.lock();
+		let block_import = self.block_import.lock().await;
 
 		match block_import.import_block(import_block).await {
 			Ok(res) => {
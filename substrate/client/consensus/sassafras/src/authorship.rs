@@ -0,0 +1,770 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Sassafras authority selection, slot claiming and tickets generation.
+
+use crate::{
+	aux_schema, find_next_epoch_digest, find_slot, Epoch, Error, SassafrasIntermediate,
+	SassafrasLink, TicketSecret, INTERMEDIATE_KEY, LOG_TARGET,
+};
+use codec::Encode;
+use futures::{channel::oneshot, prelude::*};
+use log::{debug, info, trace, warn};
+use sc_client_api::{backend::AuxStore, BlockchainEvents};
+use sc_consensus::{
+	block_import::{BlockImport, BlockImportParams, StateAction},
+	JustificationSyncLink,
+};
+use sc_consensus_epochs::{
+	descendent_query, Epoch as EpochT, EpochIdentifier, EpochIdentifierPosition,
+	SharedEpochChanges, ViableEpochDescriptor,
+};
+use sc_consensus_slots::{
+	BackoffAuthoringBlocksStrategy, InherentDataProviderExt, SimpleSlotWorker, SlotInfo,
+	SlotProportion, StorageChanges,
+};
+use sc_telemetry::TelemetryHandle;
+use sc_transaction_pool_api::OffchainTransactionPoolFactory;
+use sp_api::{ApiExt, ProvideRuntimeApi};
+use sp_blockchain::{Error as ClientError, HeaderBackend, HeaderMetadata};
+use sp_consensus::{
+	BlockOrigin, Environment, Error as ConsensusError, Proposer, SelectChain, SyncOracle,
+};
+use sp_consensus_sassafras::{
+	digests::SlotClaim,
+	ticket_id_threshold,
+	vrf::{self, RingContext, VrfInput},
+	AuthorityId, AuthorityIndex, Randomness, SassafrasApi, TicketBody, TicketClaim, TicketEnvelope,
+	TicketId, KEY_TYPE,
+};
+use sp_consensus_slots::Slot;
+use sp_core::{
+	crypto::{ByteArray, Wraps},
+	ed25519,
+	traits::SpawnNamed,
+	Pair,
+};
+use sp_inherents::CreateInherentDataProviders;
+use sp_keystore::KeystorePtr;
+use sp_runtime::{
+	traits::{Block as BlockT, Header, NumberFor},
+	DigestItem,
+};
+use std::{pin::Pin, sync::Arc, time::Duration};
+
+/// Get the fallback authority index for the given slot.
+///
+/// The fallback authority is in charge of the slots which have no ticket assigned.
+pub(crate) fn fallback_authority_index(
+	randomness: &Randomness,
+	slot: Slot,
+	authorities_len: usize,
+) -> AuthorityIndex {
+	let hash = sp_crypto_hashing::blake2_256(&(randomness, slot).encode());
+	let mut bytes = [0_u8; 8];
+	bytes.copy_from_slice(&hash[..8]);
+	(u64::from_le_bytes(bytes) % authorities_len as u64) as AuthorityIndex
+}
+
+/// Data signed with the erased key of a ticket to claim the slot assigned to it.
+///
+/// The signature is bound to the slot claim VRF signature, which is itself bound to the
+/// slot and to the claiming authority.
+pub(crate) fn ticket_claim_data(vrf_signature: &vrf::VrfSignature) -> [u8; 32] {
+	vrf_signature.pre_output.make_bytes()
+}
+
+/// VRF input used to derive the key which is revealed when a ticket is claimed.
+fn revealed_key_input(randomness: &Randomness, attempt: u32, epoch: u64) -> VrfInput {
+	let v = [
+		b"sassafras-revealed",
+		randomness.as_slice(),
+		&attempt.to_le_bytes(),
+		&epoch.to_le_bytes(),
+	]
+	.concat();
+	VrfInput::new(&v[..])
+}
+
+/// Claim a slot if it is our turn.
+///
+/// If the slot has a ticket assigned (`maybe_ticket`, as returned by the runtime), the slot can
+/// only be claimed if the ticket was generated by one of our authorities. Otherwise the slot
+/// is claimed by the fallback authority.
+///
+/// Returns `None` if it is not our turn to author the block.
+pub fn claim_slot(
+	slot: Slot,
+	epoch: &Epoch,
+	maybe_ticket: Option<(TicketId, TicketBody)>,
+	keystore: &KeystorePtr,
+) -> Option<(SlotClaim, AuthorityId)> {
+	if epoch.authorities.is_empty() {
+		return None
+	}
+
+	let mut epoch_index = epoch.index;
+	if epoch.end_slot() <= slot {
+		// Slot doesn't strictly belong to the epoch, create a clone with fixed values.
+		epoch_index = epoch.clone_for_slot(slot).index;
+	}
+
+	let (authority_idx, ticket_secret) = match maybe_ticket {
+		Some((ticket_id, ticket_body)) => {
+			trace!(target: LOG_TARGET, "Slot {} is assigned to ticket {:032x}", slot, ticket_id);
+			let secret = epoch.tickets_aux.get(&ticket_id)?;
+			let erased_pair = ed25519::Pair::from_seed(&secret.erased_seed);
+			if erased_pair.public() != ticket_body.erased_public {
+				warn!(target: LOG_TARGET, "Erased key mismatch for ticket {:032x}", ticket_id);
+				return None
+			}
+			(secret.authority_idx, Some(erased_pair))
+		},
+		None => {
+			trace!(target: LOG_TARGET, "Slot {} has no ticket, trying fallback claim", slot);
+			(fallback_authority_index(&epoch.randomness, slot, epoch.authorities.len()), None)
+		},
+	};
+
+	let authority_id = epoch.authorities.get(authority_idx as usize)?;
+
+	let data = vrf::slot_claim_sign_data(&epoch.randomness, slot, epoch_index);
+	let vrf_signature = keystore
+		.bandersnatch_vrf_sign(KEY_TYPE, authority_id.as_ref(), &data)
+		.ok()
+		.flatten()?;
+
+	let ticket_claim = ticket_secret.map(|erased_pair| TicketClaim {
+		erased_signature: erased_pair.sign(&ticket_claim_data(&vrf_signature)),
+	});
+
+	let claim = SlotClaim { authority_idx, slot, vrf_signature, ticket_claim };
+	Some((claim, authority_id.clone()))
+}
+
+/// Erase the secret of a claimed ticket and persist the updated epoch changes.
+///
+/// The ephemeral key of a ticket must be usable only once, thus its seed is dropped as soon as
+/// the slot assigned to the ticket has been claimed.
+pub fn erase_ticket_secret<B, C>(
+	client: &C,
+	epoch_changes: &SharedEpochChanges<B, Epoch>,
+	epoch_descriptor: &ViableEpochDescriptor<B::Hash, NumberFor<B>, Epoch>,
+	ticket_id: TicketId,
+) -> Result<(), Error<B>>
+where
+	B: BlockT,
+	C: AuxStore,
+{
+	// The genesis epoch is not imported yet, thus it holds no tickets.
+	let ViableEpochDescriptor::Signaled(epoch_id, _) = epoch_descriptor else { return Ok(()) };
+
+	let mut epoch_changes = epoch_changes.shared_data();
+	let Some(epoch) = epoch_changes.epoch_mut(epoch_id) else { return Ok(()) };
+	if epoch.tickets_aux.remove(&ticket_id).is_none() {
+		return Ok(())
+	}
+	trace!(target: LOG_TARGET, "Erased secret of ticket {:032x}", ticket_id);
+
+	aux_schema::write_epoch_changes::<B, _, _>(&epoch_changes, |values| {
+		client.insert_aux(values, [])
+	})
+	.map_err(Error::Client)
+}
+
+/// Generate the tickets of the local authorities for the given epoch.
+///
+/// Only the tickets with an identifier below the epoch threshold are returned. The secrets of
+/// the returned tickets are stored in the epoch `tickets_aux`, so that the slots assigned to
+/// them can be claimed later on.
+pub fn generate_epoch_tickets(
+	epoch: &mut Epoch,
+	keystore: &KeystorePtr,
+	ring_context: &RingContext,
+) -> Vec<TicketEnvelope> {
+	let mut tickets = Vec::new();
+
+	let threshold = ticket_id_threshold(
+		epoch.config.redundancy_factor,
+		epoch.length,
+		epoch.config.attempts_number,
+		epoch.authorities.len() as u32,
+	);
+	debug!(target: LOG_TARGET, "Tickets threshold for epoch {}: {:032x}", epoch.index, threshold);
+
+	let public_keys: Vec<_> =
+		epoch.authorities.iter().map(|id| id.as_inner_ref().clone()).collect();
+
+	for (authority_idx, authority_id) in epoch.authorities.iter().enumerate() {
+		if !keystore.has_keys(&[(authority_id.to_raw_vec(), KEY_TYPE)]) {
+			continue
+		}
+
+		debug!(target: LOG_TARGET, "Generating tickets for authority {}", authority_idx);
+
+		// The ring prover is expensive to build, do it only if required.
+		let mut prover = None;
+
+		for attempt_idx in 0..epoch.config.attempts_number {
+			let ticket_id_input = vrf::ticket_id_input(&epoch.randomness, attempt_idx, epoch.index);
+			let Ok(Some(pre_output)) = keystore.bandersnatch_vrf_pre_output(
+				KEY_TYPE,
+				authority_id.as_ref(),
+				&ticket_id_input,
+			) else {
+				break
+			};
+
+			let ticket_id = vrf::make_ticket_id(&pre_output);
+			if ticket_id >= threshold {
+				continue
+			}
+
+			let revealed_input = revealed_key_input(&epoch.randomness, attempt_idx, epoch.index);
+			let Ok(Some(revealed_pre_output)) = keystore.bandersnatch_vrf_pre_output(
+				KEY_TYPE,
+				authority_id.as_ref(),
+				&revealed_input,
+			) else {
+				break
+			};
+			let revealed_public =
+				ed25519::Pair::from_seed(&revealed_pre_output.make_bytes()).public();
+
+			let (erased_pair, erased_seed) = ed25519::Pair::generate();
+			let body =
+				TicketBody { attempt_idx, erased_public: erased_pair.public(), revealed_public };
+
+			let sign_data = vrf::ticket_body_sign_data(&body, ticket_id_input);
+			let prover =
+				prover.get_or_insert_with(|| ring_context.prover(&public_keys, authority_idx));
+			let Ok(Some(signature)) = keystore.bandersnatch_ring_vrf_sign(
+				KEY_TYPE,
+				authority_id.as_ref(),
+				&sign_data,
+				prover,
+			) else {
+				warn!(target: LOG_TARGET, "Failed to sign ticket {:032x}", ticket_id);
+				continue
+			};
+
+			debug!(target: LOG_TARGET, "Generated ticket {:032x} (attempt {})", ticket_id, attempt_idx);
+			tickets.push(TicketEnvelope { body, signature });
+			epoch.tickets_aux.insert(
+				ticket_id,
+				TicketSecret { authority_idx: authority_idx as AuthorityIndex, erased_seed },
+			);
+		}
+	}
+
+	tickets
+}
+
+/// Parameters for Sassafras.
+pub struct SassafrasParams<B: BlockT, C, SC, E, I, SO, L, CIDP, BS> {
+	/// The keystore that manages the keys of the node.
+	pub keystore: KeystorePtr,
+
+	/// The client to use
+	pub client: Arc<C>,
+
+	/// The SelectChain Strategy
+	pub select_chain: SC,
+
+	/// The environment we are producing blocks for.
+	pub env: E,
+
+	/// The underlying block-import object to supply our produced blocks to.
+	/// This must be a `SassafrasBlockImport` or a wrapper of it, otherwise
+	/// critical consensus logic will be omitted.
+	pub block_import: I,
+
+	/// A sync oracle
+	pub sync_oracle: SO,
+
+	/// Hook into the sync module to control the justification sync process.
+	pub justification_sync_link: L,
+
+	/// Something that can create the inherent data providers.
+	pub create_inherent_data_providers: CIDP,
+
+	/// Force authoring of blocks even if we are offline
+	pub force_authoring: bool,
+
+	/// Strategy and parameters for backing off block production.
+	pub backoff_authoring_blocks: Option<BS>,
+
+	/// The state shared with the import queue, created by [`crate::block_import`].
+	pub sassafras_link: SassafrasLink<B>,
+
+	/// The proportion of the slot dedicated to proposing.
+	pub block_proposal_slot_portion: SlotProportion,
+
+	/// The maximum proportion of the slot dedicated to proposing with any lenience factor applied
+	/// due to no blocks being produced.
+	pub max_block_proposal_slot_portion: Option<SlotProportion>,
+
+	/// Handle use to report telemetries.
+	pub telemetry: Option<TelemetryHandle>,
+
+	/// The offchain transaction pool factory.
+	///
+	/// Will be used when submitting the tickets.
+	pub offchain_tx_pool_factory: OffchainTransactionPoolFactory<B>,
+
+	/// Spawner used to run the tickets generation, which involves expensive ring proofs, as a
+	/// blocking task.
+	pub spawner: Box<dyn SpawnNamed>,
+}
+
+/// Worker for Sassafras which implements `Future<Output=()>`. This must be polled.
+///
+/// It drives both the block authoring and the generation of the tickets of the local
+/// authorities.
+#[must_use]
+pub struct SassafrasWorker {
+	inner: Pin<Box<dyn Future<Output = ()> + Send + 'static>>,
+}
+
+impl Future for SassafrasWorker {
+	type Output = ();
+
+	fn poll(
+		mut self: Pin<&mut Self>,
+		cx: &mut std::task::Context,
+	) -> std::task::Poll<Self::Output> {
+		self.inner.as_mut().poll(cx)
+	}
+}
+
+/// Start the Sassafras worker.
+pub fn start_sassafras<B, C, SC, E, I, SO, CIDP, BS, L, Error>(
+	SassafrasParams {
+		keystore,
+		client,
+		select_chain,
+		env,
+		block_import,
+		sync_oracle,
+		justification_sync_link,
+		create_inherent_data_providers,
+		force_authoring,
+		backoff_authoring_blocks,
+		sassafras_link,
+		block_proposal_slot_portion,
+		max_block_proposal_slot_portion,
+		telemetry,
+		offchain_tx_pool_factory,
+		spawner,
+	}: SassafrasParams<B, C, SC, E, I, SO, L, CIDP, BS>,
+) -> Result<SassafrasWorker, ConsensusError>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B>
+		+ HeaderBackend<B>
+		+ HeaderMetadata<B, Error = ClientError>
+		+ BlockchainEvents<B>
+		+ AuxStore
+		+ Send
+		+ Sync
+		+ 'static,
+	C::Api: SassafrasApi<B>,
+	SC: SelectChain<B> + 'static,
+	E: Environment<B, Error = Error> + Send + Sync + 'static,
+	E::Proposer: Proposer<B, Error = Error>,
+	I: BlockImport<B, Error = ConsensusError> + Send + Sync + 'static,
+	SO: SyncOracle + Send + Sync + Clone + 'static,
+	L: JustificationSyncLink<B> + 'static,
+	CIDP: CreateInherentDataProviders<B, ()> + Send + Sync + 'static,
+	CIDP::InherentDataProviders: InherentDataProviderExt + Send,
+	BS: BackoffAuthoringBlocksStrategy<NumberFor<B>> + Send + Sync + 'static,
+	Error: std::error::Error + Send + From<ConsensusError> + From<I::Error> + 'static,
+{
+	let worker = SassafrasSlotWorker {
+		client: client.clone(),
+		block_import,
+		env,
+		sync_oracle: sync_oracle.clone(),
+		justification_sync_link,
+		force_authoring,
+		backoff_authoring_blocks,
+		keystore: keystore.clone(),
+		epoch_changes: sassafras_link.epoch_changes.clone(),
+		config: sassafras_link.config.clone(),
+		block_proposal_slot_portion,
+		max_block_proposal_slot_portion,
+		telemetry,
+	};
+
+	info!(target: LOG_TARGET, "🌳 Starting Sassafras Authorship worker");
+
+	let slot_worker = sc_consensus_slots::start_slot_worker(
+		sassafras_link.config.slot_duration(),
+		select_chain,
+		sc_consensus_slots::SimpleSlotWorkerToSlotWorker(worker),
+		sync_oracle.clone(),
+		create_inherent_data_providers,
+	);
+
+	let tickets_worker = tickets_worker(
+		client,
+		keystore,
+		sassafras_link.epoch_changes,
+		sync_oracle,
+		offchain_tx_pool_factory,
+		spawner,
+	);
+
+	let inner = future::join(slot_worker, tickets_worker).map(|_| ());
+
+	Ok(SassafrasWorker { inner: Box::pin(inner) })
+}
+
+/// Generate and submit the tickets of the local authorities whenever a new epoch is announced.
+///
+/// Ring proofs are expensive to build, so each generation runs as a blocking task and the worker
+/// waits for it to finish before handling the next announcement.
+async fn tickets_worker<B, C, SO>(
+	client: Arc<C>,
+	keystore: KeystorePtr,
+	epoch_changes: SharedEpochChanges<B, Epoch>,
+	sync_oracle: SO,
+	offchain_tx_pool_factory: OffchainTransactionPoolFactory<B>,
+	spawner: Box<dyn SpawnNamed>,
+) where
+	B: BlockT,
+	C: BlockchainEvents<B> + ProvideRuntimeApi<B> + AuxStore + Send + Sync + 'static,
+	C::Api: SassafrasApi<B>,
+	SO: SyncOracle,
+{
+	let mut notifications = client.import_notification_stream();
+
+	while let Some(notification) = notifications.next().await {
+		if !notification.is_new_best || sync_oracle.is_major_syncing() {
+			continue
+		}
+
+		match find_next_epoch_digest::<B>(&notification.header) {
+			Ok(Some(_)) => {},
+			_ => continue,
+		}
+
+		let (tx, rx) = oneshot::channel();
+		let client = client.clone();
+		let keystore = keystore.clone();
+		let epoch_changes = epoch_changes.clone();
+		let offchain_tx_pool_factory = offchain_tx_pool_factory.clone();
+		let number = *notification.header.number();
+		spawner.spawn_blocking(
+			"sassafras-tickets-generation",
+			Some("sassafras"),
+			async move {
+				let result = generate_and_submit_tickets(
+					&*client,
+					&keystore,
+					&epoch_changes,
+					&offchain_tx_pool_factory,
+					notification.hash,
+					number,
+				);
+				let _ = tx.send(result);
+			}
+			.boxed(),
+		);
+
+		match rx.await {
+			Ok(Ok(())) => {},
+			Ok(Err(err)) => warn!(target: LOG_TARGET, "Failed to generate tickets: {}", err),
+			Err(_) => warn!(target: LOG_TARGET, "Tickets generation task dropped"),
+		}
+	}
+}
+
+/// Generate the tickets for the epoch announced by the given block, and submit them.
+fn generate_and_submit_tickets<B, C>(
+	client: &C,
+	keystore: &KeystorePtr,
+	epoch_changes: &SharedEpochChanges<B, Epoch>,
+	offchain_tx_pool_factory: &OffchainTransactionPoolFactory<B>,
+	hash: B::Hash,
+	number: NumberFor<B>,
+) -> Result<(), Error<B>>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + AuxStore,
+	C::Api: SassafrasApi<B>,
+{
+	// The first announcement is done by block #1, along with the genesis epoch.
+	let (epoch_id, mut epoch) = {
+		let epoch_changes = epoch_changes.shared_data();
+		[EpochIdentifierPosition::Regular, EpochIdentifierPosition::Genesis1]
+			.into_iter()
+			.map(|position| EpochIdentifier { position, hash, number })
+			.find_map(|id| epoch_changes.epoch(&id).cloned().map(|epoch| (id, epoch)))
+			.ok_or(Error::FetchEpoch(hash))?
+	};
+
+	if !epoch.tickets_aux.is_empty() {
+		trace!(target: LOG_TARGET, "Tickets for epoch {} already generated", epoch.index);
+		return Ok(())
+	}
+
+	let Some(ring_context) = client.runtime_api().ring_context(hash).map_err(Error::RuntimeApi)?
+	else {
+		debug!(target: LOG_TARGET, "Ring context not initialized, skipping tickets generation");
+		return Ok(())
+	};
+
+	let tickets = generate_epoch_tickets(&mut epoch, keystore, &ring_context);
+	if tickets.is_empty() {
+		return Ok(())
+	}
+
+	info!(
+		target: LOG_TARGET,
+		"🌳 Generated {} tickets for epoch {}",
+		tickets.len(),
+		epoch.index
+	);
+
+	{
+		let mut epoch_changes = epoch_changes.shared_data();
+		let Some(target) = epoch_changes.epoch_mut(&epoch_id) else {
+			debug!(target: LOG_TARGET, "Epoch {} pruned before storing its tickets", epoch.index);
+			return Ok(())
+		};
+		target.tickets_aux = epoch.tickets_aux.clone();
+		aux_schema::write_epoch_changes::<B, _, _>(&epoch_changes, |values| {
+			client.insert_aux(values, [])
+		})
+		.map_err(Error::Client)?;
+	}
+
+	let mut runtime_api = client.runtime_api();
+
+	// Register the offchain tx pool to be able to use it from the runtime.
+	runtime_api.register_extension(offchain_tx_pool_factory.offchain_transaction_pool(hash));
+
+	// The runtime accepts at most an epoch length of tickets per extrinsic.
+	for chunk in tickets.chunks(epoch.length.max(1) as usize) {
+		let submitted = runtime_api
+			.submit_tickets_unsigned_extrinsic(hash, chunk.to_vec())
+			.map_err(Error::RuntimeApi)?;
+		if !submitted {
+			warn!(target: LOG_TARGET, "Failed to submit tickets for epoch {}", epoch.index);
+		}
+	}
+
+	Ok(())
+}
+
+struct SassafrasSlotWorker<B: BlockT, C, E, I, SO, L, BS> {
+	client: Arc<C>,
+	block_import: I,
+	env: E,
+	sync_oracle: SO,
+	justification_sync_link: L,
+	force_authoring: bool,
+	backoff_authoring_blocks: Option<BS>,
+	keystore: KeystorePtr,
+	epoch_changes: SharedEpochChanges<B, Epoch>,
+	config: crate::SassafrasConfiguration,
+	block_proposal_slot_portion: SlotProportion,
+	max_block_proposal_slot_portion: Option<SlotProportion>,
+	telemetry: Option<TelemetryHandle>,
+}
+
+#[async_trait::async_trait]
+impl<B, C, E, I, Error, SO, L, BS> SimpleSlotWorker<B>
+	for SassafrasSlotWorker<B, C, E, I, SO, L, BS>
+where
+	B: BlockT,
+	C: ProvideRuntimeApi<B> + HeaderBackend<B> + HeaderMetadata<B, Error = ClientError> + AuxStore,
+	C::Api: SassafrasApi<B>,
+	E: Environment<B, Error = Error> + Send + Sync,
+	E::Proposer: Proposer<B, Error = Error>,
+	I: BlockImport<B> + Send + Sync + 'static,
+	SO: SyncOracle + Send + Clone + Sync,
+	L: JustificationSyncLink<B>,
+	BS: BackoffAuthoringBlocksStrategy<NumberFor<B>> + Send + Sync,
+	Error: std::error::Error + Send + From<ConsensusError> + From<I::Error> + 'static,
+{
+	type Claim = (SlotClaim, AuthorityId);
+	type SyncOracle = SO;
+	type JustificationSyncLink = L;
+	type CreateProposer =
+		Pin<Box<dyn Future<Output = Result<E::Proposer, ConsensusError>> + Send + 'static>>;
+	type Proposer = E::Proposer;
+	type BlockImport = I;
+	type AuxData = ViableEpochDescriptor<B::Hash, NumberFor<B>, Epoch>;
+
+	fn logging_target(&self) -> &'static str {
+		LOG_TARGET
+	}
+
+	fn block_import(&mut self) -> &mut Self::BlockImport {
+		&mut self.block_import
+	}
+
+	fn aux_data(&self, parent: &B::Header, slot: Slot) -> Result<Self::AuxData, ConsensusError> {
+		self.epoch_changes
+			.shared_data()
+			.epoch_descriptor_for_child_of(
+				descendent_query(&*self.client),
+				&parent.hash(),
+				*parent.number(),
+				slot,
+			)
+			.map_err(|e| ConsensusError::ChainLookup(e.to_string()))?
+			.ok_or(ConsensusError::InvalidAuthoritiesSet)
+	}
+
+	fn authorities_len(&self, epoch_descriptor: &Self::AuxData) -> Option<usize> {
+		self.epoch_changes
+			.shared_data()
+			.viable_epoch(epoch_descriptor, |slot| Epoch::genesis(&self.config, slot))
+			.map(|epoch| epoch.as_ref().authorities.len())
+	}
+
+	async fn claim_slot(
+		&mut self,
+		parent_header: &B::Header,
+		slot: Slot,
+		epoch_descriptor: &ViableEpochDescriptor<B::Hash, NumberFor<B>, Epoch>,
+	) -> Option<Self::Claim> {
+		debug!(target: LOG_TARGET, "Attempting to claim slot {}", slot);
+
+		let maybe_ticket = self
+			.client
+			.runtime_api()
+			.slot_ticket(parent_header.hash(), slot)
+			.map_err(|err| warn!(target: LOG_TARGET, "Failed to fetch slot ticket: {}", err))
+			.ok()?;
+		let ticket_id = maybe_ticket.as_ref().map(|(ticket_id, _)| *ticket_id);
+
+		let claim = claim_slot(
+			slot,
+			self.epoch_changes
+				.shared_data()
+				.viable_epoch(epoch_descriptor, |slot| Epoch::genesis(&self.config, slot))?
+				.as_ref(),
+			maybe_ticket,
+			&self.keystore,
+		);
+
+		if claim.is_some() {
+			debug!(target: LOG_TARGET, "Claimed slot {}", slot);
+			if let Some(ticket_id) = ticket_id {
+				if let Err(err) = erase_ticket_secret(
+					&*self.client,
+					&self.epoch_changes,
+					epoch_descriptor,
+					ticket_id,
+				) {
+					warn!(target: LOG_TARGET, "Failed to erase ticket secret: {}", err);
+				}
+			}
+		}
+
+		claim
+	}
+
+	fn pre_digest_data(&self, _slot: Slot, claim: &Self::Claim) -> Vec<DigestItem> {
+		vec![DigestItem::from(&claim.0)]
+	}
+
+	async fn block_import_params(
+		&self,
+		header: B::Header,
+		header_hash: &B::Hash,
+		body: Vec<B::Extrinsic>,
+		storage_changes: StorageChanges<B>,
+		(_, public): Self::Claim,
+		epoch_descriptor: Self::AuxData,
+	) -> Result<BlockImportParams<B>, ConsensusError> {
+		let signature: crate::AuthoritySignature = self
+			.keystore
+			.bandersnatch_sign(KEY_TYPE, public.as_ref(), header_hash.as_ref())
+			.map_err(|e| ConsensusError::CannotSign(format!("{}. Key: {:?}", e, public)))?
+			.ok_or_else(|| {
+				ConsensusError::CannotSign(format!(
+					"Could not find key in keystore. Key: {:?}",
+					public
+				))
+			})?
+			.into();
+
+		let mut import_block = BlockImportParams::new(BlockOrigin::Own, header);
+		import_block.post_digests.push(DigestItem::from(&signature));
+		import_block.body = Some(body);
+		import_block.state_action =
+			StateAction::ApplyChanges(sc_consensus::StorageChanges::Changes(storage_changes));
+		import_block
+			.insert_intermediate(INTERMEDIATE_KEY, SassafrasIntermediate::<B> { epoch_descriptor });
+
+		Ok(import_block)
+	}
+
+	fn force_authoring(&self) -> bool {
+		self.force_authoring
+	}
+
+	fn should_backoff(&self, slot: Slot, chain_head: &B::Header) -> bool {
+		if let Some(ref strategy) = self.backoff_authoring_blocks {
+			if let Ok(chain_head_slot) = find_slot::<B>(chain_head) {
+				return strategy.should_backoff(
+					*chain_head.number(),
+					chain_head_slot,
+					self.client.info().finalized_number,
+					slot,
+					self.logging_target(),
+				)
+			}
+		}
+		false
+	}
+
+	fn sync_oracle(&mut self) -> &mut Self::SyncOracle {
+		&mut self.sync_oracle
+	}
+
+	fn justification_sync_link(&mut self) -> &mut Self::JustificationSyncLink {
+		&mut self.justification_sync_link
+	}
+
+	fn proposer(&mut self, block: &B::Header) -> Self::CreateProposer {
+		Box::pin(self.env.init(block).map_err(|e| ConsensusError::ClientImport(e.to_string())))
+	}
+
+	fn telemetry(&self) -> Option<TelemetryHandle> {
+		self.telemetry.clone()
+	}
+
+	fn proposing_remaining_duration(&self, slot_info: &SlotInfo<B>) -> Duration {
+		let parent_slot = find_slot::<B>(&slot_info.chain_head).ok();
+
+		sc_consensus_slots::proposing_remaining_duration(
+			parent_slot,
+			slot_info,
+			&self.block_proposal_slot_portion,
+			self.max_block_proposal_slot_portion.as_ref(),
+			sc_consensus_slots::SlotLenienceType::Exponential,
+			self.logging_target(),
+		)
+	}
+}
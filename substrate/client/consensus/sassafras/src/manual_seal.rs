@@ -0,0 +1,174 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Manual-seal consensus data provider for Sassafras runtimes.
+//!
+//! Lives here rather than in `sc-consensus-manual-seal`, as this crate is not published yet.
+
+use crate::{
+	authorship, configuration, Epoch, SassafrasApi, SassafrasConfiguration, SassafrasIntermediate,
+	INTERMEDIATE_KEY, LOG_TARGET,
+};
+use sc_client_api::{AuxStore, UsageProvider};
+use sc_consensus::BlockImportParams;
+use sc_consensus_epochs::{descendent_query, SharedEpochChanges, ViableEpochDescriptor};
+use sc_consensus_manual_seal::{ConsensusDataProvider, Error};
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::{HeaderBackend, HeaderMetadata};
+use sp_consensus_sassafras::inherents::SassafrasInherentData;
+use sp_consensus_slots::{Slot, SlotDuration};
+use sp_inherents::InherentData;
+use sp_keystore::KeystorePtr;
+use sp_runtime::{
+	traits::{Block as BlockT, Header, NumberFor},
+	Digest, DigestItem,
+};
+use sp_timestamp::TimestampInherentData;
+use std::{marker::PhantomData, sync::Arc};
+
+/// Provides Sassafras-compatible predigests for blocks authored by manual seal.
+///
+/// The slots are claimed exactly as by the Sassafras authoring worker, thus the keystore
+/// must contain the key of an authority of the current epoch.
+pub struct SassafrasConsensusDataProvider<B: BlockT, C, P> {
+	/// Shared reference to keystore.
+	keystore: KeystorePtr,
+	/// Shared reference to the client.
+	client: Arc<C>,
+	/// Shared epoch changes.
+	epoch_changes: SharedEpochChanges<B, Epoch>,
+	/// Sassafras config, read from the runtime at creation.
+	config: SassafrasConfiguration,
+	_phantom: PhantomData<P>,
+}
+
+impl<B, C, P> SassafrasConsensusDataProvider<B, C, P>
+where
+	B: BlockT,
+	C: AuxStore
+		+ HeaderBackend<B>
+		+ ProvideRuntimeApi<B>
+		+ HeaderMetadata<B, Error = sp_blockchain::Error>
+		+ UsageProvider<B>,
+	C::Api: SassafrasApi<B>,
+{
+	/// Create a new instance of the provider.
+	pub fn new(
+		client: Arc<C>,
+		keystore: KeystorePtr,
+		epoch_changes: SharedEpochChanges<B, Epoch>,
+		slot_duration: SlotDuration,
+	) -> Result<Self, Error> {
+		let config = configuration(&*client, slot_duration)?;
+		Ok(Self { keystore, client, epoch_changes, config, _phantom: Default::default() })
+	}
+
+	fn slot(&self, inherents: &InherentData) -> Result<Slot, Error> {
+		if let Some(slot) = inherents.sassafras_inherent_data()? {
+			return Ok(slot)
+		}
+		let timestamp = inherents
+			.timestamp_inherent_data()?
+			.ok_or_else(|| Error::StringError("No timestamp inherent data".into()))?;
+		Ok(Slot::from_timestamp(timestamp, self.config.slot_duration()))
+	}
+
+	fn epoch_descriptor(
+		&self,
+		parent: &B::Header,
+		slot: Slot,
+	) -> Result<ViableEpochDescriptor<B::Hash, NumberFor<B>, Epoch>, Error> {
+		self.epoch_changes
+			.shared_data()
+			.epoch_descriptor_for_child_of(
+				descendent_query(&*self.client),
+				&parent.hash(),
+				*parent.number(),
+				slot,
+			)
+			.map_err(|e| Error::StringError(format!("failed to fetch epoch_descriptor: {}", e)))?
+			.ok_or_else(|| sp_consensus::Error::InvalidAuthoritiesSet.into())
+	}
+}
+
+impl<B, C, P> ConsensusDataProvider<B> for SassafrasConsensusDataProvider<B, C, P>
+where
+	B: BlockT,
+	C: AuxStore
+		+ HeaderBackend<B>
+		+ HeaderMetadata<B, Error = sp_blockchain::Error>
+		+ UsageProvider<B>
+		+ ProvideRuntimeApi<B>,
+	C::Api: SassafrasApi<B>,
+	P: Send + Sync,
+{
+	type Proof = P;
+
+	fn create_digest(&self, parent: &B::Header, inherents: &InherentData) -> Result<Digest, Error> {
+		let slot = self.slot(inherents)?;
+		let epoch_descriptor = self.epoch_descriptor(parent, slot)?;
+		let epoch = self
+			.epoch_changes
+			.shared_data()
+			.viable_epoch(&epoch_descriptor, |slot| Epoch::genesis(&self.config, slot))
+			.ok_or(sp_consensus::Error::InvalidAuthoritiesSet)?
+			.as_ref()
+			.clone();
+
+		let maybe_ticket = self
+			.client
+			.runtime_api()
+			.slot_ticket(parent.hash(), slot)
+			.map_err(|e| Error::Other(Box::new(e)))?;
+		let ticket_id = maybe_ticket.as_ref().map(|(ticket_id, _)| *ticket_id);
+
+		let (claim, _) = authorship::claim_slot(slot, &epoch, maybe_ticket, &self.keystore)
+			.ok_or_else(|| {
+				log::info!(target: LOG_TARGET, "create_digest: unable to claim slot {}", slot);
+				Error::StringError(format!("Unable to claim slot {}", slot))
+			})?;
+
+		if let Some(ticket_id) = ticket_id {
+			authorship::erase_ticket_secret(
+				&*self.client,
+				&self.epoch_changes,
+				&epoch_descriptor,
+				ticket_id,
+			)
+			.map_err(|e| Error::StringError(e.to_string()))?;
+		}
+
+		Ok(Digest { logs: vec![DigestItem::from(&claim)] })
+	}
+
+	fn append_block_import(
+		&self,
+		parent: &B::Header,
+		params: &mut BlockImportParams<B>,
+		inherents: &InherentData,
+		_proof: Self::Proof,
+	) -> Result<(), Error> {
+		let slot = self.slot(inherents)?;
+		let epoch_descriptor = self.epoch_descriptor(parent, slot)?;
+
+		params
+			.insert_intermediate(INTERMEDIATE_KEY, SassafrasIntermediate::<B> { epoch_descriptor });
+
+		Ok(())
+	}
+}
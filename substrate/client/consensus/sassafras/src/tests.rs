@@ -0,0 +1,619 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Sassafras client tests.
+
+use super::*;
+use crate::{
+	authorship::{claim_slot, fallback_authority_index},
+	manual_seal::SassafrasConsensusDataProvider,
+	verification::{check_header, VerificationParams},
+};
+use futures::executor::block_on;
+use sc_block_builder::BlockBuilderBuilder;
+use sc_client_api::{ClientInfo, OnFinalityAction, OnImportAction, PreCommitActions};
+use sc_consensus::{BlockImport, BlockImportParams, ImportResult, Verifier};
+use sc_consensus_epochs::EpochIdentifier;
+use sc_consensus_manual_seal::ConsensusDataProvider;
+use sc_consensus_slots::CheckedHeader;
+use sc_transaction_pool_api::{OffchainTransactionPoolFactory, RejectAllTxPool};
+use sp_api::{ApiRef, ProvideRuntimeApi};
+use sp_blockchain::{BlockStatus, CachedHeaderMetadata, Info};
+use sp_consensus::BlockOrigin;
+use sp_consensus_sassafras::{
+	inherents::{InherentDataProvider, SassafrasInherentData},
+	EquivocationProof, OpaqueKeyOwnershipProof, KEY_TYPE,
+};
+use sp_core::{crypto::Pair as _, ed25519};
+use sp_inherents::{CheckInherentsResult, InherentData};
+use sp_keystore::{testing::MemoryKeystore, Keystore, KeystorePtr};
+use sp_runtime::{ApplyExtrinsicResult, Digest, DigestItem};
+use std::sync::Mutex;
+use substrate_test_runtime_client::{
+	runtime::{Block as TestBlock, Extrinsic, Hash, Header as TestHeader},
+	DefaultTestClientBuilderExt, TestClientBuilder, TestClientBuilderExt,
+};
+
+const EPOCH_LENGTH: u32 = 10;
+
+type TicketsMap = Arc<Mutex<BTreeMap<Slot, (TicketId, TicketBody)>>>;
+
+/// Test client, backed by the test runtime client, which mocks the Sassafras runtime API.
+#[derive(Clone)]
+struct TestClient {
+	inner: Arc<substrate_test_runtime_client::TestClient>,
+	genesis_epoch: sp_consensus_sassafras::Epoch,
+	tickets: TicketsMap,
+}
+
+struct RuntimeApi {
+	inner: TestClient,
+}
+
+impl ProvideRuntimeApi<TestBlock> for TestClient {
+	type Api = RuntimeApi;
+
+	fn runtime_api(&self) -> ApiRef<'_, Self::Api> {
+		RuntimeApi { inner: self.clone() }.into()
+	}
+}
+
+sp_api::mock_impl_runtime_apis! {
+	impl SassafrasApi<TestBlock> for RuntimeApi {
+		fn ring_context(&self) -> Option<vrf::RingContext> {
+			None
+		}
+
+		fn submit_tickets_unsigned_extrinsic(&self, _tickets: Vec<TicketEnvelope>) -> bool {
+			false
+		}
+
+		fn slot_ticket_id(&self, slot: Slot) -> Option<TicketId> {
+			self.inner.tickets.lock().unwrap().get(&slot).map(|(ticket_id, _)| *ticket_id)
+		}
+
+		fn slot_ticket(&self, slot: Slot) -> Option<(TicketId, TicketBody)> {
+			self.inner.tickets.lock().unwrap().get(&slot).cloned()
+		}
+
+		fn current_epoch(&self) -> sp_consensus_sassafras::Epoch {
+			self.inner.genesis_epoch.clone()
+		}
+
+		fn next_epoch(&self) -> sp_consensus_sassafras::Epoch {
+			self.inner.genesis_epoch.clone()
+		}
+
+		fn generate_key_ownership_proof(
+			&self,
+			_authority_id: AuthorityId,
+		) -> Option<OpaqueKeyOwnershipProof> {
+			None
+		}
+
+		fn submit_report_equivocation_unsigned_extrinsic(
+			&self,
+			_equivocation_proof: EquivocationProof<TestHeader>,
+			_key_owner_proof: OpaqueKeyOwnershipProof,
+		) -> bool {
+			false
+		}
+	}
+
+	impl sp_block_builder::BlockBuilder<TestBlock> for RuntimeApi {
+		fn apply_extrinsic(&self, _extrinsic: Extrinsic) -> ApplyExtrinsicResult {
+			unimplemented!("blocks are built with the test runtime")
+		}
+
+		fn finalize_block(&self) -> TestHeader {
+			unimplemented!("blocks are built with the test runtime")
+		}
+
+		fn inherent_extrinsics(&self, _data: InherentData) -> Vec<Extrinsic> {
+			unimplemented!("blocks are built with the test runtime")
+		}
+
+		fn check_inherents(&self, _block: TestBlock, _data: InherentData) -> CheckInherentsResult {
+			CheckInherentsResult::new()
+		}
+	}
+}
+
+impl HeaderBackend<TestBlock> for TestClient {
+	fn header(&self, hash: Hash) -> ClientResult<Option<TestHeader>> {
+		self.inner.header(hash)
+	}
+
+	fn info(&self) -> Info<TestBlock> {
+		self.inner.info()
+	}
+
+	fn status(&self, hash: Hash) -> ClientResult<BlockStatus> {
+		self.inner.status(hash)
+	}
+
+	fn number(&self, hash: Hash) -> ClientResult<Option<u64>> {
+		self.inner.number(hash)
+	}
+
+	fn hash(&self, number: u64) -> ClientResult<Option<Hash>> {
+		self.inner.hash(number)
+	}
+}
+
+impl HeaderMetadata<TestBlock> for TestClient {
+	type Error = ClientError;
+
+	fn header_metadata(&self, hash: Hash) -> ClientResult<CachedHeaderMetadata<TestBlock>> {
+		self.inner.header_metadata(hash)
+	}
+
+	fn insert_header_metadata(&self, hash: Hash, metadata: CachedHeaderMetadata<TestBlock>) {
+		self.inner.insert_header_metadata(hash, metadata)
+	}
+
+	fn remove_header_metadata(&self, hash: Hash) {
+		self.inner.remove_header_metadata(hash)
+	}
+}
+
+impl AuxStore for TestClient {
+	fn insert_aux<
+		'a,
+		'b: 'a,
+		'c: 'a,
+		I: IntoIterator<Item = &'a (&'c [u8], &'c [u8])>,
+		D: IntoIterator<Item = &'a &'b [u8]>,
+	>(
+		&self,
+		insert: I,
+		delete: D,
+	) -> ClientResult<()> {
+		self.inner.insert_aux(insert, delete)
+	}
+
+	fn get_aux(&self, key: &[u8]) -> ClientResult<Option<Vec<u8>>> {
+		self.inner.get_aux(key)
+	}
+}
+
+impl UsageProvider<TestBlock> for TestClient {
+	fn usage_info(&self) -> ClientInfo<TestBlock> {
+		self.inner.usage_info()
+	}
+}
+
+impl PreCommitActions<TestBlock> for TestClient {
+	fn register_import_action(&self, action: OnImportAction<TestBlock>) {
+		self.inner.register_import_action(action)
+	}
+
+	fn register_finality_action(&self, action: OnFinalityAction<TestBlock>) {
+		self.inner.register_finality_action(action)
+	}
+}
+
+fn create_keystore(authorities: usize) -> (KeystorePtr, Vec<AuthorityId>) {
+	let keystore = MemoryKeystore::new();
+	let authorities = (0..authorities)
+		.map(|i| {
+			let seed = format!("//Authority{}", i);
+			keystore.bandersnatch_generate_new(KEY_TYPE, Some(&seed)).unwrap().into()
+		})
+		.collect();
+	(keystore.into(), authorities)
+}
+
+fn create_epoch(authorities: Vec<AuthorityId>) -> Epoch {
+	sp_consensus_sassafras::Epoch {
+		index: 3,
+		start: 30.into(),
+		length: EPOCH_LENGTH,
+		randomness: [7; 32],
+		authorities,
+		config: EpochConfiguration { redundancy_factor: 1, attempts_number: 4 },
+	}
+	.into()
+}
+
+fn sealed_header(claim: &SlotClaim, keystore: &KeystorePtr, author: &AuthorityId) -> TestHeader {
+	let mut header = TestHeader::new(
+		2,
+		Default::default(),
+		Default::default(),
+		Default::default(),
+		Digest { logs: vec![DigestItem::from(claim)] },
+	);
+	let signature: AuthoritySignature = keystore
+		.bandersnatch_sign(KEY_TYPE, author.as_ref(), header.hash().as_ref())
+		.unwrap()
+		.unwrap()
+		.into();
+	header.digest_mut().push(DigestItem::from(&signature));
+	header
+}
+
+fn check(
+	header: TestHeader,
+	epoch: &Epoch,
+	maybe_ticket: Option<(TicketId, TicketBody)>,
+) -> Result<CheckedHeader<TestHeader, AuthorityId>, Error<TestBlock>> {
+	let slot_claim = find_slot_claim::<TestBlock>(&header)?;
+	check_header::<TestBlock>(VerificationParams {
+		header,
+		slot_claim: &slot_claim,
+		slot_now: slot_claim.slot + 1,
+		epoch,
+		maybe_ticket,
+	})
+	.map(|checked| match checked {
+		CheckedHeader::Checked(header, info) => CheckedHeader::Checked(header, info.author),
+		CheckedHeader::Deferred(header, slot) => CheckedHeader::Deferred(header, slot),
+	})
+}
+
+#[test]
+fn epoch_increment_uses_announced_data() {
+	let (_, authorities) = create_keystore(2);
+	let epoch = create_epoch(authorities.clone());
+
+	let next = epoch.increment(NextEpochDescriptor {
+		randomness: [1; 32],
+		authorities: authorities[..1].to_vec(),
+		config: None,
+	});
+	assert_eq!(next.index, 4);
+	assert_eq!(next.start, epoch.end_slot());
+	assert_eq!(next.randomness, [1; 32]);
+	assert_eq!(next.authorities, authorities[..1].to_vec());
+	assert_eq!(next.config, epoch.config);
+
+	let config = EpochConfiguration { redundancy_factor: 2, attempts_number: 8 };
+	let next = epoch.increment(NextEpochDescriptor {
+		randomness: [1; 32],
+		authorities,
+		config: Some(config),
+	});
+	assert_eq!(next.config, config);
+}
+
+#[test]
+fn clone_for_slot_drops_tickets_of_skipped_epochs() {
+	let (_, authorities) = create_keystore(1);
+	let mut epoch = create_epoch(authorities);
+	epoch
+		.tickets_aux
+		.insert(42, TicketSecret { authority_idx: 0, erased_seed: [0; 32] });
+
+	let same = epoch.clone_for_slot(epoch.start + 5);
+	assert_eq!(same, epoch);
+
+	let skipped = epoch.clone_for_slot(epoch.start + 2 * EPOCH_LENGTH as u64 + 5);
+	assert_eq!(skipped.index, epoch.index + 2);
+	assert_eq!(skipped.start, epoch.start + 2 * EPOCH_LENGTH as u64);
+	assert!(skipped.tickets_aux.is_empty());
+}
+
+#[test]
+fn fallback_claim_is_verified() {
+	let (keystore, authorities) = create_keystore(3);
+	let epoch = create_epoch(authorities.clone());
+	let slot = epoch.start + 1;
+
+	let expected_idx = fallback_authority_index(&epoch.randomness, slot, authorities.len());
+	let (claim, author) = claim_slot(slot, &epoch, None, &keystore).unwrap();
+	assert_eq!(claim.authority_idx, expected_idx);
+	assert_eq!(author, authorities[expected_idx as usize]);
+	assert!(claim.ticket_claim.is_none());
+
+	let header = sealed_header(&claim, &keystore, &author);
+	assert!(matches!(
+		check(header.clone(), &epoch, None),
+		Ok(CheckedHeader::Checked(_, checked_author)) if checked_author == author
+	));
+
+	// Author doesn't match the authority at the claimed index.
+	let other_idx = (expected_idx + 1) % authorities.len() as AuthorityIndex;
+	let mut other_epoch = epoch.clone();
+	other_epoch.authorities.swap(expected_idx as usize, other_idx as usize);
+	assert!(matches!(check(header.clone(), &other_epoch, None), Err(Error::BadSignature(_))));
+
+	// Header from the future is deferred.
+	let slot_claim = find_slot_claim::<TestBlock>(&header).unwrap();
+	assert!(matches!(
+		check_header::<TestBlock>(VerificationParams {
+			header,
+			slot_claim: &slot_claim,
+			slot_now: slot - 1,
+			epoch: &epoch,
+			maybe_ticket: None,
+		}),
+		Ok(CheckedHeader::Deferred(_, s)) if s == slot
+	));
+}
+
+#[test]
+fn ticket_claim_is_verified() {
+	let (keystore, authorities) = create_keystore(3);
+	let mut epoch = create_epoch(authorities.clone());
+	let slot = epoch.start + 2;
+
+	let erased_seed = [3; 32];
+	let ticket_id = 0x1234;
+	let ticket_body = TicketBody {
+		attempt_idx: 0,
+		erased_public: ed25519::Pair::from_seed(&erased_seed).public(),
+		revealed_public: ed25519::Pair::from_seed(&[4; 32]).public(),
+	};
+	// Not one of our tickets.
+	assert!(claim_slot(slot, &epoch, Some((ticket_id, ticket_body.clone())), &keystore).is_none());
+
+	// Pick an authority which is not the fallback one.
+	let fallback_idx = fallback_authority_index(&epoch.randomness, slot, authorities.len());
+	let authority_idx = (fallback_idx + 1) % authorities.len() as AuthorityIndex;
+	epoch.tickets_aux.insert(ticket_id, TicketSecret { authority_idx, erased_seed });
+
+	let (claim, author) =
+		claim_slot(slot, &epoch, Some((ticket_id, ticket_body.clone())), &keystore).unwrap();
+	assert_eq!(claim.authority_idx, authority_idx);
+	assert!(claim.ticket_claim.is_some());
+
+	let header = sealed_header(&claim, &keystore, &author);
+	assert!(matches!(
+		check(header.clone(), &epoch, Some((ticket_id, ticket_body.clone()))),
+		Ok(CheckedHeader::Checked(..))
+	));
+
+	// The runtime doesn't know about the ticket.
+	assert!(matches!(
+		check(header.clone(), &epoch, None),
+		Err(Error::UnexpectedTicketClaim(s)) if s == slot
+	));
+
+	// Erased key mismatch.
+	let mut wrong_body = ticket_body.clone();
+	wrong_body.erased_public = ticket_body.revealed_public;
+	assert!(matches!(
+		check(header, &epoch, Some((ticket_id, wrong_body))),
+		Err(Error::TicketClaimVerificationFailed)
+	));
+
+	// Ticket claim stripped, the author is not the fallback one.
+	let mut stripped_claim = claim.clone();
+	stripped_claim.ticket_claim = None;
+	let header = sealed_header(&stripped_claim, &keystore, &author);
+	assert!(matches!(
+		check(header, &epoch, None),
+		Err(Error::InvalidAuthor(expected, found))
+			if expected == authorities[fallback_idx as usize] && found == author
+	));
+
+	// Fallback claim for a slot with a ticket.
+	let (fallback_claim, fallback_author) = claim_slot(slot, &epoch, None, &keystore).unwrap();
+	let header = sealed_header(&fallback_claim, &keystore, &fallback_author);
+	assert!(matches!(
+		check(header, &epoch, Some((ticket_id, ticket_body))),
+		Err(Error::ExpectedTicketClaim(s)) if s == slot
+	));
+}
+
+/// Everything needed to author blocks with manual seal and import them as if they came from
+/// the network.
+struct TestNode {
+	client: Arc<TestClient>,
+	keystore: KeystorePtr,
+	authorities: Vec<AuthorityId>,
+	link: SassafrasLink<TestBlock>,
+	select_chain: sc_consensus::LongestChain<substrate_test_runtime_client::Backend, TestBlock>,
+	provider: SassafrasConsensusDataProvider<TestBlock, TestClient, ()>,
+	block_import:
+		SassafrasBlockImport<TestBlock, TestClient, Arc<substrate_test_runtime_client::TestClient>>,
+}
+
+impl TestNode {
+	fn new(authorities: usize) -> Self {
+		let (keystore, authorities) = create_keystore(authorities);
+		let mut genesis_epoch = (*create_epoch(authorities.clone())).clone();
+		genesis_epoch.index = 0;
+		genesis_epoch.start = 0.into();
+		let (inner, select_chain) = TestClientBuilder::new().build_with_longest_chain();
+		let client = Arc::new(TestClient {
+			inner: Arc::new(inner),
+			genesis_epoch,
+			tickets: Default::default(),
+		});
+		let slot_duration = SlotDuration::from_millis(1000);
+		let config = configuration(&*client, slot_duration).unwrap();
+		let (block_import, link) =
+			crate::block_import(config, client.inner.clone(), client.clone()).unwrap();
+		let provider = SassafrasConsensusDataProvider::new(
+			client.clone(),
+			keystore.clone(),
+			link.epoch_changes().clone(),
+			slot_duration,
+		)
+		.unwrap();
+		TestNode { client, keystore, authorities, link, select_chain, provider, block_import }
+	}
+
+	fn inherents(slot: u64) -> InherentData {
+		let mut inherents = InherentData::new();
+		inherents.sassafras_replace_inherent_data(slot.into());
+		inherents
+	}
+
+	/// Author a block on top of `parent` with the manual seal provider, the way a dev node does,
+	/// and seal it with the key of the slot author.
+	fn author(&self, parent: &TestHeader, slot: u64, extra_digests: Vec<DigestItem>) -> TestBlock {
+		let mut digest = self.provider.create_digest(parent, &Self::inherents(slot)).unwrap();
+		digest.logs.extend(extra_digests);
+		let claim = digest.logs.iter().find_map(|log| SlotClaim::try_from(log).ok()).unwrap();
+
+		let block = BlockBuilderBuilder::new(&*self.client.inner)
+			.on_parent_block(parent.hash())
+			.with_parent_block_number(*parent.number())
+			.with_inherent_digests(digest)
+			.build()
+			.unwrap()
+			.build()
+			.unwrap()
+			.block;
+
+		let (mut header, body) = block.deconstruct();
+		let author = &self.authorities[claim.authority_idx as usize];
+		let signature: AuthoritySignature = self
+			.keystore
+			.bandersnatch_sign(KEY_TYPE, author.as_ref(), header.hash().as_ref())
+			.unwrap()
+			.unwrap()
+			.into();
+		header.digest_mut().push(DigestItem::from(&signature));
+		TestBlock::new(header, body)
+	}
+
+	/// Verify and import the block as if it was received from the network.
+	fn import(&self, block: TestBlock) -> ImportResult {
+		let verifier = SassafrasVerifier::new(
+			self.client.clone(),
+			self.select_chain.clone(),
+			|_, ()| async { Ok((InherentDataProvider::new(100.into()),)) },
+			self.link.clone(),
+			None,
+			OffchainTransactionPoolFactory::new(RejectAllTxPool::default()),
+		);
+		let (header, body) = block.deconstruct();
+		let mut params = BlockImportParams::new(BlockOrigin::NetworkBroadcast, header);
+		params.body = Some(body);
+		let params = block_on(verifier.verify(params)).unwrap();
+		block_on(self.block_import.import_block(params)).unwrap()
+	}
+
+	fn epoch_id(&self, parent: &TestHeader, slot: u64) -> EpochIdentifier<Hash, u64> {
+		match self
+			.link
+			.epoch_changes()
+			.shared_data()
+			.epoch_descriptor_for_child_of(
+				descendent_query(&*self.client),
+				&parent.hash(),
+				*parent.number(),
+				slot.into(),
+			)
+			.unwrap()
+			.unwrap()
+		{
+			ViableEpochDescriptor::Signaled(id, _) => id,
+			ViableEpochDescriptor::UnimportedGenesis(_) => panic!("genesis epoch is imported"),
+		}
+	}
+}
+
+#[test]
+fn manual_seal_provider_claims_slots() {
+	let node = TestNode::new(2);
+	let genesis = node.client.header(node.client.info().genesis_hash).unwrap().unwrap();
+
+	let digest = node.provider.create_digest(&genesis, &TestNode::inherents(3)).unwrap();
+	let [claim_log] = &digest.logs[..] else { panic!("a single slot claim is expected") };
+	let claim = SlotClaim::try_from(claim_log).unwrap();
+	assert_eq!(claim.slot, 3.into());
+	assert!(claim.ticket_claim.is_none());
+
+	let mut params = BlockImportParams::new(BlockOrigin::Own, genesis.clone());
+	node.provider
+		.append_block_import(&genesis, &mut params, &TestNode::inherents(3), ())
+		.unwrap();
+	let intermediate = params
+		.remove_intermediate::<SassafrasIntermediate<TestBlock>>(INTERMEDIATE_KEY)
+		.unwrap();
+	assert!(matches!(
+		intermediate.epoch_descriptor,
+		ViableEpochDescriptor::UnimportedGenesis(slot) if slot == 3.into()
+	));
+
+	// Not an authority of the epoch.
+	let (keystore, _) = create_keystore(0);
+	let provider = SassafrasConsensusDataProvider::<_, _, ()>::new(
+		node.client.clone(),
+		keystore,
+		node.link.epoch_changes().clone(),
+		SlotDuration::from_millis(1000),
+	)
+	.unwrap();
+	assert!(provider.create_digest(&genesis, &TestNode::inherents(3)).is_err());
+}
+
+#[test]
+fn authored_blocks_are_imported_and_ticket_secrets_erased() {
+	let node = TestNode::new(2);
+	let genesis = node.client.header(node.client.info().genesis_hash).unwrap().unwrap();
+
+	// The first block announces the next epoch.
+	let next_epoch = ConsensusLog::NextEpochData(NextEpochDescriptor {
+		randomness: [1; 32],
+		authorities: node.authorities.clone(),
+		config: None,
+	});
+	let block = node.author(
+		&genesis,
+		1,
+		vec![DigestItem::Consensus(SASSAFRAS_ENGINE_ID, next_epoch.encode())],
+	);
+	let header1 = block.header().clone();
+	assert!(matches!(node.import(block), ImportResult::Imported(_)));
+	assert_eq!(block_weight(&*node.client, header1.hash()).unwrap(), Some(0));
+
+	// Give one of our tickets to slot 2.
+	let (ticket_id, erased_seed) = (0x1234, [3; 32]);
+	let ticket_body = TicketBody {
+		attempt_idx: 0,
+		erased_public: ed25519::Pair::from_seed(&erased_seed).public(),
+		revealed_public: ed25519::Pair::from_seed(&[4; 32]).public(),
+	};
+	node.client.tickets.lock().unwrap().insert(2.into(), (ticket_id, ticket_body));
+	let epoch_id = node.epoch_id(&header1, 2);
+	node.link
+		.epoch_changes()
+		.shared_data()
+		.epoch_mut(&epoch_id)
+		.unwrap()
+		.tickets_aux
+		.insert(ticket_id, TicketSecret { authority_idx: 1, erased_seed });
+
+	let block = node.author(&header1, 2, vec![]);
+	let header2 = block.header().clone();
+
+	// The secret is gone, both from memory and from the aux storage.
+	assert!(node
+		.link
+		.epoch_changes()
+		.shared_data()
+		.epoch(&epoch_id)
+		.unwrap()
+		.tickets_aux
+		.is_empty());
+	let stored = aux_schema::load_epoch_changes::<TestBlock, _>(&*node.client).unwrap();
+	assert!(stored.shared_data().epoch(&epoch_id).unwrap().tickets_aux.is_empty());
+
+	// The ticket can't be claimed twice.
+	assert!(node.provider.create_digest(&header1, &TestNode::inherents(2)).is_err());
+
+	assert!(matches!(node.import(block), ImportResult::Imported(_)));
+	assert_eq!(block_weight(&*node.client, header2.hash()).unwrap(), Some(1));
+	assert_eq!(node.client.info().best_hash, header2.hash());
+}
@@ -1044,6 +1044,23 @@ impl<Block: BlockT> sp_state_machine::Storage<HashingFor<Block>> for DbGenesisSt
 	}
 }
 
+/// Trie nodes produced by re-executing blocks on top of a state snapshot, backed by the
+/// snapshot state in the database.
+struct ReplayStorage<Block: BlockT> {
+	changes: PrefixedMemoryDB<HashingFor<Block>>,
+	storage: Arc<StorageDb<Block>>,
+}
+
+impl<Block: BlockT> sp_state_machine::Storage<HashingFor<Block>> for ReplayStorage<Block> {
+	fn get(&self, key: &Block::Hash, prefix: Prefix) -> Result<Option<DBValue>, String> {
+		use hash_db::HashDB;
+		match self.changes.get(key, prefix) {
+			Some(value) => Ok(Some(value)),
+			None => sp_state_machine::Storage::get(self.storage.as_ref(), key, prefix),
+		}
+	}
+}
+
 struct EmptyStorage<Block: BlockT>(pub Block::Hash);
 
 impl<Block: BlockT> EmptyStorage<Block> {
@@ -2540,6 +2557,43 @@ impl<Block: BlockT> sc_client_api::backend::Backend<Block> for Backend<Block> {
 		}
 	}
 
+	fn state_snapshot(&self, number: NumberFor<Block>) -> Option<(NumberFor<Block>, Block::Hash)> {
+		self.storage
+			.state_db
+			.nearest_snapshot(number.saturated_into::<u64>())
+			.map(|(number, hash)| (number.saturated_into(), hash))
+	}
+
+	fn state_with_changes(
+		&self,
+		base: Block::Hash,
+		changes: BackendTransaction<HashingFor<Block>>,
+		root: Block::Hash,
+	) -> ClientResult<Self::State> {
+		let hdr = self.blockchain.header_metadata(base)?;
+		let hint = || {
+			sc_state_db::NodeDb::get(self.storage.as_ref(), hdr.state_root.as_ref())
+				.unwrap_or(None)
+				.is_some()
+		};
+		if self
+			.storage
+			.state_db
+			.pin(&base, hdr.number.saturated_into::<u64>(), hint)
+			.is_err()
+		{
+			return Err(sp_blockchain::Error::UnknownBlock(format!(
+				"State already discarded for {base:?}",
+			)))
+		}
+
+		let storage: Arc<dyn sp_state_machine::Storage<HashingFor<Block>>> =
+			Arc::new(ReplayStorage { changes, storage: self.storage.clone() });
+		let db_state = DbStateBuilder::<HashingFor<Block>>::new(storage, root).build();
+		let state = RefTrackingState::new(db_state, self.storage.clone(), Some(base));
+		Ok(RecordStatsState::new(state, None, self.state_usage.clone()))
+	}
+
 	fn have_state_at(&self, hash: Block::Hash, number: NumberFor<Block>) -> bool {
 		if self.is_archive {
 			match self.blockchain.header_metadata(hash) {
@@ -2641,6 +2695,7 @@ pub(crate) mod tests {
 		traits::{BlakeTwo256, Hash},
 		ConsensusEngineId, StateVersion,
 	};
+	use std::num::NonZeroU32;
 
 	const CONS0_ENGINE_ID: ConsensusEngineId = *b"CON0";
 	const CONS1_ENGINE_ID: ConsensusEngineId = *b"CON1";
@@ -3080,6 +3135,102 @@ pub(crate) mod tests {
 			.is_none());
 	}
 
+	#[test]
+	fn hybrid_pruning_keeps_snapshots() {
+		let state_version = StateVersion::V1;
+		let paritydb_dir = tempfile::tempdir().unwrap();
+		let sources = [
+			DatabaseSource::Custom {
+				db: sp_database::as_database(kvdb_memorydb::create(crate::utils::NUM_COLUMNS)),
+				require_create_flag: true,
+			},
+			DatabaseSource::ParityDb { path: paritydb_dir.path().to_owned() },
+		];
+
+		for source in sources {
+			let backend = Backend::<Block>::new(
+				DatabaseSettings {
+					trie_cache_maximum_size: Some(16 * 1024 * 1024),
+					state_pruning: Some(PruningMode::hybrid(1, NonZeroU32::new(2).unwrap())),
+					source,
+					blocks_pruning: BlocksPruning::KeepFinalized,
+					metrics_registry: None,
+				},
+				0,
+			)
+			.unwrap();
+
+			let genesis = {
+				let mut op = backend.begin_operation().unwrap();
+				backend.begin_state_operation(&mut op, Default::default()).unwrap();
+				let storage = vec![(vec![1], vec![0])];
+				let header = Header {
+					number: 0,
+					parent_hash: Default::default(),
+					state_root: op
+						.old_state
+						.storage_root(
+							storage.iter().map(|(k, v)| (&k[..], Some(&v[..]))),
+							state_version,
+						)
+						.0
+						.into(),
+					digest: Default::default(),
+					extrinsics_root: Default::default(),
+				};
+				op.reset_storage(
+					Storage {
+						top: storage.into_iter().collect(),
+						children_default: Default::default(),
+					},
+					state_version,
+				)
+				.unwrap();
+				op.set_block_data(header.clone(), Some(vec![]), None, None, NewBlockState::Final)
+					.unwrap();
+				backend.commit_operation(op).unwrap();
+				header.hash()
+			};
+
+			// Every block sets the value of the same key to its number.
+			let mut hashes = vec![genesis];
+			for number in 1..=5u64 {
+				let parent_hash = *hashes.last().unwrap();
+				let mut op = backend.begin_operation().unwrap();
+				backend.begin_state_operation(&mut op, parent_hash).unwrap();
+				let storage = vec![(vec![1], Some(vec![number as u8]))];
+				let (root, overlay) = op.old_state.storage_root(
+					storage.iter().map(|(k, v)| (&k[..], v.as_deref())),
+					state_version,
+				);
+				op.update_db_storage(overlay).unwrap();
+				let header = Header {
+					number,
+					parent_hash,
+					state_root: root.into(),
+					digest: Default::default(),
+					extrinsics_root: Default::default(),
+				};
+				op.update_storage(storage, Vec::new()).unwrap();
+				op.set_block_data(header.clone(), Some(vec![]), None, None, NewBlockState::Final)
+					.unwrap();
+				backend.commit_operation(op).unwrap();
+				hashes.push(header.hash());
+			}
+
+			for (number, hash) in hashes.iter().enumerate().take(4) {
+				let is_snapshot = number % 2 == 0;
+				assert_eq!(backend.have_state_at(*hash, number as u64), is_snapshot);
+				if is_snapshot {
+					let state = backend.state_at(*hash, TrieCacheContext::Untrusted).unwrap();
+					assert_eq!(state.storage(&[1]).unwrap(), Some(vec![number as u8]));
+				}
+			}
+			assert!(backend.have_state_at(hashes[5], 5));
+			assert_eq!(backend.storage.state_db.nearest_snapshot(3), Some((2, hashes[2])));
+		}
+	}
+
 	#[test]
 	fn tree_route_works() {
 		let backend = Backend::<Block>::new_test(1000, 100);
@@ -34,6 +34,9 @@ pub enum SyncMode {
 	},
 	/// Warp sync - verify authority set transitions and the latest state.
 	Warp,
+	/// Full block download and verification, downloading headers first and block bodies from
+	/// multiple peers in parallel.
+	HeaderFirst,
 }
 
 impl SyncMode {
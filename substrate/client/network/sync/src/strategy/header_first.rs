@@ -0,0 +1,1074 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Header-first syncing strategy.
+//!
+//! Headers and justifications are downloaded ahead of the best block, verified with a
+//! [`HeaderVerifier`] and linked into a chain first. Bodies of the linked headers are then
+//! requested in disjoint ranges from all available peers in parallel and queued for import as soon
+//! as a contiguous run of them is available. Close to the tip of the chain the strategy finishes
+//! and block sync continues with [`ChainSync`](crate::strategy::chain_sync::ChainSync).
+
+use crate::{
+	block_relay_protocol::{BlockDownloader, BlockResponseError},
+	blocks::BlockCollection,
+	service::network::NetworkServiceHandle,
+	strategy::{
+		chain_sync::{legacy_justification_mapping, validate_blocks},
+		disconnected_peers::DisconnectedPeers,
+		StrategyKey, SyncingAction,
+	},
+	types::{BadPeer, SyncState, SyncStatus},
+	LOG_TARGET,
+};
+use futures::FutureExt;
+use log::{debug, info, trace, warn};
+use sc_consensus::{BlockImportError, BlockImportStatus, IncomingBlock};
+use sc_network::ProtocolName;
+use sc_network_common::sync::message::{
+	BlockAnnounce, BlockAttributes, BlockData, BlockRequest, Direction, FromBlock,
+};
+use sc_network_types::PeerId;
+use sp_blockchain::HeaderBackend;
+use sp_consensus::BlockOrigin;
+use sp_runtime::{
+	traits::{Block as BlockT, Header, NumberFor, One},
+	Justifications, SaturatedConversion,
+};
+use std::{
+	any::Any,
+	cmp,
+	collections::{BTreeMap, HashMap, HashSet},
+	fmt,
+	sync::Arc,
+};
+
+/// Distance below the median best block of the peers at which the strategy finishes and hands
+/// over to `ChainSync`, which deals with forks near the tip.
+const CHAIN_SYNC_HANDOVER_DISTANCE: u32 = 256;
+
+/// Maximum number of linked headers kept ahead of the last block queued for import.
+const MAX_HEADERS_AHEAD: u32 = 8192;
+
+/// Maximum number of blocks whose bodies are downloaded ahead of the last block queued for
+/// import.
+const MAX_BODIES_AHEAD: u32 = 2048;
+
+/// Number of consecutive header ranges failing to extend the header chain after which the
+/// strategy gives up and hands over to `ChainSync`.
+const MAX_LINK_FAILURES: usize = 8;
+
+mod rep {
+	use sc_network::ReputationChange as Rep;
+
+	/// Reputation change for peers which send us a known bad block.
+	pub const BAD_BLOCK: Rep = Rep::new(-(1 << 29), "Bad block");
+
+	/// Reputation change for peers which send us a block which we fail to verify.
+	pub const VERIFICATION_FAIL: Rep = Rep::new(-(1 << 29), "Block verification failed");
+
+	/// Peer did not provide us with advertised block data.
+	pub const NO_BLOCK: Rep = Rep::new(-(1 << 29), "No requested block data");
+
+	/// Reputation change for peers which send us non-requested block data.
+	pub const NOT_REQUESTED: Rep = Rep::new(-(1 << 29), "Not requested block data");
+
+	/// Peer response data does not have requested bits.
+	pub const BAD_RESPONSE: Rep = Rep::new(-(1 << 12), "Incomplete response");
+
+	/// We received a message that failed to decode.
+	pub const BAD_MESSAGE: Rep = Rep::new(-(1 << 12), "Bad message");
+}
+
+enum PeerState<B: BlockT> {
+	Available,
+	DownloadingHeaders,
+	DownloadingBodies(NumberFor<B>),
+}
+
+impl<B: BlockT> PeerState<B> {
+	fn is_available(&self) -> bool {
+		matches!(self, PeerState::Available)
+	}
+}
+
+struct Peer<B: BlockT> {
+	best_number: NumberFor<B>,
+	state: PeerState<B>,
+}
+
+/// Verifies headers downloaded by [`HeaderFirstSync`] before any body is requested for them.
+///
+/// Headers are verified long before the state of their parent is available, so implementations
+/// can only rely on the headers and justifications themselves, e.g. to check seals and
+/// finality proofs against authority sets they track from the header digests.
+pub trait HeaderVerifier<B: BlockT>: fmt::Debug + Send + Sync {
+	/// Verify `header` and its `justifications`.
+	///
+	/// Called in ascending order for every header extending the header chain, starting with the
+	/// child of the best block at the time the strategy was created. A header failing
+	/// verification is followed by another header with the same parent.
+	fn verify(
+		&self,
+		header: &B::Header,
+		justifications: Option<&Justifications>,
+	) -> Result<(), String>;
+}
+
+/// Verifies headers with both verifiers, e.g. one checking the block production seals and one
+/// checking the finality proofs.
+impl<B: BlockT, A: HeaderVerifier<B>, C: HeaderVerifier<B>> HeaderVerifier<B> for (A, C) {
+	fn verify(
+		&self,
+		header: &B::Header,
+		justifications: Option<&Justifications>,
+	) -> Result<(), String> {
+		self.0.verify(header, justifications)?;
+		self.1.verify(header, justifications)
+	}
+}
+
+/// Header linked to the header chain whose body is not queued for import yet.
+struct LinkedHeader<B: BlockT> {
+	hash: B::Hash,
+	header: B::Header,
+	justifications: Option<Justifications>,
+}
+
+/// Syncing strategy that downloads headers first and block bodies in parallel.
+pub struct HeaderFirstSync<B: BlockT, Client> {
+	client: Arc<Client>,
+	block_downloader: Arc<dyn BlockDownloader<B>>,
+	header_verifier: Arc<dyn HeaderVerifier<B>>,
+	max_blocks_per_request: u32,
+	peers: HashMap<PeerId, Peer<B>>,
+	disconnected_peers: DisconnectedPeers,
+	/// Header ranges being downloaded or waiting to be linked to `header_tip`.
+	header_ranges: BlockCollection<B>,
+	/// Body ranges being downloaded or waiting for the preceding ranges to complete.
+	body_ranges: BlockCollection<B>,
+	/// Linked headers above `best_queued`.
+	headers: BTreeMap<NumberFor<B>, LinkedHeader<B>>,
+	/// Last header of the header chain.
+	header_tip: (B::Hash, NumberFor<B>),
+	/// Last block queued for import.
+	best_queued: (B::Hash, NumberFor<B>),
+	/// Blocks queued for import and not processed yet.
+	queue_blocks: HashSet<B::Hash>,
+	/// Number of consecutive header ranges that failed to extend `header_tip`.
+	link_failures: usize,
+	downloaded_blocks: usize,
+	actions: Vec<SyncingAction<B>>,
+	finished: bool,
+}
+
+impl<B, Client> HeaderFirstSync<B, Client>
+where
+	B: BlockT,
+	Client: HeaderBackend<B> + 'static,
+{
+	/// Strategy key used by header-first sync.
+	pub const STRATEGY_KEY: StrategyKey = StrategyKey::new("HeaderFirst");
+
+	/// Create a new instance starting at the current best block of `client`.
+	pub fn new(
+		client: Arc<Client>,
+		max_blocks_per_request: u32,
+		block_downloader: Arc<dyn BlockDownloader<B>>,
+		header_verifier: Arc<dyn HeaderVerifier<B>>,
+	) -> Self {
+		let info = client.info();
+		let best = (info.best_hash, info.best_number);
+
+		Self {
+			client,
+			block_downloader,
+			header_verifier,
+			max_blocks_per_request,
+			peers: HashMap::new(),
+			disconnected_peers: DisconnectedPeers::new(),
+			header_ranges: BlockCollection::new(),
+			body_ranges: BlockCollection::new(),
+			headers: BTreeMap::new(),
+			header_tip: best,
+			best_queued: best,
+			queue_blocks: HashSet::new(),
+			link_failures: 0,
+			downloaded_blocks: 0,
+			actions: Vec::new(),
+			finished: false,
+		}
+	}
+
+	/// Notify that a new peer has connected.
+	pub fn add_peer(&mut self, peer_id: PeerId, _best_hash: B::Hash, best_number: NumberFor<B>) {
+		self.peers.insert(peer_id, Peer { best_number, state: PeerState::Available });
+	}
+
+	/// Notify that a peer has disconnected.
+	pub fn remove_peer(&mut self, peer_id: &PeerId) {
+		if let Some(peer) = self.peers.remove(peer_id) {
+			if !peer.state.is_available() {
+				self.header_ranges.clear_peer_download(peer_id);
+				self.body_ranges.clear_peer_download(peer_id);
+
+				if let Some(bad_peer) =
+					self.disconnected_peers.on_disconnect_during_request(*peer_id)
+				{
+					self.actions.push(SyncingAction::DropPeer(bad_peer));
+				}
+			}
+		}
+	}
+
+	/// Submit a validated block announcement.
+	///
+	/// Returns new best hash & best number of the peer if they are updated.
+	#[must_use]
+	pub fn on_validated_block_announce(
+		&mut self,
+		is_best: bool,
+		peer_id: PeerId,
+		announce: &BlockAnnounce<B::Header>,
+	) -> Option<(B::Hash, NumberFor<B>)> {
+		is_best.then(|| {
+			let best_number = *announce.header.number();
+			let best_hash = announce.header.hash();
+			if let Some(ref mut peer) = self.peers.get_mut(&peer_id) {
+				peer.best_number = best_number;
+			}
+			// Let `SyncingEngine` know that we should update the peer info.
+			(best_hash, best_number)
+		})
+	}
+
+	/// Process generic response.
+	pub fn on_generic_response(
+		&mut self,
+		peer_id: &PeerId,
+		protocol_name: ProtocolName,
+		response: Box<dyn Any + Send>,
+	) {
+		if &protocol_name != self.block_downloader.protocol_name() {
+			warn!(
+				target: LOG_TARGET,
+				"Unexpected generic response protocol {protocol_name}, strategy key \
+				{:?}",
+				Self::STRATEGY_KEY,
+			);
+			debug_assert!(false);
+			return;
+		}
+
+		let Ok(response) =
+			response.downcast::<(BlockRequest<B>, Result<Vec<BlockData<B>>, BlockResponseError>)>()
+		else {
+			warn!(target: LOG_TARGET, "Failed to downcast block response");
+			debug_assert!(false);
+			return;
+		};
+
+		let (request, response) = *response;
+		let blocks = match response {
+			Ok(blocks) => blocks,
+			Err(BlockResponseError::DecodeFailed(e)) => {
+				debug!(
+					target: LOG_TARGET,
+					"Failed to decode block response from peer {:?}: {:?}.",
+					peer_id,
+					e
+				);
+				self.actions.push(SyncingAction::DropPeer(BadPeer(*peer_id, rep::BAD_MESSAGE)));
+				return;
+			},
+			Err(BlockResponseError::ExtractionFailed(e)) => {
+				debug!(
+					target: LOG_TARGET,
+					"Failed to extract blocks from peer response {:?}: {:?}.",
+					peer_id,
+					e
+				);
+				self.actions.push(SyncingAction::DropPeer(BadPeer(*peer_id, rep::BAD_MESSAGE)));
+				return;
+			},
+		};
+
+		self.on_block_response(*peer_id, request, blocks);
+	}
+
+	/// Process header or body range response.
+	pub fn on_block_response(
+		&mut self,
+		peer_id: PeerId,
+		request: BlockRequest<B>,
+		blocks: Vec<BlockData<B>>,
+	) {
+		if let Err(bad_peer) = self.on_block_response_inner(peer_id, request, blocks) {
+			self.actions.push(SyncingAction::DropPeer(bad_peer));
+		}
+	}
+
+	fn on_block_response_inner(
+		&mut self,
+		peer_id: PeerId,
+		request: BlockRequest<B>,
+		blocks: Vec<BlockData<B>>,
+	) -> Result<(), BadPeer> {
+		let Some(peer) = self.peers.get_mut(&peer_id) else {
+			debug!(target: LOG_TARGET, "Block response from unknown peer {peer_id}");
+			return Err(BadPeer(peer_id, rep::NOT_REQUESTED))
+		};
+
+		match std::mem::replace(&mut peer.state, PeerState::Available) {
+			PeerState::DownloadingHeaders => {
+				self.header_ranges.clear_peer_download(&peer_id);
+				self.on_header_response(peer_id, request, blocks)
+			},
+			PeerState::DownloadingBodies(start) => {
+				self.body_ranges.clear_peer_download(&peer_id);
+				self.on_body_response(peer_id, start, request, blocks)
+			},
+			PeerState::Available => {
+				debug!(target: LOG_TARGET, "Unexpected block response from {peer_id}");
+				Err(BadPeer(peer_id, rep::NOT_REQUESTED))
+			},
+		}
+	}
+
+	fn on_header_response(
+		&mut self,
+		peer_id: PeerId,
+		request: BlockRequest<B>,
+		blocks: Vec<BlockData<B>>,
+	) -> Result<(), BadPeer> {
+		if let Some(start) = validate_blocks::<B>(&blocks, &peer_id, Some(request))? {
+			self.header_ranges.insert(start, blocks, peer_id);
+		}
+
+		self.link_headers();
+		Ok(())
+	}
+
+	fn on_body_response(
+		&mut self,
+		peer_id: PeerId,
+		start: NumberFor<B>,
+		request: BlockRequest<B>,
+		blocks: Vec<BlockData<B>>,
+	) -> Result<(), BadPeer> {
+		if blocks.is_empty() {
+			debug!(target: LOG_TARGET, "Empty body response from {peer_id} for #{start}");
+			return Err(BadPeer(peer_id, rep::NO_BLOCK))
+		}
+
+		if Some(blocks.len() as u32) > request.max {
+			debug!(
+				target: LOG_TARGET,
+				"Too many blocks ({}) in body response from {peer_id}, requested {:?}",
+				blocks.len(),
+				request.max,
+			);
+			return Err(BadPeer(peer_id, rep::NOT_REQUESTED))
+		}
+
+		let mut number = start;
+		for block in &blocks {
+			match self.headers.get(&number) {
+				Some(header) if header.hash == block.hash => {},
+				_ => {
+					debug!(
+						target: LOG_TARGET,
+						"Body response from {peer_id} contains unexpected block {:?} at #{number}",
+						block.hash,
+					);
+					return Err(BadPeer(peer_id, rep::NOT_REQUESTED))
+				},
+			}
+
+			if block.body.is_none() {
+				debug!(
+					target: LOG_TARGET,
+					"Missing body of block #{number} in response from {peer_id}",
+				);
+				return Err(BadPeer(peer_id, rep::BAD_RESPONSE))
+			}
+
+			number += One::one();
+		}
+
+		self.downloaded_blocks += blocks.len();
+		self.body_ranges.insert(start, blocks, peer_id);
+		self.queue_ready_blocks();
+		Ok(())
+	}
+
+	/// Verify downloaded header ranges following `header_tip` and extend the header chain with
+	/// them.
+	fn link_headers(&mut self) {
+		let ready = self.header_ranges.ready_blocks(self.header_tip.1 + One::one());
+		let mut linked = true;
+
+		for block_data in ready {
+			self.header_ranges.clear_queued(&block_data.block.hash);
+			if !linked {
+				// Discarded ranges are requested again.
+				continue
+			}
+
+			let Some(header) = block_data.block.header else {
+				linked = false;
+				continue
+			};
+
+			let (tip_hash, tip_number) = self.header_tip;
+			if *header.number() != tip_number + One::one() || *header.parent_hash() != tip_hash {
+				debug!(
+					target: LOG_TARGET,
+					"Header #{} ({:?}) from {:?} doesn't extend header chain at #{tip_number} \
+					({tip_hash:?}), discarding it.",
+					header.number(),
+					block_data.block.hash,
+					block_data.origin,
+				);
+				self.link_failures += 1;
+				linked = false;
+				continue
+			}
+
+			let justifications = block_data
+				.block
+				.justifications
+				.or_else(|| legacy_justification_mapping(block_data.block.justification));
+			if let Err(e) = self.header_verifier.verify(&header, justifications.as_ref()) {
+				debug!(
+					target: LOG_TARGET,
+					"Header #{} ({:?}) from {:?} failed verification: {e}",
+					header.number(),
+					block_data.block.hash,
+					block_data.origin,
+				);
+				if let Some(peer_id) = block_data.origin {
+					self.actions
+						.push(SyncingAction::DropPeer(BadPeer(peer_id, rep::VERIFICATION_FAIL)));
+				}
+				self.link_failures += 1;
+				linked = false;
+				continue
+			}
+
+			self.header_tip = (block_data.block.hash, *header.number());
+			self.headers.insert(
+				*header.number(),
+				LinkedHeader { hash: block_data.block.hash, header, justifications },
+			);
+			self.link_failures = 0;
+		}
+
+		if self.link_failures >= MAX_LINK_FAILURES {
+			info!(
+				target: LOG_TARGET,
+				"Downloaded headers don't extend header chain at #{}, continuing with block sync.",
+				self.header_tip.1,
+			);
+			self.finish();
+		}
+	}
+
+	/// Queue contiguous downloaded blocks following `best_queued` for import.
+	fn queue_ready_blocks(&mut self) {
+		let mut blocks = Vec::new();
+
+		for block_data in self.body_ranges.ready_blocks(self.best_queued.1 + One::one()) {
+			self.body_ranges.clear_queued(&block_data.block.hash);
+
+			let number = self.best_queued.1 + One::one();
+			let Some(LinkedHeader { hash, header, justifications }) = self.headers.remove(&number)
+			else {
+				debug_assert!(false, "Bodies are only requested for linked headers; qed");
+				break
+			};
+
+			self.best_queued = (hash, number);
+			self.queue_blocks.insert(hash);
+			blocks.push(IncomingBlock {
+				hash,
+				header: Some(header),
+				body: block_data.block.body,
+				indexed_body: None,
+				justifications,
+				origin: block_data.origin,
+				allow_missing_state: true,
+				import_existing: false,
+				skip_execution: false,
+				state: None,
+			});
+		}
+
+		if !blocks.is_empty() {
+			trace!(
+				target: LOG_TARGET,
+				"Queueing {} blocks for import, best queued #{}",
+				blocks.len(),
+				self.best_queued.1,
+			);
+			self.actions.push(SyncingAction::ImportBlocks {
+				origin: BlockOrigin::NetworkInitialSync,
+				blocks,
+			});
+		}
+	}
+
+	/// A batch of blocks have been processed, with or without errors.
+	pub fn on_blocks_processed(
+		&mut self,
+		imported: usize,
+		count: usize,
+		results: Vec<(Result<BlockImportStatus<NumberFor<B>>, BlockImportError>, B::Hash)>,
+	) {
+		trace!(target: LOG_TARGET, "Header-first sync: imported {imported} of {count}");
+
+		for (result, hash) in results {
+			if !self.queue_blocks.remove(&hash) {
+				continue
+			}
+
+			let bad_peer = match result {
+				Ok(_) | Err(BlockImportError::Cancelled) => continue,
+				Err(e) => {
+					warn!(target: LOG_TARGET, "💔 Error importing block {hash:?}: {e}");
+					match e {
+						BlockImportError::IncompleteHeader(Some(peer_id)) |
+						BlockImportError::VerificationFailed(Some(peer_id), _) =>
+							Some(BadPeer(peer_id, rep::VERIFICATION_FAIL)),
+						BlockImportError::BadBlock(Some(peer_id)) =>
+							Some(BadPeer(peer_id, rep::BAD_BLOCK)),
+						_ => None,
+					}
+				},
+			};
+
+			if let Some(bad_peer) = bad_peer {
+				self.actions.push(SyncingAction::DropPeer(bad_peer));
+			}
+
+			if !self.finished {
+				info!(
+					target: LOG_TARGET,
+					"Header-first sync failed, continuing with block sync.",
+				);
+				self.finish();
+			}
+		}
+	}
+
+	/// Median best block number of the connected peers.
+	fn median_best(&self) -> Option<NumberFor<B>> {
+		let mut best_numbers: Vec<_> = self.peers.values().map(|peer| peer.best_number).collect();
+		if best_numbers.is_empty() {
+			return None
+		}
+		best_numbers.sort();
+		Some(best_numbers[best_numbers.len() / 2])
+	}
+
+	/// Block number up to which headers and bodies are downloaded.
+	fn target(&self) -> Option<NumberFor<B>> {
+		self.median_best()
+			.map(|median| median.saturating_sub(CHAIN_SYNC_HANDOVER_DISTANCE.into()))
+	}
+
+	/// Returns `true` if all blocks up to the target are imported.
+	fn is_complete(&self) -> bool {
+		self.target().map_or(false, |target| {
+			self.queue_blocks.is_empty() && self.client.info().best_number >= target
+		})
+	}
+
+	/// Finish the strategy, cancelling pending requests.
+	fn finish(&mut self) {
+		self.finished = true;
+
+		for (peer_id, peer) in self.peers.iter_mut() {
+			if !peer.state.is_available() {
+				peer.state = PeerState::Available;
+				self.actions.push(SyncingAction::CancelRequest {
+					peer_id: *peer_id,
+					key: Self::STRATEGY_KEY,
+				});
+			}
+		}
+
+		self.actions.push(SyncingAction::Finished);
+	}
+
+	/// Produce header and body range requests for available peers.
+	fn block_requests(&mut self) -> Vec<(PeerId, BlockRequest<B>)> {
+		if self.finished {
+			return Vec::new()
+		}
+		let Some(target) = self.target() else { return Vec::new() };
+
+		let header_tip = self.header_tip.1;
+		let best_queued = self.best_queued.1;
+		let headers_window_full = self.headers.len() >= MAX_HEADERS_AHEAD as usize;
+		let mut requests = Vec::new();
+
+		for (peer_id, peer) in self.peers.iter_mut() {
+			if !peer.state.is_available() || !self.disconnected_peers.is_peer_available(peer_id) {
+				continue
+			}
+
+			// Bodies of the linked headers are requested first, so that headers are only
+			// downloaded by peers that have nothing else to do.
+			if let Some(range) = self.body_ranges.needed_blocks(
+				*peer_id,
+				self.max_blocks_per_request,
+				cmp::min(peer.best_number, header_tip),
+				best_queued,
+				1,
+				MAX_BODIES_AHEAD,
+			) {
+				let Some(first) = self.headers.get(&range.start) else {
+					debug_assert!(false, "Body ranges never exceed the header chain; qed");
+					self.body_ranges.clear_peer_download(peer_id);
+					continue
+				};
+
+				trace!(
+					target: LOG_TARGET,
+					"New body request to {peer_id} for #{}..#{}",
+					range.start,
+					range.end,
+				);
+				peer.state = PeerState::DownloadingBodies(range.start);
+				requests.push((
+					*peer_id,
+					BlockRequest::<B> {
+						id: 0,
+						fields: BlockAttributes::BODY,
+						from: FromBlock::Hash(first.hash),
+						direction: Direction::Ascending,
+						max: Some((range.end - range.start).saturated_into::<u32>()),
+					},
+				));
+				continue
+			}
+
+			if headers_window_full {
+				continue
+			}
+
+			if let Some(range) = self.header_ranges.needed_blocks(
+				*peer_id,
+				self.max_blocks_per_request,
+				cmp::min(peer.best_number, target),
+				header_tip,
+				1,
+				MAX_HEADERS_AHEAD,
+			) {
+				trace!(
+					target: LOG_TARGET,
+					"New header request to {peer_id} for #{}..#{}",
+					range.start,
+					range.end,
+				);
+				peer.state = PeerState::DownloadingHeaders;
+				requests.push((
+					*peer_id,
+					BlockRequest::<B> {
+						id: 0,
+						fields: BlockAttributes::HEADER | BlockAttributes::JUSTIFICATION,
+						from: FromBlock::Number(range.start),
+						direction: Direction::Ascending,
+						max: Some((range.end - range.start).saturated_into::<u32>()),
+					},
+				));
+			}
+		}
+
+		requests
+	}
+
+	/// Get the number of peers known to header-first sync.
+	pub fn num_peers(&self) -> usize {
+		self.peers.len()
+	}
+
+	/// Get the total number of downloaded block bodies.
+	pub fn num_downloaded_blocks(&self) -> usize {
+		self.downloaded_blocks
+	}
+
+	/// Get the number of pending header and body requests.
+	pub fn num_sync_requests(&self) -> usize {
+		self.peers.values().filter(|peer| !peer.state.is_available()).count()
+	}
+
+	/// Returns the current sync status.
+	pub fn status(&self) -> SyncStatus<B> {
+		let best_seen_block = self.median_best();
+
+		SyncStatus {
+			state: match best_seen_block {
+				Some(target) if !self.finished && !self.is_complete() =>
+					SyncState::Downloading { target },
+				_ => SyncState::Idle,
+			},
+			best_seen_block,
+			num_peers: self.peers.len().saturated_into(),
+			queued_blocks: self.queue_blocks.len().saturated_into(),
+			state_sync: None,
+			warp_sync: None,
+		}
+	}
+
+	/// Get actions that should be performed by the owner on [`HeaderFirstSync`]'s behalf
+	#[must_use]
+	pub fn actions(
+		&mut self,
+		_network_service: &NetworkServiceHandle,
+	) -> impl Iterator<Item = SyncingAction<B>> {
+		if !self.finished && self.is_complete() {
+			info!(
+				target: LOG_TARGET,
+				"Header-first sync is complete at #{}, continuing with block sync.",
+				self.client.info().best_number,
+			);
+			self.finish();
+		}
+
+		let block_requests = self.block_requests().into_iter().map(|(peer_id, request)| {
+			let downloader = self.block_downloader.clone();
+
+			SyncingAction::StartRequest {
+				peer_id,
+				key: Self::STRATEGY_KEY,
+				request: async move {
+					Ok(downloader.download_blocks(peer_id, request.clone()).await?.and_then(
+						|(response, protocol_name)| {
+							let decoded_response =
+								downloader.block_response_into_blocks(&request, response);
+							let result =
+								Box::new((request, decoded_response)) as Box<dyn Any + Send>;
+							Ok((result, protocol_name))
+						},
+					))
+				}
+				.boxed(),
+				remove_obsolete: false,
+			}
+		});
+		self.actions.extend(block_requests);
+
+		std::mem::take(&mut self.actions).into_iter()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+	use crate::{mock::MockBlockDownloader, service::network::NetworkServiceProvider};
+	use sc_block_builder::BlockBuilderBuilder;
+	use sp_core::H256;
+	use substrate_test_runtime_client::{
+		runtime::{Block, Hash},
+		ClientBlockImportExt, DefaultTestClientBuilderExt, TestClient, TestClientBuilder,
+		TestClientBuilderExt,
+	};
+
+	fn build_chain(len: usize) -> Vec<Block> {
+		let mut client = TestClientBuilder::new().build();
+		let mut blocks = Vec::new();
+		for _ in 0..len {
+			let block = BlockBuilderBuilder::new(&client)
+				.on_parent_block(client.chain_info().best_hash)
+				.with_parent_block_number(client.chain_info().best_number)
+				.build()
+				.unwrap()
+				.build()
+				.unwrap()
+				.block;
+			futures::executor::block_on(client.import(BlockOrigin::Own, block.clone())).unwrap();
+			blocks.push(block);
+		}
+		blocks
+	}
+
+	fn header_data(block: &Block) -> BlockData<Block> {
+		BlockData::<Block> {
+			hash: block.header.hash(),
+			header: Some(block.header.clone()),
+			body: None,
+			indexed_body: None,
+			receipt: None,
+			message_queue: None,
+			justification: None,
+			justifications: None,
+		}
+	}
+
+	fn body_data(block: &Block) -> BlockData<Block> {
+		BlockData::<Block> {
+			hash: block.header.hash(),
+			header: None,
+			body: Some(block.extrinsics.clone()),
+			indexed_body: None,
+			receipt: None,
+			message_queue: None,
+			justification: None,
+			justifications: None,
+		}
+	}
+
+	/// Rejects headers with the given state root.
+	#[derive(Debug)]
+	struct TestHeaderVerifier(Hash);
+
+	impl HeaderVerifier<Block> for TestHeaderVerifier {
+		fn verify(
+			&self,
+			header: &<Block as BlockT>::Header,
+			_: Option<&Justifications>,
+		) -> Result<(), String> {
+			if header.state_root == self.0 {
+				return Err("Bad seal".into())
+			}
+			Ok(())
+		}
+	}
+
+	const FORGED_STATE_ROOT: Hash = H256::repeat_byte(0xff);
+
+	fn new_sync(max_blocks_per_request: u32) -> HeaderFirstSync<Block, TestClient> {
+		let client = Arc::new(TestClientBuilder::new().build());
+		HeaderFirstSync::new(
+			client,
+			max_blocks_per_request,
+			Arc::new(MockBlockDownloader::new()),
+			Arc::new(TestHeaderVerifier(FORGED_STATE_ROOT)),
+		)
+	}
+
+	fn number_range(request: &BlockRequest<Block>) -> (u64, u64) {
+		let FromBlock::Number(start) = request.from else { panic!("Expected number request") };
+		(start, start + request.max.unwrap() as u64)
+	}
+
+	#[test]
+	fn no_requests_without_peers() {
+		let mut sync = new_sync(64);
+		assert!(sync.block_requests().is_empty());
+		assert!(!sync.status().state.is_major_syncing());
+	}
+
+	#[test]
+	fn finishes_when_close_to_peers_best_block() {
+		let mut sync = new_sync(64);
+		sync.add_peer(PeerId::random(), Hash::random(), CHAIN_SYNC_HANDOVER_DISTANCE as u64);
+
+		let (_network_service, network_handle) = NetworkServiceProvider::new();
+		let actions: Vec<_> = sync.actions(&network_handle).collect();
+		assert_eq!(actions.len(), 1);
+		assert!(actions[0].is_finished());
+	}
+
+	#[test]
+	fn header_ranges_are_requested_from_peers_in_parallel() {
+		let mut sync = new_sync(64);
+		let best_number = 1000 + CHAIN_SYNC_HANDOVER_DISTANCE as u64;
+		for _ in 0..3 {
+			sync.add_peer(PeerId::random(), Hash::random(), best_number);
+		}
+
+		let mut ranges: Vec<_> = sync
+			.block_requests()
+			.into_iter()
+			.map(|(_, request)| {
+				assert_eq!(
+					request.fields,
+					BlockAttributes::HEADER | BlockAttributes::JUSTIFICATION
+				);
+				assert_eq!(request.direction, Direction::Ascending);
+				number_range(&request)
+			})
+			.collect();
+		ranges.sort();
+
+		assert_eq!(ranges, vec![(1, 65), (65, 129), (129, 193)]);
+		// All peers are busy.
+		assert!(sync.block_requests().is_empty());
+	}
+
+	#[test]
+	fn bodies_are_requested_in_parallel_and_imported_in_order() {
+		let blocks = build_chain(8);
+		let mut sync = new_sync(4);
+		let peers: Vec<_> = (0..2).map(|_| PeerId::random()).collect();
+		for peer_id in &peers {
+			sync.add_peer(*peer_id, Hash::random(), 8 + CHAIN_SYNC_HANDOVER_DISTANCE as u64);
+		}
+
+		// Both peers download headers; respond with the second range first.
+		let mut requests = sync.block_requests();
+		requests.sort_by_key(|(_, request)| number_range(request));
+		for (peer_id, request) in requests.into_iter().rev() {
+			let (start, end) = number_range(&request);
+			let response =
+				blocks[start as usize - 1..end as usize - 1].iter().map(header_data).collect();
+			sync.on_block_response(peer_id, request, response);
+		}
+		assert_eq!(sync.header_tip, (blocks[7].header.hash(), 8));
+		assert_eq!(sync.headers.len(), 8);
+
+		// Bodies are requested by hash in disjoint ranges.
+		let mut requests = sync.block_requests();
+		assert_eq!(requests.len(), 2);
+		requests.sort_by_key(|(_, request)| {
+			let FromBlock::Hash(hash) = request.from else { panic!("Expected hash request") };
+			blocks.iter().position(|block| block.header.hash() == hash).unwrap()
+		});
+		for (i, (_, request)) in requests.iter().enumerate() {
+			assert_eq!(request.fields, BlockAttributes::BODY);
+			assert_eq!(request.from, FromBlock::Hash(blocks[i * 4].header.hash()));
+			assert_eq!(request.max, Some(4));
+		}
+
+		// The second range can't be imported before the first one.
+		let (second_peer, second_request) = requests.pop().unwrap();
+		let (first_peer, first_request) = requests.pop().unwrap();
+		sync.on_block_response(
+			second_peer,
+			second_request,
+			blocks[4..8].iter().map(body_data).collect(),
+		);
+		assert!(sync.actions.is_empty());
+
+		sync.on_block_response(
+			first_peer,
+			first_request,
+			blocks[0..4].iter().map(body_data).collect(),
+		);
+		let imported: Vec<_> = match sync.actions.pop() {
+			Some(SyncingAction::ImportBlocks { blocks, .. }) => blocks
+				.into_iter()
+				.map(|block| {
+					assert!(block.header.is_some() && block.body.is_some());
+					block.hash
+				})
+				.collect(),
+			_ => panic!("Expected blocks to be imported"),
+		};
+		assert_eq!(imported, blocks.iter().map(|block| block.header.hash()).collect::<Vec<_>>());
+		assert_eq!(sync.best_queued, (blocks[7].header.hash(), 8));
+		assert!(sync.headers.is_empty());
+	}
+
+	#[test]
+	fn headers_not_extending_header_chain_are_requested_again() {
+		let blocks = build_chain(4);
+		let mut sync = new_sync(4);
+		let peer_id = PeerId::random();
+		sync.add_peer(peer_id, Hash::random(), 4 + CHAIN_SYNC_HANDOVER_DISTANCE as u64);
+
+		let (_, request) = sync.block_requests().pop().unwrap();
+		let mut response: Vec<_> = blocks.iter().map(header_data).collect();
+		// Header with a valid hash, but not building on the previous one.
+		let mut fork_header = blocks[1].header.clone();
+		fork_header.parent_hash = H256::random();
+		response[1] = BlockData::<Block> { hash: fork_header.hash(), ..header_data(&blocks[1]) };
+		response[1].header = Some(fork_header);
+		sync.on_block_response(peer_id, request, response);
+
+		assert_eq!(sync.header_tip, (blocks[0].header.hash(), 1));
+		assert_eq!(sync.link_failures, 1);
+		assert!(sync.actions.is_empty());
+
+		// The body of the linked header is requested first.
+		let (_, request) = sync.block_requests().pop().unwrap();
+		assert_eq!(request.from, FromBlock::Hash(blocks[0].header.hash()));
+		sync.on_block_response(peer_id, request, vec![body_data(&blocks[0])]);
+
+		// The discarded headers are requested again.
+		let (_, request) = sync.block_requests().pop().unwrap();
+		assert_eq!(request.fields, BlockAttributes::HEADER | BlockAttributes::JUSTIFICATION);
+		assert_eq!(number_range(&request), (2, 5));
+	}
+
+	#[test]
+	fn import_error_finishes_strategy() {
+		let blocks = build_chain(1);
+		let mut sync = new_sync(4);
+		let peer_id = PeerId::random();
+		sync.add_peer(peer_id, Hash::random(), 1 + CHAIN_SYNC_HANDOVER_DISTANCE as u64);
+
+		let (_, request) = sync.block_requests().pop().unwrap();
+		sync.on_block_response(peer_id, request, vec![header_data(&blocks[0])]);
+		let (_, request) = sync.block_requests().pop().unwrap();
+		sync.on_block_response(peer_id, request, vec![body_data(&blocks[0])]);
+		assert!(matches!(sync.actions.pop(), Some(SyncingAction::ImportBlocks { .. })));
+
+		sync.on_blocks_processed(
+			0,
+			1,
+			vec![(Err(BlockImportError::BadBlock(Some(peer_id))), blocks[0].header.hash())],
+		);
+		assert!(sync.finished);
+		assert!(sync.actions.iter().any(SyncingAction::is_finished));
+		assert!(sync.actions.iter().any(|action| matches!(
+			action,
+			SyncingAction::DropPeer(BadPeer(peer, _)) if *peer == peer_id
+		)));
+	}
+
+	#[test]
+	fn forged_headers_are_rejected_before_requesting_bodies() {
+		let blocks = build_chain(4);
+		let mut forged = Vec::new();
+		let mut parent_hash = blocks[0].header.parent_hash;
+		for block in &blocks {
+			let mut header = block.header.clone();
+			header.parent_hash = parent_hash;
+			header.state_root = FORGED_STATE_ROOT;
+			parent_hash = header.hash();
+			forged.push(Block { header, extrinsics: block.extrinsics.clone() });
+		}
+
+		let mut sync = new_sync(4);
+		let peer_id = PeerId::random();
+		sync.add_peer(peer_id, Hash::random(), 4 + CHAIN_SYNC_HANDOVER_DISTANCE as u64);
+
+		let (_, request) = sync.block_requests().pop().unwrap();
+		sync.on_block_response(peer_id, request, forged.iter().map(header_data).collect());
+
+		// The peer is dropped and nothing is linked.
+		assert!(matches!(
+			sync.actions.as_slice(),
+			[SyncingAction::DropPeer(BadPeer(id, change))]
+				if *id == peer_id && *change == rep::VERIFICATION_FAIL
+		));
+		assert!(sync.headers.is_empty());
+		assert_eq!(sync.header_tip.1, 0);
+
+		// Only headers are requested again, no bodies.
+		let requests = sync.block_requests();
+		assert_eq!(requests.len(), 1);
+		assert!(requests
+			.iter()
+			.all(|(_, request)| !request.fields.contains(BlockAttributes::BODY)));
+	}
+}
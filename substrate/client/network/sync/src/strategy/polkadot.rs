@@ -25,8 +25,9 @@ use crate::{
 	service::network::NetworkServiceHandle,
 	strategy::{
 		chain_sync::{ChainSync, ChainSyncMode},
+		header_first::{HeaderFirstSync, HeaderVerifier},
 		state::StateStrategy,
-		warp::{WarpSync, WarpSyncConfig},
+		warp::{WarpSync, WarpSyncConfig, MIN_PEERS_TO_START_WARP_SYNC},
 		StrategyKey, SyncingAction, SyncingStrategy,
 	},
 	types::SyncStatus,
@@ -34,15 +35,23 @@ use crate::{
 };
 use log::{debug, error, info, warn};
 use prometheus_endpoint::Registry;
-use sc_client_api::{BlockBackend, ProofProvider};
+use sc_client_api::{AuxStore, BlockBackend, ProofProvider};
 use sc_consensus::{BlockImportError, BlockImportStatus};
 use sc_network::ProtocolName;
 use sc_network_common::sync::{message::BlockAnnounce, SyncMode};
 use sc_network_types::PeerId;
 use sp_blockchain::{Error as ClientError, HeaderBackend, HeaderMetadata};
-use sp_runtime::traits::{Block as BlockT, Header, NumberFor};
+use sp_runtime::traits::{Block as BlockT, Header, NumberFor, Saturating};
 use std::{any::Any, collections::HashMap, sync::Arc};
 
+/// Maximum number of blocks the target of a state download resumed after a restart may be behind
+/// the best block of the peers.
+///
+/// Peers keep the state of the last 256 blocks by default. A resumed download whose target is
+/// further behind is abandoned in favour of a fresh warp sync, leaving the peers time to serve
+/// the rest of the state before pruning it.
+const MAX_STATE_SYNC_RESUME_DISTANCE: u32 = 128;
+
 /// Corresponding `ChainSync` mode.
 fn chain_sync_mode(sync_mode: SyncMode) -> ChainSyncMode {
 	match sync_mode {
@@ -50,6 +59,7 @@ fn chain_sync_mode(sync_mode: SyncMode) -> ChainSyncMode {
 		SyncMode::LightState { skip_proofs, storage_chain_mode } =>
 			ChainSyncMode::LightState { skip_proofs, storage_chain_mode },
 		SyncMode::Warp => ChainSyncMode::Full,
+		SyncMode::HeaderFirst => ChainSyncMode::Full,
 	}
 }
 
@@ -73,6 +83,9 @@ where
 	pub state_request_protocol_name: ProtocolName,
 	/// Block downloader
 	pub block_downloader: Arc<dyn BlockDownloader<Block>>,
+	/// Verifier of the headers downloaded by header-first sync, required in
+	/// [`SyncMode::HeaderFirst`].
+	pub header_verifier: Option<Arc<dyn HeaderVerifier<Block>>>,
 }
 
 /// Proxy to specific syncing strategies used in Polkadot.
@@ -85,11 +98,16 @@ pub struct PolkadotSyncingStrategy<B: BlockT, Client> {
 	warp: Option<WarpSync<B, Client>>,
 	/// State strategy.
 	state: Option<StateStrategy<B>>,
+	/// Header-first strategy.
+	header_first: Option<HeaderFirstSync<B, Client>>,
 	/// `ChainSync` strategy.`
 	chain_sync: Option<ChainSync<B, Client>>,
 	/// Connected peers and their best blocks used to seed a new strategy when switching to it in
 	/// `PolkadotSyncingStrategy::proceed_to_next`.
 	peer_best_blocks: HashMap<PeerId, (B::Hash, NumberFor<B>)>,
+	/// Warp sync configuration to restart warp sync with if the target of a resumed state
+	/// download turns out to be too old.
+	resumed_warp_sync: Option<(WarpSyncConfig<B>, Option<ProtocolName>)>,
 }
 
 impl<B: BlockT, Client> SyncingStrategy<B> for PolkadotSyncingStrategy<B, Client>
@@ -99,6 +117,7 @@ where
 		+ BlockBackend<B>
 		+ HeaderMetadata<B, Error = sp_blockchain::Error>
 		+ ProofProvider<B>
+		+ AuxStore
 		+ Send
 		+ Sync
 		+ 'static,
@@ -108,12 +127,16 @@ where
 
 		self.warp.as_mut().map(|s| s.add_peer(peer_id, best_hash, best_number));
 		self.state.as_mut().map(|s| s.add_peer(peer_id, best_hash, best_number));
+		self.header_first.as_mut().map(|s| s.add_peer(peer_id, best_hash, best_number));
 		self.chain_sync.as_mut().map(|s| s.add_peer(peer_id, best_hash, best_number));
+
+		self.restart_stale_state_sync();
 	}
 
 	fn remove_peer(&mut self, peer_id: &PeerId) {
 		self.warp.as_mut().map(|s| s.remove_peer(peer_id));
 		self.state.as_mut().map(|s| s.remove_peer(peer_id));
+		self.header_first.as_mut().map(|s| s.remove_peer(peer_id));
 		self.chain_sync.as_mut().map(|s| s.remove_peer(peer_id));
 
 		self.peer_best_blocks.remove(peer_id);
@@ -129,6 +152,8 @@ where
 			warp.on_validated_block_announce(is_best, peer_id, announce)
 		} else if let Some(ref mut state) = self.state {
 			state.on_validated_block_announce(is_best, peer_id, announce)
+		} else if let Some(ref mut header_first) = self.header_first {
+			header_first.on_validated_block_announce(is_best, peer_id, announce)
 		} else if let Some(ref mut chain_sync) = self.chain_sync {
 			chain_sync.on_validated_block_announce(is_best, peer_id, announce)
 		} else {
@@ -147,6 +172,7 @@ where
 					 (already disconnected?)",
 				);
 			}
+			self.restart_stale_state_sync();
 		}
 
 		new_best
@@ -197,6 +223,7 @@ where
 					};
 
 					state.on_state_response(peer_id, *response);
+					self.restart_stale_state_sync();
 				} else if let Some(chain_sync) = &mut self.chain_sync {
 					chain_sync.on_generic_response(peer_id, key, protocol_name, response);
 				} else {
@@ -218,6 +245,17 @@ where
 					);
 					debug_assert!(false);
 				},
+			HeaderFirstSync::<B, Client>::STRATEGY_KEY =>
+				if let Some(header_first) = &mut self.header_first {
+					header_first.on_generic_response(peer_id, protocol_name, response);
+				} else {
+					error!(
+						target: LOG_TARGET,
+						"`on_generic_response()` called with unexpected key {key:?} \
+						 or header-first strategy is not active",
+					);
+					debug_assert!(false);
+				},
 			ChainSync::<B, Client>::STRATEGY_KEY =>
 				if let Some(chain_sync) = &mut self.chain_sync {
 					chain_sync.on_generic_response(peer_id, key, protocol_name, response);
@@ -245,9 +283,12 @@ where
 		count: usize,
 		results: Vec<(Result<BlockImportStatus<NumberFor<B>>, BlockImportError>, B::Hash)>,
 	) {
-		// Only `StateStrategy` and `ChainSync` are interested in block processing notifications.
+		// Only `StateStrategy`, `HeaderFirstSync` and `ChainSync` are interested in block
+		// processing notifications.
 		if let Some(ref mut state) = self.state {
 			state.on_blocks_processed(imported, count, results);
+		} else if let Some(ref mut header_first) = self.header_first {
+			header_first.on_blocks_processed(imported, count, results);
 		} else if let Some(ref mut chain_sync) = self.chain_sync {
 			chain_sync.on_blocks_processed(imported, count, results);
 		}
@@ -270,9 +311,10 @@ where
 	fn is_major_syncing(&self) -> bool {
 		self.warp.is_some() ||
 			self.state.is_some() ||
-			match self.chain_sync {
-				Some(ref s) => s.status().state.is_major_syncing(),
-				None => unreachable!("At least one syncing strategy is active; qed"),
+			match (&self.header_first, &self.chain_sync) {
+				(Some(s), _) => s.status().state.is_major_syncing(),
+				(None, Some(s)) => s.status().state.is_major_syncing(),
+				(None, None) => unreachable!("At least one syncing strategy is active; qed"),
 			}
 	}
 
@@ -287,6 +329,8 @@ where
 			warp.status()
 		} else if let Some(ref state) = self.state {
 			state.status()
+		} else if let Some(ref header_first) = self.header_first {
+			header_first.status()
 		} else if let Some(ref chain_sync) = self.chain_sync {
 			chain_sync.status()
 		} else {
@@ -295,12 +339,20 @@ where
 	}
 
 	fn num_downloaded_blocks(&self) -> usize {
+		if let Some(ref header_first) = self.header_first {
+			return header_first.num_downloaded_blocks()
+		}
+
 		self.chain_sync
 			.as_ref()
 			.map_or(0, |chain_sync| chain_sync.num_downloaded_blocks())
 	}
 
 	fn num_sync_requests(&self) -> usize {
+		if let Some(ref header_first) = self.header_first {
+			return header_first.num_sync_requests()
+		}
+
 		self.chain_sync.as_ref().map_or(0, |chain_sync| chain_sync.num_sync_requests())
 	}
 
@@ -314,6 +366,8 @@ where
 			warp.actions(network_service).map(Into::into).collect()
 		} else if let Some(ref mut state) = self.state {
 			state.actions(network_service).map(Into::into).collect()
+		} else if let Some(ref mut header_first) = self.header_first {
+			header_first.actions(network_service).collect()
 		} else if let Some(ref mut chain_sync) = self.chain_sync {
 			chain_sync.actions(network_service)?
 		} else {
@@ -335,6 +389,7 @@ where
 		+ BlockBackend<B>
 		+ HeaderMetadata<B, Error = sp_blockchain::Error>
 		+ ProofProvider<B>
+		+ AuxStore
 		+ Send
 		+ Sync
 		+ 'static,
@@ -355,6 +410,27 @@ where
 		}
 
 		if let SyncMode::Warp = config.mode {
+			if client.info().finalized_state.is_none() {
+				if let Some(state) = StateStrategy::resume(
+					client.clone(),
+					std::iter::empty(),
+					config.state_request_protocol_name.clone(),
+				) {
+					let warp_sync_config = warp_sync_config
+						.expect("Warp sync configuration must be supplied in warp sync mode.");
+					return Ok(Self {
+						config,
+						client,
+						warp: None,
+						state: Some(state),
+						header_first: None,
+						chain_sync: None,
+						peer_best_blocks: Default::default(),
+						resumed_warp_sync: Some((warp_sync_config, warp_sync_protocol_name)),
+					})
+				}
+			}
+
 			let warp_sync_config = warp_sync_config
 				.expect("Warp sync configuration must be supplied in warp sync mode.");
 			let warp_sync = WarpSync::new(
@@ -369,8 +445,31 @@ where
 				client,
 				warp: Some(warp_sync),
 				state: None,
+				header_first: None,
 				chain_sync: None,
 				peer_best_blocks: Default::default(),
+				resumed_warp_sync: None,
+			})
+		} else if let SyncMode::HeaderFirst = config.mode {
+			let header_verifier = config
+				.header_verifier
+				.clone()
+				.expect("Header verifier must be supplied in header-first sync mode.");
+			let header_first = HeaderFirstSync::new(
+				client.clone(),
+				config.max_blocks_per_request,
+				config.block_downloader.clone(),
+				header_verifier,
+			);
+			Ok(Self {
+				config,
+				client,
+				warp: None,
+				state: None,
+				header_first: Some(header_first),
+				chain_sync: None,
+				peer_best_blocks: Default::default(),
+				resumed_warp_sync: None,
 			})
 		} else {
 			let chain_sync = ChainSync::new(
@@ -388,15 +487,64 @@ where
 				client,
 				warp: None,
 				state: None,
+				header_first: None,
 				chain_sync: Some(chain_sync),
 				peer_best_blocks: Default::default(),
+				resumed_warp_sync: None,
 			})
 		}
 	}
 
+	/// Abandon a resumed state download in favour of a fresh warp sync if its target is too far
+	/// behind the best block of the peers for them to still keep its state.
+	fn restart_stale_state_sync(&mut self) {
+		let Some(state) = &self.state else { return };
+		let min_peers =
+			self.config.min_peers_to_start_warp_sync.unwrap_or(MIN_PEERS_TO_START_WARP_SYNC);
+		if self.resumed_warp_sync.is_none() ||
+			self.peer_best_blocks.len() < min_peers ||
+			!state.can_abandon()
+		{
+			return
+		}
+
+		let mut best_numbers: Vec<_> =
+			self.peer_best_blocks.values().map(|(_, best_number)| *best_number).collect();
+		best_numbers.sort_unstable();
+		let median = best_numbers[best_numbers.len() / 2];
+		let target = state.target_number();
+		if median <= target.saturating_add(MAX_STATE_SYNC_RESUME_DISTANCE.into()) {
+			return
+		}
+
+		let Some((warp_sync_config, protocol_name)) = self.resumed_warp_sync.take() else { return };
+		info!(
+			target: LOG_TARGET,
+			"Resumed state sync of #{target} is {} blocks behind the peers, restarting warp sync.",
+			median.saturating_sub(target),
+		);
+
+		let mut warp_sync = WarpSync::new(
+			self.client.clone(),
+			warp_sync_config,
+			protocol_name,
+			self.config.block_downloader.clone(),
+			self.config.min_peers_to_start_warp_sync,
+		);
+		for (peer_id, (best_hash, best_number)) in &self.peer_best_blocks {
+			warp_sync.add_peer(*peer_id, *best_hash, *best_number);
+		}
+
+		if let Some(state) = self.state.take() {
+			state.abandon();
+		}
+		self.warp = Some(warp_sync);
+	}
+
 	/// Proceed with the next strategy if the active one finished.
 	pub fn proceed_to_next(&mut self) -> Result<(), ClientError> {
-		// The strategies are switched as `WarpSync` -> `StateStrategy` -> `ChainSync`.
+		// The strategies are switched as `WarpSync` -> `StateStrategy` -> `ChainSync` or
+		// `HeaderFirstSync` -> `ChainSync`.
 		if let Some(ref mut warp) = self.warp {
 			match warp.take_result() {
 				Some(res) => {
@@ -404,7 +552,7 @@ where
 						target: LOG_TARGET,
 						"Warp sync is complete, continuing with state sync."
 					);
-					let state_sync = StateStrategy::new(
+					let state_sync = StateStrategy::new_persistent(
 						self.client.clone(),
 						res.target_header,
 						res.target_body,
@@ -475,10 +623,34 @@ where
 			};
 
 			self.state = None;
+			self.resumed_warp_sync = None;
+			self.chain_sync = Some(chain_sync);
+			Ok(())
+		} else if self.header_first.is_some() {
+			let chain_sync = match ChainSync::new(
+				chain_sync_mode(self.config.mode),
+				self.client.clone(),
+				self.config.max_parallel_downloads,
+				self.config.max_blocks_per_request,
+				self.config.state_request_protocol_name.clone(),
+				self.config.block_downloader.clone(),
+				self.config.metrics_registry.as_ref(),
+				self.peer_best_blocks.iter().map(|(peer_id, (best_hash, best_number))| {
+					(*peer_id, *best_hash, *best_number)
+				}),
+			) {
+				Ok(chain_sync) => chain_sync,
+				Err(e) => {
+					error!(target: LOG_TARGET, "Failed to start `ChainSync`.");
+					return Err(e);
+				},
+			};
+
+			self.header_first = None;
 			self.chain_sync = Some(chain_sync);
 			Ok(())
 		} else {
-			unreachable!("Only warp & state strategies can finish; qed")
+			unreachable!("Only warp, state & header-first strategies can finish; qed")
 		}
 	}
 }
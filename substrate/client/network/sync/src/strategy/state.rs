@@ -32,7 +32,7 @@ use crate::{
 use futures::{channel::oneshot, FutureExt};
 use log::{debug, error, trace};
 use prost::Message;
-use sc_client_api::ProofProvider;
+use sc_client_api::{AuxStore, ProofProvider};
 use sc_consensus::{BlockImportError, BlockImportStatus, IncomingBlock};
 use sc_network::{IfDisconnected, ProtocolName};
 use sc_network_common::sync::message::BlockAnnounce;
@@ -97,25 +97,50 @@ impl<B: BlockT> StateStrategy<B> {
 	where
 		Client: ProofProvider<B> + Send + Sync + 'static,
 	{
-		let peers = initial_peers
-			.map(|(peer_id, best_number)| {
-				(peer_id, Peer { best_number, state: PeerState::Available })
-			})
-			.collect();
-		Self {
-			state_sync: Box::new(StateSync::new(
-				client,
-				target_header,
-				target_body,
-				target_justifications,
-				skip_proof,
-			)),
-			peers,
-			disconnected_peers: DisconnectedPeers::new(),
-			actions: Vec::new(),
-			protocol_name,
-			succeeded: false,
-		}
+		let state_sync =
+			StateSync::new(client, target_header, target_body, target_justifications, skip_proof);
+
+		Self::new_with_provider(Box::new(state_sync), initial_peers, protocol_name)
+	}
+
+	/// Create a new instance persisting the download progress, so that it can be continued with
+	/// [`StateStrategy::resume`] after a restart.
+	///
+	/// Only use it if the owner resumes the persisted download on startup, otherwise the
+	/// progress is left behind in the auxiliary storage until the next persistent download.
+	pub fn new_persistent<Client>(
+		client: Arc<Client>,
+		target_header: B::Header,
+		target_body: Option<Vec<B::Extrinsic>>,
+		target_justifications: Option<Justifications>,
+		skip_proof: bool,
+		initial_peers: impl Iterator<Item = (PeerId, NumberFor<B>)>,
+		protocol_name: ProtocolName,
+	) -> Self
+	where
+		Client: ProofProvider<B> + AuxStore + Send + Sync + 'static,
+	{
+		let state_sync =
+			StateSync::new(client, target_header, target_body, target_justifications, skip_proof)
+				.persistent();
+
+		Self::new_with_provider(Box::new(state_sync), initial_peers, protocol_name)
+	}
+
+	/// Continue the state download persisted by a previous instance.
+	///
+	/// Returns `None` if there is no valid persisted state download, see [`StateSync::resume`].
+	pub fn resume<Client>(
+		client: Arc<Client>,
+		initial_peers: impl Iterator<Item = (PeerId, NumberFor<B>)>,
+		protocol_name: ProtocolName,
+	) -> Option<Self>
+	where
+		Client: ProofProvider<B> + AuxStore + Send + Sync + 'static,
+	{
+		let state_sync = StateSync::resume(client)?;
+
+		Some(Self::new_with_provider(Box::new(state_sync), initial_peers, protocol_name))
 	}
 
 	/// Create a new instance with a custom state sync provider.
@@ -278,6 +303,7 @@ impl<B: BlockT> StateStrategy<B> {
 				);
 			});
 			self.succeeded |= results.into_iter().any(|result| result.is_ok());
+			self.state_sync.clear_progress();
 			self.actions.push(SyncingAction::Finished);
 		}
 	}
@@ -388,6 +414,22 @@ impl<B: BlockT> StateStrategy<B> {
 	pub fn is_succeeded(&self) -> bool {
 		self.succeeded
 	}
+
+	/// Returns the number of the block whose state is downloaded.
+	pub fn target_number(&self) -> NumberFor<B> {
+		self.state_sync.target_number()
+	}
+
+	/// Whether the download can be abandoned, i.e. no state request is in flight and the state
+	/// is not being imported.
+	pub fn can_abandon(&self) -> bool {
+		!self.state_sync.is_complete() && self.peers.values().all(|peer| peer.state.is_available())
+	}
+
+	/// Abandon the download, discarding its persisted progress.
+	pub fn abandon(mut self) {
+		self.state_sync.clear_progress();
+	}
 }
 
 #[cfg(test)]
@@ -396,17 +438,22 @@ mod test {
 	use crate::{
 		schema::v1::{StateRequest, StateResponse},
 		service::network::NetworkServiceProvider,
-		strategy::state_sync::{ImportResult, StateSyncProgress, StateSyncProvider},
+		strategy::state_sync::{
+			state_range_key, ImportResult, PersistedRange, RangeContent, StateSyncProgress,
+			StateSyncProvider,
+		},
 	};
-	use codec::Decode;
+	use codec::{Decode, Encode};
 	use sc_block_builder::BlockBuilderBuilder;
 	use sc_client_api::KeyValueStates;
 	use sc_consensus::{ImportedAux, ImportedState};
+	use sp_blockchain::HeaderBackend;
 	use sp_core::H256;
 	use sp_runtime::traits::Zero;
 	use substrate_test_runtime_client::{
 		runtime::{Block, Hash},
-		BlockBuilderExt, DefaultTestClientBuilderExt, TestClientBuilder, TestClientBuilderExt,
+		BlockBuilderExt, DefaultTestClientBuilderExt, TestClient, TestClientBuilder,
+		TestClientBuilderExt,
 	};
 
 	mockall::mock! {
@@ -881,4 +928,131 @@ mod test {
 		// No more actions generated.
 		assert_eq!(state_strategy.actions(&network_handle).count(), 0);
 	}
+
+	fn sorted_state(state: KeyValueStates) -> Vec<(Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>)> {
+		let mut levels: Vec<_> = state
+			.0
+			.into_iter()
+			.map(|mut level| {
+				level.key_values.sort();
+				(level.state_root, level.key_values)
+			})
+			.collect();
+		levels.sort();
+		levels
+	}
+
+	/// Responds to state requests for the genesis state of `source`.
+	fn respond(source: &TestClient, request: StateRequest) -> StateResponse {
+		let (proof, _) = source
+			.read_proof_collection(source.info().genesis_hash, &request.start, 1024)
+			.unwrap();
+		StateResponse { entries: Vec::new(), proof: proof.encode() }
+	}
+
+	#[test]
+	fn persisted_state_download_is_resumed() {
+		let source = TestClientBuilder::new().build();
+		let target_header = source.header(source.info().genesis_hash).unwrap().unwrap();
+
+		let download = |state_sync: &mut dyn StateSyncProvider<Block>| loop {
+			if let ImportResult::Import(_, _, state, _, _) =
+				state_sync.import(respond(&source, state_sync.next_request()))
+			{
+				break sorted_state(state.state)
+			}
+		};
+
+		let expected = download(&mut StateSync::new(
+			Arc::new(TestClientBuilder::new().build()),
+			target_header.clone(),
+			None,
+			None,
+			false,
+		));
+
+		let client = Arc::new(TestClientBuilder::new().build());
+		let mut state_sync =
+			StateSync::new(client.clone(), target_header, None, None, false).persistent();
+		for _ in 0..2 {
+			let response = respond(&source, state_sync.next_request());
+			assert!(matches!(state_sync.import(response), ImportResult::Continue));
+		}
+		let next_request = state_sync.next_request();
+		let progress = state_sync.progress();
+		drop(state_sync);
+
+		// Node is restarted.
+		let mut state_sync = StateSync::<Block, _>::resume(client.clone()).unwrap();
+		assert_eq!(state_sync.next_request(), next_request);
+		assert_eq!(state_sync.progress(), progress);
+		assert_eq!(download(&mut state_sync), expected);
+
+		// Progress is discarded once the state is imported.
+		state_sync.clear_progress();
+		drop(state_sync);
+		assert!(StateSync::<Block, _>::resume(client).is_none());
+	}
+
+	#[test]
+	fn persisted_ranges_are_verified_on_resume() {
+		let source = TestClientBuilder::new().build();
+		let target_header = source.header(source.info().genesis_hash).unwrap().unwrap();
+
+		let client = Arc::new(TestClientBuilder::new().build());
+		let mut state_sync =
+			StateSync::new(client.clone(), target_header, None, None, false).persistent();
+		let response = respond(&source, state_sync.next_request());
+		assert!(matches!(state_sync.import(response), ImportResult::Continue));
+		drop(state_sync);
+
+		// The first range is replaced by a range of another state.
+		let other = TestClientBuilder::new().add_extra_storage(b":other".to_vec(), vec![1]).build();
+		let range = PersistedRange {
+			start: Vec::new(),
+			content: RangeContent::Proof(respond(&other, StateRequest::default()).proof),
+		};
+		client
+			.insert_aux(&[(&state_range_key(0)[..], &range.encode()[..])], &[])
+			.unwrap();
+
+		assert!(StateSync::<Block, _>::resume(client.clone()).is_none());
+		// The invalid progress is gone.
+		assert!(StateSync::<Block, _>::resume(client).is_none());
+	}
+
+	#[test]
+	fn state_strategy_persists_only_if_requested() {
+		let client = Arc::new(TestClientBuilder::new().build());
+		let target_header = client.header(client.info().genesis_hash).unwrap().unwrap();
+		let resume = || {
+			StateStrategy::<Block>::resume(
+				client.clone(),
+				std::iter::empty(),
+				ProtocolName::Static(""),
+			)
+		};
+
+		drop(StateStrategy::<Block>::new(
+			client.clone(),
+			target_header.clone(),
+			None,
+			None,
+			false,
+			std::iter::empty(),
+			ProtocolName::Static(""),
+		));
+		assert!(resume().is_none());
+
+		drop(StateStrategy::<Block>::new_persistent(
+			client.clone(),
+			target_header,
+			None,
+			None,
+			false,
+			std::iter::empty(),
+			ProtocolName::Static(""),
+		));
+		assert!(resume().is_some());
+	}
 }
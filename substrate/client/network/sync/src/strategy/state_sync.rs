@@ -23,8 +23,8 @@ use crate::{
 	LOG_TARGET,
 };
 use codec::{Decode, Encode};
-use log::debug;
-use sc_client_api::{CompactProof, KeyValueStates, ProofProvider};
+use log::{debug, info, warn};
+use sc_client_api::{AuxStore, CompactProof, KeyValueStates, ProofProvider};
 use sc_consensus::ImportedState;
 use smallvec::SmallVec;
 use sp_core::storage::well_known_keys;
@@ -32,7 +32,18 @@ use sp_runtime::{
 	traits::{Block as BlockT, Header, NumberFor},
 	Justifications,
 };
-use std::{collections::HashMap, fmt, sync::Arc};
+use std::{
+	collections::{BTreeMap, HashMap},
+	fmt,
+	sync::{mpsc, Arc},
+	thread,
+};
+
+/// Auxiliary storage key of the persisted state sync progress.
+const STATE_SYNC_PROGRESS_KEY: &[u8] = b"state_sync_progress";
+
+/// Auxiliary storage key prefix of the persisted state ranges.
+const STATE_SYNC_RANGE_PREFIX: &[u8] = b"state_sync_range";
 
 /// Generic state sync provider. Used for mocking in tests.
 pub trait StateSyncProvider<B: BlockT>: Send + Sync {
@@ -48,6 +59,9 @@ pub trait StateSyncProvider<B: BlockT>: Send + Sync {
 	fn target_hash(&self) -> B::Hash;
 	/// Returns state sync estimated progress.
 	fn progress(&self) -> StateSyncProgress;
+	/// Discard persisted progress once the downloaded state has been processed by the import
+	/// queue.
+	fn clear_progress(&mut self) {}
 }
 
 // Reported state sync phase.
@@ -136,6 +150,159 @@ impl<B: BlockT> StateSyncMetadata<B> {
 	}
 }
 
+/// Progress of a state download persisted in the auxiliary storage.
+#[derive(Encode, Decode)]
+struct PersistedProgress<B: BlockT> {
+	target_header: B::Header,
+	target_body: Option<Vec<B::Extrinsic>>,
+	target_justifications: Option<Justifications>,
+	skip_proof: bool,
+	last_key: Vec<Vec<u8>>,
+	imported_bytes: u64,
+	/// Number of persisted state ranges.
+	ranges: u32,
+}
+
+/// Key-value pairs grouped by (child) trie root.
+type KeyValuesByRoot = Vec<(Vec<u8>, Vec<(Vec<u8>, Vec<u8>)>)>;
+
+/// A key range of the state downloaded by a single response, persisted in the auxiliary storage.
+#[derive(Encode, Decode)]
+pub(super) struct PersistedRange {
+	/// The key the range starts after, empty for the first range.
+	pub(super) start: Vec<Vec<u8>>,
+	pub(super) content: RangeContent,
+}
+
+/// The content of a [`PersistedRange`].
+#[derive(Encode, Decode)]
+pub(super) enum RangeContent {
+	/// The encoded compact proof of the range, holding the trie nodes it completed. It is
+	/// verified again against the target state root when the download is resumed.
+	Proof(Vec<u8>),
+	/// The key-value pairs of the range, if proofs are skipped.
+	KeyValues(KeyValuesByRoot),
+}
+
+pub(super) fn state_range_key(index: u32) -> Vec<u8> {
+	(STATE_SYNC_RANGE_PREFIX, index).encode()
+}
+
+/// Number of ranges of the progress persisted in `store`.
+fn persisted_ranges<B: BlockT>(store: &dyn ProgressStore) -> u32 {
+	store
+		.read(STATE_SYNC_PROGRESS_KEY)
+		.and_then(|progress| PersistedProgress::<B>::decode(&mut &progress[..]).ok())
+		.map_or(0, |progress| progress.ranges)
+}
+
+/// Storage the state sync progress is persisted to.
+trait ProgressStore: Send + Sync {
+	fn read(&self, key: &[u8]) -> Option<Vec<u8>>;
+	fn write(&self, insert: &[(&[u8], &[u8])], delete: &[&[u8]]);
+}
+
+impl<T: AuxStore + Send + Sync> ProgressStore for T {
+	fn read(&self, key: &[u8]) -> Option<Vec<u8>> {
+		self.get_aux(key).unwrap_or_else(|e| {
+			warn!(target: LOG_TARGET, "Failed to read state sync progress: {e}");
+			None
+		})
+	}
+
+	fn write(&self, insert: &[(&[u8], &[u8])], delete: &[&[u8]]) {
+		if let Err(e) = self.insert_aux(insert, delete) {
+			warn!(target: LOG_TARGET, "Failed to persist state sync progress: {e}");
+		}
+	}
+}
+
+/// A write of the state sync progress, see [`ProgressWriter`].
+enum WriteCommand {
+	/// Persist the range at `index`, if any, and the progress following it.
+	Range { index: u32, range: Option<Vec<u8>>, progress: Vec<u8> },
+	/// Remove the persisted progress and all its ranges.
+	Clear,
+}
+
+/// Writes the state sync progress to the auxiliary storage from a dedicated thread, so that the
+/// syncing event loop doesn't wait for the database.
+///
+/// The writes queued while a batch is written are merged into the next batch. Dropping the
+/// writer waits for the queued writes to complete.
+struct ProgressWriter {
+	commands: Option<mpsc::Sender<WriteCommand>>,
+	worker: Option<thread::JoinHandle<()>>,
+}
+
+impl ProgressWriter {
+	/// Start writing to `store`, which holds `ranges` persisted ranges.
+	fn spawn(store: Arc<dyn ProgressStore>, ranges: u32) -> Option<Self> {
+		let (commands, receiver) = mpsc::channel();
+		let worker = thread::Builder::new()
+			.name("state-sync-writer".into())
+			.spawn(move || Self::run(&*store, ranges, receiver))
+			.map_err(|e| {
+				warn!(target: LOG_TARGET, "Failed to spawn state sync progress writer: {e}");
+			})
+			.ok()?;
+
+		Some(Self { commands: Some(commands), worker: Some(worker) })
+	}
+
+	fn send(&self, command: WriteCommand) {
+		if let Some(commands) = &self.commands {
+			let _ = commands.send(command);
+		}
+	}
+
+	fn run(store: &dyn ProgressStore, mut ranges: u32, commands: mpsc::Receiver<WriteCommand>) {
+		while let Ok(command) = commands.recv() {
+			// The latest write of every key, `None` for deletions.
+			let mut batch = BTreeMap::<Vec<u8>, Option<Vec<u8>>>::new();
+			for command in std::iter::once(command).chain(commands.try_iter()) {
+				match command {
+					WriteCommand::Range { index, range, progress } => {
+						if let Some(range) = range {
+							batch.insert(state_range_key(index), Some(range));
+							ranges = ranges.max(index + 1);
+						}
+						batch.insert(STATE_SYNC_PROGRESS_KEY.to_vec(), Some(progress));
+					},
+					WriteCommand::Clear => {
+						for index in 0..ranges {
+							batch.insert(state_range_key(index), None);
+						}
+						batch.insert(STATE_SYNC_PROGRESS_KEY.to_vec(), None);
+						ranges = 0;
+					},
+				}
+			}
+
+			let insert: Vec<_> = batch
+				.iter()
+				.filter_map(|(key, value)| Some((&key[..], &value.as_ref()?[..])))
+				.collect();
+			let delete: Vec<_> = batch
+				.iter()
+				.filter_map(|(key, value)| value.is_none().then_some(&key[..]))
+				.collect();
+			store.write(&insert, &delete);
+		}
+	}
+}
+
+impl Drop for ProgressWriter {
+	fn drop(&mut self) {
+		self.commands = None;
+		if let Some(worker) = self.worker.take() {
+			if worker.join().is_err() {
+				warn!(target: LOG_TARGET, "State sync progress writer panicked");
+			}
+		}
+	}
+}
+
 /// State sync state machine.
 ///
 /// Accumulates partial state data until it is ready to be imported.
@@ -143,6 +310,10 @@ pub struct StateSync<B: BlockT, Client> {
 	metadata: StateSyncMetadata<B>,
 	state: HashMap<Vec<u8>, (Vec<(Vec<u8>, Vec<u8>)>, Vec<Vec<u8>>)>,
 	client: Arc<Client>,
+	/// Writer of the progress, if persisting it is enabled.
+	writer: Option<ProgressWriter>,
+	/// Number of state ranges persisted so far.
+	persisted_ranges: u32,
 }
 
 impl<B, Client> StateSync<B, Client>
@@ -170,9 +341,143 @@ where
 				skip_proof,
 			},
 			state: HashMap::default(),
+			writer: None,
+			persisted_ranges: 0,
 		}
 	}
 
+	/// Persist the progress of the state download after every response, so that it can be
+	/// continued with [`StateSync::resume`] after a restart.
+	///
+	/// Progress persisted by a previous state download is discarded.
+	pub fn persistent(mut self) -> Self
+	where
+		Client: AuxStore,
+	{
+		let store: Arc<dyn ProgressStore> = self.client.clone();
+		let ranges = persisted_ranges::<B>(&*store);
+		self.writer = ProgressWriter::spawn(store, ranges);
+		if let Some(writer) = &self.writer {
+			writer.send(WriteCommand::Clear);
+		}
+		self.persist_progress(None);
+		self
+	}
+
+	/// Resume the state download persisted by [`StateSync::persistent`].
+	///
+	/// The persisted proofs are verified again against the state root of the target block.
+	/// Returns `None` if there is no persisted progress or if it is invalid, in which case it is
+	/// discarded.
+	pub fn resume(client: Arc<Client>) -> Option<Self>
+	where
+		Client: AuxStore,
+	{
+		let store: Arc<dyn ProgressStore> = client.clone();
+		let progress = store.read(STATE_SYNC_PROGRESS_KEY)?;
+		let Ok(progress) = PersistedProgress::<B>::decode(&mut &progress[..]) else {
+			warn!(
+				target: LOG_TARGET,
+				"Failed to decode persisted state sync progress, discarding it.",
+			);
+			clear_persisted_progress(&*store, 0);
+			return None
+		};
+
+		let mut state_sync = Self::new(
+			client,
+			progress.target_header,
+			progress.target_body,
+			progress.target_justifications,
+			progress.skip_proof,
+		);
+
+		for index in 0..progress.ranges {
+			let range = store
+				.read(&state_range_key(index))
+				.and_then(|range| PersistedRange::decode(&mut &range[..]).ok());
+			if !range.is_some_and(|range| state_sync.restore_range(range)) {
+				warn!(
+					target: LOG_TARGET,
+					"Persisted state range {index} is missing or invalid, discarding state sync \
+					progress",
+				);
+				clear_persisted_progress(&*store, progress.ranges);
+				return None
+			}
+		}
+
+		state_sync.metadata.last_key = progress.last_key.into();
+		state_sync.metadata.imported_bytes = progress.imported_bytes;
+		state_sync.persisted_ranges = progress.ranges;
+		state_sync.writer = ProgressWriter::spawn(store, progress.ranges);
+
+		info!(
+			target: LOG_TARGET,
+			"Resuming state sync of #{} ({}) from {} persisted ranges ({} MiB).",
+			state_sync.metadata.target_number(),
+			state_sync.metadata.target_hash(),
+			progress.ranges,
+			progress.imported_bytes / (1024 * 1024),
+		);
+
+		Some(state_sync)
+	}
+
+	/// Process a range persisted by a previous instance, verifying its proof.
+	fn restore_range(&mut self, range: PersistedRange) -> bool {
+		match range.content {
+			RangeContent::Proof(proof) => {
+				let Ok(proof) = CompactProof::decode(&mut &proof[..]) else { return false };
+				match self.client.verify_range_proof(
+					self.metadata.target_root(),
+					proof,
+					range.start.as_slice(),
+				) {
+					Ok((values, _)) => {
+						self.process_state_verified(values);
+						true
+					},
+					Err(e) => {
+						debug!(target: LOG_TARGET, "Persisted state range failed verification: {e}");
+						false
+					},
+				}
+			},
+			RangeContent::KeyValues(key_values) => {
+				for (state_root, key_values) in key_values {
+					self.process_state_key_values(state_root, key_values);
+				}
+				true
+			},
+		}
+	}
+
+	/// Queue a downloaded state range for persistence together with the updated download
+	/// progress.
+	fn persist_progress(&mut self, range: Option<PersistedRange>) {
+		let Some(writer) = &self.writer else { return };
+
+		let index = self.persisted_ranges;
+		let range = range.map(|range| range.encode());
+		if range.is_some() {
+			self.persisted_ranges += 1;
+		}
+
+		let progress = PersistedProgress::<B> {
+			target_header: self.metadata.target_header.clone(),
+			target_body: self.metadata.target_body.clone(),
+			target_justifications: self.metadata.target_justifications.clone(),
+			skip_proof: self.metadata.skip_proof,
+			last_key: self.metadata.last_key.to_vec(),
+			imported_bytes: self.metadata.imported_bytes,
+			ranges: self.persisted_ranges,
+		}
+		.encode();
+
+		writer.send(WriteCommand::Range { index, range, progress });
+	}
+
 	fn process_state_key_values(
 		&mut self,
 		state_root: Vec<u8>,
@@ -211,7 +516,8 @@ where
 		}
 	}
 
-	fn process_state_unverified(&mut self, response: StateResponse) -> bool {
+	fn process_state_unverified(&mut self, response: StateResponse) -> (bool, KeyValuesByRoot) {
+		let mut persisted = KeyValuesByRoot::new();
 		let mut complete = true;
 		// if the trie is a child trie and one of its parent trie is empty,
 		// the parent cursor stays valid.
@@ -239,12 +545,14 @@ where
 			}
 
 			let KeyValueStateEntry { state_root, entries, complete: _ } = state;
-			self.process_state_key_values(
-				state_root,
-				entries.into_iter().map(|StateEntry { key, value }| (key, value)),
-			);
+			let key_values: Vec<_> =
+				entries.into_iter().map(|StateEntry { key, value }| (key, value)).collect();
+			if self.writer.is_some() {
+				persisted.push((state_root.clone(), key_values.clone()));
+			}
+			self.process_state_key_values(state_root, key_values);
 		}
-		complete
+		(complete, persisted)
 	}
 }
 
@@ -263,7 +571,8 @@ where
 			debug!(target: LOG_TARGET, "Missing proof");
 			return ImportResult::BadResponse
 		}
-		let complete = if !self.metadata.skip_proof {
+		let start = self.metadata.last_key.to_vec();
+		let (complete, content) = if !self.metadata.skip_proof {
 			debug!(target: LOG_TARGET, "Importing state from {} trie nodes", response.proof.len());
 			let proof_size = response.proof.len() as u64;
 			let proof = match CompactProof::decode(&mut response.proof.as_ref()) {
@@ -297,9 +606,10 @@ where
 
 			self.process_state_verified(values);
 			self.metadata.imported_bytes += proof_size;
-			complete
+			(complete, RangeContent::Proof(response.proof))
 		} else {
-			self.process_state_unverified(response)
+			let (complete, key_values) = self.process_state_unverified(response);
+			(complete, RangeContent::KeyValues(key_values))
 		};
 		if complete {
 			self.metadata.complete = true;
@@ -312,6 +622,7 @@ where
 				self.metadata.target_justifications.clone(),
 			)
 		} else {
+			self.persist_progress(Some(PersistedRange { start, content }));
 			ImportResult::Continue
 		}
 	}
@@ -340,4 +651,23 @@ where
 	fn progress(&self) -> StateSyncProgress {
 		self.metadata.progress()
 	}
+
+	/// Discard persisted progress once the downloaded state has been processed by the import
+	/// queue.
+	fn clear_progress(&mut self) {
+		if let Some(writer) = self.writer.take() {
+			writer.send(WriteCommand::Clear);
+		}
+	}
+}
+
+/// Remove state sync progress persisted by [`StateSync::persistent`] with `ranges` ranges.
+fn clear_persisted_progress(store: &dyn ProgressStore, ranges: u32) {
+	let range_keys: Vec<_> = (0..ranges).map(state_range_key).collect();
+	let delete: Vec<_> = range_keys
+		.iter()
+		.map(Vec::as_slice)
+		.chain(std::iter::once(STATE_SYNC_PROGRESS_KEY))
+		.collect();
+	store.write(&[], &delete);
 }
@@ -211,6 +211,7 @@ impl TestNetworkBuilder {
 			metrics_registry: None,
 			state_request_protocol_name: state_request_protocol_config.name.clone(),
 			block_downloader: block_relay_params.downloader,
+			header_verifier: None,
 			min_peers_to_start_warp_sync: None,
 		};
 		// Initialize syncing strategy.
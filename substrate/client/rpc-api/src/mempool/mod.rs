@@ -0,0 +1,141 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Substrate mempool API.
+//!
+//! Allows to inspect the content of the transaction pool: the transactions are listed along
+//! with the tags they require and provide, so it is possible to tell why a transaction is not
+//! ready to be included. All the methods are marked `unsafe`.
+
+pub mod error;
+
+use error::Error;
+use jsonrpsee::proc_macros::rpc;
+use serde::{Deserialize, Serialize};
+use sp_core::Bytes;
+
+/// State of the transaction in the pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TransactionState {
+	/// All the tags required by the transaction are provided, it can be included in the block.
+	Ready,
+	/// Some of the tags required by the transaction are not provided yet.
+	Future,
+}
+
+/// Order of the listed transactions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PendingOrder {
+	/// Ready transactions in the order they are provided to the block builder, followed by the
+	/// future transactions.
+	#[default]
+	Pool,
+	/// By the priority, highest first.
+	Priority,
+}
+
+/// Filter of the transactions listed by `mempool_pendingExtrinsics`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PendingFilter {
+	/// Only the transactions in given state.
+	pub state: Option<TransactionState>,
+	/// Only the transactions providing a tag starting with given bytes.
+	///
+	/// For FRAME based runtimes the tags provided by a signed transaction are the SCALE encoded
+	/// `(AccountId, Nonce)` tuples, so the encoded account id selects the transactions of the
+	/// sender.
+	pub sender: Option<Bytes>,
+	/// Only the transactions with the priority at least given one.
+	pub min_priority: Option<u64>,
+	/// Order of the transactions.
+	pub order: PendingOrder,
+	/// Maximal number of the transactions returned.
+	pub limit: Option<usize>,
+}
+
+/// Transaction in the pool.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingExtrinsic<Hash> {
+	/// Hash of the transaction.
+	pub hash: Hash,
+	/// State of the transaction.
+	pub state: TransactionState,
+	/// Priority of the transaction.
+	pub priority: u64,
+	/// Number of blocks the transaction is valid for.
+	pub longevity: u64,
+	/// Whether the transaction is propagated to other peers.
+	pub propagate: bool,
+	/// Tags required by the transaction.
+	pub requires: Vec<Bytes>,
+	/// Tags provided by the transaction.
+	pub provides: Vec<Bytes>,
+	/// Required tags which are not provided by any ready transaction.
+	///
+	/// These are the reason the transaction is not ready, always empty for ready transactions.
+	pub missing: Vec<Bytes>,
+	/// Encoded transaction.
+	pub extrinsic: Bytes,
+}
+
+/// Transactions to be removed by `mempool_removeExtrinsics`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ExtrinsicSelector<Hash> {
+	/// The transaction of given hash.
+	Hash(Hash),
+	/// The transactions providing given tag.
+	Tag(Bytes),
+}
+
+/// Substrate mempool API.
+///
+/// The content of the pool is reported as seen at the most recently notified best block.
+#[rpc(client, server)]
+pub trait MempoolApi<Hash> {
+	/// Returns the transactions in the pool matching the filter.
+	#[method(name = "mempool_pendingExtrinsics", with_extensions)]
+	fn pending_extrinsics(
+		&self,
+		filter: Option<PendingFilter>,
+	) -> Result<Vec<PendingExtrinsic<Hash>>, Error>;
+
+	/// Returns the transaction of given hash, if it is in the pool.
+	#[method(name = "mempool_extrinsic", with_extensions)]
+	fn extrinsic(&self, hash: Hash) -> Result<Option<PendingExtrinsic<Hash>>, Error>;
+
+	/// Removes given transactions from the pool and temporarily bans them to prevent
+	/// reimporting.
+	///
+	/// The transactions depending on the removed ones are removed and banned as well. The pool
+	/// does not provide a removal without ban: until the ban expires (`--tx-ban-seconds`, 30
+	/// minutes by default) submitting any of them again fails with a "temporarily banned" error.
+	/// Watchers of the removed transactions are notified with an `invalid` event.
+	///
+	/// Returns the hashes of the removed transactions, followed by the hashes of the removed
+	/// dependents.
+	#[method(name = "mempool_removeExtrinsics", with_extensions)]
+	fn remove_extrinsics(
+		&self,
+		selectors: Vec<ExtrinsicSelector<Hash>>,
+	) -> Result<Vec<Hash>, Error>;
+}
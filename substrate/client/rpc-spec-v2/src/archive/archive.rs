@@ -272,7 +272,7 @@ where
 		items: Vec<ArchiveStorageDiffItem<String>>,
 		previous_hash: Option<Block::Hash>,
 	) {
-		let storage_client = ArchiveStorageDiff::new(self.client.clone());
+		let storage_client = ArchiveStorageDiff::new(self.client.clone(), self.backend.clone());
 		let client = self.client.clone();
 
 		log::trace!(target: LOG_TARGET, "Storage diff subscription started");
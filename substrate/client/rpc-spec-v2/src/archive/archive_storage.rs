@@ -26,6 +26,7 @@ use std::{
 use itertools::Itertools;
 use sc_client_api::{Backend, ChildInfo, StorageKey, StorageProvider};
 use sp_runtime::traits::Block as BlockT;
+use sp_state_machine::{backend::AsTrieBackend, TrieDiff};
 
 use super::error::Error as ArchiveError;
 use crate::{
@@ -84,11 +85,12 @@ enum FetchedStorage {
 
 pub struct ArchiveStorageDiff<Client, Block, BE> {
 	client: Storage<Client, Block, BE>,
+	backend: Arc<BE>,
 }
 
 impl<Client, Block, BE> ArchiveStorageDiff<Client, Block, BE> {
-	pub fn new(client: Arc<Client>) -> Self {
-		Self { client: Storage::new(client) }
+	pub fn new(client: Arc<Client>, backend: Arc<BE>) -> Self {
+		Self { client: Storage::new(client), backend }
 	}
 }
 
@@ -199,95 +201,79 @@ where
 		true
 	}
 
-	fn handle_trie_queries_inner(
+	/// Fetch and send the storage of a key that differs between the two blocks.
+	///
+	/// Returns `false` if the sender has been closed.
+	fn handle_diff(
 		&self,
 		hash: Block::Hash,
 		previous_hash: Block::Hash,
-		items: Vec<DiffDetails>,
+		diff: TrieDiff,
+		items: &[DiffDetails],
 		tx: &mpsc::Sender<ArchiveStorageDiffEvent>,
-	) -> Result<(), String> {
-		// Parse the child trie key as `ChildInfo` and `String`.
+	) -> Result<bool, String> {
+		let (operation_type, key) = match diff {
+			TrieDiff::Added(key) => (ArchiveStorageDiffOperationType::Added, StorageKey(key)),
+			TrieDiff::Modified(key) => (ArchiveStorageDiffOperationType::Modified, StorageKey(key)),
+			TrieDiff::Deleted(key) => (ArchiveStorageDiffOperationType::Deleted, StorageKey(key)),
+		};
+
+		let Some(fetch_type) = Self::belongs_to_query(&key, items) else {
+			// The key does not belong the the query items.
+			return Ok(true)
+		};
+
 		let maybe_child_trie = items.first().and_then(|item| item.child_trie_key.clone());
 		let maybe_child_trie_str =
 			items.first().and_then(|item| item.child_trie_key_string.clone());
 
-		// Iterator over the current block and previous block
-		// at the same time to compare the keys. This approach effectively
-		// leverages backpressure to avoid memory consumption.
-		let keys_iter = self.client.raw_keys_iter(hash, maybe_child_trie.clone())?;
-		let previous_keys_iter =
-			self.client.raw_keys_iter(previous_hash, maybe_child_trie.clone())?;
-
-		let mut diff_iter = lexicographic_diff(keys_iter, previous_keys_iter);
-
-		while let Some(item) = diff_iter.next() {
-			let (operation_type, key) = match item {
-				Diff::Added(key) => (ArchiveStorageDiffOperationType::Added, key),
-				Diff::Deleted(key) => (ArchiveStorageDiffOperationType::Deleted, key),
-				Diff::Equal(key) => (ArchiveStorageDiffOperationType::Modified, key),
-			};
-
-			let Some(fetch_type) = Self::belongs_to_query(&key, &items) else {
-				// The key does not belong the the query items.
-				continue;
-			};
+		// Deleted keys are reported with their value in the previous block.
+		let at = match operation_type {
+			ArchiveStorageDiffOperationType::Deleted => previous_hash,
+			_ => hash,
+		};
 
-			let maybe_result = match operation_type {
-				ArchiveStorageDiffOperationType::Added =>
-					self.fetch_storage(hash, key.clone(), maybe_child_trie.clone(), fetch_type)?,
-				ArchiveStorageDiffOperationType::Deleted => self.fetch_storage(
-					previous_hash,
-					key.clone(),
-					maybe_child_trie.clone(),
-					fetch_type,
-				)?,
-				ArchiveStorageDiffOperationType::Modified => {
-					let Some(storage_result) = self.fetch_storage(
-						hash,
-						key.clone(),
-						maybe_child_trie.clone(),
-						fetch_type,
-					)?
-					else {
-						continue
-					};
-
-					let Some(previous_storage_result) = self.fetch_storage(
-						previous_hash,
-						key.clone(),
-						maybe_child_trie.clone(),
-						fetch_type,
-					)?
-					else {
-						continue
-					};
+		match self.fetch_storage(at, key, maybe_child_trie, fetch_type)? {
+			Some(storage_result) =>
+				Ok(Self::send_result(tx, storage_result, operation_type, maybe_child_trie_str)),
+			None => Ok(true),
+		}
+	}
 
-					// For modified records we need to check the actual storage values.
-					if storage_result == previous_storage_result {
-						continue
-					}
+	fn handle_trie_queries_inner(
+		&self,
+		hash: Block::Hash,
+		previous_hash: Block::Hash,
+		items: Vec<DiffDetails>,
+		tx: &mpsc::Sender<ArchiveStorageDiffEvent>,
+	) -> Result<(), String> {
+		let maybe_child_trie = items.first().and_then(|item| item.child_trie_key.clone());
 
-					Some(storage_result)
+		// The differences are computed by comparing the tries of the two blocks, starting from
+		// their roots. Subtries that are identical in both blocks are skipped, so only the nodes
+		// that changed are read.
+		let state = self.backend.state_at(hash).map_err(|error| error.to_string())?;
+		let previous_state =
+			self.backend.state_at(previous_hash).map_err(|error| error.to_string())?;
+
+		let mut result = Ok(());
+		state.as_trie_backend().storage_diff(
+			previous_state.as_trie_backend(),
+			maybe_child_trie.as_ref(),
+			|diff| match self.handle_diff(hash, previous_hash, diff, &items, tx) {
+				Ok(proceed) => proceed,
+				Err(error) => {
+					result = Err(error);
+					false
 				},
-			};
-
-			if let Some(storage_result) = maybe_result {
-				if !Self::send_result(
-					&tx,
-					storage_result,
-					operation_type,
-					maybe_child_trie_str.clone(),
-				) {
-					return Ok(())
-				}
-			}
-		}
+			},
+		)?;
 
-		Ok(())
+		result
 	}
 
-	/// This method will iterate over the keys of the main trie or a child trie and fetch the
-	/// given keys. The fetched keys will be sent to the provided `tx` sender to leverage
+	/// This method will compare the main trie or a child trie of the two blocks and fetch the
+	/// keys that differ. The fetched keys will be sent to the provided `tx` sender to leverage
 	/// the backpressure mechanism.
 	pub async fn handle_trie_queries(
 		&self,
@@ -296,7 +282,8 @@ where
 		previous_hash: Block::Hash,
 		tx: mpsc::Sender<ArchiveStorageDiffEvent>,
 	) -> Result<(), tokio::task::JoinError> {
-		let this = ArchiveStorageDiff { client: self.client.clone() };
+		let this =
+			ArchiveStorageDiff { client: self.client.clone(), backend: self.backend.clone() };
 
 		tokio::task::spawn_blocking(move || {
 			// Deduplicate the items.
@@ -350,57 +337,6 @@ where
 	}
 }
 
-/// The result of the `lexicographic_diff` method.
-#[derive(Debug, PartialEq)]
-enum Diff<T> {
-	Added(T),
-	Deleted(T),
-	Equal(T),
-}
-
-/// Compare two iterators lexicographically and return the differences.
-fn lexicographic_diff<T, LeftIter, RightIter>(
-	mut left: LeftIter,
-	mut right: RightIter,
-) -> impl Iterator<Item = Diff<T>>
-where
-	T: Ord,
-	LeftIter: Iterator<Item = T>,
-	RightIter: Iterator<Item = T>,
-{
-	let mut a = left.next();
-	let mut b = right.next();
-
-	core::iter::from_fn(move || match (a.take(), b.take()) {
-		(Some(a_value), Some(b_value)) =>
-			if a_value < b_value {
-				b = Some(b_value);
-				a = left.next();
-
-				Some(Diff::Added(a_value))
-			} else if a_value > b_value {
-				a = Some(a_value);
-				b = right.next();
-
-				Some(Diff::Deleted(b_value))
-			} else {
-				a = left.next();
-				b = right.next();
-
-				Some(Diff::Equal(a_value))
-			},
-		(Some(a_value), None) => {
-			a = left.next();
-			Some(Diff::Added(a_value))
-		},
-		(None, Some(b_value)) => {
-			b = right.next();
-			Some(Diff::Deleted(b_value))
-		},
-		(None, None) => None,
-	})
-}
-
 /// Deduplicate the provided items and return a list of `DiffDetails`.
 ///
 /// Each list corresponds to a single child trie or the main trie.
@@ -798,52 +734,4 @@ mod tests {
 
 		assert_eq!(result, expected);
 	}
-
-	#[test]
-	fn test_lexicographic_diff() {
-		let left = vec![1, 2, 3, 4, 5];
-		let right = vec![2, 3, 4, 5, 6];
-
-		let diff = lexicographic_diff(left.into_iter(), right.into_iter()).collect::<Vec<_>>();
-		let expected = vec![
-			Diff::Added(1),
-			Diff::Equal(2),
-			Diff::Equal(3),
-			Diff::Equal(4),
-			Diff::Equal(5),
-			Diff::Deleted(6),
-		];
-		assert_eq!(diff, expected);
-	}
-
-	#[test]
-	fn test_lexicographic_diff_one_side_empty() {
-		let left = vec![];
-		let right = vec![1, 2, 3, 4, 5, 6];
-
-		let diff = lexicographic_diff(left.into_iter(), right.into_iter()).collect::<Vec<_>>();
-		let expected = vec![
-			Diff::Deleted(1),
-			Diff::Deleted(2),
-			Diff::Deleted(3),
-			Diff::Deleted(4),
-			Diff::Deleted(5),
-			Diff::Deleted(6),
-		];
-		assert_eq!(diff, expected);
-
-		let left = vec![1, 2, 3, 4, 5, 6];
-		let right = vec![];
-
-		let diff = lexicographic_diff(left.into_iter(), right.into_iter()).collect::<Vec<_>>();
-		let expected = vec![
-			Diff::Added(1),
-			Diff::Added(2),
-			Diff::Added(3),
-			Diff::Added(4),
-			Diff::Added(5),
-			Diff::Added(6),
-		];
-		assert_eq!(diff, expected);
-	}
 }
@@ -0,0 +1,229 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Implementation of the [`MempoolApiServer`] trait allowing to inspect the content of the
+//! transaction pool.
+
+#[cfg(test)]
+mod tests;
+
+use codec::Encode;
+use jsonrpsee::Extensions;
+use sc_rpc_api::check_if_safe;
+use sc_transaction_pool_api::{InPoolTransaction, TransactionPool, TxHash, TxInvalidityReportMap};
+use std::{collections::HashSet, sync::Arc};
+
+pub use sc_rpc_api::mempool::{
+	error::{Error, Result},
+	ExtrinsicSelector, MempoolApiServer, PendingExtrinsic, PendingFilter, PendingOrder,
+	TransactionState,
+};
+
+/// The Mempool API. All methods are unsafe.
+pub struct Mempool<P> {
+	pool: Arc<P>,
+}
+
+impl<P> Mempool<P> {
+	/// Create a new Mempool API.
+	pub fn new(pool: Arc<P>) -> Self {
+		Self { pool }
+	}
+}
+
+impl<P: TransactionPool> Mempool<P> {
+	/// Returns the ready transactions in the order they are provided to the block builder,
+	/// followed by the future transactions.
+	fn pending(&self) -> Vec<PendingExtrinsic<TxHash<P>>> {
+		let ready = self.pool.ready().collect::<Vec<_>>();
+		let future = self.pool.futures();
+		let provided = ready
+			.iter()
+			.flat_map(|tx| tx.provides().iter().map(|tag| &tag[..]))
+			.collect::<HashSet<_>>();
+
+		ready
+			.iter()
+			.map(|tx| pending_extrinsic(&**tx, TransactionState::Ready, &provided))
+			.chain(
+				future
+					.iter()
+					.map(|tx| pending_extrinsic(tx, TransactionState::Future, &provided)),
+			)
+			.collect()
+	}
+}
+
+fn pending_extrinsic<T>(
+	tx: &T,
+	state: TransactionState,
+	provided: &HashSet<&[u8]>,
+) -> PendingExtrinsic<T::Hash>
+where
+	T: InPoolTransaction,
+	T::Transaction: Encode,
+	T::Hash: Clone,
+{
+	let missing = match state {
+		TransactionState::Ready => Vec::new(),
+		TransactionState::Future => tx
+			.requires()
+			.iter()
+			.filter(|tag| !provided.contains(&tag[..]))
+			.map(|tag| tag.clone().into())
+			.collect(),
+	};
+
+	PendingExtrinsic {
+		hash: tx.hash().clone(),
+		state,
+		priority: *tx.priority(),
+		longevity: *tx.longevity(),
+		propagate: tx.is_propagable(),
+		requires: tx.requires().iter().map(|tag| tag.clone().into()).collect(),
+		provides: tx.provides().iter().map(|tag| tag.clone().into()).collect(),
+		missing,
+		extrinsic: tx.data().encode().into(),
+	}
+}
+
+impl<P> MempoolApiServer<TxHash<P>> for Mempool<P>
+where
+	P: TransactionPool + Sync + Send + 'static,
+{
+	fn pending_extrinsics(
+		&self,
+		ext: &Extensions,
+		filter: Option<PendingFilter>,
+	) -> Result<Vec<PendingExtrinsic<TxHash<P>>>> {
+		check_if_safe(ext)?;
+
+		let filter = filter.unwrap_or_default();
+		let mut pending = self.pending();
+		pending.retain(|tx| {
+			filter.state.map_or(true, |state| tx.state == state) &&
+				filter.min_priority.map_or(true, |priority| tx.priority >= priority) &&
+				filter.sender.as_ref().map_or(true, |sender| {
+					tx.provides.iter().any(|tag| tag.starts_with(&sender[..]))
+				})
+		});
+		if filter.order == PendingOrder::Priority {
+			pending.sort_by(|a, b| b.priority.cmp(&a.priority));
+		}
+		if let Some(limit) = filter.limit {
+			pending.truncate(limit);
+		}
+
+		Ok(pending)
+	}
+
+	fn extrinsic(
+		&self,
+		ext: &Extensions,
+		hash: TxHash<P>,
+	) -> Result<Option<PendingExtrinsic<TxHash<P>>>> {
+		check_if_safe(ext)?;
+
+		Ok(self.pending().into_iter().find(|tx| tx.hash == hash))
+	}
+
+	fn remove_extrinsics(
+		&self,
+		ext: &Extensions,
+		selectors: Vec<ExtrinsicSelector<TxHash<P>>>,
+	) -> Result<Vec<TxHash<P>>> {
+		check_if_safe(ext)?;
+
+		let pooled = self.pool.ready().chain(self.pool.futures().into_iter().map(Arc::new));
+		let pooled = pooled.collect::<Vec<_>>();
+
+		let mut hashes = TxInvalidityReportMap::<TxHash<P>>::default();
+		let mut tags = Vec::new();
+		for selector in selectors {
+			match selector {
+				ExtrinsicSelector::Hash(hash) => {
+					hashes.insert(hash, None);
+				},
+				ExtrinsicSelector::Tag(tag) => tags.push(tag),
+			}
+		}
+		for tx in &pooled {
+			if tx.provides().iter().any(|tag| tags.iter().any(|t| t[..] == tag[..])) {
+				hashes.insert(tx.hash().clone(), None);
+			}
+		}
+
+		// The pool removes the transactions depending on the selected ones as well, but only
+		// returns the selected ones. Find the dependents by their tags to report them too.
+		let dependents = dependents(&pooled, hashes.keys().cloned().collect());
+
+		// Reporting without an error forcibly removes the transactions, the pool bans them as it
+		// does for any invalid transaction.
+		let mut removed = self
+			.pool
+			.report_invalid(None, hashes)
+			.into_iter()
+			.map(|tx| tx.hash().clone())
+			.collect::<Vec<_>>();
+
+		let remaining = self
+			.pool
+			.ready()
+			.map(|tx| tx.hash().clone())
+			.chain(self.pool.futures().iter().map(|tx| tx.hash().clone()))
+			.collect::<HashSet<_>>();
+		for hash in dependents {
+			if !remaining.contains(&hash) && !removed.contains(&hash) {
+				removed.push(hash);
+			}
+		}
+		Ok(removed)
+	}
+}
+
+/// Returns the hashes of the pooled transactions depending (directly or through other
+/// transactions) on the selected ones, in the order they are found.
+fn dependents<T>(pooled: &[Arc<T>], selected: HashSet<T::Hash>) -> Vec<T::Hash>
+where
+	T: InPoolTransaction,
+	T::Hash: Clone + Eq + std::hash::Hash,
+{
+	let mut removed = selected;
+	let mut provided = pooled
+		.iter()
+		.filter(|tx| removed.contains(tx.hash()))
+		.flat_map(|tx| tx.provides().iter().map(|tag| &tag[..]))
+		.collect::<HashSet<_>>();
+
+	let mut dependents = Vec::new();
+	loop {
+		let found = pooled
+			.iter()
+			.filter(|tx| !removed.contains(tx.hash()))
+			.filter(|tx| tx.requires().iter().any(|tag| provided.contains(&tag[..])))
+			.collect::<Vec<_>>();
+		if found.is_empty() {
+			return dependents
+		}
+		for tx in found {
+			removed.insert(tx.hash().clone());
+			provided.extend(tx.provides().iter().map(|tag| &tag[..]));
+			dependents.push(tx.hash().clone());
+		}
+	}
+}
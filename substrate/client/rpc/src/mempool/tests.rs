@@ -0,0 +1,261 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use super::*;
+use crate::DenyUnsafe;
+use assert_matches::assert_matches;
+use jsonrpsee::{core::EmptyServerParams as EmptyParams, MethodsError as RpcError, RpcModule};
+use sc_block_builder::BlockBuilderBuilder;
+use sc_transaction_pool::{ForkAwareTxPool, FullChainApi};
+use sc_transaction_pool_api::{
+	error::{Error as TxPoolError, IntoPoolError},
+	ChainEvent, MaintainedTransactionPool, TransactionSource,
+};
+use sp_blockchain::HeaderBackend;
+use sp_consensus::BlockOrigin;
+use sp_core::{Bytes, H256};
+use sp_runtime::traits::Block as BlockT;
+use substrate_test_runtime_client::{
+	prelude::*,
+	runtime::{Block, Extrinsic, ExtrinsicBuilder, Transfer},
+	Backend, Client, Sr25519Keyring,
+};
+
+type FullTransactionPool = ForkAwareTxPool<FullChainApi<Client<Backend>, Block>, Block>;
+
+fn uxt(sender: Sr25519Keyring, nonce: u64) -> Extrinsic {
+	let tx = Transfer {
+		amount: Default::default(),
+		nonce,
+		from: sender.into(),
+		to: Sr25519Keyring::Bob.into(),
+	};
+	ExtrinsicBuilder::new_transfer(tx).build()
+}
+
+fn tag(sender: Sr25519Keyring, nonce: u64) -> Bytes {
+	(sender.public(), nonce).encode().into()
+}
+
+struct TestSetup {
+	pub pool: Arc<FullTransactionPool>,
+	pub best: H256,
+}
+
+impl TestSetup {
+	/// Creates the pool with a view at the first block.
+	async fn new() -> Self {
+		let client = Arc::new(substrate_test_runtime_client::new());
+		let pool = Arc::new(ForkAwareTxPool::new_full(
+			Default::default(),
+			None,
+			true.into(),
+			None,
+			sp_core::testing::TaskExecutor::new(),
+			client.clone(),
+		));
+
+		let block = BlockBuilderBuilder::new(&*client)
+			.on_parent_block(client.chain_info().genesis_hash)
+			.with_parent_block_number(0)
+			.build()
+			.unwrap()
+			.build()
+			.unwrap()
+			.block;
+		let best = block.hash();
+		client.import(BlockOrigin::Own, block).await.unwrap();
+		pool.maintain(ChainEvent::NewBestBlock { hash: best, tree_route: None }).await;
+
+		TestSetup { pool, best }
+	}
+
+	async fn submit(&self, xt: Extrinsic) -> H256 {
+		self.pool.submit_one(self.best, TransactionSource::External, xt).await.unwrap()
+	}
+
+	fn to_rpc(&self) -> RpcModule<Mempool<FullTransactionPool>> {
+		let mut module = Mempool::new(self.pool.clone()).into_rpc();
+		module.extensions_mut().insert(DenyUnsafe::No);
+		module
+	}
+}
+
+#[tokio::test]
+async fn mempool_should_list_ready_and_future_extrinsics() {
+	let setup = TestSetup::new().await;
+	let api = setup.to_rpc();
+
+	let alice0 = setup.submit(uxt(Sr25519Keyring::Alice, 0)).await;
+	let alice2 = setup.submit(uxt(Sr25519Keyring::Alice, 2)).await;
+	let bob0 = setup.submit(uxt(Sr25519Keyring::Bob, 0)).await;
+
+	let pending: Vec<PendingExtrinsic<H256>> =
+		api.call("mempool_pendingExtrinsics", EmptyParams::new()).await.unwrap();
+	let states = pending.iter().map(|tx| (tx.hash, tx.state)).collect::<Vec<_>>();
+	assert_eq!(
+		states,
+		vec![
+			(alice0, TransactionState::Ready),
+			(bob0, TransactionState::Ready),
+			(alice2, TransactionState::Future),
+		]
+	);
+	assert_eq!(pending[0].provides, vec![tag(Sr25519Keyring::Alice, 0)]);
+	assert_eq!(pending[0].extrinsic, Bytes::from(uxt(Sr25519Keyring::Alice, 0).encode()));
+	assert!(pending[0].missing.is_empty());
+	assert_eq!(pending[2].missing, vec![tag(Sr25519Keyring::Alice, 1)]);
+}
+
+#[tokio::test]
+async fn mempool_should_filter_pending_extrinsics() {
+	let setup = TestSetup::new().await;
+	let api = setup.to_rpc();
+
+	let alice0 = setup.submit(uxt(Sr25519Keyring::Alice, 0)).await;
+	let alice2 = setup.submit(uxt(Sr25519Keyring::Alice, 2)).await;
+	let _bob0 = setup.submit(uxt(Sr25519Keyring::Bob, 0)).await;
+
+	let pending = |filter: PendingFilter| {
+		let api = &api;
+		async move {
+			api.call::<_, Vec<PendingExtrinsic<H256>>>("mempool_pendingExtrinsics", [filter])
+				.await
+				.unwrap()
+				.into_iter()
+				.map(|tx| tx.hash)
+				.collect::<Vec<_>>()
+		}
+	};
+
+	let alice = Bytes::from(Sr25519Keyring::Alice.public().encode());
+	assert_eq!(
+		pending(PendingFilter { sender: Some(alice.clone()), ..Default::default() }).await,
+		vec![alice0, alice2]
+	);
+	assert_eq!(
+		pending(PendingFilter {
+			sender: Some(alice),
+			state: Some(TransactionState::Future),
+			..Default::default()
+		})
+		.await,
+		vec![alice2]
+	);
+	assert_eq!(pending(PendingFilter { limit: Some(1), ..Default::default() }).await, vec![alice0]);
+}
+
+#[tokio::test]
+async fn mempool_should_explain_stuck_extrinsic() {
+	let setup = TestSetup::new().await;
+	let api = setup.to_rpc();
+
+	let alice1 = setup.submit(uxt(Sr25519Keyring::Alice, 1)).await;
+	let alice2 = setup.submit(uxt(Sr25519Keyring::Alice, 2)).await;
+
+	// Both are stuck because of the missing nonce `0`, `alice2` is waiting for `alice1`.
+	let tx: Option<PendingExtrinsic<H256>> = api.call("mempool_extrinsic", [alice2]).await.unwrap();
+	assert_matches!(tx, Some(tx) if tx.state == TransactionState::Future &&
+		tx.missing == vec![tag(Sr25519Keyring::Alice, 1)]);
+	let tx: Option<PendingExtrinsic<H256>> = api.call("mempool_extrinsic", [alice1]).await.unwrap();
+	assert_matches!(tx, Some(tx) if tx.missing == vec![tag(Sr25519Keyring::Alice, 0)]);
+
+	let tx: Option<PendingExtrinsic<H256>> =
+		api.call("mempool_extrinsic", [H256::repeat_byte(1)]).await.unwrap();
+	assert_eq!(tx, None);
+}
+
+#[tokio::test]
+async fn mempool_should_remove_extrinsics_by_tag() {
+	let setup = TestSetup::new().await;
+	let api = setup.to_rpc();
+
+	let alice0 = setup.submit(uxt(Sr25519Keyring::Alice, 0)).await;
+	let bob0 = setup.submit(uxt(Sr25519Keyring::Bob, 0)).await;
+	assert_eq!(setup.pool.status().ready, 2);
+
+	// Notice how we need an extra `Vec` wrapping the `Vec` we want to submit as params.
+	let removed: Vec<H256> = api
+		.call(
+			"mempool_removeExtrinsics",
+			vec![vec![ExtrinsicSelector::<H256>::Tag(tag(Sr25519Keyring::Bob, 0))]],
+		)
+		.await
+		.unwrap();
+	assert_eq!(removed, vec![bob0]);
+
+	let removed: Vec<H256> = api
+		.call("mempool_removeExtrinsics", vec![vec![ExtrinsicSelector::Hash(alice0)]])
+		.await
+		.unwrap();
+	assert_eq!(removed, vec![alice0]);
+	assert_eq!(setup.pool.status().ready, 0);
+}
+
+#[tokio::test]
+async fn mempool_should_return_removed_dependents() {
+	let setup = TestSetup::new().await;
+	let api = setup.to_rpc();
+
+	let alice0 = setup.submit(uxt(Sr25519Keyring::Alice, 0)).await;
+	let alice1 = setup.submit(uxt(Sr25519Keyring::Alice, 1)).await;
+	let bob0 = setup.submit(uxt(Sr25519Keyring::Bob, 0)).await;
+	assert_eq!(setup.pool.status().ready, 3);
+
+	let removed: Vec<H256> = api
+		.call("mempool_removeExtrinsics", vec![vec![ExtrinsicSelector::Hash(alice0)]])
+		.await
+		.unwrap();
+	assert_eq!(removed, vec![alice0, alice1]);
+
+	let pending: Vec<PendingExtrinsic<H256>> =
+		api.call("mempool_pendingExtrinsics", EmptyParams::new()).await.unwrap();
+	assert_eq!(pending.into_iter().map(|tx| tx.hash).collect::<Vec<_>>(), vec![bob0]);
+}
+
+#[tokio::test]
+async fn mempool_removed_extrinsics_are_banned() {
+	let setup = TestSetup::new().await;
+	let api = setup.to_rpc();
+
+	let alice0 = setup.submit(uxt(Sr25519Keyring::Alice, 0)).await;
+	let removed: Vec<H256> = api
+		.call("mempool_removeExtrinsics", vec![vec![ExtrinsicSelector::Hash(alice0)]])
+		.await
+		.unwrap();
+	assert_eq!(removed, vec![alice0]);
+
+	let result = setup
+		.pool
+		.submit_one(setup.best, TransactionSource::External, uxt(Sr25519Keyring::Alice, 0))
+		.await;
+	assert_matches!(result.unwrap_err().into_pool_error(), Ok(TxPoolError::TemporarilyBanned));
+}
+
+#[tokio::test]
+async fn deny_unsafe_works() {
+	let setup = TestSetup::new().await;
+	let mut api = setup.to_rpc();
+	api.extensions_mut().insert(DenyUnsafe::Yes);
+
+	assert_matches!(
+		api.call::<_, Vec<PendingExtrinsic<H256>>>("mempool_pendingExtrinsics", EmptyParams::new())
+			.await,
+		Err(RpcError::JsonRpc(e)) if e.message() == "RPC call is unsafe to be called externally"
+	);
+}
@@ -31,7 +31,7 @@ use log::info;
 use prometheus_endpoint::Registry;
 use sc_chain_spec::{get_extension, ChainSpec};
 use sc_client_api::{
-	execution_extensions::ExecutionExtensions, proof_provider::ProofProvider, BadBlocks,
+	execution_extensions::ExecutionExtensions, proof_provider::ProofProvider, AuxStore, BadBlocks,
 	BlockBackend, BlockchainEvents, ExecutorProvider, ForkBlocks, StorageProvider, UsageProvider,
 };
 use sc_client_db::{Backend, BlocksPruning, DatabaseSettings, PruningMode};
@@ -59,6 +59,7 @@ use sc_network_sync::{
 	service::network::{NetworkServiceHandle, NetworkServiceProvider},
 	state_request_handler::StateRequestHandler,
 	strategy::{
+		header_first::HeaderVerifier,
 		polkadot::{PolkadotSyncingStrategy, PolkadotSyncingStrategyConfig},
 		SyncingStrategy,
 	},
@@ -68,6 +69,7 @@ use sc_network_sync::{
 use sc_rpc::{
 	author::AuthorApiServer,
 	chain::ChainApiServer,
+	mempool::MempoolApiServer,
 	offchain::OffchainApiServer,
 	state::{ChildStateApiServer, StateApiServer},
 	system::SystemApiServer,
@@ -260,6 +262,7 @@ where
 				no_genesis: config.no_genesis(),
 				wasm_runtime_substitutes,
 				enable_import_proof_recording,
+				state_replay_max_blocks: config.state_replay_max_blocks,
 			},
 		)?;
 
@@ -769,6 +772,8 @@ where
 	)
 	.into_rpc();
 
+	let mempool = sc_rpc::mempool::Mempool::new(transaction_pool.clone()).into_rpc();
+
 	let author = sc_rpc::author::Author::new(
 		client.clone(),
 		transaction_pool,
@@ -796,6 +801,7 @@ where
 	// Part of the old RPC spec.
 	rpc_api.merge(chain).map_err(|e| Error::Application(e.into()))?;
 	rpc_api.merge(author).map_err(|e| Error::Application(e.into()))?;
+	rpc_api.merge(mempool).map_err(|e| Error::Application(e.into()))?;
 	rpc_api.merge(system).map_err(|e| Error::Application(e.into()))?;
 	rpc_api.merge(state).map_err(|e| Error::Application(e.into()))?;
 	rpc_api.merge(child_state).map_err(|e| Error::Application(e.into()))?;
@@ -830,6 +836,8 @@ where
 	>,
 	/// Optional warp sync config.
 	pub warp_sync_config: Option<WarpSyncConfig<Block>>,
+	/// Optional verifier of the headers downloaded by header-first sync.
+	pub header_verifier: Option<Arc<dyn HeaderVerifier<Block>>>,
 	/// User specified block relay params. If not specified, the default
 	/// block request handler will be used.
 	pub block_relay: Option<BlockRelayParams<Block, Net>>,
@@ -857,6 +865,7 @@ where
 		+ BlockBackend<Block>
 		+ BlockIdTo<Block, Error = sp_blockchain::Error>
 		+ ProofProvider<Block>
+		+ AuxStore
 		+ HeaderBackend<Block>
 		+ BlockchainEvents<Block>
 		+ 'static,
@@ -873,6 +882,7 @@ where
 		import_queue,
 		block_announce_validator_builder,
 		warp_sync_config,
+		header_verifier,
 		block_relay,
 		metrics,
 	} = params;
@@ -917,6 +927,7 @@ where
 		fork_id,
 		&mut net_config,
 		warp_sync_config,
+		header_verifier,
 		block_downloader,
 		client.clone(),
 		&spawn_handle,
@@ -1018,6 +1029,7 @@ where
 		+ BlockBackend<Block>
 		+ BlockIdTo<Block, Error = sp_blockchain::Error>
 		+ ProofProvider<Block>
+		+ AuxStore
 		+ HeaderBackend<Block>
 		+ BlockchainEvents<Block>
 		+ 'static,
@@ -1178,6 +1190,8 @@ where
 	pub network_service_handle: NetworkServiceHandle,
 	/// Warp sync configuration (when used).
 	pub warp_sync_config: Option<WarpSyncConfig<Block>>,
+	/// Verifier of the headers downloaded by header-first sync (when used).
+	pub header_verifier: Option<Arc<dyn HeaderVerifier<Block>>>,
 	/// A shared client returned by `new_full_parts`.
 	pub client: Arc<Client>,
 	/// Blocks import queue API.
@@ -1203,6 +1217,7 @@ where
 		+ BlockBackend<Block>
 		+ HeaderMetadata<Block, Error = sp_blockchain::Error>
 		+ ProofProvider<Block>
+		+ AuxStore
 		+ Send
 		+ Sync
 		+ 'static,
@@ -1216,6 +1231,7 @@ where
 		block_announce_validator,
 		network_service_handle,
 		warp_sync_config,
+		header_verifier,
 		client,
 		import_queue_service,
 		num_peers_hint,
@@ -1238,6 +1254,7 @@ where
 		fork_id,
 		net_config,
 		warp_sync_config,
+		header_verifier,
 		block_downloader,
 		client.clone(),
 		spawn_handle,
@@ -1305,6 +1322,7 @@ pub fn build_polkadot_syncing_strategy<Block, Client, Net>(
 	fork_id: Option<&str>,
 	net_config: &mut FullNetworkConfiguration<Block, <Block as BlockT>::Hash, Net>,
 	warp_sync_config: Option<WarpSyncConfig<Block>>,
+	header_verifier: Option<Arc<dyn HeaderVerifier<Block>>>,
 	block_downloader: Arc<dyn BlockDownloader<Block>>,
 	client: Arc<Client>,
 	spawn_handle: &SpawnTaskHandle,
@@ -1316,6 +1334,7 @@ where
 		+ BlockBackend<Block>
 		+ HeaderMetadata<Block, Error = sp_blockchain::Error>
 		+ ProofProvider<Block>
+		+ AuxStore
 		+ Send
 		+ Sync
 		+ 'static,
@@ -1325,12 +1344,16 @@ where
 		return Err("Warp sync enabled, but no warp sync provider configured.".into())
 	}
 
+	if header_verifier.is_none() && net_config.network_config.sync_mode == SyncMode::HeaderFirst {
+		return Err("Header-first sync enabled, but no header verifier configured.".into())
+	}
+
 	if client.requires_full_sync() {
 		match net_config.network_config.sync_mode {
 			SyncMode::LightState { .. } =>
 				return Err("Fast sync doesn't work for archive nodes".into()),
 			SyncMode::Warp => return Err("Warp sync doesn't work for archive nodes".into()),
-			SyncMode::Full => {},
+			SyncMode::Full | SyncMode::HeaderFirst => {},
 		}
 	}
 
@@ -1377,6 +1400,7 @@ where
 		metrics_registry: metrics_registry.cloned(),
 		state_request_protocol_name,
 		block_downloader,
+		header_verifier,
 	};
 	Ok(Box::new(PolkadotSyncingStrategy::new(
 		syncing_config,
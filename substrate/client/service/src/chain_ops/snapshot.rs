@@ -0,0 +1,361 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! State snapshots.
+//!
+//! A snapshot file starts with [`SNAPSHOT_MAGIC`] followed by the format version and a sequence
+//! of chunks. Each chunk is the SCALE encoded length of its payload, the payload and the
+//! blake2-256 hash of the payload. The first chunk holds the header and justifications of the
+//! snapshot block, it is followed by the compact proofs covering the whole state of the block,
+//! as served to state sync, and terminated by a chunk holding the number of state chunks.
+
+use crate::error::Error;
+use codec::{Compact, Decode, Encode};
+use futures::future;
+use futures_timer::Delay;
+use log::info;
+use sc_client_api::{BlockBackend, HeaderBackend, ProofProvider};
+use sc_consensus::import_queue::{
+	BlockImportError, BlockImportStatus, ImportQueue, IncomingBlock, Link,
+};
+use sc_network_sync::{
+	strategy::state_sync::{ImportResult, StateSync, StateSyncProvider},
+	StateResponse,
+};
+use smallvec::SmallVec;
+use sp_consensus::BlockOrigin;
+use sp_core::hashing::blake2_256;
+use sp_runtime::{
+	traits::{Block as BlockT, Header as HeaderT, NumberFor},
+	Justifications,
+};
+use std::{
+	io::{Read, Write},
+	sync::{Arc, Mutex},
+	task::Poll,
+	time::Duration,
+};
+
+/// Magic bytes every snapshot file starts with.
+pub const SNAPSHOT_MAGIC: [u8; 8] = *b"substsnp";
+
+/// Version of the snapshot format.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Maximum size of a chunk payload accepted when reading a snapshot.
+const MAX_CHUNK_SIZE: u32 = 256 * 1024 * 1024;
+
+/// Number of milliseconds to wait until next poll of the import queue.
+const DELAY_TIME: u64 = 200;
+
+/// A chunk of a snapshot file.
+#[derive(Debug, PartialEq, Encode, Decode)]
+enum SnapshotChunk<Header> {
+	/// The header and justifications of the snapshot block.
+	Header { header: Header, justifications: Option<Justifications> },
+	/// An encoded `CompactProof` of a range of the state.
+	State { proof: Vec<u8> },
+	/// The end of the snapshot.
+	End { state_chunks: u32 },
+}
+
+/// Writes the framed chunks of a snapshot.
+struct SnapshotWriter<W> {
+	output: W,
+}
+
+impl<W: Write> SnapshotWriter<W> {
+	fn new(mut output: W) -> Result<Self, Error> {
+		output.write_all(&SNAPSHOT_MAGIC)?;
+		output.write_all(&SNAPSHOT_VERSION.encode())?;
+		Ok(Self { output })
+	}
+
+	fn write_chunk<Header: Encode>(&mut self, chunk: &SnapshotChunk<Header>) -> Result<(), Error> {
+		let payload = chunk.encode();
+		let len = u32::try_from(payload.len())
+			.map_err(|_| Error::Other("Snapshot chunk is too large".into()))?;
+		self.output.write_all(&Compact(len).encode())?;
+		self.output.write_all(&payload)?;
+		self.output.write_all(&blake2_256(&payload))?;
+		Ok(())
+	}
+
+	fn finish(mut self) -> Result<(), Error> {
+		self.output.flush().map_err(Into::into)
+	}
+}
+
+/// Reads and checks the framed chunks of a snapshot.
+struct SnapshotReader<R> {
+	input: codec::IoReader<R>,
+	read_chunks: u32,
+}
+
+impl<R: Read> SnapshotReader<R> {
+	fn new(input: R) -> Result<Self, Error> {
+		let mut input = codec::IoReader(input);
+		let magic = <[u8; 8]>::decode(&mut input)
+			.map_err(|e| Error::Other(format!("Error reading the snapshot magic: {e}")))?;
+		if magic != SNAPSHOT_MAGIC {
+			return Err(Error::Other("The input is not a state snapshot".into()))
+		}
+		let version = u32::decode(&mut input)
+			.map_err(|e| Error::Other(format!("Error reading the snapshot version: {e}")))?;
+		if version != SNAPSHOT_VERSION {
+			return Err(Error::Other(format!("Unsupported snapshot version {version}")))
+		}
+		Ok(Self { input, read_chunks: 0 })
+	}
+
+	fn read_chunk<Header: Decode>(&mut self) -> Result<SnapshotChunk<Header>, Error> {
+		let index = self.read_chunks;
+		let read_error =
+			|e: codec::Error| Error::Other(format!("Error reading snapshot chunk #{index}: {e}"));
+
+		let len = <Compact<u32>>::decode(&mut self.input).map_err(read_error)?.0;
+		if len > MAX_CHUNK_SIZE {
+			return Err(Error::Other(format!("Snapshot chunk #{index} is too large: {len} bytes")))
+		}
+		let mut payload = vec![0; len as usize];
+		codec::Input::read(&mut self.input, &mut payload).map_err(read_error)?;
+		let checksum = <[u8; 32]>::decode(&mut self.input).map_err(read_error)?;
+		if checksum != blake2_256(&payload) {
+			return Err(Error::Other(format!("Checksum mismatch of snapshot chunk #{index}")))
+		}
+		self.read_chunks += 1;
+
+		SnapshotChunk::decode(&mut &payload[..]).map_err(read_error)
+	}
+}
+
+/// Export a snapshot of the state of the finalized block `hash`, along with its header and
+/// justifications, to `output`.
+///
+/// The state is split into chunks of about `chunk_size` bytes.
+pub fn export_snapshot<B, C>(
+	client: Arc<C>,
+	hash: B::Hash,
+	output: impl Write,
+	chunk_size: usize,
+) -> Result<(), Error>
+where
+	B: BlockT,
+	C: HeaderBackend<B> + BlockBackend<B> + ProofProvider<B>,
+{
+	let header = client
+		.header(hash)?
+		.ok_or_else(|| Error::Other(format!("Unknown block {hash:?}")))?;
+	let justifications = client.justifications(hash)?;
+	let number = *header.number();
+	let root = *header.state_root();
+
+	// Only a finalized block can be imported without any following blocks.
+	let finalized_number = client.info().finalized_number;
+	if number > finalized_number || client.hash(number)? != Some(hash) {
+		return Err(Error::Other(format!(
+			"Block #{number} ({hash:?}) is not finalized, last finalized block is #{finalized_number}"
+		)))
+	}
+
+	info!("Exporting snapshot of block #{number} ({hash:?})");
+	let mut writer = SnapshotWriter::new(output)?;
+	writer.write_chunk(&SnapshotChunk::Header { header, justifications })?;
+
+	let mut last_key = SmallVec::<[Vec<u8>; 2]>::new();
+	let mut state_chunks = 0u32;
+	loop {
+		let (proof, count) = client.read_proof_collection(hash, &last_key, chunk_size)?;
+		// The proof is verified the same way state sync does, to find where the next chunk
+		// starts.
+		let (values, completed) = client.verify_range_proof(root, proof.clone(), &last_key)?;
+		writer.write_chunk(&SnapshotChunk::<B::Header>::State { proof: proof.encode() })?;
+		state_chunks += 1;
+		log::debug!("Exported snapshot chunk #{state_chunks} with {count} keys");
+
+		if completed == 0 {
+			break
+		}
+		if !values.update_last_key(completed, &mut last_key) {
+			return Err(Error::Other(format!("Error updating the state cursor, depth {completed}")))
+		}
+	}
+
+	writer.write_chunk(&SnapshotChunk::<B::Header>::End { state_chunks })?;
+	writer.finish()?;
+	info!("🎉 Exported snapshot of block #{number} in {state_chunks} state chunks");
+	Ok(())
+}
+
+/// Import a snapshot read from `input`.
+///
+/// The state chunks are verified and collected by [`StateSync`], the resulting state is then
+/// imported through `import_queue` like the target block of state sync.
+pub async fn import_snapshot<B, IQ, C>(
+	client: Arc<C>,
+	mut import_queue: IQ,
+	input: impl Read + Send,
+) -> Result<(), Error>
+where
+	B: BlockT,
+	C: HeaderBackend<B> + ProofProvider<B> + Send + Sync + 'static,
+	IQ: ImportQueue<B> + 'static,
+{
+	let mut reader = SnapshotReader::new(input)?;
+	let (header, justifications) = match reader.read_chunk::<B::Header>()? {
+		SnapshotChunk::Header { header, justifications } => (header, justifications),
+		_ => return Err(Error::Other("The snapshot doesn't start with a header".into())),
+	};
+	let number = *header.number();
+	info!("Importing snapshot of block #{number} ({:?})", header.hash());
+
+	let mut state_sync = StateSync::new(client.clone(), header, None, justifications, false);
+	let mut state_chunks = 0u32;
+	let imported = loop {
+		let proof = match reader.read_chunk::<B::Header>()? {
+			SnapshotChunk::State { proof } => proof,
+			_ => return Err(Error::Other("The snapshot state is incomplete".into())),
+		};
+		state_chunks += 1;
+		match state_sync.import(StateResponse { entries: Vec::new(), proof }) {
+			ImportResult::Import(hash, header, state, body, justifications) =>
+				break IncomingBlock {
+					hash,
+					header: Some(header),
+					body,
+					indexed_body: None,
+					justifications,
+					origin: None,
+					allow_missing_state: true,
+					import_existing: true,
+					skip_execution: true,
+					state: Some(state),
+				},
+			ImportResult::Continue => continue,
+			ImportResult::BadResponse =>
+				return Err(Error::Other(format!(
+					"Snapshot state chunk #{state_chunks} failed verification"
+				))),
+		}
+	};
+	match reader.read_chunk::<B::Header>()? {
+		SnapshotChunk::End { state_chunks: expected } if expected == state_chunks => {},
+		_ => return Err(Error::Other("Invalid snapshot end".into())),
+	}
+
+	let hash = imported.hash;
+	import_queue
+		.service_ref()
+		.import_blocks(BlockOrigin::NetworkInitialSync, vec![imported]);
+
+	let link = WaitLink::<B> { hash, result: Mutex::new(None) };
+	loop {
+		future::poll_fn(|cx| {
+			import_queue.poll_actions(cx, &link);
+			Poll::Ready(())
+		})
+		.await;
+		if let Some(result) = link.result.lock().expect("poisoned lock").take() {
+			result.map_err(|e| Error::Other(format!("Error importing the snapshot block: {e}")))?;
+			break
+		}
+		Delay::new(Duration::from_millis(DELAY_TIME)).await;
+	}
+
+	info!(
+		"🎉 Imported snapshot of block #{number} ({hash:?}). Best: #{}",
+		client.info().best_number
+	);
+	Ok(())
+}
+
+/// Waits for the import of the snapshot block.
+struct WaitLink<B: BlockT> {
+	hash: B::Hash,
+	result: Mutex<Option<Result<(), String>>>,
+}
+
+impl<B: BlockT> Link<B> for WaitLink<B> {
+	fn blocks_processed(
+		&self,
+		_imported: usize,
+		_num_expected_blocks: usize,
+		results: Vec<(Result<BlockImportStatus<NumberFor<B>>, BlockImportError>, B::Hash)>,
+	) {
+		for (result, hash) in results {
+			if hash == self.hash {
+				*self.result.lock().expect("poisoned lock") =
+					Some(result.map(|_| ()).map_err(|e| e.to_string()));
+			}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use sp_runtime::testing::Header;
+
+	fn header() -> Header {
+		Header::new(
+			7,
+			Default::default(),
+			Default::default(),
+			Default::default(),
+			Default::default(),
+		)
+	}
+
+	#[test]
+	fn snapshot_chunks_roundtrip() {
+		let chunks = vec![
+			SnapshotChunk::Header { header: header(), justifications: None },
+			SnapshotChunk::State { proof: vec![1; 100] },
+			SnapshotChunk::End { state_chunks: 1 },
+		];
+		let mut output = Vec::new();
+		let mut writer = SnapshotWriter::new(&mut output).unwrap();
+		for chunk in &chunks {
+			writer.write_chunk(chunk).unwrap();
+		}
+		writer.finish().unwrap();
+
+		let mut reader = SnapshotReader::new(&output[..]).unwrap();
+		for chunk in chunks {
+			assert_eq!(reader.read_chunk::<Header>().unwrap(), chunk);
+		}
+		assert!(reader.read_chunk::<Header>().is_err());
+	}
+
+	#[test]
+	fn corrupted_snapshot_is_rejected() {
+		let mut output = Vec::new();
+		let mut writer = SnapshotWriter::new(&mut output).unwrap();
+		writer
+			.write_chunk(&SnapshotChunk::<Header>::State { proof: vec![1; 100] })
+			.unwrap();
+		writer.finish().unwrap();
+
+		output[50] ^= 1;
+		let mut reader = SnapshotReader::new(&output[..]).unwrap();
+		assert!(reader.read_chunk::<Header>().is_err());
+
+		output[0] ^= 1;
+		assert!(SnapshotReader::new(&output[..]).is_err());
+	}
+}
@@ -23,6 +23,7 @@ use super::{
 	CodeProvider,
 };
 use crate::client::notification_pinning::NotificationPinningWorker;
+use codec::Encode;
 use log::{debug, info, trace, warn};
 use parking_lot::{Mutex, RwLock};
 use prometheus_endpoint::Registry;
@@ -48,6 +49,7 @@ use sc_consensus::{
 };
 use sc_executor::RuntimeVersion;
 use sc_telemetry::{telemetry, TelemetryHandle, SUBSTRATE_INFO};
+use schnellru::{ByLength, LruMap};
 use sp_api::{
 	ApiExt, ApiRef, CallApiAt, CallApiAtParams, ConstructRuntimeApi, Core as CoreApi,
 	ProvideRuntimeApi,
@@ -60,7 +62,10 @@ use sp_consensus::{BlockOrigin, BlockStatus, Error as ConsensusError};
 
 use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedSender};
 use sp_core::{
-	storage::{ChildInfo, ChildType, PrefixedStorageKey, StorageChild, StorageData, StorageKey},
+	storage::{
+		well_known_keys, ChildInfo, ChildType, PrefixedStorageKey, StorageChild, StorageData,
+		StorageKey,
+	},
 	traits::{CallContext, SpawnNamed},
 };
 use sp_runtime::{
@@ -74,11 +79,12 @@ use sp_runtime::{
 use sp_state_machine::{
 	prove_child_read, prove_range_read_with_child_with_size, prove_read,
 	read_range_proof_check_with_child_on_proving_backend, Backend as StateBackend,
-	ChildStorageCollection, KeyValueStates, KeyValueStorageLevel, StorageCollection,
-	MAX_NESTED_TRIE_DEPTH,
+	BackendTransaction, ChildStorageCollection, KeyValueStates, KeyValueStorageLevel,
+	OverlayedChanges, StorageCollection, MAX_NESTED_TRIE_DEPTH,
 };
 use sp_trie::{proof_size_extension::ProofSizeExt, CompactProof, MerkleValue, StorageProof};
 use std::{
+	cell::RefCell,
 	collections::{HashMap, HashSet},
 	marker::PhantomData,
 	path::PathBuf,
@@ -114,9 +120,22 @@ where
 	telemetry: Option<TelemetryHandle>,
 	unpin_worker_sender: TracingUnboundedSender<UnpinWorkerMessage<Block>>,
 	code_provider: CodeProvider<Block, B, E>,
+	/// The states rebuilt on top of a state snapshot, see [`Client::state_at`].
+	replayed_states: Mutex<LruMap<Block::Hash, ReplayedState<Block>>>,
 	_phantom: PhantomData<RA>,
 }
 
+/// Number of states rebuilt on top of a state snapshot kept in memory.
+const REPLAYED_STATES_CACHE_SIZE: u32 = 8;
+
+/// A state rebuilt by re-executing blocks on top of a state snapshot.
+#[derive(Clone)]
+struct ReplayedState<Block: BlockT> {
+	snapshot: Block::Hash,
+	changes: BackendTransaction<HashingFor<Block>>,
+	root: Block::Hash,
+}
+
 /// Used in importing a block, where additional changes are made after the runtime
 /// executed.
 enum PrePostHeader<H> {
@@ -166,6 +185,9 @@ pub struct ClientConfig<Block: BlockT> {
 	pub wasm_runtime_substitutes: HashMap<NumberFor<Block>, Vec<u8>>,
 	/// Enable recording of storage proofs during block import
 	pub enable_import_proof_recording: bool,
+	/// Maximum number of blocks re-executed on top of a state snapshot to rebuild the state of a
+	/// pruned block. Pruned state is not rebuilt if `None`.
+	pub state_replay_max_blocks: Option<u32>,
 }
 
 impl<Block: BlockT> Default for ClientConfig<Block> {
@@ -177,6 +199,7 @@ impl<Block: BlockT> Default for ClientConfig<Block> {
 			no_genesis: false,
 			wasm_runtime_substitutes: HashMap::new(),
 			enable_import_proof_recording: false,
+			state_replay_max_blocks: None,
 		}
 	}
 }
@@ -421,6 +444,7 @@ where
 			telemetry,
 			unpin_worker_sender,
 			code_provider,
+			replayed_states: Mutex::new(LruMap::new(ByLength::new(REPLAYED_STATES_CACHE_SIZE))),
 			_phantom: Default::default(),
 		})
 	}
@@ -438,8 +462,108 @@ where
 	}
 
 	/// Get a reference to the state at a given block.
+	///
+	/// If the state was pruned, but the backend keeps a state snapshot at most
+	/// [`ClientConfig::state_replay_max_blocks`] below the block, the state is rebuilt by
+	/// re-executing the blocks following the snapshot.
 	pub fn state_at(&self, hash: Block::Hash) -> sp_blockchain::Result<B::State> {
-		self.backend.state_at(hash, TrieCacheContext::Untrusted)
+		match self.backend.state_at(hash, TrieCacheContext::Untrusted) {
+			Err(err) => self.state_from_snapshot(hash)?.ok_or(err),
+			state => state,
+		}
+	}
+
+	/// Rebuild the pruned state of `hash` on top of the closest state snapshot below it.
+	///
+	/// Returns `None` if rebuilding pruned state is disabled, or if there is no snapshot `hash`
+	/// descends from within [`ClientConfig::state_replay_max_blocks`].
+	fn state_from_snapshot(&self, hash: Block::Hash) -> sp_blockchain::Result<Option<B::State>> {
+		let Some(max_blocks) = self.config.state_replay_max_blocks else { return Ok(None) };
+		let cached = self.replayed_states.lock().get(&hash).cloned();
+		if let Some(replayed) = cached {
+			return self
+				.backend
+				.state_with_changes(replayed.snapshot, replayed.changes, replayed.root)
+				.map(Some)
+		}
+
+		let Some(number) = self.backend.blockchain().number(hash)? else { return Ok(None) };
+		let Some((snapshot_number, snapshot_hash)) = self.backend.state_snapshot(number) else {
+			return Ok(None)
+		};
+		if number - snapshot_number > max_blocks.into() {
+			debug!(
+				target: "db",
+				"Not rebuilding state of #{number} ({hash:?}), the closest snapshot at \
+				 #{snapshot_number} is more than {max_blocks} blocks below",
+			);
+			return Ok(None)
+		}
+
+		let mut blocks = Vec::new();
+		let mut current = hash;
+		while current != snapshot_hash {
+			let header = self.backend.blockchain().expect_header(current)?;
+			if *header.number() <= snapshot_number {
+				return Ok(None)
+			}
+			let body = self.backend.blockchain().body(current)?.ok_or_else(|| {
+				Error::UnknownBlock(format!(
+					"Missing body of {current:?}, required to rebuild the state of {hash:?}"
+				))
+			})?;
+			current = *header.parent_hash();
+			blocks.push(Block::new(header, body));
+		}
+		if blocks.is_empty() {
+			return Ok(None)
+		}
+
+		debug!(
+			target: "db",
+			"Rebuilding state of #{number} ({hash:?}) from snapshot at #{snapshot_number}",
+		);
+
+		let state_version = self.runtime_version_at(snapshot_hash)?.state_version();
+		let changes = RefCell::new(OverlayedChanges::default());
+		let extensions = RefCell::new(
+			self.executor.execution_extensions().extensions(snapshot_hash, snapshot_number),
+		);
+		for block in blocks.into_iter().rev() {
+			// Blocks are executed with the runtime of the snapshot.
+			if changes.borrow_mut().storage(well_known_keys::CODE).is_some() {
+				return Err(Error::Backend(format!(
+					"Runtime upgraded after snapshot at #{snapshot_number}, can not rebuild the \
+					 state of {hash:?}",
+				)))
+			}
+			self.executor.contextual_call(
+				snapshot_hash,
+				"Core_execute_block",
+				&block.encode(),
+				&changes,
+				&None,
+				CallContext::Onchain,
+				&extensions,
+			)?;
+		}
+
+		let snapshot_state = self.backend.state_at(snapshot_hash, TrieCacheContext::Untrusted)?;
+		let storage_changes = changes
+			.into_inner()
+			.drain_storage_changes(&snapshot_state, state_version)
+			.map_err(Error::Storage)?;
+		let root = storage_changes.transaction_storage_root;
+		if root != *self.backend.blockchain().expect_header(hash)?.state_root() {
+			return Err(Error::InvalidStateRoot)
+		}
+
+		let replayed =
+			ReplayedState { snapshot: snapshot_hash, changes: storage_changes.transaction, root };
+		self.replayed_states.lock().insert(hash, replayed.clone());
+		self.backend
+			.state_with_changes(replayed.snapshot, replayed.changes, replayed.root)
+			.map(Some)
 	}
 
 	/// Get the code at a given block.
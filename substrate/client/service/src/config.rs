@@ -76,6 +76,9 @@ pub struct Configuration {
 	///
 	/// NOTE: only finalized blocks are subject for removal!
 	pub blocks_pruning: BlocksPruning,
+	/// Maximum number of blocks re-executed on top of a state snapshot to rebuild the state of a
+	/// pruned block. Pruned state is not rebuilt if `None`.
+	pub state_replay_max_blocks: Option<u32>,
 	/// Chain configuration.
 	pub chain_spec: Box<dyn ChainSpec>,
 	/// Runtime executor configuration.
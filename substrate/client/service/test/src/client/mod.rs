@@ -53,6 +53,7 @@ use substrate_test_runtime_client::{
 };
 
 mod db;
+mod snapshot;
 
 const TEST_ENGINE_ID: ConsensusEngineId = *b"TEST";
 
@@ -1911,6 +1912,104 @@ fn returns_status_for_pruned_blocks() {
 	);
 }
 
+#[test]
+fn pruned_state_is_rebuilt_from_snapshot() {
+	sp_tracing::try_init_simple();
+	let tmp = tempfile::tempdir().unwrap();
+
+	// keep the state of the last block and of every fourth block
+	let backend = Arc::new(
+		Backend::new(
+			DatabaseSettings {
+				trie_cache_maximum_size: Some(1 << 20),
+				state_pruning: Some(PruningMode::hybrid(1, std::num::NonZeroU32::new(4).unwrap())),
+				blocks_pruning: BlocksPruning::KeepFinalized,
+				source: DatabaseSource::RocksDb { path: tmp.path().into(), cache_size: 1024 },
+				metrics_registry: None,
+			},
+			u64::MAX,
+		)
+		.unwrap(),
+	);
+
+	let client = TestClientBuilder::with_backend(backend.clone()).enable_state_replay(2).build();
+
+	let mut hashes = vec![client.chain_info().genesis_hash];
+	let mut pairs = Vec::new();
+	for nonce in 0..5 {
+		let mut builder = BlockBuilderBuilder::new(&client)
+			.on_parent_block(*hashes.last().unwrap())
+			.with_parent_block_number(nonce)
+			.build()
+			.unwrap();
+		builder
+			.push_transfer(Transfer {
+				from: Sr25519Keyring::Alice.into(),
+				to: Sr25519Keyring::Ferdie.into(),
+				amount: 1 * DOLLARS,
+				nonce,
+			})
+			.unwrap();
+		let block = builder.build().unwrap().block;
+		block_on(client.import_as_final(BlockOrigin::Own, block.clone())).unwrap();
+
+		let hash = block.hash();
+		pairs.push(client.storage_pairs(hash, None, None).unwrap().collect::<Vec<_>>());
+		hashes.push(hash);
+	}
+
+	// blocks 1 to 3 are pruned, block 0 is the closest snapshot
+	for hash in &hashes[1..4] {
+		assert!(sc_client_api::Backend::state_at(
+			&*backend,
+			*hash,
+			sc_client_api::TrieCacheContext::Untrusted
+		)
+		.is_err());
+	}
+	assert_eq!(client.storage_pairs(hashes[2], None, None).unwrap().collect::<Vec<_>>(), pairs[1]);
+	// the rebuilt state is cached
+	assert_eq!(client.storage_pairs(hashes[2], None, None).unwrap().collect::<Vec<_>>(), pairs[1]);
+	// block 3 is further away from the snapshot than the 2 blocks allowed to re-execute
+	assert!(client.storage_pairs(hashes[3], None, None).is_err());
+}
+
+#[test]
+fn pruned_state_is_not_rebuilt_by_default() {
+	let tmp = tempfile::tempdir().unwrap();
+	let backend = Arc::new(
+		Backend::new(
+			DatabaseSettings {
+				trie_cache_maximum_size: Some(1 << 20),
+				state_pruning: Some(PruningMode::hybrid(1, std::num::NonZeroU32::new(4).unwrap())),
+				blocks_pruning: BlocksPruning::KeepFinalized,
+				source: DatabaseSource::RocksDb { path: tmp.path().into(), cache_size: 1024 },
+				metrics_registry: None,
+			},
+			u64::MAX,
+		)
+		.unwrap(),
+	);
+	let client = TestClientBuilder::with_backend(backend).build();
+
+	let mut hashes = vec![client.chain_info().genesis_hash];
+	for _ in 0..3 {
+		let block = BlockBuilderBuilder::new(&client)
+			.on_parent_block(*hashes.last().unwrap())
+			.fetch_parent_block_number(&client)
+			.unwrap()
+			.build()
+			.unwrap()
+			.build()
+			.unwrap()
+			.block;
+		hashes.push(block.hash());
+		block_on(client.import_as_final(BlockOrigin::Own, block)).unwrap();
+	}
+
+	assert!(client.storage_pairs(hashes[1], None, None).is_err());
+}
+
 #[test]
 fn storage_keys_prefix_and_start_key_works() {
 	let child_info = ChildInfo::new_default(b"child");
@@ -0,0 +1,112 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+use futures::executor::block_on;
+use sc_block_builder::BlockBuilderBuilder;
+use sc_client_api::{HeaderBackend, StorageProvider};
+use sc_consensus::{BasicQueue, BlockImportParams, ForkChoiceStrategy, Verifier};
+use sc_service::chain_ops::{export_snapshot, import_snapshot};
+use sp_consensus::BlockOrigin;
+use sp_core::testing::TaskExecutor;
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+use sp_state_machine::backend::Backend as _;
+use std::sync::Arc;
+use substrate_test_runtime_client::{
+	runtime::{currency::DOLLARS, Block, Transfer},
+	BlockBuilderExt, ClientBlockImportExt, DefaultTestClientBuilderExt, Sr25519Keyring,
+	TestClientBuilder, TestClientBuilderExt,
+};
+
+/// Accepts the snapshot block as finalized.
+struct FinalizedVerifier;
+
+#[async_trait::async_trait]
+impl Verifier<Block> for FinalizedVerifier {
+	async fn verify(
+		&self,
+		mut block: BlockImportParams<Block>,
+	) -> Result<BlockImportParams<Block>, String> {
+		block.fork_choice = Some(ForkChoiceStrategy::LongestChain);
+		block.finalized = true;
+		Ok(block)
+	}
+}
+
+#[test]
+fn snapshot_export_import_roundtrip() {
+	sp_tracing::try_init_simple();
+	let client = Arc::new(TestClientBuilder::new().build());
+
+	let mut parent = client.chain_info().genesis_hash;
+	for nonce in 0..3 {
+		let mut builder = BlockBuilderBuilder::new(&*client)
+			.on_parent_block(parent)
+			.with_parent_block_number(nonce)
+			.build()
+			.unwrap();
+		builder
+			.push_transfer(Transfer {
+				from: Sr25519Keyring::Alice.into(),
+				to: Sr25519Keyring::Ferdie.into(),
+				amount: 1 * DOLLARS,
+				nonce,
+			})
+			.unwrap();
+		let block = builder.build().unwrap().block;
+		parent = block.hash();
+		block_on(client.import_as_final(BlockOrigin::Own, block)).unwrap();
+	}
+	let header = client.header(parent).unwrap().unwrap();
+
+	// a block that is not finalized can not be exported
+	let unfinalized = BlockBuilderBuilder::new(&*client)
+		.on_parent_block(parent)
+		.with_parent_block_number(3)
+		.build()
+		.unwrap()
+		.build()
+		.unwrap()
+		.block;
+	block_on(client.import(BlockOrigin::Own, unfinalized.clone())).unwrap();
+	assert!(export_snapshot(client.clone(), unfinalized.hash(), Vec::new(), 1024).is_err());
+
+	// small chunks to split the state into several chunks
+	let mut snapshot = Vec::new();
+	export_snapshot(client.clone(), parent, &mut snapshot, 1024).unwrap();
+
+	let imported = Arc::new(TestClientBuilder::new().build());
+	let import_queue = BasicQueue::new(
+		FinalizedVerifier,
+		Box::new(imported.clone()),
+		None,
+		&TaskExecutor::new(),
+		None,
+	);
+	block_on(import_snapshot(imported.clone(), import_queue, &snapshot[..])).unwrap();
+
+	assert_eq!(imported.info().finalized_hash, parent);
+	let imported_header = imported.header(parent).unwrap().unwrap();
+	assert_eq!(imported_header, header);
+	let state_version = imported.runtime_version_at(parent).unwrap().state_version();
+	let state = imported.state_at(parent).unwrap();
+	assert_eq!(state.storage_root(std::iter::empty(), state_version).0, *header.state_root());
+	assert_eq!(
+		imported.storage_pairs(parent, None, None).unwrap().collect::<Vec<_>>(),
+		client.storage_pairs(parent, None, None).unwrap().collect::<Vec<_>>(),
+	);
+}
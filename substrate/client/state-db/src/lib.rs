@@ -46,7 +46,7 @@ mod pruning;
 #[cfg(test)]
 mod test;
 
-use codec::Codec;
+use codec::{Codec, Decode, Encode};
 use log::trace;
 use noncanonical::NonCanonicalOverlay;
 use parking_lot::RwLock;
@@ -54,6 +54,7 @@ use pruning::{HaveBlock, RefWindow};
 use std::{
 	collections::{hash_map::Entry, HashMap},
 	fmt,
+	num::NonZeroU32,
 };
 
 const LOG_TARGET: &str = "state-db";
@@ -62,7 +63,14 @@ const PRUNING_MODE: &[u8] = b"mode";
 const PRUNING_MODE_ARCHIVE: &[u8] = b"archive";
 const PRUNING_MODE_ARCHIVE_CANON: &[u8] = b"archive_canonical";
 const PRUNING_MODE_CONSTRAINED: &[u8] = b"constrained";
+const PRUNING_MODE_HYBRID: &[u8] = b"hybrid";
+const PRUNING_SNAPSHOT_INTERVAL: &[u8] = b"snapshot_interval";
 pub(crate) const DEFAULT_MAX_BLOCK_CONSTRAINT: u32 = 256;
+/// Default number of blocks between two state snapshots kept by [`PruningMode::Hybrid`].
+pub const DEFAULT_SNAPSHOT_INTERVAL: NonZeroU32 = match NonZeroU32::new(14_400) {
+	Some(interval) => interval,
+	None => panic!("the default snapshot interval is not zero"),
+};
 
 /// Database value type.
 pub type DBValue = Vec<u8>;
@@ -235,6 +243,17 @@ pub enum PruningMode {
 	ArchiveAll,
 	/// Canonicalization discards non-canonical nodes. All the canonical nodes are kept in the DB.
 	ArchiveCanonical,
+	/// Maintain a pruning window and additionally keep the full state of every canonical block
+	/// whose number is a multiple of `snapshot_interval`.
+	///
+	/// The state of a pruned block may be recovered by re-executing the blocks on top of the
+	/// nearest snapshot, see [`StateDb::nearest_snapshot`].
+	Hybrid {
+		/// The pruning window constraints.
+		constraints: Constraints,
+		/// Number of blocks between two state snapshots.
+		snapshot_interval: NonZeroU32,
+	},
 }
 
 impl PruningMode {
@@ -243,11 +262,17 @@ impl PruningMode {
 		PruningMode::Constrained(Constraints { max_blocks: Some(n) })
 	}
 
+	/// Create a mode that keeps given number of blocks and the state of every
+	/// `snapshot_interval`-th block.
+	pub fn hybrid(n: u32, snapshot_interval: NonZeroU32) -> PruningMode {
+		PruningMode::Hybrid { constraints: Constraints { max_blocks: Some(n) }, snapshot_interval }
+	}
+
 	/// Is this an archive (either ArchiveAll or ArchiveCanonical) pruning mode?
 	pub fn is_archive(&self) -> bool {
 		match *self {
 			PruningMode::ArchiveAll | PruningMode::ArchiveCanonical => true,
-			PruningMode::Constrained(_) => false,
+			PruningMode::Constrained(_) | PruningMode::Hybrid { .. } => false,
 		}
 	}
 
@@ -257,14 +282,23 @@ impl PruningMode {
 			PruningMode::ArchiveAll => PRUNING_MODE_ARCHIVE,
 			PruningMode::ArchiveCanonical => PRUNING_MODE_ARCHIVE_CANON,
 			PruningMode::Constrained(_) => PRUNING_MODE_CONSTRAINED,
+			PruningMode::Hybrid { .. } => PRUNING_MODE_HYBRID,
 		}
 	}
 
+	/// Create a mode from its [`id`](Self::id).
+	///
+	/// The snapshot interval of [`PruningMode::Hybrid`] is not part of the id and is set to
+	/// [`DEFAULT_SNAPSHOT_INTERVAL`]. [`StateDb`] stores the interval in a separate meta entry.
 	pub fn from_id(id: &[u8]) -> Option<Self> {
 		match id {
 			PRUNING_MODE_ARCHIVE => Some(Self::ArchiveAll),
 			PRUNING_MODE_ARCHIVE_CANON => Some(Self::ArchiveCanonical),
 			PRUNING_MODE_CONSTRAINED => Some(Self::Constrained(Default::default())),
+			PRUNING_MODE_HYBRID => Some(Self::Hybrid {
+				constraints: Default::default(),
+				snapshot_interval: DEFAULT_SNAPSHOT_INTERVAL,
+			}),
 			_ => None,
 		}
 	}
@@ -318,7 +352,14 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 		let non_canonical: NonCanonicalOverlay<BlockHash, Key> = NonCanonicalOverlay::new(&db)?;
 		let pruning: Option<RefWindow<BlockHash, Key, D>> = match mode {
 			PruningMode::Constrained(Constraints { max_blocks }) =>
-				Some(RefWindow::new(db, max_blocks.unwrap_or(0), ref_counting)?),
+				Some(RefWindow::new(db, max_blocks.unwrap_or(0), ref_counting, None)?),
+			PruningMode::Hybrid { constraints: Constraints { max_blocks }, snapshot_interval } =>
+				Some(RefWindow::new(
+					db,
+					max_blocks.unwrap_or(0),
+					ref_counting,
+					Some(snapshot_interval),
+				)?),
 			PruningMode::ArchiveAll | PruningMode::ArchiveCanonical => None,
 		};
 
@@ -338,7 +379,9 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 				// write changes immediately
 				Ok(CommitSet { data: changeset, meta: Default::default() })
 			},
-			PruningMode::Constrained(_) | PruningMode::ArchiveCanonical => self
+			PruningMode::Constrained(_) |
+			PruningMode::Hybrid { .. } |
+			PruningMode::ArchiveCanonical => self
 				.non_canonical
 				.insert(hash, number, parent_hash, changeset)
 				.map_err(Into::into),
@@ -380,7 +423,9 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 	fn is_pruned(&self, hash: &BlockHash, number: u64) -> IsPruned {
 		match self.mode {
 			PruningMode::ArchiveAll => IsPruned::NotPruned,
-			PruningMode::ArchiveCanonical | PruningMode::Constrained(_) => {
+			PruningMode::ArchiveCanonical |
+			PruningMode::Constrained(_) |
+			PruningMode::Hybrid { .. } => {
 				if self
 					.non_canonical
 					.last_canonicalized_block_number()
@@ -408,8 +453,10 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 	}
 
 	fn prune(&mut self, commit: &mut CommitSet<Key>) -> Result<(), Error<D::Error>> {
-		if let (&mut Some(ref mut pruning), PruningMode::Constrained(constraints)) =
-			(&mut self.pruning, &self.mode)
+		if let (
+			&mut Some(ref mut pruning),
+			PruningMode::Constrained(constraints) | PruningMode::Hybrid { constraints, .. },
+		) = (&mut self.pruning, &self.mode)
 		{
 			loop {
 				if pruning.window_size() <= constraints.max_blocks.unwrap_or(0) as u64 {
@@ -442,16 +489,18 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 	fn revert_one(&mut self) -> Option<CommitSet<Key>> {
 		match self.mode {
 			PruningMode::ArchiveAll => Some(CommitSet::default()),
-			PruningMode::ArchiveCanonical | PruningMode::Constrained(_) =>
-				self.non_canonical.revert_one(),
+			PruningMode::ArchiveCanonical |
+			PruningMode::Constrained(_) |
+			PruningMode::Hybrid { .. } => self.non_canonical.revert_one(),
 		}
 	}
 
 	fn remove(&mut self, hash: &BlockHash) -> Option<CommitSet<Key>> {
 		match self.mode {
 			PruningMode::ArchiveAll => Some(CommitSet::default()),
-			PruningMode::ArchiveCanonical | PruningMode::Constrained(_) =>
-				self.non_canonical.remove(hash),
+			PruningMode::ArchiveCanonical |
+			PruningMode::Constrained(_) |
+			PruningMode::Hybrid { .. } => self.non_canonical.remove(hash),
 		}
 	}
 
@@ -461,7 +510,9 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 	{
 		match self.mode {
 			PruningMode::ArchiveAll => Ok(()),
-			PruningMode::ArchiveCanonical | PruningMode::Constrained(_) => {
+			PruningMode::ArchiveCanonical |
+			PruningMode::Constrained(_) |
+			PruningMode::Hybrid { .. } => {
 				let have_block = self.non_canonical.have_block(hash) ||
 					self.pruning.as_ref().map_or_else(
 						|| hint(),
@@ -506,6 +557,10 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDbSync<BlockHash, Key, D> {
 		self.non_canonical.sync();
 	}
 
+	fn nearest_snapshot(&self, number: u64) -> Option<(u64, BlockHash)> {
+		self.pruning.as_ref().and_then(|pruning| pruning.nearest_snapshot(number))
+	}
+
 	pub fn get<DB: NodeDb, Q: ?Sized>(
 		&self,
 		key: &Q,
@@ -538,6 +593,10 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDb<BlockHash, Key, D> {
 		should_init: bool,
 	) -> Result<(CommitSet<Key>, StateDb<BlockHash, Key, D>), Error<D::Error>> {
 		let stored_mode = fetch_stored_pruning_mode(&db)?;
+		let stored_interval = match stored_mode {
+			Some(PruningMode::Hybrid { snapshot_interval, .. }) => Some(snapshot_interval),
+			_ => None,
+		};
 
 		let selected_mode = match (should_init, stored_mode, requested_mode) {
 			(true, stored_mode, requested_mode) => {
@@ -556,18 +615,22 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDb<BlockHash, Key, D> {
 			(false, Some(stored), Some(requested)) => choose_pruning_mode(stored, requested)?,
 		};
 
-		let db_init_commit_set = if should_init {
-			let mut cs: CommitSet<Key> = Default::default();
-
+		let mut db_init_commit_set: CommitSet<Key> = Default::default();
+		if should_init {
 			let key = to_meta_key(PRUNING_MODE, &());
 			let value = selected_mode.id().to_owned();
 
-			cs.meta.inserted.push((key, value));
+			db_init_commit_set.meta.inserted.push((key, value));
+		}
 
-			cs
-		} else {
-			Default::default()
-		};
+		// The snapshot interval is stored next to the mode, so that restarting without
+		// specifying it keeps using the same interval.
+		if let PruningMode::Hybrid { snapshot_interval, .. } = selected_mode {
+			if stored_interval != Some(snapshot_interval) {
+				let key = to_meta_key(PRUNING_SNAPSHOT_INTERVAL, &());
+				db_init_commit_set.meta.inserted.push((key, snapshot_interval.get().encode()));
+			}
+		}
 
 		let state_db =
 			StateDb { db: RwLock::new(StateDbSync::new(selected_mode, ref_counting, db)?) };
@@ -652,6 +715,14 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> StateDb<BlockHash, Key, D> {
 		self.db.read().is_pruned(hash, number)
 	}
 
+	/// Returns the number and hash of the latest block at or before `number` whose full state is
+	/// kept by [`PruningMode::Hybrid`], if any.
+	///
+	/// The state of a pruned block can be recovered by re-executing the blocks on top of it.
+	pub fn nearest_snapshot(&self, number: u64) -> Option<(u64, BlockHash)> {
+		self.db.read().nearest_snapshot(number)
+	}
+
 	/// Reset in-memory changes to the last disk-backed state.
 	pub fn reset(&self, db: D) -> Result<(), Error<D::Error>> {
 		let mut state_db = self.db.write();
@@ -675,7 +746,13 @@ fn fetch_stored_pruning_mode<D: MetaDb>(db: &D) -> Result<Option<PruningMode>, E
 	let meta_key_mode = to_meta_key(PRUNING_MODE, &());
 	if let Some(stored_mode) = db.get_meta(&meta_key_mode).map_err(Error::Db)? {
 		if let Some(mode) = PruningMode::from_id(&stored_mode) {
-			Ok(Some(mode))
+			match mode {
+				PruningMode::Hybrid { constraints, .. } => Ok(Some(PruningMode::Hybrid {
+					constraints,
+					snapshot_interval: fetch_stored_snapshot_interval(db)?,
+				})),
+				mode => Ok(Some(mode)),
+			}
 		} else {
 			Err(StateDbError::Metadata(format!(
 				"Invalid value stored for PRUNING_MODE: {:02x?}",
@@ -688,6 +765,24 @@ fn fetch_stored_pruning_mode<D: MetaDb>(db: &D) -> Result<Option<PruningMode>, E
 	}
 }
 
+fn fetch_stored_snapshot_interval<D: MetaDb>(db: &D) -> Result<NonZeroU32, Error<D::Error>> {
+	let meta_key = to_meta_key(PRUNING_SNAPSHOT_INTERVAL, &());
+	let stored = db.get_meta(&meta_key).map_err(Error::Db)?.ok_or_else(|| {
+		StateDbError::Metadata(
+			"A hybrid pruning StateDb does not have a snapshot interval stored in its meta-data"
+				.into(),
+		)
+	})?;
+
+	u32::decode(&mut &stored[..]).ok().and_then(NonZeroU32::new).ok_or_else(|| {
+		StateDbError::Metadata(format!(
+			"Invalid value stored for the snapshot interval: {:02x?}",
+			stored
+		))
+		.into()
+	})
+}
+
 fn choose_pruning_mode(
 	stored: PruningMode,
 	requested: PruningMode,
@@ -698,6 +793,7 @@ fn choose_pruning_mode(
 			Ok(PruningMode::ArchiveCanonical),
 		(PruningMode::Constrained(_), PruningMode::Constrained(requested)) =>
 			Ok(PruningMode::Constrained(requested)),
+		(PruningMode::Hybrid { .. }, requested @ PruningMode::Hybrid { .. }) => Ok(requested),
 		(stored, requested) => Err(StateDbError::IncompatiblePruningModes { requested, stored }),
 	}
 }
@@ -706,9 +802,11 @@ fn choose_pruning_mode(
 mod tests {
 	use crate::{
 		test::{make_changeset, make_db, TestDb},
-		Constraints, Error, IsPruned, PruningMode, StateDb, StateDbError,
+		to_meta_key, CommitSet, Constraints, Error, IsPruned, PruningMode, StateDb, StateDbError,
+		PRUNING_MODE, PRUNING_MODE_HYBRID,
 	};
 	use sp_core::H256;
+	use std::num::NonZeroU32;
 
 	fn make_test_db(settings: PruningMode) -> (TestDb, StateDb<H256, H256, TestDb>) {
 		let mut db = make_db(&[91, 921, 922, 93, 94]);
@@ -847,6 +945,18 @@ mod tests {
 		assert!(db.data_eq(&make_db(&[1, 21, 3, 921, 922, 93, 94])));
 	}
 
+	#[test]
+	fn hybrid_keeps_snapshots() {
+		let (db, sdb) = make_test_db(PruningMode::hybrid(0, NonZeroU32::new(2).unwrap()));
+		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(1), 1), IsPruned::Pruned);
+		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(21), 2), IsPruned::NotPruned);
+		assert_eq!(sdb.is_pruned(&H256::from_low_u64_be(22), 2), IsPruned::Pruned);
+		assert_eq!(sdb.nearest_snapshot(1), None);
+		assert_eq!(sdb.nearest_snapshot(3), Some((2, H256::from_low_u64_be(21))));
+		// 93 was deleted by block 3 but belongs to the snapshot of block 2.
+		assert!(db.data_eq(&make_db(&[21, 3, 922, 93, 94])));
+	}
+
 	#[test]
 	fn detects_incompatible_mode() {
 		let mut db = make_db(&[]);
@@ -932,6 +1042,26 @@ mod tests {
 				Ok(PruningMode::ArchiveAll),
 			),
 			(Some(PruningMode::ArchiveAll), Some(PruningMode::ArchiveCanonical), Err(())),
+			(
+				Some(PruningMode::hybrid(256, NonZeroU32::new(100).unwrap())),
+				Some(PruningMode::hybrid(128, NonZeroU32::new(10).unwrap())),
+				Ok(PruningMode::hybrid(128, NonZeroU32::new(10).unwrap())),
+			),
+			(
+				Some(PruningMode::hybrid(256, NonZeroU32::new(100).unwrap())),
+				None,
+				Ok(PruningMode::hybrid(256, NonZeroU32::new(100).unwrap())),
+			),
+			(
+				Some(PruningMode::hybrid(256, NonZeroU32::new(100).unwrap())),
+				Some(PruningMode::blocks_pruning(256)),
+				Err(()),
+			),
+			(
+				Some(PruningMode::blocks_pruning(256)),
+				Some(PruningMode::hybrid(256, NonZeroU32::new(100).unwrap())),
+				Err(()),
+			),
 			(Some(PruningMode::ArchiveCanonical), None, Ok(PruningMode::ArchiveCanonical)),
 			(Some(PruningMode::ArchiveCanonical), Some(PruningMode::blocks_pruning(256)), Err(())),
 			(Some(PruningMode::ArchiveCanonical), Some(PruningMode::blocks_pruning(128)), Err(())),
@@ -946,4 +1076,46 @@ mod tests {
 			check_stored_and_requested_mode_compatibility(created, reopened, expected);
 		}
 	}
+
+	#[test]
+	fn changed_snapshot_interval_is_persisted() {
+		let mut db = make_db(&[]);
+		let (commit, _) = StateDb::<H256, H256, TestDb>::open(
+			db.clone(),
+			Some(PruningMode::hybrid(256, NonZeroU32::new(100).unwrap())),
+			false,
+			true,
+		)
+		.unwrap();
+		db.commit(&commit);
+
+		let mut reopen = |requested| {
+			let (commit, state_db) =
+				StateDb::<H256, H256, TestDb>::open(db.clone(), requested, false, false).unwrap();
+			db.commit(&commit);
+			state_db.pruning_mode()
+		};
+
+		assert_eq!(
+			reopen(Some(PruningMode::hybrid(256, NonZeroU32::new(10).unwrap()))),
+			PruningMode::hybrid(256, NonZeroU32::new(10).unwrap()),
+		);
+		assert_eq!(reopen(None), PruningMode::hybrid(256, NonZeroU32::new(10).unwrap()));
+	}
+
+	#[test]
+	fn missing_snapshot_interval_is_an_error() {
+		let mut db = make_db(&[]);
+		let mut commit = CommitSet::default();
+		commit
+			.meta
+			.inserted
+			.push((to_meta_key(PRUNING_MODE, &()), PRUNING_MODE_HYBRID.to_vec()));
+		db.commit(&commit);
+
+		assert!(matches!(
+			StateDb::<H256, H256, TestDb>::open(db, None, false, false),
+			Err(Error::StateDb(StateDbError::Metadata(_)))
+		));
+	}
 }
@@ -23,6 +23,17 @@
 //! If a node is re-inserted into the window it gets removed from
 //! the death list.
 //! The changes are journaled in the DB.
+//!
+//! In hybrid mode the full state of every block whose number is a multiple of the snapshot
+//! interval is kept as well. When a block is canonicalized, the nodes it deletes that belong to
+//! the latest snapshot are left out of its death row, so they are never pruned. To tell them apart
+//! from the nodes inserted after the snapshot, the references inserted since the latest snapshot
+//! are counted for every node. The counts are checkpointed regularly and the journal records
+//! following the checkpoint are retained, to restore them on restart.
+//!
+//! Databases that don't count references can't tell a node kept by a snapshot from the same node
+//! re-inserted later, so the kept nodes are marked in the meta-data. The marks are part of the
+//! snapshots and, like them, are never removed.
 
 use crate::{
 	noncanonical::LAST_CANONICAL, to_meta_key, CommitSet, Error, Hash, MetaDb, StateDbError,
@@ -30,10 +41,18 @@ use crate::{
 };
 use codec::{Decode, Encode};
 use log::trace;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::{
+	collections::{HashMap, HashSet, VecDeque},
+	num::NonZeroU32,
+};
 
 pub(crate) const LAST_PRUNED: &[u8] = b"last_pruned";
 const PRUNING_JOURNAL: &[u8] = b"pruning_journal";
+const PRUNING_SNAPSHOTS: &[u8] = b"pruning_snapshots";
+const PRUNING_SNAPSHOT_NODE: &[u8] = b"pruning_snapshot_node";
+const PRUNING_SNAPSHOT_INSERTIONS: &[u8] = b"pruning_snapshot_insertions";
+/// Number of blocks between two checkpoints of the references inserted since the latest snapshot.
+const SNAPSHOT_CHECKPOINT_INTERVAL: u64 = DEFAULT_MAX_BLOCK_CONSTRAINT as u64;
 
 /// See module documentation.
 pub struct RefWindow<BlockHash: Hash, Key: Hash, D: MetaDb> {
@@ -42,6 +61,31 @@ pub struct RefWindow<BlockHash: Hash, Key: Hash, D: MetaDb> {
 	queue: DeathRowQueue<BlockHash, Key, D>,
 	/// Block number that is next to be pruned.
 	base: u64,
+	/// The state snapshots, only maintained in hybrid mode.
+	snapshots: Option<Snapshots<BlockHash, Key, D>>,
+}
+
+/// The full state snapshots kept in hybrid mode.
+struct Snapshots<BlockHash: Hash, Key: Hash, D: MetaDb> {
+	/// A snapshot is taken of every block whose number is a multiple of the interval.
+	interval: u64,
+	/// The snapshots taken so far, ordered by block number.
+	blocks: Vec<(u64, BlockHash)>,
+	/// The number of references to each node inserted since the latest snapshot and not deleted
+	/// yet.
+	insertions: HashMap<Key, u32>,
+	/// The block at which `insertions` were last checkpointed. The journal records of the pruned
+	/// blocks following it are retained.
+	checkpoint: Option<u64>,
+	/// The database used to look up the nodes kept by a snapshot. Only set when the database
+	/// doesn't count references, as a node re-inserted after being kept must not be deleted
+	/// then.
+	db: Option<D>,
+	/// The nodes marked as kept since the previous checkpoint, whose marks may not have been
+	/// written to the database yet.
+	marked: HashSet<Key>,
+	/// The nodes marked as kept between the two previous checkpoints.
+	previously_marked: HashSet<Key>,
 }
 
 /// `DeathRowQueue` used to keep track of blocks in the pruning window, there are two flavors:
@@ -300,11 +344,182 @@ impl From<bool> for HaveBlock {
 	}
 }
 
+impl<BlockHash: Hash, Key: Hash, D: MetaDb> Snapshots<BlockHash, Key, D> {
+	/// Load the snapshots and the references inserted since the latest one.
+	fn load(db: &D, interval: NonZeroU32) -> Result<Self, Error<D::Error>> {
+		let blocks = match db.get_meta(&to_meta_key(PRUNING_SNAPSHOTS, &())).map_err(Error::Db)? {
+			Some(buffer) => Decode::decode(&mut buffer.as_slice())?,
+			None => Vec::new(),
+		};
+		let (checkpoint, insertions) =
+			match db.get_meta(&to_meta_key(PRUNING_SNAPSHOT_INSERTIONS, &())).map_err(Error::Db)? {
+				Some(buffer) => {
+					let (block, insertions): (u64, Vec<(Key, u32)>) =
+						Decode::decode(&mut buffer.as_slice())?;
+					(Some(block), insertions.into_iter().collect())
+				},
+				None => (None, HashMap::new()),
+			};
+		let mut snapshots = Snapshots {
+			interval: interval.get().into(),
+			blocks,
+			insertions,
+			checkpoint,
+			db: None,
+			marked: HashSet::new(),
+			previously_marked: HashSet::new(),
+		};
+		trace!(
+			target: LOG_TARGET,
+			"Reading pruning journal for the snapshots. {} snapshots, checkpoint at {:?}",
+			snapshots.blocks.len(),
+			snapshots.checkpoint,
+		);
+
+		if let Some(checkpoint) = checkpoint {
+			let mut block = checkpoint + 1;
+			while let Some(record) = db.get_meta(&to_journal_key(block)).map_err(Error::Db)? {
+				let record: JournalRecord<BlockHash, Key> = Decode::decode(&mut record.as_slice())?;
+				for key in &record.deleted {
+					snapshots.remove_insertion(key);
+				}
+				snapshots.note_insertions(block, record.inserted.iter());
+				if snapshots.blocks.binary_search_by_key(&block, |(n, _)| *n).is_ok() {
+					snapshots.insertions.clear();
+				}
+				block += 1;
+			}
+		}
+		Ok(snapshots)
+	}
+
+	/// The latest snapshot taken strictly before block `number`.
+	fn before(&self, number: u64) -> Option<&(u64, BlockHash)> {
+		let index = self.blocks.partition_point(|(n, _)| *n < number);
+		index.checked_sub(1).map(|i| &self.blocks[i])
+	}
+
+	/// Whether the insertions of block `number` need to be tracked.
+	fn tracks_insertions(&self, number: u64) -> bool {
+		self.before(number).is_some()
+	}
+
+	fn note_insertions<'a>(&mut self, number: u64, inserted: impl Iterator<Item = &'a Key>) {
+		if !self.tracks_insertions(number) {
+			return
+		}
+		for key in inserted {
+			*self.insertions.entry(key.clone()).or_default() += 1;
+		}
+	}
+
+	fn remove_insertion(&mut self, key: &Key) {
+		if let Some(count) = self.insertions.get_mut(key) {
+			*count -= 1;
+			if *count == 0 {
+				self.insertions.remove(key);
+			}
+		}
+	}
+
+	/// Return the nodes deleted by block `number` that don't belong to the latest snapshot taken
+	/// before it, and can be pruned.
+	fn prunable(
+		&mut self,
+		number: u64,
+		deleted: Vec<Key>,
+		commit: &mut CommitSet<Key>,
+	) -> Result<Vec<Key>, Error<D::Error>> {
+		if !self.tracks_insertions(number) {
+			return Ok(deleted)
+		}
+		let mut prunable = Vec::with_capacity(deleted.len());
+		for key in deleted {
+			if !self.insertions.contains_key(&key) {
+				self.mark_kept(key, commit);
+			} else if !self.is_marked(&key)? {
+				self.remove_insertion(&key);
+				prunable.push(key);
+			}
+			// Otherwise, the node was kept by an earlier snapshot before being re-inserted.
+		}
+		Ok(prunable)
+	}
+
+	/// Note the canonicalization of block `number`, and take a snapshot of it if it is due.
+	///
+	/// `base` is the next block to be pruned.
+	fn note_canonical(
+		&mut self,
+		hash: &BlockHash,
+		number: u64,
+		base: u64,
+		commit: &mut CommitSet<Key>,
+	) {
+		if number % self.interval == 0 {
+			trace!(target: LOG_TARGET, "Keeping state snapshot {:?} (#{})", hash, number);
+			self.blocks.push((number, hash.clone()));
+			commit
+				.meta
+				.inserted
+				.push((to_meta_key(PRUNING_SNAPSHOTS, &()), self.blocks.encode()));
+			self.insertions.clear();
+			self.checkpoint(number, base, commit);
+		} else if self.checkpoint.is_some_and(|c| number >= c + SNAPSHOT_CHECKPOINT_INTERVAL) {
+			self.checkpoint(number, base, commit);
+		}
+	}
+
+	/// Store the insertions counted up to block `number`, and remove the journal records of the
+	/// pruned blocks that are no longer needed to restore them.
+	fn checkpoint(&mut self, number: u64, base: u64, commit: &mut CommitSet<Key>) {
+		if let Some(previous) = self.checkpoint {
+			commit.meta.deleted.extend((previous + 1..base).map(to_journal_key));
+		}
+		let insertions = self.insertions.iter().collect::<Vec<_>>();
+		commit
+			.meta
+			.inserted
+			.push((to_meta_key(PRUNING_SNAPSHOT_INSERTIONS, &()), (number, insertions).encode()));
+		self.checkpoint = Some(number);
+		self.previously_marked = std::mem::take(&mut self.marked);
+	}
+
+	/// Whether the journal record of the pruned block `number` is needed to restore the
+	/// insertions.
+	fn retains_journal(&self, number: u64) -> bool {
+		self.checkpoint.is_some_and(|c| number > c)
+	}
+
+	/// Whether the node `key` was marked as kept by a snapshot.
+	fn is_marked(&self, key: &Key) -> Result<bool, Error<D::Error>> {
+		match self.db {
+			Some(ref db) => Ok(self.marked.contains(key) ||
+				self.previously_marked.contains(key) ||
+				db.get_meta(&to_snapshot_node_key(key)).map_err(Error::Db)?.is_some()),
+			None => Ok(false),
+		}
+	}
+
+	/// Mark the node `key` as kept by a snapshot if the database doesn't count references.
+	fn mark_kept(&mut self, key: Key, commit: &mut CommitSet<Key>) {
+		if self.db.is_some() {
+			commit.meta.inserted.push((to_snapshot_node_key(&key), Vec::new()));
+			self.marked.insert(key);
+		}
+	}
+}
+
+fn to_snapshot_node_key<Key: Hash>(key: &Key) -> Vec<u8> {
+	to_meta_key(PRUNING_SNAPSHOT_NODE, key)
+}
+
 impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 	pub fn new(
 		db: D,
 		window_size: u32,
 		count_insertions: bool,
+		snapshot_interval: Option<NonZeroU32>,
 	) -> Result<RefWindow<BlockHash, Key, D>, Error<D::Error>> {
 		// the block number of the first block in the queue or the next block number if the queue is
 		// empty
@@ -318,6 +533,10 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 				Some(buffer) => Some(<(BlockHash, u64)>::decode(&mut buffer.as_slice())?.1),
 				None => None,
 			};
+		let mut snapshots = match snapshot_interval {
+			Some(interval) => Some(Snapshots::load(&db, interval)?),
+			None => None,
+		};
 
 		let queue = if count_insertions {
 			// Highly scientific crafted number for deciding when to print the warning!
@@ -332,7 +551,11 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 				);
 			}
 
-			DeathRowQueue::new_mem(&db, base)?
+			let queue = DeathRowQueue::new_mem(&db, base)?;
+			if let Some(snapshots) = snapshots.as_mut() {
+				snapshots.db = Some(db);
+			}
+			queue
 		} else {
 			let last = match last_canonicalized_number {
 				Some(last_canonicalized_number) => {
@@ -347,7 +570,7 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 			DeathRowQueue::new_db_backed(db, base, last, window_size)?
 		};
 
-		Ok(RefWindow { queue, base })
+		Ok(RefWindow { queue, base, snapshots })
 	}
 
 	pub fn window_size(&self) -> u64 {
@@ -376,6 +599,13 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 	pub fn have_block(&self, hash: &BlockHash, number: u64) -> HaveBlock {
 		// if the queue is empty or the block number exceed the pruning window, we definitely
 		// do not have this block
+		if number < self.base {
+			if let Some(snapshots) = &self.snapshots {
+				if let Ok(index) = snapshots.blocks.binary_search_by_key(&number, |(n, _)| *n) {
+					return (snapshots.blocks[index].1 == *hash).into()
+				}
+			}
+		}
 		if self.is_empty() || number < self.base || number >= self.base + self.window_size() {
 			return HaveBlock::No
 		}
@@ -388,9 +618,11 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 			trace!(target: LOG_TARGET, "Pruning {:?} ({} deleted)", pruned.hash, pruned.deleted.len());
 			let index = self.base;
 			commit.data.deleted.extend(pruned.deleted.into_iter());
-			commit.meta.inserted.push((to_meta_key(LAST_PRUNED, &()), index.encode()));
-			commit.meta.deleted.push(to_journal_key(self.base));
+			if !self.snapshots.as_ref().map_or(false, |s| s.retains_journal(index)) {
+				commit.meta.deleted.push(to_journal_key(index));
+			}
 			self.base += 1;
+			commit.meta.inserted.push((to_meta_key(LAST_PRUNED, &()), index.encode()));
 			Ok(())
 		} else {
 			trace!(target: LOG_TARGET, "Trying to prune when there's nothing to prune");
@@ -424,34 +656,51 @@ impl<BlockHash: Hash, Key: Hash, D: MetaDb> RefWindow<BlockHash, Key, D> {
 			commit.data.inserted.len(),
 			commit.data.deleted.len(),
 		);
-		let inserted = if matches!(self.queue, DeathRowQueue::Mem { .. }) {
+		let inserted: Vec<_> = if matches!(self.queue, DeathRowQueue::Mem { .. }) ||
+			self.snapshots.as_ref().map_or(false, |s| s.tracks_insertions(number))
+		{
 			commit.data.inserted.iter().map(|(k, _)| k.clone()).collect()
 		} else {
 			Default::default()
 		};
-		let deleted = std::mem::take(&mut commit.data.deleted);
+		let mut deleted = std::mem::take(&mut commit.data.deleted);
+		if let Some(snapshots) = self.snapshots.as_mut() {
+			deleted = snapshots.prunable(number, deleted, commit)?;
+			snapshots.note_insertions(number, inserted.iter());
+			snapshots.note_canonical(hash, number, self.base, commit);
+		}
 		let journal_record = JournalRecord { hash: hash.clone(), inserted, deleted };
 		commit.meta.inserted.push((to_journal_key(number), journal_record.encode()));
 		self.queue.import(self.base, number, journal_record);
 		Ok(())
 	}
+
+	/// Returns the latest snapshot taken at or before block `number`.
+	pub fn nearest_snapshot(&self, number: u64) -> Option<(u64, BlockHash)> {
+		self.snapshots.as_ref()?.before(number.saturating_add(1)).cloned()
+	}
 }
 
 #[cfg(test)]
 mod tests {
-	use super::{to_journal_key, DeathRowQueue, HaveBlock, JournalRecord, RefWindow, LAST_PRUNED};
+	use super::{
+		to_journal_key, DeathRowQueue, HaveBlock, JournalRecord, RefWindow, LAST_PRUNED,
+		SNAPSHOT_CHECKPOINT_INTERVAL,
+	};
 	use crate::{
 		noncanonical::LAST_CANONICAL,
 		test::{make_commit, make_db, TestDb},
-		to_meta_key, CommitSet, Error, Hash, StateDbError, DEFAULT_MAX_BLOCK_CONSTRAINT,
+		to_meta_key, CommitSet, Error, Hash, MetaDb, StateDbError, DEFAULT_MAX_BLOCK_CONSTRAINT,
 	};
 	use codec::Encode;
 	use sp_core::H256;
+	use std::num::NonZeroU32;
 
 	fn check_journal(pruning: &RefWindow<H256, H256, TestDb>, db: &TestDb) {
 		let count_insertions = matches!(pruning.queue, DeathRowQueue::Mem { .. });
 		let restored: RefWindow<H256, H256, TestDb> =
-			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, count_insertions).unwrap();
+			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, count_insertions, None)
+				.unwrap();
 		assert_eq!(pruning.base, restored.base);
 		assert_eq!(pruning.queue.get_mem_queue_state(), restored.queue.get_mem_queue_state());
 	}
@@ -460,7 +709,7 @@ mod tests {
 	fn created_from_empty_db() {
 		let db = make_db(&[]);
 		let pruning: RefWindow<H256, H256, TestDb> =
-			RefWindow::new(db, DEFAULT_MAX_BLOCK_CONSTRAINT, true).unwrap();
+			RefWindow::new(db, DEFAULT_MAX_BLOCK_CONSTRAINT, true, None).unwrap();
 		assert_eq!(pruning.base, 0);
 		let (death_rows, death_index) = pruning.queue.get_mem_queue_state().unwrap();
 		assert!(death_rows.is_empty());
@@ -471,7 +720,7 @@ mod tests {
 	fn prune_empty() {
 		let db = make_db(&[]);
 		let mut pruning: RefWindow<H256, H256, TestDb> =
-			RefWindow::new(db, DEFAULT_MAX_BLOCK_CONSTRAINT, true).unwrap();
+			RefWindow::new(db, DEFAULT_MAX_BLOCK_CONSTRAINT, true, None).unwrap();
 		let mut commit = CommitSet::default();
 		assert_eq!(
 			Err(Error::StateDb(StateDbError::BlockUnavailable)),
@@ -487,7 +736,7 @@ mod tests {
 	fn prune_one() {
 		let mut db = make_db(&[1, 2, 3]);
 		let mut pruning: RefWindow<H256, H256, TestDb> =
-			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, true).unwrap();
+			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, true, None).unwrap();
 		let mut commit = make_commit(&[4, 5], &[1, 3]);
 		let hash = H256::random();
 		pruning.note_canonical(&hash, 0, &mut commit).unwrap();
@@ -517,7 +766,7 @@ mod tests {
 	fn prune_two() {
 		let mut db = make_db(&[1, 2, 3]);
 		let mut pruning: RefWindow<H256, H256, TestDb> =
-			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, true).unwrap();
+			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, true, None).unwrap();
 		let mut commit = make_commit(&[4], &[1]);
 		pruning.note_canonical(&H256::random(), 0, &mut commit).unwrap();
 		db.commit(&commit);
@@ -543,7 +792,7 @@ mod tests {
 	fn prune_two_pending() {
 		let mut db = make_db(&[1, 2, 3]);
 		let mut pruning: RefWindow<H256, H256, TestDb> =
-			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, true).unwrap();
+			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, true, None).unwrap();
 		let mut commit = make_commit(&[4], &[1]);
 		pruning.note_canonical(&H256::random(), 0, &mut commit).unwrap();
 		db.commit(&commit);
@@ -566,7 +815,7 @@ mod tests {
 	fn reinserted_survives() {
 		let mut db = make_db(&[1, 2, 3]);
 		let mut pruning: RefWindow<H256, H256, TestDb> =
-			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, true).unwrap();
+			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, true, None).unwrap();
 		let mut commit = make_commit(&[], &[2]);
 		pruning.note_canonical(&H256::random(), 0, &mut commit).unwrap();
 		db.commit(&commit);
@@ -598,7 +847,7 @@ mod tests {
 	fn reinserted_survive_pending() {
 		let mut db = make_db(&[1, 2, 3]);
 		let mut pruning: RefWindow<H256, H256, TestDb> =
-			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, true).unwrap();
+			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, true, None).unwrap();
 		let mut commit = make_commit(&[], &[2]);
 		pruning.note_canonical(&H256::random(), 0, &mut commit).unwrap();
 		db.commit(&commit);
@@ -628,7 +877,7 @@ mod tests {
 	fn reinserted_ignores() {
 		let mut db = make_db(&[1, 2, 3]);
 		let mut pruning: RefWindow<H256, H256, TestDb> =
-			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, false).unwrap();
+			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, false, None).unwrap();
 		let mut commit = make_commit(&[], &[2]);
 		pruning.note_canonical(&H256::random(), 0, &mut commit).unwrap();
 		db.commit(&commit);
@@ -648,6 +897,155 @@ mod tests {
 		assert!(db.data_eq(&make_db(&[1, 3])));
 	}
 
+	fn note_and_prune(
+		pruning: &mut RefWindow<H256, H256, TestDb>,
+		db: &mut TestDb,
+		number: u64,
+		inserted: &[u64],
+		deleted: &[u64],
+	) {
+		let mut commit = make_commit(inserted, deleted);
+		pruning
+			.note_canonical(&H256::from_low_u64_be(number), number, &mut commit)
+			.unwrap();
+		db.commit(&commit);
+		let mut commit = CommitSet::default();
+		pruning.prune_one(&mut commit).unwrap();
+		db.commit(&commit);
+	}
+
+	#[test]
+	fn hybrid_keeps_snapshots() {
+		for count_insertions in [true, false] {
+			let mut db = make_db(&[1, 2, 3]);
+			let mut pruning: RefWindow<H256, H256, TestDb> =
+				RefWindow::new(db.clone(), 0, count_insertions, Some(NonZeroU32::new(2).unwrap()))
+					.unwrap();
+			note_and_prune(&mut pruning, &mut db, 0, &[4], &[1]);
+			assert!(db.data_eq(&make_db(&[2, 3, 4])));
+			// 2 belongs to the snapshot of block 0.
+			note_and_prune(&mut pruning, &mut db, 1, &[5], &[2]);
+			assert!(db.data_eq(&make_db(&[2, 3, 4, 5])));
+			// 5 was inserted after the snapshot of block 0.
+			note_and_prune(&mut pruning, &mut db, 2, &[6], &[5]);
+			assert!(db.data_eq(&make_db(&[2, 3, 4, 6])));
+			note_and_prune(&mut pruning, &mut db, 3, &[], &[3, 6]);
+			assert!(db.data_eq(&make_db(&[2, 3, 4, 6])));
+			// 7 belongs to the snapshot of block 4.
+			note_and_prune(&mut pruning, &mut db, 4, &[7], &[]);
+			note_and_prune(&mut pruning, &mut db, 5, &[], &[7]);
+			assert!(db.data_eq(&make_db(&[2, 3, 4, 6, 7])));
+			assert_eq!(pruning.base, 6);
+
+			assert_eq!(pruning.have_block(&H256::from_low_u64_be(0), 0), HaveBlock::Yes);
+			assert_eq!(pruning.have_block(&H256::from_low_u64_be(1), 1), HaveBlock::No);
+			assert_eq!(pruning.have_block(&H256::from_low_u64_be(2), 2), HaveBlock::Yes);
+			assert_eq!(pruning.have_block(&H256::random(), 2), HaveBlock::No);
+			assert_eq!(pruning.nearest_snapshot(3), Some((2, H256::from_low_u64_be(2))));
+
+			// only the journal records following the latest checkpoint are retained
+			let snapshots = pruning.snapshots.as_ref().unwrap();
+			assert_eq!(snapshots.checkpoint, Some(4));
+			assert!(db.get_meta(&to_journal_key(4)).unwrap().is_none());
+			assert!(db.get_meta(&to_journal_key(5)).unwrap().is_some());
+
+			// the insertions since the latest snapshot are restored from the journal records
+			for number in [6, 7] {
+				let mut commit = make_commit(&[number + 2], &[]);
+				pruning
+					.note_canonical(&H256::from_low_u64_be(number), number, &mut commit)
+					.unwrap();
+				db.commit(&commit);
+			}
+			let snapshots = pruning.snapshots.as_ref().unwrap();
+			assert_eq!(snapshots.checkpoint, Some(6));
+			assert_eq!(snapshots.insertions, [(H256::from_low_u64_be(9), 1)].into());
+			assert!(db.get_meta(&to_journal_key(5)).unwrap().is_none());
+
+			let restored: RefWindow<H256, H256, TestDb> =
+				RefWindow::new(db.clone(), 0, count_insertions, Some(NonZeroU32::new(2).unwrap()))
+					.unwrap();
+			let restored_snapshots = restored.snapshots.as_ref().unwrap();
+			assert_eq!(restored.base, pruning.base);
+			assert_eq!(restored_snapshots.blocks, snapshots.blocks);
+			assert_eq!(restored_snapshots.insertions, snapshots.insertions);
+			assert_eq!(restored_snapshots.checkpoint, snapshots.checkpoint);
+		}
+	}
+
+	#[test]
+	fn hybrid_retains_journal_records_since_checkpoint() {
+		for count_insertions in [true, false] {
+			let mut db = make_db(&[]);
+			let mut pruning: RefWindow<H256, H256, TestDb> = RefWindow::new(
+				db.clone(),
+				0,
+				count_insertions,
+				Some(NonZeroU32::new(10_000).unwrap()),
+			)
+			.unwrap();
+			// Every block replaces the node inserted by its parent.
+			note_and_prune(&mut pruning, &mut db, 0, &[], &[]);
+			for number in 1..600 {
+				let deleted = if number > 1 { vec![number - 1] } else { vec![] };
+				note_and_prune(&mut pruning, &mut db, number, &[number], &deleted);
+			}
+			assert!(db.data_eq(&make_db(&[599])));
+
+			let snapshots = pruning.snapshots.as_ref().unwrap();
+			let checkpoint = 2 * SNAPSHOT_CHECKPOINT_INTERVAL;
+			assert_eq!(snapshots.checkpoint, Some(checkpoint));
+			assert_eq!(snapshots.insertions, [(H256::from_low_u64_be(599), 1)].into());
+			assert!(db.get_meta(&to_journal_key(checkpoint)).unwrap().is_none());
+			assert!(db.get_meta(&to_journal_key(checkpoint + 1)).unwrap().is_some());
+
+			let restored: RefWindow<H256, H256, TestDb> = RefWindow::new(
+				db.clone(),
+				0,
+				count_insertions,
+				Some(NonZeroU32::new(10_000).unwrap()),
+			)
+			.unwrap();
+			assert_eq!(restored.snapshots.unwrap().insertions, snapshots.insertions);
+		}
+	}
+
+	#[test]
+	fn hybrid_keeps_reinserted_snapshot_nodes() {
+		let mut db = make_db(&[1, 2, 3]);
+		let mut pruning: RefWindow<H256, H256, TestDb> = RefWindow::new(
+			db.clone(),
+			DEFAULT_MAX_BLOCK_CONSTRAINT,
+			true,
+			Some(NonZeroU32::new(4).unwrap()),
+		)
+		.unwrap();
+		// 1 and 2 belong to the snapshot of block 0, 2 is re-inserted while its death row is
+		// pending and deleted again before the next snapshot.
+		for (number, inserted, deleted) in [
+			(0, &[][..], &[][..]),
+			(1, &[][..], &[1, 2][..]),
+			(2, &[2][..], &[][..]),
+			(3, &[][..], &[2][..]),
+		] {
+			let mut commit = make_commit(inserted, deleted);
+			pruning.note_canonical(&H256::random(), number, &mut commit).unwrap();
+			db.commit(&commit);
+		}
+		for _ in 0..4 {
+			let mut commit = CommitSet::default();
+			pruning.prune_one(&mut commit).unwrap();
+			db.commit(&commit);
+		}
+		assert!(db.data_eq(&make_db(&[1, 2, 3])));
+
+		// 1 is re-inserted after being kept and deleted again before the next snapshot.
+		note_and_prune(&mut pruning, &mut db, 4, &[], &[]);
+		note_and_prune(&mut pruning, &mut db, 5, &[1], &[]);
+		note_and_prune(&mut pruning, &mut db, 6, &[], &[1, 2]);
+		assert!(db.data_eq(&make_db(&[1, 2, 3])));
+	}
+
 	fn push_last_canonicalized<H: Hash>(block: u64, commit: &mut CommitSet<H>) {
 		commit
 			.meta
@@ -666,7 +1064,7 @@ mod tests {
 
 		fn load_pruning_from_db(db: TestDb) -> (usize, u64) {
 			let pruning: RefWindow<u64, H256, TestDb> =
-				RefWindow::new(db, DEFAULT_MAX_BLOCK_CONSTRAINT, false).unwrap();
+				RefWindow::new(db, DEFAULT_MAX_BLOCK_CONSTRAINT, false, None).unwrap();
 			let (cache, _) = pruning.queue.get_db_backed_queue_state().unwrap();
 			(cache.len(), pruning.base)
 		}
@@ -720,7 +1118,7 @@ mod tests {
 	fn db_backed_queue() {
 		let mut db = make_db(&[]);
 		let mut pruning: RefWindow<u64, H256, TestDb> =
-			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, false).unwrap();
+			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, false, None).unwrap();
 		let cache_capacity = DEFAULT_MAX_BLOCK_CONSTRAINT as usize;
 
 		// start as an empty queue
@@ -764,7 +1162,7 @@ mod tests {
 
 		// revert the last add that no apply yet
 		// NOTE: do not commit the previous `CommitSet` to db
-		pruning = RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, false).unwrap();
+		pruning = RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, false, None).unwrap();
 		let cache_capacity = DEFAULT_MAX_BLOCK_CONSTRAINT as usize;
 		assert_eq!(pruning.window_size(), cache_capacity as u64 + 10);
 		let (cache, _) = pruning.queue.get_db_backed_queue_state().unwrap();
@@ -785,7 +1183,7 @@ mod tests {
 		// load a new queue from db
 		// `cache` is full again but the content of the queue should be the same
 		let pruning: RefWindow<u64, H256, TestDb> =
-			RefWindow::new(db, DEFAULT_MAX_BLOCK_CONSTRAINT, false).unwrap();
+			RefWindow::new(db, DEFAULT_MAX_BLOCK_CONSTRAINT, false, None).unwrap();
 		assert_eq!(pruning.window_size(), cache_capacity as u64 + 9);
 		let (cache, _) = pruning.queue.get_db_backed_queue_state().unwrap();
 		assert_eq!(cache.len(), cache_capacity);
@@ -798,7 +1196,7 @@ mod tests {
 	fn load_block_from_db() {
 		let mut db = make_db(&[]);
 		let mut pruning: RefWindow<u64, H256, TestDb> =
-			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, false).unwrap();
+			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, false, None).unwrap();
 		let cache_capacity = DEFAULT_MAX_BLOCK_CONSTRAINT as usize;
 
 		// import blocks
@@ -835,7 +1233,7 @@ mod tests {
 		// load a new queue from db
 		// `cache` should be the same
 		let pruning: RefWindow<u64, H256, TestDb> =
-			RefWindow::new(db, DEFAULT_MAX_BLOCK_CONSTRAINT, false).unwrap();
+			RefWindow::new(db, DEFAULT_MAX_BLOCK_CONSTRAINT, false, None).unwrap();
 		assert_eq!(pruning.window_size(), 10);
 		let (cache, _) = pruning.queue.get_db_backed_queue_state().unwrap();
 		assert_eq!(cache.len(), 10);
@@ -848,7 +1246,7 @@ mod tests {
 	fn get_block_from_queue() {
 		let mut db = make_db(&[]);
 		let mut pruning: RefWindow<u64, H256, TestDb> =
-			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, false).unwrap();
+			RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, false, None).unwrap();
 		let cache_capacity = DEFAULT_MAX_BLOCK_CONSTRAINT as u64;
 
 		// import blocks and commit to db
@@ -890,7 +1288,8 @@ mod tests {
 		for count_insertions in [true, false] {
 			let mut db = make_db(&[]);
 			let mut pruning: RefWindow<u64, H256, TestDb> =
-				RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, count_insertions).unwrap();
+				RefWindow::new(db.clone(), DEFAULT_MAX_BLOCK_CONSTRAINT, count_insertions, None)
+					.unwrap();
 			let block = 10000;
 
 			// import blocks
@@ -902,7 +1301,7 @@ mod tests {
 			// load a new queue from db
 			// `cache` should be the same
 			let pruning: RefWindow<u64, H256, TestDb> =
-				RefWindow::new(db, DEFAULT_MAX_BLOCK_CONSTRAINT, count_insertions).unwrap();
+				RefWindow::new(db, DEFAULT_MAX_BLOCK_CONSTRAINT, count_insertions, None).unwrap();
 
 			assert_eq!(HaveBlock::Yes, pruning.have_block(&block, block));
 		}
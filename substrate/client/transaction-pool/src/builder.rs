@@ -20,8 +20,11 @@
 
 use crate::{
 	common::api::FullChainApi,
-	fork_aware_txpool::ForkAwareTxPool as ForkAwareFullPool,
-	graph::{base_pool::Transaction, ChainApi, ExtrinsicFor, ExtrinsicHash, IsValidator, Options},
+	fork_aware_txpool::{ForkAwareTxPool as ForkAwareFullPool, JournalOptions},
+	graph::{
+		base_pool::Transaction, ChainApi, ExtrinsicFor, ExtrinsicHash, IsValidator, Options,
+		TransactionOrdering,
+	},
 	single_state_txpool::BasicPool as SingleStateFullPool,
 	TransactionPoolWrapper, LOG_TARGET,
 };
@@ -29,7 +32,7 @@ use prometheus_endpoint::Registry as PrometheusRegistry;
 use sc_transaction_pool_api::{LocalTransactionPool, MaintainedTransactionPool};
 use sp_core::traits::SpawnEssentialNamed;
 use sp_runtime::traits::Block as BlockT;
-use std::{marker::PhantomData, sync::Arc, time::Duration};
+use std::{marker::PhantomData, path::Path, sync::Arc, time::Duration};
 
 /// The type of transaction pool.
 #[derive(Debug, Clone)]
@@ -45,11 +48,16 @@ pub enum TransactionPoolType {
 pub struct TransactionPoolOptions {
 	txpool_type: TransactionPoolType,
 	options: Options,
+	journal: Option<JournalOptions>,
 }
 
 impl Default for TransactionPoolOptions {
 	fn default() -> Self {
-		Self { txpool_type: TransactionPoolType::SingleState, options: Default::default() }
+		Self {
+			txpool_type: TransactionPoolType::SingleState,
+			options: Default::default(),
+			journal: None,
+		}
 	}
 }
 
@@ -81,7 +89,28 @@ impl TransactionPoolOptions {
 			Duration::from_secs(30 * 60)
 		};
 
-		TransactionPoolOptions { options, txpool_type }
+		TransactionPoolOptions { options, txpool_type, journal: None }
+	}
+
+	/// Enables the on-disk journal of the submitted transactions.
+	///
+	/// The journal is only supported by the fork-aware transaction pool.
+	pub fn with_journal(mut self, journal: JournalOptions) -> Self {
+		self.journal = Some(journal);
+		self
+	}
+
+	/// Resolves the relative path of the journal (if enabled) against the given directory.
+	pub fn with_journal_dir(mut self, dir: &Path) -> Self {
+		if let Some(journal) = self.journal.as_mut().filter(|journal| journal.path.is_relative()) {
+			journal.path = dir.join(&journal.path);
+		}
+		self
+	}
+
+	/// Returns the journal options, if the journal is enabled.
+	pub fn journal(&self) -> Option<&JournalOptions> {
+		self.journal.as_ref()
 	}
 
 	/// Creates predefined options for benchmarking
@@ -98,8 +127,10 @@ impl TransactionPoolOptions {
 				},
 				reject_future_transactions: false,
 				ban_time: Duration::from_secs(30 * 60),
+				..Default::default()
 			},
 			txpool_type: TransactionPoolType::SingleState,
+			journal: None,
 		}
 	}
 }
@@ -173,6 +204,7 @@ pub struct Builder<'a, Block, Client> {
 	options: TransactionPoolOptions,
 	is_validator: IsValidator,
 	prometheus: Option<&'a PrometheusRegistry>,
+	ordering: Option<Arc<dyn TransactionOrdering>>,
 	client: Arc<Client>,
 	spawner: Box<dyn SpawnEssentialNamed>,
 	_phantom: PhantomData<(Client, Block)>,
@@ -207,6 +239,7 @@ where
 			client,
 			is_validator,
 			prometheus: None,
+			ordering: None,
 		}
 	}
 
@@ -222,25 +255,49 @@ where
 		self
 	}
 
+	/// Sets the policy used to order the ready transactions provided to the block builder.
+	///
+	/// Overrides the ordering given in the options. The transactions are always provided in the
+	/// order of their dependencies, the policy selects the next one among these that are ready
+	/// to be included.
+	pub fn with_ordering(mut self, ordering: impl TransactionOrdering + 'static) -> Self {
+		self.ordering = Some(Arc::new(ordering));
+		self
+	}
+
 	/// Creates an instance of transaction pool.
-	pub fn build(self) -> TransactionPoolHandle<Block, Client> {
+	pub fn build(mut self) -> TransactionPoolHandle<Block, Client> {
+		if let Some(ordering) = self.ordering.take() {
+			self.options.options.ordering = ordering;
+		}
 		tracing::info!(
 			target: LOG_TARGET,
 			txpool_type = ?self.options.txpool_type,
 			ready = ?self.options.options.ready,
 			future = ?self.options.options.future,
+			ordering = ?self.options.options.ordering,
+			journal = ?self.options.journal,
 			"Creating transaction pool"
 		);
 		TransactionPoolWrapper::<Block, Client>(match self.options.txpool_type {
-			TransactionPoolType::SingleState => Box::new(SingleStateFullPool::new_full(
-				self.options.options,
-				self.is_validator,
-				self.prometheus,
-				self.spawner,
-				self.client,
-			)),
+			TransactionPoolType::SingleState => {
+				if self.options.journal.is_some() {
+					tracing::warn!(
+						target: LOG_TARGET,
+						"The journal is not supported by the single-state transaction pool"
+					);
+				}
+				Box::new(SingleStateFullPool::new_full(
+					self.options.options,
+					self.is_validator,
+					self.prometheus,
+					self.spawner,
+					self.client,
+				))
+			},
 			TransactionPoolType::ForkAware => Box::new(ForkAwareFullPool::new_full(
 				self.options.options,
+				self.options.journal,
 				self.is_validator,
 				self.prometheus,
 				self.spawner,
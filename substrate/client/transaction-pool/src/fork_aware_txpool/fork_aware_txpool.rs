@@ -21,6 +21,7 @@
 use super::{
 	dropped_watcher::{MultiViewDroppedWatcherController, StreamOfDropped},
 	import_notification_sink::MultiViewImportNotificationSink,
+	journal::{JournalOptions, TxJournal},
 	metrics::{EventsMetricsCollector, MetricsLink as PrometheusMetrics},
 	multi_view_listener::MultiViewListener,
 	tx_mem_pool::{InsertionInfo, TxMemPool, TXMEMPOOL_TRANSACTION_LIMIT_MULTIPLIER},
@@ -199,25 +200,23 @@ where
 		finalized_hash: Block::Hash,
 		finality_timeout_threshold: Option<usize>,
 	) -> (Self, ForkAwareTxPoolTask) {
-		Self::new_test_with_limits(
+		Self::new_test_with_options(
 			pool_api,
 			best_block_hash,
 			finalized_hash,
-			Options::default().ready,
-			Options::default().future,
+			Options::default(),
 			usize::MAX,
 			finality_timeout_threshold,
 		)
 	}
 
-	/// Create new fork aware transaction pool with given limits and with provided shared instance
-	/// of `ChainApi` intended for tests.
-	pub fn new_test_with_limits(
+	/// Create new fork aware transaction pool with given options and with provided shared
+	/// instance of `ChainApi` intended for tests.
+	pub fn new_test_with_options(
 		pool_api: Arc<ChainApi>,
 		best_block_hash: Block::Hash,
 		finalized_hash: Block::Hash,
-		ready_limits: crate::PoolLimit,
-		future_limits: crate::PoolLimit,
+		options: Options,
 		mempool_max_transactions_count: usize,
 		finality_timeout_threshold: Option<usize>,
 	) -> (Self, ForkAwareTxPoolTask) {
@@ -232,7 +231,8 @@ where
 			listener.clone(),
 			Default::default(),
 			mempool_max_transactions_count,
-			ready_limits.total_bytes + future_limits.total_bytes,
+			options.ready.total_bytes + options.future.total_bytes,
+			None,
 		));
 
 		let (dropped_stream_controller, dropped_stream) =
@@ -257,8 +257,6 @@ where
 		}
 		.boxed();
 
-		let options = Options { ready: ready_limits, future: future_limits, ..Default::default() };
-
 		(
 			Self {
 				mempool,
@@ -338,8 +336,12 @@ where
 	///
 	/// The txpool essential tasks (including a revalidation worker) are spawned using provided
 	/// spawner.
+	///
+	/// If the `journal` is given, the submitted transactions are recorded on disk and restored
+	/// when the pool is created.
 	pub fn new_with_background_worker(
 		options: Options,
+		journal: Option<JournalOptions>,
 		is_validator: IsValidator,
 		pool_api: Arc<ChainApi>,
 		prometheus: Option<&PrometheusRegistry>,
@@ -361,12 +363,23 @@ where
 		let (import_notification_sink, import_notification_sink_task) =
 			MultiViewImportNotificationSink::new_with_worker();
 
+		let (journal, journal_task) = match journal.map(TxJournal::open).transpose() {
+			Ok(Some((journal, entries, journal_task))) =>
+				(Some((journal, entries)), Some(journal_task)),
+			Ok(None) => (None, None),
+			Err(error) => {
+				warn!(target: LOG_TARGET, %error, "Failed to open the transaction pool journal");
+				(None, None)
+			},
+		};
+
 		let mempool = Arc::from(TxMemPool::new(
 			pool_api.clone(),
 			listener.clone(),
 			metrics.clone(),
 			TXMEMPOOL_TRANSACTION_LIMIT_MULTIPLIER * options.total_count(),
 			options.ready.total_bytes + options.future.total_bytes,
+			journal,
 		));
 
 		let (dropped_stream_controller, dropped_stream) =
@@ -393,6 +406,14 @@ where
 		}
 		.boxed();
 		spawner.spawn_essential("txpool-background", Some("transaction-pool"), combined_tasks);
+		if let Some(journal_task) = journal_task {
+			// The journal file is written with blocking I/O.
+			spawner.spawn_essential_blocking(
+				"txpool-journal",
+				Some("transaction-pool"),
+				journal_task,
+			);
+		}
 
 		Self {
 			mempool,
@@ -966,13 +987,15 @@ where
 		result
 	}
 
-	/// Returns an iterator for ready transactions at a specific block, ordered by priority.
+	/// Returns an iterator for ready transactions at a specific block, ordered by the configured
+	/// [`TransactionOrdering`](crate::TransactionOrdering).
 	async fn ready_at(&self, at: <Self::Block as BlockT>::Hash) -> ReadyIteratorFor<ChainApi> {
 		let (_, result) = self.ready_at_internal(at);
 		result.await
 	}
 
-	/// Returns an iterator for ready transactions, ordered by priority.
+	/// Returns an iterator for ready transactions, ordered by the configured
+	/// [`TransactionOrdering`](crate::TransactionOrdering).
 	///
 	/// Currently the set of ready transactions is returned if it exists for the most recently
 	/// notified best block (for which maintain process was accomplished).
@@ -1599,6 +1622,7 @@ where
 		let finalized_xts = self.view_store.handle_finalized(finalized_hash, tree_route).await;
 
 		self.mempool.purge_finalized_transactions(&finalized_xts).await;
+		self.mempool.maybe_rotate_journal();
 		self.import_notification_sink.clean_notified_items(&finalized_xts);
 
 		self.metrics
@@ -1859,6 +1883,7 @@ where
 	/// Create new fork aware transaction pool for a full node with the provided api.
 	pub fn new_full(
 		options: Options,
+		journal: Option<JournalOptions>,
 		is_validator: IsValidator,
 		prometheus: Option<&PrometheusRegistry>,
 		spawner: impl SpawnEssentialNamed,
@@ -1867,6 +1892,7 @@ where
 		let pool_api = Arc::new(FullChainApi::new(client.clone(), prometheus, &spawner));
 		let pool = Self::new_with_background_worker(
 			options,
+			journal,
 			is_validator,
 			pool_api,
 			prometheus,
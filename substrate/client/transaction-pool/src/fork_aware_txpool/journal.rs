@@ -0,0 +1,310 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! On-disk journal of the submitted transactions.
+//!
+//! Every transaction accepted into the [`TxMemPool`](super::tx_mem_pool::TxMemPool) is appended
+//! to the journal file. On startup the journal is read back and its transactions are put into the
+//! mempool, so they get revalidated and imported into the first view created at the best block.
+//!
+//! The journal is only ever appended to. It is periodically rotated (i.e. rewritten with the
+//! current content of the mempool) to get rid of the transactions which are no longer in the
+//! pool, and whenever the size limit would be exceeded.
+//!
+//! The file is written by a background worker, the [`TxJournal`] only accounts the size of the
+//! journal and sends the entries to the worker. This keeps the file I/O out of the mempool.
+
+use codec::{Decode, Encode};
+use futures::{future::BoxFuture, FutureExt, StreamExt};
+use parking_lot::Mutex;
+use sc_transaction_pool_api::TransactionSource;
+use sc_utils::mpsc::{tracing_unbounded, TracingUnboundedReceiver, TracingUnboundedSender};
+use std::{
+	fs::{self, File, OpenOptions},
+	io::{self, BufWriter, Write},
+	path::{Path, PathBuf},
+};
+use tracing::{debug, warn};
+
+use crate::LOG_TARGET;
+
+/// Options of the transaction pool journal.
+#[derive(Debug, Clone)]
+pub struct JournalOptions {
+	/// Path of the journal file.
+	pub path: PathBuf,
+	/// Maximal size of the journal file in bytes.
+	pub max_bytes: usize,
+}
+
+/// An entry of the journal: the source and the encoding of the transaction.
+pub(super) type JournalEntry = (TransactionSource, Vec<u8>);
+
+/// The background task writing the journal file.
+pub(super) type JournalWorker = BoxFuture<'static, ()>;
+
+/// Command sent to the [`JournalWorker`], carrying already encoded entries.
+enum JournalCommand {
+	/// Append the entry to the journal file.
+	Append(Vec<u8>),
+	/// Replace the journal file with given entries.
+	Rotate(Vec<Vec<u8>>),
+}
+
+struct JournalState {
+	/// Whether the journal was rotated at least once.
+	writable: bool,
+	/// Size of the journal file once the worker processed all sent commands.
+	bytes: usize,
+}
+
+/// The journal of the submitted transactions.
+pub(super) struct TxJournal {
+	max_bytes: usize,
+	state: Mutex<JournalState>,
+	to_worker: TracingUnboundedSender<JournalCommand>,
+}
+
+impl TxJournal {
+	/// Opens the journal, returning the entries recorded in it and the worker writing the file.
+	///
+	/// The journal is not writable until it is [rotated](Self::rotate) for the first time, which
+	/// allows to restore the entries without recording them again.
+	pub(super) fn open(
+		options: JournalOptions,
+	) -> io::Result<(Self, Vec<JournalEntry>, JournalWorker)> {
+		if let Some(parent) = options.path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		let entries = match fs::read(&options.path) {
+			Ok(data) => Self::decode_entries(&data),
+			Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+			Err(e) => return Err(e),
+		};
+		debug!(
+			target: LOG_TARGET,
+			path = ?options.path,
+			count = entries.len(),
+			"txjournal: loaded"
+		);
+
+		let (to_worker, from_journal) = tracing_unbounded("mpsc_txpool_journal", 100_000);
+		let journal = Self {
+			max_bytes: options.max_bytes,
+			state: Mutex::new(JournalState { writable: false, bytes: 0 }),
+			to_worker,
+		};
+		Ok((journal, entries, Self::run(options.path, from_journal).boxed()))
+	}
+
+	fn decode_entries(mut data: &[u8]) -> Vec<JournalEntry> {
+		let mut entries = Vec::new();
+		while !data.is_empty() {
+			match JournalEntry::decode(&mut data) {
+				Ok(entry) => entries.push(entry),
+				Err(error) => {
+					// Most likely the node was stopped while writing the last entry.
+					warn!(
+						target: LOG_TARGET,
+						%error,
+						remaining = data.len(),
+						"txjournal: ignoring corrupted tail"
+					);
+					break
+				},
+			}
+		}
+		entries
+	}
+
+	/// Returns the size of the journal file.
+	pub(super) fn bytes(&self) -> usize {
+		self.state.lock().bytes
+	}
+
+	/// Appends a transaction to the journal.
+	///
+	/// Returns `false` if the entry was not written because the journal would exceed its size
+	/// limit, or because the journal is not writable.
+	pub(super) fn append(&self, source: TransactionSource, xt: &[u8]) -> bool {
+		let entry = (source, xt).encode();
+		let mut state = self.state.lock();
+		if !state.writable || state.bytes + entry.len() > self.max_bytes {
+			return false
+		}
+		state.bytes += entry.len();
+		self.send(JournalCommand::Append(entry));
+		true
+	}
+
+	/// Rewrites the journal with given entries.
+	///
+	/// Entries that do not fit into the size limit are skipped.
+	pub(super) fn rotate(&self, entries: impl IntoIterator<Item = JournalEntry>) {
+		let mut bytes = 0;
+		let mut skipped = 0;
+		let entries = entries
+			.into_iter()
+			.map(|entry| entry.encode())
+			.filter(|entry| {
+				if bytes + entry.len() > self.max_bytes {
+					skipped += 1;
+					return false
+				}
+				bytes += entry.len();
+				true
+			})
+			.collect();
+
+		let mut state = self.state.lock();
+		state.writable = true;
+		state.bytes = bytes;
+		self.send(JournalCommand::Rotate(entries));
+		debug!(target: LOG_TARGET, bytes, skipped, "txjournal: rotation scheduled");
+	}
+
+	fn send(&self, command: JournalCommand) {
+		if let Err(error) = self.to_worker.unbounded_send(command) {
+			warn!(target: LOG_TARGET, ?error, "txjournal: worker is gone");
+		}
+	}
+
+	/// Writes the journal file until the [`TxJournal`] is dropped.
+	async fn run(path: PathBuf, mut from_journal: TracingUnboundedReceiver<JournalCommand>) {
+		let mut file: Option<File> = None;
+		while let Some(command) = from_journal.next().await {
+			match command {
+				JournalCommand::Append(entry) => {
+					let Some(file) = file.as_mut() else { continue };
+					if let Err(error) = file.write_all(&entry) {
+						warn!(target: LOG_TARGET, %error, "txjournal: append failed");
+					}
+				},
+				JournalCommand::Rotate(entries) => match Self::rewrite(&path, entries) {
+					Ok(rotated) => file = Some(rotated),
+					Err(error) => warn!(target: LOG_TARGET, %error, "txjournal: rotation failed"),
+				},
+			}
+		}
+	}
+
+	/// Replaces the journal file with given entries, returning the append handle.
+	fn rewrite(path: &Path, entries: Vec<Vec<u8>>) -> io::Result<File> {
+		let tmp_path = path.with_extension("tmp");
+		let mut writer = BufWriter::new(File::create(&tmp_path)?);
+		for entry in entries {
+			writer.write_all(&entry)?;
+		}
+		writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+		fs::rename(&tmp_path, path)?;
+		OpenOptions::new().append(true).open(path)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::thread::JoinHandle;
+
+	/// The journal with its worker running on a separate thread.
+	struct TestJournal {
+		journal: TxJournal,
+		worker: JoinHandle<()>,
+	}
+
+	impl std::ops::Deref for TestJournal {
+		type Target = TxJournal;
+
+		fn deref(&self) -> &TxJournal {
+			&self.journal
+		}
+	}
+
+	impl TestJournal {
+		/// Drops the journal and waits until its worker has written all entries.
+		fn close(self) {
+			drop(self.journal);
+			self.worker.join().unwrap();
+		}
+	}
+
+	fn journal(dir: &tempfile::TempDir, max_bytes: usize) -> (TestJournal, Vec<JournalEntry>) {
+		let (journal, entries, worker) =
+			TxJournal::open(JournalOptions { path: dir.path().join("txpool/journal"), max_bytes })
+				.unwrap();
+		let worker = std::thread::spawn(move || futures::executor::block_on(worker));
+		(TestJournal { journal, worker }, entries)
+	}
+
+	#[test]
+	fn journal_is_restored_after_reopen() {
+		let dir = tempfile::tempdir().unwrap();
+		let (journal1, entries) = journal(&dir, 1024);
+		assert!(entries.is_empty());
+		assert!(
+			!journal1.append(TransactionSource::External, &[1]),
+			"not writable before rotation"
+		);
+
+		journal1.rotate(vec![(TransactionSource::Local, vec![0])]);
+		assert!(journal1.append(TransactionSource::External, &[1, 1]));
+		journal1.close();
+
+		let (_, entries) = journal(&dir, 1024);
+		assert_eq!(
+			entries,
+			vec![(TransactionSource::Local, vec![0]), (TransactionSource::External, vec![1, 1])]
+		);
+	}
+
+	#[test]
+	fn journal_obeys_size_limit() {
+		let dir = tempfile::tempdir().unwrap();
+		let entry = |x: u8| (TransactionSource::External, vec![x; 8]);
+		let entry_size = entry(0).encode().len();
+		let (journal1, _) = journal(&dir, 2 * entry_size);
+
+		journal1.rotate(vec![entry(0), entry(1), entry(2)]);
+		assert_eq!(journal1.bytes(), 2 * entry_size);
+		assert!(!journal1.append(entry(3).0, &entry(3).1));
+
+		journal1.rotate(vec![entry(2)]);
+		assert!(journal1.append(entry(3).0, &entry(3).1));
+		journal1.close();
+
+		let (_, entries) = journal(&dir, 2 * entry_size);
+		assert_eq!(entries, vec![entry(2), entry(3)]);
+	}
+
+	#[test]
+	fn corrupted_tail_is_ignored() {
+		let dir = tempfile::tempdir().unwrap();
+		let (journal1, _) = journal(&dir, 1024);
+		journal1.rotate(vec![(TransactionSource::External, vec![7; 4])]);
+		journal1.close();
+
+		let path = dir.path().join("txpool/journal");
+		let mut data = fs::read(&path).unwrap();
+		data.extend_from_slice(&(TransactionSource::Local, vec![8u8; 16]).encode()[..5]);
+		fs::write(&path, data).unwrap();
+
+		let (_, entries) = journal(&dir, 1024);
+		assert_eq!(entries, vec![(TransactionSource::External, vec![7; 4])]);
+	}
+}
@@ -205,6 +205,14 @@
 //! - removal of finalized transaction from the *mempool*,
 //! - trigger [*mempool* background revalidation](#mempool-pruningrevalidation).
 //! - clean up of multi-view listeners which is required to avoid ever-growing structures,
+//! - rotation of the [journal](#journal) if it mostly contains stale transactions.
+//!
+//! ### Journal
+//! Optionally, every transaction accepted into the *mempool* is appended to an on-disk journal.
+//! When the pool is created, the transactions recorded in the journal are put back into the
+//! *mempool*, so they are revalidated and imported into the first view, created at the best
+//! block. The journal is size limited and it is rewritten with the current content of the
+//! *mempool* when it grows too much. The file is written by a dedicated background task.
 //!
 //! ### Light maintain
 //! The [maintain](#maintain) procedure can sometimes be quite heavy, and it may not be accomplished
@@ -237,6 +245,11 @@
 //! are called both of them immediately provide the ready transactions iterator (which is simply
 //! requested on the appropriate instance of the [`View`]).
 //!
+//! The order in which the iterator provides the transactions is determined by the
+//! [`TransactionOrdering`] policy given in the pool options (by default: by priority). The block
+//! builder (e.g. `sc-basic-authorship`) includes them in the order they are provided, so the
+//! policy is directly reflected in the authored blocks.
+//!
 //! The little [`ReadyPoll`] helper contained within [`ForkAwareTxPool`] as ([`ready_poll`])
 //! implements the futures management.
 //!
@@ -358,6 +371,7 @@
 //! [`submit_and_watch`]: ../struct.ForkAwareTxPool.html#method.submit_and_watch
 //! [`ReadyPoll`]: ../fork_aware_txpool/fork_aware_txpool/struct.ReadyPoll.html
 //! [`TreeRoute`]: sp_blockchain::TreeRoute
+//! [`TransactionOrdering`]: crate::TransactionOrdering
 //! [runtime_api::validate]: sp_transaction_pool::runtime_api::TaggedTransactionQueue::validate_transaction
 //! [`notification_future`]: crate::common::notification_future
 //! [`EnactmentState`]: crate::common::enactment_state::EnactmentState
@@ -369,6 +383,7 @@
 mod dropped_watcher;
 pub(crate) mod fork_aware_txpool;
 mod import_notification_sink;
+mod journal;
 mod metrics;
 mod multi_view_listener;
 mod revalidation_worker;
@@ -377,6 +392,7 @@ mod view;
 mod view_store;
 
 pub use fork_aware_txpool::{ForkAwareTxPool, ForkAwareTxPoolTask};
+pub use journal::JournalOptions;
 
 mod stream_map_util {
 	use futures::Stream;
@@ -36,10 +36,11 @@ use std::{
 	time::Instant,
 };
 
+use codec::{Decode, Encode};
 use futures::FutureExt;
 use itertools::Itertools;
 use parking_lot::RwLock;
-use tracing::{debug, trace};
+use tracing::{debug, info, trace};
 
 use sc_transaction_pool_api::{TransactionPriority, TransactionSource};
 use sp_blockchain::HashAndNumber;
@@ -56,6 +57,7 @@ use crate::{
 };
 
 use super::{
+	journal::{JournalEntry, TxJournal},
 	metrics::MetricsLink as PrometheusMetrics,
 	multi_view_listener::MultiViewListener,
 	view_store::{ViewStore, ViewStoreSubmitOutcome},
@@ -224,6 +226,9 @@ where
 
 	/// Maximal size of encodings of all transactions in the memory pool.
 	max_transactions_total_bytes: usize,
+
+	/// On-disk journal of the transactions in the memory pool (if enabled).
+	journal: Option<TxJournal>,
 }
 
 /// Helper structure to encapsulate a result of [`TxMemPool::try_insert`].
@@ -251,21 +256,31 @@ where
 {
 	/// Creates a new `TxMemPool` instance with the given API, listener, metrics,
 	/// and max transaction count.
+	///
+	/// If the journal is given, the transactions recorded in it are restored into the memory
+	/// pool.
 	pub(super) fn new(
 		api: Arc<ChainApi>,
 		listener: Arc<MultiViewListener<ChainApi>>,
 		metrics: PrometheusMetrics,
 		max_transactions_count: usize,
 		max_transactions_total_bytes: usize,
+		journal: Option<(TxJournal, Vec<JournalEntry>)>,
 	) -> Self {
-		Self {
+		let (journal, entries) = journal.unzip();
+		let mempool = Self {
 			api,
 			listener,
 			transactions: Default::default(),
 			metrics,
 			max_transactions_count,
 			max_transactions_total_bytes,
+			journal,
+		};
+		if let Some(entries) = entries {
+			mempool.restore_journal(entries);
 		}
+		mempool
 	}
 
 	/// Creates a new `TxMemPool` instance for testing purposes.
@@ -282,6 +297,59 @@ where
 			metrics: Default::default(),
 			max_transactions_count,
 			max_transactions_total_bytes,
+			journal: None,
+		}
+	}
+
+	/// Inserts the transactions read from the journal and rewrites the journal with the actual
+	/// content of the memory pool.
+	///
+	/// The transactions will be revalidated when they are submitted to the first view.
+	fn restore_journal(&self, entries: Vec<JournalEntry>) {
+		let count = entries.len();
+		let restored = entries
+			.into_iter()
+			.filter_map(|(source, xt)| {
+				let xt = Block::Extrinsic::decode(&mut &xt[..])
+					.map_err(
+						|error| debug!(target: LOG_TARGET, %error, "txjournal: undecodable transaction"),
+					)
+					.ok()?;
+				let xt: ExtrinsicFor<ChainApi> = Arc::from(xt);
+				let (hash, length) = self.api.hash_and_length(&xt);
+				self.try_insert(hash, TxInMemPool::new_unwatched(source, xt, length)).ok()
+			})
+			.count();
+		info!(target: LOG_TARGET, restored, count, "Restored transactions from the journal");
+		self.rotate_journal();
+	}
+
+	/// Rewrites the journal with the transactions currently kept in the memory pool.
+	///
+	/// If the journal size limit is exceeded, the transactions with higher priority are kept.
+	pub(super) fn rotate_journal(&self) {
+		let Some(journal) = self.journal.as_ref() else { return };
+		let mut transactions = self.transactions.read().values().cloned().collect::<Vec<_>>();
+		transactions.sort_by(|a, b| b.priority().cmp(&a.priority()));
+		journal.rotate(transactions.into_iter().map(|tx| (tx.source.source, tx.tx.encode())));
+	}
+
+	/// Rotates the journal if it mostly consists of transactions no longer kept in the memory
+	/// pool.
+	pub(super) fn maybe_rotate_journal(&self) {
+		let Some(journal) = self.journal.as_ref() else { return };
+		if journal.bytes() > 2 * self.transactions.bytes() {
+			self.rotate_journal();
+		}
+	}
+
+	/// Records the newly inserted transaction in the journal (if enabled).
+	fn journal_insertion(&self, source: TransactionSource, xt: &ExtrinsicFor<ChainApi>) {
+		let Some(journal) = self.journal.as_ref() else { return };
+		if !journal.append(source, &xt.encode()) && journal.bytes() > self.transactions.bytes() {
+			// The journal is full, but contains stale entries. The rotation will also write the
+			// just inserted transaction.
+			self.rotate_journal();
 		}
 	}
 
@@ -421,12 +489,15 @@ where
 		}
 
 		let source = new_tx.source();
+		let xt = new_tx.tx();
 		transactions.insert(hash, Arc::from(new_tx));
 		for worst_hash in &to_be_removed {
 			transactions.remove(worst_hash);
 		}
 		debug_assert!(!self.is_limit_exceeded(transactions.len(), self.transactions.bytes()));
+		drop(transactions);
 
+		self.journal_insertion(source.source, &xt);
 		Ok(InsertionInfo::new_with_removed(hash, source, to_be_removed))
 	}
 
@@ -444,7 +515,12 @@ where
 			.iter()
 			.map(|xt| {
 				let (hash, length) = self.api.hash_and_length(&xt);
-				self.try_insert(hash, TxInMemPool::new_unwatched(source, xt.clone(), length))
+				let result =
+					self.try_insert(hash, TxInMemPool::new_unwatched(source, xt.clone(), length));
+				if result.is_ok() {
+					self.journal_insertion(source, xt);
+				}
+				result
 			})
 			.collect::<Vec<_>>();
 		result
@@ -458,7 +534,11 @@ where
 		xt: ExtrinsicFor<ChainApi>,
 	) -> Result<InsertionInfo<ExtrinsicHash<ChainApi>>, sc_transaction_pool_api::error::Error> {
 		let (hash, length) = self.api.hash_and_length(&xt);
-		self.try_insert(hash, TxInMemPool::new_watched(source, xt.clone(), length))
+		let result = self.try_insert(hash, TxInMemPool::new_watched(source, xt.clone(), length));
+		if result.is_ok() {
+			self.journal_insertion(source, &xt);
+		}
+		result
 	}
 
 	/// Clones and returns a `HashMap` of references to all transactions in the memory pool.
@@ -644,7 +724,7 @@ mod tx_mem_pool_tests {
 	use substrate_test_runtime::{AccountId, Extrinsic, ExtrinsicBuilder, Transfer, H256};
 	use substrate_test_runtime_client::Sr25519Keyring::*;
 
-	use crate::{common::tests::TestApi, graph::ChainApi};
+	use crate::{common::tests::TestApi, fork_aware_txpool::JournalOptions, graph::ChainApi};
 
 	use super::*;
 
@@ -993,4 +1073,51 @@ mod tx_mem_pool_tests {
 			sc_transaction_pool_api::error::Error::ImmediatelyDropped
 		));
 	}
+
+	#[test]
+	fn journal_restores_transactions() {
+		let dir = tempfile::tempdir().unwrap();
+		let journal = JournalOptions { path: dir.path().join("journal"), max_bytes: 1024 * 1024 };
+		let api = Arc::from(TestApi::default());
+		// returns the mempool and the thread running its journal worker
+		let new_mempool = || {
+			let (journal, entries, worker) = TxJournal::open(journal.clone()).unwrap();
+			let mempool = TxMemPool::new(
+				api.clone(),
+				Arc::from(MultiViewListener::new_with_worker(Default::default()).0),
+				Default::default(),
+				10,
+				usize::MAX,
+				Some((journal, entries)),
+			);
+			(mempool, std::thread::spawn(move || futures::executor::block_on(worker)))
+		};
+		// waits until the journal worker has written all entries
+		let close = |(mempool, worker): (TxMemPool<_, _>, std::thread::JoinHandle<()>)| {
+			drop(mempool);
+			worker.join().unwrap();
+		};
+
+		let xts = (0..5).map(|x| Arc::from(uxt(x as _))).collect::<Vec<_>>();
+		let (mempool, worker) = new_mempool();
+		assert_eq!(mempool.len(), 0);
+		assert!(mempool
+			.extend_unwatched(TransactionSource::External, &xts)
+			.iter()
+			.all(Result::is_ok));
+		mempool.push_watched(TransactionSource::Local, Arc::from(uxt(5))).unwrap();
+		close((mempool, worker));
+
+		let (mempool, worker) = new_mempool();
+		assert_eq!(mempool.unwatched_and_watched_count(), (6, 0));
+		let local = mempool.get_by_hash(api.hash_and_length(&uxt(5)).0).unwrap();
+		assert_eq!(local.source().source, TransactionSource::Local);
+
+		// transactions no longer in the mempool are dropped from the journal by rotation
+		mempool.remove_transactions(&[api.hash_and_length(&uxt(0)).0]);
+		mempool.rotate_journal();
+		close((mempool, worker));
+
+		assert_eq!(new_mempool().0.len(), 5);
+	}
 }
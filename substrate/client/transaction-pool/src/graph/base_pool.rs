@@ -37,6 +37,7 @@ use tracing::{trace, warn};
 
 use super::{
 	future::{FutureTransactions, WaitingTransaction},
+	ordering::TransactionOrdering,
 	ready::{BestIterator, ReadyTransactions, TransactionRef},
 };
 
@@ -433,6 +434,11 @@ impl<Hash: hash::Hash + Member + Serialize, Ex: std::fmt::Debug> BasePool<Hash,
 		self.ready.get()
 	}
 
+	/// Returns an iterator over ready transactions in the pool, ordered by given policy.
+	pub fn ready_ordered(&self, ordering: Arc<dyn TransactionOrdering>) -> BestIterator<Hash, Ex> {
+		self.ready.get_ordered(ordering)
+	}
+
 	/// Returns an iterator over future transactions in the pool.
 	pub fn futures(&self) -> impl Iterator<Item = &Transaction<Hash, Ex>> {
 		self.future.all()
@@ -28,6 +28,7 @@
 
 mod future;
 mod listener;
+mod ordering;
 mod pool;
 mod ready;
 mod rotator;
@@ -37,9 +38,15 @@ mod validated_pool;
 pub mod base_pool;
 pub mod watcher;
 
-pub use self::pool::{
-	BlockHash, ChainApi, ExtrinsicFor, ExtrinsicHash, NumberFor, Options, Pool, RawExtrinsicFor,
-	TransactionFor, ValidatedTransactionFor,
+pub use self::{
+	ordering::{
+		DeprioritizeOrdering, FifoOrdering, PriorityOrdering, ReadyCandidate, RoundRobinOrdering,
+		TransactionOrdering,
+	},
+	pool::{
+		BlockHash, ChainApi, ExtrinsicFor, ExtrinsicHash, NumberFor, Options, Pool,
+		RawExtrinsicFor, TransactionFor, ValidatedTransactionFor,
+	},
 };
 pub use validated_pool::{
 	BaseSubmitOutcome, EventDispatcher, IsValidator, ValidatedPoolSubmitOutcome,
@@ -0,0 +1,139 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: GPL-3.0-or-later WITH Classpath-exception-2.0
+
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE. See the
+// GNU General Public License for more details.
+
+// You should have received a copy of the GNU General Public License
+// along with this program. If not, see <https://www.gnu.org/licenses/>.
+
+//! Ordering policies of ready transactions.
+//!
+//! The ready iterator always respects the dependencies between transactions: a transaction is
+//! never provided before all the transactions providing its required tags. Among the
+//! transactions that have all their requirements satisfied, the next one is selected by the
+//! [`TransactionOrdering`] policy configured in the pool [`Options`](super::Options).
+
+use super::base_pool::TimedTransactionSource;
+use sp_runtime::transaction_validity::{
+	TransactionLongevity, TransactionPriority, TransactionTag as Tag,
+};
+use std::{cmp::Ordering, fmt, sync::Arc};
+
+/// A ready transaction, which has all of its requirements satisfied, as seen by the ordering
+/// policy.
+#[derive(Debug, Clone, Copy)]
+pub struct ReadyCandidate<'a> {
+	/// Priority provided by the runtime.
+	pub priority: TransactionPriority,
+	/// Block number until which the transaction is valid.
+	pub valid_till: TransactionLongevity,
+	/// Unique, monotonically increasing id assigned when the transaction was inserted into the
+	/// ready queue.
+	pub insertion_id: u64,
+	/// Encoded size of the transaction.
+	pub bytes: usize,
+	/// Source of the transaction, along with its submission time (if known).
+	pub source: &'a TimedTransactionSource,
+	/// Tags provided by the transaction.
+	///
+	/// For FRAME based runtimes these identify the sender and the nonce of the transaction.
+	pub provides: &'a [Tag],
+	/// Number of transactions of the same dependency chain that were already provided by the
+	/// iterator.
+	///
+	/// Transactions of a single sender usually form a chain (each one requires the tag provided
+	/// by the previous nonce), so this is the number of already provided transactions of the
+	/// sender.
+	pub chained: usize,
+}
+
+/// Policy selecting the next ready transaction to be provided to the block builder.
+pub trait TransactionOrdering: fmt::Debug + Send + Sync {
+	/// Compares two ready transactions. The greater one is provided first.
+	///
+	/// Transactions comparing equal are provided in the order of their insertion into the ready
+	/// queue.
+	fn cmp(&self, a: &ReadyCandidate, b: &ReadyCandidate) -> Ordering;
+}
+
+/// The default ordering: by priority, then by the remaining longevity (shorter first).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PriorityOrdering;
+
+impl TransactionOrdering for PriorityOrdering {
+	fn cmp(&self, a: &ReadyCandidate, b: &ReadyCandidate) -> Ordering {
+		a.priority.cmp(&b.priority).then_with(|| b.valid_till.cmp(&a.valid_till))
+	}
+}
+
+/// First-in, first-out ordering, ignoring the runtime provided priority.
+///
+/// Transactions with a known submission time are sorted by it and provided before the
+/// transactions without one. The latter, as well as the transactions submitted at the same time,
+/// are sorted by their insertion into the ready queue.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FifoOrdering;
+
+impl TransactionOrdering for FifoOrdering {
+	fn cmp(&self, a: &ReadyCandidate, b: &ReadyCandidate) -> Ordering {
+		let by_timestamp = match (a.source.timestamp, b.source.timestamp) {
+			(Some(a), Some(b)) => b.cmp(&a),
+			(Some(_), None) => Ordering::Greater,
+			(None, Some(_)) => Ordering::Less,
+			(None, None) => Ordering::Equal,
+		};
+		by_timestamp.then_with(|| b.insertion_id.cmp(&a.insertion_id))
+	}
+}
+
+/// Round-robin over the dependency chains (usually: the senders).
+///
+/// Chains which already provided fewer transactions go first, so that a single sender can not
+/// fill the block before others get a chance. Ties are broken by the wrapped ordering.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RoundRobinOrdering<O = PriorityOrdering>(pub O);
+
+impl<O: TransactionOrdering> TransactionOrdering for RoundRobinOrdering<O> {
+	fn cmp(&self, a: &ReadyCandidate, b: &ReadyCandidate) -> Ordering {
+		b.chained.cmp(&a.chained).then_with(|| self.0.cmp(a, b))
+	}
+}
+
+/// Ordering which provides the transactions matched by the filter after all the others.
+///
+/// Can be used to deprioritise the transactions of spamming origins without dropping them.
+/// Both groups are sorted by the wrapped ordering.
+#[derive(Clone)]
+pub struct DeprioritizeOrdering<O = PriorityOrdering> {
+	inner: O,
+	filter: Arc<dyn Fn(&ReadyCandidate) -> bool + Send + Sync>,
+}
+
+impl<O> DeprioritizeOrdering<O> {
+	/// Creates new ordering deprioritising the transactions for which `filter` returns `true`.
+	pub fn new(inner: O, filter: impl Fn(&ReadyCandidate) -> bool + Send + Sync + 'static) -> Self {
+		Self { inner, filter: Arc::new(filter) }
+	}
+}
+
+impl<O: fmt::Debug> fmt::Debug for DeprioritizeOrdering<O> {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.debug_struct("DeprioritizeOrdering").field("inner", &self.inner).finish()
+	}
+}
+
+impl<O: TransactionOrdering> TransactionOrdering for DeprioritizeOrdering<O> {
+	fn cmp(&self, a: &ReadyCandidate, b: &ReadyCandidate) -> Ordering {
+		(self.filter)(b).cmp(&(self.filter)(a)).then_with(|| self.inner.cmp(a, b))
+	}
+}
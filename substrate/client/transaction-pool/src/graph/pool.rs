@@ -37,6 +37,7 @@ use tracing::{debug, trace};
 
 use super::{
 	base_pool as base,
+	ordering::{PriorityOrdering, TransactionOrdering},
 	validated_pool::{IsValidator, ValidatedPool, ValidatedTransaction},
 	EventHandler, ValidatedPoolSubmitOutcome,
 };
@@ -146,6 +147,8 @@ pub struct Options {
 	pub reject_future_transactions: bool,
 	/// How long the extrinsic is banned for.
 	pub ban_time: Duration,
+	/// Policy used to order the ready transactions.
+	pub ordering: Arc<dyn TransactionOrdering>,
 }
 
 impl Default for Options {
@@ -155,6 +158,7 @@ impl Default for Options {
 			future: base::Limit { count: 512, total_bytes: 1 * 1024 * 1024 },
 			reject_future_transactions: false,
 			ban_time: Duration::from_secs(60 * 30),
+			ordering: Arc::new(PriorityOrdering),
 		}
 	}
 }
@@ -32,6 +32,7 @@ use tracing::trace;
 use super::{
 	base_pool::Transaction,
 	future::WaitingTransaction,
+	ordering::{PriorityOrdering, ReadyCandidate, TransactionOrdering},
 	tracked_map::{self, TrackedMap},
 };
 
@@ -162,11 +163,25 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 	/// In such case the entire subgraph of transactions that depend on the reported one will be
 	/// skipped.
 	pub fn get(&self) -> BestIterator<Hash, Ex> {
+		self.get_ordered(Arc::new(PriorityOrdering))
+	}
+
+	/// Returns an iterator of ready transactions, using given policy to order them.
+	///
+	/// Transactions are still returned in the order of their dependencies, the `ordering` is
+	/// only used to select the next transaction among these that have all requirements satisfied
+	/// (steps 2-4 of [`Self::get`]).
+	pub fn get_ordered(&self, ordering: Arc<dyn TransactionOrdering>) -> BestIterator<Hash, Ex> {
 		BestIterator {
 			all: self.ready.clone_map(),
-			best: self.best.clone(),
+			best: self
+				.best
+				.iter()
+				.map(|tx| BestRef::new(tx.clone(), 0, ordering.clone()))
+				.collect(),
 			awaiting: Default::default(),
 			invalid: Default::default(),
+			ordering,
 		}
 	}
 
@@ -481,21 +496,79 @@ impl<Hash: hash::Hash + Member + Serialize, Ex> ReadyTransactions<Hash, Ex> {
 	}
 }
 
-/// Iterator of ready transactions ordered by priority.
+/// A transaction reference in the best set of the iterator, ordered by the iterator's policy.
+struct BestRef<Hash, Ex> {
+	transaction: TransactionRef<Hash, Ex>,
+	/// Number of already provided transactions of the dependency chain.
+	chained: usize,
+	ordering: Arc<dyn TransactionOrdering>,
+}
+
+impl<Hash, Ex> BestRef<Hash, Ex> {
+	fn new(
+		transaction: TransactionRef<Hash, Ex>,
+		chained: usize,
+		ordering: Arc<dyn TransactionOrdering>,
+	) -> Self {
+		Self { transaction, chained, ordering }
+	}
+
+	fn candidate(&self) -> ReadyCandidate {
+		let tx = &self.transaction.transaction;
+		ReadyCandidate {
+			priority: tx.priority,
+			valid_till: tx.valid_till,
+			insertion_id: self.transaction.insertion_id,
+			bytes: tx.bytes,
+			source: &tx.source,
+			provides: &tx.provides,
+			chained: self.chained,
+		}
+	}
+}
+
+impl<Hash, Ex> Ord for BestRef<Hash, Ex> {
+	fn cmp(&self, other: &Self) -> cmp::Ordering {
+		self.ordering
+			.cmp(&self.candidate(), &other.candidate())
+			.then_with(|| other.transaction.insertion_id.cmp(&self.transaction.insertion_id))
+	}
+}
+
+impl<Hash, Ex> PartialOrd for BestRef<Hash, Ex> {
+	fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
+		Some(self.cmp(other))
+	}
+}
+
+impl<Hash, Ex> PartialEq for BestRef<Hash, Ex> {
+	fn eq(&self, other: &Self) -> bool {
+		self.cmp(other) == cmp::Ordering::Equal
+	}
+}
+impl<Hash, Ex> Eq for BestRef<Hash, Ex> {}
+
+/// Iterator of ready transactions ordered by the configured [`TransactionOrdering`].
 pub struct BestIterator<Hash, Ex> {
 	all: HashMap<Hash, ReadyTx<Hash, Ex>>,
 	awaiting: HashMap<Hash, (usize, TransactionRef<Hash, Ex>)>,
-	best: BTreeSet<TransactionRef<Hash, Ex>>,
+	best: BTreeSet<BestRef<Hash, Ex>>,
 	invalid: HashSet<Hash>,
+	ordering: Arc<dyn TransactionOrdering>,
 }
 
 impl<Hash: hash::Hash + Member, Ex> BestIterator<Hash, Ex> {
 	/// Depending on number of satisfied requirements insert given ref
 	/// either to awaiting set or to best set.
-	fn best_or_awaiting(&mut self, satisfied: usize, tx_ref: TransactionRef<Hash, Ex>) {
+	fn best_or_awaiting(
+		&mut self,
+		satisfied: usize,
+		chained: usize,
+		tx_ref: TransactionRef<Hash, Ex>,
+	) {
 		if satisfied >= tx_ref.transaction.requires.len() {
 			// If we have satisfied all deps insert to best
-			self.best.insert(tx_ref);
+			self.best.insert(BestRef::new(tx_ref, chained, self.ordering.clone()));
 		} else {
 			// otherwise we're still awaiting for some deps
 			self.awaiting.insert(tx_ref.transaction.hash.clone(), (satisfied, tx_ref));
@@ -536,9 +609,8 @@ impl<Hash: hash::Hash + Member, Ex> Iterator for BestIterator<Hash, Ex> {
 
 	fn next(&mut self) -> Option<Self::Item> {
 		loop {
-			let best = self.best.iter().next_back()?.clone();
-			let best = self.best.take(&best)?;
-			let tx_hash = &best.transaction.hash;
+			let best = self.best.pop_last()?;
+			let tx_hash = &best.transaction.transaction.hash;
 
 			// Check if the transaction was marked invalid.
 			if self.invalid.contains(tx_hash) {
@@ -569,11 +641,11 @@ impl<Hash: hash::Hash + Member, Ex> Iterator for BestIterator<Hash, Ex> {
 						.map(|next| (next.requires_offset + 1, next.transaction.clone()))
 				};
 				if let Some((satisfied, tx_ref)) = res {
-					self.best_or_awaiting(satisfied, tx_ref)
+					self.best_or_awaiting(satisfied, best.chained + 1, tx_ref)
 				}
 			}
 
-			return Some(best.transaction)
+			return Some(best.transaction.transaction)
 		}
 	}
 }
@@ -824,4 +896,66 @@ mod tests {
 		assert!(!tx1_unlocks.contains(&tx3.hash));
 		assert!(tx1_unlocks.contains(&tx4.hash));
 	}
+
+	#[test]
+	fn should_order_ready_transactions_with_given_policy() {
+		use crate::graph::ordering::{DeprioritizeOrdering, FifoOrdering, RoundRobinOrdering};
+
+		let chained = |sender: u8, nonce: u8, priority| {
+			let mut tx = tx(sender * 10 + nonce);
+			tx.priority = priority;
+			tx.requires = if nonce > 0 { vec![vec![sender, nonce - 1]] } else { vec![] };
+			tx.provides = vec![vec![sender, nonce]];
+			tx
+		};
+		let mut ready = ReadyTransactions::default();
+		// sender 2 submits first, but with a lower priority
+		import(&mut ready, chained(2, 0, 1)).unwrap();
+		import(&mut ready, chained(2, 1, 1)).unwrap();
+		import(&mut ready, chained(1, 0, 10)).unwrap();
+		import(&mut ready, chained(1, 1, 10)).unwrap();
+		import(&mut ready, chained(1, 2, 10)).unwrap();
+
+		let order = |ordering: Arc<dyn TransactionOrdering>| {
+			ready.get_ordered(ordering).map(|tx| tx.data[0]).collect::<Vec<_>>()
+		};
+
+		assert_eq!(order(Arc::new(PriorityOrdering)), vec![10, 11, 12, 20, 21]);
+		assert_eq!(order(Arc::new(FifoOrdering)), vec![20, 21, 10, 11, 12]);
+		assert_eq!(order(Arc::new(RoundRobinOrdering::default())), vec![10, 20, 11, 21, 12]);
+		assert_eq!(
+			order(Arc::new(DeprioritizeOrdering::new(PriorityOrdering, |tx| {
+				tx.provides.iter().any(|tag| tag[0] == 1)
+			}))),
+			vec![20, 21, 10, 11, 12]
+		);
+	}
+
+	#[test]
+	fn fifo_ordering_handles_transactions_without_timestamp() {
+		use crate::graph::ordering::FifoOrdering;
+		use std::time::{Duration, Instant};
+
+		let now = Instant::now();
+		let independent = |id: u8, timestamp: Option<Instant>| {
+			let mut tx = tx(id);
+			tx.requires = vec![];
+			tx.provides = vec![vec![id]];
+			tx.source.timestamp = timestamp;
+			tx
+		};
+		let mut ready = ReadyTransactions::default();
+		import(&mut ready, independent(1, None)).unwrap();
+		import(&mut ready, independent(2, Some(now + Duration::from_secs(1)))).unwrap();
+		import(&mut ready, independent(3, None)).unwrap();
+		import(&mut ready, independent(4, Some(now))).unwrap();
+
+		assert_eq!(
+			ready
+				.get_ordered(Arc::new(FifoOrdering))
+				.map(|tx| tx.data[0])
+				.collect::<Vec<_>>(),
+			vec![4, 2, 1, 3]
+		);
+	}
 }
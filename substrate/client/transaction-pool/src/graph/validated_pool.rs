@@ -768,9 +768,9 @@ impl<B: ChainApi, L: EventHandler<B>> ValidatedPool<B, L> {
 		invalid
 	}
 
-	/// Get an iterator for ready transactions ordered by priority
+	/// Get an iterator for ready transactions ordered by the configured policy
 	pub fn ready(&self) -> impl ReadyTransactions<Item = TransactionFor<B>> + Send {
-		self.pool.read().ready()
+		self.pool.read().ready_ordered(self.options.ordering.clone())
 	}
 
 	/// Returns a Vec of hashes and extrinsics in the future pool.
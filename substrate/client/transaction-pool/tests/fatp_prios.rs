@@ -22,7 +22,7 @@ pub mod fatp_common;
 
 use fatp_common::{invalid_hash, new_best_block_event, TestPoolBuilder, LOG_TARGET, SOURCE};
 use futures::{executor::block_on, FutureExt};
-use sc_transaction_pool::ChainApi;
+use sc_transaction_pool::{ChainApi, FifoOrdering, RoundRobinOrdering};
 use sc_transaction_pool_api::{
 	error::Error as TxPoolError, LocalTransactionPool, MaintainedTransactionPool, TransactionPool,
 	TransactionStatus,
@@ -559,3 +559,55 @@ fn fatp_prios_submit_local_full_mempool_higher_prio_is_accepted() {
 	assert_ready_iterator!(header02.hash(), pool, [xt3, xt2]);
 	assert_ready_iterator!(header03.hash(), pool, [xt5, xt4]);
 }
+
+#[test]
+fn fatp_prios_round_robin_ordering_alternates_senders() {
+	sp_tracing::try_init_simple();
+
+	let builder = TestPoolBuilder::new();
+	let (pool, api, _) = builder.with_ordering(RoundRobinOrdering::default()).build();
+	api.set_nonce(api.genesis_hash(), Bob.into(), 300);
+
+	let header01 = api.push_block(1, vec![], true);
+	let event = new_best_block_event(&pool, None, header01.hash());
+	block_on(pool.maintain(event));
+
+	let xts0 = (200..203).map(|nonce| uxt(Alice, nonce)).collect::<Vec<_>>();
+	let xts1 = (300..302).map(|nonce| uxt(Bob, nonce)).collect::<Vec<_>>();
+	xts0.iter().for_each(|xt| api.set_priority(xt, 10));
+	xts1.iter().for_each(|xt| api.set_priority(xt, 1));
+
+	for xt in xts0.iter().chain(xts1.iter()) {
+		block_on(pool.submit_one(header01.hash(), SOURCE, xt.clone())).unwrap();
+	}
+
+	assert_pool_status!(header01.hash(), &pool, 5, 0);
+	assert_ready_iterator!(header01.hash(), pool, [xts0[0], xts1[0], xts0[1], xts1[1], xts0[2]]);
+}
+
+#[test]
+fn fatp_prios_fifo_ordering_ignores_priority() {
+	sp_tracing::try_init_simple();
+
+	let builder = TestPoolBuilder::new();
+	let (pool, api, _) = builder.with_ordering(FifoOrdering).build();
+	api.set_nonce(api.genesis_hash(), Bob.into(), 300);
+
+	let header01 = api.push_block(1, vec![], true);
+	let event = new_best_block_event(&pool, None, header01.hash());
+	block_on(pool.maintain(event));
+
+	let xt0 = uxt(Bob, 300);
+	let xt1 = uxt(Alice, 200);
+	let xt2 = uxt(Bob, 301);
+	api.set_priority(&xt0, 1);
+	api.set_priority(&xt1, 10);
+	api.set_priority(&xt2, 1);
+
+	for xt in [&xt0, &xt1, &xt2] {
+		block_on(pool.submit_one(header01.hash(), SOURCE, xt.clone())).unwrap();
+	}
+
+	assert_pool_status!(header01.hash(), &pool, 3, 0);
+	assert_ready_iterator!(header01.hash(), pool, [xt0, xt1, xt2]);
+}
@@ -47,7 +47,9 @@ use frame_support::{
 	BoundedSlice, BoundedVec, ConsensusEngineId, Parameter,
 };
 use log;
-use sp_consensus_aura::{AuthorityIndex, ConsensusLog, Slot, AURA_ENGINE_ID};
+use sp_consensus_aura::{
+	AuthorityIndex, ConsensusLog, Slot, AURA_ENGINE_ID, AURA_SLOT_CLAIM_ENGINE_ID,
+};
 use sp_runtime::{
 	generic::DigestItem,
 	traits::{IsMember, Member, SaturatedConversion, Saturating, Zero},
@@ -79,12 +81,33 @@ impl<T: pallet_timestamp::Config> Get<T::Moment> for MinimumPeriodTimesTwo<T> {
 #[frame_support::pallet]
 pub mod pallet {
 	use super::*;
-	use frame_support::pallet_prelude::*;
+	use frame_support::{derive_impl, pallet_prelude::*};
 	use frame_system::pallet_prelude::*;
 
-	#[pallet::config]
+	/// Default preludes for [`Config`].
+	pub mod config_preludes {
+		use super::*;
+		use frame_support::traits::{ConstBool, ConstU32};
+
+		/// Default prelude sensible to be used in a testing environment.
+		pub struct TestDefaultConfig;
+
+		#[derive_impl(frame_system::config_preludes::TestDefaultConfig, no_aggregated_types)]
+		impl frame_system::DefaultConfig for TestDefaultConfig {}
+
+		#[frame_support::register_default_impl(TestDefaultConfig)]
+		impl DefaultConfig for TestDefaultConfig {
+			type MaxAuthorities = ConstU32<32>;
+			type DisabledValidators = ();
+			type AllowMultipleBlocksPerSlot = ConstBool<false>;
+			type MaxBlocksPerSlot = ConstU32<1>;
+		}
+	}
+
+	#[pallet::config(with_default)]
 	pub trait Config: pallet_timestamp::Config + frame_system::Config {
 		/// The identifier type for an authority.
+		#[pallet::no_default]
 		type AuthorityId: Member
 			+ Parameter
 			+ RuntimeAppPublic
@@ -112,11 +135,31 @@ pub mod pallet {
 		/// using the same slot.
 		type AllowMultipleBlocksPerSlot: Get<bool>;
 
+		/// The maximum number of blocks an author may build on the same slot.
+		///
+		/// Only taken into account if [`Config::AllowMultipleBlocksPerSlot`] is `true`, otherwise
+		/// exactly one block per slot is allowed. Runtimes which already limit the number of
+		/// blocks per slot in another pallet, e.g. through a consensus hook, can set this to
+		/// `u32::MAX`.
+		///
+		/// This value is exposed to the node through [`Pallet::max_blocks_per_slot`], which allows
+		/// the authoring node to split its slot into sub-slots. Blocks built on the same slot still
+		/// need to be at least [`pallet_timestamp::Config::MinimumPeriod`] apart, so
+		/// `MaxBlocksPerSlot * MinimumPeriod` must not exceed [`Config::SlotDuration`]. For
+		/// example, with [`MinimumPeriodTimesTwo`] at most two blocks fit into a slot. This is
+		/// checked by the `integrity_test` of this pallet.
+		///
+		/// Every block after the first one of a slot has to claim its index within the slot through
+		/// an [`AURA_SLOT_CLAIM_ENGINE_ID`] pre-runtime digest.
+		#[pallet::constant]
+		type MaxBlocksPerSlot: Get<u32>;
+
 		/// The slot duration Aura should run with, expressed in milliseconds.
 		/// The effective value of this type should not change while the chain is running.
 		///
 		/// For backwards compatibility either use [`MinimumPeriodTimesTwo`] or a const.
 		#[pallet::constant]
+		#[pallet::no_default]
 		type SlotDuration: Get<<Self as pallet_timestamp::Config>::Moment>;
 	}
 
@@ -129,8 +172,29 @@ pub mod pallet {
 			if let Some(new_slot) = Self::current_slot_from_digests() {
 				let current_slot = CurrentSlot::<T>::get();
 
+				let mut weight = T::DbWeight::get().reads_writes(2, 1);
+
 				if T::AllowMultipleBlocksPerSlot::get() {
 					assert!(current_slot <= new_slot, "Slot must not decrease");
+
+					let blocks_in_slot = if current_slot == new_slot {
+						BlocksInCurrentSlot::<T>::get().saturating_add(1)
+					} else {
+						1
+					};
+					assert!(
+						blocks_in_slot <= T::MaxBlocksPerSlot::get(),
+						"Too many blocks authored in the same slot",
+					);
+					if let Some(index) = Self::slot_claim_from_digests() {
+						assert_eq!(
+							index,
+							blocks_in_slot - 1,
+							"Slot claim does not match the blocks authored in the same slot",
+						);
+					}
+					BlocksInCurrentSlot::<T>::put(blocks_in_slot);
+					weight.saturating_accrue(T::DbWeight::get().reads_writes(1, 1));
 				} else {
 					assert!(current_slot < new_slot, "Slot must increase");
 				}
@@ -150,7 +214,7 @@ pub mod pallet {
 				// TODO [#3398] Generate offence report for all authorities that skipped their
 				// slots.
 
-				T::DbWeight::get().reads_writes(2, 1)
+				weight
 			} else {
 				T::DbWeight::get().reads(1)
 			}
@@ -160,6 +224,20 @@ pub mod pallet {
 		fn try_state(_: BlockNumberFor<T>) -> Result<(), sp_runtime::TryRuntimeError> {
 			Self::do_try_state()
 		}
+
+		fn integrity_test() {
+			// Blocks built on the same slot are authored in sub-slots, which need to be at least
+			// `MinimumPeriod` long for the timestamp inherent to be accepted.
+			let max_blocks_per_slot = Self::max_blocks_per_slot();
+			if max_blocks_per_slot > 1 {
+				assert!(
+					<T as pallet_timestamp::Config>::MinimumPeriod::get()
+						.saturating_mul(max_blocks_per_slot.into()) <=
+						T::SlotDuration::get(),
+					"`MaxBlocksPerSlot` blocks that are `MinimumPeriod` apart must fit into a slot",
+				);
+			}
+		}
 	}
 
 	/// The current authority set.
@@ -173,6 +251,12 @@ pub mod pallet {
 	#[pallet::storage]
 	pub type CurrentSlot<T: Config> = StorageValue<_, Slot, ValueQuery>;
 
+	/// The number of blocks authored in [`CurrentSlot`], including this one.
+	///
+	/// Only tracked if [`Config::AllowMultipleBlocksPerSlot`] is `true`.
+	#[pallet::storage]
+	pub type BlocksInCurrentSlot<T: Config> = StorageValue<_, u32, ValueQuery>;
+
 	#[pallet::genesis_config]
 	#[derive(frame_support::DefaultNoBound)]
 	pub struct GenesisConfig<T: Config> {
@@ -242,11 +326,36 @@ impl<T: Config> Pallet<T> {
 		None
 	}
 
+	/// Get the index of the block within its slot from the pre-runtime digests.
+	///
+	/// Returns `None` if the block doesn't claim an index, e.g. because it is the first block of
+	/// its slot.
+	fn slot_claim_from_digests() -> Option<u32> {
+		let digest = frame_system::Pallet::<T>::digest();
+		let pre_runtime_digests = digest.logs.iter().filter_map(|d| d.as_pre_runtime());
+		for (id, mut data) in pre_runtime_digests {
+			if id == AURA_SLOT_CLAIM_ENGINE_ID {
+				return u32::decode(&mut data).ok()
+			}
+		}
+
+		None
+	}
+
 	/// Determine the Aura slot-duration based on the Timestamp module configuration.
 	pub fn slot_duration() -> T::Moment {
 		T::SlotDuration::get()
 	}
 
+	/// The maximum number of blocks an author may build on the same slot.
+	pub fn max_blocks_per_slot() -> u32 {
+		if T::AllowMultipleBlocksPerSlot::get() {
+			T::MaxBlocksPerSlot::get().max(1)
+		} else {
+			1
+		}
+	}
+
 	/// Ensure the correctness of the state of this pallet.
 	///
 	/// This should be valid before or after each state transition of this pallet.
@@ -258,6 +367,11 @@ impl<T: Config> Pallet<T> {
 	/// If we don't allow for multiple blocks per slot, then the current slot must be less than the
 	/// maximal slot number. Otherwise, it can be arbitrary.
 	///
+	/// ## `BlocksInCurrentSlot`
+	///
+	/// If we allow for multiple blocks per slot, the number of blocks in the current slot must not
+	/// exceed `T::MaxBlocksPerSlot`.
+	///
 	/// ## `Authorities`
 	///
 	/// * The authorities must be non-empty.
@@ -278,6 +392,11 @@ impl<T: Config> Pallet<T> {
 				current_slot < u64::MAX,
 				"Current slot has reached maximum value and cannot be incremented further.",
 			);
+		} else {
+			frame_support::ensure!(
+				BlocksInCurrentSlot::<T>::get() <= T::MaxBlocksPerSlot::get(),
+				"Too many blocks authored in the current slot.",
+			);
 		}
 
 		let authorities_len =
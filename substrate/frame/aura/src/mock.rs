@@ -55,6 +55,7 @@ impl pallet_timestamp::Config for Test {
 parameter_types! {
 	static DisabledValidatorTestValue: Vec<AuthorityIndex> = Default::default();
 	pub static AllowMultipleBlocksPerSlot: bool = false;
+	pub static MaxBlocksPerSlot: u32 = u32::MAX;
 }
 
 pub struct MockDisabledValidators;
@@ -84,6 +85,7 @@ impl pallet_aura::Config for Test {
 	type DisabledValidators = MockDisabledValidators;
 	type MaxAuthorities = ConstU32<10>;
 	type AllowMultipleBlocksPerSlot = AllowMultipleBlocksPerSlot;
+	type MaxBlocksPerSlot = MaxBlocksPerSlot;
 	type SlotDuration = ConstU64<SLOT_DURATION>;
 }
 
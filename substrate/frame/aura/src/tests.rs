@@ -22,8 +22,8 @@
 use super::pallet;
 use crate::mock::{build_ext_and_execute_test, Aura, MockDisabledValidators, System, Test};
 use codec::Encode;
-use frame_support::traits::OnInitialize;
-use sp_consensus_aura::{Slot, AURA_ENGINE_ID};
+use frame_support::traits::{IntegrityTest, OnInitialize};
+use sp_consensus_aura::{Slot, AURA_ENGINE_ID, AURA_SLOT_CLAIM_ENGINE_ID};
 use sp_runtime::{Digest, DigestItem};
 
 #[test]
@@ -94,6 +94,84 @@ fn pallet_can_allow_unchanged_slot() {
 	});
 }
 
+#[test]
+#[should_panic(expected = "Too many blocks authored in the same slot")]
+fn pallet_limits_blocks_per_slot() {
+	build_ext_and_execute_test(vec![0, 1, 2, 3], || {
+		crate::mock::AllowMultipleBlocksPerSlot::set(true);
+		crate::mock::MaxBlocksPerSlot::set(2);
+		assert_eq!(Aura::max_blocks_per_slot(), 2);
+
+		let slot = Slot::from(1);
+		let pre_digest =
+			Digest { logs: vec![DigestItem::PreRuntime(AURA_ENGINE_ID, slot.encode())] };
+
+		System::reset_events();
+		System::initialize(&42, &System::parent_hash(), &pre_digest);
+
+		// two blocks in the same slot are fine, the third one exceeds the limit.
+		Aura::on_initialize(42);
+		Aura::on_initialize(42);
+		assert_eq!(pallet::BlocksInCurrentSlot::<Test>::get(), 2);
+		Aura::on_initialize(42);
+	});
+}
+
+fn slot_digest(slot: u64, index: Option<u32>) -> Digest {
+	let mut logs = vec![DigestItem::PreRuntime(AURA_ENGINE_ID, Slot::from(slot).encode())];
+	logs.extend(
+		index.map(|index| DigestItem::PreRuntime(AURA_SLOT_CLAIM_ENGINE_ID, index.encode())),
+	);
+	Digest { logs }
+}
+
+#[test]
+fn pallet_accepts_valid_slot_claims() {
+	build_ext_and_execute_test(vec![0, 1, 2, 3], || {
+		crate::mock::AllowMultipleBlocksPerSlot::set(true);
+		crate::mock::MaxBlocksPerSlot::set(3);
+
+		System::initialize(&42, &System::parent_hash(), &slot_digest(1, None));
+		Aura::on_initialize(42);
+		System::initialize(&43, &System::parent_hash(), &slot_digest(1, Some(1)));
+		Aura::on_initialize(43);
+		assert_eq!(pallet::BlocksInCurrentSlot::<Test>::get(), 2);
+
+		// The first block of a new slot doesn't claim an index.
+		System::initialize(&44, &System::parent_hash(), &slot_digest(2, None));
+		Aura::on_initialize(44);
+		assert_eq!(pallet::BlocksInCurrentSlot::<Test>::get(), 1);
+	});
+}
+
+#[test]
+#[should_panic(expected = "Slot claim does not match the blocks authored in the same slot")]
+fn pallet_rejects_invalid_slot_claim() {
+	build_ext_and_execute_test(vec![0, 1, 2, 3], || {
+		crate::mock::AllowMultipleBlocksPerSlot::set(true);
+		crate::mock::MaxBlocksPerSlot::set(3);
+
+		System::initialize(&42, &System::parent_hash(), &slot_digest(1, None));
+		Aura::on_initialize(42);
+		System::initialize(&43, &System::parent_hash(), &slot_digest(1, Some(2)));
+		Aura::on_initialize(43);
+	});
+}
+
+#[test]
+#[should_panic(
+	expected = "`MaxBlocksPerSlot` blocks that are `MinimumPeriod` apart must fit into a slot"
+)]
+fn integrity_test_limits_blocks_per_slot_by_minimum_period() {
+	crate::mock::AllowMultipleBlocksPerSlot::set(true);
+
+	// A slot of two milliseconds fits two blocks that are one millisecond apart, but not three.
+	crate::mock::MaxBlocksPerSlot::set(2);
+	Aura::integrity_test();
+	crate::mock::MaxBlocksPerSlot::set(3);
+	Aura::integrity_test();
+}
+
 #[test]
 #[should_panic(expected = "Slot must not decrease")]
 fn pallet_always_rejects_decreasing_slot() {
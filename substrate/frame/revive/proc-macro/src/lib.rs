@@ -508,18 +508,33 @@ fn expand_functions(def: &EnvDef) -> TokenStream2 {
 			.sync_from_executor(memory.gas())
 			.map_err(TrapReason::from)?;
 
-		// This is the overhead to call an empty syscall that always needs to be charged.
-		self.charge_gas(crate::wasm::RuntimeCosts::HostFn).map_err(TrapReason::from)?;
-
 		// They will be mapped to variable names by the syscall specific code.
 		let (__a0__, __a1__, __a2__, __a3__, __a4__, __a5__) = memory.read_input_regs();
 
+		// Report the syscall to the tracer before any of its costs are charged.
+		crate::tracing::if_tracing(|tracer| {
+			tracer.enter_opcode(
+				memory.program_counter(),
+				__syscall_symbol__,
+				self.ext.gas_meter().gas_left(),
+				&[__a0__, __a1__, __a2__, __a3__, __a4__, __a5__],
+				&|| memory.memory_snapshot(),
+			)
+		});
+
 		// Execute the syscall specific logic in a closure so that the gas metering code is always executed.
-		let result = (|| match __syscall_symbol__ {
-			#( #impls )*
-			_ => Err(TrapReason::SupervisorError(Error::<E::T>::InvalidSyscall.into()))
+		let result = (|| {
+			// This is the overhead to call an empty syscall that always needs to be charged.
+			self.charge_gas(crate::wasm::RuntimeCosts::HostFn).map_err(TrapReason::from)?;
+
+			match __syscall_symbol__ {
+				#( #impls )*
+				_ => Err(TrapReason::SupervisorError(Error::<E::T>::InvalidSyscall.into()))
+			}
 		})();
 
+		crate::tracing::if_tracing(|tracer| tracer.exit_opcode(self.ext.gas_meter().gas_left()));
+
 		// Write gas from pallet-revive into polkavm after leaving the host function.
 		let gas = self.ext.gas_meter_mut().sync_to_executor(__gas_left_before__).map_err(TrapReason::from)?;
 		memory.set_gas(gas.into());
@@ -0,0 +1,235 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+//! Ethereum pub/sub JSON-RPC methods.
+
+use crate::{client::ChainNotification, *};
+use futures::StreamExt;
+use jsonrpsee::{proc_macros::rpc, PendingSubscriptionSink};
+use sc_rpc::utils::{BoundedVecDeque, PendingSubscription};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::error::RecvError;
+
+/// The maximum number of notifications buffered for a subscriber before it is dropped.
+const MAX_BUFFERED_NOTIFICATIONS: usize = 1024;
+
+/// The kind of events an `eth_subscribe` subscription listens to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SubscriptionKind {
+	/// New block headers, including the ones of blocks that are part of a reorg.
+	NewHeads,
+	/// Logs matching the subscription filter.
+	Logs,
+	/// Hashes of the transactions submitted through this server.
+	NewPendingTransactions,
+}
+
+/// An item sent to `eth_subscribe` subscribers.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SubscriptionItem {
+	/// A new block, sent to [`SubscriptionKind::NewHeads`] subscribers.
+	Header(Box<Block>),
+	/// A log, sent to [`SubscriptionKind::Logs`] subscribers.
+	Log(Log),
+	/// A transaction hash, sent to [`SubscriptionKind::NewPendingTransactions`] subscribers.
+	TransactionHash(H256),
+}
+
+/// Ethereum pub/sub JSON-RPC apis.
+#[rpc(server, client)]
+pub trait EthPubSubRpc {
+	/// Subscribe to new heads, logs or pending transactions.
+	///
+	/// ## References
+	///
+	/// - <https://geth.ethereum.org/docs/interacting-with-geth/rpc/pubsub>
+	#[subscription(
+		name = "eth_subscribe" => "eth_subscription",
+		unsubscribe = "eth_unsubscribe",
+		item = SubscriptionItem
+	)]
+	fn subscribe(&self, kind: SubscriptionKind, filter: Option<Filter>);
+}
+
+pub struct EthPubSubServerImpl {
+	client: client::Client,
+}
+
+impl EthPubSubServerImpl {
+	pub fn new(client: client::Client) -> Self {
+		Self { client }
+	}
+}
+
+impl EthPubSubRpcServer for EthPubSubServerImpl {
+	fn subscribe(
+		&self,
+		pending: PendingSubscriptionSink,
+		kind: SubscriptionKind,
+		filter: Option<Filter>,
+	) {
+		let filter = filter.unwrap_or_default();
+		let receiver = self.client.subscribe_notifications();
+
+		let stream = futures::stream::unfold(receiver, |mut receiver| async move {
+			loop {
+				match receiver.recv().await {
+					Ok(notification) => return Some((notification, receiver)),
+					Err(RecvError::Lagged(skipped)) => {
+						log::warn!(
+							target: LOG_TARGET,
+							"eth_subscribe subscriber lagged behind, {skipped} notifications skipped"
+						);
+					},
+					Err(RecvError::Closed) => return None,
+				}
+			}
+		})
+		.flat_map(move |notification| {
+			futures::stream::iter(subscription_items(kind, &filter, notification))
+		})
+		.boxed();
+
+		tokio::spawn(
+			PendingSubscription::from(pending)
+				.pipe_from_stream(stream, BoundedVecDeque::new(MAX_BUFFERED_NOTIFICATIONS)),
+		);
+	}
+}
+
+/// Convert a chain notification into the items sent to a subscriber of the given `kind`.
+fn subscription_items(
+	kind: SubscriptionKind,
+	filter: &Filter,
+	notification: ChainNotification,
+) -> Vec<SubscriptionItem> {
+	match (kind, notification) {
+		(SubscriptionKind::NewHeads, ChainNotification::NewHead(block)) =>
+			vec![SubscriptionItem::Header(block)],
+		(SubscriptionKind::Logs, ChainNotification::Logs(logs)) => logs
+			.into_iter()
+			.filter(|log| log_matches_filter(filter, log))
+			.map(SubscriptionItem::Log)
+			.collect(),
+		(
+			SubscriptionKind::NewPendingTransactions,
+			ChainNotification::NewPendingTransaction(hash),
+		) => vec![SubscriptionItem::TransactionHash(hash)],
+		_ => vec![],
+	}
+}
+
+/// Check whether the log matches the address and topics of the filter.
+///
+/// The block range of the filter is ignored, since subscriptions only receive new logs.
+fn log_matches_filter(filter: &Filter, log: &Log) -> bool {
+	let address_matches = match &filter.address {
+		None => true,
+		Some(AddressOrAddresses::Address(address)) => &log.address == address,
+		Some(AddressOrAddresses::Addresses(addresses)) =>
+			addresses.is_empty() || addresses.contains(&log.address),
+	};
+
+	let topics_matches = filter.topics.iter().flatten().enumerate().all(|(i, topic)| {
+		let Some(log_topic) = log.topics.get(i) else { return false };
+		match topic {
+			FilterTopic::Single(hash) => log_topic == hash,
+			FilterTopic::Multiple(hashes) => hashes.is_empty() || hashes.contains(log_topic),
+		}
+	});
+
+	address_matches && topics_matches
+}
+
+#[cfg(test)]
+mod test {
+	use super::*;
+
+	#[test]
+	fn log_matches_filter_works() {
+		let log = Log {
+			address: H160::from_low_u64_be(1),
+			topics: vec![H256::from_low_u64_be(1), H256::from_low_u64_be(2)],
+			..Default::default()
+		};
+
+		let filters = [
+			(Filter::default(), true),
+			(
+				Filter {
+					address: Some(AddressOrAddresses::Address(H160::from_low_u64_be(1))),
+					..Default::default()
+				},
+				true,
+			),
+			(
+				Filter {
+					address: Some(AddressOrAddresses::Address(H160::from_low_u64_be(2))),
+					..Default::default()
+				},
+				false,
+			),
+			(
+				Filter {
+					address: Some(AddressOrAddresses::Addresses(vec![
+						H160::from_low_u64_be(2),
+						H160::from_low_u64_be(1),
+					])),
+					topics: Some(vec![FilterTopic::Single(H256::from_low_u64_be(1))]),
+					..Default::default()
+				},
+				true,
+			),
+			(
+				Filter {
+					topics: Some(vec![
+						FilterTopic::Multiple(vec![]),
+						FilterTopic::Multiple(vec![
+							H256::from_low_u64_be(3),
+							H256::from_low_u64_be(2),
+						]),
+					]),
+					..Default::default()
+				},
+				true,
+			),
+			(
+				Filter {
+					topics: Some(vec![FilterTopic::Single(H256::from_low_u64_be(2))]),
+					..Default::default()
+				},
+				false,
+			),
+			(
+				Filter {
+					topics: Some(vec![
+						FilterTopic::Multiple(vec![]),
+						FilterTopic::Multiple(vec![]),
+						FilterTopic::Single(H256::from_low_u64_be(3)),
+					]),
+					..Default::default()
+				},
+				false,
+			),
+		];
+
+		for (filter, expected) in filters {
+			assert_eq!(log_matches_filter(&filter, &log), expected, "filter: {filter:?}");
+		}
+	}
+}
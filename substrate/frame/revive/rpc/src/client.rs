@@ -25,15 +25,15 @@ use storage_api::StorageApi;
 
 use crate::{
 	subxt_client::{self, revive::calls::types::EthTransact, SrcChainConfig},
-	BlockInfoProvider, BlockTag, FeeHistoryProvider, ReceiptProvider, SubxtBlockInfoProvider,
-	TracerType, TransactionInfo, LOG_TARGET,
+	BlockInfoProvider, BlockTag, FeeHistoryProvider, FilterChanges, FilterProvider,
+	ReceiptProvider, SubxtBlockInfoProvider, TracerType, TransactionInfo, LOG_TARGET,
 };
 use jsonrpsee::types::{error::CALL_EXECUTION_FAILED_CODE, ErrorObjectOwned};
 use pallet_revive::{
 	evm::{
 		decode_revert_reason, Block, BlockNumberOrTag, BlockNumberOrTagOrHash, FeeHistoryResult,
-		Filter, GenericTransaction, Log, ReceiptInfo, SyncingProgress, SyncingStatus, Trace,
-		TransactionSigned, TransactionTrace, H256, U256,
+		Filter, FilterResults, GenericTransaction, Log, ReceiptInfo, SyncingProgress,
+		SyncingStatus, Trace, TransactionSigned, TransactionTrace, H256, U256,
 	},
 	EthTransactError,
 };
@@ -53,6 +53,7 @@ use subxt::{
 	Config, OnlineClient,
 };
 use thiserror::Error;
+use tokio::sync::broadcast;
 
 /// The substrate block type.
 pub type SubstrateBlock = subxt::blocks::Block<SrcChainConfig, OnlineClient<SrcChainConfig>>;
@@ -69,6 +70,24 @@ pub type SubstrateBlockHash = <SrcChainConfig as Config>::Hash;
 /// The runtime balance type.
 pub type Balance = u128;
 
+/// The capacity of the channel used to publish [`ChainNotification`]s.
+const NOTIFICATION_CHANNEL_CAPACITY: usize = 1024;
+
+/// The maximum number of blocks walked back to find the retracted blocks of a reorg.
+const MAX_REORG_DEPTH: usize = 256;
+
+/// A notification published to the pub/sub subscribers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChainNotification {
+	/// A new best block.
+	NewHead(Box<Block>),
+	/// The logs of a new best block, or of a block retracted by a reorg.
+	/// Logs of retracted blocks have `removed` set to `true`.
+	Logs(Vec<Log>),
+	/// The hash of a transaction submitted through this server.
+	NewPendingTransaction(H256),
+}
+
 /// The subscription type used to listen to new blocks.
 #[derive(Debug, Clone, Copy)]
 pub enum SubscriptionType {
@@ -138,6 +157,12 @@ pub enum ClientError {
 	/// Failed to filter logs.
 	#[error("Failed to filter logs")]
 	LogFilterFailed(#[from] anyhow::Error),
+	/// The filter was not found.
+	#[error("filter not found")]
+	FilterNotFound,
+	/// The filter missed some changes and was uninstalled.
+	#[error("filter missed some changes and was uninstalled, install a new one")]
+	FilterLagged,
 }
 
 const REVERT_CODE: i32 = 3;
@@ -180,8 +205,10 @@ pub struct Client {
 	receipt_provider: ReceiptProvider,
 	block_provider: SubxtBlockInfoProvider,
 	fee_history_provider: FeeHistoryProvider,
+	filter_provider: FilterProvider,
 	chain_id: u64,
 	max_block_weight: Weight,
+	notifications: broadcast::Sender<ChainNotification>,
 }
 
 /// Fetch the chain ID from the substrate chain.
@@ -239,6 +266,7 @@ impl Client {
 	) -> Result<Self, ClientError> {
 		let (chain_id, max_block_weight) =
 			tokio::try_join!(chain_id(&api), max_block_weight(&api))?;
+		let (notifications, _) = broadcast::channel(NOTIFICATION_CHANNEL_CAPACITY);
 
 		Ok(Self {
 			api,
@@ -247,8 +275,10 @@ impl Client {
 			receipt_provider,
 			block_provider,
 			fee_history_provider: FeeHistoryProvider::default(),
+			filter_provider: FilterProvider::default(),
 			chain_id,
 			max_block_weight,
+			notifications,
 		})
 	}
 
@@ -347,6 +377,12 @@ impl Client {
 
 				let evm_block =
 					self.evm_block_from_receipts(&block, &receipts, signed_txs, false).await;
+
+				if let SubscriptionType::BestBlocks = subscription_type {
+					let previous = self.block_provider.latest_block().await;
+					self.notify_new_best_block(&previous, &block, &evm_block, &receipts).await;
+				}
+
 				self.block_provider.update_latest(block, subscription_type).await;
 
 				self.fee_history_provider.update_fee_history(&evm_block, &receipts).await;
@@ -359,6 +395,107 @@ impl Client {
 		}
 	}
 
+	/// Subscribe to the notifications used by the pub/sub API.
+	pub fn subscribe_notifications(&self) -> broadcast::Receiver<ChainNotification> {
+		self.notifications.subscribe()
+	}
+
+	/// Notify the pub/sub subscribers that a transaction was submitted.
+	pub fn notify_pending_transaction(&self, hash: H256) {
+		let _ = self.notifications.send(ChainNotification::NewPendingTransaction(hash));
+	}
+
+	/// Handle a new best block, notifying the pub/sub subscribers.
+	///
+	/// If the new best block does not build on top of the `previous` best block, the receipts of
+	/// the blocks enacted between the fork point and the new best block are stored, whether or
+	/// not anyone is subscribed. The logs of the retracted blocks are then published, with
+	/// `removed` set to `true`, followed by the enacted blocks, ancestor first.
+	///
+	/// Nothing is published if the new best block is the `previous` best block.
+	async fn notify_new_best_block(
+		&self,
+		previous: &Arc<SubstrateBlock>,
+		block: &SubstrateBlock,
+		evm_block: &Block,
+		receipts: &[ReceiptInfo],
+	) {
+		if block.hash() == previous.hash() {
+			return;
+		}
+
+		let mut retracted_logs = vec![];
+		let mut enacted = vec![];
+		if block.header().parent_hash != previous.hash() {
+			let route = match self.tree_route(previous.clone(), block.header().parent_hash).await {
+				Ok(route) => route,
+				Err(err) => {
+					log::warn!(target: LOG_TARGET, "Failed to compute the reorg route: {err:?}");
+					TreeRoute::default()
+				},
+			};
+
+			for enacted_block in route.enacted {
+				let (signed_txs, receipts): (Vec<_>, Vec<_>) = match self
+					.receipt_provider
+					.insert_block_receipts(&enacted_block)
+					.await
+				{
+					Ok(receipts) => receipts.into_iter().unzip(),
+					Err(err) => {
+						log::warn!(target: LOG_TARGET, "Failed to extract receipts of enacted block {}: {err:?}", enacted_block.hash());
+						continue;
+					},
+				};
+				if self.notifications.receiver_count() > 0 {
+					let evm_block = self
+						.evm_block_from_receipts(&enacted_block, &receipts, signed_txs, false)
+						.await;
+					enacted.push((evm_block, logs_of(&receipts)));
+				}
+			}
+
+			if self.notifications.receiver_count() == 0 {
+				return;
+			}
+
+			for retracted_block in route.retracted {
+				match self.receipt_provider.receipts_from_block(&retracted_block).await {
+					Ok(receipts) => retracted_logs
+						.push(receipts.into_iter().flat_map(|(_, receipt)| receipt.logs).collect()),
+					Err(err) => {
+						log::warn!(target: LOG_TARGET, "Failed to extract receipts of retracted block {}: {err:?}", retracted_block.hash());
+					},
+				}
+			}
+		}
+
+		if self.notifications.receiver_count() == 0 {
+			return;
+		}
+		enacted.push((evm_block.clone(), logs_of(receipts)));
+
+		for notification in reorg_notifications(retracted_logs, enacted) {
+			let _ = self.notifications.send(notification);
+		}
+	}
+
+	/// Compute the route from the `previous` best block to the block with hash `new_parent`.
+	async fn tree_route(
+		&self,
+		previous: Arc<SubstrateBlock>,
+		new_parent: H256,
+	) -> Result<TreeRoute<Arc<SubstrateBlock>>, ClientError> {
+		let fetch_block = |hash: H256| async move {
+			self.block_provider
+				.block_by_hash(&hash)
+				.await?
+				.ok_or(ClientError::BlockNotFound)
+		};
+
+		tree_route(previous, fetch_block(new_parent).await?, fetch_block).await
+	}
+
 	/// Cache old blocks up to the given block number.
 	pub async fn subscribe_and_cache_blocks(&self, index_last_n_blocks: SubstrateBlockNumber) {
 		let last = self.latest_block().await.number().saturating_sub(1);
@@ -715,6 +852,49 @@ impl Client {
 		Ok(logs)
 	}
 
+	/// Install a log filter and return its id.
+	pub async fn new_filter(&self, filter: Filter) -> U256 {
+		let latest = self.block_provider.latest_block_number().await;
+		self.filter_provider.new_log_filter(filter, latest).await
+	}
+
+	/// Install a new block filter and return its id.
+	pub async fn new_block_filter(&self) -> U256 {
+		self.filter_provider.new_block_filter().await
+	}
+
+	/// Install a new pending transaction filter and return its id.
+	pub async fn new_pending_transaction_filter(&self) -> U256 {
+		self.filter_provider.new_pending_transaction_filter().await
+	}
+
+	/// Feed the installed filters with the chain notifications, and remove the expired ones.
+	pub async fn update_filters(&self) {
+		self.filter_provider.run(self.subscribe_notifications()).await
+	}
+
+	/// Uninstall the filter with the given id.
+	pub async fn uninstall_filter(&self, filter_id: U256) -> bool {
+		self.filter_provider.uninstall(filter_id).await
+	}
+
+	/// Get the changes of the filter with the given id since its last poll.
+	pub async fn filter_changes(&self, filter_id: U256) -> Result<FilterResults, ClientError> {
+		let latest = self.block_provider.latest_block_number().await;
+		match self.filter_provider.poll(filter_id, latest).await? {
+			FilterChanges::Logs(None) => Ok(FilterResults::Logs(vec![])),
+			FilterChanges::Logs(Some(filter)) =>
+				Ok(FilterResults::Logs(self.logs(Some(filter)).await?)),
+			FilterChanges::Hashes(hashes) => Ok(FilterResults::Hashes(hashes)),
+		}
+	}
+
+	/// Get all the logs matching the log filter with the given id.
+	pub async fn filter_logs(&self, filter_id: U256) -> Result<Vec<Log>, ClientError> {
+		let filter = self.filter_provider.log_filter(filter_id).await?;
+		self.logs(Some(filter)).await
+	}
+
 	pub async fn fee_history(
 		&self,
 		block_count: u32,
@@ -730,3 +910,229 @@ impl Client {
 			.await
 	}
 }
+
+/// The minimal view of a block needed to compute a [`TreeRoute`].
+pub(crate) trait RouteBlock {
+	/// Returns the block hash.
+	fn hash(&self) -> H256;
+	/// Returns the block number.
+	fn number(&self) -> SubstrateBlockNumber;
+	/// Returns the hash of the parent block.
+	fn parent_hash(&self) -> H256;
+}
+
+impl RouteBlock for Arc<SubstrateBlock> {
+	fn hash(&self) -> H256 {
+		SubstrateBlock::hash(self)
+	}
+	fn number(&self) -> SubstrateBlockNumber {
+		SubstrateBlock::number(self)
+	}
+	fn parent_hash(&self) -> H256 {
+		self.header().parent_hash
+	}
+}
+
+/// The blocks to leave and to enter when switching between two chains.
+#[derive(Debug)]
+pub(crate) struct TreeRoute<B> {
+	/// Blocks of the old chain that are not part of the new one, starting from the old tip.
+	pub retracted: Vec<B>,
+	/// Blocks of the new chain that are not part of the old one, ancestor first.
+	pub enacted: Vec<B>,
+}
+
+impl<B> Default for TreeRoute<B> {
+	fn default() -> Self {
+		Self { retracted: vec![], enacted: vec![] }
+	}
+}
+
+/// Walk back from `old` and `new` until their common ancestor is found.
+///
+/// At most [`MAX_REORG_DEPTH`] blocks are walked, deeper reorgs yield a truncated route.
+pub(crate) async fn tree_route<B, F, Fut>(
+	old: B,
+	new: B,
+	fetch_block: F,
+) -> Result<TreeRoute<B>, ClientError>
+where
+	B: RouteBlock,
+	F: Fn(H256) -> Fut,
+	Fut: std::future::Future<Output = Result<B, ClientError>>,
+{
+	let mut route = TreeRoute::default();
+	let mut old_block = old;
+	let mut new_block = new;
+
+	for _ in 0..MAX_REORG_DEPTH {
+		if old_block.hash() == new_block.hash() {
+			route.enacted.reverse();
+			return Ok(route);
+		}
+
+		if old_block.number() >= new_block.number() {
+			let parent_hash = old_block.parent_hash();
+			route.retracted.push(old_block);
+			old_block = fetch_block(parent_hash).await?;
+		} else {
+			let parent_hash = new_block.parent_hash();
+			route.enacted.push(new_block);
+			new_block = fetch_block(parent_hash).await?;
+		}
+	}
+
+	log::warn!(target: LOG_TARGET, "Reorg deeper than {MAX_REORG_DEPTH} blocks, some notifications may be missing");
+	route.enacted.reverse();
+	Ok(route)
+}
+
+/// The notifications published when the best chain changes.
+///
+/// `retracted_logs` are the logs of the retracted blocks, which are published first with
+/// `removed` set to `true`. Each of the `enacted` blocks is then published with its logs, in
+/// the given order.
+pub(crate) fn reorg_notifications(
+	retracted_logs: Vec<Vec<Log>>,
+	enacted: Vec<(Block, Vec<Log>)>,
+) -> Vec<ChainNotification> {
+	let mut notifications = vec![];
+
+	for logs in retracted_logs {
+		if !logs.is_empty() {
+			notifications.push(ChainNotification::Logs(
+				logs.into_iter().map(|log| Log { removed: Some(true), ..log }).collect(),
+			));
+		}
+	}
+
+	for (block, logs) in enacted {
+		notifications.push(ChainNotification::NewHead(Box::new(block)));
+		if !logs.is_empty() {
+			notifications.push(ChainNotification::Logs(logs));
+		}
+	}
+
+	notifications
+}
+
+fn logs_of(receipts: &[ReceiptInfo]) -> Vec<Log> {
+	receipts.iter().flat_map(|receipt| receipt.logs.iter().cloned()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::collections::HashMap;
+
+	#[derive(Debug, Clone, Copy, PartialEq)]
+	struct MockBlock {
+		hash: H256,
+		number: SubstrateBlockNumber,
+		parent_hash: H256,
+	}
+
+	impl RouteBlock for MockBlock {
+		fn hash(&self) -> H256 {
+			self.hash
+		}
+		fn number(&self) -> SubstrateBlockNumber {
+			self.number
+		}
+		fn parent_hash(&self) -> H256 {
+			self.parent_hash
+		}
+	}
+
+	/// Build a chain of `len` blocks on top of `parent`, using `fork` to derive unique hashes.
+	fn build_chain(parent: MockBlock, len: u32, fork: u8) -> Vec<MockBlock> {
+		let mut blocks = vec![];
+		let mut parent = parent;
+		for _ in 0..len {
+			let number = parent.number + 1;
+			let mut hash = H256::repeat_byte(fork);
+			hash.0[..4].copy_from_slice(&number.to_be_bytes());
+			let block = MockBlock { hash, number, parent_hash: parent.hash };
+			blocks.push(block);
+			parent = block;
+		}
+		blocks
+	}
+
+	fn block_logs(block: &MockBlock) -> Vec<Log> {
+		vec![Log {
+			block_hash: block.hash,
+			block_number: block.number.into(),
+			..Default::default()
+		}]
+	}
+
+	#[tokio::test]
+	async fn reorg_publishes_retracted_logs_and_enacted_blocks() {
+		let genesis = MockBlock { hash: H256::zero(), number: 0, parent_hash: H256::zero() };
+		let common = build_chain(genesis, 1, 1)[0];
+		// Old best chain: common <- a2 <- a3.
+		let old_chain = build_chain(common, 2, 0xa);
+		// New best chain: common <- b2 <- b3 <- b4 <- b5 (new best block).
+		let new_chain = build_chain(common, 4, 0xb);
+
+		let blocks: HashMap<_, _> = [genesis, common]
+			.into_iter()
+			.chain(old_chain.iter().copied())
+			.chain(new_chain.iter().copied())
+			.map(|block| (block.hash, block))
+			.collect();
+		let fetch_block = |hash: H256| {
+			let block = blocks.get(&hash).copied().ok_or(ClientError::BlockNotFound);
+			async move { block }
+		};
+
+		let new_best = new_chain[3];
+		let route =
+			tree_route(old_chain[1], fetch_block(new_best.parent_hash).await.unwrap(), fetch_block)
+				.await
+				.unwrap();
+		assert_eq!(route.retracted, vec![old_chain[1], old_chain[0]]);
+		assert_eq!(route.enacted, new_chain[..3].to_vec());
+
+		let evm_block = |block: &MockBlock| Block {
+			hash: block.hash,
+			number: block.number.into(),
+			..Default::default()
+		};
+		let retracted_logs = route.retracted.iter().map(block_logs).collect();
+		let enacted = route
+			.enacted
+			.iter()
+			.chain(std::iter::once(&new_best))
+			.map(|block| (evm_block(block), block_logs(block)))
+			.collect();
+
+		let mut expected = vec![];
+		for block in &route.retracted {
+			let logs = block_logs(block)
+				.into_iter()
+				.map(|log| Log { removed: Some(true), ..log })
+				.collect();
+			expected.push(ChainNotification::Logs(logs));
+		}
+		for block in new_chain.iter() {
+			expected.push(ChainNotification::NewHead(Box::new(evm_block(block))));
+			expected.push(ChainNotification::Logs(block_logs(block)));
+		}
+
+		let notifications = reorg_notifications(retracted_logs, enacted);
+		assert_eq!(notifications, expected);
+	}
+
+	#[tokio::test]
+	async fn tree_route_is_empty_without_reorg() {
+		let genesis = MockBlock { hash: H256::zero(), number: 0, parent_hash: H256::zero() };
+		let chain = build_chain(genesis, 2, 1);
+		let fetch_block = |_: H256| async { Err::<MockBlock, _>(ClientError::BlockNotFound) };
+
+		let route = tree_route(chain[1], chain[1], fetch_block).await.unwrap();
+		assert!(route.retracted.is_empty());
+		assert!(route.enacted.is_empty());
+	}
+}
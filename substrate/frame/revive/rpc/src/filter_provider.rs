@@ -0,0 +1,368 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::{
+	client::{ChainNotification, SubstrateBlockNumber},
+	BlockNumberOrTag, BlockTag, ClientError, LOG_TARGET,
+};
+use pallet_revive::evm::Filter;
+use sp_core::{H256, U256};
+use std::{
+	collections::HashMap,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc,
+	},
+	time::{Duration, Instant},
+};
+use tokio::sync::{
+	broadcast::{self, error::RecvError},
+	Mutex,
+};
+
+/// Installed filters that are not polled within this duration are removed.
+const FILTER_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+/// How often expired filters are removed.
+const FILTER_SWEEP_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The maximum number of hashes buffered by a filter between two polls.
+const MAX_BUFFERED_HASHES: usize = 10_000;
+
+/// The hashes received by a block or pending transaction filter since its last poll.
+#[derive(Default)]
+struct HashBuffer {
+	hashes: Vec<H256>,
+	/// Whether some hashes were dropped, because the buffer was full or notifications were
+	/// missed.
+	lagged: bool,
+}
+
+impl HashBuffer {
+	fn push(&mut self, hash: H256) {
+		if self.hashes.len() < MAX_BUFFERED_HASHES {
+			self.hashes.push(hash);
+		} else {
+			self.lagged = true;
+		}
+	}
+}
+
+/// The kind of an installed filter.
+enum FilterKind {
+	/// A log filter, and the next block number to poll the logs from.
+	Logs { filter: Filter, next_block: SubstrateBlockNumber },
+	/// A new block filter.
+	Blocks(HashBuffer),
+	/// A new pending transaction filter.
+	PendingTransactions(HashBuffer),
+}
+
+/// A filter installed with `eth_newFilter`, `eth_newBlockFilter` or
+/// `eth_newPendingTransactionFilter`.
+struct InstalledFilter {
+	kind: FilterKind,
+	last_poll: Instant,
+}
+
+impl InstalledFilter {
+	fn is_expired(&self) -> bool {
+		self.last_poll.elapsed() >= FILTER_TIMEOUT
+	}
+}
+
+/// The changes of an installed filter since its last poll.
+#[derive(Debug, PartialEq)]
+pub enum FilterChanges {
+	/// The query to run against the logs database, or `None` if no new block was produced.
+	Logs(Option<Filter>),
+	/// New block or transaction hashes.
+	Hashes(Vec<H256>),
+}
+
+/// Manages the filters installed by the stateful filter API.
+///
+/// Block and pending transaction filters buffer the hashes they receive from [`Self::run`]
+/// until they are polled.
+#[derive(Default, Clone)]
+pub struct FilterProvider {
+	filters: Arc<Mutex<HashMap<U256, InstalledFilter>>>,
+	next_id: Arc<AtomicU64>,
+}
+
+/// Resolve a block number or tag into a block number, given the latest block number.
+fn as_block_number(
+	block: &Option<BlockNumberOrTag>,
+	latest: SubstrateBlockNumber,
+) -> SubstrateBlockNumber {
+	match block {
+		Some(BlockNumberOrTag::U256(n)) => (*n).try_into().unwrap_or(SubstrateBlockNumber::MAX),
+		Some(BlockNumberOrTag::BlockTag(BlockTag::Earliest)) => 0,
+		Some(BlockNumberOrTag::BlockTag(_)) | None => latest,
+	}
+}
+
+/// Get the installed filter with the given id, removing it if it expired.
+fn get_filter(
+	filters: &mut HashMap<U256, InstalledFilter>,
+	id: U256,
+) -> Result<&mut InstalledFilter, ClientError> {
+	if filters.get(&id).is_some_and(InstalledFilter::is_expired) {
+		filters.remove(&id);
+	}
+
+	let installed = filters.get_mut(&id).ok_or(ClientError::FilterNotFound)?;
+	installed.last_poll = Instant::now();
+	Ok(installed)
+}
+
+impl FilterProvider {
+	/// Feed the block and pending transaction filters with the given notifications, and
+	/// periodically remove the filters that expired.
+	pub async fn run(&self, mut notifications: broadcast::Receiver<ChainNotification>) {
+		let mut sweep = tokio::time::interval(FILTER_SWEEP_INTERVAL);
+		loop {
+			tokio::select! {
+				notification = notifications.recv() => match notification {
+					Ok(notification) => self.on_notification(notification).await,
+					Err(RecvError::Lagged(skipped)) => {
+						log::warn!(target: LOG_TARGET, "Filters lagged behind, {skipped} notifications skipped");
+						self.mark_lagged().await;
+					},
+					Err(RecvError::Closed) => return,
+				},
+				_ = sweep.tick() => self.remove_expired().await,
+			}
+		}
+	}
+
+	/// Buffer the hash carried by `notification` in the filters it matches.
+	async fn on_notification(&self, notification: ChainNotification) {
+		let mut filters = self.filters.lock().await;
+		for installed in filters.values_mut() {
+			match (&mut installed.kind, &notification) {
+				(FilterKind::Blocks(buffer), ChainNotification::NewHead(block)) =>
+					buffer.push(block.hash),
+				(
+					FilterKind::PendingTransactions(buffer),
+					ChainNotification::NewPendingTransaction(hash),
+				) => buffer.push(*hash),
+				_ => {},
+			}
+		}
+	}
+
+	/// Flag all block and pending transaction filters as having missed some hashes.
+	async fn mark_lagged(&self) {
+		let mut filters = self.filters.lock().await;
+		for installed in filters.values_mut() {
+			if let FilterKind::Blocks(buffer) | FilterKind::PendingTransactions(buffer) =
+				&mut installed.kind
+			{
+				buffer.lagged = true;
+			}
+		}
+	}
+
+	/// Remove the filters that were not polled within [`FILTER_TIMEOUT`].
+	async fn remove_expired(&self) {
+		self.filters.lock().await.retain(|_, filter| !filter.is_expired());
+	}
+
+	/// Install a filter and return its id.
+	async fn install(&self, kind: FilterKind) -> U256 {
+		let id = U256::from(self.next_id.fetch_add(1, Ordering::Relaxed) + 1);
+		let mut filters = self.filters.lock().await;
+		filters.insert(id, InstalledFilter { kind, last_poll: Instant::now() });
+		id
+	}
+
+	/// Install a log filter. Only the logs of the blocks produced after `latest` are reported
+	/// as changes.
+	pub async fn new_log_filter(&self, filter: Filter, latest: SubstrateBlockNumber) -> U256 {
+		let next_block = as_block_number(&filter.from_block, latest).max(latest.saturating_add(1));
+		self.install(FilterKind::Logs { filter, next_block }).await
+	}
+
+	/// Install a new block filter.
+	pub async fn new_block_filter(&self) -> U256 {
+		self.install(FilterKind::Blocks(HashBuffer::default())).await
+	}
+
+	/// Install a new pending transaction filter.
+	pub async fn new_pending_transaction_filter(&self) -> U256 {
+		self.install(FilterKind::PendingTransactions(HashBuffer::default())).await
+	}
+
+	/// Uninstall the filter with the given id. Returns `true` if the filter was installed.
+	pub async fn uninstall(&self, id: U256) -> bool {
+		self.filters.lock().await.remove(&id).is_some()
+	}
+
+	/// Return the changes of the filter since its last poll.
+	///
+	/// Block and pending transaction filters that missed some hashes are uninstalled and
+	/// return [`ClientError::FilterLagged`], so that clients install a new filter.
+	pub async fn poll(
+		&self,
+		id: U256,
+		latest: SubstrateBlockNumber,
+	) -> Result<FilterChanges, ClientError> {
+		let mut filters = self.filters.lock().await;
+		let installed = get_filter(&mut filters, id)?;
+
+		let changes = match &mut installed.kind {
+			FilterKind::Logs { filter, next_block } => {
+				let to_block = as_block_number(&filter.to_block, latest).min(latest);
+				if filter.block_hash.is_some() || *next_block > to_block {
+					return Ok(FilterChanges::Logs(None));
+				}
+
+				let query = Filter {
+					from_block: Some(U256::from(*next_block).into()),
+					to_block: Some(U256::from(to_block).into()),
+					..filter.clone()
+				};
+				*next_block = to_block.saturating_add(1);
+				FilterChanges::Logs(Some(query))
+			},
+			FilterKind::Blocks(buffer) | FilterKind::PendingTransactions(buffer)
+				if !buffer.lagged =>
+				FilterChanges::Hashes(std::mem::take(&mut buffer.hashes)),
+			FilterKind::Blocks(_) | FilterKind::PendingTransactions(_) => {
+				filters.remove(&id);
+				return Err(ClientError::FilterLagged);
+			},
+		};
+
+		Ok(changes)
+	}
+
+	/// Return the filter of the log filter with the given id.
+	pub async fn log_filter(&self, id: U256) -> Result<Filter, ClientError> {
+		let mut filters = self.filters.lock().await;
+		match &get_filter(&mut filters, id)?.kind {
+			FilterKind::Logs { filter, .. } => Ok(filter.clone()),
+			_ => Err(ClientError::FilterNotFound),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use pallet_revive::evm::Block;
+
+	#[tokio::test]
+	async fn log_filter_changes_works() {
+		let provider = FilterProvider::default();
+		let id = provider.new_log_filter(Filter::default(), 10).await;
+
+		// No new block was produced.
+		assert_eq!(provider.poll(id, 10).await.unwrap(), FilterChanges::Logs(None));
+
+		// Two new blocks were produced.
+		assert_eq!(
+			provider.poll(id, 12).await.unwrap(),
+			FilterChanges::Logs(Some(Filter {
+				from_block: Some(U256::from(11).into()),
+				to_block: Some(U256::from(12).into()),
+				..Default::default()
+			}))
+		);
+		assert_eq!(provider.poll(id, 12).await.unwrap(), FilterChanges::Logs(None));
+		assert_eq!(provider.log_filter(id).await.unwrap(), Filter::default());
+
+		assert!(provider.uninstall(id).await);
+		assert!(!provider.uninstall(id).await);
+		assert!(matches!(provider.poll(id, 12).await, Err(ClientError::FilterNotFound)));
+	}
+
+	#[tokio::test]
+	async fn hash_filter_changes_works() {
+		let provider = FilterProvider::default();
+		let block_filter = provider.new_block_filter().await;
+		let tx_filter = provider.new_pending_transaction_filter().await;
+
+		let block = Block { hash: H256::repeat_byte(1), ..Default::default() };
+		provider.on_notification(ChainNotification::NewHead(Box::new(block))).await;
+		provider
+			.on_notification(ChainNotification::NewPendingTransaction(H256::repeat_byte(2)))
+			.await;
+
+		assert_eq!(
+			provider.poll(block_filter, 0).await.unwrap(),
+			FilterChanges::Hashes(vec![H256::repeat_byte(1)])
+		);
+		assert_eq!(
+			provider.poll(tx_filter, 0).await.unwrap(),
+			FilterChanges::Hashes(vec![H256::repeat_byte(2)])
+		);
+		assert_eq!(provider.poll(tx_filter, 0).await.unwrap(), FilterChanges::Hashes(vec![]));
+		assert!(matches!(provider.log_filter(tx_filter).await, Err(ClientError::FilterNotFound)));
+	}
+
+	#[tokio::test]
+	async fn lagged_filters_are_uninstalled() {
+		let provider = FilterProvider::default();
+		let full_filter = provider.new_pending_transaction_filter().await;
+		for i in 0..=MAX_BUFFERED_HASHES {
+			provider
+				.on_notification(ChainNotification::NewPendingTransaction(H256::from_low_u64_be(
+					i as u64,
+				)))
+				.await;
+		}
+		let missed_filter = provider.new_block_filter().await;
+		provider.mark_lagged().await;
+
+		for id in [full_filter, missed_filter] {
+			assert!(matches!(provider.poll(id, 0).await, Err(ClientError::FilterLagged)));
+			assert!(matches!(provider.poll(id, 0).await, Err(ClientError::FilterNotFound)));
+		}
+	}
+
+	#[tokio::test]
+	async fn filters_are_fed_by_notifications_and_expire() {
+		let provider = FilterProvider::default();
+		let (sender, _) = broadcast::channel(16);
+		let task = tokio::spawn({
+			let provider = provider.clone();
+			let receiver = sender.subscribe();
+			async move { provider.run(receiver).await }
+		});
+
+		let block_filter = provider.new_block_filter().await;
+		let log_filter = provider.new_log_filter(Filter::default(), 0).await;
+		let block = Block { hash: H256::repeat_byte(1), ..Default::default() };
+		sender.send(ChainNotification::NewHead(Box::new(block))).unwrap();
+		drop(sender);
+		task.await.unwrap();
+
+		assert_eq!(
+			provider.poll(block_filter, 0).await.unwrap(),
+			FilterChanges::Hashes(vec![H256::repeat_byte(1)])
+		);
+
+		// Expired filters are removed, including by `log_filter`.
+		for filter in provider.filters.lock().await.values_mut() {
+			filter.last_poll -= FILTER_TIMEOUT;
+		}
+		assert!(matches!(provider.log_filter(log_filter).await, Err(ClientError::FilterNotFound)));
+		provider.remove_expired().await;
+		assert!(provider.filters.lock().await.is_empty());
+	}
+}
@@ -44,6 +44,9 @@ pub use receipt_provider::*;
 mod fee_history_provider;
 pub use fee_history_provider::*;
 
+mod filter_provider;
+pub use filter_provider::*;
+
 mod receipt_extractor;
 pub use receipt_extractor::*;
 
@@ -176,6 +179,7 @@ impl EthRpcServer for EthRpcServerImpl {
 		})?;
 
 		log::debug!(target: LOG_TARGET, "send_raw_transaction hash: {hash:?}");
+		self.client.notify_pending_transaction(hash);
 		Ok(hash)
 	}
 
@@ -298,6 +302,15 @@ impl EthRpcServer for EthRpcServerImpl {
 		Ok(self.client.receipts_count_per_block(&block.hash).await.map(U256::from))
 	}
 
+	async fn get_filter_changes(&self, filter_id: U256) -> RpcResult<FilterResults> {
+		Ok(self.client.filter_changes(filter_id).await?)
+	}
+
+	async fn get_filter_logs(&self, filter_id: U256) -> RpcResult<FilterResults> {
+		let logs = self.client.filter_logs(filter_id).await?;
+		Ok(FilterResults::Logs(logs))
+	}
+
 	async fn get_logs(&self, filter: Option<Filter>) -> RpcResult<FilterResults> {
 		let logs = self.client.logs(filter).await?;
 		Ok(FilterResults::Logs(logs))
@@ -374,6 +387,22 @@ impl EthRpcServer for EthRpcServerImpl {
 		Ok(nonce)
 	}
 
+	async fn new_block_filter(&self) -> RpcResult<U256> {
+		Ok(self.client.new_block_filter().await)
+	}
+
+	async fn new_filter(&self, filter: Filter) -> RpcResult<U256> {
+		Ok(self.client.new_filter(filter).await)
+	}
+
+	async fn new_pending_transaction_filter(&self) -> RpcResult<U256> {
+		Ok(self.client.new_pending_transaction_filter().await)
+	}
+
+	async fn uninstall_filter(&self, filter_id: U256) -> RpcResult<bool> {
+		Ok(self.client.uninstall_filter(filter_id).await)
+	}
+
 	async fn web3_client_version(&self) -> RpcResult<String> {
 		let git_revision = env!("GIT_REVISION");
 		let rustc_version = env!("RUSTC_VERSION");
@@ -16,7 +16,7 @@
 // limitations under the License.
 
 use crate::evm::Bytes;
-use alloc::{string::String, vec::Vec};
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
 use codec::{Decode, Encode};
 use derive_more::From;
 use scale_info::TypeInfo;
@@ -24,12 +24,17 @@ use serde::{Deserialize, Serialize};
 use sp_core::{H160, H256, U256};
 
 /// The type of tracer to use.
-/// Only "callTracer" is supported for now.
 #[derive(TypeInfo, Debug, Clone, Encode, Decode, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "tracer", content = "tracerConfig", rename_all = "camelCase")]
 pub enum TracerType {
 	/// A tracer that traces calls.
 	CallTracer(Option<CallTracerConfig>),
+
+	/// A tracer that reports the state of the accounts touched by a transaction.
+	PrestateTracer(Option<PrestateTracerConfig>),
+
+	/// A tracer that reports every step of the execution.
+	StructLogger(Option<StructLoggerConfig>),
 }
 
 impl From<CallTracerConfig> for TracerType {
@@ -38,15 +43,27 @@ impl From<CallTracerConfig> for TracerType {
 	}
 }
 
+impl From<PrestateTracerConfig> for TracerType {
+	fn from(config: PrestateTracerConfig) -> Self {
+		TracerType::PrestateTracer(Some(config))
+	}
+}
+
+impl From<StructLoggerConfig> for TracerType {
+	fn from(config: StructLoggerConfig) -> Self {
+		TracerType::StructLogger(Some(config))
+	}
+}
+
 impl Default for TracerType {
 	fn default() -> Self {
-		TracerType::CallTracer(Some(CallTracerConfig::default()))
+		TracerType::StructLogger(Some(StructLoggerConfig::default()))
 	}
 }
 
 /// Tracer configuration used to trace calls.
 #[derive(TypeInfo, Debug, Clone, Default, PartialEq)]
-#[cfg_attr(feature = "std", derive(Deserialize, Serialize), serde(rename_all = "camelCase"))]
+#[cfg_attr(feature = "std", derive(Serialize), serde(rename_all = "camelCase"))]
 pub struct TracerConfig {
 	/// The tracer type.
 	#[cfg_attr(feature = "std", serde(flatten, default))]
@@ -57,6 +74,41 @@ pub struct TracerConfig {
 	pub timeout: Option<core::time::Duration>,
 }
 
+/// When no tracer is specified, the struct logger is used, and its options are read from the top
+/// level of the configuration, like geth does.
+#[cfg(feature = "std")]
+impl<'de> Deserialize<'de> for TracerConfig {
+	fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+	where
+		D: serde::Deserializer<'de>,
+	{
+		#[derive(Deserialize)]
+		#[serde(rename_all = "camelCase")]
+		struct TracerConfigFields {
+			/// All the fields, used to tell whether a tracer was specified.
+			#[serde(flatten)]
+			fields: BTreeMap<String, serde::de::IgnoredAny>,
+			#[serde(flatten)]
+			config: Option<TracerType>,
+			#[serde(flatten)]
+			struct_logger_config: StructLoggerConfig,
+			#[serde(with = "humantime_serde", default)]
+			timeout: Option<core::time::Duration>,
+		}
+
+		let TracerConfigFields { fields, config, struct_logger_config, timeout } =
+			TracerConfigFields::deserialize(deserializer)?;
+		let config = match config {
+			Some(config) => config,
+			None if fields.contains_key("tracer") =>
+				return Err(serde::de::Error::custom("unknown tracer or invalid tracer config")),
+			None => struct_logger_config.into(),
+		};
+
+		Ok(TracerConfig { config, timeout })
+	}
+}
+
 /// The configuration for the call tracer.
 #[derive(Clone, Debug, Decode, Serialize, Deserialize, Encode, PartialEq, TypeInfo)]
 #[serde(default, rename_all = "camelCase")]
@@ -74,6 +126,34 @@ impl Default for CallTracerConfig {
 	}
 }
 
+/// The configuration for the prestate tracer.
+#[derive(Clone, Debug, Decode, Serialize, Deserialize, Encode, PartialEq, TypeInfo, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct PrestateTracerConfig {
+	/// Whether to report the state changes made by the transaction instead of the prestate.
+	pub diff_mode: bool,
+
+	/// Whether to exclude the code of the accounts from the trace.
+	pub disable_code: bool,
+
+	/// Whether to exclude the storage of the accounts from the trace.
+	pub disable_storage: bool,
+}
+
+/// The configuration for the struct logger.
+#[derive(Clone, Debug, Decode, Serialize, Deserialize, Encode, PartialEq, TypeInfo, Default)]
+#[serde(default, rename_all = "camelCase")]
+pub struct StructLoggerConfig {
+	/// Whether to exclude the syscall arguments from the steps.
+	pub disable_stack: bool,
+
+	/// Whether to exclude the accessed storage slots from the steps.
+	pub disable_storage: bool,
+
+	/// Whether to include a snapshot of the contract memory in the steps.
+	pub enable_memory: bool,
+}
+
 /// Serialization should support the following JSON format:
 ///
 /// ```json
@@ -83,6 +163,14 @@ impl Default for CallTracerConfig {
 /// ```json
 /// { "tracer": "callTracer" }
 /// ```
+///
+/// ```json
+/// { "tracer": "prestateTracer", "tracerConfig": { "diffMode": true } }
+/// ```
+///
+/// ```json
+/// { "enableMemory": true, "disableStack": true }
+/// ```
 #[test]
 fn test_tracer_config_serialization() {
 	let tracers = vec![
@@ -111,6 +199,35 @@ fn test_tracer_config_serialization() {
 				timeout: Some(core::time::Duration::from_millis(10)),
 			},
 		),
+		(
+			r#"{"tracer": "prestateTracer"}"#,
+			TracerConfig { config: TracerType::PrestateTracer(None), timeout: None },
+		),
+		(
+			r#"{"tracer": "prestateTracer", "tracerConfig": { "diffMode": true }}"#,
+			TracerConfig {
+				config: PrestateTracerConfig {
+					diff_mode: true,
+					disable_code: false,
+					disable_storage: false,
+				}
+				.into(),
+				timeout: None,
+			},
+		),
+		(r#"{}"#, TracerConfig { config: StructLoggerConfig::default().into(), timeout: None }),
+		(
+			r#"{"enableMemory": true, "disableStack": true, "timeout": "10ms"}"#,
+			TracerConfig {
+				config: StructLoggerConfig {
+					disable_stack: true,
+					disable_storage: false,
+					enable_memory: true,
+				}
+				.into(),
+				timeout: Some(core::time::Duration::from_millis(10)),
+			},
+		),
 	];
 
 	for (json_data, expected) in tracers {
@@ -118,6 +235,8 @@ fn test_tracer_config_serialization() {
 			serde_json::from_str(json_data).expect("Deserialization should succeed");
 		assert_eq!(result, expected);
 	}
+
+	assert!(serde_json::from_str::<TracerConfig>(r#"{"tracer": "fooTracer"}"#).is_err());
 }
 
 /// The type of call that was executed.
@@ -141,6 +260,105 @@ pub enum CallType {
 pub enum Trace {
 	/// A call trace.
 	Call(CallTrace),
+	/// A prestate trace.
+	Prestate(PrestateTrace),
+	/// A struct logger trace.
+	StructLog(StructLoggerTrace),
+}
+
+/// A prestate Trace
+#[derive(TypeInfo, Encode, Decode, Serialize, Deserialize, Clone, Debug, Eq, PartialEq)]
+#[serde(untagged)]
+pub enum PrestateTrace {
+	/// The state of the accounts touched by the transaction, before it was executed.
+	Prestate(BTreeMap<H160, PrestateTraceInfo>),
+
+	/// The state changes made by the transaction.
+	/// Accounts that are not modified are omitted, and only the modified fields of an account
+	/// are reported in `post`.
+	DiffMode {
+		/// The state of the modified accounts, before the transaction was executed.
+		pre: BTreeMap<H160, PrestateTraceInfo>,
+		/// The state of the modified accounts, after the transaction was executed.
+		post: BTreeMap<H160, PrestateTraceInfo>,
+	},
+}
+
+/// The state of an account reported by the prestate tracer.
+#[derive(
+	TypeInfo, Default, Encode, Decode, Serialize, Deserialize, Clone, Debug, Eq, PartialEq,
+)]
+pub struct PrestateTraceInfo {
+	/// The balance of the account.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub balance: Option<U256>,
+	/// The nonce of the account.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub nonce: Option<u32>,
+	/// The code of the account.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub code: Option<Bytes>,
+	/// The storage slots of the account that were accessed.
+	#[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+	pub storage: BTreeMap<Bytes, Option<Bytes>>,
+}
+
+impl PrestateTraceInfo {
+	/// Returns true if the account holds no balance, no code and no storage, and has a zero
+	/// nonce.
+	pub fn is_empty(&self) -> bool {
+		self.balance.unwrap_or_default().is_zero() &&
+			self.nonce.unwrap_or_default() == 0 &&
+			self.code.is_none() &&
+			self.storage.is_empty()
+	}
+}
+
+/// A struct logger trace, in the format of the geth struct logger.
+#[derive(
+	TypeInfo, Default, Encode, Decode, Serialize, Deserialize, Clone, Debug, Eq, PartialEq,
+)]
+pub struct StructLoggerTrace<Gas = U256> {
+	/// Amount of gas used by the transaction.
+	pub gas: Gas,
+	/// Whether the transaction failed.
+	pub failed: bool,
+	/// The data returned by the transaction.
+	#[serde(rename = "returnValue")]
+	pub return_value: Bytes,
+	/// The steps of the execution.
+	#[serde(rename = "structLogs")]
+	pub struct_logs: Vec<StructLog<Gas>>,
+}
+
+/// A step of the execution, recorded for every syscall made by a contract.
+#[derive(
+	TypeInfo, Default, Encode, Decode, Serialize, Deserialize, Clone, Debug, Eq, PartialEq,
+)]
+pub struct StructLog<Gas = U256> {
+	/// The program counter of the syscall.
+	pub pc: u64,
+	/// The name of the syscall.
+	pub op: String,
+	/// Amount of gas left before the step.
+	pub gas: Gas,
+	/// Amount of gas used by the step.
+	#[serde(rename = "gasCost")]
+	pub gas_cost: Gas,
+	/// The depth of the call frame executing the step, starting at 1.
+	pub depth: u32,
+	/// The syscall arguments.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub stack: Option<Vec<U256>>,
+	/// The contract memory, in 32 bytes words.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub memory: Option<Vec<Bytes>>,
+	/// The storage slots of the contract accessed so far, if the step accessed the storage.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub storage: Option<BTreeMap<Bytes, Bytes>>,
+	/// The error message if the step failed.
+	#[serde(skip_serializing_if = "Option::is_none")]
+	pub error: Option<String>,
 }
 
 /// A smart contract execution call trace.
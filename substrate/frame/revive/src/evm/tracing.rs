@@ -15,27 +15,39 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 use crate::{
-	evm::{CallTrace, Trace},
+	evm::{CallTrace, StructLoggerTrace, Trace},
 	tracing::Tracing,
-	Weight,
+	Config, Weight,
 };
 use sp_core::U256;
 
 mod call_tracing;
 pub use call_tracing::*;
 
+mod prestate_tracing;
+pub use prestate_tracing::*;
+
+mod struct_logger;
+pub use struct_logger::*;
+
 /// A composite tracer.
 #[derive(derive_more::From, Debug)]
-pub enum Tracer {
+pub enum Tracer<T> {
 	/// A tracer that traces calls.
 	CallTracer(CallTracer<U256, fn(Weight) -> U256>),
+	/// A tracer that traces the prestate.
+	PrestateTracer(PrestateTracer<T>),
+	/// A tracer that reports every step of the execution.
+	StructLogger(StructLogger<U256, fn(Weight) -> U256>),
 }
 
-impl Tracer {
+impl<T: Config> Tracer<T> {
 	/// Returns an empty trace.
 	pub fn empty_trace(&self) -> Trace {
 		match self {
 			Tracer::CallTracer(_) => CallTrace::default().into(),
+			Tracer::PrestateTracer(inner) => inner.empty_trace().into(),
+			Tracer::StructLogger(_) => StructLoggerTrace::default().into(),
 		}
 	}
 
@@ -43,6 +55,8 @@ impl Tracer {
 	pub fn as_tracing(&mut self) -> &mut (dyn Tracing + 'static) {
 		match self {
 			Tracer::CallTracer(inner) => inner as &mut dyn Tracing,
+			Tracer::PrestateTracer(inner) => inner as &mut dyn Tracing,
+			Tracer::StructLogger(inner) => inner as &mut dyn Tracing,
 		}
 	}
 
@@ -50,6 +64,8 @@ impl Tracer {
 	pub fn collect_trace(&mut self) -> Option<Trace> {
 		match self {
 			Tracer::CallTracer(inner) => inner.collect_trace().map(Trace::Call),
+			Tracer::PrestateTracer(inner) => inner.collect_trace().map(Trace::Prestate),
+			Tracer::StructLogger(inner) => inner.collect_trace().map(Trace::StructLog),
 		}
 	}
 }
@@ -0,0 +1,212 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::{
+	evm::{Bytes, PrestateTrace, PrestateTraceInfo, PrestateTracerConfig},
+	exec::Key,
+	primitives::ExecReturnValue,
+	tracing::Tracing,
+	AddressMapper, Config, ContractInfoOf, DispatchError, Pallet, PristineCode, Weight,
+};
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::marker::PhantomData;
+use frame_support::storage::child;
+use frame_system::Pallet as System;
+use sp_core::{H160, U256};
+use sp_runtime::SaturatedConversion;
+
+/// A Tracer that reports the state of the accounts touched by a transaction.
+///
+/// The state of an account is captured the first time it is touched. When `diff_mode` is
+/// enabled, the state of the same accounts is read again once the transaction completed and only
+/// the differences are reported.
+#[derive(Debug)]
+pub struct PrestateTracer<T> {
+	/// The tracer configuration.
+	config: PrestateTracerConfig,
+	/// Stack of the addresses whose storage is accessed by the active call frames.
+	current_stack: Vec<H160>,
+	/// The state of the touched accounts before they were first accessed.
+	pre: BTreeMap<H160, PrestateTraceInfo>,
+	/// The hashed trie keys of the storage slots recorded in `pre`.
+	storage_keys: BTreeMap<H160, BTreeMap<Bytes, Vec<u8>>>,
+	_phantom: PhantomData<T>,
+}
+
+impl<T: Config> PrestateTracer<T> {
+	/// Create a new [`PrestateTracer`] instance.
+	pub fn new(config: PrestateTracerConfig) -> Self {
+		Self {
+			config,
+			current_stack: Vec::new(),
+			pre: BTreeMap::new(),
+			storage_keys: BTreeMap::new(),
+			_phantom: PhantomData,
+		}
+	}
+
+	/// Returns an empty trace.
+	pub fn empty_trace(&self) -> PrestateTrace {
+		if self.config.diff_mode {
+			PrestateTrace::DiffMode { pre: Default::default(), post: Default::default() }
+		} else {
+			PrestateTrace::Prestate(Default::default())
+		}
+	}
+
+	/// Collect the traces and return them.
+	pub fn collect_trace(&mut self) -> Option<PrestateTrace> {
+		self.current_stack.clear();
+		let pre = core::mem::take(&mut self.pre);
+		let mut storage_keys = core::mem::take(&mut self.storage_keys);
+
+		if pre.is_empty() {
+			return None
+		}
+
+		if !self.config.diff_mode {
+			return Some(PrestateTrace::Prestate(pre))
+		}
+
+		let mut diff_pre = BTreeMap::new();
+		let mut diff_post = BTreeMap::new();
+		for (address, mut pre_info) in pre {
+			let mut post_info = self.read_account(&address);
+			let keys = storage_keys.remove(&address).unwrap_or_default();
+			let contract_info = ContractInfoOf::<T>::get(&address);
+
+			pre_info.storage.retain(|key, pre_value| {
+				let post_value =
+					keys.get(key).zip(contract_info.as_ref()).and_then(|(hashed_key, info)| {
+						child::get_raw(&info.child_trie_info(), hashed_key).map(Bytes::from)
+					});
+				if *pre_value == post_value {
+					return false
+				}
+				post_info.storage.insert(key.clone(), post_value);
+				true
+			});
+
+			if pre_info == post_info {
+				continue
+			}
+
+			// Only report the fields that changed in the post state.
+			if post_info.balance == pre_info.balance {
+				post_info.balance = None;
+			}
+			if post_info.nonce == pre_info.nonce {
+				post_info.nonce = None;
+			}
+			if post_info.code == pre_info.code {
+				post_info.code = None;
+			}
+
+			// Accounts that did not exist before the transaction are only reported in the post
+			// state.
+			if !pre_info.is_empty() {
+				diff_pre.insert(address, pre_info);
+			}
+			diff_post.insert(address, post_info);
+		}
+
+		Some(PrestateTrace::DiffMode { pre: diff_pre, post: diff_post })
+	}
+
+	/// Read the current state of the given account, without its storage.
+	fn read_account(&self, address: &H160) -> PrestateTraceInfo {
+		let account_id = T::AddressMapper::to_account_id(address);
+		let code = if self.config.disable_code {
+			None
+		} else {
+			ContractInfoOf::<T>::get(address)
+				.and_then(|info| PristineCode::<T>::get(info.code_hash))
+				.map(|code| Bytes::from(code.into_inner()))
+		};
+
+		PrestateTraceInfo {
+			balance: Some(Pallet::<T>::evm_balance(address)),
+			nonce: Some(System::<T>::account_nonce(&account_id).saturated_into()),
+			code,
+			storage: Default::default(),
+		}
+	}
+
+	/// Record the storage slot `key` of the current contract with the given value, unless it was
+	/// already recorded.
+	fn record_storage(&mut self, key: &Key, value: Option<&[u8]>) {
+		if self.config.disable_storage {
+			return
+		}
+
+		let Some(address) = self.current_stack.last().copied() else { return };
+		self.watch_address(&address);
+
+		let raw_key = Bytes::from(key.unhashed().to_vec());
+		if let Some(info) = self.pre.get_mut(&address) {
+			if !info.storage.contains_key(&raw_key) {
+				info.storage.insert(raw_key.clone(), value.map(|v| v.to_vec().into()));
+				self.storage_keys.entry(address).or_default().insert(raw_key, key.hash());
+			}
+		}
+	}
+}
+
+impl<T: Config> Tracing for PrestateTracer<T> {
+	fn watch_address(&mut self, address: &H160) {
+		if !self.pre.contains_key(address) {
+			let info = self.read_account(address);
+			self.pre.insert(*address, info);
+		}
+	}
+
+	fn enter_child_span(
+		&mut self,
+		from: H160,
+		to: H160,
+		is_delegate_call: bool,
+		_is_read_only: bool,
+		_value: U256,
+		_input: &[u8],
+		_gas_left: Weight,
+	) {
+		self.watch_address(&from);
+		self.watch_address(&to);
+
+		// A delegate call accesses the storage of the contract issuing it.
+		let storage_owner = match self.current_stack.last() {
+			Some(owner) if is_delegate_call => *owner,
+			_ => to,
+		};
+		self.current_stack.push(storage_owner);
+	}
+
+	fn exit_child_span(&mut self, _output: &ExecReturnValue, _gas_used: Weight) {
+		self.current_stack.pop();
+	}
+
+	fn exit_child_span_with_error(&mut self, _error: DispatchError, _gas_used: Weight) {
+		self.current_stack.pop();
+	}
+
+	fn storage_read(&mut self, key: &Key, value: Option<&[u8]>) {
+		self.record_storage(key, value);
+	}
+
+	fn storage_write(&mut self, key: &Key, old_value: Option<Vec<u8>>, _new_value: Option<&[u8]>) {
+		self.record_storage(key, old_value.as_deref());
+	}
+}
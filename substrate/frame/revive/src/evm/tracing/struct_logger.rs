@@ -0,0 +1,183 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+use crate::{
+	evm::{Bytes, StructLog, StructLoggerConfig, StructLoggerTrace},
+	exec::Key,
+	primitives::ExecReturnValue,
+	tracing::Tracing,
+	DispatchError, Weight,
+};
+use alloc::{collections::BTreeMap, format, string::String, vec::Vec};
+use sp_core::{H160, U256};
+
+/// The size of the words the memory snapshots are split into.
+const MEMORY_WORD_SIZE: usize = 32;
+
+/// A call frame tracked by the [`StructLogger`].
+#[derive(Default, Debug, Clone, PartialEq)]
+struct Frame {
+	/// The index of the last step executed by the frame and the gas left before it.
+	last_step: Option<(usize, Weight)>,
+	/// The storage slots of the frame's contract accessed so far.
+	storage: BTreeMap<Bytes, Bytes>,
+	/// Whether the last step accessed the storage.
+	storage_accessed: bool,
+}
+
+/// A Tracer that reports every syscall made by the contracts, in the format of the geth struct
+/// logger.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct StructLogger<Gas, GasMapper> {
+	/// Map Weight to Gas equivalent.
+	gas_mapper: GasMapper,
+	/// The tracer configuration.
+	config: StructLoggerConfig,
+	/// The recorded steps.
+	struct_logs: Vec<StructLog<Gas>>,
+	/// Stack of the active call frames.
+	frames: Vec<Frame>,
+	/// The trace, available once the top call frame exited.
+	trace: Option<StructLoggerTrace<Gas>>,
+}
+
+impl<Gas, GasMapper> StructLogger<Gas, GasMapper> {
+	/// Create a new [`StructLogger`] instance.
+	pub fn new(config: StructLoggerConfig, gas_mapper: GasMapper) -> Self {
+		Self { gas_mapper, config, struct_logs: Vec::new(), frames: Vec::new(), trace: None }
+	}
+
+	/// Collect the traces and return them.
+	pub fn collect_trace(&mut self) -> Option<StructLoggerTrace<Gas>> {
+		self.frames.clear();
+		self.trace.take()
+	}
+}
+
+impl<Gas: Default, GasMapper: Fn(Weight) -> Gas> StructLogger<Gas, GasMapper> {
+	/// Record the storage slot `key` of the current contract with the given value.
+	fn record_storage(&mut self, key: &Key, value: Option<&[u8]>) {
+		if self.config.disable_storage {
+			return
+		}
+
+		if let Some(frame) = self.frames.last_mut() {
+			let value = value.map(|v| v.to_vec()).unwrap_or_default();
+			frame.storage.insert(key.unhashed().to_vec().into(), value.into());
+			frame.storage_accessed = true;
+		}
+	}
+
+	/// Exit the current call frame, setting the given error on its last step if any.
+	fn exit_frame(&mut self, error: Option<String>, failed: bool, output: &[u8], gas_used: Weight) {
+		let Some(frame) = self.frames.pop() else { return };
+		if let Some((index, _)) = frame.last_step {
+			self.struct_logs[index].error = error;
+		}
+
+		if self.frames.is_empty() {
+			self.trace = Some(StructLoggerTrace {
+				gas: (self.gas_mapper)(gas_used),
+				failed,
+				return_value: output.to_vec().into(),
+				struct_logs: core::mem::take(&mut self.struct_logs),
+			});
+		}
+	}
+}
+
+impl<Gas: Default, GasMapper: Fn(Weight) -> Gas> Tracing for StructLogger<Gas, GasMapper> {
+	fn enter_child_span(
+		&mut self,
+		_from: H160,
+		_to: H160,
+		_is_delegate_call: bool,
+		_is_read_only: bool,
+		_value: U256,
+		_input: &[u8],
+		_gas_left: Weight,
+	) {
+		self.frames.push(Frame::default());
+	}
+
+	fn exit_child_span(&mut self, output: &ExecReturnValue, gas_used: Weight) {
+		self.exit_frame(None, output.did_revert(), &output.data, gas_used);
+	}
+
+	fn exit_child_span_with_error(&mut self, error: DispatchError, gas_used: Weight) {
+		let error = match error {
+			DispatchError::Module(sp_runtime::ModuleError { message, .. }) =>
+				String::from(message.unwrap_or_default()),
+			_ => format!("{:?}", error),
+		};
+		self.exit_frame(Some(error), true, &[], gas_used);
+	}
+
+	fn enter_opcode(
+		&mut self,
+		pc: u64,
+		opcode: &[u8],
+		gas_left: Weight,
+		args: &[u64],
+		memory: &dyn Fn() -> Vec<u8>,
+	) {
+		let depth = self.frames.len() as u32;
+		let Some(frame) = self.frames.last_mut() else { return };
+
+		let memory = self.config.enable_memory.then(|| {
+			memory()
+				.chunks(MEMORY_WORD_SIZE)
+				.map(|word| {
+					let mut word = word.to_vec();
+					word.resize(MEMORY_WORD_SIZE, 0);
+					Bytes::from(word)
+				})
+				.collect()
+		});
+
+		frame.last_step = Some((self.struct_logs.len(), gas_left));
+		frame.storage_accessed = false;
+		self.struct_logs.push(StructLog {
+			pc,
+			op: String::from_utf8_lossy(opcode).into_owned(),
+			gas: (self.gas_mapper)(gas_left),
+			depth,
+			stack: (!self.config.disable_stack)
+				.then(|| args.iter().map(|arg| U256::from(*arg)).collect()),
+			memory,
+			..Default::default()
+		});
+	}
+
+	fn exit_opcode(&mut self, gas_left: Weight) {
+		let Some(frame) = self.frames.last_mut() else { return };
+		let Some((index, gas_before)) = frame.last_step else { return };
+
+		let step = &mut self.struct_logs[index];
+		step.gas_cost = (self.gas_mapper)(gas_before.saturating_sub(gas_left));
+		if frame.storage_accessed {
+			step.storage = Some(frame.storage.clone());
+		}
+	}
+
+	fn storage_read(&mut self, key: &Key, value: Option<&[u8]>) {
+		self.record_storage(key, value);
+	}
+
+	fn storage_write(&mut self, key: &Key, _old_value: Option<Vec<u8>>, new_value: Option<&[u8]>) {
+		self.record_storage(key, new_value);
+	}
+}
@@ -83,11 +83,6 @@ pub enum Key {
 
 impl Key {
 	/// Reference to the raw unhashed key.
-	///
-	/// # Note
-	///
-	/// Only used by benchmarking in order to generate storage collisions on purpose.
-	#[cfg(feature = "runtime-benchmarks")]
 	pub fn unhashed(&self) -> &[u8] {
 		match self {
 			Key::Fix(v) => v.as_ref(),
@@ -1525,6 +1520,7 @@ where
 		if frame.entry_point == ExportedFunction::Constructor {
 			return Err(Error::<T>::TerminatedInConstructor.into());
 		}
+		if_tracing(|tracer| tracer.watch_address(beneficiary));
 		let info = frame.terminate();
 		let beneficiary_account = T::AddressMapper::to_account_id(beneficiary);
 		frame.nested_storage.terminate(&info, beneficiary_account);
@@ -1624,7 +1620,9 @@ where
 	T::Hash: frame_support::traits::IsType<H256>,
 {
 	fn get_storage(&mut self, key: &Key) -> Option<Vec<u8>> {
-		self.top_frame_mut().contract_info().read(key)
+		let value = self.top_frame_mut().contract_info().read(key);
+		if_tracing(|tracer| tracer.storage_read(key, value.as_deref()));
+		value
 	}
 
 	fn get_storage_size(&mut self, key: &Key) -> Option<u32> {
@@ -1638,12 +1636,11 @@ where
 		take_old: bool,
 	) -> Result<WriteOutcome, DispatchError> {
 		let frame = self.top_frame_mut();
-		frame.contract_info.get(&frame.account_id).write(
-			key.into(),
-			value,
-			Some(&mut frame.nested_storage),
-			take_old,
-		)
+		let contract_info = frame.contract_info.get(&frame.account_id);
+		if_tracing(|tracer| {
+			tracer.storage_write(key, contract_info.read(key), value.as_deref());
+		});
+		contract_info.write(key.into(), value, Some(&mut frame.nested_storage), take_old)
 	}
 
 	fn charge_storage(&mut self, diff: &Diff) {
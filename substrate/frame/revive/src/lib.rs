@@ -44,12 +44,13 @@ pub mod weights;
 
 use crate::{
 	evm::{
-		runtime::GAS_PRICE, CallTracer, GasEncoder, GenericTransaction, Trace, Tracer, TracerType,
-		TYPE_EIP1559,
+		runtime::GAS_PRICE, CallTracer, GasEncoder, GenericTransaction, PrestateTracer,
+		StructLogger, Trace, Tracer, TracerType, TYPE_EIP1559,
 	},
-	exec::{AccountIdOf, ExecError, Executable, Key, Stack as ExecStack},
+	exec::{AccountIdOf, ExecError, Executable, Stack as ExecStack},
 	gas::GasMeter,
 	storage::{meter::Meter as StorageMeter, ContractInfo, DeletionQueueManager},
+	tracing::Tracing,
 	wasm::{CodeInfo, RuntimeCosts, WasmBlob},
 };
 use alloc::{boxed::Box, format, vec};
@@ -73,18 +74,19 @@ use frame_support::{
 use frame_system::{
 	ensure_signed,
 	pallet_prelude::{BlockNumberFor, OriginFor},
-	Pallet as System,
+	ChainContext, Pallet as System,
 };
 use scale_info::TypeInfo;
 use sp_core::{H160, H256, U256};
 use sp_runtime::{
-	traits::{BadOrigin, Bounded, Convert, Dispatchable, Saturating, Zero},
+	generic::{CheckedExtrinsic, ExtrinsicFormat},
+	traits::{BadOrigin, Bounded, Checkable, Convert, Dispatchable, Saturating, Zero},
 	AccountId32, DispatchError,
 };
 
 pub use crate::{
 	address::{create1, create2, is_eth_derived, AccountId32Mapper, AddressMapper},
-	exec::{MomentOf, Origin},
+	exec::{Key, MomentOf, Origin},
 	pallet::*,
 };
 pub use primitives::*;
@@ -1407,14 +1409,43 @@ where
 	}
 
 	/// Build an EVM tracer from the given tracer type.
-	pub fn evm_tracer(tracer_type: TracerType) -> Tracer {
+	pub fn evm_tracer(tracer_type: TracerType) -> Tracer<T> {
 		match tracer_type {
 			TracerType::CallTracer(config) => CallTracer::new(
 				config.unwrap_or_default(),
 				Self::evm_gas_from_weight as fn(Weight) -> U256,
 			)
 			.into(),
+			TracerType::PrestateTracer(config) =>
+				PrestateTracer::new(config.unwrap_or_default()).into(),
+			TracerType::StructLogger(config) => StructLogger::new(
+				config.unwrap_or_default(),
+				Self::evm_gas_from_weight as fn(Weight) -> U256,
+			)
+			.into(),
+		}
+	}
+
+	/// Trace the application of `extrinsic` by `apply`.
+	///
+	/// The state of the signer is recorded before `apply` runs, so that tracers report its
+	/// balance and nonce from before the transaction fee and nonce are charged.
+	pub fn trace_extrinsic<Xt, Call, Extension, R>(
+		tracer: &mut (dyn Tracing + 'static),
+		extrinsic: Xt,
+		apply: impl FnOnce(Xt) -> R,
+	) -> R
+	where
+		Xt: Checkable<ChainContext<T>, Checked = CheckedExtrinsic<T::AccountId, Call, Extension>>
+			+ Clone,
+	{
+		if let Ok(checked) = extrinsic.clone().check(&Default::default()) {
+			if let ExtrinsicFormat::Signed(signer, _) = checked.format {
+				tracer.watch_address(&T::AddressMapper::to_address(&signer));
+			}
 		}
+
+		tracing::trace(tracer, || apply(extrinsic))
 	}
 
 	/// A generalized version of [`Self::upload_code`].
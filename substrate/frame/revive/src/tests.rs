@@ -4179,6 +4179,213 @@ fn tracing_works_for_transfers() {
 	});
 }
 
+#[test]
+fn prestate_tracing_works_for_transfers() {
+	use crate::evm::{PrestateTrace, PrestateTracer, PrestateTracerConfig};
+	ExtBuilder::default().build().execute_with(|| {
+		let _ = <Test as Config>::Currency::set_balance(&ALICE, 100_000_000);
+		let alice_balance = Pallet::<Test>::evm_balance(&ALICE_ADDR);
+
+		let mut tracer = PrestateTracer::<Test>::new(PrestateTracerConfig::default());
+		trace(&mut tracer, || {
+			builder::bare_call(BOB_ADDR).value(10_000_000).build_and_unwrap_result();
+		});
+		let Some(PrestateTrace::Prestate(prestate)) = tracer.collect_trace() else {
+			panic!("expected a prestate trace")
+		};
+		assert_eq!(prestate[&ALICE_ADDR].balance, Some(alice_balance));
+		assert_eq!(prestate[&BOB_ADDR].balance, Some(U256::zero()));
+
+		let mut tracer = PrestateTracer::<Test>::new(PrestateTracerConfig {
+			diff_mode: true,
+			..Default::default()
+		});
+		let alice_balance = Pallet::<Test>::evm_balance(&ALICE_ADDR);
+		let bob_balance = Pallet::<Test>::evm_balance(&BOB_ADDR);
+		trace(&mut tracer, || {
+			builder::bare_call(BOB_ADDR).value(10_000_000).build_and_unwrap_result();
+		});
+		let Some(PrestateTrace::DiffMode { pre, post }) = tracer.collect_trace() else {
+			panic!("expected a diff mode trace")
+		};
+		assert_eq!(pre[&ALICE_ADDR].balance, Some(alice_balance));
+		assert_eq!(pre[&BOB_ADDR].balance, Some(bob_balance));
+		assert_eq!(post[&ALICE_ADDR].balance, Some(Pallet::<Test>::evm_balance(&ALICE_ADDR)));
+		assert_eq!(post[&BOB_ADDR].balance, Some(Pallet::<Test>::evm_balance(&BOB_ADDR)));
+		assert_eq!(post[&ALICE_ADDR].nonce, None);
+	});
+}
+
+#[test]
+fn prestate_tracing_attributes_delegate_call_storage_to_the_caller() {
+	use crate::evm::{Bytes, PrestateTrace, PrestateTracer, PrestateTracerConfig};
+	let (caller_code, _) = compile_module("delegate_call").unwrap();
+	let (callee_code, _) = compile_module("delegate_call_lib").unwrap();
+
+	ExtBuilder::default().existential_deposit(500).build().execute_with(|| {
+		let _ = <Test as Config>::Currency::set_balance(&ALICE, 1_000_000);
+		let Contract { addr: caller_addr, .. } =
+			builder::bare_instantiate(Code::Upload(caller_code))
+				.value(300_000)
+				.build_and_unwrap_contract();
+		let Contract { addr: callee_addr, .. } =
+			builder::bare_instantiate(Code::Upload(callee_code))
+				.value(100_000)
+				.build_and_unwrap_contract();
+
+		// Both contracts write to the slot `1`, the callee through a delegate call.
+		let mut slot = [0u8; 32];
+		slot[0] = 1;
+		let key = Bytes::from(slot.to_vec());
+		let mut value = [0u8; 32];
+		value[0] = 1;
+		let call = || {
+			builder::bare_call(caller_addr)
+				.value(1337)
+				.data((callee_addr, u64::MAX, u64::MAX).encode())
+				.build_and_unwrap_result();
+		};
+
+		let mut tracer = PrestateTracer::<Test>::new(PrestateTracerConfig::default());
+		trace(&mut tracer, call);
+		let Some(PrestateTrace::Prestate(prestate)) = tracer.collect_trace() else {
+			panic!("expected a prestate trace")
+		};
+		assert_eq!(prestate[&caller_addr].storage, [(key.clone(), None)].into());
+		assert!(prestate.get(&callee_addr).map_or(true, |info| info.storage.is_empty()));
+
+		// Clear the slot so the second run observes the same prestate.
+		let info = ContractInfoOf::<Test>::get(&caller_addr).unwrap();
+		info.write(&Key::Fix(slot), None, None, false).unwrap();
+		let mut tracer = PrestateTracer::<Test>::new(PrestateTracerConfig {
+			diff_mode: true,
+			..Default::default()
+		});
+		trace(&mut tracer, call);
+		let Some(PrestateTrace::DiffMode { pre, post }) = tracer.collect_trace() else {
+			panic!("expected a diff mode trace")
+		};
+		assert_eq!(pre[&caller_addr].storage, [(key.clone(), None)].into());
+		assert_eq!(post[&caller_addr].storage, [(key, Some(Bytes::from(value.to_vec())))].into());
+		assert!(post.get(&callee_addr).map_or(true, |info| info.storage.is_empty()));
+	});
+}
+
+#[test]
+fn prestate_tracing_reports_the_signer_before_fees_are_charged() {
+	use crate::evm::{PrestateTrace, PrestateTracer, PrestateTracerConfig};
+	use frame_system::ChainContext;
+	use sp_runtime::{
+		generic::{CheckedExtrinsic, ExtrinsicFormat},
+		traits::Checkable,
+		transaction_validity::TransactionValidityError,
+	};
+
+	// An extrinsic signed by `ALICE`, applied by the closure passed to `trace_extrinsic`.
+	#[derive(Clone)]
+	struct SignedByAlice;
+
+	impl Checkable<ChainContext<Test>> for SignedByAlice {
+		type Checked = CheckedExtrinsic<AccountId32, RuntimeCall, ()>;
+
+		fn check(self, _: &ChainContext<Test>) -> Result<Self::Checked, TransactionValidityError> {
+			Ok(CheckedExtrinsic {
+				format: ExtrinsicFormat::Signed(ALICE, ()),
+				function: frame_system::Call::remark { remark: vec![] }.into(),
+			})
+		}
+
+		#[cfg(feature = "try-runtime")]
+		fn unchecked_into_checked_i_know_what_i_am_doing(
+			self,
+			context: &ChainContext<Test>,
+		) -> Result<Self::Checked, TransactionValidityError> {
+			self.check(context)
+		}
+	}
+
+	ExtBuilder::default().build().execute_with(|| {
+		let _ = <Test as Config>::Currency::set_balance(&ALICE, 100_000_000);
+		let alice_balance = Pallet::<Test>::evm_balance(&ALICE_ADDR);
+		let alice_nonce = System::account_nonce(&ALICE);
+
+		let mut tracer = PrestateTracer::<Test>::new(PrestateTracerConfig::default());
+		Pallet::<Test>::trace_extrinsic(&mut tracer, SignedByAlice, |_| {
+			// Charge the fee and bump the nonce, like the transaction extensions do.
+			<Test as Config>::Currency::burn_from(
+				&ALICE,
+				1_000,
+				Preservation::Preserve,
+				frame_support::traits::tokens::Precision::Exact,
+				frame_support::traits::tokens::Fortitude::Polite,
+			)
+			.unwrap();
+			System::inc_account_nonce(&ALICE);
+			builder::bare_call(BOB_ADDR).value(10_000_000).build_and_unwrap_result();
+		});
+		let Some(PrestateTrace::Prestate(prestate)) = tracer.collect_trace() else {
+			panic!("expected a prestate trace")
+		};
+		assert_eq!(prestate[&ALICE_ADDR].balance, Some(alice_balance));
+		assert_eq!(prestate[&ALICE_ADDR].nonce, Some(alice_nonce as u32));
+	});
+}
+
+#[test]
+fn struct_logger_works() {
+	use crate::evm::{StructLogger, StructLoggerConfig};
+	let (code, _code_hash) = compile_module("tracing_callee").unwrap();
+	ExtBuilder::default().existential_deposit(200).build().execute_with(|| {
+		let _ = <Test as Config>::Currency::set_balance(&ALICE, 100_000_000);
+		let Contract { addr, .. } =
+			builder::bare_instantiate(Code::Upload(code)).build_and_unwrap_contract();
+
+		let mut tracer = StructLogger::new(StructLoggerConfig::default(), |w: Weight| w);
+		trace(&mut tracer, || {
+			builder::bare_call(addr).data(0u32.encode()).build_and_unwrap_result();
+		});
+
+		let trace = tracer.collect_trace().unwrap();
+		assert!(!trace.failed);
+		assert_eq!(trace.return_value, 0u32.to_le_bytes().to_vec().into());
+
+		let last_step = trace.struct_logs.last().unwrap();
+		assert_eq!(last_step.op, "seal_return");
+		assert!(trace.struct_logs.iter().all(|step| step.depth == 1 &&
+			step.stack.as_ref().is_some_and(|args| args.len() == 6) &&
+			step.memory.is_none() &&
+			step.gas_cost.all_lte(step.gas)));
+
+		let mut tracer = StructLogger::new(
+			StructLoggerConfig { disable_stack: true, enable_memory: true, ..Default::default() },
+			|w: Weight| w,
+		);
+		trace(&mut tracer, || {
+			builder::bare_call(addr).data(0u32.encode()).build_and_unwrap_result();
+		});
+		let trace = tracer.collect_trace().unwrap();
+		assert!(trace
+			.struct_logs
+			.iter()
+			.all(|step| step.stack.is_none() && step.memory.is_some()));
+
+		// Running out of gas is reported on the last step.
+		let gas_required = builder::bare_call(addr).data(0u32.encode()).build().gas_required;
+		let mut tracer = StructLogger::new(StructLoggerConfig::default(), |w: Weight| w);
+		trace(&mut tracer, || {
+			builder::bare_call(addr)
+				.data(0u32.encode())
+				.gas_limit(gas_required.sub_ref_time(1))
+				.build();
+		});
+		let trace = tracer.collect_trace().unwrap();
+		assert!(trace.failed);
+		let last_step = trace.struct_logs.last().unwrap();
+		assert_eq!(last_step.error.as_deref(), Some("OutOfGas"));
+		assert!(last_step.gas_cost.all_lte(last_step.gas));
+	});
+}
+
 #[test]
 fn tracing_works() {
 	use crate::evm::*;
@@ -15,7 +15,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::{primitives::ExecReturnValue, DispatchError, Weight};
+use crate::{exec::Key, primitives::ExecReturnValue, DispatchError, Weight};
+use alloc::vec::Vec;
 use environmental::environmental;
 use sp_core::{H160, H256, U256};
 
@@ -41,6 +42,9 @@ pub(crate) fn if_tracing<F: FnOnce(&mut (dyn Tracing + 'static))>(f: F) {
 
 /// Defines methods to trace contract interactions.
 pub trait Tracing {
+	/// Register an address whose state should be recorded by the tracer.
+	fn watch_address(&mut self, _addr: &H160) {}
+
 	/// Called before a contract call is executed
 	fn enter_child_span(
 		&mut self,
@@ -54,6 +58,23 @@ pub trait Tracing {
 	) {
 	}
 
+	/// Called before a syscall of the current contract is executed.
+	///
+	/// `args` are the raw syscall arguments and `memory` returns a snapshot of the contract
+	/// memory. It is only meant to be called by tracers that record the memory.
+	fn enter_opcode(
+		&mut self,
+		_pc: u64,
+		_opcode: &[u8],
+		_gas_left: Weight,
+		_args: &[u64],
+		_memory: &dyn Fn() -> Vec<u8>,
+	) {
+	}
+
+	/// Called after a syscall of the current contract is executed.
+	fn exit_opcode(&mut self, _gas_left: Weight) {}
+
 	/// Record a log event
 	fn log_event(&mut self, _event: H160, _topics: &[H256], _data: &[u8]) {}
 
@@ -62,4 +83,16 @@ pub trait Tracing {
 
 	/// Called when a contract call terminates with an error
 	fn exit_child_span_with_error(&mut self, _error: DispatchError, _gas_left: Weight) {}
+
+	/// Called when a storage slot of the current contract is read.
+	fn storage_read(&mut self, _key: &Key, _value: Option<&[u8]>) {}
+
+	/// Called before a storage slot of the current contract is written.
+	fn storage_write(
+		&mut self,
+		_key: &Key,
+		_old_value: Option<Vec<u8>>,
+		_new_value: Option<&[u8]>,
+	) {
+	}
 }
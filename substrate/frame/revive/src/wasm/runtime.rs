@@ -146,6 +146,8 @@ pub trait PolkaVmInstance<T: Config>: Memory<T> {
 	fn set_gas(&mut self, gas: polkavm::Gas);
 	fn read_input_regs(&self) -> (u64, u64, u64, u64, u64, u64);
 	fn write_output(&mut self, output: u64);
+	fn program_counter(&self) -> u64;
+	fn memory_snapshot(&self) -> Vec<u8>;
 }
 
 // Memory implementation used in benchmarking where guest memory is mapped into the host.
@@ -216,6 +218,18 @@ impl<T: Config> PolkaVmInstance<T> for polkavm::RawInstance {
 	fn write_output(&mut self, output: u64) {
 		self.set_reg(polkavm::Reg::A0, output);
 	}
+
+	fn program_counter(&self) -> u64 {
+		self.program_counter().map_or(0, |pc| pc.0.into())
+	}
+
+	fn memory_snapshot(&self) -> Vec<u8> {
+		// The writable data and the heap, which hold the memory managed by the contract.
+		let memory_map = self.module().memory_map();
+		let start = memory_map.rw_data_address();
+		let end = memory_map.heap_base().saturating_add(self.heap_size());
+		self.read_memory(start, end.saturating_sub(start)).unwrap_or_default()
+	}
 }
 
 parameter_types! {
@@ -20,7 +20,7 @@
 //! This implements the digests for AuRa, to allow the private
 //! `CompatibleDigestItem` trait to appear in public interfaces.
 
-use crate::AURA_ENGINE_ID;
+use crate::{AURA_ENGINE_ID, AURA_SLOT_CLAIM_ENGINE_ID};
 use codec::{Codec, Encode};
 use sp_consensus_slots::Slot;
 use sp_runtime::generic::DigestItem;
@@ -38,6 +38,12 @@ pub trait CompatibleDigestItem<Signature>: Sized {
 
 	/// If this item is an AuRa pre-digest, return the slot number
 	fn as_aura_pre_digest(&self) -> Option<Slot>;
+
+	/// Construct a digest item which contains the index of the block within its slot
+	fn aura_slot_claim(index: u32) -> Self;
+
+	/// If this item is an AuRa slot claim, return the index of the block within its slot
+	fn as_aura_slot_claim(&self) -> Option<u32>;
 }
 
 impl<Signature> CompatibleDigestItem<Signature> for DigestItem
@@ -59,4 +65,12 @@ where
 	fn as_aura_pre_digest(&self) -> Option<Slot> {
 		self.pre_runtime_try_to(&AURA_ENGINE_ID)
 	}
+
+	fn aura_slot_claim(index: u32) -> Self {
+		DigestItem::PreRuntime(AURA_SLOT_CLAIM_ENGINE_ID, index.encode())
+	}
+
+	fn as_aura_slot_claim(&self) -> Option<u32> {
+		self.pre_runtime_try_to(&AURA_SLOT_CLAIM_ENGINE_ID)
+	}
 }
@@ -69,6 +69,13 @@ pub use sp_consensus_slots::{Slot, SlotDuration};
 /// The `ConsensusEngineId` of AuRa.
 pub const AURA_ENGINE_ID: ConsensusEngineId = [b'a', b'u', b'r', b'a'];
 
+/// The `ConsensusEngineId` of the pre-runtime digest holding an AuRa slot claim.
+///
+/// The slot claim is the index of a block among the blocks built on the same slot. It is only
+/// present in blocks which are not the first block of their slot, see
+/// [`AuraApi::max_blocks_per_slot`].
+pub const AURA_SLOT_CLAIM_ENGINE_ID: ConsensusEngineId = [b'a', b'u', b'r', b'c'];
+
 /// The index of an authority.
 pub type AuthorityIndex = u32;
 
@@ -93,5 +100,13 @@ sp_api::decl_runtime_apis! {
 
 		/// Return the current set of authorities.
 		fn authorities() -> Vec<AuthorityId>;
+
+		/// Returns the maximum number of blocks an author may build within a single slot.
+		///
+		/// Every block following another block on the same slot has to claim its index within the
+		/// slot through an [`AURA_SLOT_CLAIM_ENGINE_ID`] pre-runtime digest. Runtimes that only
+		/// implement version 1 of this api allow one block per slot.
+		#[api_version(2)]
+		fn max_blocks_per_slot() -> u32;
 	}
 }
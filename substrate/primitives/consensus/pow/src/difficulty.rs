@@ -0,0 +1,195 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Difficulty adjustment algorithms for PoW chains.
+//!
+//! The algorithms are plain functions over the timestamps and difficulties of previous blocks, so
+//! they can be used from the runtime (through the [`DifficultyApi`](crate::DifficultyApi)) as well
+//! as from a node side `PowAlgorithm` implementation. Difficulties are expressed as the expected
+//! number of hashes needed to find a seal, i.e. a higher difficulty means a lower target.
+
+use sp_core::{U256, U512};
+
+/// Linearly weighted moving average (LWMA) difficulty adjustment.
+///
+/// `blocks` contains the `(timestamp, difficulty)` pairs of the last blocks in the averaging
+/// window, ordered from the oldest to the most recent one. Recent solve times are weighted more
+/// than older ones, which lets the difficulty react quickly to hash rate changes while staying
+/// resistant to timestamp manipulation, because each solve time is clamped to
+/// `1..=6 * target_block_time`.
+///
+/// Returns `None` if `blocks` contains fewer than two entries or `target_block_time` is zero.
+pub fn lwma(blocks: &[(u64, U256)], target_block_time: u64) -> Option<U256> {
+	if blocks.len() < 2 || target_block_time == 0 {
+		return None
+	}
+
+	let window = blocks.len() as u64 - 1;
+	let mut weighted_solve_times = 0u128;
+	let mut difficulty_sum = U256::zero();
+
+	for (weight, pair) in (1u64..).zip(blocks.windows(2)) {
+		let (previous_timestamp, _) = pair[0];
+		let (timestamp, difficulty) = pair[1];
+
+		let solve_time =
+			timestamp.saturating_sub(previous_timestamp).clamp(1, 6 * target_block_time);
+
+		weighted_solve_times += weight as u128 * solve_time as u128;
+		difficulty_sum = difficulty_sum.saturating_add(difficulty);
+	}
+
+	// Don't allow the difficulty to grow by more than a factor of 10 at once.
+	let lower_bound = (window as u128 * (window as u128 + 1) * target_block_time as u128) / 20;
+	let weighted_solve_times = weighted_solve_times.max(lower_bound).max(1);
+
+	// `average difficulty * target block time / weighted average solve time`, where the
+	// weights sum up to `window * (window + 1) / 2`.
+	let next = difficulty_sum
+		.saturating_mul(U256::from(target_block_time as u128 * (window as u128 + 1))) /
+		U256::from(2 * weighted_solve_times);
+
+	Some(next.max(U256::one()))
+}
+
+/// Absolutely scheduled exponentially rising targets (ASERT) difficulty adjustment.
+///
+/// This is the `aserti3-2d` algorithm with the difficulty, instead of the target, as input and
+/// output. The next difficulty only depends on how far the chain is ahead of or behind its ideal
+/// schedule since a fixed anchor block, and halves (or doubles) for every `half_life` the chain is
+/// behind (or ahead of) that schedule.
+///
+/// * `anchor_difficulty` - Difficulty of the anchor block.
+/// * `time_delta` - Timestamp of the parent of the block the difficulty is computed for, minus the
+///   timestamp of the parent of the anchor block.
+/// * `height_delta` - Height of the parent of the block the difficulty is computed for, minus the
+///   height of the anchor block.
+/// * `target_block_time` - The ideal time between two blocks.
+/// * `half_life` - The time after which the difficulty halves if no block is produced. Must be
+///   expressed in the same unit as the other times.
+pub fn asert(
+	anchor_difficulty: U256,
+	time_delta: i64,
+	height_delta: u64,
+	target_block_time: u64,
+	half_life: u64,
+) -> U256 {
+	const RADIX_BITS: i128 = 16;
+
+	let half_life = half_life.max(1) as i128;
+	let schedule = target_block_time as i128 * (height_delta as i128 + 1);
+
+	// Fixed point exponent of two, with `RADIX_BITS` fractional bits. The sign is flipped in
+	// comparison to the target based formula, as the difficulty falls when the chain is late.
+	let exponent = ((schedule - time_delta as i128) << RADIX_BITS) / half_life;
+
+	let shifts = exponent >> RADIX_BITS;
+	let frac = (exponent & 0xffff) as u128;
+
+	// Cubic approximation of `2^frac` as specified by `aserti3-2d`, scaled by `2^RADIX_BITS`.
+	let factor = 65536u128 +
+		((195_766_423_245_049 * frac +
+			971_821_376 * frac.pow(2) +
+			5_127 * frac.pow(3) +
+			(1 << 47)) >>
+			48);
+
+	// The factor has 17 bits, so the product fits into 512 bits.
+	let next = U512::from(anchor_difficulty) * U512::from(factor);
+
+	let shifts = shifts - RADIX_BITS;
+	let next = if shifts >= 0 {
+		if shifts > next.leading_zeros() as i128 {
+			return U256::MAX
+		}
+		next << shifts as usize
+	} else if -shifts >= 512 {
+		U512::zero()
+	} else {
+		next >> (-shifts) as usize
+	};
+
+	U256::try_from(next).unwrap_or(U256::MAX).max(U256::one())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const BLOCK_TIME: u64 = 60;
+
+	fn chain(solve_time: u64, difficulty: u64, len: u64) -> Vec<(u64, U256)> {
+		(0..len).map(|i| (i * solve_time, U256::from(difficulty))).collect()
+	}
+
+	#[test]
+	fn lwma_keeps_difficulty_on_schedule() {
+		assert_eq!(lwma(&chain(BLOCK_TIME, 1_000_000, 61), BLOCK_TIME), Some(1_000_000.into()));
+	}
+
+	#[test]
+	fn lwma_follows_solve_times() {
+		assert_eq!(lwma(&chain(2 * BLOCK_TIME, 1_000_000, 61), BLOCK_TIME), Some(500_000.into()));
+		assert_eq!(lwma(&chain(BLOCK_TIME / 2, 1_000_000, 61), BLOCK_TIME), Some(2_000_000.into()));
+	}
+
+	#[test]
+	fn lwma_limits_difficulty_increase() {
+		assert_eq!(lwma(&chain(0, 1_000_000, 61), BLOCK_TIME), Some(10_000_000.into()));
+	}
+
+	#[test]
+	fn lwma_requires_two_blocks() {
+		assert_eq!(lwma(&chain(BLOCK_TIME, 1_000_000, 1), BLOCK_TIME), None);
+	}
+
+	#[test]
+	fn asert_keeps_difficulty_on_schedule() {
+		let anchor = U256::from(1_000_000);
+		let on_schedule = (BLOCK_TIME * 11) as i64;
+
+		assert_eq!(asert(anchor, on_schedule, 10, BLOCK_TIME, 3600), anchor);
+	}
+
+	#[test]
+	fn asert_halves_difficulty_per_half_life() {
+		let anchor = U256::from(1_000_000);
+		let on_schedule = (BLOCK_TIME * 11) as i64;
+
+		assert_eq!(asert(anchor, on_schedule + 3600, 10, BLOCK_TIME, 3600), anchor / 2);
+		assert_eq!(asert(anchor, on_schedule - 3600, 10, BLOCK_TIME, 3600), anchor * 2);
+		assert_eq!(asert(anchor, on_schedule + 2 * 3600, 10, BLOCK_TIME, 3600), anchor / 4);
+	}
+
+	#[test]
+	fn asert_handles_large_difficulties() {
+		let on_schedule = (BLOCK_TIME * 11) as i64;
+
+		// The anchor difficulty times the fixed point factor exceeds 256 bits.
+		assert_eq!(asert(U256::MAX >> 2, on_schedule + 3600, 10, BLOCK_TIME, 3600), U256::MAX >> 3);
+		// Shifting up to the most significant bit doesn't saturate.
+		let ahead = |half_lives: i64| on_schedule - half_lives * 3600;
+		assert_eq!(asert(U256::one(), ahead(255), 10, BLOCK_TIME, 3600), U256::one() << 255);
+		assert_eq!(asert(U256::one(), ahead(256), 10, BLOCK_TIME, 3600), U256::MAX);
+	}
+
+	#[test]
+	fn asert_saturates() {
+		assert_eq!(asert(U256::MAX, -1_000_000, 0, BLOCK_TIME, 1), U256::MAX);
+		assert_eq!(asert(U256::from(1), 1_000_000, 0, BLOCK_TIME, 1), U256::one());
+	}
+}
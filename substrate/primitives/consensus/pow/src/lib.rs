@@ -26,6 +26,8 @@ use alloc::vec::Vec;
 use codec::Decode;
 use sp_runtime::ConsensusEngineId;
 
+pub mod difficulty;
+
 /// The `ConsensusEngineId` of PoW.
 pub const POW_ENGINE_ID: ConsensusEngineId = [b'p', b'o', b'w', b'_'];
 
@@ -154,7 +154,7 @@ mod std_reexport {
 	pub use crate::{testing::TestExternalities, trie_backend::create_proof_check_backend};
 	pub use sp_trie::{
 		trie_types::{TrieDBMutV0, TrieDBMutV1},
-		CompactProof, DBValue, LayoutV0, LayoutV1, MemoryDB, StorageProof, TrieMut,
+		CompactProof, DBValue, LayoutV0, LayoutV1, MemoryDB, StorageProof, TrieDiff, TrieMut,
 	};
 }
 
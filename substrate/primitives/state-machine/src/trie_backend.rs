@@ -388,6 +388,22 @@ where
 		self.essence.root()
 	}
 
+	/// Compare the main trie, or the given child trie, with the one of `previous`, and call
+	/// `on_diff` with every key whose value differs, in lexicographic order.
+	///
+	/// See [`TrieBackendEssence::storage_diff`] for details.
+	pub fn storage_diff(
+		&self,
+		previous: &Self,
+		child_info: Option<&ChildInfo>,
+		on_diff: impl FnMut(sp_trie::TrieDiff) -> bool,
+	) -> Result<(), crate::DefaultError>
+	where
+		H::Out: Ord,
+	{
+		self.essence.storage_diff(&previous.essence, child_info, on_diff)
+	}
+
 	/// Consumes self and returns underlying storage.
 	pub fn into_storage(self) -> S {
 		self.essence.into_storage()
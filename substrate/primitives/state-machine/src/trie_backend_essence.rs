@@ -35,10 +35,10 @@ use sp_core::storage::{ChildInfo, ChildType, StateVersion};
 use sp_trie::{
 	child_delta_trie_root, delta_trie_root, empty_child_trie_root,
 	read_child_trie_first_descendant_value, read_child_trie_hash, read_child_trie_value,
-	read_trie_first_descendant_value, read_trie_value,
+	read_trie_first_descendant_value, read_trie_value, trie_diff,
 	trie_types::{TrieDBBuilder, TrieError},
 	DBValue, KeySpacedDB, MerkleValue, NodeCodec, PrefixedMemoryDB, Trie, TrieCache,
-	TrieDBRawIterator, TrieRecorder, TrieRecorderProvider,
+	TrieDBRawIterator, TrieDiff, TrieRecorder, TrieRecorderProvider,
 };
 #[cfg(feature = "std")]
 use std::collections::HashMap;
@@ -549,6 +549,44 @@ where
 		})
 	}
 
+	/// Compare the main trie, or the given child trie, with the one of `previous`, and call
+	/// `on_diff` with every key whose value differs, in lexicographic order.
+	///
+	/// Only the nodes that differ between the two tries are read. The comparison stops as soon
+	/// as `on_diff` returns `false`.
+	pub fn storage_diff(
+		&self,
+		previous: &Self,
+		child_info: Option<&ChildInfo>,
+		on_diff: impl FnMut(TrieDiff) -> bool,
+	) -> Result<()> {
+		let map_e = |e| format!("Trie lookup error: {}", e);
+		let previous_backend = previous as &dyn HashDBRef<H, Vec<u8>>;
+		let backend = self as &dyn HashDBRef<H, Vec<u8>>;
+
+		match child_info {
+			Some(child_info) => {
+				let previous_root = previous.child_root(child_info)?;
+				let root = self.child_root(child_info)?;
+				trie_diff::<Layout<H>>(
+					&KeySpacedDB::new(previous_backend, child_info.keyspace()),
+					previous_root.as_ref(),
+					&KeySpacedDB::new(backend, child_info.keyspace()),
+					root.as_ref(),
+					on_diff,
+				)
+			},
+			None => trie_diff::<Layout<H>>(
+				previous_backend,
+				Some(&previous.root),
+				backend,
+				Some(&self.root),
+				on_diff,
+			),
+		}
+		.map_err(map_e)
+	}
+
 	/// Get the closest merkle value at given key.
 	pub fn closest_merkle_value(&self, key: &[u8]) -> Result<Option<MerkleValue<H::Out>>> {
 		let map_e = |e| format!("Trie lookup error: {}", e);
@@ -939,4 +977,80 @@ mod test {
 		assert_eq!(essence_2.next_child_storage_key(child_info, b"5"), Ok(Some(b"6".to_vec())));
 		assert_eq!(essence_2.next_child_storage_key(child_info, b"6"), Ok(None));
 	}
+
+	#[test]
+	fn storage_diff_works() {
+		let child_info = ChildInfo::new_default(b"MyChild");
+		let child_info = &child_info;
+		let mut child_root_1 = H256::default();
+		let mut child_root_2 = H256::default();
+		let mut root_1 = H256::default();
+		let mut root_2 = H256::default();
+
+		let mut mdb = PrefixedMemoryDB::<Blake2Hasher>::default();
+		{
+			let mut mdb = KeySpacedDBMut::new(&mut mdb, child_info.keyspace());
+			let mut trie = TrieDBMutBuilder::new(&mut mdb, &mut child_root_1).build();
+			trie.insert(b"3", &[1]).expect("insert failed");
+			trie.insert(b"4", &[1]).expect("insert failed");
+			trie.insert(b"6", &[1]).expect("insert failed");
+		}
+		{
+			let mut mdb = KeySpacedDBMut::new(&mut mdb, child_info.keyspace());
+			let mut trie = TrieDBMutBuilder::new(&mut mdb, &mut child_root_2).build();
+			trie.insert(b"3", &[2]).expect("insert failed");
+			trie.insert(b"4", &[1]).expect("insert failed");
+			trie.insert(b"7", &[1]).expect("insert failed");
+		}
+		{
+			let mut trie = TrieDBMutBuilder::new(&mut mdb, &mut root_1).build();
+			trie.insert(b"1", &[1]).expect("insert failed");
+			trie.insert(child_info.prefixed_storage_key().as_slice(), child_root_1.as_ref())
+				.expect("insert failed");
+		}
+		{
+			let mut trie = TrieDBMutBuilder::new(&mut mdb, &mut root_2).build();
+			trie.insert(b"1", &[1]).expect("insert failed");
+			trie.insert(b"2", &[1]).expect("insert failed");
+			trie.insert(child_info.prefixed_storage_key().as_slice(), child_root_2.as_ref())
+				.expect("insert failed");
+		}
+
+		let essence_1 =
+			TrieBackendEssence::<_, _, LocalTrieCache<_>, sp_trie::recorder::Recorder<_>>::new(
+				mdb.clone(),
+				root_1,
+			);
+		let essence_2 =
+			TrieBackendEssence::<_, _, LocalTrieCache<_>, sp_trie::recorder::Recorder<_>>::new(
+				mdb, root_2,
+			);
+
+		let diff = |child_info| {
+			let mut diff = Vec::new();
+			essence_2
+				.storage_diff(&essence_1, child_info, |item| {
+					diff.push(item);
+					true
+				})
+				.unwrap();
+			diff
+		};
+
+		assert_eq!(
+			diff(None),
+			vec![
+				TrieDiff::Added(b"2".to_vec()),
+				TrieDiff::Modified(child_info.prefixed_storage_key().into_inner()),
+			]
+		);
+		assert_eq!(
+			diff(Some(child_info)),
+			vec![
+				TrieDiff::Modified(b"3".to_vec()),
+				TrieDiff::Deleted(b"6".to_vec()),
+				TrieDiff::Added(b"7".to_vec()),
+			]
+		);
+	}
 }
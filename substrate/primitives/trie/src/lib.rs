@@ -32,6 +32,7 @@ pub mod recorder;
 pub mod recorder_ext;
 mod storage_proof;
 mod trie_codec;
+mod trie_diff;
 mod trie_stream;
 
 #[cfg(feature = "std")]
@@ -63,6 +64,8 @@ pub use trie_db::{
 	TrieRecorder,
 };
 pub use trie_db::{proof::VerifyError, MerkleValue};
+/// Comparison of two tries.
+pub use trie_diff::{trie_diff, TrieDiff};
 /// The Substrate format implementation of `TrieStream`.
 pub use trie_stream::TrieStream;
 
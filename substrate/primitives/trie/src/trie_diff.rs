@@ -0,0 +1,375 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Compute the keys that differ between two tries by comparing their nodes.
+
+use crate::{TrieError, TrieHash, TrieLayout};
+use alloc::{boxed::Box, vec::Vec};
+use hash_db::{HashDBRef, Prefix};
+use trie_db::{
+	node::{Node, NodeHandle, Value},
+	DBValue, NibbleVec, NodeCodec as NodeCodecT,
+};
+
+/// A key whose value differs between two tries.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrieDiff {
+	/// The key only exists in the new trie.
+	Added(Vec<u8>),
+	/// The key exists in both tries, with different values.
+	Modified(Vec<u8>),
+	/// The key only exists in the previous trie.
+	Deleted(Vec<u8>),
+}
+
+/// A subtrie of one of the compared tries.
+enum SubTrie<L: TrieLayout> {
+	/// A node stored in the database under the given hash.
+	Hash(TrieHash<L>),
+	/// An encoded node, of which the first `consumed` nibbles of the partial key were already
+	/// traversed.
+	Node { encoded: Vec<u8>, consumed: usize },
+}
+
+/// A reference to a value stored in a trie.
+enum ValueRef<L: TrieLayout> {
+	/// A value stored inline in its node.
+	Inline(Vec<u8>),
+	/// A value stored in the database under the given hash.
+	Hash(TrieHash<L>),
+}
+
+// Implemented by hand, since deriving it would require `L: PartialEq`.
+impl<L: TrieLayout> PartialEq for SubTrie<L> {
+	fn eq(&self, other: &Self) -> bool {
+		match (self, other) {
+			(SubTrie::Hash(hash), SubTrie::Hash(other_hash)) => hash == other_hash,
+			(
+				SubTrie::Node { encoded, consumed },
+				SubTrie::Node { encoded: other_encoded, consumed: other_consumed },
+			) => encoded == other_encoded && consumed == other_consumed,
+			_ => false,
+		}
+	}
+}
+
+impl<L: TrieLayout> PartialEq for ValueRef<L> {
+	fn eq(&self, other: &Self) -> bool {
+		match (self, other) {
+			(ValueRef::Inline(value), ValueRef::Inline(other_value)) => value == other_value,
+			(ValueRef::Hash(hash), ValueRef::Hash(other_hash)) => hash == other_hash,
+			_ => false,
+		}
+	}
+}
+
+/// A subtrie seen as a branch at the current path.
+struct Expanded<L: TrieLayout> {
+	value: Option<ValueRef<L>>,
+	children: [Option<SubTrie<L>>; 16],
+}
+
+impl<L: TrieLayout> Default for Expanded<L> {
+	fn default() -> Self {
+		Self { value: None, children: Default::default() }
+	}
+}
+
+/// Compare the trie with root `previous_root` to the trie with root `root`, and call `on_diff`
+/// with every key whose value differs, in lexicographic order.
+///
+/// Subtries that are identical in both tries are skipped, so the cost is proportional to the
+/// number of nodes that changed rather than to the number of keys. A `None` root stands for a trie
+/// that does not exist. The comparison stops as soon as `on_diff` returns `false`.
+pub fn trie_diff<L: TrieLayout>(
+	previous_db: &dyn HashDBRef<L::Hash, DBValue>,
+	previous_root: Option<&TrieHash<L>>,
+	db: &dyn HashDBRef<L::Hash, DBValue>,
+	root: Option<&TrieHash<L>>,
+	mut on_diff: impl FnMut(TrieDiff) -> bool,
+) -> Result<(), Box<TrieError<L>>> {
+	let differ = Differ::<L> { previous_db, db };
+	differ.diff(
+		previous_root.map(|root| SubTrie::Hash(*root)),
+		root.map(|root| SubTrie::Hash(*root)),
+		&mut NibbleVec::new(),
+		&mut on_diff,
+	)?;
+	Ok(())
+}
+
+struct Differ<'a, L: TrieLayout> {
+	previous_db: &'a dyn HashDBRef<L::Hash, DBValue>,
+	db: &'a dyn HashDBRef<L::Hash, DBValue>,
+}
+
+impl<'a, L: TrieLayout> Differ<'a, L> {
+	/// Compare two subtries at `path`. Returns `false` if the comparison was stopped.
+	fn diff(
+		&self,
+		previous: Option<SubTrie<L>>,
+		new: Option<SubTrie<L>>,
+		path: &mut NibbleVec,
+		on_diff: &mut impl FnMut(TrieDiff) -> bool,
+	) -> Result<bool, Box<TrieError<L>>> {
+		if previous == new {
+			return Ok(true)
+		}
+
+		let previous = previous
+			.map(|subtrie| expand(self.previous_db, subtrie, path))
+			.transpose()?
+			.unwrap_or_default();
+		let new = new
+			.map(|subtrie| expand(self.db, subtrie, path))
+			.transpose()?
+			.unwrap_or_default();
+
+		let key = || path.inner().to_vec();
+		let diff = match (previous.value, new.value) {
+			(None, None) => None,
+			(None, Some(_)) => Some(TrieDiff::Added(key())),
+			(Some(_), None) => Some(TrieDiff::Deleted(key())),
+			(Some(previous_value), Some(value)) => {
+				let modified = match (&previous_value, &value) {
+					(ValueRef::Inline(_), ValueRef::Inline(_)) |
+					(ValueRef::Hash(_), ValueRef::Hash(_)) => previous_value != value,
+					// The same value can be stored inline in one trie and hashed in the other one.
+					_ =>
+						fetch_value(self.previous_db, previous_value, path.as_prefix())? !=
+							fetch_value(self.db, value, path.as_prefix())?,
+				};
+				modified.then(|| TrieDiff::Modified(key()))
+			},
+		};
+		if let Some(diff) = diff {
+			if !on_diff(diff) {
+				return Ok(false)
+			}
+		}
+
+		for (nibble, (previous_child, child)) in
+			previous.children.into_iter().zip(new.children).enumerate()
+		{
+			path.push(nibble as u8);
+			let proceed = self.diff(previous_child, child, path, on_diff)?;
+			path.pop();
+			if !proceed {
+				return Ok(false)
+			}
+		}
+
+		Ok(true)
+	}
+}
+
+/// Decode a node hash.
+fn decode_hash<L: TrieLayout>(data: &[u8]) -> Result<TrieHash<L>, Box<TrieError<L>>> {
+	let mut hash = TrieHash::<L>::default();
+	if data.len() != hash.as_ref().len() {
+		return Err(Box::new(TrieError::<L>::InvalidHash(hash, data.to_vec())))
+	}
+	hash.as_mut().copy_from_slice(data);
+	Ok(hash)
+}
+
+/// Convert a node handle to a subtrie.
+fn to_subtrie<L: TrieLayout>(handle: NodeHandle) -> Result<SubTrie<L>, Box<TrieError<L>>> {
+	Ok(match handle {
+		NodeHandle::Hash(hash) => SubTrie::Hash(decode_hash::<L>(hash)?),
+		NodeHandle::Inline(encoded) => SubTrie::Node { encoded: encoded.to_vec(), consumed: 0 },
+	})
+}
+
+/// Convert a value to a value reference.
+fn value_ref<L: TrieLayout>(value: Value) -> Result<ValueRef<L>, Box<TrieError<L>>> {
+	Ok(match value {
+		Value::Inline(value) => ValueRef::Inline(value.to_vec()),
+		Value::Node(hash) => ValueRef::Hash(decode_hash::<L>(hash)?),
+	})
+}
+
+/// Read the value referenced by `value`, stored under the given key.
+fn fetch_value<L: TrieLayout>(
+	db: &dyn HashDBRef<L::Hash, DBValue>,
+	value: ValueRef<L>,
+	prefix: Prefix,
+) -> Result<Vec<u8>, Box<TrieError<L>>> {
+	match value {
+		ValueRef::Inline(value) => Ok(value),
+		ValueRef::Hash(hash) => db
+			.get(&hash, prefix)
+			.ok_or_else(|| Box::new(TrieError::<L>::IncompleteDatabase(hash))),
+	}
+}
+
+/// Expand the subtrie located at `path` into the value stored at `path` and the subtries of its
+/// 16 children.
+fn expand<L: TrieLayout>(
+	db: &dyn HashDBRef<L::Hash, DBValue>,
+	mut subtrie: SubTrie<L>,
+	path: &NibbleVec,
+) -> Result<Expanded<L>, Box<TrieError<L>>> {
+	loop {
+		let (hash, encoded, consumed) = match subtrie {
+			SubTrie::Hash(hash) => {
+				let encoded = db
+					.get(&hash, path.as_prefix())
+					.ok_or_else(|| Box::new(TrieError::<L>::IncompleteDatabase(hash)))?;
+				(hash, encoded, 0)
+			},
+			SubTrie::Node { encoded, consumed } => (Default::default(), encoded, consumed),
+		};
+		let node = L::Codec::decode(&encoded)
+			.map_err(|error| Box::new(TrieError::<L>::DecoderError(hash, error)))?;
+
+		let mut expanded = Expanded::default();
+		// The subtrie made of the rest of the node, when the path is within its partial key.
+		let mut rest_of_partial = |partial: trie_db::NibbleSlice| {
+			expanded.children[partial.at(consumed) as usize] =
+				Some(SubTrie::Node { encoded: encoded.clone(), consumed: consumed + 1 });
+		};
+
+		match node {
+			Node::Empty => {},
+			Node::Leaf(partial, value) if consumed == partial.len() => {
+				expanded.value = Some(value_ref::<L>(value)?);
+			},
+			Node::Leaf(partial, _) => rest_of_partial(partial),
+			Node::Extension(partial, child) if consumed == partial.len() => {
+				subtrie = to_subtrie::<L>(child)?;
+				continue
+			},
+			Node::Extension(partial, _) => rest_of_partial(partial),
+			Node::NibbledBranch(partial, _, _) if consumed < partial.len() =>
+				rest_of_partial(partial),
+			Node::Branch(children, value) | Node::NibbledBranch(_, children, value) => {
+				expanded.value = value.map(value_ref::<L>).transpose()?;
+				for (child, handle) in expanded.children.iter_mut().zip(children) {
+					*child = handle.map(to_subtrie::<L>).transpose()?;
+				}
+			},
+		}
+
+		return Ok(expanded)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::{LayoutV0, LayoutV1, MemoryDB, TrieDBMutBuilder, TrieMut};
+	use sp_core::Blake2Hasher;
+
+	fn create_trie<L: TrieLayout<Hash = Blake2Hasher>>(
+		db: &mut MemoryDB<Blake2Hasher>,
+		data: &[(&[u8], &[u8])],
+	) -> TrieHash<L> {
+		let mut root = Default::default();
+		{
+			let mut trie = TrieDBMutBuilder::<L>::new(db, &mut root).build();
+			for (key, value) in data {
+				trie.insert(key, value).unwrap();
+			}
+		}
+		root
+	}
+
+	fn collect_diff<L: TrieLayout<Hash = Blake2Hasher>>(
+		db: &MemoryDB<Blake2Hasher>,
+		previous_root: Option<&TrieHash<L>>,
+		root: Option<&TrieHash<L>>,
+	) -> Vec<TrieDiff> {
+		let mut diff = Vec::new();
+		trie_diff::<L>(db, previous_root, db, root, |item| {
+			diff.push(item);
+			true
+		})
+		.unwrap();
+		diff
+	}
+
+	#[test]
+	fn trie_diff_works() {
+		let long_value = [7u8; 64];
+		let mut db = MemoryDB::default();
+		let previous_root = create_trie::<LayoutV1<Blake2Hasher>>(
+			&mut db,
+			&[
+				(b"alfa", b"1"),
+				(b"bravo", b"2"),
+				(b"bravo2", &long_value),
+				(b"charlie", b"3"),
+				(b"delta", b"4"),
+			],
+		);
+		let root = create_trie::<LayoutV1<Blake2Hasher>>(
+			&mut db,
+			&[
+				(b"alfa", b"1"),
+				(b"bravo", b"22"),
+				(b"bravo2", &long_value),
+				(b"bravo3", b"5"),
+				(b"delta", b"4"),
+				(b"echo", &long_value),
+			],
+		);
+
+		assert_eq!(
+			collect_diff::<LayoutV1<Blake2Hasher>>(&db, Some(&previous_root), Some(&root)),
+			vec![
+				TrieDiff::Modified(b"bravo".to_vec()),
+				TrieDiff::Added(b"bravo3".to_vec()),
+				TrieDiff::Deleted(b"charlie".to_vec()),
+				TrieDiff::Added(b"echo".to_vec()),
+			]
+		);
+		assert!(collect_diff::<LayoutV1<Blake2Hasher>>(&db, Some(&root), Some(&root)).is_empty());
+		assert_eq!(
+			collect_diff::<LayoutV1<Blake2Hasher>>(&db, None, Some(&previous_root)).len(),
+			5
+		);
+
+		// The comparison stops when requested.
+		let mut diff = Vec::new();
+		trie_diff::<LayoutV1<Blake2Hasher>>(&db, Some(&previous_root), &db, Some(&root), |item| {
+			diff.push(item);
+			false
+		})
+		.unwrap();
+		assert_eq!(diff, vec![TrieDiff::Modified(b"bravo".to_vec())]);
+	}
+
+	#[test]
+	fn trie_diff_compares_values_across_state_versions() {
+		let long_value = [7u8; 64];
+		let mut db = MemoryDB::default();
+		let previous_root = create_trie::<LayoutV0<Blake2Hasher>>(
+			&mut db,
+			&[(b"alfa", &long_value), (b"bravo", &long_value)],
+		);
+		let root = create_trie::<LayoutV1<Blake2Hasher>>(
+			&mut db,
+			&[(b"alfa", &long_value), (b"bravo", &[8u8; 64])],
+		);
+
+		assert_eq!(
+			collect_diff::<LayoutV1<Blake2Hasher>>(&db, Some(&previous_root), Some(&root)),
+			vec![TrieDiff::Modified(b"bravo".to_vec())]
+		);
+	}
+}
@@ -72,6 +72,7 @@ pub struct TestClientBuilder<Block: BlockT, ExecutorDispatch, Backend: 'static,
 	enable_offchain_indexing_api: bool,
 	enable_import_proof_recording: bool,
 	no_genesis: bool,
+	state_replay_max_blocks: Option<u32>,
 }
 
 impl<Block: BlockT, ExecutorDispatch, G: GenesisInit> Default
@@ -120,6 +121,7 @@ impl<Block: BlockT, ExecutorDispatch, Backend, G: GenesisInit>
 			enable_offchain_indexing_api: false,
 			no_genesis: false,
 			enable_import_proof_recording: false,
+			state_replay_max_blocks: None,
 		}
 	}
 
@@ -177,6 +179,12 @@ impl<Block: BlockT, ExecutorDispatch, Backend, G: GenesisInit>
 		self
 	}
 
+	/// Rebuild pruned state by re-executing up to `max_blocks` blocks on top of a state snapshot.
+	pub fn enable_state_replay(mut self, max_blocks: u32) -> Self {
+		self.state_replay_max_blocks = Some(max_blocks);
+		self
+	}
+
 	/// Build the test client with the given native executor.
 	pub fn build_with_executor<RuntimeApi>(
 		self,
@@ -211,6 +219,7 @@ impl<Block: BlockT, ExecutorDispatch, Backend, G: GenesisInit>
 			enable_import_proof_recording: self.enable_import_proof_recording,
 			offchain_indexing_api: self.enable_offchain_indexing_api,
 			no_genesis: self.no_genesis,
+			state_replay_max_blocks: self.state_replay_max_blocks,
 			..Default::default()
 		};
 
@@ -521,6 +521,7 @@ impl OverheadCmd {
 				no_genesis: false,
 				wasm_runtime_substitutes: Default::default(),
 				enable_import_proof_recording: chain_type.requires_proof_recording(),
+				state_replay_max_blocks: None,
 			},
 		)?);
 
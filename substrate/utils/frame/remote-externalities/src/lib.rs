@@ -31,7 +31,7 @@ use sp_core::{
 	hexdisplay::HexDisplay,
 	storage::{
 		well_known_keys::{is_default_child_storage_key, DEFAULT_CHILD_STORAGE_KEY_PREFIX},
-		ChildInfo, ChildType, PrefixedStorageKey, StorageData, StorageKey,
+		ChildInfo, ChildType, PrefixedStorageKey, Storage, StorageChild, StorageData, StorageKey,
 	},
 };
 use sp_runtime::{
@@ -114,6 +114,47 @@ pub struct RemoteExternalities<B: BlockT> {
 	pub header: B::Header,
 }
 
+impl<B: BlockT> RemoteExternalities<B> {
+	/// Collect all top and default child key-values of the state.
+	///
+	/// This can be used to start a new chain from the state, e.g. as the raw genesis storage of a
+	/// chain spec.
+	pub fn to_storage(&mut self) -> Storage {
+		self.execute_with(|| {
+			let mut storage = Storage::default();
+			let mut key = Vec::new();
+			while let Some(next_key) = sp_io::storage::next_key(&key) {
+				key = next_key;
+				match key.strip_prefix(DEFAULT_CHILD_STORAGE_KEY_PREFIX) {
+					Some(storage_key) => {
+						let mut child = StorageChild {
+							data: Default::default(),
+							child_info: ChildInfo::new_default(storage_key),
+						};
+						let mut child_key = Vec::new();
+						while let Some(next_key) =
+							sp_io::default_child_storage::next_key(storage_key, &child_key)
+						{
+							child_key = next_key;
+							if let Some(value) =
+								sp_io::default_child_storage::get(storage_key, &child_key)
+							{
+								child.data.insert(child_key.clone(), value);
+							}
+						}
+						storage.children_default.insert(storage_key.to_vec(), child);
+					},
+					None =>
+						if let Some(value) = sp_io::storage::get(&key) {
+							storage.top.insert(key.clone(), value.to_vec());
+						},
+				}
+			}
+			storage
+		})
+	}
+}
+
 impl<B: BlockT> Deref for RemoteExternalities<B> {
 	type Target = TestExternalities<HashingFor<B>>;
 	fn deref(&self) -> &Self::Target {
@@ -1297,6 +1338,28 @@ mod tests {
 			.expect("Can't read state snapshot file")
 			.execute_with(|| assert!(sp_io::storage::get(&some_key).is_none()));
 	}
+
+	#[tokio::test]
+	async fn can_convert_snapshot_to_storage() {
+		init_logger();
+
+		let mut ext = Builder::<Block>::new()
+			.mode(Mode::Offline(OfflineConfig {
+				state_snapshot: SnapshotConfig::new("test_data/test.snap"),
+			}))
+			.build()
+			.await
+			.expect("Can't read state snapshot file");
+		let storage = ext.to_storage();
+		assert!(!storage.top.is_empty());
+
+		// every collected key-value must come from the snapshot.
+		ext.execute_with(|| {
+			for (key, value) in &storage.top {
+				assert_eq!(sp_io::storage::get(key).as_deref(), Some(&value[..]));
+			}
+		});
+	}
 }
 
 #[cfg(all(test, feature = "remote-test"))]
@@ -21,6 +21,7 @@ use polkadot_sdk::*;
 pub enum Consensus {
 	ManualSeal(u64),
 	InstantSeal,
+	Pow(u64),
 	None,
 }
 
@@ -32,6 +33,11 @@ impl std::str::FromStr for Consensus {
 			Consensus::InstantSeal
 		} else if let Some(block_time) = s.strip_prefix("manual-seal-") {
 			Consensus::ManualSeal(block_time.parse().map_err(|_| "invalid block time")?)
+		} else if let Some(difficulty) = s.strip_prefix("pow-") {
+			match difficulty.parse() {
+				Ok(difficulty) if difficulty > 0 => Consensus::Pow(difficulty),
+				_ => return Err("invalid difficulty".into()),
+			}
 		} else if s.to_lowercase() == "none" {
 			Consensus::None
 		} else {
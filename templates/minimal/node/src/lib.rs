@@ -17,5 +17,6 @@
 
 pub mod chain_spec;
 pub(crate) mod cli;
+pub mod pow;
 pub mod rpc;
 pub mod service;
@@ -21,6 +21,7 @@
 mod chain_spec;
 mod cli;
 mod command;
+mod pow;
 mod rpc;
 mod service;
 
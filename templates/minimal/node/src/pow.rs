@@ -0,0 +1,63 @@
+// This file is part of Substrate.
+
+// Copyright (C) Parity Technologies (UK) Ltd.
+// SPDX-License-Identifier: Apache-2.0
+
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+// 	http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A proof of work algorithm with a fixed difficulty, for `--consensus pow-<difficulty>`.
+
+use minimal_template_runtime::interface::OpaqueBlock as Block;
+use polkadot_sdk::{
+	sc_consensus_pow::{Error, PowAlgorithm, Seal},
+	sp_core::{hashing::blake2_256, U256},
+	sp_runtime::{generic::BlockId, traits::Block as BlockT},
+	*,
+};
+
+/// Accepts seals whose Blake2 hash with the pre-hash, times the difficulty, fits into 256 bits.
+///
+/// Finding such a seal takes `difficulty` attempts on average. Seals are mined by external miners
+/// through the `pow_getWork` and `pow_submitWork` RPC methods.
+#[derive(Clone)]
+pub struct FixedDifficultyPow {
+	difficulty: U256,
+}
+
+impl FixedDifficultyPow {
+	/// Create a new instance requiring the given difficulty for all blocks.
+	pub fn new(difficulty: u64) -> Self {
+		Self { difficulty: difficulty.into() }
+	}
+}
+
+impl PowAlgorithm<Block> for FixedDifficultyPow {
+	type Difficulty = U256;
+
+	fn difficulty(&self, _parent: <Block as BlockT>::Hash) -> Result<U256, Error<Block>> {
+		Ok(self.difficulty)
+	}
+
+	fn verify(
+		&self,
+		_parent: &BlockId<Block>,
+		pre_hash: &<Block as BlockT>::Hash,
+		_pre_digest: Option<&[u8]>,
+		seal: &Seal,
+		difficulty: U256,
+	) -> Result<bool, Error<Block>> {
+		let hash = blake2_256(&[pre_hash.as_ref(), &seal[..]].concat());
+		let (_, overflow) = U256::from_big_endian(&hash).overflowing_mul(difficulty);
+		Ok(!overflow)
+	}
+}
@@ -22,9 +22,19 @@
 
 #![warn(missing_docs)]
 
+use futures::channel::mpsc;
 use jsonrpsee::RpcModule;
-use minimal_template_runtime::interface::{AccountId, Nonce, OpaqueBlock};
+use minimal_template_runtime::interface::{AccountId, Hash, Nonce, OpaqueBlock};
 use polkadot_sdk::{
+	sc_consensus_manual_seal::{
+		rpc::{ManualSeal, ManualSealApiServer},
+		EngineCommand,
+	},
+	sc_consensus_pow::{
+		rpc::{PowMining, PowMiningApiServer},
+		MiningHandle,
+	},
+	sc_network_sync::SyncingService,
 	sc_transaction_pool_api::TransactionPool,
 	sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata},
 	*,
@@ -37,6 +47,17 @@ pub struct FullDeps<C, P> {
 	pub client: Arc<C>,
 	/// Transaction pool instance.
 	pub pool: Arc<P>,
+	/// Channel for sending commands to the manual seal authorship task, if it is running.
+	pub command_sink: Option<mpsc::Sender<EngineCommand<Hash>>>,
+	/// Handle of the proof of work mining worker, if it is running.
+	pub mining_handle: Option<
+		MiningHandle<
+			OpaqueBlock,
+			crate::pow::FixedDifficultyPow,
+			Arc<SyncingService<OpaqueBlock>>,
+			(),
+		>,
+	>,
 }
 
 #[docify::export]
@@ -58,9 +79,15 @@ where
 {
 	use polkadot_sdk::substrate_frame_rpc_system::{System, SystemApiServer};
 	let mut module = RpcModule::new(());
-	let FullDeps { client, pool } = deps;
+	let FullDeps { client, pool, command_sink, mining_handle } = deps;
 
 	module.merge(System::new(client.clone(), pool.clone()).into_rpc())?;
+	if let Some(command_sink) = command_sink {
+		module.merge(ManualSeal::new(command_sink).into_rpc())?;
+	}
+	if let Some(mining_handle) = mining_handle {
+		module.merge(PowMining::new(mining_handle).into_rpc())?;
+	}
 
 	Ok(module)
 }
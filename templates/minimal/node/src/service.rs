@@ -15,7 +15,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::cli::Consensus;
+use crate::{cli::Consensus, pow::FixedDifficultyPow};
 use futures::FutureExt;
 use minimal_template_runtime::{interface::OpaqueBlock as Block, RuntimeApi};
 use polkadot_sdk::{
@@ -27,7 +27,7 @@ use polkadot_sdk::{
 	sp_runtime::traits::Block as BlockT,
 	*,
 };
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 type HostFunctions = sp_io::SubstrateHostFunctions;
 
@@ -48,7 +48,23 @@ pub type Service = sc_service::PartialComponents<
 	Option<Telemetry>,
 >;
 
-pub fn new_partial(config: &Configuration) -> Result<Service, ServiceError> {
+/// Block import checking the seals of proof of work blocks.
+fn pow_block_import(
+	client: Arc<FullClient>,
+	select_chain: FullSelectChain,
+	difficulty: u64,
+) -> impl sc_consensus::BlockImport<Block, Error = sp_consensus::Error> + Send + Sync + 'static {
+	sc_consensus_pow::PowBlockImport::new(
+		client.clone(),
+		client,
+		FixedDifficultyPow::new(difficulty),
+		0,
+		select_chain,
+		|_, ()| async { Ok(sp_timestamp::InherentDataProvider::from_system_time()) },
+	)
+}
+
+pub fn new_partial(config: &Configuration, consensus: &Consensus) -> Result<Service, ServiceError> {
 	let telemetry = config
 		.telemetry_endpoints
 		.clone()
@@ -88,11 +104,20 @@ pub fn new_partial(config: &Configuration) -> Result<Service, ServiceError> {
 		.build(),
 	);
 
-	let import_queue = sc_consensus_manual_seal::import_queue(
-		Box::new(client.clone()),
-		&task_manager.spawn_essential_handle(),
-		config.prometheus_registry(),
-	);
+	let import_queue = match consensus {
+		Consensus::Pow(difficulty) => sc_consensus_pow::import_queue(
+			Box::new(pow_block_import(client.clone(), select_chain.clone(), *difficulty)),
+			None,
+			FixedDifficultyPow::new(*difficulty),
+			&task_manager.spawn_essential_handle(),
+			config.prometheus_registry(),
+		)?,
+		_ => sc_consensus_manual_seal::import_queue(
+			Box::new(client.clone()),
+			&task_manager.spawn_essential_handle(),
+			config.prometheus_registry(),
+		),
+	};
 
 	Ok(sc_service::PartialComponents {
 		client,
@@ -120,7 +145,7 @@ pub fn new_full<Network: sc_network::NetworkBackend<Block, <Block as BlockT>::Ha
 		select_chain,
 		transaction_pool,
 		other: mut telemetry,
-	} = new_partial(&config)?;
+	} = new_partial(&config, &consensus)?;
 
 	let net_config = sc_network::config::FullNetworkConfiguration::<
 		Block,
@@ -144,6 +169,7 @@ pub fn new_full<Network: sc_network::NetworkBackend<Block, <Block as BlockT>::Ha
 			import_queue,
 			block_announce_validator_builder: None,
 			warp_sync_config: None,
+			header_verifier: None,
 			block_relay: None,
 			metrics,
 		})?;
@@ -169,18 +195,68 @@ pub fn new_full<Network: sc_network::NetworkBackend<Block, <Block as BlockT>::Ha
 		);
 	}
 
+	// the manual seal commands are also exposed over RPC, so the channel is created upfront.
+	let (command_sink, commands_stream) = match consensus {
+		Consensus::ManualSeal(_) => {
+			let (sink, stream) = futures::channel::mpsc::channel(1024);
+			(Some(sink), Some(stream))
+		},
+		_ => (None, None),
+	};
+
+	let prometheus_registry = config.prometheus_registry().cloned();
+
+	let proposer = sc_basic_authorship::ProposerFactory::new(
+		task_manager.spawn_handle(),
+		client.clone(),
+		transaction_pool.clone(),
+		prometheus_registry.as_ref(),
+		telemetry.as_ref().map(|x| x.handle()),
+	);
+
+	// the proof of work is mined by external miners over RPC, so the mining worker is started
+	// upfront.
+	let mining_handle = match consensus {
+		Consensus::Pow(difficulty) => {
+			let (mining_handle, mining_worker) = sc_consensus_pow::start_mining_worker(
+				Box::new(pow_block_import(client.clone(), select_chain.clone(), difficulty)),
+				client.clone(),
+				select_chain.clone(),
+				FixedDifficultyPow::new(difficulty),
+				proposer.clone(),
+				sync_service.clone(),
+				sync_service.clone(),
+				None,
+				|_, ()| async { Ok(sp_timestamp::InherentDataProvider::from_system_time()) },
+				Duration::from_secs(10),
+				Duration::from_secs(10),
+			);
+			task_manager.spawn_essential_handle().spawn_blocking(
+				"pow-mining-worker",
+				None,
+				mining_worker,
+			);
+			Some(mining_handle)
+		},
+		_ => None,
+	};
+
 	let rpc_extensions_builder = {
 		let client = client.clone();
 		let pool = transaction_pool.clone();
+		let command_sink = command_sink.clone();
 
 		Box::new(move |_| {
-			let deps = crate::rpc::FullDeps { client: client.clone(), pool: pool.clone() };
+			let deps = crate::rpc::FullDeps {
+				client: client.clone(),
+				pool: pool.clone(),
+				command_sink: command_sink.clone(),
+				mining_handle: mining_handle.clone(),
+			};
 			crate::rpc::create_full(deps).map_err(Into::into)
 		})
 	};
 
-	let prometheus_registry = config.prometheus_registry().cloned();
-
 	let _rpc_handlers = sc_service::spawn_tasks(sc_service::SpawnTasksParams {
 		network,
 		client: client.clone(),
@@ -188,7 +264,7 @@ pub fn new_full<Network: sc_network::NetworkBackend<Block, <Block as BlockT>::Ha
 		task_manager: &mut task_manager,
 		transaction_pool: transaction_pool.clone(),
 		rpc_builder: rpc_extensions_builder,
-		backend,
+		backend: backend.clone(),
 		system_rpc_tx,
 		tx_handler_controller,
 		sync_service,
@@ -196,14 +272,6 @@ pub fn new_full<Network: sc_network::NetworkBackend<Block, <Block as BlockT>::Ha
 		telemetry: telemetry.as_mut(),
 	})?;
 
-	let proposer = sc_basic_authorship::ProposerFactory::new(
-		task_manager.spawn_handle(),
-		client.clone(),
-		transaction_pool.clone(),
-		prometheus_registry.as_ref(),
-		telemetry.as_ref().map(|x| x.handle()),
-	);
-
 	match consensus {
 		Consensus::InstantSeal => {
 			let params = sc_consensus_manual_seal::InstantSealParams {
@@ -227,7 +295,8 @@ pub fn new_full<Network: sc_network::NetworkBackend<Block, <Block as BlockT>::Ha
 			);
 		},
 		Consensus::ManualSeal(block_time) => {
-			let (mut sink, commands_stream) = futures::channel::mpsc::channel(1024);
+			let mut sink = command_sink.expect("created above for manual seal; qed");
+			let commands_stream = commands_stream.expect("created above for manual seal; qed");
 			task_manager.spawn_handle().spawn("block_authoring", None, async move {
 				loop {
 					futures_timer::Delay::new(std::time::Duration::from_millis(block_time)).await;
@@ -241,6 +310,8 @@ pub fn new_full<Network: sc_network::NetworkBackend<Block, <Block as BlockT>::Ha
 				}
 			});
 
+			let time_travel = sc_consensus_manual_seal::TimeTravel::new();
+			let time_travel_for_cidp = time_travel.clone();
 			let params = sc_consensus_manual_seal::ManualSealParams {
 				block_import: client.clone(),
 				env: proposer,
@@ -249,9 +320,13 @@ pub fn new_full<Network: sc_network::NetworkBackend<Block, <Block as BlockT>::Ha
 				select_chain,
 				commands_stream: Box::pin(commands_stream),
 				consensus_data_provider: None,
-				create_inherent_data_providers: move |_, ()| async move {
-					Ok(sp_timestamp::InherentDataProvider::from_system_time())
+				create_inherent_data_providers: move |_, ()| {
+					let timestamp = time_travel_for_cidp
+						.timestamp_provider(*sp_timestamp::Timestamp::current());
+					async move { Ok::<_, Box<dyn std::error::Error + Send + Sync>>(timestamp?) }
 				},
+				backend: Some(backend),
+				time_travel: Some(time_travel),
 			};
 			let authorship_future = sc_consensus_manual_seal::run_manual_seal(params);
 
@@ -179,6 +179,7 @@ pub fn new_full<
 			import_queue,
 			block_announce_validator_builder: None,
 			warp_sync_config: Some(WarpSyncConfig::WithProvider(warp_sync)),
+			header_verifier: None,
 			block_relay: None,
 			metrics,
 		})?;
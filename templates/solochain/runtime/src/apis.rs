@@ -118,6 +118,7 @@ impl_runtime_apis! {
 		}
 	}
 
+	#[api_version(2)]
 	impl sp_consensus_aura::AuraApi<Block, AuraId> for Runtime {
 		fn slot_duration() -> sp_consensus_aura::SlotDuration {
 			sp_consensus_aura::SlotDuration::from_millis(Aura::slot_duration())
@@ -126,6 +127,10 @@ impl_runtime_apis! {
 		fn authorities() -> Vec<AuraId> {
 			pallet_aura::Authorities::<Runtime>::get().into_inner()
 		}
+
+		fn max_blocks_per_slot() -> u32 {
+			Aura::max_blocks_per_slot()
+		}
 	}
 
 	impl sp_session::SessionKeys<Block> for Runtime {